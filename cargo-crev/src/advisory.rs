@@ -0,0 +1,191 @@
+//! RustSec advisories, for `cargo crev import advisories`
+//!
+//! Advisories are read from a checkout of the RustSec advisory database
+//! (https://github.com/RustSec/advisory-db), with one file per advisory
+//! under `crates/<name>/`: either Markdown, with the TOML of the advisory
+//! in a ```` ```toml ```` fenced block at its start, or older plain TOML.
+//! Both the `[advisory]` version lists (`patched_versions`,
+//! `unaffected_versions`) and the newer `[versions]` table are understood.
+//! Withdrawn advisories are skipped.
+use crate::prelude::*;
+use crev_data::proof::review::Rating;
+use serde::Deserialize;
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryFields,
+    #[serde(default)]
+    versions: VersionsFields,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFields {
+    id: String,
+    package: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    informational: Option<String>,
+    /// Date the advisory was withdrawn on, if it was
+    #[serde(default)]
+    withdrawn: Option<toml::Value>,
+    #[serde(default)]
+    patched_versions: Vec<String>,
+    #[serde(default)]
+    unaffected_versions: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct VersionsFields {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+/// An advisory against some versions of a crate
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    /// Eg. `RUSTSEC-2019-0001`
+    pub id: String,
+    pub package: String,
+    pub title: String,
+    pub url: Option<String>,
+    /// Kind of an informational advisory (eg. `unmaintained`), which isn't
+    /// about a vulnerability
+    pub informational: Option<String>,
+    /// Set if the advisory was withdrawn, eg. as a false alarm
+    pub withdrawn: bool,
+    patched: Vec<semver::VersionReq>,
+    unaffected: Vec<semver::VersionReq>,
+}
+
+fn parse_version_reqs(reqs: &[String]) -> Result<Vec<semver::VersionReq>> {
+    reqs.iter()
+        .map(|req| {
+            semver::VersionReq::parse(req)
+                .map_err(|e| format_err!("Invalid version requirement {}: {}", req, e))
+        })
+        .collect()
+}
+
+/// The TOML front matter of the Markdown advisory `s`
+fn toml_front_matter(s: &str) -> Result<&str> {
+    let mut lines = s.lines();
+    if lines.next().map(str::trim) != Some("```toml") {
+        bail!("No ```toml block at the start");
+    }
+    let start = s.find('\n').map_or(s.len(), |i| i + 1);
+    let mut end = start;
+    for line in lines {
+        if line.trim() == "```" {
+            return Ok(&s[start..end]);
+        }
+        end += line.len() + 1;
+    }
+    bail!("Unterminated ```toml block")
+}
+
+impl Advisory {
+    /// Parse an advisory in Markdown, with TOML front matter
+    pub fn parse_markdown(s: &str) -> Result<Self> {
+        Self::parse(toml_front_matter(s)?)
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let file: AdvisoryFile = toml::from_str(s)?;
+        let fields = file.advisory;
+        let patched = [fields.patched_versions, file.versions.patched].concat();
+        let unaffected = [fields.unaffected_versions, file.versions.unaffected].concat();
+        Ok(Advisory {
+            id: fields.id,
+            package: fields.package,
+            title: fields.title,
+            url: fields.url,
+            informational: fields.informational,
+            withdrawn: fields.withdrawn.is_some(),
+            patched: parse_version_reqs(&patched)?,
+            unaffected: parse_version_reqs(&unaffected)?,
+        })
+    }
+
+    /// Is `version` neither patched nor unaffected
+    pub fn affects(&self, version: &semver::Version) -> bool {
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|req| req.matches(version))
+    }
+
+    /// Rating of the versions affected: informational advisories only
+    /// make them negative, others dangerous
+    pub fn rating(&self) -> Rating {
+        if self.informational.is_some() {
+            Rating::Negative
+        } else {
+            Rating::Dangerous
+        }
+    }
+
+    /// One line description, to put in review comments
+    pub fn summary(&self) -> String {
+        match self.url {
+            Some(ref url) => format!("{}: {} ({})", self.id, self.title, url),
+            None => format!("{}: {}", self.id, self.title),
+        }
+    }
+}
+
+fn advisory_files_in(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            advisory_files_in(&path, out)?;
+        } else if path.extension() == Some(OsStr::new("md"))
+            || path.extension() == Some(OsStr::new("toml"))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// All the advisories of the advisory database checked out in `db_dir`,
+/// by id
+pub fn load_db(db_dir: &Path) -> Result<Vec<Advisory>> {
+    let crates_dir = db_dir.join("crates");
+    if !crates_dir.is_dir() {
+        bail!(
+            "{} is not a RustSec advisory database: no crates directory",
+            db_dir.display()
+        );
+    }
+    let mut paths = vec![];
+    advisory_files_in(&crates_dir, &mut paths)?;
+    let mut advisories = vec![];
+    for path in &paths {
+        let content = fs::read_to_string(path)?;
+        let advisory = if path.extension() == Some(OsStr::new("md")) {
+            Advisory::parse_markdown(&content)
+        } else {
+            Advisory::parse(&content)
+        }
+        .map_err(|e| format_err!("Can't read advisory {}: {}", path.display(), e))?;
+        if !advisory.withdrawn {
+            advisories.push(advisory);
+        }
+    }
+    if advisories.is_empty() {
+        bail!("No advisories found in {}", crates_dir.display());
+    }
+    advisories.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(advisories)
+}
@@ -0,0 +1,86 @@
+//! `.crate` files, as downloaded by cargo
+//!
+//! A crate is reviewed as cargo unpacks it from its `.crate` file, which
+//! can differ from the git tree it was published from. Digesting the
+//! `.crate` file itself gives the same digest as its unpacked source,
+//! regardless of what happened to the unpacked copy since.
+use crate::prelude::*;
+use crev_data::Digest;
+use flate2::read::GzDecoder;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use tar::Archive;
+
+/// Unpack `crate_file` into `dst`, the way cargo does
+///
+/// All the entries have to be in a single top level directory (eg.
+/// `foo-1.0.0/`), which is returned.
+pub fn unpack(crate_file: &Path, dst: &Path) -> Result<PathBuf> {
+    let mut archive = Archive::new(GzDecoder::new(fs::File::open(crate_file)?));
+    let mut prefix: Option<PathBuf> = None;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        let top_dir = match entry_path.components().next() {
+            Some(Component::Normal(top_dir)) => PathBuf::from(top_dir),
+            _ => bail!("Invalid entry in crate file: {}", entry_path.display()),
+        };
+        match prefix {
+            Some(ref prefix) if *prefix != top_dir => bail!(
+                "Crate file entry {} outside of {}",
+                entry_path.display(),
+                prefix.display()
+            ),
+            Some(_) => {}
+            None => prefix = Some(top_dir),
+        }
+        if entry.header().entry_type().is_hard_link() {
+            bail!("Hard link in crate file: {}", entry_path.display());
+        }
+        if !entry.unpack_in(dst)? {
+            bail!("Invalid entry in crate file: {}", entry_path.display());
+        }
+    }
+
+    let prefix = prefix.ok_or_else(|| format_err!("Empty crate file"))?;
+    Ok(dst.join(prefix))
+}
+
+/// Digest of the content of `crate_file`
+///
+/// Along with the digest, the name of the top level directory (eg.
+/// `foo-1.0.0`) is returned, so the caller can check it's the right crate.
+pub fn digest(crate_file: &Path, ignore_list: &HashSet<PathBuf>) -> Result<(String, Digest)> {
+    let tmp_dir = tempdir::TempDir::new("cargo-crev")?;
+    let dir = unpack(crate_file, tmp_dir.path())?;
+    let digest = crev_lib::get_dir_digest(&dir, ignore_list)?;
+    let top_dir = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format_err!("Invalid crate file dir name"))?
+        .to_owned();
+    Ok((top_dir, digest))
+}
+
+/// Path of the `.crate` file cargo unpacked `pkg_dir` from
+///
+/// Cargo keeps them in a `cache` registry dir, next to the `src` one,
+/// eg. `~/.cargo/registry/src/<registry>/foo-1.0.0` has been unpacked
+/// from `~/.cargo/registry/cache/<registry>/foo-1.0.0.crate`.
+pub fn path_for_registry_src_dir(pkg_dir: &Path) -> Option<PathBuf> {
+    let pkg_dir_name = pkg_dir.file_name()?.to_str()?;
+    let registry_src_dir = pkg_dir.parent()?;
+    let registry_name = registry_src_dir.file_name()?;
+    Some(
+        registry_src_dir
+            .parent()?
+            .parent()?
+            .join("cache")
+            .join(registry_name)
+            .join(format!("{}.crate", pkg_dir_name)),
+    )
+}
@@ -9,25 +9,29 @@ use cargo::{
     core::{package_id::PackageId, SourceId},
     util::important_paths::find_root_manifest_for_wd,
 };
-use crev_lib::ProofStore;
 use crev_lib::{self, local::Local};
 use default::default;
 use semver;
 use serde::Deserialize;
 use std::{
-    collections::HashSet,
-    env, fmt,
+    collections::{HashMap, HashSet},
+    env,
+    fmt::{self, Write as FmtWrite},
     path::{Path, PathBuf},
     process,
 };
 use structopt::StructOpt;
 
+mod advisory;
+mod crate_file;
 mod crates_io;
 mod opts;
 mod prelude;
 mod term;
+#[cfg(test)]
+mod tests;
 
-use crev_data::proof;
+use crev_data::{proof, proof::trust::TrustDimension};
 use crev_lib::{TrustOrDistrust, TrustOrDistrust::*};
 
 struct Repo {
@@ -42,6 +46,9 @@ const GOTO_CRATE_NAME_ENV: &str = "CARGO_CREV_GOTO_ORIGINAL_NAME";
 /// Name of ENV with version of the crate that we've `goto`ed to
 const GOTO_CRATE_VERSION_ENV: &str = "CARGO_CREV_GOTO_ORIGINAL_VERSION";
 
+/// Name of the file with the diff, in the dir with changes staged by `diff-review`
+const DIFF_FILE_NAME: &str = "crev.diff";
+
 /// Name of file we store user-personalized
 const KNOWN_CARGO_OWNERS_FILE: &str = "known_cargo_owners.txt";
 
@@ -79,6 +86,16 @@ impl VcsInfoJson {
         Some(s.to_string())
     }
 }
+/// A crate of the current Cargo workspace
+struct WorkspaceMember {
+    name: String,
+    version: String,
+    /// `package.repository` of the crate, used as `source` of its reviews
+    repository: Option<String>,
+    dir: PathBuf,
+    digest: crev_data::Digest,
+}
+
 #[derive(Debug)]
 struct KnownOwnersColored(usize);
 
@@ -146,6 +163,60 @@ impl Repo {
         Ok(())
     }
 
+    /// Every dependency from a registry, without downloading it
+    fn for_every_dependency(&self, mut f: impl FnMut(&PackageId) -> Result<()>) -> Result<()> {
+        let workspace = cargo::core::Workspace::new(&self.manifest_path, &self.config)?;
+        let (_package_set, resolve) = cargo::ops::resolve_ws(&workspace)?;
+        let mut pkg_ids: Vec<_> = resolve
+            .iter()
+            .filter(|pkg_id| pkg_id.source_id().is_registry())
+            .collect();
+        pkg_ids.sort();
+
+        for pkg_id in pkg_ids {
+            f(pkg_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// All the crates of the current workspace
+    ///
+    /// The digest of a crate leaves out directories of other crates nested
+    /// in it, so that every crate can be reviewed on its own.
+    fn workspace_members(&self) -> Result<Vec<WorkspaceMember>> {
+        let workspace = cargo::core::Workspace::new(&self.manifest_path, &self.config)?;
+        let dirs: Vec<PathBuf> = workspace
+            .members()
+            .map(|pkg| pkg.root().to_owned())
+            .collect();
+
+        workspace
+            .members()
+            .map(|pkg| {
+                let dir = pkg.root().to_owned();
+                let mut ignore_list = cargo_full_ignore_list();
+                ignore_list.extend(
+                    dirs.iter()
+                        .filter(|other| **other != dir)
+                        .filter_map(|other| other.strip_prefix(&dir).ok())
+                        .map(ToOwned::to_owned),
+                );
+                Ok(WorkspaceMember {
+                    name: pkg.name().to_string(),
+                    version: pkg.version().to_string(),
+                    repository: pkg.manifest().metadata().repository.clone(),
+                    digest: crev_lib::get_dir_or_git_subdir_digest(&dir, &ignore_list)?,
+                    dir,
+                })
+            })
+            .collect()
+    }
+
+    fn workspace_root_dir(&self) -> &Path {
+        self.manifest_path.parent().expect("manifest in a dir")
+    }
+
     fn find_idependent_crate_dir(
         &self,
         name: &str,
@@ -333,12 +404,97 @@ fn review_crate(
     assert!(!pkg_dir.starts_with(std::env::current_dir()?));
     let local = Local::auto_open()?;
 
+    let digest_clean = match crate_file::path_for_registry_src_dir(&pkg_dir).filter(|p| p.exists())
+    {
+        Some(crate_file) => digest_from_crate_file(&pkg_dir, &crate_file, name, &crate_version)?,
+        None => digest_from_fresh_download(&repo, &pkg_dir, name, version, independent)?,
+    };
+
+    let vcs = VcsInfoJson::read_from_crate_dir(&pkg_dir)?;
+    let pub_id = local.read_current_locked_id()?.to_pubid();
+
+    let review = proof::review::PackageBuilder::default()
+        .from(pub_id)
+        .package(proof::PackageInfo {
+            id: None,
+            source: PROJECT_SOURCE_CRATES_IO.to_owned(),
+            name: name.to_owned(),
+            version: crate_version.to_string(),
+            digest: digest_clean.into_vec(),
+            digest_type: proof::default_digest_type(),
+            revision: vcs
+                .and_then(|vcs| vcs.get_git_revision())
+                .unwrap_or_else(|| "".into()),
+            revision_type: proof::default_revision_type(),
+        })
+        .review(trust.to_review())
+        .build()
+        .map_err(|e| format_err!("{}", e))?;
+
+    let review = crev_lib::util::edit_proof_content_iteractively(&review.into())?;
+
+    let id = local.read_current_unlocked_id(&crev_common::read_passphrase)?;
+    let proof = review.sign_by(&id)?;
+
+    local.insert_own(&proof, &id)?;
+    local.complete_todo(
+        crev_lib::todo::Kind::Dependency,
+        &format!("{} {}", name, crate_version),
+    )?;
+    Ok(())
+}
+
+/// Digest of a crate, from the `.crate` file cargo downloaded
+///
+/// Makes sure the unpacked source in `pkg_dir`, that was reviewed, is
+/// still the same.
+fn digest_from_crate_file(
+    pkg_dir: &Path,
+    crate_file: &Path,
+    name: &str,
+    version: &semver::Version,
+) -> Result<crev_data::Digest> {
+    let (top_dir, digest_clean) = crate_file::digest(crate_file, &cargo_min_ignore_list())?;
+    if top_dir != format!("{}-{}", name, version) {
+        bail!(
+            "{} is not a crate file of {} {}",
+            crate_file.display(),
+            name,
+            version
+        );
+    }
+    let digest_reviewed =
+        crev_lib::get_recursive_digest_for_dir(pkg_dir, &cargo_full_ignore_list())?;
+
+    if digest_clean != digest_reviewed {
+        bail!(
+            "The digest of the reviewed crate and its crate file were different; {} != {}; {} != {}. Use `cargo crev clean` to restore it.",
+            digest_clean,
+            digest_reviewed,
+            crate_file.display(),
+            pkg_dir.display(),
+        );
+    }
+    Ok(digest_clean)
+}
+
+/// Digest of a crate, from a freshly downloaded copy
+///
+/// Used if the `.crate` file is not available. Makes sure the source in
+/// `pkg_dir`, that was reviewed, is the same.
+fn digest_from_fresh_download(
+    repo: &Repo,
+    pkg_dir: &Path,
+    name: &str,
+    version: Option<&str>,
+    independent: bool,
+) -> Result<crev_data::Digest> {
     // to protect from creating a digest from a crate in unclean state
     // we move the old directory, download a fresh one and double
     // check if the digest was the same
     // BUG: TODO: https://users.rust-lang.org/t/append-an-additional-extension/23586
     let reviewed_pkg_dir: PathBuf =
-        crev_common::fs::append_to_path(pkg_dir.clone(), ".crev.reviewed");
+        crev_common::fs::append_to_path(pkg_dir.to_owned(), ".crev.reviewed");
     if reviewed_pkg_dir.is_dir() {
         std::fs::remove_dir_all(&reviewed_pkg_dir)?;
     }
@@ -347,12 +503,11 @@ fn review_crate(
     // having the cwd pulled from under them and confusing their
     // shells, we move all the entries in a dir, instead of the whole
     // dir. this is not a perfect solution, but better than nothing.
-    crev_common::fs::move_dir_content(&pkg_dir, &reviewed_pkg_dir)?;
-    let (pkg_dir_second, crate_version_second) = repo.find_crate(name, version, independent)?;
-    assert_eq!(pkg_dir, pkg_dir_second);
-    assert_eq!(crate_version, crate_version_second);
+    crev_common::fs::move_dir_content(pkg_dir, &reviewed_pkg_dir)?;
+    let (pkg_dir_second, _crate_version_second) = repo.find_crate(name, version, independent)?;
+    assert_eq!(pkg_dir, pkg_dir_second.as_path());
 
-    let digest_clean = crev_lib::get_recursive_digest_for_dir(&pkg_dir, &cargo_min_ignore_list())?;
+    let digest_clean = crev_lib::get_recursive_digest_for_dir(pkg_dir, &cargo_min_ignore_list())?;
     let digest_reviewed =
         crev_lib::get_recursive_digest_for_dir(&reviewed_pkg_dir, &cargo_full_ignore_list())?;
 
@@ -367,32 +522,189 @@ fn review_crate(
     }
     std::fs::remove_dir_all(&reviewed_pkg_dir)?;
 
-    let vcs = VcsInfoJson::read_from_crate_dir(&pkg_dir)?;
+    Ok(digest_clean)
+}
+
+/// Review a crate of the current workspace
+///
+/// * `name` - if not given, the crate containing the current directory
+fn review_workspace_member(name: Option<&str>, trust: TrustOrDistrust) -> Result<()> {
+    let repo = Repo::auto_open_cwd()?;
+    let members = repo.workspace_members()?;
+    let member = match name {
+        Some(name) => members.iter().find(|member| member.name == name),
+        None => {
+            let cwd = env::current_dir()?.canonicalize()?;
+            members
+                .iter()
+                .filter(|member| cwd.starts_with(&member.dir))
+                .max_by_key(|member| member.dir.components().count())
+        }
+    }
+    .ok_or_else(|| format_err!("Could not find requested workspace crate"))?;
+    let source = member.repository.clone().ok_or_else(|| {
+        format_err!(
+            "`package.repository` of {} not set; it's needed to identify the crate",
+            member.name
+        )
+    })?;
+
+    let local = Local::auto_open()?;
+    let pub_id = local.read_current_locked_id()?.to_pubid();
+
+    let review = proof::review::PackageBuilder::default()
+        .from(pub_id)
+        .package(proof::PackageInfo {
+            id: None,
+            source,
+            name: member.name.clone(),
+            version: member.version.clone(),
+            digest: member.digest.as_slice().to_vec(),
+            digest_type: proof::default_digest_type(),
+            revision: "".into(),
+            revision_type: proof::default_revision_type(),
+        })
+        .review(trust.to_review())
+        .build()
+        .map_err(|e| format_err!("{}", e))?;
+
+    let review = crev_lib::util::edit_proof_content_iteractively(&review.into())?;
+
     let id = local.read_current_unlocked_id(&crev_common::read_passphrase)?;
+    let proof = review.sign_by(&id)?;
+
+    local.insert_own(&proof, &id)?;
+    Ok(())
+}
+
+/// Clean copy of a crate, unpacked from its `.crate` file into `tmp_dir`
+///
+/// Fails if there's no `.crate` file: `pkg_dir` itself could have been
+/// modified since it was unpacked.
+fn clean_crate_dir(pkg_dir: &Path, tmp_dir: &Path) -> Result<PathBuf> {
+    match crate_file::path_for_registry_src_dir(pkg_dir).filter(|p| p.exists()) {
+        Some(crate_file) => crate_file::unpack(&crate_file, tmp_dir),
+        None => bail!("No `.crate` file found for {}", pkg_dir.display()),
+    }
+}
+
+/// Review the changes of a crate since an already reviewed version
+///
+/// Only the changed files are staged for the review, and the review is
+/// chained to an existing review of `old_version` with its `diff-base`.
+fn diff_review_crate(
+    name: &str,
+    old_version: &str,
+    new_version: &str,
+    trust: TrustOrDistrust,
+) -> Result<()> {
+    let repo = Repo::auto_open_cwd()?;
+    let (old_pkg_dir, old_version) = repo.find_crate(name, Some(old_version), true)?;
+    let (new_pkg_dir, new_version) = repo.find_crate(name, Some(new_version), true)?;
+
+    let local = Local::auto_open()?;
+    let (db, _trust_set) = local.load_db(&default())?;
+
+    let tmp_dir = tempdir::TempDir::new("cargo-crev")?;
+    let old_dir = clean_crate_dir(&old_pkg_dir, tmp_dir.path())?;
+    let new_dir = clean_crate_dir(&new_pkg_dir, tmp_dir.path())?;
+    let ignore_list = cargo_full_ignore_list();
+
+    let old_digest = crev_lib::get_dir_digest(&old_dir, &cargo_min_ignore_list())?;
+    let base = db
+        .get_package_reviews_for_package(
+            PROJECT_SOURCE_CRATES_IO,
+            Some(name),
+            Some(&old_version.to_string()),
+        )
+        .find(|review| review.diff_base.is_none() && review.package.digest == old_digest.as_slice())
+        .map(|review| review.package)
+        .ok_or_else(|| {
+            format_err!(
+                "No review of {} {} found; review it with `cargo crev review` first",
+                name,
+                old_version
+            )
+        })?;
+
+    let changes = crev_lib::dir_diff::changed_files(&old_dir, &new_dir, &ignore_list)?;
+    let staged_dir = tmp_dir.path().join("staged");
+    std::fs::create_dir_all(&staged_dir)?;
+    crev_lib::dir_diff::stage_changed_files(&new_dir, &changes, &staged_dir)?;
+
+    let diff = process::Command::new("git")
+        .args(["diff", "--no-index", "--no-color", "--"])
+        .arg(&old_dir)
+        .arg(&new_dir)
+        .output()?;
+    // `git diff` returns 1 if there were differences
+    if diff.status.code().map(|code| code > 1).unwrap_or(true) {
+        bail!("`git diff` returned {}", diff.status);
+    }
+    let diff = String::from_utf8_lossy(&diff.stdout);
+    crev_common::store_str_to_file(&staged_dir.join(DIFF_FILE_NAME), &diff)?;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "Changes of {} from {} to {}:",
+        name, old_version, new_version
+    )?;
+    for changed in &changes {
+        let change = match changed.change {
+            crev_lib::dir_diff::FileChange::Added => "added",
+            crev_lib::dir_diff::FileChange::Modified => "modified",
+            crev_lib::dir_diff::FileChange::Removed => "removed",
+        };
+        writeln!(out, "  {:8} {}", change, changed.path.display())?;
+    }
+    writeln!(out)?;
+    out.push_str(&diff);
+    local.print_paged(&out)?;
+
+    let shell = env::var_os("SHELL").ok_or_else(|| format_err!("$SHELL not set"))?;
+    eprintln!("Opening shell in: {}", staged_dir.display());
+    eprintln!(
+        "The changed files, along with the full diff in `{}`, are staged there.",
+        DIFF_FILE_NAME
+    );
+    eprintln!("Use `exit` or Ctrl-D when done reviewing to create the review.");
+    let status = process::Command::new(shell)
+        .current_dir(&staged_dir)
+        .status()?;
+    if !status.success() {
+        bail!("Shell returned {}", status);
+    }
+
+    let new_digest = crev_lib::get_dir_digest(&new_dir, &cargo_min_ignore_list())?;
+    let vcs = VcsInfoJson::read_from_crate_dir(&new_dir)?;
+    let pub_id = local.read_current_locked_id()?.to_pubid();
 
     let review = proof::review::PackageBuilder::default()
-        .from(id.id.to_owned())
+        .from(pub_id)
         .package(proof::PackageInfo {
             id: None,
             source: PROJECT_SOURCE_CRATES_IO.to_owned(),
             name: name.to_owned(),
-            version: crate_version.to_string(),
-            digest: digest_clean.into_vec(),
+            version: new_version.to_string(),
+            digest: new_digest.into_vec(),
             digest_type: proof::default_digest_type(),
             revision: vcs
                 .and_then(|vcs| vcs.get_git_revision())
                 .unwrap_or_else(|| "".into()),
             revision_type: proof::default_revision_type(),
         })
+        .diff_base(Some(base))
         .review(trust.to_review())
         .build()
         .map_err(|e| format_err!("{}", e))?;
 
     let review = crev_lib::util::edit_proof_content_iteractively(&review.into())?;
 
+    let id = local.read_current_unlocked_id(&crev_common::read_passphrase)?;
     let proof = review.sign_by(&id)?;
 
-    local.insert(&proof)?;
+    local.insert_own(&proof, &id)?;
     Ok(())
 }
 
@@ -409,12 +721,185 @@ fn find_reviews(
     ))
 }
 
-fn list_reviews(crate_: &opts::CrateSelector) -> Result<()> {
+fn list_reviews(crate_: &opts::CrateSelector, tags: &[String]) -> Result<()> {
+    let local = crev_lib::Local::auto_open()?;
+    let mut out = String::new();
     // TODO: take trust params?
-    for review in find_reviews(crate_, &default())? {
-        println!("{}", review);
+    for review in find_reviews(crate_, &default())?
+        .filter(|review| tags.iter().all(|tag| review.tags.contains(tag)))
+    {
+        writeln!(out, "{}", review)?;
+    }
+
+    local.print_paged(&out)
+}
+
+/// Reviewed version of a crate newer than `current` with the best review
+/// coverage, if it's better than that of `current`
+///
+/// Of equally well covered versions, the oldest one is picked, so that
+/// the upgrade is as small as it can be.
+fn best_upgrade(
+    coverage: &HashMap<String, crev_lib::VersionCoverage>,
+    current: &semver::Version,
+) -> Option<(semver::Version, crev_lib::VersionCoverage)> {
+    let current_coverage = coverage
+        .get(&current.to_string())
+        .cloned()
+        .unwrap_or_default();
+    let mut candidates: Vec<_> = coverage
+        .iter()
+        .filter_map(|(version, coverage)| {
+            semver::Version::parse(version)
+                .ok()
+                .filter(|version| version > current)
+                .map(|version| (version, coverage.clone()))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut best: Option<(semver::Version, crev_lib::VersionCoverage)> = None;
+    for (version, coverage) in candidates {
+        if coverage.is_better_than(best.as_ref().map_or(&current_coverage, |(_, c)| c)) {
+            best = Some((version, coverage));
+        }
+    }
+    best
+}
+
+fn update_check(args: &opts::UpdateCheck) -> Result<()> {
+    let mut term = term::Term::new();
+    let local = crev_lib::Local::auto_create_or_open()?;
+    let (db, trust_set) = local.load_db(&args.trust_params.clone().into())?;
+    let repo = Repo::auto_open_cwd()?;
+    let ignore_list = cargo_min_ignore_list();
+
+    if term.stderr_is_tty && term.stdout_is_tty {
+        eprint!(
+            "{:<20} {:<15} {:8} {:8}",
+            "crate", "version", "verifi.", "reviews"
+        );
+        eprintln!("    {:<15} {:8} {:8}", "upgrade", "verifi.", "reviews");
     }
+    repo.for_every_non_local_dependency_dir(|pkg_id, path| {
+        let pkg_name = pkg_id.name().as_str();
+        let mut coverage =
+            db.get_package_version_coverage(PROJECT_SOURCE_CRATES_IO, pkg_name, &trust_set);
+        let current = pkg_id.version();
+        // the version in use counts with the reviews of its own digest only
+        let digest = crev_lib::get_dir_digest(path, &ignore_list)?;
+        let current_coverage = db.get_package_digest_coverage(&digest, &trust_set);
+        coverage.insert(current.to_string(), current_coverage.clone());
+        let upgrade = best_upgrade(&coverage, current);
+        if upgrade.is_none() && args.only_better {
+            return Ok(());
+        }
+
+        print!("{:<20} {:<15} ", pkg_name, current.to_string());
+        term.stdout(
+            format_args!("{:8}", current_coverage.status),
+            &current_coverage.status,
+        )?;
+        print!(" {:8}", current_coverage.trusted_reviews);
+        if let Some((version, coverage)) = upgrade {
+            print!(" -> {:<15} ", version.to_string());
+            term.stdout(format_args!("{:8}", coverage.status), &coverage.status)?;
+            println!(" {:8}", coverage.trusted_reviews);
+        } else {
+            println!();
+        }
+        Ok(())
+    })
+}
+
+/// Flag the dependencies affected by the RustSec advisories in
+/// `args.db_dir`, with Package Reviews signed by the advisory bot id
+///
+/// All the advisories affecting a version go in a single review, which
+/// is skipped if the bot already made the same one. The reviews only make
+/// verification fail for whoever trusts the bot for advisories.
+fn import_advisories(args: &opts::ImportAdvisories) -> Result<()> {
+    let local = Local::auto_open()?;
+    let bot_id = match args.bot_id {
+        Some(ref id) => crev_data::Id::crevid_from_str(id)?,
+        None => local.load_user_config()?.advisory_bot_id.ok_or_else(|| {
+            format_err!(
+                "No advisory bot Id; use --bot-id, or set advisory-bot-id in the user config"
+            )
+        })?,
+    };
+    let advisories = advisory::load_db(&args.db_dir)?;
+    let (db, trust_set) = local.load_db(&default())?;
+    let repo = Repo::auto_open_cwd()?;
+    let ignore_list = cargo_min_ignore_list();
+
+    let mut bot = None;
+    let mut imported = 0;
+    repo.for_every_non_local_dependency_dir(|pkg_id, path| {
+        let pkg_name = pkg_id.name().as_str();
+        let affecting: Vec<_> = advisories
+            .iter()
+            .filter(|advisory| advisory.package == pkg_name && advisory.affects(pkg_id.version()))
+            .collect();
+        let rating = match affecting.iter().map(|advisory| advisory.rating()).min() {
+            Some(rating) => rating,
+            None => return Ok(()),
+        };
+        let comment = affecting
+            .iter()
+            .map(|advisory| advisory.summary())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let digest = crev_lib::get_dir_digest(path, &ignore_list)?;
+        if db
+            .get_package_reviews_by_digest(&digest)
+            .any(|review| review.from.id == bot_id && review.comment == comment)
+        {
+            return Ok(());
+        }
 
+        if bot.is_none() {
+            bot = Some(local.read_unlocked_id(&bot_id, &crev_common::read_passphrase)?);
+        }
+        let bot = bot.as_ref().expect("unlocked above");
+        let review = proof::review::PackageBuilder::default()
+            .from(bot.id.to_owned())
+            .package(proof::PackageInfo {
+                id: None,
+                source: PROJECT_SOURCE_CRATES_IO.to_owned(),
+                name: pkg_name.to_owned(),
+                version: pkg_id.version().to_string(),
+                digest: digest.into_vec(),
+                digest_type: proof::default_digest_type(),
+                revision: "".into(),
+                revision_type: proof::default_revision_type(),
+            })
+            .review(crev_data::Review {
+                thoroughness: crev_data::Level::None,
+                understanding: crev_data::Level::None,
+                rating,
+            })
+            .comment(comment)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+        let proof = review.sign_by(bot)?;
+        local.insert_own(&proof, bot)?;
+        println!("{} {}", pkg_name, pkg_id.version());
+        imported += 1;
+        Ok(())
+    })?;
+
+    eprintln!("Flagged {} dependencies", imported);
+    if trust_set
+        .get_effective_trust_level_for(&bot_id, TrustDimension::Advisories)
+        .is_none()
+    {
+        eprintln!(
+            "Warning: advisory bot Id {} is not trusted for advisories, so verification ignores them",
+            bot_id
+        );
+    }
     Ok(())
 }
 
@@ -451,7 +936,12 @@ fn run_command(command: opts::Command) -> Result<()> {
         opts::Command::New(cmd) => match cmd {
             opts::New::Id(args) => {
                 let local = Local::auto_create_or_open()?;
-                let res = local.generate_id(args.url, args.github_username, args.use_https_push);
+                let res = local.generate_id(
+                    args.url,
+                    args.github_username,
+                    args.use_https_push,
+                    args.name,
+                );
                 if res.is_err() {
                     eprintln!("Visit https://github.com/dpc/crev/wiki/Proof-Repository for help.");
                 }
@@ -476,6 +966,44 @@ fn run_command(command: opts::Command) -> Result<()> {
             }
         },
         opts::Command::Verify(cmd) => match cmd {
+            opts::Verify::Workspace(args) => {
+                let mut term = term::Term::new();
+                let local = crev_lib::Local::auto_create_or_open()?;
+                let (db, trust_set) = local.load_db(&args.trust_params.clone().into())?;
+                let repo = Repo::auto_open_cwd()?;
+
+                if term.stderr_is_tty && term.stdout_is_tty {
+                    if args.verbose {
+                        eprint!("{:43} ", "digest");
+                    }
+                    eprint!("{:8} {:8}", "verifi.", "reviews");
+                    eprintln!(" {:<19} {:<15} path", "crate", "version");
+                }
+                for member in repo.workspace_members()? {
+                    let result = db.verify_package_digest(&member.digest, &trust_set);
+                    let review_count = member
+                        .repository
+                        .as_ref()
+                        .map(|source| db.get_package_review_count(source, Some(&member.name), None))
+                        .unwrap_or(0);
+                    let rel_dir = member
+                        .dir
+                        .strip_prefix(repo.workspace_root_dir())
+                        .unwrap_or(&member.dir);
+
+                    if args.verbose {
+                        print!("{:43} ", member.digest);
+                    }
+                    term.stdout(format_args!("{:8}", result), &result)?;
+                    println!(
+                        " {:8} {:<20} {:<15} {}",
+                        review_count,
+                        member.name,
+                        member.version,
+                        rel_dir.display()
+                    );
+                }
+            }
             opts::Verify::Deps(args) => {
                 let mut term = term::Term::new();
                 let local = crev_lib::Local::auto_create_or_open()?;
@@ -496,6 +1024,11 @@ fn run_command(command: opts::Command) -> Result<()> {
                     eprintln!(" {:<19} {:<15}", "crate", "version");
                 }
                 let known_owners = read_known_owners().unwrap_or_else(|_| HashSet::new());
+                let mut todo = if args.todo {
+                    Some(local.open_todo()?)
+                } else {
+                    None
+                };
                 repo.for_every_non_local_dependency_dir(|pkg_id, path| {
                     let pkg_name = pkg_id.name().as_str();
                     let pkg_version = pkg_id.version().to_string();
@@ -503,6 +1036,12 @@ fn run_command(command: opts::Command) -> Result<()> {
                     let digest = crev_lib::get_dir_digest(&path, &ignore_list)?;
                     let result = db.verify_package_digest(&digest, &trust_set);
 
+                    if let Some(todo) = todo.as_mut().filter(|_| !result.is_verified()) {
+                        todo.add(crev_lib::todo::Item::new(
+                            crev_lib::todo::Kind::Dependency,
+                            format!("{} {}", pkg_name, pkg_version),
+                        ));
+                    }
                     if result.is_verified() && args.skip_verified {
                         return Ok(());
                     }
@@ -567,6 +1106,9 @@ fn run_command(command: opts::Command) -> Result<()> {
 
                     Ok(())
                 })?;
+                if let Some(todo) = todo {
+                    todo.save()?;
+                }
             }
         },
         opts::Command::Query(cmd) => match cmd {
@@ -583,35 +1125,58 @@ fn run_command(command: opts::Command) -> Result<()> {
                 opts::QueryId::Trusted { trust_params } => {
                     let local = crev_lib::Local::auto_open()?;
                     let (db, trust_set) = local.load_db(&trust_params.into())?;
+                    let mut out = String::new();
                     for id in trust_set.trusted_ids() {
-                        println!(
+                        writeln!(
+                            out,
                             "{} {}",
                             id,
                             db.lookup_url(id).map(|url| url.url.as_str()).unwrap_or("")
-                        );
+                        )?;
                     }
+                    local.print_paged(&out)?;
                 }
                 // TODO: move to crev-lib
                 opts::QueryId::All => {
                     let local = crev_lib::Local::auto_open()?;
                     let (db, _trust_set) = local.load_db(&default())?;
 
+                    let mut out = String::new();
                     for id in &db.all_known_ids() {
-                        println!(
+                        writeln!(
+                            out,
                             "{} {}",
                             id,
                             db.lookup_url(id).map(|url| url.url.as_str()).unwrap_or("")
-                        );
+                        )?;
                     }
+                    local.print_paged(&out)?;
                 }
             },
-            opts::Query::Review(args) => list_reviews(&args.crate_)?,
+            opts::Query::Review(args) => list_reviews(&args.crate_, &args.tags)?,
         },
+        opts::Command::UpdateCheck(args) => update_check(&args)?,
         opts::Command::Review(args) => {
             handle_goto_mode_command(&args, |c, v, i| {
                 review_crate(c, v, i, TrustOrDistrust::Trust)
             })?;
         }
+        opts::Command::ReviewMember(args) => {
+            let trust = if args.flag {
+                TrustOrDistrust::Distrust
+            } else {
+                TrustOrDistrust::Trust
+            };
+            review_workspace_member(args.name.as_deref(), trust)?;
+        }
+        opts::Command::DiffReview(args) => {
+            let trust = if args.flag {
+                TrustOrDistrust::Distrust
+            } else {
+                TrustOrDistrust::Trust
+            };
+            diff_review_crate(&args.name, &args.old_version, &args.new_version, trust)?;
+        }
         opts::Command::Goto(args) => {
             goto_crate_src(&args.crate_, args.independent)?;
         }
@@ -667,17 +1232,23 @@ fn run_command(command: opts::Command) -> Result<()> {
         opts::Command::Fetch(cmd) => match cmd {
             opts::Fetch::Trusted(params) => {
                 let local = Local::auto_open()?;
+                crev_lib::util::interrupt::install_handler();
                 local.fetch_trusted(params.into())?;
             }
             opts::Fetch::Url(params) => {
                 let local = Local::auto_open()?;
+                crev_lib::util::interrupt::install_handler();
                 local.fetch_url(&params.url)?;
             }
             opts::Fetch::All => {
                 let local = Local::auto_open()?;
+                crev_lib::util::interrupt::install_handler();
                 local.fetch_all()?;
             }
         },
+        opts::Command::Import(cmd) => match cmd {
+            opts::Import::Advisories(args) => import_advisories(&args)?,
+        },
     }
 
     Ok(())
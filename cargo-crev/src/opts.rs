@@ -1,4 +1,4 @@
-use std::ffi::OsString;
+use std::{ffi::OsString, path::PathBuf};
 
 #[derive(Debug, StructOpt, Clone)]
 pub struct CrateSelector {
@@ -17,6 +17,9 @@ pub struct NewId {
     #[structopt(long = "https-push")]
     /// Setup `https` instead of recommended `ssh`-based push url
     pub use_https_push: bool,
+    #[structopt(long = "name")]
+    /// Name to refer to the Id by, eg. `work` or `personal`
+    pub name: Option<String>,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -28,7 +31,7 @@ pub enum New {
 
 #[derive(Debug, StructOpt, Clone)]
 pub struct SwitchId {
-    /// Own Id to switch to
+    /// Own Id (or its name) to switch to
     pub id: String,
 }
 
@@ -37,21 +40,29 @@ pub struct SwitchId {
 pub struct TrustParams {
     #[structopt(long = "depth", default_value = "10")]
     pub depth: u64,
+    /// Maximum number of trust proofs between you and a trusted id
+    #[structopt(long = "max-hops")]
+    pub max_hops: Option<u64>,
     #[structopt(long = "high-cost", default_value = "0")]
     pub high_cost: u64,
     #[structopt(long = "medium-cost", default_value = "1")]
     pub medium_cost: u64,
     #[structopt(long = "low-cost", default_value = "5")]
     pub low_cost: u64,
+    /// Extend trust in an id to its linked own ids, up to the level of the link
+    #[structopt(long = "follow-id-links")]
+    pub follow_id_links: bool,
 }
 
 impl From<TrustParams> for crev_lib::TrustDistanceParams {
     fn from(params: TrustParams) -> Self {
         crev_lib::TrustDistanceParams {
             max_distance: params.depth,
+            max_depth: params.max_hops,
             high_trust_distance: params.high_cost,
             medium_trust_distance: params.medium_cost,
             low_trust_distance: params.low_cost,
+            follow_id_links: params.follow_id_links,
         }
     }
 }
@@ -69,6 +80,29 @@ pub struct VerifyDeps {
 
     #[structopt(long = "skip-known-owners")]
     pub skip_known_owners: bool,
+
+    /// Queue the dependencies that aren't verified on `crev todo`
+    #[structopt(long = "todo")]
+    pub todo: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct UpdateCheck {
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+
+    /// Only list dependencies with a better reviewed newer version
+    #[structopt(long = "only-better")]
+    pub only_better: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct VerifyWorkspace {
+    #[structopt(long = "verbose", short = "v")]
+    pub verbose: bool,
+
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -76,6 +110,10 @@ pub enum Verify {
     /// Verify dependencies
     #[structopt(name = "deps")]
     Deps(VerifyDeps),
+
+    /// Verify crates of the current workspace
+    #[structopt(name = "workspace")]
+    Workspace(VerifyWorkspace),
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -105,6 +143,26 @@ pub enum Fetch {
     All,
 }
 
+#[derive(Debug, StructOpt, Clone)]
+pub struct ImportAdvisories {
+    /// Directory of a checkout of the RustSec advisory database
+    #[structopt(parse(from_os_str))]
+    pub db_dir: PathBuf,
+
+    /// Own Id to sign the advisories with, instead of `advisory-bot-id` of
+    /// the user config
+    #[structopt(long = "bot-id")]
+    pub bot_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum Import {
+    #[structopt(name = "advisories")]
+    /// Flag the dependencies affected by RustSec advisories, as reviewed by
+    /// an advisory bot Id
+    Advisories(ImportAdvisories),
+}
+
 #[derive(Debug, StructOpt, Clone)]
 pub enum QueryId {
     /// Show current Id
@@ -131,6 +189,9 @@ pub enum QueryId {
 pub struct QueryReview {
     #[structopt(flatten)]
     pub crate_: CrateSelector,
+    /// Only reviews with this tag, eg. `crypto`; can be given many times
+    #[structopt(long = "tag")]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -179,6 +240,30 @@ pub struct ReviewOrGoto {
     pub independent: bool,
 }
 
+#[derive(Debug, StructOpt, Clone)]
+pub struct ReviewMember {
+    /// Name of the crate (default: the one containing the current directory)
+    pub name: Option<String>,
+
+    /// Flag the crate instead
+    #[structopt(long = "flag")]
+    pub flag: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct DiffReview {
+    /// Name of the crate
+    pub name: String,
+    /// Already reviewed version
+    pub old_version: String,
+    /// Version to review
+    pub new_version: String,
+
+    /// Flag the crate instead
+    #[structopt(long = "flag")]
+    pub flag: bool,
+}
+
 #[derive(Debug, StructOpt, Clone)]
 pub enum Command {
     /// Create an Id, ...
@@ -197,6 +282,10 @@ pub enum Command {
     #[structopt(name = "verify")]
     Verify(Verify),
 
+    /// Tell which dependencies have newer versions with better review coverage
+    #[structopt(name = "update-check")]
+    UpdateCheck(UpdateCheck),
+
     /// Review a crate
     #[structopt(name = "review")]
     Review(ReviewOrGoto),
@@ -205,6 +294,14 @@ pub enum Command {
     #[structopt(name = "flag")]
     Flag(ReviewOrGoto),
 
+    /// Review a crate of the current workspace
+    #[structopt(name = "review-member")]
+    ReviewMember(ReviewMember),
+
+    /// Review only the changes of a crate since an already reviewed version
+    #[structopt(name = "diff-review")]
+    DiffReview(DiffReview),
+
     /// Query Ids, packages, reviews...
     #[structopt(name = "query")]
     Query(Query),
@@ -221,6 +318,10 @@ pub enum Command {
     #[structopt(name = "fetch")]
     Fetch(Fetch),
 
+    /// Import proofs from other formats
+    #[structopt(name = "import")]
+    Import(Import),
+
     /// Run raw git commands in the local proof repository
     #[structopt(name = "git")]
     #[structopt(raw(setting = "structopt::clap::AppSettings::TrailingVarArg"))]
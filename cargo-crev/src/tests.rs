@@ -0,0 +1,136 @@
+use super::*;
+
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fs,
+    io::{self, Write},
+};
+use tar::{EntryType, Header};
+
+/// A `.crate` file at `path`, with an entry added by `add` for each of
+/// `entries`
+fn write_crate_file<T>(
+    path: &Path,
+    entries: &[T],
+    add: impl Fn(&mut tar::Builder<GzEncoder<fs::File>>, &T) -> io::Result<()>,
+) -> Result<()> {
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        fs::File::create(path)?,
+        Compression::default(),
+    ));
+    for entry in entries {
+        add(&mut builder, entry)?;
+    }
+    builder.into_inner()?.finish()?.flush()?;
+    Ok(())
+}
+
+/// Add a file at `path` with `content`
+fn add_file(
+    builder: &mut tar::Builder<GzEncoder<fs::File>>,
+    path: &str,
+    content: &str,
+) -> io::Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, content.as_bytes())
+}
+
+#[test]
+fn crate_file_digest_is_the_one_of_its_source() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("cargo-crev-test")?;
+    let files = [
+        ("foo-1.0.0/Cargo.toml", "[package]\nname = \"foo\"\n"),
+        ("foo-1.0.0/src/lib.rs", "pub fn foo() {}\n"),
+    ];
+    let src_dir = tmp_dir.path().join("src");
+    for (path, content) in &files {
+        let path = src_dir.join(path);
+        fs::create_dir_all(path.parent().expect("not a root path"))?;
+        fs::write(path, content)?;
+    }
+    let crate_path = tmp_dir.path().join("foo-1.0.0.crate");
+    write_crate_file(&crate_path, &files, |builder, (path, content)| {
+        add_file(builder, path, content)
+    })?;
+
+    let unpacked = crate_file::unpack(&crate_path, &tmp_dir.path().join("unpacked"))?;
+    assert_eq!(unpacked, tmp_dir.path().join("unpacked").join("foo-1.0.0"));
+    let (top_dir, digest) = crate_file::digest(&crate_path, &default())?;
+    assert_eq!(top_dir, "foo-1.0.0");
+    assert_eq!(
+        digest.to_string(),
+        crev_lib::get_dir_digest(&src_dir.join("foo-1.0.0"), &HashSet::new())?.to_string()
+    );
+    Ok(())
+}
+
+#[test]
+fn crate_file_entries_outside_its_dir_are_rejected() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("cargo-crev-test")?;
+    let crate_path = tmp_dir.path().join("foo-1.0.0.crate");
+    let files = [
+        ("foo-1.0.0/src/lib.rs", "pub fn foo() {}\n"),
+        ("bar-1.0.0/src/lib.rs", "pub fn bar() {}\n"),
+    ];
+    write_crate_file(&crate_path, &files, |builder, (path, content)| {
+        add_file(builder, path, content)
+    })?;
+
+    let dst = tmp_dir.path().join("unpacked");
+    let error = crate_file::unpack(&crate_path, &dst).unwrap_err();
+    assert!(error.to_string().contains("outside of foo-1.0.0"));
+    assert!(!dst.join("bar-1.0.0").exists());
+    Ok(())
+}
+
+#[test]
+fn crate_file_hard_links_are_rejected() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("cargo-crev-test")?;
+    let crate_path = tmp_dir.path().join("foo-1.0.0.crate");
+    write_crate_file(&crate_path, &[()], |builder, ()| {
+        add_file(builder, "foo-1.0.0/src/lib.rs", "pub fn foo() {}\n")?;
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Link);
+        header.set_link_name("foo-1.0.0/src/lib.rs")?;
+        header.set_size(0);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "foo-1.0.0/src/main.rs", io::empty())
+    })?;
+
+    let dst = tmp_dir.path().join("unpacked");
+    let error = crate_file::unpack(&crate_path, &dst).unwrap_err();
+    assert!(error.to_string().contains("Hard link"));
+    assert!(!dst.join("foo-1.0.0/src/main.rs").exists());
+    Ok(())
+}
+
+#[test]
+fn advisories_are_read_from_markdown_front_matter() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("cargo-crev-test")?;
+    let crate_dir = tmp_dir.path().join("crates/foo");
+    fs::create_dir_all(&crate_dir)?;
+    fs::write(
+        crate_dir.join("RUSTSEC-2019-0001.md"),
+        "```toml\n[advisory]\nid = \"RUSTSEC-2019-0001\"\npackage = \"foo\"\n\n\
+         [versions]\npatched = [\">= 1.1.0\"]\n```\n\n# Foo is broken\n\nDetails.\n",
+    )?;
+    fs::write(
+        crate_dir.join("RUSTSEC-2019-0002.md"),
+        "```toml\n[advisory]\nid = \"RUSTSEC-2019-0002\"\npackage = \"foo\"\n\
+         withdrawn = \"2019-02-01\"\n```\n\n# False alarm\n",
+    )?;
+
+    let advisories = advisory::load_db(tmp_dir.path())?;
+    assert_eq!(advisories.len(), 1);
+    assert_eq!(advisories[0].id, "RUSTSEC-2019-0001");
+    assert!(advisories[0].affects(&semver::Version::parse("1.0.0")?));
+    assert!(!advisories[0].affects(&semver::Version::parse("1.1.0")?));
+
+    fs::remove_file(crate_dir.join("RUSTSEC-2019-0001.md"))?;
+    assert!(advisory::load_db(tmp_dir.path()).is_err());
+    Ok(())
+}
@@ -0,0 +1,210 @@
+//! Interactive browsing of verification results (`crev verify --interactive`),
+//! and watching them change (`crev verify --watch`)
+use chrono::prelude::*;
+use common_failures::prelude::*;
+use crev_data::proof::{review::Common, ContentCommon};
+use crev_lib::{
+    local::Local,
+    proof::humanize_age,
+    repo::{FileVerification, Repo},
+    TrustDistanceParams, TrustOrDistrust, VerificationStatus,
+};
+use std::{collections::HashMap, io::Write, path::PathBuf};
+
+/// What to do after looking at a file
+enum FileAction {
+    /// Go back to the list of files
+    Back,
+    /// Something changed; verify again before going back
+    Refresh,
+}
+
+fn print_file(file: &FileVerification) {
+    let now = Utc::now();
+    println!();
+    println!("{} ({})", file.path.display(), file.status);
+    if let Some((rename, previous_status)) = &file.renamed_from {
+        println!(
+            "{} from {}, {}; the content there is {}.",
+            if rename.recorded { "Moved" } else { "Renamed" },
+            rename.from.display(),
+            if rename.unchanged {
+                "content unchanged"
+            } else {
+                "content changed"
+            },
+            previous_status
+        );
+    }
+    if file.reviews.is_empty() {
+        println!("No reviews of the current content of this file.");
+    }
+    for (i, candidate) in file.reviews.iter().enumerate() {
+        let review = &candidate.review;
+        let trust = candidate
+            .reviewer_trust
+            .map(|level| level.to_string())
+            .unwrap_or_else(|| "untrusted".into());
+        println!(
+            "{:>3}. {} ({}, {})",
+            i + 1,
+            review.from.id,
+            review.from.url.url,
+            trust
+        );
+        let details = review.review();
+        println!(
+            "     rating: {}, thoroughness: {}, understanding: {}, {}",
+            details.rating,
+            details.thoroughness,
+            details.understanding,
+            humanize_age(&review.date_utc(), &now)
+        );
+        if let Some(line) = review.comment.lines().find(|line| !line.trim().is_empty()) {
+            println!("     {}", line.trim());
+        }
+    }
+}
+
+fn browse_file(repo: &mut Repo, local: &Local, file: &FileVerification) -> Result<FileAction> {
+    loop {
+        print_file(file);
+        let reply = rprompt::prompt_reply_stderr(
+            "[a]dd to review, [t]rust the author of a review (eg. `t 1`), [b]ack: ",
+        )?;
+        let mut words = reply.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("a"), None) => {
+                repo.add(vec![repo.root_dir().join(&file.path)])?;
+                return Ok(FileAction::Back);
+            }
+            (Some("t"), Some(n)) => {
+                let candidate = n
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|i| file.reviews.get(i));
+                match candidate {
+                    Some(candidate) => {
+                        local.build_trust_proof(
+                            vec![candidate.review.from.id.to_string()],
+                            &crev_common::read_passphrase,
+                            TrustOrDistrust::Trust,
+                        )?;
+                        return Ok(FileAction::Refresh);
+                    }
+                    None => eprintln!("No review number {}", n),
+                }
+            }
+            (Some("b"), None) | (None, _) => return Ok(FileAction::Back),
+            _ => {}
+        }
+    }
+}
+
+/// Browse the files of `repo` that are not verified, and their
+/// candidate reviews, staging files and trusting reviewers along the way
+pub fn browse_verification(
+    repo: &mut Repo,
+    allow_dirty: bool,
+    trust_params: &TrustDistanceParams,
+) -> Result<()> {
+    let local = Local::auto_open()?;
+    let mut files: Vec<_> = vec![];
+    let mut stale = true;
+    loop {
+        if stale {
+            files = repo
+                .files_verify(allow_dirty, trust_params)?
+                .into_iter()
+                .filter(|file| !file.status.is_verified())
+                .collect();
+            stale = false;
+        }
+        if files.is_empty() {
+            println!("All files are verified.");
+            return Ok(());
+        }
+
+        println!();
+        for (i, file) in files.iter().enumerate() {
+            let renamed = match &file.renamed_from {
+                Some((rename, _)) => format!(", renamed from {}", rename.from.display()),
+                None => "".into(),
+            };
+            println!(
+                "{:>3}. {:<8} {} ({} reviews{})",
+                i + 1,
+                file.status,
+                file.path.display(),
+                file.reviews.len(),
+                renamed
+            );
+        }
+        let reply = rprompt::prompt_reply_stderr(
+            "Pick a file by number, [a]dd all to review, [r]efresh, [q]uit: ",
+        )?;
+        match reply.trim() {
+            "q" | "" => return Ok(()),
+            "r" => stale = true,
+            "a" => {
+                let paths = files
+                    .iter()
+                    .map(|file| repo.root_dir().join(&file.path))
+                    .collect();
+                repo.add(paths)?;
+            }
+            n => {
+                let file = n
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|i| files.get(i));
+                match file {
+                    Some(file) => {
+                        if let FileAction::Refresh = browse_file(repo, &local, file)? {
+                            stale = true;
+                        }
+                    }
+                    None => eprintln!("No file number {}", n),
+                }
+            }
+        }
+    }
+}
+
+/// Verify the files of `repo` again as they change, printing the ones
+/// whose status changed, and a status line with the totals
+pub fn watch_verification(repo: &mut Repo, trust_params: &TrustDistanceParams) -> Result<()> {
+    let mut previous: HashMap<PathBuf, VerificationStatus> = HashMap::new();
+    repo.watch_verify(trust_params, &mut |changed, files| {
+        let mut current = HashMap::new();
+        let mut printed = false;
+        for file in files {
+            let affected =
+                changed.contains(&file.path) || previous.get(&file.path) != Some(&file.status);
+            if affected && !(previous.is_empty() && file.status.is_verified()) {
+                if !printed {
+                    // end the status line
+                    eprintln!();
+                    printed = true;
+                }
+                println!("{:<8} {}", file.status, file.path.display());
+            }
+            current.insert(file.path.clone(), file.status.clone());
+        }
+        let verified = files
+            .iter()
+            .filter(|file| file.status.is_verified())
+            .count();
+        eprint!(
+            "\r{}: {}/{} files verified ",
+            chrono::Local::now().format("%H:%M:%S"),
+            verified,
+            files.len()
+        );
+        std::io::stderr().flush()?;
+        previous = current;
+        Ok(true)
+    })
+}
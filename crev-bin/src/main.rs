@@ -1,5 +1,7 @@
 #![allow(deprecated)]
 
+#[macro_use]
+extern crate failure;
 #[macro_use]
 extern crate quicli;
 use crev_common;
@@ -9,35 +11,405 @@ use rprompt;
 extern crate structopt;
 
 use crev_lib::TrustOrDistrust::*;
-use crev_lib::{local::Local, repo::Repo};
+use crev_lib::{local::Local, repo::Repo, todo, ProjectIdentity};
 use default::default;
 use hex;
-use std::path::PathBuf;
-use structopt::StructOpt;
+use std::path::{Path, PathBuf};
 
+mod browse;
 mod opts;
 mod util;
 
-main!(|opts: opts::Opts| match opts.command {
+main!(|| match util::parse_opts_or_run_plugin()?.command {
     opts::Command::Id(id) => match id.id_command {
         opts::IdCommand::Show => unimplemented!(),
         opts::IdCommand::New => unimplemented!(),
+        opts::IdCommand::Rekey => {
+            let local = Local::auto_open()?;
+            local.rekey_current_id(&crev_common::read_passphrase)?;
+        }
+        opts::IdCommand::Passwd => {
+            let local = Local::auto_open()?;
+            local.change_current_id_passphrase(
+                &crev_common::read_passphrase,
+                &crev_common::read_new_passphrase,
+            )?;
+        }
+        opts::IdCommand::Logout => {
+            let local = Local::auto_open()?;
+            if !local.forget_current_id_passphrase()? {
+                eprintln!("No passphrase of the current CrevID in the keychain.");
+            }
+        }
+        opts::IdCommand::ExportPub(export) => {
+            let local = Local::auto_open()?;
+            let capabilities = if export.capabilities.is_empty() {
+                None
+            } else {
+                Some(export.capabilities)
+            };
+            let bundle = local.export_current_pub_id(
+                export.name,
+                export.comment,
+                capabilities,
+                &crev_common::read_passphrase,
+            )?;
+            print!("{}", bundle);
+        }
+        opts::IdCommand::ImportPub(import) => {
+            let local = Local::auto_open()?;
+            let bundle = util::read_pub_id_bundle(import.path.as_deref())?;
+            let bundle_id = &bundle.bundle.id;
+            eprintln!("Id:          {}", bundle_id.id);
+            eprintln!("URL:         {}", bundle_id.url.url);
+            if !bundle.bundle.name.is_empty() {
+                eprintln!("Name:        {}", bundle.bundle.name);
+            }
+            if !bundle.bundle.comment.is_empty() {
+                eprintln!("Comment:     {}", bundle.bundle.comment);
+            }
+            if let Some(ref capabilities) = bundle.bundle.capabilities {
+                let capabilities: Vec<_> = capabilities.iter().map(ToString::to_string).collect();
+                eprintln!("Restricted:  {}", capabilities.join(", "));
+            }
+            eprintln!("Fingerprint: {}", bundle_id.id.fingerprint());
+            if !crev_common::yes_or_no_was_y(
+                "Does the fingerprint match the one the owner gave you? (y/n) ",
+            )? {
+                bail!("Import aborted");
+            }
+            local.import_pub_id(&bundle)?;
+        }
+        opts::IdCommand::EncryptTo(encrypt_to) => {
+            let local = Local::auto_open()?;
+            local.set_encrypt_to(encrypt_to.ids)?;
+        }
+        opts::IdCommand::Compromise(compromise) => {
+            let local = Local::auto_open()?;
+            let since = match compromise.since {
+                Some(since) => Some(chrono::DateTime::parse_from_rfc3339(&since)?),
+                None => None,
+            };
+            let id = local.read_current_id()?;
+            eprintln!(
+                "This revokes {} for good: its proofs will be ignored by everyone.",
+                id
+            );
+            if !crev_common::yes_or_no_was_y("Continue? (y/n) ")? {
+                bail!("Canceled");
+            }
+            let backup_passphrase = || {
+                eprintln!("Unlocking the backup id");
+                crev_common::read_passphrase()
+            };
+            let response = local.respond_to_compromise(
+                since,
+                compromise.comment,
+                &crev_common::read_passphrase,
+                &backup_passphrase,
+            )?;
+            match response.revocation {
+                Some(ref revocation) => eprintln!(
+                    "Revoked {} proofs: {}",
+                    response.revoked,
+                    crev_common::base64_encode(&revocation.digest)
+                ),
+                None => eprintln!("No proofs to revoke"),
+            }
+            eprintln!("Marked {} revoked in the proof repository", response.id);
+            if let Some(ref backup_id) = response.backup_id {
+                eprintln!(
+                    "Backup id {} re-signed {} Trust Proofs, and is now current",
+                    backup_id,
+                    response.resigned.len()
+                );
+            }
+            let message = format!("Revoke compromised id {}", response.id);
+            let pushed = local
+                .run_git(vec!["commit".into(), "-m".into(), message.into()])?
+                .success()
+                && local.run_git(vec!["push".into()])?.success();
+            eprintln!();
+            eprintln!("What's left to do:");
+            for step in response.checklist(pushed) {
+                eprintln!("* {}", step);
+            }
+        }
+        opts::IdCommand::Link(link) => {
+            let local = Local::auto_open()?;
+            let other_id = local.own_id_from_str(&link.id)?;
+            let other_passphrase = || {
+                eprintln!("Unlocking {}", other_id);
+                crev_common::read_passphrase()
+            };
+            let proofs = local.link_own_ids(
+                &other_id,
+                link.level,
+                link.back_level.unwrap_or(link.level),
+                link.comment,
+                &crev_common::read_passphrase,
+                &other_passphrase,
+            )?;
+            for proof in proofs {
+                print!("{}", proof);
+            }
+        }
+        opts::IdCommand::List => {
+            let local = Local::auto_open()?;
+            local.list_own_ids()?;
+        }
+        opts::IdCommand::Switch(switch) => {
+            let local = Local::auto_open()?;
+            local.switch_id(&switch.id)?;
+        }
+        opts::IdCommand::Name(name) => {
+            let local = Local::auto_open()?;
+            let id = match name.id {
+                Some(name_or_id) => local.own_id_from_str(&name_or_id)?,
+                None => local.read_current_id()?,
+            };
+            local.name_id(&id, &name.name)?;
+        }
     },
     opts::Command::Trust(trust) => match trust {
         opts::Trust::Add(trust) => {
             let local = Local::auto_open()?;
-            local.build_trust_proof(trust.pub_ids, &crev_common::read_passphrase, Trust)?;
+            let level = if trust.wizard {
+                if trust.pub_ids.len() != 1 {
+                    bail!("The wizard works with exactly one id");
+                }
+                let info = local.trust_wizard_info(&trust.pub_ids[0], 5)?;
+                println!("{}", info);
+                let level = util::ask_trust_wizard_questions()?.trust_level();
+                eprintln!("Suggested trust level: {}", level);
+                level
+            } else {
+                trust
+                    .level
+                    .unwrap_or(crev_data::proof::trust::TrustLevel::Medium)
+            };
+            local.create_trust_proof(
+                trust.pub_ids,
+                level,
+                trust.comment,
+                &crev_common::read_passphrase,
+            )?;
+        }
+        opts::Trust::Distrust(distrust) => {
+            let local = Local::auto_open()?;
+            local.build_trust_proof(distrust.pub_ids, &crev_common::read_passphrase, Distrust)?;
         }
+        opts::Trust::Graph(graph) => {
+            let local = Local::auto_open()?;
+            let (db, trust_set) = local.load_db(&graph.trust_params.into())?;
+            print!("{}", trust_set.to_dot(&db));
+        }
+        opts::Trust::Simulate(simulate) => {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            let params = simulate.trust_params.into();
+            if simulate.pub_ids.is_empty() {
+                let candidates = repo.trust_candidates(simulate.level, &params)?;
+                if candidates.is_empty() {
+                    eprintln!("Trusting no single reviewer would verify more files");
+                }
+                let (db, _trust_set) = Local::auto_open()?.load_db(&params)?;
+                for candidate in candidates {
+                    let url = db
+                        .lookup_url(&candidate.id)
+                        .map(|url| url.url.clone())
+                        .unwrap_or_default();
+                    println!("{:>5} {} {}", candidate.verified.len(), candidate.id, url);
+                }
+            } else {
+                let ids = simulate
+                    .pub_ids
+                    .iter()
+                    .map(|id| crev_data::Id::crevid_from_str(id))
+                    .collect::<common_failures::Result<Vec<_>>>()?;
+                let changes = repo.simulate_trust(&ids, simulate.level, &params)?;
+                if changes.is_empty() {
+                    eprintln!("No file would change its status");
+                }
+                for change in changes {
+                    println!(
+                        "{:<10} -> {:<10} {}",
+                        change.before,
+                        change.after,
+                        change.path.display()
+                    );
+                }
+            }
+        }
+        opts::Trust::Explain(explain) => {
+            let local = Local::auto_open()?;
+            let from = local
+                .get_current_userid()?
+                .ok_or_else(|| format_err!("Current Id not set"))?;
+            let to = crev_data::Id::crevid_from_str(&explain.pub_id)?;
+            let params = explain.trust_params.into();
+            let (db, _trust_set) = local.load_db(&params)?;
+            let chains = db.explain_trust(&from, &to, &params);
+            if chains.is_empty() {
+                bail!("{} is not trusted through any Trust Proofs", explain.pub_id);
+            }
+            let name = |id: &crev_data::Id| match db.lookup_url(id) {
+                Some(url) => url.url.clone(),
+                None => id.to_string(),
+            };
+            for (i, chain) in chains.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                for link in chain {
+                    println!("{} trusts {}: {}", name(&link.from), name(&link.to), link);
+                }
+            }
+        }
+        opts::Trust::OrgPolicy(org_policy) => match org_policy {
+            opts::TrustOrgPolicy::Set(set) => {
+                let local = Local::auto_open()?;
+                let mut config = local.load_user_config()?;
+                config.org_policy = Some(crev_lib::org_policy::OrgPolicySource {
+                    url: set.url,
+                    curator: crev_data::Id::crevid_from_str(&set.curator)?,
+                });
+                local.store_user_config(&config)?;
+                local.fetch_org_policy()?;
+            }
+            opts::TrustOrgPolicy::Unset => {
+                let local = Local::auto_open()?;
+                let mut config = local.load_user_config()?;
+                config.org_policy = None;
+                local.store_user_config(&config)?;
+            }
+            opts::TrustOrgPolicy::Fetch => {
+                let local = Local::auto_open()?;
+                if local.fetch_org_policy()?.is_none() {
+                    bail!("No organization policy configured; see `crev trust org-policy set`");
+                }
+            }
+            opts::TrustOrgPolicy::Show => {
+                let local = Local::auto_open()?;
+                match local.load_org_policy()? {
+                    Some(policy) => {
+                        let policy = policy.policy;
+                        println!("Organization: {}", policy.name);
+                        println!("Curator:      {}", policy.from.id);
+                        println!("Date:         {}", policy.date.to_rfc3339());
+                        for required in &policy.required_reviews {
+                            println!(
+                                "Required:     {} reviewers with {} trust for {}",
+                                required.reviewers, required.trust, required.path
+                            );
+                        }
+                        for seed in &policy.seeds {
+                            println!("Seed:         {} {}", seed.id.id, seed.id.url.url);
+                        }
+                        for banned in &policy.banned {
+                            println!("Banned:       {} {}", banned.id, banned.reason);
+                        }
+                    }
+                    None => eprintln!("No organization policy in effect"),
+                }
+            }
+            opts::TrustOrgPolicy::Create(create) => {
+                let local = Local::auto_open()?;
+                let banned = create
+                    .banned
+                    .iter()
+                    .map(|banned| {
+                        let mut parts = banned.splitn(2, ':');
+                        let id = parts.next().unwrap_or_default();
+                        Ok(crev_data::org_policy::BannedId {
+                            id: crev_data::Id::crevid_from_str(id)?,
+                            reason: parts.next().unwrap_or_default().to_owned(),
+                        })
+                    })
+                    .collect::<common_failures::Result<_>>()?;
+                let policy = local.create_org_policy(
+                    create.name,
+                    create.required_reviews,
+                    create.seeds,
+                    banned,
+                    &crev_common::read_passphrase,
+                )?;
+                print!("{}", policy);
+            }
+        },
+        opts::Trust::Seed(seed) => match seed {
+            opts::TrustSeed::Create(create) => {
+                let local = Local::auto_open()?;
+                let list = local.create_seed_list(
+                    create.name,
+                    create.pub_ids,
+                    &crev_common::read_passphrase,
+                )?;
+                print!("{}", list);
+            }
+            opts::TrustSeed::Import(import) => {
+                let local = Local::auto_open()?;
+                let list = crev_data::seed_list::SignedSeedList::parse(
+                    &crev_common::read_file_to_string(&import.path)?,
+                )?;
+                let curator = &list.list.from;
+                eprintln!("Seed list:   {}", list.list.name);
+                eprintln!("Curator:     {} {}", curator.id, curator.url.url);
+                eprintln!("Fingerprint: {}", curator.id.fingerprint());
+                eprintln!("Ids:         {}", list.list.ids.len());
+                eprintln!(
+                    "Listed ids will be trusted at `low` level, until you trust them yourself."
+                );
+                if !crev_common::yes_or_no_was_y(
+                    "Does the fingerprint match the one the curator published? (y/n) ",
+                )? {
+                    bail!("Import aborted");
+                }
+                local.import_seed_list(&list)?;
+            }
+            opts::TrustSeed::List => {
+                let local = Local::auto_open()?;
+                for list in local.list_seed_lists()? {
+                    println!(
+                        "{} {} ({} ids)",
+                        list.list.from.id,
+                        list.list.name,
+                        list.list.ids.len()
+                    );
+                }
+            }
+            opts::TrustSeed::Remove(remove) => {
+                let local = Local::auto_open()?;
+                local.remove_seed_list(&remove.curator)?;
+            }
+        },
     },
     opts::Command::Add(add) => {
-        let mut repo = Repo::auto_open()?;
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
         repo.add(add.paths)?;
     }
     opts::Command::Commit(opts) => {
-        let mut repo = Repo::auto_open()?;
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        if opts.date.is_some() && !opts.dry_run {
+            bail!("`--date` is only supported with `--dry-run`");
+        }
         if opts.all {
+        } else if opts.dry_run {
+            let date = match opts.date {
+                Some(date) => Some(chrono::DateTime::parse_from_rfc3339(&date)?),
+                None => None,
+            };
+            print!(
+                "{}",
+                repo.commit_dry_run(opts.allow_dirty, opts.summarize, opts.grouped, date)?
+            );
         } else {
-            repo.commit(&crev_common::read_passphrase, opts.allow_dirty)?;
+            repo.commit(
+                &crev_common::read_passphrase,
+                opts.allow_dirty,
+                opts.summarize,
+                opts.grouped,
+                opts.id.as_deref(),
+            )?;
         }
     }
     opts::Command::Package(package) => match package {
@@ -47,29 +419,193 @@ main!(|opts: opts::Opts| match opts.command {
             Repo::init(&PathBuf::from(".".to_string()), cur_id.to_string())?;
         }
         opts::Package::Trust(package_trust) => {
-            let mut repo = Repo::auto_open()?;
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
             repo.trust_package(&crev_common::read_passphrase, package_trust.allow_dirty)?;
+            let local = Local::auto_open()?;
+            local.complete_todo(todo::Kind::Revision, &repo.root_dir().display().to_string())?;
         }
         opts::Package::Verify(verify) => {
-            let mut repo = Repo::auto_open()?;
-            println!("{}", repo.package_verify(verify.allow_dirty)?);
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            let trust_params = verify.trust_params.into();
+            let statuses = repo.package_verify_nested(verify.allow_dirty, &trust_params)?;
+            let unverified = statuses
+                .iter()
+                .filter(|(_rel_dir, status)| !status.is_verified())
+                .count();
+            for (rel_dir, status) in &statuses {
+                if rel_dir.as_os_str().is_empty() {
+                    println!("{}", status);
+                } else {
+                    println!("{:<8} {}", status, rel_dir.display());
+                }
+            }
+            if unverified > 0 {
+                bail!("{} of {} scopes not verified", unverified, statuses.len());
+            }
         }
         opts::Package::Digest(digest) => {
-            let mut repo = Repo::auto_open()?;
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
             println!("{}", repo.package_digest(digest.allow_dirty)?);
         }
+        opts::Package::Identify(identify) => {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            let project_id =
+                repo.identify_project(identify.name, &identify.url, &crev_common::read_passphrase)?;
+            println!("{}", project_id);
+        }
+        opts::Package::Identity => {
+            let repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            let project_id = repo.load_package_config()?.project_id.ok_or_else(|| {
+                format_err!("Project identity not set. Use `crev package identify` to create it.")
+            })?;
+            let local = Local::auto_open()?;
+            let (db, _trust_set) = local.load_db(&default())?;
+            match db.get_project_identity(&project_id) {
+                ProjectIdentity::Unknown => {
+                    bail!("No Project Proof found for project {}", project_id)
+                }
+                ProjectIdentity::Unique(project) => print!("{}", project),
+                ProjectIdentity::Colliding(projects) => {
+                    for project in projects {
+                        println!("---");
+                        print!("{}", project);
+                    }
+                    bail!(
+                        "Identity of project {} collides with other projects",
+                        project_id
+                    );
+                }
+            }
+            for old_project_id in db.get_project_lineage(&project_id).iter().skip(1) {
+                eprintln!("Continues project {}", old_project_id);
+            }
+            if let Some(migration) = db.get_project_migration(&project_id) {
+                eprintln!("Warning: project moved to {}", migration.url);
+            }
+        }
+        opts::Package::Migrate(migrate) => {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            let project_id =
+                repo.migrate_project(&migrate.url, migrate.name, &crev_common::read_passphrase)?;
+            println!("{}", project_id);
+        }
+        opts::Package::HandOver(hand_over) => {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            repo.hand_over_trust_root(&hand_over.id, &crev_common::read_passphrase)?;
+        }
+        opts::Package::RequestReview(request) => {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            repo.request_review(&crev_common::read_passphrase, request.allow_dirty)?;
+        }
+        opts::Package::Advisory(advisory) => {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            repo.create_advisory(
+                advisory.severity,
+                advisory.introduced.as_deref(),
+                advisory.fixed.as_deref(),
+                &advisory.paths,
+                &crev_common::read_passphrase,
+            )?;
+        }
     },
-    opts::Command::Status => {
-        let mut repo = Repo::auto_open()?;
-        repo.status()?;
+    opts::Command::Status(status) => {
+        if status.porcelain {
+            println!("{}", util::porcelain_status(status.fast));
+        } else {
+            let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+            repo.status()?;
+        }
     }
     opts::Command::Remove(remove) => {
-        let mut repo = Repo::auto_open()?;
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
         repo.remove(remove.paths)?;
     }
+    opts::Command::MergeStaging(merge) => {
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        let summary = repo.merge_staging(&merge.path, &|conflict| {
+            util::resolve_staging_conflict(conflict, merge.use_current)
+        })?;
+        println!(
+            "Merged: {} added, {} already staged, {} conflicts",
+            summary.added, summary.same, summary.conflicts
+        );
+    }
+    opts::Command::Move(mv) => {
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        repo.move_path(&mv.from, &mv.to, &crev_common::read_passphrase)?;
+    }
     opts::Command::Verify(verify_opts) => {
-        let mut repo = Repo::auto_open()?;
-        repo.package_verify(verify_opts.allow_dirty)?;
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        let trust_params = verify_opts.trust_params.into();
+        if verify_opts.watch {
+            browse::watch_verification(&mut repo, &trust_params)?;
+        } else if verify_opts.interactive {
+            browse::browse_verification(&mut repo, verify_opts.allow_dirty, &trust_params)?;
+        } else if !verify_opts.paths.is_empty() {
+            if verify_opts.json {
+                bail!("`--json` is only supported when verifying the whole package");
+            }
+            let files =
+                repo.verify_paths(&verify_opts.paths, verify_opts.allow_dirty, &trust_params)?;
+            let unverified: Vec<_> = files
+                .iter()
+                .filter(|file| !file.status.is_verified())
+                .collect();
+            for file in &unverified {
+                println!("{:<10} {}", file.status, file.path.display());
+            }
+            println!("{}", crev_lib::repo::ScopeStatus::of_files(&files));
+            if !unverified.is_empty() {
+                bail!("{} of {} files not verified", unverified.len(), files.len());
+            }
+        } else {
+            let result = repo.verify(verify_opts.allow_dirty, &trust_params)?;
+            if verify_opts.json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                for file in result
+                    .files
+                    .iter()
+                    .filter(|file| file.outcome != crev_lib::repo::FileOutcome::Verified)
+                {
+                    println!("{:<22} {}", file.outcome, file.path.display());
+                }
+                for file in result.files.iter().filter(|file| file.self_reviewed) {
+                    println!("{:<22} {}", "self-reviewed", file.path.display());
+                }
+                for (kind, (files, verified)) in result.coverage_by_kind() {
+                    println!("{:<7} {} of {} files verified", kind, verified, files);
+                }
+                println!("{}", result.status);
+            }
+            if !result.verified {
+                bail!("Package not verified");
+            }
+        }
+    }
+    opts::Command::Annotate(annotate) => {
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        print!(
+            "{}",
+            repo.annotate(&annotate.path, &annotate.trust_params.into())?
+        );
+    }
+    opts::Command::Stale(stale) => {
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        let params = crev_lib::stale::StaleParams {
+            max_age_days: stale.max_age_days,
+            max_commits: stale.max_commits,
+            max_inactive_days: stale.max_inactive_days,
+        };
+        for review in repo.stale_reviews(&params, &stale.trust_params.into())? {
+            println!("{} {}", review.from, review.date.to_rfc3339());
+            for reason in &review.reasons {
+                println!("  {}", reason);
+            }
+            for path in &review.files {
+                println!("  covers {}", path.display());
+            }
+        }
     }
     opts::Command::Db(cmd) => match cmd {
         opts::Db::Git(git) => {
@@ -79,7 +615,381 @@ main!(|opts: opts::Opts| match opts.command {
         }
         opts::Db::Fetch => {
             let local = Local::auto_open()?;
+            crev_lib::util::interrupt::install_handler();
             local.fetch_trusted(default())?;
         }
+        opts::Db::Decrypt => {
+            let local = Local::auto_open()?;
+            let count = local.decrypt_proofs(&crev_common::read_passphrase)?;
+            eprintln!("Decrypted {} proofs", count);
+        }
+        opts::Db::Import(import) => {
+            let local = Local::auto_open()?;
+            let bundle = crev_common::read_file_to_string(&import.path)?;
+            let count = local.import_bundle(&bundle, &crev_common::read_passphrase)?;
+            eprintln!("Imported {} new proofs", count);
+        }
+        opts::Db::Reindex => {
+            let local = Local::auto_open()?;
+            let index = local.rebuild_proof_index()?;
+            eprintln!("Indexed {} proofs", index.entries().count());
+        }
     },
+    opts::Command::Quarantine(cmd) => {
+        let local = Local::auto_open()?;
+        let quarantine = local.quarantine();
+        match cmd {
+            opts::Quarantine::List => {
+                for (id, entry) in quarantine.list()? {
+                    println!(
+                        "{} {} {} {}: {}",
+                        id,
+                        entry.date.to_rfc3339(),
+                        entry.url,
+                        entry.path.display(),
+                        entry.reason
+                    );
+                }
+            }
+            opts::Quarantine::Inspect(inspect) => {
+                let (id, entry, data) = quarantine.inspect(&inspect.id)?;
+                println!("Id:     {}", id);
+                println!("Date:   {}", entry.date.to_rfc3339());
+                println!("URL:    {}", entry.url);
+                println!("Path:   {}", entry.path.display());
+                println!("Reason: {}", entry.reason);
+                println!();
+                print!("{}", data);
+            }
+            opts::Quarantine::Purge(purge) => {
+                let purged = quarantine.purge(purge.id.as_deref())?;
+                eprintln!("Purged {} quarantined entries", purged);
+            }
+        }
+    }
+    opts::Command::AuditLog(audit_log) => {
+        let local = Local::auto_open()?;
+        let log = local.audit_log();
+        if audit_log.check {
+            let problems = log.check()?;
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+            if !problems.is_empty() {
+                bail!("The audit log was tampered with");
+            }
+            eprintln!("The audit log is intact");
+        } else {
+            for entry in log.entries()? {
+                let by = match entry.by {
+                    Some(by) => format!("signed by {}", by),
+                    None => "unsigned".to_owned(),
+                };
+                println!("{} {} ({})", entry.date.to_rfc3339(), entry.action, by);
+            }
+        }
+    }
+    opts::Command::Import(import) => {
+        let local = Local::auto_open()?;
+        let count = if import.path == Path::new("-") {
+            let stdin = std::io::stdin();
+            local.import_proofs(stdin.lock(), import.format)?
+        } else {
+            let file = std::io::BufReader::new(std::fs::File::open(&import.path)?);
+            local.import_proofs(file, import.format)?
+        };
+        eprintln!("Imported {} new proofs", count);
+    }
+    opts::Command::Export(export) => {
+        let local = Local::auto_open()?;
+        let filter = crev_lib::proof_index::ProofFilter {
+            author: export
+                .author
+                .as_ref()
+                .map(|id| crev_data::Id::crevid_from_str(id))
+                .transpose()?,
+            proof_type: export.proof_type,
+            ..default()
+        };
+        let count = if export.path == Path::new("-") {
+            let stdout = std::io::stdout();
+            local.export_proofs(
+                &filter,
+                export.include_decrypted,
+                stdout.lock(),
+                export.format,
+            )?
+        } else {
+            let file = std::fs::File::create(&export.path)?;
+            local.export_proofs(&filter, export.include_decrypted, file, export.format)?
+        };
+        eprintln!("Exported {} proofs", count);
+    }
+    opts::Command::Store(cmd) => {
+        let local = Local::auto_open()?;
+        match cmd {
+            opts::Store::WriteManifest => {
+                let own_id = local.read_current_unlocked_id(&crev_common::read_passphrase)?;
+                local.write_store_manifest(Some(&own_id))?;
+                eprintln!("Wrote {}", local.store_manifest_path().display());
+            }
+            opts::Store::VerifyManifest => {
+                let (manifest, changes) = local.verify_store_manifest()?;
+                for change in &changes {
+                    eprintln!("{}", change);
+                }
+                let by = match manifest.by {
+                    Some(by) => format!("signed by {}", by),
+                    None => "unsigned".to_owned(),
+                };
+                if !changes.is_empty() {
+                    bail!(
+                        "Proof files changed since the manifest of {} ({})",
+                        manifest.date.to_rfc3339(),
+                        by
+                    );
+                }
+                eprintln!(
+                    "Proof files match the manifest of {} ({})",
+                    manifest.date.to_rfc3339(),
+                    by
+                );
+            }
+        }
+    }
+    opts::Command::Revoke(revoke) => {
+        let local = Local::auto_open()?;
+        local.create_revocation_proof(
+            revoke.digests,
+            revoke.comment,
+            &crev_common::read_passphrase,
+        )?;
+    }
+    opts::Command::Amend(amend) => {
+        let local = Local::auto_open()?;
+        let proof = local.amend_proof(&amend.digest, &crev_common::read_passphrase)?;
+        eprintln!(
+            "Proof {} superseded by {}",
+            amend.digest,
+            crev_common::base64_encode(&proof.digest)
+        );
+    }
+    opts::Command::Reissue => {
+        let local = Local::auto_open()?;
+        let expired = local.expired_own_proofs()?;
+        for proof in &expired {
+            println!(
+                "{} {}",
+                crev_common::base64_encode(&proof.digest),
+                proof.content.draft_title()
+            );
+        }
+        if expired.is_empty() {
+            eprintln!("No expired proofs");
+        } else if crev_common::yes_or_no_was_y("Reissue them? (y/n) ")? {
+            let reissued = local.reissue_proofs(&expired, &crev_common::read_passphrase)?;
+            eprintln!("Reissued {} proofs", reissued.len());
+        }
+    }
+    opts::Command::Show(show) => {
+        let local = Local::auto_open()?;
+        let proof = local.find_proof_by_digest(&show.digest)?;
+        if show.pretty {
+            let (db, _trust_set) = local.load_db(&default())?;
+            crev_lib::proof::Pretty::new(&proof, &db).print()?;
+        } else if show.json {
+            print!("{}", proof.to_string_in(crev_data::proof::Format::Json)?);
+        } else {
+            print!("{}", proof);
+        }
+    }
+    opts::Command::Report(report) => {
+        let local = Local::auto_open()?;
+        let proof = local.find_proof_by_digest(&report.digest)?;
+        let (db, _trust_set) = local.load_db(&default())?;
+        print!(
+            "{}",
+            crev_lib::proof::Pretty::new(&proof, &db).to_report(report.format)
+        );
+    }
+    opts::Command::Reproduce(reproduce) => {
+        let local = Local::auto_open()?;
+        let proof = local.find_proof_by_digest(&reproduce.digest)?;
+        let mut signature_valid = false;
+        for step in proof.reproduce() {
+            match step.error {
+                None => println!("== {}: ok", step.name),
+                Some(ref e) => println!("== {}: {}", step.name, e),
+            }
+            println!("{}", step.output.trim_end());
+            signature_valid = step.error.is_none();
+        }
+        if !signature_valid {
+            bail!("Signature of proof {} is not valid", reproduce.digest);
+        }
+    }
+    opts::Command::Attest(attest) => {
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        repo.attest_build(
+            &attest.artifact,
+            &crev_common::read_passphrase,
+            attest.allow_dirty,
+        )?;
+    }
+    opts::Command::Evidence(evidence) => {
+        let mut repo = Repo::auto_open_with(&util::choose_package_scope)?;
+        let trust_params = evidence.trust_params.into();
+        let collected =
+            repo.collect_evidence(&evidence.rev, &crev_common::read_passphrase, &trust_params)?;
+        let output = match evidence.output {
+            Some(output) => output,
+            None => PathBuf::from(format!("evidence-{}.tar", evidence.rev)),
+        };
+        collected.write_tar(&mut std::fs::File::create(&output)?)?;
+        eprintln!(
+            "Wrote {}: {} with {} proofs",
+            output.display(),
+            collected.report.report.status,
+            collected.proofs.len()
+        );
+    }
+    opts::Command::Bundle(bundle) => {
+        let mut repo = match bundle.for_ {
+            Some(ref path) => Repo::open(path)?,
+            None => Repo::auto_open_with(&util::choose_package_scope)?,
+        };
+        let recipient = crev_data::Id::crevid_from_str(&bundle.recipient)?;
+        let proofs = repo.proof_bundle(&recipient, &bundle.trust_params.into())?;
+        let text = if bundle.encrypt {
+            let local = Local::auto_open()?;
+            let own_id = local.read_current_unlocked_id(&crev_common::read_passphrase)?;
+            crev_data::encrypted::EncryptedProof::seal_bundle(&proofs, &own_id, &[recipient])?
+                .to_string()
+        } else {
+            proofs.iter().map(ToString::to_string).collect()
+        };
+        match bundle.output {
+            Some(ref output) => std::fs::write(output, &text)?,
+            None => print!("{}", text),
+        }
+        eprintln!("Bundled {} proofs", proofs.len());
+    }
+    opts::Command::Sign(sign) => {
+        let local = Local::auto_open()?;
+        let mut paths = sign.paths;
+        if sign.all_drafts {
+            paths.extend(local.queued_drafts()?);
+        }
+        if paths.is_empty() {
+            bail!("No drafts to sign");
+        }
+        let approve: crev_lib::local::DraftApprovalFn = &|path, content| {
+            eprintln!("Draft in {}:", path.display());
+            print!("{}", content.to_unsigned_string());
+            Ok(crev_common::yes_or_no_was_y("Sign it? (y/n) ")?)
+        };
+        let count = local.sign_drafts(&paths, approve, &crev_common::read_passphrase)?;
+        eprintln!("Signed {} proofs", count);
+    }
+    opts::Command::Todo(todo) => {
+        let local = Local::auto_open()?;
+        let mut queue = local.open_todo()?;
+        match todo {
+            opts::Todo::List => {
+                for (i, item) in queue.items().iter().enumerate() {
+                    println!("{:3} {}", i + 1, item);
+                }
+            }
+            opts::Todo::Next => match queue.items().first() {
+                Some(item) => {
+                    println!("{}", item);
+                    eprintln!("{}", util::todo_hint(item));
+                }
+                None => eprintln!("Nothing to review"),
+            },
+            opts::Todo::Add(add) => {
+                let mut item = todo::Item::new(todo::Kind::Request, add.target);
+                item.priority = add.priority;
+                item.comment = add.comment;
+                if !queue.add(item) {
+                    eprintln!("Already queued");
+                }
+            }
+            opts::Todo::Skip(skip) => {
+                let number = queue.skip(skip.number)?;
+                eprintln!("Now number {}", number);
+            }
+            opts::Todo::Done(done) => {
+                let item = queue.complete(done.number)?;
+                eprintln!("Done: {}", item);
+            }
+            opts::Todo::Watch(watch) => {
+                let repo = match watch.dir {
+                    Some(dir) => Repo::open(&dir)?,
+                    None => Repo::auto_open_with(&util::choose_package_scope)?,
+                };
+                let revision = if watch.unreviewed {
+                    String::new()
+                } else {
+                    repo.head_commit_id()
+                        .ok_or_else(|| format_err!("The project has no git revision"))?
+                };
+                queue.watch(repo.root_dir(), revision);
+            }
+            opts::Todo::Unwatch(watch) => {
+                let repo = match watch.dir {
+                    Some(dir) => Repo::open(&dir)?,
+                    None => Repo::auto_open_with(&util::choose_package_scope)?,
+                };
+                if !queue.unwatch(repo.root_dir()) {
+                    bail!("Not watched: {}", repo.root_dir().display());
+                }
+            }
+            opts::Todo::Scan => {
+                let added = queue.scan(&|dir| Repo::open(dir).ok()?.head_commit_id());
+                eprintln!("Queued {} new revisions", added);
+            }
+        }
+        queue.save()?;
+    }
+    opts::Command::Dashboard(dashboard) => {
+        let local = Local::auto_open()?;
+        let mut repo = match Repo::auto_open_with(&util::choose_package_scope) {
+            Ok(repo) => Some(repo),
+            Err(e) => {
+                eprintln!("Not showing coverage: {}", e);
+                None
+            }
+        };
+        crev_lib::dashboard::serve(dashboard.port, &mut || {
+            let scopes = match repo {
+                Some(ref mut repo) => repo.package_verify_nested(true, &Default::default())?,
+                None => vec![],
+            };
+            local.dashboard_info(scopes, 20)
+        })?;
+    }
+    opts::Command::EditorServer(server) => {
+        let local = Local::auto_open()?;
+        let watch_dirs = vec![local.get_proofs_dir_path()?, local.cache_remotes_path()];
+        let mut repo = Repo::auto_open()?;
+        let root_dir = repo.root_dir().to_owned();
+        let verify = &mut || repo.files_verify(true, &Default::default());
+        match server.port {
+            Some(port) => {
+                crev_lib::editor_server::serve_tcp(&root_dir, watch_dirs, port, verify)?
+            }
+            None => {
+                let stdin = std::io::stdin();
+                crev_lib::editor_server::serve(
+                    &root_dir,
+                    watch_dirs,
+                    None,
+                    &mut stdin.lock(),
+                    &mut std::io::stdout(),
+                    verify,
+                )?;
+            }
+        }
+    }
 });
@@ -14,6 +14,105 @@ pub enum IdCommand {
     #[structopt(name = "show")]
     /// Show CrevID information
     Show,
+    #[structopt(name = "rekey")]
+    /// Re-encrypt current CrevID using KDF parameters from the config
+    Rekey,
+    #[structopt(name = "passwd")]
+    /// Change passphrase of the current CrevID
+    Passwd,
+    #[structopt(name = "logout")]
+    /// Remove passphrase of the current CrevID from the OS keychain
+    Logout,
+    #[structopt(name = "export-pub")]
+    /// Print a signed bundle of the current public CrevID, to send to others
+    ExportPub(IdExportPub),
+    #[structopt(name = "import-pub")]
+    /// Import someone's public CrevID bundle, after confirming its fingerprint
+    ImportPub(IdImportPub),
+    #[structopt(name = "encrypt-to")]
+    /// Encrypt own proofs from now on, readable only by these ids and you
+    EncryptTo(IdEncryptTo),
+    #[structopt(name = "compromise")]
+    /// Revoke the current CrevID, as its key leaked, and switch to the backup id if configured
+    Compromise(IdCompromise),
+    #[structopt(name = "link")]
+    /// Link the current CrevID and another own one, so trust in one can extend to the other
+    Link(IdLink),
+    #[structopt(name = "list")]
+    /// List own CrevIDs, with their names
+    List,
+    #[structopt(name = "switch")]
+    /// Make another own CrevID the current one
+    Switch(IdSwitch),
+    #[structopt(name = "name")]
+    /// Name an own CrevID, eg. `work` or `personal`, to refer to it by
+    Name(IdName),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdSwitch {
+    /// Own id, or its name
+    pub id: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdName {
+    pub name: String,
+    /// Own id (or its current name) to name; the current one by default
+    #[structopt(long = "id")]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdExportPub {
+    #[structopt(long = "name", default_value = "")]
+    /// Name to include in the bundle
+    pub name: String,
+    #[structopt(long = "comment", default_value = "")]
+    /// Comment to include in the bundle
+    pub comment: String,
+    #[structopt(long = "capability")]
+    /// Restrict the proofs of the id others count to these kinds, eg. for bots
+    /// (`trust`, `code-review`, `package-review`, `build-attestation` or
+    /// `project`; repeatable)
+    pub capabilities: Vec<crev_data::id_bundle::Capability>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdImportPub {
+    #[structopt(parse(from_os_str))]
+    /// File with the bundle; read from stdin if not given
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdEncryptTo {
+    /// Ids of the recipients; none to stop encrypting
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdCompromise {
+    /// Revoke only the proofs dated from this RFC 3339 date on, eg. when the key may have leaked
+    #[structopt(long = "since")]
+    pub since: Option<String>,
+    /// Why the id is revoked, for the Revocation Proof
+    #[structopt(long = "comment", default_value = "")]
+    pub comment: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct IdLink {
+    /// Other own id (or its name) to link to
+    pub id: String,
+    /// Most trust in the current id extending to the other one: `high`, `medium`, `low` or `none`
+    #[structopt(long = "level", default_value = "medium")]
+    pub level: crev_data::proof::trust::TrustLevel,
+    /// Most trust in the other id extending to the current one; same as `--level` by default
+    #[structopt(long = "back-level")]
+    pub back_level: Option<crev_data::proof::trust::TrustLevel>,
+    #[structopt(long = "comment", default_value = "")]
+    pub comment: String,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -30,10 +129,132 @@ pub struct Remove {
     pub paths: Vec<PathBuf>,
 }
 
+#[derive(Debug, StructOpt, Clone)]
+pub struct Sign {
+    #[structopt(parse(from_os_str))]
+    /// Files of drafts to sign
+    pub paths: Vec<PathBuf>,
+    /// Sign all the drafts queued for signing, eg. by importers
+    #[structopt(long = "all-drafts")]
+    pub all_drafts: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Attest {
+    #[structopt(parse(from_os_str))]
+    /// Artifact built from the current sources, eg. a binary
+    pub artifact: PathBuf,
+    #[structopt(long = "allow-dirty")]
+    pub allow_dirty: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Evidence {
+    /// Revision to collect the evidence for, eg. a release tag; has to be
+    /// checked out
+    #[structopt(long = "rev")]
+    pub rev: String,
+    #[structopt(long = "output", short = "o", parse(from_os_str))]
+    /// Archive to write; `evidence-<rev>.tar` by default
+    pub output: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Bundle {
+    /// Directory of the project to verify; the current one by default
+    #[structopt(long = "for", parse(from_os_str))]
+    pub for_: Option<PathBuf>,
+    /// Id of whoever the bundle is for
+    #[structopt(long = "recipient")]
+    pub recipient: String,
+    /// Encrypt the bundle, so only the recipient and you can read it
+    #[structopt(long = "encrypt")]
+    pub encrypt: bool,
+    #[structopt(long = "output", short = "o", parse(from_os_str))]
+    /// File to write; stdout by default
+    pub output: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Import {
+    #[structopt(parse(from_os_str))]
+    /// File to read proofs from; `-` for stdin
+    pub path: PathBuf,
+    /// `armor`, as in proof files, or `json`, a proof per line
+    #[structopt(long = "format", default_value = "armor")]
+    pub format: crev_data::proof::Format,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Export {
+    #[structopt(parse(from_os_str))]
+    /// File to write proofs to; `-` for stdout
+    pub path: PathBuf,
+    /// `armor`, as in proof files, or `json`, a proof per line
+    #[structopt(long = "format", default_value = "armor")]
+    pub format: crev_data::proof::Format,
+    /// Only proofs by this id
+    #[structopt(long = "author")]
+    pub author: Option<String>,
+    /// Only proofs of this type, eg. `code review` or `trust`
+    #[structopt(long = "type")]
+    pub proof_type: Option<String>,
+    /// Include encrypted proofs, decrypted; they're left out by default
+    #[structopt(long = "include-decrypted")]
+    pub include_decrypted: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct DbImport {
+    #[structopt(parse(from_os_str))]
+    /// Bundle file, as made by `crev bundle`
+    pub path: PathBuf,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct MergeStaging {
+    #[structopt(parse(from_os_str))]
+    /// Staging file to merge, eg. `.crev/staging` of another checkout
+    pub path: PathBuf,
+    #[structopt(long = "current")]
+    /// Resolve conflicts with the content matching the current files
+    /// instead of asking, unstaging the other ones
+    pub use_current: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Move {
+    #[structopt(parse(from_os_str))]
+    /// Path the file was at
+    pub from: PathBuf,
+    #[structopt(parse(from_os_str))]
+    /// Path the file is moving to
+    pub to: PathBuf,
+}
+
 #[derive(Debug, StructOpt, Clone)]
 pub struct TrustAdd {
     /// Public IDs to create Trust Proof for
     pub pub_ids: Vec<String>,
+    /// Show what is known about the id and ask a few questions to pick the trust level
+    #[structopt(long = "wizard")]
+    pub wizard: bool,
+    /// Trust level to pre-fill: `high`, `medium`, `low` or `none`
+    #[structopt(long = "level")]
+    pub level: Option<crev_data::proof::trust::TrustLevel>,
+    /// Comment to pre-fill
+    #[structopt(long = "comment", default_value = "")]
+    pub comment: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustDistrust {
+    /// Public IDs to create Distrust Proof for
+    pub pub_ids: Vec<String>,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -41,6 +262,131 @@ pub enum Trust {
     #[structopt(name = "add")]
     /// Create a new Trust Proof
     Add(TrustAdd),
+    #[structopt(name = "distrust")]
+    /// Create a new Distrust Proof, revoking any trust given before
+    Distrust(TrustDistrust),
+    #[structopt(name = "seed")]
+    /// Curated lists of ids to trust provisionally
+    Seed(TrustSeed),
+    #[structopt(name = "org-policy")]
+    /// The policy of your organization, fetched from a configured URL
+    OrgPolicy(TrustOrgPolicy),
+    #[structopt(name = "graph")]
+    /// Print the web of trust as a Graphviz DOT graph
+    Graph(TrustGraph),
+    #[structopt(name = "explain")]
+    /// Show the chains of Trust Proofs through which you trust an id
+    Explain(TrustExplain),
+    #[structopt(name = "simulate")]
+    /// Show how verification would change if you trusted some ids, or which ids to trust
+    Simulate(TrustSimulate),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustOrgPolicySet {
+    /// Git repository with the policy in `policy.crev`
+    pub url: String,
+    /// Id of the curator the policy must be signed by
+    pub curator: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustOrgPolicyCreate {
+    #[structopt(long = "name")]
+    /// Name of the organization
+    pub name: String,
+    #[structopt(long = "require")]
+    /// Reviews required of every package, as `<reviewers>:<trust>[:<path>]`
+    pub required_reviews: Vec<crev_data::org_policy::RequiredReviews>,
+    #[structopt(long = "seed")]
+    /// Public IDs to trust provisionally
+    pub seeds: Vec<String>,
+    #[structopt(long = "ban")]
+    /// Public IDs to distrust, as `<id>[:<reason>]`
+    pub banned: Vec<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum TrustOrgPolicy {
+    #[structopt(name = "set")]
+    /// Follow the policy at a URL, signed by a curator
+    Set(TrustOrgPolicySet),
+    #[structopt(name = "unset")]
+    /// Stop following any policy
+    Unset,
+    #[structopt(name = "fetch")]
+    /// Fetch the policy now
+    Fetch,
+    #[structopt(name = "show")]
+    /// Show the policy in effect
+    Show,
+    #[structopt(name = "create")]
+    /// Print a policy signed by the current id
+    Create(TrustOrgPolicyCreate),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustSimulate {
+    /// Public IDs to simulate trusting; without any, list the untrusted reviewers
+    /// that would verify the most files
+    pub pub_ids: Vec<String>,
+    /// Trust level to simulate: `high`, `medium`, `low`, `none` or `distrust`
+    #[structopt(long = "level", default_value = "medium")]
+    pub level: crev_data::proof::trust::TrustLevel,
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustExplain {
+    /// Public ID to explain the trust in
+    pub pub_id: String,
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustGraph {
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustSeedCreate {
+    #[structopt(long = "name")]
+    /// Title of the list
+    pub name: String,
+    /// Public IDs to put on the list
+    pub pub_ids: Vec<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustSeedImport {
+    #[structopt(parse(from_os_str))]
+    /// File with the seed list
+    pub path: PathBuf,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustSeedRemove {
+    /// Id of the curator of the list to remove
+    pub curator: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum TrustSeed {
+    #[structopt(name = "create")]
+    /// Print a seed list signed by the current id
+    Create(TrustSeedCreate),
+    #[structopt(name = "import")]
+    /// Import a seed list, after confirming the fingerprint of its curator
+    Import(TrustSeedImport),
+    #[structopt(name = "list")]
+    /// List imported seed lists
+    List,
+    #[structopt(name = "remove")]
+    /// Remove an imported seed list
+    Remove(TrustSeedRemove),
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -51,6 +397,57 @@ pub enum Db {
     #[structopt(name = "fetch")]
     /// Update trustdb by fetching updates from trusted sources
     Fetch,
+    #[structopt(name = "decrypt")]
+    /// Decrypt fetched encrypted proofs addressed to you
+    Decrypt,
+    #[structopt(name = "reindex")]
+    /// Rebuild the index of own, fetched, decrypted and imported proofs
+    Reindex,
+    #[structopt(name = "import")]
+    /// Import the proofs of a bundle someone made for you
+    Import(DbImport),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum Quarantine {
+    #[structopt(name = "list")]
+    /// List the quarantined data, oldest first
+    List,
+    #[structopt(name = "inspect")]
+    /// Display quarantined data, and why it was quarantined
+    Inspect(QuarantineInspect),
+    #[structopt(name = "purge")]
+    /// Remove quarantined data
+    Purge(QuarantinePurge),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct QuarantineInspect {
+    /// Id of the entry (or an unambiguous prefix of it)
+    pub id: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct QuarantinePurge {
+    /// Id of the entry (or an unambiguous prefix of it); all of them if not given
+    pub id: Option<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct AuditLog {
+    /// Check that no entry was changed or removed, instead of showing them
+    #[structopt(long = "check")]
+    pub check: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum Store {
+    #[structopt(name = "write-manifest")]
+    /// Write the manifest of the proof files now, signed by the current id
+    WriteManifest,
+    #[structopt(name = "verify-manifest")]
+    /// Check that no proof file was added, changed or removed since the manifest was written
+    VerifyManifest,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -70,6 +467,30 @@ pub struct PackageTrust {
 pub struct PackageVerify {
     #[structopt(long = "allow-dirty")]
     pub allow_dirty: bool,
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct PackageRequestReview {
+    #[structopt(long = "allow-dirty")]
+    pub allow_dirty: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct PackageAdvisory {
+    /// `low`, `medium`, `high` or `critical`
+    #[structopt(long = "severity", default_value = "medium")]
+    pub severity: crev_data::proof::advisory::Severity,
+    /// First revision with the problem, eg. a tag (all the earlier ones if not given)
+    #[structopt(long = "introduced")]
+    pub introduced: Option<String>,
+    /// First revision without the problem (not fixed yet if not given)
+    #[structopt(long = "fixed")]
+    pub fixed: Option<String>,
+    /// Files with the problem, to flag wherever they are found
+    #[structopt(parse(from_os_str))]
+    pub paths: Vec<PathBuf>,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -78,6 +499,32 @@ pub struct PackageDigest {
     pub allow_dirty: bool,
 }
 
+#[derive(Debug, StructOpt, Clone)]
+pub struct PackageIdentify {
+    /// Name of the project
+    #[structopt(long = "name")]
+    pub name: String,
+    /// `https://` URL of the project repository
+    #[structopt(long = "url")]
+    pub url: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct PackageMigrate {
+    /// `https://` URL the project continues under
+    #[structopt(long = "url")]
+    pub url: String,
+    /// New name of the project (default: keep the current one)
+    #[structopt(long = "name")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct PackageHandOver {
+    /// Id of the new trust root
+    pub id: String,
+}
+
 #[derive(Debug, StructOpt, Clone)]
 pub enum Package {
     #[structopt(name = "init")]
@@ -92,19 +539,114 @@ pub enum Package {
     #[structopt(name = "digest")]
     /// Display package digest
     Digest(PackageDigest),
+    #[structopt(name = "identify")]
+    /// Create a Project Proof for the package, as its trust root
+    Identify(PackageIdentify),
+    #[structopt(name = "identity")]
+    /// Display the identity of the package and check it for collisions
+    Identity,
+    #[structopt(name = "migrate")]
+    /// Create a Project Migration Proof, moving the project to a new URL
+    Migrate(PackageMigrate),
+    #[structopt(name = "hand-over")]
+    /// Create a Trust Root Handover Proof, making another Id the trust root
+    HandOver(PackageHandOver),
+    #[structopt(name = "request-review")]
+    /// Create a Review Request Proof, asking followers to review the current revision
+    RequestReview(PackageRequestReview),
+    #[structopt(name = "advisory")]
+    /// Create an Advisory Proof, warning that a range of revisions is dangerous
+    Advisory(PackageAdvisory),
+}
+
+/// Parameters describing trust graph traversal
+#[derive(Debug, StructOpt, Clone)]
+pub struct TrustParams {
+    /// Distance from you beyond which ids aren't trusted
+    #[structopt(long = "depth", default_value = "10")]
+    pub depth: u64,
+    /// Maximum number of trust proofs between you and a trusted id
+    #[structopt(long = "max-hops")]
+    pub max_hops: Option<u64>,
+    #[structopt(long = "high-cost", default_value = "0")]
+    pub high_cost: u64,
+    #[structopt(long = "medium-cost", default_value = "1")]
+    pub medium_cost: u64,
+    #[structopt(long = "low-cost", default_value = "5")]
+    pub low_cost: u64,
+    /// Extend trust in an id to its linked own ids, up to the level of the link
+    #[structopt(long = "follow-id-links")]
+    pub follow_id_links: bool,
+}
+
+impl From<TrustParams> for crev_lib::TrustDistanceParams {
+    fn from(params: TrustParams) -> Self {
+        crev_lib::TrustDistanceParams {
+            max_distance: params.depth,
+            max_depth: params.max_hops,
+            high_trust_distance: params.high_cost,
+            medium_trust_distance: params.medium_cost,
+            low_trust_distance: params.low_cost,
+            follow_id_links: params.follow_id_links,
+        }
+    }
 }
 
 #[derive(Debug, StructOpt, Clone)]
 pub struct Verify {
-    depth: u64,
-    #[structopt(long = "high-cost")]
-    high_cost: u64,
-    #[structopt(long = "medium-cost")]
-    medium_cost: u64,
-    #[structopt(long = "low-cost")]
-    low_cost: u64,
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
     #[structopt(long = "allow-dirty")]
     pub allow_dirty: bool,
+    /// Browse unverified files and their reviews, to stage files or trust reviewers
+    #[structopt(long = "interactive", short = "i")]
+    pub interactive: bool,
+    /// Keep verifying files again as they change, allowing uncommitted changes
+    #[structopt(long = "watch")]
+    pub watch: bool,
+    /// Print the verification of the package and of each file as JSON
+    #[structopt(long = "json")]
+    pub json: bool,
+    #[structopt(parse(from_os_str))]
+    /// Verify only the files at or under these paths, eg. `src/crypto`
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Annotate {
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+    #[structopt(parse(from_os_str))]
+    /// File to annotate
+    pub path: PathBuf,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Stale {
+    #[structopt(flatten)]
+    pub trust_params: TrustParams,
+    /// Flag reviews made more than this many days ago
+    #[structopt(long = "max-age-days", default_value = "730")]
+    pub max_age_days: i64,
+    /// Flag reviews of files the rest of the scope changed around in more
+    /// than this many commits since
+    #[structopt(long = "max-commits", default_value = "50")]
+    pub max_commits: usize,
+    /// Flag reviews by ids with no proof in this many days
+    #[structopt(long = "max-inactive-days", default_value = "365")]
+    pub max_inactive_days: i64,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Status {
+    /// Print one word instead: `verified`, `partially`, `unverified`, or
+    /// `none` outside of a crev project
+    #[structopt(long = "porcelain")]
+    pub porcelain: bool,
+    /// With `--porcelain`, answer from the result of the last verification,
+    /// or `unknown` if HEAD moved since; fast enough for shell prompts
+    #[structopt(long = "fast")]
+    pub fast: bool,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -113,6 +655,133 @@ pub struct Commit {
     pub all: bool,
     #[structopt(long = "allow-dirty")]
     pub allow_dirty: bool,
+    /// Pre-fill the comment with a summary of the git history since your last review
+    #[structopt(long = "summarize")]
+    pub summarize: bool,
+    /// Group the files by directory, to comment on each group separately
+    #[structopt(long = "grouped")]
+    pub grouped: bool,
+    /// Print the proof body that would be signed, without editing, signing or storing it
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+    /// Date the dry run body at this RFC 3339 date instead of now, eg. for comparing runs
+    #[structopt(long = "date")]
+    pub date: Option<String>,
+    /// Sign with this own id, or the one with this name, instead of the current one
+    #[structopt(long = "id", conflicts_with = "dry_run")]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Revoke {
+    /// Digests of own proofs to revoke (or unambiguous prefixes of them)
+    pub digests: Vec<String>,
+    /// Why they are revoked, to pre-fill
+    #[structopt(long = "comment", default_value = "")]
+    pub comment: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Amend {
+    /// Digest of the own proof to amend (or an unambiguous prefix of it)
+    pub digest: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Show {
+    /// Digest of the proof (or an unambiguous prefix of it)
+    pub digest: String,
+    /// Display aligned fields and signature status instead of the signed form
+    #[structopt(long = "pretty")]
+    pub pretty: bool,
+    /// Output the proof as a JSON object, with its signed body as a string
+    #[structopt(long = "json", conflicts_with = "pretty")]
+    pub json: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Report {
+    /// Digest of the proof (or an unambiguous prefix of it)
+    pub digest: String,
+    /// `markdown` or `html`
+    #[structopt(long = "format", default_value = "markdown")]
+    pub format: crev_lib::proof::ReportFormat,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Reproduce {
+    /// Digest of the proof (or an unambiguous prefix of it)
+    pub digest: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct EditorServer {
+    /// Listen on this port on localhost, instead of using stdin and stdout
+    #[structopt(long = "port")]
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TodoAdd {
+    /// What to review, eg. a crate, a URL, or a directory
+    pub target: String,
+    /// Higher goes first
+    #[structopt(long = "priority", default_value = "0")]
+    pub priority: i64,
+    /// Who asked for it, and why
+    #[structopt(long = "comment", default_value = "")]
+    pub comment: String,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TodoItem {
+    /// Number of the item, as listed
+    pub number: usize,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct TodoWatch {
+    #[structopt(parse(from_os_str))]
+    /// Directory of the project (default: the current scope)
+    pub dir: Option<PathBuf>,
+    /// Queue the current revision too, instead of taking it as reviewed
+    #[structopt(long = "unreviewed")]
+    pub unreviewed: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum Todo {
+    #[structopt(name = "list")]
+    /// List the queue, most important first
+    List,
+    #[structopt(name = "next")]
+    /// Show the most important item, and how to review it
+    Next,
+    #[structopt(name = "add")]
+    /// Queue a requested review
+    Add(TodoAdd),
+    #[structopt(name = "skip")]
+    /// Put an item off, after the others of the same priority
+    Skip(TodoItem),
+    #[structopt(name = "done")]
+    /// Remove an item as reviewed
+    Done(TodoItem),
+    #[structopt(name = "watch")]
+    /// Queue new revisions of a project, on `crev todo scan`
+    Watch(TodoWatch),
+    #[structopt(name = "unwatch")]
+    /// Stop watching a project
+    Unwatch(TodoWatch),
+    #[structopt(name = "scan")]
+    /// Queue new revisions of watched projects
+    Scan,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Dashboard {
+    /// Port to listen on, on localhost
+    #[structopt(long = "port", default_value = "8871")]
+    pub port: u16,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -135,16 +804,32 @@ pub enum Command {
 
     #[structopt(name = "status")]
     /// Display pending review list
-    Status,
+    Status(Status),
 
     #[structopt(name = "rm")]
     /// Remove path from reviewed list
     Remove(Remove),
 
+    #[structopt(name = "merge-staging")]
+    /// Merge a staging file from another checkout into the reviewed list
+    MergeStaging(MergeStaging),
+
+    #[structopt(name = "mv")]
+    /// Move a file, recording the move in a Path Move Proof
+    Move(Move),
+
     #[structopt(name = "verify")]
     /// Verify review coverage of the package
     Verify(Verify),
 
+    #[structopt(name = "annotate")]
+    /// Print a file with the reviews covering it, to pipe into a pager
+    Annotate(Annotate),
+
+    #[structopt(name = "stale")]
+    /// List trusted reviews that are likely stale: old, outpaced by changes around them, or by inactive ids
+    Stale(Stale),
+
     #[structopt(name = "trust")]
     /// Trust Store management
     Trust(Trust),
@@ -152,6 +837,78 @@ pub enum Command {
     #[structopt(name = "db")]
     /// Trust Store
     Db(Db),
+
+    #[structopt(name = "quarantine")]
+    /// Suspicious fetched proofs, kept as evidence
+    Quarantine(Quarantine),
+
+    #[structopt(name = "audit-log")]
+    /// Show the log of ids created, proofs signed, trust granted and remotes added
+    AuditLog(AuditLog),
+
+    #[structopt(name = "store")]
+    /// Spot proof files tampered with or corrupted, using a manifest of their digests
+    Store(Store),
+
+    #[structopt(name = "revoke")]
+    /// Create a new Revocation Proof, retracting own proofs
+    Revoke(Revoke),
+
+    #[structopt(name = "amend")]
+    /// Edit an own proof, signing a new one that supersedes it
+    Amend(Amend),
+
+    #[structopt(name = "reissue")]
+    /// Sign fresh copies of own proofs that have expired
+    Reissue,
+
+    #[structopt(name = "show")]
+    /// Display a proof
+    Show(Show),
+
+    #[structopt(name = "report")]
+    /// Render a proof as a document to share, eg. on a PR
+    Report(Report),
+
+    #[structopt(name = "reproduce")]
+    /// Re-derive the signed bytes of a proof and check its signature step by step
+    Reproduce(Reproduce),
+
+    #[structopt(name = "attest")]
+    /// Attest that building the package sources gave an artifact
+    Attest(Attest),
+
+    #[structopt(name = "bundle")]
+    /// Put together just the proofs someone needs to verify a project, to send to them
+    Bundle(Bundle),
+
+    #[structopt(name = "import")]
+    /// Import proofs from a file or stdin, eg. piped from another machine
+    Import(Import),
+
+    #[structopt(name = "export")]
+    /// Write known proofs to a file or stdout, eg. to pipe to another machine
+    Export(Export),
+
+    #[structopt(name = "evidence")]
+    /// Archive the proofs and a signed report of why a revision was verified
+    Evidence(Evidence),
+
+    #[structopt(name = "sign")]
+    /// Sign drafts of proofs prepared earlier, showing each first and unlocking the id once
+    Sign(Sign),
+
+    #[structopt(name = "todo")]
+    /// Queue of things to review
+    Todo(Todo),
+
+    #[structopt(name = "dashboard")]
+    /// Serve a local web UI with coverage, trust and recent proofs
+    Dashboard(Dashboard),
+
+    #[structopt(name = "editor-server")]
+    /// Answer JSON queries of editors about the review status of files
+    EditorServer(EditorServer),
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -1 +1,214 @@
+use common_failures::prelude::*;
+use crev_data::{id_bundle::SignedPubIdBundle, proof::limits};
+use crev_lib::{
+    hooks,
+    repo::{
+        self,
+        staging::{MergeChoice, MergeConflict},
+        Repo, ScopeStatus,
+    },
+    todo,
+    trust_wizard::{Acquaintance, TrustWizardAnswers},
+};
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
 
+/// Read a public id bundle from `path`, or stdin if not given
+pub fn read_pub_id_bundle(path: Option<&Path>) -> Result<SignedPubIdBundle> {
+    let mut text = String::new();
+    // one byte over the limit is enough for `parse` to reject it
+    let limit = limits::MAX_PROOF_FILE_SIZE + 1;
+    if let Some(path) = path {
+        fs::File::open(path)?
+            .take(limit)
+            .read_to_string(&mut text)?;
+    } else {
+        // the fingerprint confirmation is read from stdin too
+        if !atty::is(atty::Stream::Stdin) {
+            bail!("Can't confirm a bundle piped on stdin; pass it as a file instead");
+        }
+        eprintln!("Paste the bundle, then press Ctrl-D:");
+        io::stdin().take(limit).read_to_string(&mut text)?;
+    }
+    SignedPubIdBundle::parse(&text)
+}
+
+/// Ask the trust wizard questions on the terminal
+pub fn ask_trust_wizard_questions() -> Result<TrustWizardAnswers> {
+    let malicious =
+        crev_common::yes_or_no_was_y("Do you believe the owner is malicious or careless? (y/n) ")?;
+    if malicious {
+        return Ok(TrustWizardAnswers {
+            malicious,
+            fingerprint_confirmed: false,
+            acquaintance: Acquaintance::Stranger,
+            careful_reviewer: false,
+        });
+    }
+
+    let fingerprint_confirmed = crev_common::yes_or_no_was_y(
+        "Did you confirm the fingerprint with the owner over another channel (in person, call, ...)? (y/n) ",
+    )?;
+    let acquaintance = loop {
+        let reply = rprompt::prompt_reply_stderr(
+            "How do you know the owner? [s]tranger / by [r]eputation / [p]ersonally: ",
+        )?;
+        match reply.trim() {
+            "s" | "S" => break Acquaintance::Stranger,
+            "r" | "R" => break Acquaintance::Reputation,
+            "p" | "P" => break Acquaintance::Personal,
+            _ => {}
+        }
+    };
+    let careful_reviewer = crev_common::yes_or_no_was_y(
+        "Have you found the owner's reviews to be careful and well-founded? (y/n) ",
+    )?;
+
+    Ok(TrustWizardAnswers {
+        malicious,
+        fingerprint_confirmed,
+        acquaintance,
+        careful_reviewer,
+    })
+}
+
+/// Ask which of nested `.crev` scopes (nearest first) to use
+///
+/// If stdin is not a terminal, the nearest one is used.
+pub fn choose_package_scope(dirs: &[PathBuf]) -> Result<PathBuf> {
+    if !atty::is(atty::Stream::Stdin) {
+        eprintln!(
+            "Using the nearest of nested `.crev` scopes: {}",
+            dirs[0].display()
+        );
+        return Ok(dirs[0].clone());
+    }
+
+    eprintln!("Current directory is in nested `.crev` scopes:");
+    for (i, dir) in dirs.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, dir.display());
+    }
+    loop {
+        let reply = rprompt::prompt_reply_stderr("Which one to use? [1]: ")?;
+        let reply = reply.trim();
+        if reply.is_empty() {
+            return Ok(dirs[0].clone());
+        }
+        match reply.parse::<usize>() {
+            Ok(i) if 1 <= i && i <= dirs.len() => return Ok(dirs[i - 1].clone()),
+            _ => {}
+        }
+    }
+}
+
+/// Resolve a conflict of merging staging files: automatically with
+/// `use_current`, or if stdin is not a terminal, otherwise by asking
+///
+/// Automatic resolution keeps the side matching the current content of
+/// the file, or unstages the file if neither does.
+pub fn resolve_staging_conflict(
+    conflict: &MergeConflict<'_>,
+    use_current: bool,
+) -> Result<MergeChoice> {
+    let fallback = conflict.current.unwrap_or(MergeChoice::Drop);
+    if use_current || !atty::is(atty::Stream::Stdin) {
+        return Ok(fallback);
+    }
+
+    let describe = |choice| {
+        if conflict.current == Some(choice) {
+            " (matches the current file)"
+        } else {
+            ""
+        }
+    };
+    eprintln!(
+        "{} staged with different content on both sides:",
+        conflict.path.display()
+    );
+    eprintln!(
+        "  [o]urs:   {}{}",
+        crev_common::base64_encode(conflict.ours.digest()),
+        describe(MergeChoice::Ours)
+    );
+    eprintln!(
+        "  [t]heirs: {}{}",
+        crev_common::base64_encode(conflict.theirs.digest()),
+        describe(MergeChoice::Theirs)
+    );
+    loop {
+        let reply = rprompt::prompt_reply_stderr("Keep [o]urs, [t]heirs or [d]rop it? ")?;
+        match reply.trim() {
+            "o" | "O" => return Ok(MergeChoice::Ours),
+            "t" | "T" => return Ok(MergeChoice::Theirs),
+            "d" | "D" => return Ok(MergeChoice::Drop),
+            _ => {}
+        }
+    }
+}
+
+/// How to go about reviewing `item`, for `crev todo next`
+pub fn todo_hint(item: &todo::Item) -> String {
+    match item.kind {
+        todo::Kind::Dependency => format!("Review with `cargo crev review {}`", item.target),
+        todo::Kind::Revision => format!(
+            "Review in {} with `crev verify`, `crev add` and `crev package trust`",
+            item.target
+        ),
+        todo::Kind::Request => "Mark it reviewed with `crev todo done 1`".into(),
+    }
+}
+
+/// One word status of the nearest crev scope, for `crev status --porcelain`
+///
+/// Never fails nor prompts, as it's meant for shell prompts; anything
+/// going wrong is `unknown`.
+pub fn porcelain_status(fast: bool) -> String {
+    let dirs = match repo::find_package_root_dirs() {
+        Ok(dirs) => dirs,
+        Err(_) => return "none".into(),
+    };
+    let status = Repo::open(&dirs[0]).and_then(|mut repo| {
+        if fast {
+            repo.cached_status()
+        } else {
+            repo.files_verify(true, &Default::default())
+                .map(|files| Some(ScopeStatus::of_files(&files)))
+        }
+    });
+    match status {
+        Ok(Some(status)) => status.to_string(),
+        _ => "unknown".into(),
+    }
+}
+
+/// Parse the command line, unless it's for a `crev-<name>` plugin
+///
+/// Like `git`, subcommands not known to crev run the `crev-<name>`
+/// executable found on `PATH`, with the rest of the arguments; crev
+/// then exits with its exit code.
+pub fn parse_opts_or_run_plugin() -> Result<crate::opts::Opts> {
+    use structopt::{clap::ErrorKind, StructOpt};
+
+    let args: Vec<_> = env::args_os().collect();
+    let e = match crate::opts::Opts::from_iter_safe(&args) {
+        Ok(opts) => return Ok(opts),
+        Err(e) => e,
+    };
+    if e.kind == ErrorKind::UnrecognizedSubcommand || e.kind == ErrorKind::InvalidSubcommand {
+        let plugin = args
+            .get(1)
+            .and_then(|name| name.to_str())
+            .and_then(hooks::find_plugin);
+        if let Some(plugin) = plugin {
+            let status = std::process::Command::new(plugin)
+                .args(&args[2..])
+                .status()?;
+            std::process::exit(status.code().unwrap_or(1));
+        }
+    }
+    e.exit()
+}
@@ -10,6 +10,7 @@ use blake2;
 use chrono;
 
 use blake2::{digest::FixedOutput, Digest};
+use clear_on_drop::ClearOnDrop;
 use rpassword;
 use rprompt;
 use std::io::{Read, Write};
@@ -89,17 +90,20 @@ pub fn read_passphrase() -> io::Result<String> {
     rpassword::read_password()
 }
 
-pub fn read_new_passphrase() -> io::Result<String> {
+/// Ask for a new passphrase twice, until both match
+///
+/// It's wiped once the returned value is dropped.
+pub fn read_new_passphrase() -> io::Result<ClearOnDrop<String>> {
     if let Ok(pass) = env::var("CREV_PASSPHRASE") {
         eprint!("Using passphrase set in CREV_PASSPHRASE\n");
-        return Ok(pass);
+        return Ok(ClearOnDrop::new(pass));
     }
     loop {
         eprint!("Enter new passphrase: ");
-        let p1 = rpassword::read_password()?;
+        let p1 = ClearOnDrop::new(rpassword::read_password()?);
         eprint!("Enter new passphrase again: ");
-        let p2 = rpassword::read_password()?;
-        if p1 == p2 {
+        let p2 = ClearOnDrop::new(rpassword::read_password()?);
+        if *p1 == *p2 {
             return Ok(p1);
         }
         eprintln!("\nPassphrases don't match, try again.");
@@ -86,6 +86,34 @@ where
     serializer.serialize_str(&key.to_rfc3339())
 }
 
+pub fn from_rfc3339_fixed_opt<'d, D>(
+    deserializer: D,
+) -> Result<Option<chrono::DateTime<FixedOffset>>, D::Error>
+where
+    D: serde::Deserializer<'d>,
+{
+    use self::serde::de::Error;
+    Option::<String>::deserialize(deserializer)?
+        .map(|string| {
+            DateTime::<FixedOffset>::parse_from_rfc3339(&string)
+                .map_err(|err| Error::custom(err.to_string()))
+        })
+        .transpose()
+}
+
+pub fn as_rfc3339_fixed_opt<S>(
+    key: &Option<chrono::DateTime<FixedOffset>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match key {
+        Some(key) => serializer.serialize_some(&key.to_rfc3339()),
+        None => serializer.serialize_none(),
+    }
+}
+
 impl MyTryFromBytes for Vec<u8> {
     type Err = io::Error;
     fn try_from(slice: &[u8]) -> Result<Self, Self::Err> {
@@ -103,9 +131,11 @@ pub fn write_as_headerless_yaml<T: self::serde::Serialize>(
 ) -> fmt::Result {
     // TODO: Don't serialize to string, and instead serialize to writer
     let yaml_document = serde_yaml::to_string(t).map_err(|_| fmt::Error)?;
-    let mut lines = yaml_document.lines();
-    let dropped_header = lines.next();
-    assert_eq!(dropped_header, Some("---"));
+    let mut lines = yaml_document.lines().peekable();
+    // some versions of `serde_yaml` don't emit the header at all
+    if lines.peek() == Some(&"---") {
+        lines.next();
+    }
 
     for line in lines {
         f.write_str(&line)?;
@@ -0,0 +1,422 @@
+//! Encrypted proofs, readable only by a set of recipients
+//!
+//! For internal projects, whose proofs would leak the code structure
+//! (file names, package names, comments) when published. A complete
+//! signed proof is encrypted, and wrapped in an envelope signed by its
+//! author:
+//!
+//! ```text
+//! -----BEGIN CREV ENCRYPTED PROOF-----
+//! version: -1
+//! date: "2019-01-01T00:00:00+00:00"
+//! from:
+//!   id-type: crev
+//!   id: ZrNCfFGPQp3rnxodUYHwSB9ZtzNSo8liYUnGVhbh60o
+//!   url: "https://github.com/someone/crev-proofs"
+//! ephemeral-key: ...
+//! recipients:
+//!   - id-type: crev
+//!     id: ...
+//!     key: ...
+//! ciphertext: ...
+//! -----BEGIN CREV ENCRYPTED PROOF SIGNATURE-----
+//! ...
+//! -----END CREV ENCRYPTED PROOF-----
+//! ```
+//!
+//! Anyone can verify the envelope signature over the ciphertext; only
+//! the author and the recipients learn what the proof says. The proof is
+//! encrypted with a random key using AES-SIV, and that key is wrapped for
+//! every recipient with one derived from an X25519 exchange between an
+//! ephemeral key and the recipient's CrevID.
+//!
+//! Bundles of proofs of any authors are encrypted the same way, in a
+//! `CREV ENCRYPTED BUNDLE` block signed by whoever put them together; see
+//! `EncryptedProof::seal_bundle`.
+use crate::{
+    id::OwnId,
+    proof::{self, canonical, limits},
+    util::armor,
+    Id, PubId, Result,
+};
+use blake2::{Blake2b, Digest};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_base64, as_rfc3339_fixed, from_base64, from_rfc3339_fixed},
+};
+use curve25519_dalek::{
+    constants::X25519_BASEPOINT, edwards::CompressedEdwardsY, montgomery::MontgomeryPoint,
+    scalar::Scalar,
+};
+use ed25519_dalek::ExpandedSecretKey;
+use miscreant::aead::{Aes256Siv, Algorithm};
+use rand::{OsRng, RngCore};
+use serde_yaml;
+use std::fmt;
+
+const MARKERS: armor::Markers = armor::Markers {
+    begin: "-----BEGIN CREV ENCRYPTED PROOF-----",
+    begin_signature: "-----BEGIN CREV ENCRYPTED PROOF SIGNATURE-----",
+    end: "-----END CREV ENCRYPTED PROOF-----",
+};
+
+const BUNDLE_MARKERS: armor::Markers = armor::Markers {
+    begin: "-----BEGIN CREV ENCRYPTED BUNDLE-----",
+    begin_signature: "-----BEGIN CREV ENCRYPTED BUNDLE SIGNATURE-----",
+    end: "-----END CREV ENCRYPTED BUNDLE-----",
+};
+
+const CURRENT_ENCRYPTED_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+/// Extension of files with encrypted proofs
+///
+/// Different from the one of proof files, so tools not knowing about
+/// encrypted proofs skip them.
+pub const FILE_EXTENSION: &str = "crev-encrypted";
+
+/// Maximum size of the envelope body
+///
+/// A whole proof, encrypted and base64-encoded, doesn't fit the limits
+/// of a proof body - but neither can it nest.
+const MAX_BODY_SIZE: usize = 3 * limits::MAX_BODY_SIZE;
+
+/// Maximum size of the envelope body of a bundle
+///
+/// Room for hundreds of proofs of the largest size, encrypted and
+/// base64-encoded, while anything near the size of a whole file of proofs
+/// is refused before it's parsed.
+const MAX_BUNDLE_BODY_SIZE: usize = 256 * limits::MAX_BODY_SIZE;
+
+/// Maximum number of recipients of an encrypted proof
+pub const MAX_RECIPIENTS: usize = 256;
+
+const KEY_LENGTH: usize = 64;
+const KDF_CONTEXT: &[u8] = b"crev encrypted proof key";
+
+/// Recipient of an encrypted proof, with the proof key wrapped for them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recipient {
+    #[serde(flatten)]
+    pub id: Id,
+    #[serde(
+        rename = "key",
+        serialize_with = "as_base64",
+        deserialize_with = "from_base64"
+    )]
+    wrapped_key: Vec<u8>,
+}
+
+/// Body of the envelope of an encrypted proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedProof {
+    version: i64,
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: PubId,
+    #[serde(
+        rename = "ephemeral-key",
+        serialize_with = "as_base64",
+        deserialize_with = "from_base64"
+    )]
+    ephemeral_key: Vec<u8>,
+    pub recipients: Vec<Recipient>,
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    ciphertext: Vec<u8>,
+}
+
+/// An `EncryptedProof` along with its body as written, signed over the
+/// `canonical::signed_encoding` of it
+#[derive(Clone, Debug)]
+pub struct SignedEncryptedProof {
+    pub body: String,
+    pub signature: String,
+    pub encrypted: EncryptedProof,
+}
+
+/// Like `SignedEncryptedProof`, but of many proofs of any authors, put
+/// together by the author of the envelope; see `EncryptedProof::seal_bundle`
+#[derive(Clone, Debug)]
+pub struct SignedEncryptedBundle {
+    pub body: String,
+    pub signature: String,
+    pub encrypted: EncryptedProof,
+}
+
+fn montgomery_point_of(id: &Id) -> Result<MontgomeryPoint> {
+    match id {
+        Id::Crev { id: bytes } => {
+            if bytes.len() != 32 {
+                bail!("Invalid CrevID length: {}", bytes.len());
+            }
+            let mut compressed = [0u8; 32];
+            compressed.copy_from_slice(bytes);
+            Ok(CompressedEdwardsY(compressed)
+                .decompress()
+                .ok_or_else(|| format_err!("Invalid CrevID: {}", id))?
+                .to_montgomery())
+        }
+    }
+}
+
+/// The X25519 secret scalar corresponding to the signing key of `own_id`
+fn secret_scalar_of(own_id: &OwnId) -> Scalar {
+    let expanded = ExpandedSecretKey::from_secret_key::<Blake2b>(&own_id.keypair.secret);
+    let mut bits = [0u8; 32];
+    bits.copy_from_slice(&expanded.to_bytes()[..32]);
+    Scalar::from_bits(bits)
+}
+
+fn key_wrapping_key(
+    shared: &MontgomeryPoint,
+    ephemeral: &MontgomeryPoint,
+    recipient: &Id,
+) -> Vec<u8> {
+    let mut hasher = Blake2b::new();
+    hasher.input(KDF_CONTEXT);
+    hasher.input(shared.as_bytes());
+    hasher.input(ephemeral.as_bytes());
+    hasher.input(recipient.to_string().as_bytes());
+    hasher.result().to_vec()
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    OsRng::new()?.fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+impl EncryptedProof {
+    /// Encrypt `proof` of `author` for `recipients`
+    ///
+    /// The author is always a recipient, so they can read their proofs
+    /// back.
+    pub fn seal(
+        proof: &proof::Proof,
+        author: &OwnId,
+        recipients: &[Id],
+    ) -> Result<SignedEncryptedProof> {
+        if proof.content.author_id() != author.id.id {
+            bail!("Only the author of a proof can encrypt it");
+        }
+
+        let (encrypted, body, signature) =
+            Self::encrypt(proof.to_string().as_bytes(), author, recipients)?;
+        Ok(SignedEncryptedProof {
+            body,
+            signature,
+            encrypted,
+        })
+    }
+
+    /// Encrypt `proofs`, of any authors, for `recipients`, in a bundle
+    /// signed by `sender`, who is always a recipient too
+    pub fn seal_bundle(
+        proofs: &[proof::Proof],
+        sender: &OwnId,
+        recipients: &[Id],
+    ) -> Result<SignedEncryptedBundle> {
+        let plaintext: String = proofs.iter().map(ToString::to_string).collect();
+        let (encrypted, body, signature) = Self::encrypt(plaintext.as_bytes(), sender, recipients)?;
+        if body.len() > MAX_BUNDLE_BODY_SIZE {
+            bail!("Too many proofs to bundle: {}", proofs.len());
+        }
+        Ok(SignedEncryptedBundle {
+            body,
+            signature,
+            encrypted,
+        })
+    }
+
+    /// The envelope of `plaintext`, with its body and signature by `author`
+    fn encrypt(
+        plaintext: &[u8],
+        author: &OwnId,
+        recipients: &[Id],
+    ) -> Result<(EncryptedProof, String, String)> {
+        let mut ids: Vec<Id> = vec![author.id.id.clone()];
+        for id in recipients {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        if ids.len() > MAX_RECIPIENTS {
+            bail!("Too many recipients: {}", ids.len());
+        }
+
+        let proof_key = random_bytes(KEY_LENGTH)?;
+        let mut ephemeral_bits = [0u8; 32];
+        ephemeral_bits.copy_from_slice(&random_bytes(32)?);
+        ephemeral_bits[0] &= 248;
+        ephemeral_bits[31] &= 127;
+        ephemeral_bits[31] |= 64;
+        let ephemeral_secret = Scalar::from_bits(ephemeral_bits);
+        let ephemeral_key = ephemeral_secret * X25519_BASEPOINT;
+
+        let mut wrapped = vec![];
+        for id in ids {
+            let shared = ephemeral_secret * montgomery_point_of(&id)?;
+            let mut siv = Aes256Siv::new(&key_wrapping_key(&shared, &ephemeral_key, &id));
+            wrapped.push(Recipient {
+                wrapped_key: siv.seal(&[], &[], &proof_key),
+                id,
+            });
+        }
+
+        let ciphertext = Aes256Siv::new(&proof_key).seal(&[], &[], plaintext);
+        let encrypted = EncryptedProof {
+            version: CURRENT_ENCRYPTED_PROOF_SERIALIZATION_VERSION,
+            date: crev_common::now(),
+            from: author.id.clone(),
+            ephemeral_key: ephemeral_key.to_bytes().to_vec(),
+            recipients: wrapped,
+            ciphertext,
+        };
+
+        let body = encrypted.to_string();
+        let signature = crev_common::base64_encode(
+            &author.sign(canonical::signed_encoding(&body)?.as_bytes()),
+        );
+        Ok((encrypted, body, signature))
+    }
+
+    /// Decrypt the ciphertext with the key of `own_id`, one of the
+    /// recipients
+    fn decrypt(&self, own_id: &OwnId) -> Result<Vec<u8>> {
+        let recipient = self
+            .recipients
+            .iter()
+            .find(|recipient| recipient.id == own_id.id.id)
+            .ok_or_else(|| format_err!("Encrypted proof not addressed to {}", own_id.id.id))?;
+
+        if self.ephemeral_key.len() != 32 {
+            bail!("Invalid ephemeral key length: {}", self.ephemeral_key.len());
+        }
+        let mut ephemeral_bytes = [0u8; 32];
+        ephemeral_bytes.copy_from_slice(&self.ephemeral_key);
+        let ephemeral_key = MontgomeryPoint(ephemeral_bytes);
+
+        let shared = secret_scalar_of(own_id) * ephemeral_key;
+        let mut siv = Aes256Siv::new(&key_wrapping_key(&shared, &ephemeral_key, &recipient.id));
+        let proof_key = siv
+            .open(&[], &[], &recipient.wrapped_key)
+            .map_err(|_| format_err!("Can't unwrap the key of the encrypted proof"))?;
+        if proof_key.len() != KEY_LENGTH {
+            bail!("Invalid key length: {}", proof_key.len());
+        }
+        Aes256Siv::new(&proof_key)
+            .open(&[], &[], &self.ciphertext)
+            .map_err(|_| format_err!("Can't decrypt the encrypted proof"))
+    }
+
+    pub fn is_addressed_to(&self, id: &Id) -> bool {
+        self.recipients.iter().any(|recipient| recipient.id == *id)
+    }
+}
+
+impl fmt::Display for EncryptedProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl SignedEncryptedProof {
+    /// Parse an encrypted proof and verify its envelope signature
+    pub fn parse(s: &str) -> Result<Self> {
+        let (body, signature) = armor::parse(s, &MARKERS)?;
+        if body.len() > MAX_BODY_SIZE {
+            bail!("Encrypted proof body too long");
+        }
+
+        let encrypted: EncryptedProof = serde_yaml::from_str(&body)?;
+        if encrypted.recipients.len() > MAX_RECIPIENTS {
+            bail!("Too many recipients: {}", encrypted.recipients.len());
+        }
+        canonical::verify_body(&encrypted.from.id, &body, &signature)?;
+
+        Ok(Self {
+            body,
+            signature,
+            encrypted,
+        })
+    }
+
+    /// Digest identifying the encrypted proof; see `canonical::digest`
+    pub fn digest(&self) -> Result<Vec<u8>> {
+        canonical::digest(&self.body)
+    }
+
+    /// Decrypt the proof with the key of `own_id`, one of the recipients
+    ///
+    /// The proof has to be signed by the author of the envelope, so an
+    /// envelope can't pass off somebody else's proof as its author's.
+    pub fn open(&self, own_id: &OwnId) -> Result<proof::Proof> {
+        let encrypted = &self.encrypted;
+        let plaintext = encrypted.decrypt(own_id)?;
+
+        let mut proofs = proof::Proof::parse(plaintext.as_slice())?;
+        if proofs.len() != 1 {
+            bail!("Encrypted proof has to contain exactly one proof");
+        }
+        let proof = proofs.pop().expect("not empty");
+        proof.verify()?;
+        if proof.content.author_id() != encrypted.from.id {
+            bail!("Encrypted proof contains a proof of a different author");
+        }
+        Ok(proof)
+    }
+}
+
+impl fmt::Display for SignedEncryptedProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        armor::write(f, &MARKERS, &self.body, &self.signature)
+    }
+}
+
+impl SignedEncryptedBundle {
+    /// Is there an encrypted bundle in `s`
+    pub fn is_in(s: &str) -> bool {
+        s.lines().any(|line| line.trim() == BUNDLE_MARKERS.begin)
+    }
+
+    /// Parse an encrypted bundle and verify its envelope signature
+    pub fn parse(s: &str) -> Result<Self> {
+        let (body, signature) = armor::parse(s, &BUNDLE_MARKERS)?;
+        if body.len() > MAX_BUNDLE_BODY_SIZE {
+            bail!("Encrypted bundle body too long");
+        }
+
+        let encrypted: EncryptedProof = serde_yaml::from_str(&body)?;
+        if encrypted.recipients.len() > MAX_RECIPIENTS {
+            bail!("Too many recipients: {}", encrypted.recipients.len());
+        }
+        canonical::verify_body(&encrypted.from.id, &body, &signature)?;
+
+        Ok(Self {
+            body,
+            signature,
+            encrypted,
+        })
+    }
+
+    /// Decrypt the proofs with the key of `own_id`, one of the recipients
+    ///
+    /// Each of them has to have a valid signature.
+    pub fn open(&self, own_id: &OwnId) -> Result<Vec<proof::Proof>> {
+        let plaintext = self.encrypted.decrypt(own_id)?;
+        let proofs = proof::Proof::parse(plaintext.as_slice())?;
+        for proof in &proofs {
+            proof.verify()?;
+        }
+        Ok(proofs)
+    }
+}
+
+impl fmt::Display for SignedEncryptedBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        armor::write(f, &BUNDLE_MARKERS, &self.body, &self.signature)
+    }
+}
@@ -39,10 +39,29 @@ pub enum Id {
 impl Id {
     pub fn crevid_from_str(s: &str) -> Result<Self> {
         let bytes = crev_common::base64_decode(s)?;
+        if bytes.len() != ed25519_dalek::PUBLIC_KEY_LENGTH {
+            bail!("Invalid CrevID length: {}", bytes.len());
+        }
 
         Ok(Id::Crev { id: bytes })
     }
 
+    /// Short fingerprint of the public key, for comparing over another channel
+    ///
+    /// First 16 bytes of the key's Blake2b-256 hash, in hex groups of four.
+    pub fn fingerprint(&self) -> String {
+        match self {
+            Id::Crev { id } => {
+                let hash = hex::encode(&crev_common::blake2b256sum(id)[..16]);
+                hash.as_bytes()
+                    .chunks(4)
+                    .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+        }
+    }
+
     pub fn verify_signature(&self, content: &[u8], sig_str: &str) -> Result<()> {
         match self {
             Id::Crev { id } => {
@@ -93,23 +112,14 @@ impl PubId {
             url,
         })
     }
-}
-
-/// A `PubId` with the corresponding secret key
-#[derive(Debug)]
-pub struct OwnId {
-    pub id: PubId,
-    pub keypair: ed25519_dalek::Keypair,
-}
 
-impl OwnId {
     pub fn create_trust_proof(
         &self,
         ids: Vec<PubId>,
         trust_level: proof::trust::TrustLevel,
     ) -> Result<proof::Trust> {
         Ok(proof::TrustBuilder::default()
-            .from(self.id.clone())
+            .from(self.clone())
             .trust(trust_level)
             .ids(ids)
             .build()
@@ -123,7 +133,7 @@ impl OwnId {
         comment: String,
     ) -> Result<proof::review::Package> {
         Ok(proof::review::PackageBuilder::default()
-            .from(self.id.clone())
+            .from(self.clone())
             .package(package)
             .review(review)
             .comment(comment)
@@ -132,6 +142,32 @@ impl OwnId {
     }
 }
 
+/// A `PubId` with the corresponding secret key
+#[derive(Debug)]
+pub struct OwnId {
+    pub id: PubId,
+    pub keypair: ed25519_dalek::Keypair,
+}
+
+impl OwnId {
+    pub fn create_trust_proof(
+        &self,
+        ids: Vec<PubId>,
+        trust_level: proof::trust::TrustLevel,
+    ) -> Result<proof::Trust> {
+        self.id.create_trust_proof(ids, trust_level)
+    }
+
+    pub fn create_package_review_proof(
+        &self,
+        package: proof::PackageInfo,
+        review: proof::review::Review,
+        comment: String,
+    ) -> Result<proof::review::Package> {
+        self.id.create_package_review_proof(package, review, comment)
+    }
+}
+
 impl AsRef<Id> for OwnId {
     fn as_ref(&self) -> &Id {
         &self.id.id
@@ -146,8 +182,8 @@ impl AsRef<PubId> for OwnId {
 
 impl OwnId {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(url: Url, sec_key: Vec<u8>) -> Result<Self> {
-        let sec_key = SecretKey::from_bytes(&sec_key)?;
+    pub fn new(url: Url, sec_key: &[u8]) -> Result<Self> {
+        let sec_key = SecretKey::from_bytes(sec_key)?;
         let calculated_pub_key: PublicKey = PublicKey::from_secret::<blake2::Blake2b>(&sec_key);
 
         Ok(Self {
@@ -0,0 +1,212 @@
+//! Self-signed bundle of a public Id, for exchanging it out of band
+//!
+//! A bundle is small enough to be pasted into a chat or an email:
+//!
+//! ```text
+//! -----BEGIN CREV ID-----
+//! version: -1
+//! date: "2019-01-01T00:00:00+00:00"
+//! id-type: crev
+//! id: ZrNCfFGPQp3rnxodUYHwSB9ZtzNSo8liYUnGVhbh60o
+//! url: "https://github.com/someone/crev-proofs"
+//! name: Someone
+//! -----BEGIN CREV ID SIGNATURE-----
+//! ...
+//! -----END CREV ID-----
+//! ```
+//!
+//! The bundle is signed by the key it contains, which proves the owner of
+//! the key published the URL and metadata. It doesn't prove who the owner
+//! is - that's what comparing `Id::fingerprint` with them is for.
+//!
+//! A bundle can also restrict what the id may attest to, eg. for bots that
+//! only produce code reviews; see `Capability`.
+use crate::{
+    id::OwnId,
+    proof::{self, canonical, limits},
+    util::armor,
+    PubId, Result,
+};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::{fmt, str::FromStr};
+
+const MARKERS: armor::Markers = armor::Markers {
+    begin: "-----BEGIN CREV ID-----",
+    begin_signature: "-----BEGIN CREV ID SIGNATURE-----",
+    end: "-----END CREV ID-----",
+};
+
+const CURRENT_ID_BUNDLE_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_ID_BUNDLE_SERIALIZATION_VERSION
+}
+
+/// Kind of proofs an id may author
+///
+/// Ids with a restricted set of capabilities (see `PubIdBundle`) have
+/// their other proofs ignored.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Trust and Id Link Proofs, extending the WoT
+    Trust,
+    CodeReview,
+    /// Package Review and Advisory Proofs
+    PackageReview,
+    /// Build Attestation Proofs, eg. of reproducible-build bots
+    BuildAttestation,
+    /// Project, Project Migration, Trust Root Handover, Path Move and
+    /// Review Request Proofs
+    Project,
+}
+
+impl Capability {
+    pub fn all() -> &'static [Capability] {
+        &[
+            Capability::Trust,
+            Capability::CodeReview,
+            Capability::PackageReview,
+            Capability::BuildAttestation,
+            Capability::Project,
+        ]
+    }
+
+    /// Capability needed to author a proof with `content`
+    ///
+    /// Revocations need none: they only retract proofs by their own
+    /// author, whatever those needed.
+    pub fn required_for(content: &proof::Content) -> Option<Capability> {
+        use crate::proof::Content::*;
+        match content {
+            Trust(_) | IdLink(_) => Some(Capability::Trust),
+            Code(_) => Some(Capability::CodeReview),
+            Package(_) | Advisory(_) => Some(Capability::PackageReview),
+            BuildAttestation(_) => Some(Capability::BuildAttestation),
+            Project(_) | ProjectMigration(_) | TrustRootHandover(_) | PathMove(_)
+            | ReviewRequest(_) => Some(Capability::Project),
+            Revocation(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::Trust => "trust",
+            Capability::CodeReview => "code-review",
+            Capability::PackageReview => "package-review",
+            Capability::BuildAttestation => "build-attestation",
+            Capability::Project => "project",
+        })
+    }
+}
+
+impl FromStr for Capability {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "trust" => Capability::Trust,
+            "code-review" => Capability::CodeReview,
+            "package-review" => Capability::PackageReview,
+            "build-attestation" => Capability::BuildAttestation,
+            "project" => Capability::Project,
+            _ => bail!(
+                "Unknown capability: {}; use `trust`, `code-review`, `package-review`, \
+                 `build-attestation` or `project`",
+                s
+            ),
+        })
+    }
+}
+
+/// Public Id with its URL and some optional metadata
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct PubIdBundle {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    #[serde(flatten)]
+    pub id: PubId,
+    /// Name the owner goes by
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    /// If set, the only kinds of proofs of the id that count
+    #[serde(skip_serializing_if = "Option::is_none", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub capabilities: Option<Vec<Capability>>,
+}
+
+impl PubIdBundle {
+    /// Can the id author proofs needing `capability`
+    pub fn allows(&self, capability: Capability) -> bool {
+        match self.capabilities {
+            Some(ref capabilities) => capabilities.contains(&capability),
+            None => true,
+        }
+    }
+}
+
+impl PubIdBundle {
+    pub fn sign_by(self, id: &OwnId) -> Result<SignedPubIdBundle> {
+        if id.id.id != self.id.id {
+            bail!("Id bundle can only be signed by the id it contains");
+        }
+        let body = canonical::to_canonical_body(&self)?;
+        let signature = id.sign(canonical::signed_encoding(&body)?.as_bytes());
+        Ok(SignedPubIdBundle {
+            body,
+            signature: crev_common::base64_encode(&signature),
+            bundle: self,
+        })
+    }
+}
+
+/// A `PubIdBundle` along with its body as written, signed over the
+/// `canonical::signed_encoding` of it
+#[derive(Clone, Debug)]
+pub struct SignedPubIdBundle {
+    pub body: String,
+    pub signature: String,
+    pub bundle: PubIdBundle,
+}
+
+impl SignedPubIdBundle {
+    /// Parse a bundle and verify its signature
+    ///
+    /// Text around the bundle (eg. an email signature) is ignored.
+    pub fn parse(s: &str) -> Result<Self> {
+        let (body, signature) = armor::parse(s, &MARKERS)?;
+        limits::check_body(&body)?;
+
+        let bundle: PubIdBundle = serde_yaml::from_str(&body)?;
+        canonical::verify_body(&bundle.id.id, &body, &signature)?;
+
+        Ok(Self {
+            body,
+            signature,
+            bundle,
+        })
+    }
+}
+
+impl fmt::Display for SignedPubIdBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        armor::write(f, &MARKERS, &self.body, &self.signature)
+    }
+}
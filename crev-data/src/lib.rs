@@ -14,12 +14,17 @@ extern crate derive_builder;
 use common_failures::prelude::*;
 
 pub mod digest;
+pub mod encrypted;
 pub mod id;
+pub mod id_bundle;
 pub mod level;
+pub mod org_policy;
 mod prelude;
 pub mod proof;
+pub mod seed_list;
 pub mod url;
 pub mod util;
+pub mod verification_report;
 
 pub use crate::{
     digest::Digest,
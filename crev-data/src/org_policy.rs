@@ -0,0 +1,184 @@
+//! Organization policies, for managing crev centrally
+//!
+//! An organization (through its curator id) publishes a signed policy:
+//! reviews required of every package, ids to trust provisionally, like a
+//! seed list, and ids banned from its web of trust. Members configure
+//! where to fetch it from, and who must have signed it.
+use crate::{
+    id::OwnId,
+    proof::{canonical, limits, trust::TrustLevel},
+    seed_list::SeedEntry,
+    util::armor,
+    Id, PubId, Result,
+};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const MARKERS: armor::Markers = armor::Markers {
+    begin: "-----BEGIN CREV ORG POLICY-----",
+    begin_signature: "-----BEGIN CREV ORG POLICY SIGNATURE-----",
+    end: "-----END CREV ORG POLICY-----",
+};
+
+/// Maximum size of the body of a policy
+pub const MAX_ORG_POLICY_SIZE: usize = 1024 * 1024;
+
+const CURRENT_ORG_POLICY_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_ORG_POLICY_SERIALIZATION_VERSION
+}
+
+fn default_path() -> String {
+    "**".into()
+}
+
+fn default_trust() -> TrustLevel {
+    TrustLevel::Low
+}
+
+/// Reviews required of the files matching `path`, in every package
+///
+/// Written `<reviewers>:<trust>[:<path>]` on the command line, eg.
+/// `2:high:src/crypto/**`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RequiredReviews {
+    /// Pattern relative to the root of the package
+    #[serde(default = "default_path")]
+    pub path: String,
+    /// Number of distinct reviewers needed
+    pub reviewers: usize,
+    /// Minimum effective trust of each of them
+    #[serde(default = "default_trust")]
+    pub trust: TrustLevel,
+    /// Tags the reviews must have to count; none on the command line
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+}
+
+impl std::str::FromStr for RequiredReviews {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        let reviewers = parts.next().unwrap_or_default();
+        let reviewers = reviewers
+            .parse()
+            .map_err(|_| format_err!("Not a number of reviewers: {}", reviewers))?;
+        let trust = match parts.next() {
+            Some(trust) => trust.parse()?,
+            None => default_trust(),
+        };
+        let path = parts
+            .next()
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(default_path);
+        Ok(Self {
+            path,
+            reviewers,
+            trust,
+            tags: vec![],
+        })
+    }
+}
+
+/// An id banned by a policy
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BannedId {
+    pub id: Id,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct OrgPolicy {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// The curator
+    pub from: PubId,
+    /// Name of the organization
+    pub name: String,
+    #[serde(
+        rename = "required-reviews",
+        skip_serializing_if = "Vec::is_empty",
+        default = "Default::default"
+    )]
+    #[builder(default = "Default::default()")]
+    pub required_reviews: Vec<RequiredReviews>,
+    /// Ids trusted provisionally, like the ones of a seed list
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub seeds: Vec<SeedEntry>,
+    /// Ids distrusted, whoever trusts them
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub banned: Vec<BannedId>,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+}
+
+impl OrgPolicy {
+    pub fn is_banned(&self, id: &Id) -> bool {
+        self.banned.iter().any(|banned| banned.id == *id)
+    }
+
+    pub fn sign_by(self, id: &OwnId) -> Result<SignedOrgPolicy> {
+        if id.id.id != self.from.id {
+            bail!("Policy can only be signed by its curator");
+        }
+        let body = canonical::to_canonical_body(&self)?;
+        let signature = id.sign(canonical::signed_encoding(&body)?.as_bytes());
+        Ok(SignedOrgPolicy {
+            body,
+            signature: crev_common::base64_encode(&signature),
+            policy: self,
+        })
+    }
+}
+
+/// An `OrgPolicy` along with its body as written, signed over the
+/// `canonical::signed_encoding` of it
+#[derive(Clone, Debug)]
+pub struct SignedOrgPolicy {
+    pub body: String,
+    pub signature: String,
+    pub policy: OrgPolicy,
+}
+
+impl SignedOrgPolicy {
+    /// Parse a policy and verify the signature of its curator
+    pub fn parse(s: &str) -> Result<Self> {
+        let (body, signature) = armor::parse(s, &MARKERS)?;
+        if body.len() > MAX_ORG_POLICY_SIZE {
+            bail!("Policy too long");
+        }
+        limits::check_structure(&body)?;
+
+        let policy: OrgPolicy = serde_yaml::from_str(&body)?;
+        canonical::verify_body(&policy.from.id, &body, &signature)?;
+
+        Ok(Self {
+            body,
+            signature,
+            policy,
+        })
+    }
+}
+
+impl fmt::Display for SignedOrgPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        armor::write(f, &MARKERS, &self.body, &self.signature)
+    }
+}
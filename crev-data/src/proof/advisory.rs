@@ -0,0 +1,206 @@
+//! Advisory Proof - a warning that a project is dangerous to use
+//!
+//! It flags a range of revisions of a project, and optionally some files
+//! of it, as having a vulnerability or malicious code. Unlike a negative
+//! review, it isn't tied to one version, so it keeps covering the project
+//! until the revision fixing the problem.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_base64, as_rfc3339_fixed, from_base64, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::{fmt, path::PathBuf};
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV ADVISORY-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV ADVISORY SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV ADVISORY-----";
+
+const CURRENT_ADVISORY_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_ADVISORY_PROOF_SERIALIZATION_VERSION
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Severity::Medium
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::Severity::*;
+        f.write_str(match self {
+            Low => "low",
+            Medium => "medium",
+            High => "high",
+            Critical => "critical",
+        })
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "low" => Severity::Low,
+            "medium" => Severity::Medium,
+            "high" => Severity::High,
+            "critical" => Severity::Critical,
+            _ => bail!("Unknown severity: {}", s),
+        })
+    }
+}
+
+/// Revisions of the project affected by an advisory
+///
+/// Those descending from `introduced`, but not from `fixed`. Either can be
+/// empty: all the revisions up to `fixed`, or since `introduced` with no
+/// fix yet.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct AffectedRange {
+    /// First revision with the problem
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub introduced: String,
+    /// First revision without it
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub fixed: String,
+}
+
+impl AffectedRange {
+    pub fn is_fixed(&self) -> bool {
+        !self.fixed.is_empty()
+    }
+}
+
+/// A file flagged by an advisory, as it is when affected
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdvisoryFile {
+    pub path: PathBuf,
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub digest: Vec<u8>,
+}
+
+/// Body of an Advisory Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct Advisory {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: crate::PubId,
+    /// Id of the project; see `Project::project_id`
+    pub project: String,
+    #[builder(default = "Default::default()")]
+    pub severity: Severity,
+    #[builder(default = "Default::default()")]
+    pub affected: AffectedRange,
+    /// The files with the problem; the files with these digests are
+    /// flagged wherever they are found, even outside the project
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub files: Vec<AdvisoryFile>,
+    /// What the problem is, and how to deal with it
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl Advisory {
+    pub fn apply_draft(&self, draft: AdvisoryDraft) -> Advisory {
+        let mut copy = self.clone();
+        copy.severity = draft.severity;
+        copy.affected = draft.affected;
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `Advisory` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdvisoryDraft {
+    severity: Severity,
+    #[serde(default = "Default::default")]
+    affected: AffectedRange,
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<Advisory> for AdvisoryDraft {
+    fn from(advisory: Advisory) -> Self {
+        AdvisoryDraft {
+            severity: advisory.severity,
+            affected: advisory.affected,
+            comment: advisory.comment,
+        }
+    }
+}
+
+impl fmt::Display for Advisory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for AdvisoryDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl Advisory {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for Advisory {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!("Advisory ({}) for project {}", self.severity, self.project)
+    }
+}
+
+impl Advisory {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl AdvisoryDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
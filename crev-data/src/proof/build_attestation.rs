@@ -0,0 +1,151 @@
+//! Build Attestation Proof - an artifact was reproduced from the sources
+//!
+//! Attests that the author built an artifact (eg. a binary or a published
+//! archive) from the package at a source revision, and got exactly the
+//! digest recorded, so the artifact can't hide anything the sources don't.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_base64, as_rfc3339_fixed, from_base64, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV BUILD ATTESTATION-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV BUILD ATTESTATION SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV BUILD ATTESTATION-----";
+
+const CURRENT_BUILD_ATTESTATION_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_BUILD_ATTESTATION_PROOF_SERIALIZATION_VERSION
+}
+
+/// An artifact built from the sources of a package
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Artifact {
+    /// What was built, eg. a file name
+    pub name: String,
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub digest: Vec<u8>,
+    #[serde(
+        rename = "digest-type",
+        skip_serializing_if = "proof::equals_default_digest_type",
+        default = "proof::default_digest_type"
+    )]
+    pub digest_type: String,
+}
+
+/// Body of a Build Attestation Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct BuildAttestation {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: crate::PubId,
+    /// The package, with the source revision and digest built from
+    pub package: proof::PackageInfo,
+    pub artifact: Artifact,
+    /// How it was built, eg. the command and toolchain used
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub environment: String,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl BuildAttestation {
+    pub fn apply_draft(&self, draft: BuildAttestationDraft) -> BuildAttestation {
+        let mut copy = self.clone();
+        copy.environment = draft.environment;
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `BuildAttestation` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BuildAttestationDraft {
+    #[serde(default = "Default::default")]
+    environment: String,
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<BuildAttestation> for BuildAttestationDraft {
+    fn from(attestation: BuildAttestation) -> Self {
+        BuildAttestationDraft {
+            environment: attestation.environment,
+            comment: attestation.comment,
+        }
+    }
+}
+
+impl fmt::Display for BuildAttestation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for BuildAttestationDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl BuildAttestation {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for BuildAttestation {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!(
+            "Build of {} from {} {}",
+            self.artifact.name, self.package.name, self.package.version
+        )
+    }
+}
+
+impl BuildAttestation {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if content.artifact.name.is_empty() || content.artifact.digest.is_empty() {
+            bail!("Build Attestation Proof has to name the artifact and its digest");
+        }
+        if content.package.revision.is_empty() || content.package.digest.is_empty() {
+            bail!("Build Attestation Proof has to name the source revision and its digest");
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl BuildAttestationDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
@@ -0,0 +1,142 @@
+//! Canonical form of proof bodies
+//!
+//! A proof is stored exactly as it was read, between the begin and
+//! signature markers, but its signature and digest are over the
+//! `signed_encoding` of the body rather than those bytes.
+//!
+//! When signing, the body is brought into the canonical form:
+//!
+//! * UTF-8 YAML mapping, in block style, without the `---` document header,
+//! * fields in the order they are declared in the corresponding Rust type,
+//!   with fields equal to their defaults omitted,
+//! * `\n` line endings only (a `\r\n` read from a file is treated as `\n`),
+//! * no empty lines at the beginning or end, and exactly one `\n` at the end.
+//!
+//! Whitespace at the end of lines is kept, as it can be part of a scalar,
+//! eg. a block scalar comment.
+//!
+//! The signature itself is made over the `signed_encoding` of the body,
+//! with the keys sorted and a fixed formatting, so a proof written back
+//! by another serializer, with its keys reordered or its whitespace
+//! changed, still verifies. Proofs signed over the exact body bytes, as
+//! older versions did, verify too; see `verify_body`.
+//!
+//! Digests are calculated over the `signed_encoding` as well, so a
+//! reformatted proof keeps the digest it is revoked or superseded by.
+//!
+//! Other signed documents, like seed lists, id bundles, org policies,
+//! verification reports and encrypted envelopes, are signed the same way.
+use crate::{Id, Result};
+use serde_yaml::Value;
+
+/// Bring a serialized body into the canonical form
+pub fn canonicalize(body: &str) -> String {
+    let mut lines: Vec<&str> = body
+        .lines()
+        .skip_while(|line| line.is_empty())
+        .collect();
+
+    if lines.first() == Some(&"---") {
+        lines.remove(0);
+    }
+
+    while lines.last().map(|line| line.is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+
+    let mut out = String::with_capacity(body.len());
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Is `body` already in the canonical form
+pub fn is_canonical(body: &str) -> bool {
+    canonicalize(body) == body
+}
+
+/// Serialize `t` into the canonical form of a proof body
+pub fn to_canonical_body<T: serde::Serialize>(t: &T) -> Result<String> {
+    Ok(canonicalize(&serde_yaml::to_string(t)?))
+}
+
+/// The bytes signatures are made over: `body` parsed as YAML, and
+/// written back with the keys of every mapping sorted, in a compact
+/// JSON-like format
+pub fn signed_encoding(body: &str) -> Result<String> {
+    let value: Value = serde_yaml::from_str(body)?;
+    let mut out = String::with_capacity(body.len());
+    write_value(&value, &mut out);
+    Ok(out)
+}
+
+/// Digest of a proof with `body`: Blake2b256 of its `signed_encoding`
+pub fn digest(body: &str) -> Result<Vec<u8>> {
+    Ok(crev_common::blake2b256sum(
+        signed_encoding(body)?.as_bytes(),
+    ))
+}
+
+/// Verify `signature` by `id` over `body`: over its `signed_encoding`,
+/// or the exact bytes of it
+pub fn verify_body(id: &Id, body: &str, signature: &str) -> Result<()> {
+    if id.verify_signature(body.as_bytes(), signature).is_ok() {
+        return Ok(());
+    }
+    id.verify_signature(signed_encoding(body)?.as_bytes(), signature)
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(s, out),
+        Value::Sequence(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Mapping(mapping) => {
+            let mut entries: Vec<(String, &Value)> = mapping
+                .iter()
+                .map(|(key, value)| {
+                    let mut key_out = String::new();
+                    write_value(key, &mut key_out);
+                    (key_out, value)
+                })
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&key);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
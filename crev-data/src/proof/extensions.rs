@@ -0,0 +1,38 @@
+//! Forward-compatible extension of proof formats
+//!
+//! Fields that this version of `crev` doesn't know about are not dropped,
+//! but kept in `Extensions` and written back when the content is
+//! serialized again. By default they are ignored.
+//!
+//! A proof can list the names of fields it considers `critical`. A
+//! critical field that is not understood makes the proof rejected, so a
+//! newer format can prevent older clients from silently validating a proof
+//! whose meaning they can't fully grasp.
+use crate::Result;
+use std::collections::BTreeMap;
+
+/// Unknown fields of a proof content, and the names of critical ones
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Extensions {
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
+    pub critical: Vec<String>,
+    /// Fields not known to this version
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl Extensions {
+    pub fn is_empty(&self) -> bool {
+        self.critical.is_empty() && self.unknown.is_empty()
+    }
+
+    /// Fail if any of the critical fields is not understood
+    pub fn ensure_understood(&self) -> Result<()> {
+        for name in &self.critical {
+            if self.unknown.contains_key(name) {
+                bail!("Proof uses unsupported critical field `{}`", name);
+            }
+        }
+        Ok(())
+    }
+}
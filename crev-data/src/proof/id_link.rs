@@ -0,0 +1,132 @@
+//! Id Link Proof - a user vouching that another id is theirs as well
+//!
+//! Eg. an old laptop key and the new one, or a personal id and a work
+//! one. A link counts only once both ids link each other, so nobody can
+//! claim someone else's id as their own. Whoever trusts one of the ids can
+//! then extend that trust to the other one, up to the `level` of the
+//! link signed by the id they trust.
+use crate::{id, proof, proof::trust::TrustLevel, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV ID LINK-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV ID LINK SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV ID LINK-----";
+
+const CURRENT_ID_LINK_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_ID_LINK_PROOF_SERIALIZATION_VERSION
+}
+
+/// Body of an Id Link Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct IdLink {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: crate::PubId,
+    /// The other id of the same user
+    pub to: crate::PubId,
+    /// Most trust in `to` that trust in `from` can extend to
+    #[builder(default = "Default::default()")]
+    pub level: TrustLevel,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl IdLink {
+    pub fn apply_draft(&self, draft: IdLinkDraft) -> IdLink {
+        let mut copy = self.clone();
+        copy.level = draft.level;
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `IdLink` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdLinkDraft {
+    level: TrustLevel,
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<IdLink> for IdLinkDraft {
+    fn from(link: IdLink) -> Self {
+        IdLinkDraft {
+            level: link.level,
+            comment: link.comment,
+        }
+    }
+}
+
+impl fmt::Display for IdLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for IdLinkDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl IdLink {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for IdLink {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!("Link to own id {}", self.to.id)
+    }
+}
+
+impl IdLink {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if content.to.id == content.from.id {
+            bail!("Id can't link to itself");
+        }
+        if content.level == TrustLevel::Distrust {
+            bail!("Id link can't distrust");
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl IdLinkDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
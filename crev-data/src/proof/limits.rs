@@ -0,0 +1,87 @@
+//! Limits enforced when parsing proofs
+//!
+//! Proofs are typically fetched from repositories of other people, and
+//! have to be treated as untrusted input. These limits are way above
+//! anything a legitimate proof would need, but keep a malicious proof
+//! repository from exhausting memory, stack or time of the parser.
+use crate::Result;
+use serde_yaml::Value;
+use yaml_rust::scanner::{Scanner, TokenType};
+
+/// Maximum size of a single file with proofs
+pub const MAX_PROOF_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Maximum size of a body of a single proof
+pub const MAX_BODY_SIZE: usize = 16_000;
+
+/// Maximum size of a signature of a single proof
+pub const MAX_SIGNATURE_SIZE: usize = 2000;
+
+/// Maximum length of any single field of a proof
+pub const MAX_FIELD_LENGTH: usize = 8000;
+
+/// Maximum depth of nested values in a proof body
+pub const MAX_NESTING_DEPTH: usize = 8;
+
+/// Check the structure of a proof `body` against the limits,
+/// before deserializing it into a concrete type
+pub fn check_body(body: &str) -> Result<()> {
+    if body.len() > MAX_BODY_SIZE {
+        bail!("Proof body too long");
+    }
+    check_structure(body)
+}
+
+/// Like `check_body`, without limiting the total size
+///
+/// For documents which can legitimately be larger than a proof, and
+/// enforce their own size limit.
+pub fn check_structure(body: &str) -> Result<()> {
+    check_no_aliases(body)?;
+    let value: Value = serde_yaml::from_str(body)?;
+    check_value(&value, 0)
+}
+
+/// Refuse YAML anchors and aliases, before anything gets deserialized
+///
+/// Aliases are expanded while parsing, so a few hundred bytes of nested
+/// ones make billions of values. Proofs never need them. Only the tokens
+/// are looked at here, which takes time linear in the size of `body`.
+pub fn check_no_aliases(body: &str) -> Result<()> {
+    for token in Scanner::new(body.chars()) {
+        if let TokenType::Alias(_) | TokenType::Anchor(_) = token.1 {
+            bail!("YAML anchors and aliases are not allowed");
+        }
+    }
+    Ok(())
+}
+
+fn check_value(value: &Value, depth: usize) -> Result<()> {
+    if depth > MAX_NESTING_DEPTH {
+        bail!("Proof nested too deep");
+    }
+    match value {
+        Value::String(s) => check_field_length(s),
+        Value::Sequence(seq) => {
+            for v in seq {
+                check_value(v, depth + 1)?;
+            }
+            Ok(())
+        }
+        Value::Mapping(map) => {
+            for (k, v) in map {
+                check_value(k, depth + 1)?;
+                check_value(v, depth + 1)?;
+            }
+            Ok(())
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => Ok(()),
+    }
+}
+
+fn check_field_length(s: &str) -> Result<()> {
+    if s.len() > MAX_FIELD_LENGTH {
+        bail!("Proof field too long");
+    }
+    Ok(())
+}
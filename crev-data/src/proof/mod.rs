@@ -3,14 +3,37 @@
 use crate::Url;
 use chrono::{self, prelude::*};
 use crev_common;
-use std::{default, fmt, fs, io, mem, path::Path};
+use std::{
+    fmt, fs,
+    io::{self, BufRead, Read},
+    mem,
+    path::Path,
+};
 
+pub mod advisory;
+pub mod build_attestation;
+pub mod canonical;
+pub mod extensions;
+pub mod id_link;
+pub mod limits;
 pub mod package_info;
+pub mod path_move;
+pub mod project;
+pub mod project_migration;
 pub mod review;
+pub mod review_request;
 pub mod revision;
+pub mod revocation;
+pub mod schema;
 pub mod trust;
+pub mod trust_root_handover;
 
-pub use self::{package_info::*, revision::*, trust::*};
+pub use self::{
+    advisory::Advisory, build_attestation::BuildAttestation, extensions::Extensions,
+    id_link::IdLink, package_info::*, path_move::PathMove, project::Project,
+    project_migration::ProjectMigration, review_request::ReviewRequest, revision::*,
+    revocation::Revocation, trust::*, trust_root_handover::TrustRootHandover,
+};
 
 use crate::Result;
 
@@ -33,19 +56,53 @@ pub trait ContentCommon {
     fn draft_title(&self) -> String;
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ProofType {
     Code,
     Package,
     Trust,
+    Project,
+    ProjectMigration,
+    TrustRootHandover,
+    PathMove,
+    BuildAttestation,
+    ReviewRequest,
+    Revocation,
+    Advisory,
+    IdLink,
 }
 
 impl ProofType {
+    const ALL: [ProofType; 12] = [
+        ProofType::Code,
+        ProofType::Package,
+        ProofType::Trust,
+        ProofType::Project,
+        ProofType::ProjectMigration,
+        ProofType::TrustRootHandover,
+        ProofType::PathMove,
+        ProofType::BuildAttestation,
+        ProofType::ReviewRequest,
+        ProofType::Revocation,
+        ProofType::Advisory,
+        ProofType::IdLink,
+    ];
+
     fn begin_block(&self) -> &'static str {
         match self {
             ProofType::Code => review::Code::BEGIN_BLOCK,
             ProofType::Package => review::Package::BEGIN_BLOCK,
             ProofType::Trust => Trust::BEGIN_BLOCK,
+            ProofType::Project => Project::BEGIN_BLOCK,
+            ProofType::ProjectMigration => ProjectMigration::BEGIN_BLOCK,
+            ProofType::TrustRootHandover => TrustRootHandover::BEGIN_BLOCK,
+            ProofType::PathMove => PathMove::BEGIN_BLOCK,
+            ProofType::BuildAttestation => BuildAttestation::BEGIN_BLOCK,
+            ProofType::ReviewRequest => ReviewRequest::BEGIN_BLOCK,
+            ProofType::Revocation => Revocation::BEGIN_BLOCK,
+            ProofType::Advisory => Advisory::BEGIN_BLOCK,
+            ProofType::IdLink => IdLink::BEGIN_BLOCK,
         }
     }
     fn begin_signature(&self) -> &'static str {
@@ -53,6 +110,15 @@ impl ProofType {
             ProofType::Code => review::Code::BEGIN_SIGNATURE,
             ProofType::Package => review::Package::BEGIN_SIGNATURE,
             ProofType::Trust => Trust::BEGIN_SIGNATURE,
+            ProofType::Project => Project::BEGIN_SIGNATURE,
+            ProofType::ProjectMigration => ProjectMigration::BEGIN_SIGNATURE,
+            ProofType::TrustRootHandover => TrustRootHandover::BEGIN_SIGNATURE,
+            ProofType::PathMove => PathMove::BEGIN_SIGNATURE,
+            ProofType::BuildAttestation => BuildAttestation::BEGIN_SIGNATURE,
+            ProofType::ReviewRequest => ReviewRequest::BEGIN_SIGNATURE,
+            ProofType::Revocation => Revocation::BEGIN_SIGNATURE,
+            ProofType::Advisory => Advisory::BEGIN_SIGNATURE,
+            ProofType::IdLink => IdLink::BEGIN_SIGNATURE,
         }
     }
     fn end_block(&self) -> &'static str {
@@ -60,15 +126,58 @@ impl ProofType {
             ProofType::Code => review::Code::END_BLOCK,
             ProofType::Package => review::Package::END_BLOCK,
             ProofType::Trust => Trust::END_BLOCK,
+            ProofType::Project => Project::END_BLOCK,
+            ProofType::ProjectMigration => ProjectMigration::END_BLOCK,
+            ProofType::TrustRootHandover => TrustRootHandover::END_BLOCK,
+            ProofType::PathMove => PathMove::END_BLOCK,
+            ProofType::BuildAttestation => BuildAttestation::END_BLOCK,
+            ProofType::ReviewRequest => ReviewRequest::END_BLOCK,
+            ProofType::Revocation => Revocation::END_BLOCK,
+            ProofType::Advisory => Advisory::END_BLOCK,
+            ProofType::IdLink => IdLink::END_BLOCK,
         }
     }
 }
 
+/// How proofs are written out, and read back
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Between begin and end markers, as in proof files
+    Armor,
+    /// A JSON object holding `type`, `body` and `signature`, on a line of
+    /// its own
+    ///
+    /// The body is kept as the exact string it was signed as, so the
+    /// digest and signature of a proof don't change.
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "armor" => Format::Armor,
+            "json" => Format::Json,
+            _ => bail!("Unknown proof format: {}", s),
+        })
+    }
+}
+
+/// A proof in the `Json` format
+#[derive(Serialize, Deserialize)]
+struct JsonProof {
+    #[serde(rename = "type")]
+    type_: ProofType,
+    body: String,
+    signature: String,
+}
+
 /// Serialized Proof
 ///
 /// A signed proof containing some signed `Content`
 #[derive(Debug, Clone)]
-pub(crate) struct Serialized {
+pub struct Serialized {
     /// Serialized content
     pub body: String,
     /// Signature over the body
@@ -79,10 +188,20 @@ pub(crate) struct Serialized {
 
 /// Content is an enumerator of possible proof contents
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Content {
     Trust(Trust),
     Package(review::Package),
     Code(review::Code),
+    Project(Project),
+    ProjectMigration(ProjectMigration),
+    TrustRootHandover(TrustRootHandover),
+    PathMove(PathMove),
+    BuildAttestation(BuildAttestation),
+    ReviewRequest(ReviewRequest),
+    Revocation(Revocation),
+    Advisory(Advisory),
+    IdLink(IdLink),
 }
 
 impl fmt::Display for Content {
@@ -92,6 +211,15 @@ impl fmt::Display for Content {
             Trust(trust) => trust.fmt(f),
             Code(code) => code.fmt(f),
             Package(package) => package.fmt(f),
+            Project(project) => project.fmt(f),
+            ProjectMigration(migration) => migration.fmt(f),
+            TrustRootHandover(handover) => handover.fmt(f),
+            PathMove(path_move) => path_move.fmt(f),
+            BuildAttestation(attestation) => attestation.fmt(f),
+            ReviewRequest(request) => request.fmt(f),
+            Revocation(revocation) => revocation.fmt(f),
+            Advisory(advisory) => advisory.fmt(f),
+            IdLink(link) => link.fmt(f),
         }
     }
 }
@@ -114,6 +242,60 @@ impl From<Trust> for Content {
     }
 }
 
+impl From<Project> for Content {
+    fn from(project: Project) -> Self {
+        Content::Project(project)
+    }
+}
+
+impl From<ProjectMigration> for Content {
+    fn from(migration: ProjectMigration) -> Self {
+        Content::ProjectMigration(migration)
+    }
+}
+
+impl From<TrustRootHandover> for Content {
+    fn from(handover: TrustRootHandover) -> Self {
+        Content::TrustRootHandover(handover)
+    }
+}
+
+impl From<PathMove> for Content {
+    fn from(path_move: PathMove) -> Self {
+        Content::PathMove(path_move)
+    }
+}
+
+impl From<BuildAttestation> for Content {
+    fn from(attestation: BuildAttestation) -> Self {
+        Content::BuildAttestation(attestation)
+    }
+}
+
+impl From<ReviewRequest> for Content {
+    fn from(request: ReviewRequest) -> Self {
+        Content::ReviewRequest(request)
+    }
+}
+
+impl From<Revocation> for Content {
+    fn from(revocation: Revocation) -> Self {
+        Content::Revocation(revocation)
+    }
+}
+
+impl From<Advisory> for Content {
+    fn from(advisory: Advisory) -> Self {
+        Content::Advisory(advisory)
+    }
+}
+
+impl From<IdLink> for Content {
+    fn from(link: IdLink) -> Self {
+        Content::IdLink(link)
+    }
+}
+
 impl Content {
     pub fn draft_title(&self) -> String {
         use self::Content::*;
@@ -121,17 +303,39 @@ impl Content {
             Trust(trust) => trust.draft_title(),
             Code(review) => review.draft_title(),
             Package(review) => review.draft_title(),
+            Project(project) => project.draft_title(),
+            ProjectMigration(migration) => migration.draft_title(),
+            TrustRootHandover(handover) => handover.draft_title(),
+            PathMove(path_move) => path_move.draft_title(),
+            BuildAttestation(attestation) => attestation.draft_title(),
+            ReviewRequest(request) => request.draft_title(),
+            Revocation(revocation) => revocation.draft_title(),
+            Advisory(advisory) => advisory.draft_title(),
+            IdLink(link) => link.draft_title(),
         }
     }
     pub fn parse(s: &str, type_: ProofType) -> Result<Content> {
+        schema::check_body(s, type_)?;
         Ok(match type_ {
             ProofType::Code => Content::Code(review::Code::parse(&s)?),
             ProofType::Package => Content::Package(review::Package::parse(&s)?),
             ProofType::Trust => Content::Trust(Trust::parse(&s)?),
+            ProofType::Project => Content::Project(Project::parse(s)?),
+            ProofType::ProjectMigration => Content::ProjectMigration(ProjectMigration::parse(s)?),
+            ProofType::TrustRootHandover => {
+                Content::TrustRootHandover(TrustRootHandover::parse(s)?)
+            }
+            ProofType::PathMove => Content::PathMove(PathMove::parse(s)?),
+            ProofType::BuildAttestation => Content::BuildAttestation(BuildAttestation::parse(s)?),
+            ProofType::ReviewRequest => Content::ReviewRequest(ReviewRequest::parse(s)?),
+            ProofType::Revocation => Content::Revocation(Revocation::parse(s)?),
+            ProofType::Advisory => Content::Advisory(Advisory::parse(s)?),
+            ProofType::IdLink => Content::IdLink(IdLink::parse(s)?),
         })
     }
 
     pub fn parse_draft(original_proof: &Content, s: &str) -> Result<Content> {
+        schema::check_draft(s, original_proof.proof_type())?;
         Ok(match original_proof {
             Content::Code(code) => {
                 Content::Code(code.apply_draft(review::CodeDraft::parse(&s)?.into()))
@@ -142,13 +346,45 @@ impl Content {
             Content::Trust(trust) => {
                 Content::Trust(trust.apply_draft(TrustDraft::parse(&s)?.into()))
             }
+            Content::Project(project) => {
+                Content::Project(project.apply_draft(project::ProjectDraft::parse(s)?))
+            }
+            Content::ProjectMigration(migration) => Content::ProjectMigration(
+                migration.apply_draft(project_migration::ProjectMigrationDraft::parse(s)?),
+            ),
+            Content::TrustRootHandover(handover) => Content::TrustRootHandover(
+                handover.apply_draft(trust_root_handover::TrustRootHandoverDraft::parse(s)?),
+            ),
+            Content::PathMove(path_move) => {
+                Content::PathMove(path_move.apply_draft(path_move::PathMoveDraft::parse(s)?))
+            }
+            Content::BuildAttestation(attestation) => Content::BuildAttestation(
+                attestation.apply_draft(build_attestation::BuildAttestationDraft::parse(s)?),
+            ),
+            Content::ReviewRequest(request) => Content::ReviewRequest(
+                request.apply_draft(review_request::ReviewRequestDraft::parse(s)?),
+            ),
+            Content::Revocation(revocation) => {
+                Content::Revocation(revocation.apply_draft(revocation::RevocationDraft::parse(s)?))
+            }
+            Content::Advisory(advisory) => {
+                Content::Advisory(advisory.apply_draft(advisory::AdvisoryDraft::parse(s)?))
+            }
+            Content::IdLink(link) => {
+                Content::IdLink(link.apply_draft(id_link::IdLinkDraft::parse(s)?))
+            }
         })
     }
+    /// The body as signed by `sign_by`; see `canonical`
+    pub fn canonical_body(&self) -> String {
+        canonical::canonicalize(&self.to_string())
+    }
+
     pub fn sign_by(&self, id: &crate::id::OwnId) -> Result<Proof> {
-        let body = self.to_string();
-        let signature = id.sign(&body.as_bytes());
+        let body = self.canonical_body();
+        let signature = id.sign(canonical::signed_encoding(&body)?.as_bytes());
         Ok(Proof {
-            digest: crev_common::blake2b256sum(&body.as_bytes()),
+            digest: canonical::digest(&body)?,
             body: body,
             signature: crev_common::base64_encode(&signature),
             content: self.clone(),
@@ -161,6 +397,15 @@ impl Content {
             Trust(_trust) => ProofType::Trust,
             Code(_review) => ProofType::Code,
             Package(_review) => ProofType::Package,
+            Project(_project) => ProofType::Project,
+            ProjectMigration(_migration) => ProofType::ProjectMigration,
+            TrustRootHandover(_handover) => ProofType::TrustRootHandover,
+            PathMove(_path_move) => ProofType::PathMove,
+            BuildAttestation(_attestation) => ProofType::BuildAttestation,
+            ReviewRequest(_request) => ProofType::ReviewRequest,
+            Revocation(_revocation) => ProofType::Revocation,
+            Advisory(_advisory) => ProofType::Advisory,
+            IdLink(_link) => ProofType::IdLink,
         }
     }
 
@@ -170,6 +415,15 @@ impl Content {
             Trust(trust) => trust.date(),
             Code(review) => review.date(),
             Package(review) => review.date(),
+            Project(project) => project.date(),
+            ProjectMigration(migration) => migration.date(),
+            TrustRootHandover(handover) => handover.date(),
+            PathMove(path_move) => path_move.date(),
+            BuildAttestation(attestation) => attestation.date(),
+            ReviewRequest(request) => request.date(),
+            Revocation(revocation) => revocation.date(),
+            Advisory(advisory) => advisory.date(),
+            IdLink(link) => link.date(),
         }
     }
 
@@ -179,6 +433,15 @@ impl Content {
             Trust(trust) => trust.author_id(),
             Code(review) => review.author_id(),
             Package(review) => review.author_id(),
+            Project(project) => project.author_id(),
+            ProjectMigration(migration) => migration.author_id(),
+            TrustRootHandover(handover) => handover.author_id(),
+            PathMove(path_move) => path_move.author_id(),
+            BuildAttestation(attestation) => attestation.author_id(),
+            ReviewRequest(request) => request.author_id(),
+            Revocation(revocation) => revocation.author_id(),
+            Advisory(advisory) => advisory.author_id(),
+            IdLink(link) => link.author_id(),
         }
     }
 
@@ -188,7 +451,188 @@ impl Content {
             Trust(trust) => trust.author_url(),
             Code(review) => review.author_url(),
             Package(review) => review.author_url(),
+            Project(project) => project.author_url(),
+            ProjectMigration(migration) => migration.author_url(),
+            TrustRootHandover(handover) => handover.author_url(),
+            PathMove(path_move) => path_move.author_url(),
+            BuildAttestation(attestation) => attestation.author_url(),
+            ReviewRequest(request) => request.author_url(),
+            Revocation(revocation) => revocation.author_url(),
+            Advisory(advisory) => advisory.author_url(),
+            IdLink(link) => link.author_url(),
+        }
+    }
+
+    /// When the proof stops counting, if ever
+    ///
+    /// Only Trust, Code Review and Package Review Proofs can expire.
+    pub fn expires(&self) -> Option<&chrono::DateTime<FixedOffset>> {
+        use self::Content::*;
+        match self {
+            Trust(trust) => trust.expires.as_ref(),
+            Code(review) => review.expires.as_ref(),
+            Package(review) => review.expires.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Earlier proofs of the same author this one replaces
+    ///
+    /// Eg. a review updated with another thoroughness or comment. Only
+    /// Trust, Code Review and Package Review Proofs can supersede others.
+    pub fn supersedes(&self) -> &[SupersededProof] {
+        use self::Content::*;
+        match self {
+            Trust(trust) => &trust.supersedes,
+            Code(review) => &review.supersedes,
+            Package(review) => &review.supersedes,
+            _ => &[],
+        }
+    }
+
+    /// Labels the author put on the proof, eg. `crypto` for a review of
+    /// cryptographic code or `license` for a license check
+    ///
+    /// Requirements can ask for reviews with some tags; see
+    /// `PathRequirement` of `crev-lib`. Only Trust, Code Review and Package
+    /// Review Proofs have tags.
+    pub fn tags(&self) -> &[String] {
+        use self::Content::*;
+        match self {
+            Trust(trust) => &trust.tags,
+            Code(review) => &review.tags,
+            Package(review) => &review.tags,
+            _ => &[],
+        }
+    }
+
+    /// A copy of `self` dated `date`, superseding the proof with `digest`
+    ///
+    /// `None` for proofs that can't supersede others.
+    pub fn superseding(
+        &self,
+        digest: &[u8],
+        date: chrono::DateTime<FixedOffset>,
+    ) -> Option<Content> {
+        use self::Content::*;
+        let supersedes = vec![SupersededProof {
+            digest: digest.to_owned(),
+        }];
+        let mut copy = self.clone();
+        match copy {
+            Trust(ref mut trust) => {
+                trust.date = date;
+                trust.supersedes = supersedes;
+            }
+            Code(ref mut review) => {
+                review.date = date;
+                review.supersedes = supersedes;
+            }
+            Package(ref mut review) => {
+                review.date = date;
+                review.supersedes = supersedes;
+            }
+            _ => return None,
+        }
+        Some(copy)
+    }
+
+    pub fn is_expired_at(&self, now: &chrono::DateTime<Utc>) -> bool {
+        self.expires()
+            .map_or(false, |expires| expires.with_timezone(&Utc) <= *now)
+    }
+
+    /// Copy of an expiring proof dated at `date`, and expiring as long
+    /// after it as this one does
+    pub fn reissued_at(&self, date: chrono::DateTime<FixedOffset>) -> Option<Content> {
+        let validity = self.expires()?.signed_duration_since(*self.date());
+        Some(self.with_validity(date, Some(date + validity)))
+    }
+
+    /// Is this the same proof as `other`, if not for the dates
+    pub fn same_besides_validity(&self, other: &Content) -> bool {
+        let epoch = FixedOffset::east(0).timestamp(0, 0);
+        self.with_validity(epoch, None).to_string() == other.with_validity(epoch, None).to_string()
+    }
+
+    fn with_validity(
+        &self,
+        date: chrono::DateTime<FixedOffset>,
+        expires: Option<chrono::DateTime<FixedOffset>>,
+    ) -> Content {
+        use self::Content::*;
+        let mut copy = self.clone();
+        match copy {
+            Trust(ref mut trust) => {
+                trust.date = date;
+                trust.expires = expires;
+            }
+            Code(ref mut review) => {
+                review.date = date;
+                review.expires = expires;
+            }
+            Package(ref mut review) => {
+                review.date = date;
+                review.expires = expires;
+            }
+            _ => {}
+        }
+        copy
+    }
+
+    /// `self` ready to be signed later, in the form read by
+    /// `parse_unsigned`: like a proof, but without the signature
+    pub fn to_unsigned_string(&self) -> String {
+        let type_ = self.proof_type();
+        format!(
+            "{}\n{}{}\n",
+            type_.begin_block(),
+            canonical::canonicalize(&self.to_string()),
+            type_.end_block()
+        )
+    }
+
+    /// Parse contents written by `to_unsigned_string`, eg. drafts queued
+    /// for signing by importers
+    pub fn parse_unsigned(reader: impl io::BufRead) -> Result<Vec<Content>> {
+        let mut contents = vec![];
+        let mut current: Option<(ProofType, String)> = None;
+        for line in reader.take(limits::MAX_PROOF_FILE_SIZE + 1).lines() {
+            let line = line?;
+            current = match current {
+                None => {
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    match ProofType::ALL
+                        .iter()
+                        .find(|type_| type_.begin_block() == line)
+                    {
+                        Some(type_) => Some((*type_, String::new())),
+                        None => bail!("Parsing error when looking for start of unsigned proof"),
+                    }
+                }
+                Some((type_, body)) => {
+                    if line.trim() == type_.end_block() {
+                        limits::check_body(&body)?;
+                        contents.push(Content::parse(&body, type_)?);
+                        None
+                    } else if line.trim() == type_.begin_signature() {
+                        bail!("Unsigned proof has a signature");
+                    } else {
+                        if body.len() > limits::MAX_BODY_SIZE {
+                            bail!("Proof body too long");
+                        }
+                        Some((type_, body + &line + "\n"))
+                    }
+                }
+            };
         }
+        if current.is_some() {
+            bail!("Unexpected EOF while parsing");
+        }
+        Ok(contents)
     }
 
     pub fn to_draft_string(&self) -> String {
@@ -197,6 +641,26 @@ impl Content {
             Trust(trust) => format!("{}", TrustDraft::from(trust)),
             Code(review) => format!("{}", review::CodeDraft::from(review)),
             Package(review) => format!("{}", review::PackageDraft::from(review)),
+            Project(project) => format!("{}", project::ProjectDraft::from(project)),
+            ProjectMigration(migration) => format!(
+                "{}",
+                project_migration::ProjectMigrationDraft::from(migration)
+            ),
+            TrustRootHandover(handover) => format!(
+                "{}",
+                trust_root_handover::TrustRootHandoverDraft::from(handover)
+            ),
+            PathMove(path_move) => format!("{}", path_move::PathMoveDraft::from(path_move)),
+            BuildAttestation(attestation) => format!(
+                "{}",
+                build_attestation::BuildAttestationDraft::from(attestation)
+            ),
+            ReviewRequest(request) => {
+                format!("{}", review_request::ReviewRequestDraft::from(request))
+            }
+            Revocation(revocation) => format!("{}", revocation::RevocationDraft::from(revocation)),
+            Advisory(advisory) => format!("{}", advisory::AdvisoryDraft::from(advisory)),
+            IdLink(link) => format!("{}", id_link::IdLinkDraft::from(link)),
         }
     }
 }
@@ -243,147 +707,430 @@ impl fmt::Display for Proof {
 }
 
 impl Serialized {
+    pub fn to_string_in(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Armor => self.to_string(),
+            Format::Json => {
+                let mut line = serde_json::to_string(&JsonProof {
+                    type_: self.type_,
+                    body: self.body.clone(),
+                    signature: self.signature.trim().to_owned(),
+                })?;
+                line.push('\n');
+                line
+            }
+        })
+    }
+
+    pub fn parse_in(reader: impl io::BufRead, format: Format) -> Result<Vec<Self>> {
+        match format {
+            Format::Armor => Self::parse(reader),
+            Format::Json => Self::parse_json(reader),
+        }
+    }
+
+    fn parse_json(reader: impl io::BufRead) -> Result<Vec<Self>> {
+        let mut content = String::new();
+        reader
+            .take(limits::MAX_PROOF_FILE_SIZE + 1)
+            .read_to_string(&mut content)?;
+        if content.len() as u64 > limits::MAX_PROOF_FILE_SIZE {
+            bail!("Proof file too large");
+        }
+        let mut proofs = vec![];
+        for proof in serde_json::Deserializer::from_str(&content).into_iter::<JsonProof>() {
+            let proof = proof?;
+            if proof.body.len() > limits::MAX_BODY_SIZE {
+                bail!("Proof body too long");
+            }
+            if proof.signature.len() > limits::MAX_SIGNATURE_SIZE {
+                bail!("Signature too long");
+            }
+            if !proof.body.ends_with('\n') {
+                // it couldn't be written between the markers otherwise
+                bail!("Proof body doesn't end with a newline");
+            }
+            proofs.push(Serialized {
+                body: proof.body,
+                signature: proof.signature.trim().to_owned(),
+                type_: proof.type_,
+            });
+        }
+        Ok(proofs)
+    }
+
+    /// Verify the signature over the body, by the author it claims
+    ///
+    /// Only the `from` of the body is parsed, so a proof with a valid
+    /// signature can be told from a forged one even if its content can't
+    /// be parsed by this version of crev.
+    pub fn verify_signature(&self) -> Result<()> {
+        #[derive(Deserialize)]
+        struct Signer {
+            from: crate::PubId,
+        }
+
+        limits::check_body(&self.body)?;
+        let signer: Signer = serde_yaml::from_str(&self.body)?;
+        canonical::verify_body(&signer.from.id, &self.body, self.signature.trim())
+    }
+
     pub fn to_parsed(&self) -> Result<Proof> {
+        limits::check_body(&self.body)?;
+        schema::check_body(&self.body, self.type_)?;
         Ok(Proof {
             body: self.body.clone(),
             signature: self.signature.clone(),
-            digest: crev_common::blake2b256sum(&self.body.as_bytes()),
+            digest: canonical::digest(&self.body)?,
             content: match self.type_ {
                 ProofType::Code => Content::Code(review::Code::parse(&self.body)?),
                 ProofType::Package => Content::Package(review::Package::parse(&self.body)?),
                 ProofType::Trust => Content::Trust(Trust::parse(&self.body)?),
+                ProofType::Project => Content::Project(Project::parse(&self.body)?),
+                ProofType::ProjectMigration => {
+                    Content::ProjectMigration(ProjectMigration::parse(&self.body)?)
+                }
+                ProofType::TrustRootHandover => {
+                    Content::TrustRootHandover(TrustRootHandover::parse(&self.body)?)
+                }
+                ProofType::PathMove => Content::PathMove(PathMove::parse(&self.body)?),
+                ProofType::BuildAttestation => {
+                    Content::BuildAttestation(BuildAttestation::parse(&self.body)?)
+                }
+                ProofType::ReviewRequest => {
+                    Content::ReviewRequest(ReviewRequest::parse(&self.body)?)
+                }
+                ProofType::Revocation => Content::Revocation(Revocation::parse(&self.body)?),
+                ProofType::Advisory => Content::Advisory(Advisory::parse(&self.body)?),
+                ProofType::IdLink => Content::IdLink(IdLink::parse(&self.body)?),
             },
         })
     }
 
+    /// Parse all the proofs of `reader`, failing on the first error
+    ///
+    /// See `parse_all` to keep the proofs before it.
     pub fn parse(reader: impl io::BufRead) -> Result<Vec<Self>> {
-        #[derive(PartialEq, Eq)]
-        enum Stage {
-            None,
-            Body,
-            Signature,
-        }
+        parse_all(reader).collect()
+    }
+}
 
-        impl Default for Stage {
-            fn default() -> Self {
-                Stage::None
-            }
-        }
+#[derive(PartialEq, Eq)]
+enum Stage {
+    None,
+    Body,
+    Signature,
+}
 
-        struct State {
-            stage: Stage,
-            body: String,
-            signature: String,
-            type_: ProofType,
-            proofs: Vec<Serialized>,
-        }
+/// Iterator over the armored proofs of a reader, see `parse_all`
+pub struct ProofReader<R> {
+    // reading one byte over the limit is enough to tell it was exceeded
+    reader: io::Take<R>,
+    total_len: u64,
+    line_number: usize,
+    stage: Stage,
+    /// Line the proof being read begins on
+    begin_line: usize,
+    body: String,
+    signature: String,
+    type_: ProofType,
+    done: bool,
+}
 
-        impl default::Default for State {
-            fn default() -> Self {
-                State {
-                    stage: Default::default(),
-                    body: Default::default(),
-                    signature: Default::default(),
-                    type_: ProofType::Trust, // whatever
-                    proofs: vec![],
+/// Read the armored proofs of `reader` one by one, eg. of a file proofs
+/// were appended to
+///
+/// Proofs are yielded as they are read, so the ones before something that
+/// isn't a proof - trailing garbage, a proof cut short - are still
+/// returned. The error for it comes last, and tells the line it's on.
+pub fn parse_all<R: io::BufRead>(reader: R) -> ProofReader<R> {
+    ProofReader {
+        reader: reader.take(limits::MAX_PROOF_FILE_SIZE + 1),
+        total_len: 0,
+        line_number: 0,
+        stage: Stage::None,
+        begin_line: 0,
+        body: String::new(),
+        signature: String::new(),
+        type_: ProofType::Trust, // whatever
+        done: false,
+    }
+}
+
+impl<R: io::BufRead> ProofReader<R> {
+    fn read_proof(&mut self) -> Result<Option<Serialized>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let len = self.reader.read_line(&mut line)?;
+            if len == 0 {
+                if self.stage != Stage::None {
+                    bail!(
+                        "Unexpected EOF while parsing the proof beginning on line {}",
+                        self.begin_line
+                    );
+                }
+                return Ok(None);
+            }
+            self.line_number += 1;
+            self.total_len += len as u64;
+            if self.total_len > limits::MAX_PROOF_FILE_SIZE {
+                bail!("Proof file too large");
+            }
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
                 }
             }
+            let proof = self
+                .process_line(&line)
+                .map_err(|e| format_err!("Line {}: {}", self.line_number, e))?;
+            if proof.is_some() {
+                return Ok(proof);
+            }
         }
+    }
 
-        impl State {
-            fn process_line(&mut self, line: &str) -> Result<()> {
-                match self.stage {
-                    Stage::None => {
-                        let line = line.trim();
-                        if line.is_empty() {
-                        } else if line == ProofType::Code.begin_block() {
-                            self.type_ = ProofType::Code;
-                            self.stage = Stage::Body;
-                        } else if line == ProofType::Trust.begin_block() {
-                            self.type_ = ProofType::Trust;
-                            self.stage = Stage::Body;
-                        } else if line == ProofType::Package.begin_block() {
-                            self.type_ = ProofType::Package;
-                            self.stage = Stage::Body;
-                        } else {
-                            bail!("Parsing error when looking for start of code review proof");
-                        }
+    fn process_line(&mut self, line: &str) -> Result<Option<Serialized>> {
+        match self.stage {
+            Stage::None => {
+                let line = line.trim();
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                self.type_ = match line {
+                    l if l == ProofType::Code.begin_block() => ProofType::Code,
+                    l if l == ProofType::Trust.begin_block() => ProofType::Trust,
+                    l if l == ProofType::Package.begin_block() => ProofType::Package,
+                    l if l == ProofType::Project.begin_block() => ProofType::Project,
+                    l if l == ProofType::ProjectMigration.begin_block() => {
+                        ProofType::ProjectMigration
                     }
-                    Stage::Body => {
-                        if line.trim() == self.type_.begin_signature() {
-                            self.stage = Stage::Signature;
-                        } else {
-                            self.body += line;
-                            self.body += "\n";
-                        }
-                        if self.body.len() > 16_000 {
-                            bail!("Proof body too long");
-                        }
+                    l if l == ProofType::TrustRootHandover.begin_block() => {
+                        ProofType::TrustRootHandover
                     }
-                    Stage::Signature => {
-                        if line.trim() == self.type_.end_block() {
-                            self.stage = Stage::None;
-                            self.proofs.push(Serialized {
-                                body: mem::replace(&mut self.body, String::new()),
-                                signature: mem::replace(&mut self.signature, String::new()),
-                                type_: self.type_,
-                            });
-                        } else {
-                            self.signature += line;
-                            self.signature += "\n";
-                        }
-                        if self.signature.len() > 2000 {
-                            bail!("Signature too long");
-                        }
+                    l if l == ProofType::PathMove.begin_block() => ProofType::PathMove,
+                    l if l == ProofType::BuildAttestation.begin_block() => {
+                        ProofType::BuildAttestation
                     }
+                    l if l == ProofType::ReviewRequest.begin_block() => ProofType::ReviewRequest,
+                    l if l == ProofType::Revocation.begin_block() => ProofType::Revocation,
+                    l if l == ProofType::Advisory.begin_block() => ProofType::Advisory,
+                    l if l == ProofType::IdLink.begin_block() => ProofType::IdLink,
+                    _ => bail!("Expected the beginning of a proof, found something else"),
+                };
+                self.stage = Stage::Body;
+                self.begin_line = self.line_number;
+            }
+            Stage::Body => {
+                if line.trim() == self.type_.begin_signature() {
+                    self.stage = Stage::Signature;
+                } else {
+                    self.body += line;
+                    self.body += "\n";
+                }
+                if self.body.len() > limits::MAX_BODY_SIZE {
+                    bail!("Proof body too long");
                 }
-                Ok(())
             }
-
-            fn finish(self) -> Result<Vec<Serialized>> {
-                if self.stage != Stage::None {
-                    bail!("Unexpected EOF while parsing");
+            Stage::Signature => {
+                if line.trim() == self.type_.end_block() {
+                    self.stage = Stage::None;
+                    return Ok(Some(Serialized {
+                        body: mem::replace(&mut self.body, String::new()),
+                        signature: mem::replace(&mut self.signature, String::new()),
+                        type_: self.type_,
+                    }));
+                }
+                self.signature += line;
+                self.signature += "\n";
+                if self.signature.len() > limits::MAX_SIGNATURE_SIZE {
+                    bail!("Signature too long");
                 }
-                Ok(self.proofs)
             }
         }
+        Ok(None)
+    }
+}
 
-        let mut state: State = Default::default();
+impl<R: io::BufRead> Iterator for ProofReader<R> {
+    type Item = Result<Serialized>;
 
-        for line in reader.lines() {
-            state.process_line(&line?)?;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_proof() {
+            Ok(Some(proof)) => Some(Ok(proof)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
         }
-
-        state.finish()
     }
 }
 
 impl Proof {
     pub fn parse_from(path: &Path) -> Result<Vec<Self>> {
         let file = fs::File::open(path)?;
+        if file.metadata()?.len() > limits::MAX_PROOF_FILE_SIZE {
+            bail!("Proof file {} too large", path.display());
+        }
         Self::parse(io::BufReader::new(file))
     }
 
     pub fn parse(reader: impl io::BufRead) -> Result<Vec<Self>> {
+        Self::parse_in(reader, Format::Armor)
+    }
+
+    pub fn parse_in(reader: impl io::BufRead, format: Format) -> Result<Vec<Self>> {
         let mut v = vec![];
-        for serialized in Serialized::parse(reader)?.into_iter() {
+        for serialized in Serialized::parse_in(reader, format)?.into_iter() {
             v.push(serialized.to_parsed()?)
         }
         Ok(v)
     }
 
+    pub fn to_serialized(&self) -> Serialized {
+        Serialized {
+            body: self.body.clone(),
+            signature: self.signature.clone(),
+            type_: self.content.proof_type(),
+        }
+    }
+
+    pub fn to_string_in(&self, format: Format) -> Result<String> {
+        self.to_serialized().to_string_in(format)
+    }
+
     pub fn signature(&self) -> &str {
         self.signature.trim()
     }
 
+    /// Verify the signature over the body; see `canonical::verify_body`
     pub fn verify(&self) -> Result<()> {
-        let pubkey = self.content.author_id();
-        pubkey.verify_signature(self.body.as_bytes(), self.signature())?;
+        canonical::verify_body(&self.content.author_id(), &self.body, self.signature())
+    }
 
-        Ok(())
+    /// Re-derive the signed bytes and check the signature step by step
+    ///
+    /// Meant for debugging proofs that one version of crev signed and
+    /// another can't verify. Steps after a failed one are still taken.
+    pub fn reproduce(&self) -> Vec<ReproductionStep> {
+        let body = self.body.as_bytes();
+        let mut steps = vec![];
+
+        steps.push(ReproductionStep {
+            name: "signed body",
+            output: format!(
+                "{} bytes\n{}",
+                body.len(),
+                self.body
+                    .lines()
+                    .map(|line| format!("{:?}", line))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            error: None,
+        });
+
+        let canonical_body = canonical::canonicalize(&self.body);
+        steps.push(ReproductionStep {
+            name: "canonical form",
+            error: if canonical_body == self.body {
+                None
+            } else {
+                Some("the body is not in the canonical form".into())
+            },
+            output: canonical_body,
+        });
+
+        let reserialized = canonical::canonicalize(&self.content.to_string());
+        steps.push(ReproductionStep {
+            name: "re-serialized content",
+            error: if reserialized == self.body {
+                None
+            } else {
+                Some("this version of crev serializes the content differently".into())
+            },
+            output: reserialized,
+        });
+
+        let signed = canonical::signed_encoding(&self.body);
+        steps.push(ReproductionStep {
+            name: "signed encoding",
+            error: signed.as_ref().err().map(|e| e.to_string()),
+            output: signed
+                .map(|signed| {
+                    format!(
+                        "{} digest {}\n{}",
+                        default_digest_type(),
+                        crev_common::base64_encode(&crev_common::blake2b256sum(signed.as_bytes())),
+                        signed
+                    )
+                })
+                .unwrap_or_default(),
+        });
+
+        let signature = crev_common::base64_decode(self.signature());
+        steps.push(ReproductionStep {
+            name: "signature encoding",
+            output: match signature {
+                Ok(ref bytes) => format!("{} bytes", bytes.len()),
+                Err(_) => self.signature().into(),
+            },
+            error: signature.err().map(|e| e.to_string()),
+        });
+
+        let author = self.content.author_id();
+        steps.push(ReproductionStep {
+            name: "signature",
+            output: format!("by {}", author),
+            error: canonical::verify_body(&author, &self.body, self.signature())
+                .err()
+                .map(|e| e.to_string()),
+        });
+
+        steps
     }
 }
 
+/// An earlier proof replaced by a newer one; see `Content::supersedes`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SupersededProof {
+    /// Digest of the proof, as in `Proof::digest`
+    #[serde(
+        serialize_with = "crev_common::serde::as_base64",
+        deserialize_with = "crev_common::serde::from_base64"
+    )]
+    pub digest: Vec<u8>,
+}
+
+/// Fail unless each of `tags` is a single word, as in `Content::tags`
+pub fn ensure_valid_tags(tags: &[String]) -> Result<()> {
+    for tag in tags {
+        if tag.is_empty() || tag.contains(char::is_whitespace) {
+            bail!("Invalid tag {:?}: tags are single words", tag);
+        }
+    }
+    Ok(())
+}
+
+/// A step of `Proof::reproduce`
+#[derive(Debug, Clone)]
+pub struct ReproductionStep {
+    pub name: &'static str,
+    /// What the step derived
+    pub output: String,
+    /// Why the step failed, if it did
+    pub error: Option<String>,
+}
+
 fn equals_default_digest_type(s: &str) -> bool {
     s == default_digest_type()
 }
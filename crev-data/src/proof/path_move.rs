@@ -0,0 +1,158 @@
+//! Path Move Proof - files of a package moved on purpose
+//!
+//! Records that files of a package now live at other paths, eg. after
+//! reorganizing its modules, so the moves are known to verification even
+//! when they can't be guessed from git history.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV PATH MOVE-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV PATH MOVE SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV PATH MOVE-----";
+
+const CURRENT_PATH_MOVE_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_PATH_MOVE_PROOF_SERIALIZATION_VERSION
+}
+
+/// A file moved from one path (relative to the root of the package) to
+/// another
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Move {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Body of a Path Move Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct PathMove {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: crate::PubId,
+    /// The package, at the revision the files were moved in
+    pub package: proof::PackageInfo,
+    pub moves: Vec<Move>,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl PathMove {
+    pub fn apply_draft(&self, draft: PathMoveDraft) -> PathMove {
+        let mut copy = self.clone();
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `PathMove` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PathMoveDraft {
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<PathMove> for PathMoveDraft {
+    fn from(path_move: PathMove) -> Self {
+        PathMoveDraft {
+            comment: path_move.comment,
+        }
+    }
+}
+
+impl fmt::Display for PathMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for PathMoveDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl PathMove {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for PathMove {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        match self.moves.as_slice() {
+            [single] => format!(
+                "Move of {} to {}",
+                single.from.display(),
+                single.to.display()
+            ),
+            moves => format!("Move of {} files of {}", moves.len(), self.package.name),
+        }
+    }
+}
+
+/// Is `path` relative, and staying within the package
+fn is_package_path(path: &Path) -> bool {
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+impl PathMove {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if content.moves.is_empty() {
+            bail!("Path Move Proof has to move some paths");
+        }
+        for path_move in &content.moves {
+            for path in &[&path_move.from, &path_move.to] {
+                if !is_package_path(path) {
+                    bail!("Not a path within the package: {}", path.display());
+                }
+            }
+            if path_move.from == path_move.to {
+                bail!("Path can't move to itself: {}", path_move.from.display());
+            }
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl PathMoveDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
@@ -0,0 +1,170 @@
+//! Project Proof - the identity of a project
+//!
+//! Signed by the trust root of a project, it binds a canonical URL and
+//! a name to the id of the project. The id is derived from the canonical
+//! URL alone, so anyone can calculate it, and two trust roots claiming
+//! the same URL (or name) can be detected as a collision.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV PROJECT-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV PROJECT SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV PROJECT-----";
+
+const CURRENT_PROJECT_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_PROJECT_PROOF_SERIALIZATION_VERSION
+}
+
+/// Bring a project URL into the canonical form
+///
+/// Only `https://` URLs are accepted. The scheme and host are lowercased,
+/// and a trailing `/` or `.git` removed, so that all the usual ways of
+/// writing the URL of a repository map to the same project id.
+pub fn canonical_url(url: &str) -> Result<String> {
+    let url = url.trim();
+    let rest = match url.get(..8) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("https://") => &url[8..],
+        _ => bail!("Project URL must start with `https://`: {}", url),
+    };
+
+    let (host, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    if host.is_empty() {
+        bail!("Project URL has no host: {}", url);
+    }
+
+    let mut path = path.trim_end_matches('/');
+    if path.ends_with(".git") {
+        path = &path[..path.len() - 4];
+    }
+    Ok(format!(
+        "https://{}{}",
+        host.to_ascii_lowercase(),
+        path.trim_end_matches('/')
+    ))
+}
+
+/// Id of the project with a given canonical URL; see `canonical_url`
+pub fn project_id_for_url(canonical_url: &str) -> String {
+    crate::Digest::from_vec(crev_common::blake2b256sum(canonical_url.as_bytes())).to_string()
+}
+
+/// Body of a Project Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct Project {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// The trust root of the project
+    pub from: crate::PubId,
+    pub name: String,
+    /// Canonical URL; see `canonical_url`
+    pub url: String,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl Project {
+    /// Id of the project; see `project_id_for_url`
+    pub fn project_id(&self) -> String {
+        project_id_for_url(&self.url)
+    }
+
+    pub fn apply_draft(&self, draft: ProjectDraft) -> Project {
+        let mut copy = self.clone();
+        copy.name = draft.name;
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `Project` but serializes for interactive editing
+///
+/// The URL can't be edited, as it determines the project id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProjectDraft {
+    pub name: String,
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<Project> for ProjectDraft {
+    fn from(project: Project) -> Self {
+        ProjectDraft {
+            name: project.name,
+            comment: project.comment,
+        }
+    }
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for ProjectDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl Project {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for Project {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!("Identity of project {}", self.url)
+    }
+}
+
+impl Project {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if canonical_url(&content.url)? != content.url {
+            bail!("Project URL not in canonical form: {}", content.url);
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl ProjectDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
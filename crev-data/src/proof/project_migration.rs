@@ -0,0 +1,133 @@
+//! Project Migration Proof - a project moving to a new URL
+//!
+//! Signed by the trust root of a project, it declares that the project
+//! continues under a new URL (and so a new project id), eg. after
+//! a rename or when the development moved to a fork. Reviews of the old
+//! project then follow it to the new one.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV PROJECT MIGRATION-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV PROJECT MIGRATION SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV PROJECT MIGRATION-----";
+
+const CURRENT_PROJECT_MIGRATION_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_PROJECT_MIGRATION_PROOF_SERIALIZATION_VERSION
+}
+
+/// Body of a Project Migration Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct ProjectMigration {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// The trust root of the old project
+    pub from: crate::PubId,
+    /// Id of the old project
+    pub project: String,
+    /// Canonical URL the project continues under; see `project::canonical_url`
+    pub url: String,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl ProjectMigration {
+    /// Id of the project continuing the old one
+    pub fn successor_id(&self) -> String {
+        proof::project::project_id_for_url(&self.url)
+    }
+
+    pub fn apply_draft(&self, draft: ProjectMigrationDraft) -> ProjectMigration {
+        let mut copy = self.clone();
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `ProjectMigration` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProjectMigrationDraft {
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<ProjectMigration> for ProjectMigrationDraft {
+    fn from(migration: ProjectMigration) -> Self {
+        ProjectMigrationDraft {
+            comment: migration.comment,
+        }
+    }
+}
+
+impl fmt::Display for ProjectMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for ProjectMigrationDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl ProjectMigration {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for ProjectMigration {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!("Migration of project {} to {}", self.project, self.url)
+    }
+}
+
+impl ProjectMigration {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if proof::project::canonical_url(&content.url)? != content.url {
+            bail!("Project URL not in canonical form: {}", content.url);
+        }
+        if content.successor_id() == content.project {
+            bail!("Project can't migrate to itself");
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl ProjectMigrationDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
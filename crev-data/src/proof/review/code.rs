@@ -2,9 +2,12 @@ use crate::{id, proof, Result};
 use chrono::{self, prelude::*};
 use crev_common;
 use serde_yaml;
-use std::{self, default::Default, fmt, path::PathBuf};
+use std::{self, collections::BTreeMap, default::Default, fmt, path::PathBuf};
 
-use crev_common::serde::{as_base64, as_rfc3339_fixed, from_base64, from_rfc3339_fixed};
+use crev_common::serde::{
+    as_base64, as_rfc3339_fixed, as_rfc3339_fixed_opt, from_base64, from_rfc3339_fixed,
+    from_rfc3339_fixed_opt,
+};
 
 const BEGIN_BLOCK: &str = "-----BEGIN CODE REVIEW-----";
 const BEGIN_SIGNATURE: &str = "-----BEGIN CODE REVIEW SIGNATURE-----";
@@ -27,6 +30,169 @@ pub struct File {
         default = "proof::default_digest_type"
     )]
     pub digest_type: String,
+    /// Items of the file covered by the review; see `Anchor`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub anchors: Vec<Anchor>,
+    /// Applied to the content of the file, in order, before digesting it
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub normalization: Vec<Normalization>,
+    /// What the file is, as told when it was staged
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub kind: Option<FileKind>,
+}
+
+/// What a reviewed file is, so requirements and reports can tell eg.
+/// code from documentation
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileKind {
+    Code,
+    /// Configuration, including infrastructure as code
+    Config,
+    Docs,
+    Data,
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            FileKind::Code => "code",
+            FileKind::Config => "config",
+            FileKind::Docs => "docs",
+            FileKind::Data => "data",
+        })
+    }
+}
+
+impl std::str::FromStr for FileKind {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "code" => FileKind::Code,
+            "config" => FileKind::Config,
+            "docs" => FileKind::Docs,
+            "data" => FileKind::Data,
+            _ => bail!("Unknown file kind: {}", s),
+        })
+    }
+}
+
+/// A change to the text of a file before digesting it, so that the review
+/// still applies to copies differing only in ways that don't matter
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Normalization {
+    /// Remove spaces and tabs at the ends of lines
+    TrailingWhitespace,
+    /// Remove a UTF-8 byte order mark at the start
+    Bom,
+}
+
+/// A directory of the reviewed files, with a comment of its own
+///
+/// Lets a review of many files say something about each part of them,
+/// instead of one comment for all.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Group {
+    /// Directory of the files of the group, relative to the package
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub comment: String,
+}
+
+/// A reviewed item of a file, eg. a function
+///
+/// The digest covers only the tokens of the item, so the review of it
+/// still applies when the item moves to another file, or the code
+/// around it changes.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Anchor {
+    /// Kind of the item, eg. `fn` or `impl`
+    pub kind: String,
+    pub name: String,
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub digest: Vec<u8>,
+}
+
+/// Facts about reviewed Rust sources, collected automatically
+///
+/// Meant to help weigh a review, eg. a review of code full of `unsafe`
+/// is worth less than one of code without it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RustMetrics {
+    /// Number of `unsafe` blocks, functions, impls and traits
+    #[serde(rename = "unsafe-count")]
+    pub unsafe_count: u64,
+    /// Is a `build.rs` build script among the files
+    #[serde(rename = "build-script")]
+    pub build_script: bool,
+    /// Are procedural macros defined in the files
+    #[serde(rename = "proc-macro")]
+    pub proc_macro: bool,
+}
+
+impl RustMetrics {
+    pub fn add(&mut self, other: &RustMetrics) {
+        self.unsafe_count += other.unsafe_count;
+        self.build_script |= other.build_script;
+        self.proc_macro |= other.proc_macro;
+    }
+}
+
+/// Size of the reviewed files, collected automatically
+///
+/// Displays as eg. `12k LOC in 40 files (rust: 11k, markdown: 834)`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SizeMetrics {
+    pub files: u64,
+    /// Lines of code, not counting blank lines
+    pub lines: u64,
+    /// Lines of code by language, for the files of known languages
+    #[serde(
+        skip_serializing_if = "BTreeMap::is_empty",
+        default = "Default::default"
+    )]
+    pub languages: BTreeMap<String, u64>,
+}
+
+impl SizeMetrics {
+    pub fn add_file(&mut self, language: Option<&str>, lines: u64) {
+        self.files += 1;
+        self.lines += lines;
+        if let Some(language) = language {
+            *self.languages.entry(language.to_owned()).or_insert(0) += lines;
+        }
+    }
+}
+
+fn humanize_lines(lines: u64) -> String {
+    if lines < 10_000 {
+        lines.to_string()
+    } else {
+        format!("{}k", lines / 1000)
+    }
+}
+
+impl fmt::Display for SizeMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} LOC in {} files",
+            humanize_lines(self.lines),
+            self.files
+        )?;
+        if !self.languages.is_empty() {
+            let mut languages: Vec<_> = self.languages.iter().collect();
+            languages.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let languages: Vec<_> = languages
+                .into_iter()
+                .map(|(language, lines)| format!("{}: {}", language, humanize_lines(*lines)))
+                .collect();
+            write!(f, " ({})", languages.join(", "))?;
+        }
+        Ok(())
+    }
 }
 
 /// Body of a Code Review Proof
@@ -41,7 +207,7 @@ pub struct Code {
         serialize_with = "as_rfc3339_fixed",
         deserialize_with = "from_rfc3339_fixed"
     )]
-    date: chrono::DateTime<FixedOffset>,
+    pub(crate) date: chrono::DateTime<FixedOffset>,
     pub from: crate::PubId,
     #[serde(rename = "package")]
     pub package: proof::PackageInfo,
@@ -50,13 +216,47 @@ pub struct Code {
     review: super::Review,
     #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
     #[builder(default = "Default::default()")]
-    comment: String,
+    pub comment: String,
     #[serde(
         skip_serializing_if = "std::vec::Vec::is_empty",
         default = "std::vec::Vec::new"
     )]
     #[builder(default = "Default::default()")]
     pub files: Vec<File>,
+    /// Comments on the directories of `files`; see `Group`
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub groups: Vec<Group>,
+    /// Metrics of the Rust sources among `files`, if any
+    #[serde(skip_serializing_if = "Option::is_none", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub metrics: Option<RustMetrics>,
+    /// Size of `files`
+    #[serde(skip_serializing_if = "Option::is_none", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub size: Option<SizeMetrics>,
+    /// When the proof stops counting, if ever; see `Content::is_expired_at`
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        serialize_with = "as_rfc3339_fixed_opt",
+        deserialize_with = "from_rfc3339_fixed_opt"
+    )]
+    #[builder(default = "Default::default()")]
+    pub expires: Option<chrono::DateTime<FixedOffset>>,
+    /// Earlier proofs of the author this one replaces; see
+    /// `Content::supersedes`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub supersedes: Vec<proof::SupersededProof>,
+    /// Labels of what the proof is about or for, eg. `crypto` or
+    /// `unsafe-audit`; see `Content::tags`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
 }
 
 impl Code {
@@ -64,8 +264,22 @@ impl Code {
         let mut copy = self.clone();
         copy.review = draft.review;
         copy.comment = draft.comment;
+        copy.expires = draft.expires;
+        copy.tags = draft.tags;
+        // only the comments of the groups are editable
+        for group in &mut copy.groups {
+            if let Some(edited) = draft.groups.iter().find(|edited| edited.path == group.path) {
+                group.comment = edited.comment.clone();
+            }
+        }
         copy
     }
+
+    /// The group the file at `path` is in, if any
+    pub fn group_of(&self, path: &std::path::Path) -> Option<&Group> {
+        let dir = path.parent()?;
+        self.groups.iter().find(|group| group.path == dir)
+    }
 }
 
 /// Like `Code` but serializes for interactive editing
@@ -74,6 +288,25 @@ pub struct CodeDraft {
     review: super::Review,
     #[serde(default = "Default::default")]
     comment: String,
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    groups: Vec<GroupDraft>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        serialize_with = "as_rfc3339_fixed_opt",
+        deserialize_with = "from_rfc3339_fixed_opt"
+    )]
+    expires: Option<chrono::DateTime<FixedOffset>>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<String>,
+}
+
+/// Like `Group`, but with the comment always there to fill in
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct GroupDraft {
+    path: PathBuf,
+    #[serde(default = "Default::default")]
+    comment: String,
 }
 
 impl From<Code> for CodeDraft {
@@ -81,6 +314,16 @@ impl From<Code> for CodeDraft {
         CodeDraft {
             review: code.review,
             comment: code.comment,
+            groups: code
+                .groups
+                .into_iter()
+                .map(|group| GroupDraft {
+                    path: group.path,
+                    comment: group.comment,
+                })
+                .collect(),
+            expires: code.expires,
+            tags: code.tags,
         }
     }
 }
@@ -100,9 +343,15 @@ impl proof::ContentCommon for Code {
     }
 
     fn draft_title(&self) -> String {
+        let groups = if self.groups.is_empty() {
+            "".into()
+        } else {
+            format!(" in {} groups", self.groups.len())
+        };
         format!(
-            "Code Review of {} files of {} {}",
+            "Code Review of {} files{} of {} {}",
             self.files.len(),
+            groups,
             self.package.name,
             self.package.version
         )
@@ -117,7 +366,10 @@ impl super::Common for Code {
 
 impl Code {
     pub fn parse(s: &str) -> Result<Self> {
-        Ok(serde_yaml::from_str(&s)?)
+        let content: Self = serde_yaml::from_str(&s)?;
+        content.extensions.ensure_understood()?;
+        proof::ensure_valid_tags(&content.tags)?;
+        Ok(content)
     }
 
     pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
@@ -127,7 +379,9 @@ impl Code {
 
 impl CodeDraft {
     pub fn parse(s: &str) -> Result<Self> {
-        Ok(serde_yaml::from_str(&s)?)
+        let draft: Self = serde_yaml::from_str(&s)?;
+        proof::ensure_valid_tags(&draft.tags)?;
+        Ok(draft)
     }
 }
 
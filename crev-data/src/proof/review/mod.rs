@@ -1,5 +1,5 @@
 use crate::level::Level;
-use std::default::Default;
+use std::{default::Default, fmt};
 
 pub mod code;
 pub mod package;
@@ -26,6 +26,19 @@ impl Default for Rating {
     }
 }
 
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::Rating::*;
+        f.write_str(match self {
+            Dangerous => "dangerous",
+            Negative => "negative",
+            Neutral => "neutral",
+            Positive => "positive",
+            Strong => "strong",
+        })
+    }
+}
+
 /// Information about review result
 #[derive(Clone, Debug, Serialize, Deserialize, Builder, PartialEq, Eq)]
 pub struct Review {
@@ -2,7 +2,7 @@ use crate::{id, proof, Result};
 use chrono::{self, prelude::*};
 use crev_common::{
     self,
-    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+    serde::{as_rfc3339_fixed, as_rfc3339_fixed_opt, from_rfc3339_fixed, from_rfc3339_fixed_opt},
 };
 use serde_yaml;
 use std::{default::Default, fmt};
@@ -32,11 +32,42 @@ pub struct Package {
     pub from: crate::PubId,
     #[serde(rename = "package")]
     pub package: proof::PackageInfo,
+    /// Earlier, already reviewed version of the package; if set, only the
+    /// changes since it were reviewed
+    #[serde(
+        rename = "diff-base",
+        skip_serializing_if = "Option::is_none",
+        default = "Default::default"
+    )]
+    #[builder(default = "Default::default()")]
+    pub diff_base: Option<proof::PackageInfo>,
     #[builder(default = "Default::default()")]
     pub review: super::Review,
     #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
     #[builder(default = "Default::default()")]
     pub comment: String,
+    /// When the proof stops counting, if ever; see `Content::is_expired_at`
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        serialize_with = "as_rfc3339_fixed_opt",
+        deserialize_with = "from_rfc3339_fixed_opt"
+    )]
+    #[builder(default = "Default::default()")]
+    pub expires: Option<chrono::DateTime<FixedOffset>>,
+    /// Earlier proofs of the author this one replaces; see
+    /// `Content::supersedes`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub supersedes: Vec<proof::SupersededProof>,
+    /// Labels of what the proof is about or for, eg. `crypto` or
+    /// `unsafe-audit`; see `Content::tags`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
 }
 
 impl Package {
@@ -44,6 +75,8 @@ impl Package {
         let mut copy = self.clone();
         copy.review = draft.review;
         copy.comment = draft.comment;
+        copy.expires = draft.expires;
+        copy.tags = draft.tags;
         copy
     }
 }
@@ -54,6 +87,15 @@ pub struct PackageDraft {
     review: super::Review,
     #[serde(default = "Default::default")]
     comment: String,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        serialize_with = "as_rfc3339_fixed_opt",
+        deserialize_with = "from_rfc3339_fixed_opt"
+    )]
+    expires: Option<chrono::DateTime<FixedOffset>>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<String>,
 }
 
 impl From<Package> for PackageDraft {
@@ -61,6 +103,8 @@ impl From<Package> for PackageDraft {
         PackageDraft {
             review: package.review,
             comment: package.comment,
+            expires: package.expires,
+            tags: package.tags,
         }
     }
 }
@@ -81,10 +125,16 @@ impl proof::ContentCommon for Package {
     }
 
     fn draft_title(&self) -> String {
-        format!(
-            "Package Review of {} {}",
-            self.package.name, self.package.version
-        )
+        match self.diff_base {
+            Some(ref base) => format!(
+                "Package Diff Review of {} {} -> {}",
+                self.package.name, base.version, self.package.version
+            ),
+            None => format!(
+                "Package Review of {} {}",
+                self.package.name, self.package.version
+            ),
+        }
     }
 }
 
@@ -96,7 +146,10 @@ impl super::Common for Package {
 
 impl Package {
     pub fn parse(s: &str) -> Result<Self> {
-        Ok(serde_yaml::from_str(&s)?)
+        let content: Self = serde_yaml::from_str(&s)?;
+        content.extensions.ensure_understood()?;
+        proof::ensure_valid_tags(&content.tags)?;
+        Ok(content)
     }
 
     pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
@@ -106,7 +159,9 @@ impl Package {
 
 impl PackageDraft {
     pub fn parse(s: &str) -> Result<Self> {
-        Ok(serde_yaml::from_str(&s)?)
+        let draft: Self = serde_yaml::from_str(&s)?;
+        proof::ensure_valid_tags(&draft.tags)?;
+        Ok(draft)
     }
 }
 
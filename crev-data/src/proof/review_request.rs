@@ -0,0 +1,124 @@
+//! Review Request Proof - a maintainer asks for reviews of a revision
+//!
+//! Published like any other proof, so followers of the maintainer get it on
+//! their next fetch and find it queued up to review.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV REVIEW REQUEST-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV REVIEW REQUEST SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV REVIEW REQUEST-----";
+
+const CURRENT_REVIEW_REQUEST_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_REVIEW_REQUEST_PROOF_SERIALIZATION_VERSION
+}
+
+/// Body of a Review Request Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct ReviewRequest {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: crate::PubId,
+    /// The package, with the revision to review
+    pub package: proof::PackageInfo,
+    /// What to look at, and why
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl ReviewRequest {
+    pub fn apply_draft(&self, draft: ReviewRequestDraft) -> ReviewRequest {
+        let mut copy = self.clone();
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `ReviewRequest` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReviewRequestDraft {
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<ReviewRequest> for ReviewRequestDraft {
+    fn from(request: ReviewRequest) -> Self {
+        ReviewRequestDraft {
+            comment: request.comment,
+        }
+    }
+}
+
+impl fmt::Display for ReviewRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for ReviewRequestDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl ReviewRequest {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for ReviewRequest {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!(
+            "Review request for {} {}",
+            self.package.name, self.package.revision
+        )
+    }
+}
+
+impl ReviewRequest {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if content.package.name.is_empty() || content.package.revision.is_empty() {
+            bail!("Review Request Proof has to name the package and its revision");
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl ReviewRequestDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
@@ -0,0 +1,141 @@
+//! Revocation Proof - an author retracts some of their own proofs
+//!
+//! Eg. after finding out that the code they reviewed was malicious, or
+//! that they trusted the wrong id. The revoked proofs are referred to by
+//! their digest, and are ignored from then on, wherever they were
+//! published. Only the revocations by the author of a proof count for it.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_base64, as_rfc3339_fixed, from_base64, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV REVOCATION-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV REVOCATION SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV REVOCATION-----";
+
+const CURRENT_REVOCATION_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_REVOCATION_PROOF_SERIALIZATION_VERSION
+}
+
+/// A proof being revoked
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RevokedProof {
+    /// Digest of the proof, as in `Proof::digest`
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub digest: Vec<u8>,
+}
+
+/// Body of a Revocation Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct Revocation {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub from: crate::PubId,
+    pub proofs: Vec<RevokedProof>,
+    /// Why the proofs are revoked
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl Revocation {
+    pub fn apply_draft(&self, draft: RevocationDraft) -> Revocation {
+        let mut copy = self.clone();
+        copy.comment = draft.comment;
+        copy
+    }
+
+    /// Does this revoke the proof with `digest`
+    pub fn revokes(&self, digest: &[u8]) -> bool {
+        self.proofs.iter().any(|proof| proof.digest == digest)
+    }
+}
+
+/// Like `Revocation` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationDraft {
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<Revocation> for RevocationDraft {
+    fn from(revocation: Revocation) -> Self {
+        RevocationDraft {
+            comment: revocation.comment,
+        }
+    }
+}
+
+impl fmt::Display for Revocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for RevocationDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl Revocation {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for Revocation {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        match self.proofs.len() {
+            1 => format!(
+                "Revocation of proof {}",
+                crev_common::base64_encode(&self.proofs[0].digest)
+            ),
+            n => format!("Revocation of {} proofs", n),
+        }
+    }
+}
+
+impl Revocation {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if content.proofs.is_empty() {
+            bail!("Revocation Proof has to name the proofs it revokes");
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl RevocationDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
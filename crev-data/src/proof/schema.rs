@@ -0,0 +1,304 @@
+//! Checks of proof bodies and drafts, before deserializing them
+//!
+//! When serde can't deserialize a body, it tells about the first problem
+//! it runs into, often without saying where. `check_body` and
+//! `check_draft` look through the YAML for the mistakes made editing by
+//! hand - a field missing, a level misspelled, a digest or id that isn't
+//! base64, a date that isn't RFC 3339 - and report all of them, each with
+//! the path of its field, eg. `review.thoroughness` or `files[2].digest`.
+//!
+//! YAML syntax errors are left to the deserialization, which tells the
+//! line they're on.
+use crate::{
+    level::Level,
+    proof::{review::Rating, trust::TrustLevel, ProofType},
+    Result,
+};
+use serde_yaml::{self, Value};
+use std::fmt;
+
+/// A problem with one field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// Path of the field, eg. `files[2].digest`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.field, self.message)
+    }
+}
+
+/// The problems found by `check_body` or `check_draft`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub errors: Vec<FieldError>,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid proof content:")?;
+        for error in &self.errors {
+            write!(f, "\n* {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Any,
+    List,
+    Level,
+    TrustLevel,
+    Rating,
+    Base64,
+    Date,
+}
+
+/// A field, by its path, where `[]` stands for every item of a list
+///
+/// A required field is only reported missing when the field it's in is
+/// there.
+struct Rule {
+    path: &'static str,
+    kind: Kind,
+    required: bool,
+}
+
+const fn required(path: &'static str, kind: Kind) -> Rule {
+    Rule {
+        path,
+        kind,
+        required: true,
+    }
+}
+
+const fn optional(path: &'static str, kind: Kind) -> Rule {
+    Rule {
+        path,
+        kind,
+        required: false,
+    }
+}
+
+const COMMON_RULES: &[Rule] = &[
+    required("version", Kind::Any),
+    required("date", Kind::Date),
+    required("from", Kind::Any),
+    required("from.id-type", Kind::Any),
+    required("from.id", Kind::Base64),
+    required("from.url", Kind::Any),
+    optional("expires", Kind::Date),
+    optional("supersedes[].digest", Kind::Base64),
+];
+
+const TRUST_DIMENSION_RULES: &[Rule] = &[
+    optional("dimensions.reviews", Kind::TrustLevel),
+    optional("dimensions.trust", Kind::TrustLevel),
+    optional("dimensions.advisories", Kind::TrustLevel),
+];
+
+const TRUST_RULES: &[Rule] = &[
+    required("ids", Kind::List),
+    required("ids[].id", Kind::Base64),
+    required("trust", Kind::TrustLevel),
+];
+
+const PACKAGE_RULES: &[Rule] = &[
+    required("package", Kind::Any),
+    required("package.source", Kind::Any),
+    required("package.name", Kind::Any),
+    required("package.version", Kind::Any),
+    required("package.digest", Kind::Base64),
+];
+
+/// The review is flattened into the body of a Code Review Proof
+const CODE_RULES: &[Rule] = &[
+    required("thoroughness", Kind::Level),
+    required("understanding", Kind::Level),
+    required("rating", Kind::Rating),
+    optional("files", Kind::List),
+    required("files[].path", Kind::Any),
+    required("files[].digest", Kind::Base64),
+];
+
+const NESTED_REVIEW_RULES: &[Rule] = &[
+    required("review", Kind::Any),
+    required("review.thoroughness", Kind::Level),
+    required("review.understanding", Kind::Level),
+    required("review.rating", Kind::Rating),
+];
+
+const DIFF_BASE_RULES: &[Rule] = &[optional("diff-base.digest", Kind::Base64)];
+
+const ID_LINK_RULES: &[Rule] = &[
+    required("to", Kind::Any),
+    required("to.id", Kind::Base64),
+    required("level", Kind::TrustLevel),
+];
+
+const DRAFT_RULES: &[Rule] = &[optional("expires", Kind::Date)];
+
+const TRUST_DRAFT_RULES: &[Rule] = &[required("trust", Kind::TrustLevel)];
+
+const ID_LINK_DRAFT_RULES: &[Rule] = &[required("level", Kind::TrustLevel)];
+
+fn body_rules(type_: ProofType) -> Vec<&'static [Rule]> {
+    let mut rules = vec![COMMON_RULES];
+    match type_ {
+        ProofType::Trust => rules.extend(&[TRUST_RULES, TRUST_DIMENSION_RULES]),
+        ProofType::Code => rules.extend(&[PACKAGE_RULES, CODE_RULES]),
+        ProofType::Package => rules.extend(&[PACKAGE_RULES, NESTED_REVIEW_RULES, DIFF_BASE_RULES]),
+        ProofType::IdLink => rules.push(ID_LINK_RULES),
+        _ => {}
+    }
+    rules
+}
+
+fn draft_rules(type_: ProofType) -> Vec<&'static [Rule]> {
+    let mut rules = vec![DRAFT_RULES];
+    match type_ {
+        ProofType::Trust => rules.extend(&[TRUST_DRAFT_RULES, TRUST_DIMENSION_RULES]),
+        ProofType::Code | ProofType::Package => rules.push(NESTED_REVIEW_RULES),
+        ProofType::IdLink => rules.push(ID_LINK_DRAFT_RULES),
+        _ => {}
+    }
+    rules
+}
+
+/// Check the body of a proof of `type_`, as read from a proof file
+pub fn check_body(s: &str, type_: ProofType) -> Result<()> {
+    check(s, &body_rules(type_))
+}
+
+/// Check a draft of a proof of `type_`, as edited by the user
+pub fn check_draft(s: &str, type_: ProofType) -> Result<()> {
+    check(s, &draft_rules(type_))
+}
+
+fn check(s: &str, rules: &[&[Rule]]) -> Result<()> {
+    super::limits::check_no_aliases(s)?;
+    let doc: Value = match serde_yaml::from_str(s) {
+        Ok(doc) => doc,
+        Err(_) => return Ok(()),
+    };
+    let mut errors = vec![];
+    if !doc.is_mapping() {
+        errors.push(FieldError {
+            field: ".".into(),
+            message: "has to be a mapping of fields".into(),
+        });
+    } else {
+        for rule in rules.iter().flat_map(|rules| rules.iter()) {
+            check_rule(&doc, rule, &mut errors);
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaError { errors }.into())
+    }
+}
+
+fn check_rule(doc: &Value, rule: &Rule, errors: &mut Vec<FieldError>) {
+    let parts: Vec<_> = rule.path.split('.').collect();
+    let mut nodes = vec![(String::new(), doc)];
+    for (i, part) in parts.iter().enumerate() {
+        let last = i + 1 == parts.len();
+        let (key, each) = match part.strip_suffix("[]") {
+            Some(key) => (key, true),
+            None => (*part, false),
+        };
+        let mut next = vec![];
+        for (path, node) in nodes {
+            let field = if path.is_empty() {
+                key.to_owned()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            let value = match node.get(key) {
+                Some(Value::Null) | None => {
+                    if last && rule.required {
+                        errors.push(FieldError {
+                            field,
+                            message: "is missing".into(),
+                        });
+                    }
+                    continue;
+                }
+                Some(value) => value,
+            };
+            if each {
+                match value.as_sequence() {
+                    Some(items) => next.extend(
+                        items
+                            .iter()
+                            .enumerate()
+                            .map(|(i, item)| (format!("{}[{}]", field, i), item)),
+                    ),
+                    None => errors.push(FieldError {
+                        field,
+                        message: "has to be a list".into(),
+                    }),
+                }
+            } else if last {
+                if let Some(message) = check_kind(value, rule.kind) {
+                    errors.push(FieldError { field, message });
+                }
+            } else {
+                next.push((field, value));
+            }
+        }
+        nodes = next;
+    }
+}
+
+/// What's wrong with `value`, if anything, for a field of `kind`
+fn check_kind(value: &Value, kind: Kind) -> Option<String> {
+    let text = || match value {
+        Value::String(s) => s.clone(),
+        _ => serde_yaml::to_string(value)
+            .map(|s| s.trim_start_matches("---").trim().to_owned())
+            .unwrap_or_default(),
+    };
+    let is = |valid: bool, expected: &str| {
+        if valid {
+            None
+        } else {
+            Some(format!("is `{}`, but has to be {}", text(), expected))
+        }
+    };
+    match kind {
+        Kind::Any => None,
+        Kind::List => is(value.is_sequence(), "a list"),
+        Kind::Level => is(
+            serde_yaml::from_value::<Level>(value.clone()).is_ok(),
+            "one of none, low, medium, high",
+        ),
+        Kind::TrustLevel => is(
+            serde_yaml::from_value::<TrustLevel>(value.clone()).is_ok(),
+            "one of distrust, none, low, medium, high",
+        ),
+        Kind::Rating => is(
+            serde_yaml::from_value::<Rating>(value.clone()).is_ok(),
+            "one of dangerous, negative, neutral, positive, strong",
+        ),
+        Kind::Base64 => is(
+            value
+                .as_str()
+                .map_or(false, |s| crev_common::base64_decode(s).is_ok()),
+            "base64",
+        ),
+        Kind::Date => is(
+            value
+                .as_str()
+                .map_or(false, |s| chrono::DateTime::parse_from_rfc3339(s).is_ok()),
+            "an RFC 3339 date, eg. `2019-01-01T00:00:00+00:00`",
+        ),
+    }
+}
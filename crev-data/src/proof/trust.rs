@@ -2,7 +2,7 @@ use crate::{id, proof, Result};
 use chrono::{self, prelude::*};
 use crev_common::{
     self,
-    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+    serde::{as_rfc3339_fixed, as_rfc3339_fixed_opt, from_rfc3339_fixed, from_rfc3339_fixed_opt},
 };
 use serde_yaml;
 use std::fmt;
@@ -46,8 +46,9 @@ impl fmt::Display for TrustLevel {
     }
 }
 
-impl TrustLevel {
-    #[allow(unused)]
+impl std::str::FromStr for TrustLevel {
+    type Err = failure::Error;
+
     fn from_str(s: &str) -> Result<TrustLevel> {
         Ok(match s {
             "distrust" => TrustLevel::Distrust,
@@ -60,6 +61,65 @@ impl TrustLevel {
     }
 }
 
+/// Kind of proofs of an id that a Trust Proof can trust differently
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TrustDimension {
+    /// Code and Package Reviews vouching for the reviewed code
+    Reviews,
+    /// Trust Proofs, extending the WoT further
+    Trust,
+    /// Negative Code and Package Reviews, warning about the reviewed code
+    Advisories,
+}
+
+/// Levels of a Trust Proof overriding its `trust` for some dimensions
+///
+/// Eg. a skilled reviewer bad at judging others can be trusted with
+/// `trust: high` and `dimensions: { trust: low }`. Only the `trust` of
+/// the proof can distrust an id; `distrust` here is the same as `none`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrustDimensions {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reviews: Option<TrustLevel>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub trust: Option<TrustLevel>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub advisories: Option<TrustLevel>,
+}
+
+impl TrustDimensions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Level for `dimension`, with `trust` as the default
+    pub fn level_for(&self, dimension: TrustDimension, trust: TrustLevel) -> TrustLevel {
+        let level = match dimension {
+            TrustDimension::Reviews => self.reviews,
+            TrustDimension::Trust => self.trust,
+            TrustDimension::Advisories => self.advisories,
+        };
+        match level.unwrap_or(trust) {
+            TrustLevel::Distrust => TrustLevel::None,
+            level => level,
+        }
+    }
+}
+
+impl fmt::Display for TrustDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<_> = [
+            ("reviews", self.reviews),
+            ("trust", self.trust),
+            ("advisories", self.advisories),
+        ]
+        .iter()
+        .filter_map(|(name, level)| level.map(|level| format!("{}: {}", name, level)))
+        .collect();
+        f.write_str(&levels.join(", "))
+    }
+}
+
 /// Body of a Trust Proof
 #[derive(Clone, Debug, Builder, Serialize, Deserialize)]
 pub struct Trust {
@@ -75,33 +135,85 @@ pub struct Trust {
     pub ids: Vec<crate::PubId>,
     #[builder(default = "Default::default()")]
     pub trust: TrustLevel,
+    #[serde(skip_serializing_if = "TrustDimensions::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub dimensions: TrustDimensions,
     #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
     #[builder(default = "Default::default()")]
-    comment: String,
+    pub comment: String,
+    /// When the proof stops counting, if ever; see `Content::is_expired_at`
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        serialize_with = "as_rfc3339_fixed_opt",
+        deserialize_with = "from_rfc3339_fixed_opt"
+    )]
+    #[builder(default = "Default::default()")]
+    pub expires: Option<chrono::DateTime<FixedOffset>>,
+    /// Earlier proofs of the author this one replaces; see
+    /// `Content::supersedes`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub supersedes: Vec<proof::SupersededProof>,
+    /// Labels of what the proof is about or for, eg. `crypto` or
+    /// `unsafe-audit`; see `Content::tags`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[builder(default = "Default::default()")]
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
 }
 
 impl Trust {
     pub fn apply_draft(&self, draft: TrustDraft) -> Trust {
         let mut copy = self.clone();
         copy.trust = draft.trust;
+        copy.dimensions = draft.dimensions;
         copy.comment = draft.comment;
+        copy.expires = draft.expires;
+        copy.tags = draft.tags;
         copy
     }
+
+    /// Effective level of this trust for the proofs of `dimension`
+    ///
+    /// `none` when the ids are distrusted.
+    pub fn level_for(&self, dimension: TrustDimension) -> TrustLevel {
+        if self.trust == TrustLevel::Distrust {
+            return TrustLevel::None;
+        }
+        self.dimensions.level_for(dimension, self.trust)
+    }
 }
 
 /// Like `Trust` but serializes for interactive editing
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TrustDraft {
     pub trust: TrustLevel,
+    #[serde(skip_serializing_if = "TrustDimensions::is_empty", default)]
+    pub dimensions: TrustDimensions,
     #[serde(default = "Default::default")]
     comment: String,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        serialize_with = "as_rfc3339_fixed_opt",
+        deserialize_with = "from_rfc3339_fixed_opt"
+    )]
+    expires: Option<chrono::DateTime<FixedOffset>>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<String>,
 }
 
 impl From<Trust> for TrustDraft {
     fn from(trust: Trust) -> Self {
         TrustDraft {
             trust: trust.trust,
+            dimensions: trust.dimensions,
             comment: trust.comment,
+            expires: trust.expires,
+            tags: trust.tags,
         }
     }
 }
@@ -149,7 +261,10 @@ impl proof::ContentCommon for Trust {
 
 impl Trust {
     pub fn parse(s: &str) -> Result<Self> {
-        Ok(serde_yaml::from_str(&s)?)
+        let content: Self = serde_yaml::from_str(&s)?;
+        content.extensions.ensure_understood()?;
+        proof::ensure_valid_tags(&content.tags)?;
+        Ok(content)
     }
 
     pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
@@ -159,6 +274,8 @@ impl Trust {
 
 impl TrustDraft {
     pub fn parse(s: &str) -> Result<Self> {
-        Ok(serde_yaml::from_str(&s)?)
+        let draft: Self = serde_yaml::from_str(&s)?;
+        proof::ensure_valid_tags(&draft.tags)?;
+        Ok(draft)
     }
 }
@@ -0,0 +1,122 @@
+//! Trust Root Handover Proof - a package changing its trust root
+//!
+//! Signed by the current trust root of a package, it hands the role over
+//! to a new Id, eg. when the maintainer is replaced or rotates their key.
+//! Proofs of the old trust root made before the handover stay valid.
+use crate::{id, proof, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const BEGIN_BLOCK: &str = "-----BEGIN CREV TRUST ROOT HANDOVER-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN CREV TRUST ROOT HANDOVER SIGNATURE-----";
+const END_BLOCK: &str = "-----END CREV TRUST ROOT HANDOVER-----";
+
+const CURRENT_TRUST_ROOT_HANDOVER_PROOF_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_TRUST_ROOT_HANDOVER_PROOF_SERIALIZATION_VERSION
+}
+
+/// Body of a Trust Root Handover Proof
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct TrustRootHandover {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// The trust root handing over
+    pub from: crate::PubId,
+    /// The new trust root
+    pub to: crate::PubId,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+    #[serde(flatten)]
+    #[builder(default = "Default::default()")]
+    pub extensions: proof::Extensions,
+}
+
+impl TrustRootHandover {
+    pub fn apply_draft(&self, draft: TrustRootHandoverDraft) -> TrustRootHandover {
+        let mut copy = self.clone();
+        copy.comment = draft.comment;
+        copy
+    }
+}
+
+/// Like `TrustRootHandover` but serializes for interactive editing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrustRootHandoverDraft {
+    #[serde(default = "Default::default")]
+    comment: String,
+}
+
+impl From<TrustRootHandover> for TrustRootHandoverDraft {
+    fn from(handover: TrustRootHandover) -> Self {
+        TrustRootHandoverDraft {
+            comment: handover.comment,
+        }
+    }
+}
+
+impl fmt::Display for TrustRootHandover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl fmt::Display for TrustRootHandoverDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crev_common::serde::write_as_headerless_yaml(self, f)
+    }
+}
+
+impl TrustRootHandover {
+    pub(crate) const BEGIN_BLOCK: &'static str = BEGIN_BLOCK;
+    pub(crate) const BEGIN_SIGNATURE: &'static str = BEGIN_SIGNATURE;
+    pub(crate) const END_BLOCK: &'static str = END_BLOCK;
+}
+
+impl proof::ContentCommon for TrustRootHandover {
+    fn date(&self) -> &chrono::DateTime<FixedOffset> {
+        &self.date
+    }
+
+    fn author(&self) -> &crate::PubId {
+        &self.from
+    }
+
+    fn draft_title(&self) -> String {
+        format!("Handover of trust root to {}", self.to.id)
+    }
+}
+
+impl TrustRootHandover {
+    pub fn parse(s: &str) -> Result<Self> {
+        let content: Self = serde_yaml::from_str(s)?;
+        content.extensions.ensure_understood()?;
+        if content.to.id == content.from.id {
+            bail!("Trust root can't hand over to itself");
+        }
+        Ok(content)
+    }
+
+    pub fn sign_by(self, id: &id::OwnId) -> Result<proof::Proof> {
+        super::Content::from(self).sign_by(id)
+    }
+}
+
+impl TrustRootHandoverDraft {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
@@ -0,0 +1,121 @@
+//! Curated lists of reviewer ids, for bootstrapping a web of trust
+//!
+//! Someone well known in a community (the curator) publishes a signed
+//! list of ids worth following. A new user who imports the list gets
+//! useful verification results right away, before they build their own
+//! web of trust.
+//!
+//! Ids from an imported list are trusted only provisionally, at `low`
+//! level, and only locally: no trust proof is created, and any trust
+//! proof of the user overrides it.
+use crate::{id::OwnId, proof::canonical, proof::limits, util::armor, PubId, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const MARKERS: armor::Markers = armor::Markers {
+    begin: "-----BEGIN CREV SEED LIST-----",
+    begin_signature: "-----BEGIN CREV SEED LIST SIGNATURE-----",
+    end: "-----END CREV SEED LIST-----",
+};
+
+/// Maximum size of the body of a seed list
+pub const MAX_SEED_LIST_SIZE: usize = 1024 * 1024;
+
+const CURRENT_SEED_LIST_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_SEED_LIST_SERIALIZATION_VERSION
+}
+
+/// One reviewer on a seed list
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SeedEntry {
+    #[serde(flatten)]
+    pub id: PubId,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub comment: String,
+}
+
+impl From<PubId> for SeedEntry {
+    fn from(id: PubId) -> Self {
+        Self {
+            id,
+            comment: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct SeedList {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// The curator
+    pub from: PubId,
+    /// Title of the list, eg. "Reviewers of the foo ecosystem"
+    pub name: String,
+    pub ids: Vec<SeedEntry>,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+}
+
+impl SeedList {
+    pub fn sign_by(self, id: &OwnId) -> Result<SignedSeedList> {
+        if id.id.id != self.from.id {
+            bail!("Seed list can only be signed by its curator");
+        }
+        let body = canonical::to_canonical_body(&self)?;
+        let signature = id.sign(canonical::signed_encoding(&body)?.as_bytes());
+        Ok(SignedSeedList {
+            body,
+            signature: crev_common::base64_encode(&signature),
+            list: self,
+        })
+    }
+}
+
+/// A `SeedList` along with its body as written, signed over the
+/// `canonical::signed_encoding` of it
+#[derive(Clone, Debug)]
+pub struct SignedSeedList {
+    pub body: String,
+    pub signature: String,
+    pub list: SeedList,
+}
+
+impl SignedSeedList {
+    /// Parse a seed list and verify the signature of its curator
+    pub fn parse(s: &str) -> Result<Self> {
+        let (body, signature) = armor::parse(s, &MARKERS)?;
+        if body.len() > MAX_SEED_LIST_SIZE {
+            bail!("Seed list too long");
+        }
+        limits::check_structure(&body)?;
+
+        let list: SeedList = serde_yaml::from_str(&body)?;
+        canonical::verify_body(&list.from.id, &body, &signature)?;
+
+        Ok(Self {
+            body,
+            signature,
+            list,
+        })
+    }
+}
+
+impl fmt::Display for SignedSeedList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        armor::write(f, &MARKERS, &self.body, &self.signature)
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     proof::{self, Proof, Serialized},
     Result,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[test]
 pub fn signed_parse() -> Result<()> {
@@ -95,11 +95,17 @@ pub fn generate_id_and_proof() -> Result<(OwnId, Proof)> {
                 path: PathBuf::from("foo.x"),
                 digest: vec![1, 2, 3, 4],
                 digest_type: "sha256".into(),
+                anchors: vec![],
+                normalization: vec![],
+                kind: None,
             },
             proof::review::code::File {
                 path: PathBuf::from("foo.x"),
                 digest: vec![1, 2, 3, 4],
                 digest_type: "sha256".into(),
+                anchors: vec![],
+                normalization: vec![],
+                kind: None,
             },
         ])
         .build()
@@ -124,9 +130,765 @@ pub fn sign_proof_review() -> Result<()> {
 pub fn verify_works() -> Result<()> {
     let (_id, mut proof) = generate_id_and_proof()?;
 
-    proof.body += "\n";
+    proof.body = proof.body.replace("foo.x", "bar.x");
 
     assert!(proof.verify().is_err());
 
     Ok(())
 }
+
+#[test]
+pub fn serialized_verify_signature() -> Result<()> {
+    let (_id, proof) = generate_id_and_proof()?;
+
+    let serialized = proof::Serialized::parse(proof.to_string().as_bytes())?;
+    serialized[0].verify_signature()?;
+
+    let tampered = proof.to_string().replace("foo.x", "bar.x");
+    let serialized = proof::Serialized::parse(tampered.as_bytes())?;
+    assert!(serialized[0].verify_signature().is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn json_proof_round_trip() -> Result<()> {
+    let (_id, proof) = generate_id_and_proof()?;
+
+    let json = proof.to_string_in(proof::Format::Json)?;
+    assert!(json.starts_with(r#"{"type":"code","body":"#));
+    let twice = json.clone() + &json;
+    let parsed = Proof::parse_in(twice.as_bytes(), proof::Format::Json)?;
+    assert_eq!(parsed.len(), 2);
+    parsed[0].verify()?;
+    assert_eq!(parsed[0].digest, proof.digest);
+    assert_eq!(parsed[0].to_string(), proof.to_string());
+
+    let armored = parsed[1].to_string_in(proof::Format::Armor)?;
+    let back = Proof::parse(armored.as_bytes())?;
+    back[0].verify()?;
+    assert_eq!(back[0].to_string_in(proof::Format::Json)?, json);
+
+    let tampered = json.replace("foo.x", "bar.x");
+    let serialized = Serialized::parse_in(tampered.as_bytes(), proof::Format::Json)?;
+    assert!(serialized[0].verify_signature().is_err());
+    let unterminated = json.replace(r#"\n","signature""#, r#"","signature""#);
+    assert!(Serialized::parse_in(unterminated.as_bytes(), proof::Format::Json).is_err());
+    assert!("yaml".parse::<proof::Format>().is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn reproduce_proof() -> Result<()> {
+    let (_id, proof) = generate_id_and_proof()?;
+    let steps = proof.reproduce();
+    assert!(steps.iter().all(|step| step.error.is_none()));
+    assert_eq!(steps.last().unwrap().name, "signature");
+
+    let mut tampered = proof.clone();
+    tampered.body = format!("---\n{}\n", tampered.body);
+    let steps = tampered.reproduce();
+    let failed: Vec<_> = steps
+        .iter()
+        .filter(|step| step.error.is_some())
+        .map(|step| step.name)
+        .collect();
+    // still signed, as the signed encoding ignores the formatting
+    assert_eq!(failed, vec!["canonical form", "re-serialized content"]);
+
+    Ok(())
+}
+
+#[test]
+pub fn signature_survives_reserialization() -> Result<()> {
+    use crate::proof::canonical::signed_encoding;
+
+    assert_eq!(
+        signed_encoding("b: [1, \"x\"]\na:\n  d: ~\n  c: true\n")?,
+        r#"{"a":{"c":true,"d":null},"b":[1,"x"]}"#
+    );
+
+    let (_id, proof) = generate_id_and_proof()?;
+    let mapping: serde_yaml::Mapping = serde_yaml::from_str(&proof.body)?;
+    let mut entries: Vec<_> = mapping.into_iter().collect();
+    entries.reverse();
+    let reversed: serde_yaml::Mapping = entries.into_iter().collect();
+
+    let mut reserialized = proof.clone();
+    reserialized.body = serde_yaml::to_string(&reversed)?;
+    assert_ne!(reserialized.body, proof.body);
+    reserialized.verify()?;
+
+    Ok(())
+}
+
+#[test]
+pub fn canonical_body() -> Result<()> {
+    use crate::proof::canonical::{canonicalize, is_canonical};
+
+    assert_eq!(canonicalize("---\na: 1\r\nb: 2\n\n\n"), "a: 1\nb: 2\n");
+    assert_eq!(canonicalize("\n\na: 1"), "a: 1\n");
+    assert!(is_canonical("a: 1\nb: 2\n"));
+    assert!(!is_canonical("a: 1\nb: 2"));
+    // trailing whitespace of a scalar is part of it
+    let body = "comment: |\n  ends with spaces  \n  done\n";
+    assert!(is_canonical(body));
+    let comment: serde_yaml::Mapping = serde_yaml::from_str(&canonicalize(body))?;
+    assert_eq!(
+        comment[&"comment".into()],
+        serde_yaml::Value::from("ends with spaces  \ndone\n")
+    );
+
+    let (_id, proof) = generate_id_and_proof()?;
+    assert!(is_canonical(&proof.body));
+
+    Ok(())
+}
+
+// Signed body has to survive being written out and parsed back
+// byte-for-byte, and match what the current serializer would produce
+// for the parsed content.
+#[test]
+pub fn canonical_body_round_trip() -> Result<()> {
+    let (_id, proof) = generate_id_and_proof()?;
+
+    let parsed = Proof::parse(proof.to_string().as_bytes())?;
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].body, proof.body);
+    assert_eq!(parsed[0].digest, proof.digest);
+    parsed[0].verify()?;
+
+    let code = match parsed[0].content {
+        proof::Content::Code(ref code) => code,
+        _ => panic!("wrong proof type"),
+    };
+    assert_eq!(proof::canonical::to_canonical_body(code)?, proof.body);
+
+    // line endings converted during transport don't matter
+    let crlf = proof.to_string().replace("\n", "\r\n");
+    let parsed = Proof::parse(crlf.as_bytes())?;
+    assert_eq!(parsed[0].body, proof.body);
+    parsed[0].verify()?;
+
+    Ok(())
+}
+
+// Unknown fields are kept around, unless declared `critical`,
+// in which case the proof must be rejected.
+#[test]
+pub fn unknown_and_critical_fields() -> Result<()> {
+    let (_id, proof) = generate_id_and_proof()?;
+
+    let with_unknown = proof.body.clone() + "new-field: 7\n";
+    let code = proof::review::Code::parse(&with_unknown)?;
+    assert_eq!(
+        code.extensions.unknown.get("new-field"),
+        Some(&serde_yaml::Value::from(7))
+    );
+    assert_eq!(proof::canonical::to_canonical_body(&code)?, with_unknown);
+
+    let with_critical_known = proof.body.clone() + "critical:\n  - comment\n";
+    proof::review::Code::parse(&with_critical_known)?;
+
+    let with_critical_unknown = with_unknown + "critical:\n  - new-field\n";
+    assert!(proof::review::Code::parse(&with_critical_unknown).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn alias_bombs_are_rejected_unexpanded() -> Result<()> {
+    // every level refers to the one before ten times, so expanded it'd be
+    // 10^10 values
+    let mut body = "version: -1\na0: &a0 [x, x, x, x, x, x, x, x, x, x]\n".to_owned();
+    for i in 1..=10 {
+        let previous = format!("*a{}", i - 1);
+        body += &format!("a{}: &a{} [{}]\n", i, i, vec![previous; 10].join(", "));
+    }
+    let block = format!(
+        "-----BEGIN CODE REVIEW-----\n{}-----BEGIN CODE REVIEW SIGNATURE-----\nsig\n-----END CODE REVIEW-----\n",
+        body
+    );
+
+    let err = Proof::parse(block.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("aliases"));
+    assert!(Serialized::parse(block.as_bytes())?[0]
+        .verify_signature()
+        .is_err());
+    assert!(proof::Content::parse(&body, proof::ProofType::Code).is_err());
+    Ok(())
+}
+
+#[test]
+pub fn parse_limits() -> Result<()> {
+    use crate::proof::limits;
+
+    let (_id, proof) = generate_id_and_proof()?;
+
+    let too_deep = proof.body.clone() + "deep: " + &"[".repeat(20) + &"]".repeat(20) + "\n";
+    assert!(limits::check_body(&proof.body).is_ok());
+    assert!(limits::check_body(&too_deep).is_err());
+
+    let too_long_field = format!(
+        "{}extra: {}\n",
+        proof.body,
+        "x".repeat(limits::MAX_FIELD_LENGTH + 1)
+    );
+    assert!(limits::check_body(&too_long_field).is_err());
+
+    let mut too_large = proof.to_string();
+    while (too_large.len() as u64) <= limits::MAX_PROOF_FILE_SIZE {
+        too_large += "\n".repeat(1024 * 1024).as_str();
+    }
+    assert!(Proof::parse(too_large.as_bytes()).is_err());
+    assert!(Proof::parse(proof.to_string().as_bytes())?.len() == 1);
+
+    Ok(())
+}
+
+#[test]
+pub fn malformed_ids_and_signatures() -> Result<()> {
+    let (id, proof) = generate_id_and_proof()?;
+
+    assert!(crate::Id::crevid_from_str("").is_err());
+    assert!(crate::Id::crevid_from_str("AAAA").is_err());
+    assert!(crate::Id::crevid_from_str("not base64!").is_err());
+
+    let id = crate::Id::crevid_from_str(&id.id.id.to_string())?;
+    assert!(id.verify_signature(proof.body.as_bytes(), "").is_err());
+    assert!(id.verify_signature(proof.body.as_bytes(), "AAAA").is_err());
+    assert!(id.verify_signature(proof.body.as_bytes(), "!!!").is_err());
+    let signed = proof::canonical::signed_encoding(&proof.body)?;
+    id.verify_signature(signed.as_bytes(), proof.signature())?;
+
+    Ok(())
+}
+
+#[test]
+pub fn pub_id_bundle_round_trip() -> Result<()> {
+    use crate::id_bundle::{PubIdBundleBuilder, SignedPubIdBundle};
+
+    let id = OwnId::generate_for_git_url("https://github.com/someone/crev-proofs");
+    let signed = PubIdBundleBuilder::default()
+        .id(id.id.clone())
+        .name("Someone".into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&id)?;
+
+    let pasted = format!("Hi, here's my id:\n\n{}\n-- \nSomeone\n", signed);
+    let parsed = SignedPubIdBundle::parse(&pasted)?;
+    assert_eq!(parsed.bundle.id, id.id);
+    assert_eq!(parsed.bundle.name, "Someone");
+    assert_eq!(parsed.body, signed.body);
+
+    let tampered = signed.to_string().replace("Someone", "Someone Else");
+    assert!(SignedPubIdBundle::parse(&tampered).is_err());
+    // written back in another style, it's still the same bundle
+    let requoted = signed
+        .to_string()
+        .replace("name: Someone", "name: \"Someone\"");
+    assert_ne!(requoted, signed.to_string());
+    assert_eq!(SignedPubIdBundle::parse(&requoted)?.bundle.name, "Someone");
+
+    let other = OwnId::generate_for_git_url("https://github.com/other/crev-proofs");
+    assert!(parsed.bundle.sign_by(&other).is_err());
+    assert_eq!(id.id.id.fingerprint().len(), 39);
+
+    Ok(())
+}
+
+#[test]
+pub fn seed_list_round_trip() -> Result<()> {
+    use crate::seed_list::{SeedListBuilder, SignedSeedList};
+
+    let curator = OwnId::generate_for_git_url("https://github.com/curator/crev-proofs");
+    let a = OwnId::generate_for_git_url("https://github.com/a/crev-proofs");
+    let b = OwnId::generate_for_git_url("https://github.com/b/crev-proofs");
+
+    let signed = SeedListBuilder::default()
+        .from(curator.id.clone())
+        .name("Reviewers".into())
+        .ids(vec![a.id.clone().into(), b.id.clone().into()])
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&curator)?;
+
+    let parsed = SignedSeedList::parse(&signed.to_string())?;
+    assert_eq!(parsed.list.ids.len(), 2);
+    assert_eq!(parsed.list.ids[1].id, b.id);
+
+    let tampered = signed
+        .to_string()
+        .replace(&a.id.url.url, "https://github.com/evil/crev-proofs");
+    assert!(SignedSeedList::parse(&tampered).is_err());
+    assert!(parsed.list.sign_by(&a).is_err());
+
+    let requoted = signed
+        .to_string()
+        .replace("name: Reviewers", "name: 'Reviewers'");
+    assert_ne!(requoted, signed.to_string());
+    assert_eq!(SignedSeedList::parse(&requoted)?.list.name, "Reviewers");
+
+    Ok(())
+}
+
+#[test]
+pub fn verification_report_round_trip() -> Result<()> {
+    use crate::verification_report::{SignedVerificationReport, VerificationReportBuilder};
+
+    let author = OwnId::generate_for_git_url("https://github.com/author/crev-proofs");
+    let other = OwnId::generate_for_git_url("https://github.com/other/crev-proofs");
+    let package = proof::PackageInfo {
+        id: None,
+        source: "".into(),
+        name: "foo".into(),
+        version: "".into(),
+        digest: vec![1; 32],
+        digest_type: proof::default_digest_type(),
+        revision: "abcdef".into(),
+        revision_type: proof::default_revision_type(),
+    };
+
+    let signed = VerificationReportBuilder::default()
+        .from(author.id.clone())
+        .package(package)
+        .status("high".into())
+        .proofs(vec!["digest1".into(), "digest2".into()])
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&author)?;
+
+    let parsed = SignedVerificationReport::parse(&signed.to_string())?;
+    assert_eq!(parsed.body, signed.body);
+    assert_eq!(parsed.report.status, "high");
+    assert_eq!(parsed.report.proofs, vec!["digest1", "digest2"]);
+    assert_eq!(parsed.report.package.revision, "abcdef");
+
+    let tampered = signed.to_string().replace("status: high", "status: medium");
+    assert!(SignedVerificationReport::parse(&tampered).is_err());
+    assert!(parsed.report.sign_by(&other).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn project_identity() -> Result<()> {
+    use crate::proof::project::{canonical_url, project_id_for_url, ProjectBuilder};
+
+    let url = "https://github.com/dpc/crev";
+    for variant in &[
+        "https://github.com/dpc/crev",
+        "HTTPS://GitHub.com/dpc/crev/",
+        "https://github.com/dpc/crev.git",
+        " https://github.com/dpc/crev.git/ ",
+    ] {
+        assert_eq!(canonical_url(variant)?, url);
+    }
+    assert!(canonical_url("http://github.com/dpc/crev").is_err());
+    assert!(canonical_url("https:///dpc/crev").is_err());
+    assert_ne!(
+        project_id_for_url(url),
+        project_id_for_url("https://github.com/dpc/crev2")
+    );
+
+    let id = OwnId::generate_for_git_url("https://github.com/dpc/crev-proofs");
+    let proof = ProjectBuilder::default()
+        .from(id.id.clone())
+        .name("crev".into())
+        .url(url.into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&id)?;
+
+    let parsed = Proof::parse(proof.to_string().as_bytes())?;
+    assert_eq!(parsed.len(), 1);
+    parsed[0].verify()?;
+    match parsed[0].content {
+        proof::Content::Project(ref project) => {
+            assert_eq!(project.project_id(), project_id_for_url(url))
+        }
+        _ => panic!("wrong proof type"),
+    }
+
+    assert!(
+        proof::Project::parse(&proof.body.replace(url, "https://GitHub.com/dpc/crev")).is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn project_migration() -> Result<()> {
+    use crate::proof::{project::project_id_for_url, project_migration::ProjectMigrationBuilder};
+
+    let id = OwnId::generate_for_git_url("https://github.com/dpc/crev-proofs");
+    let old_id = project_id_for_url("https://github.com/dpc/crev");
+    let migration = |url: &str| -> Result<Proof> {
+        ProjectMigrationBuilder::default()
+            .from(id.id.clone())
+            .project(old_id.clone())
+            .url(url.into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(&id)
+    };
+
+    let proof = migration("https://github.com/crev-dev/crev")?;
+    let parsed = Proof::parse(proof.to_string().as_bytes())?;
+    parsed[0].verify()?;
+    match parsed[0].content {
+        proof::Content::ProjectMigration(ref migration) => assert_eq!(
+            migration.successor_id(),
+            project_id_for_url("https://github.com/crev-dev/crev")
+        ),
+        _ => panic!("wrong proof type"),
+    }
+
+    let to_itself = migration("https://github.com/dpc/crev")?;
+    assert!(Proof::parse(to_itself.to_string().as_bytes()).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn package_diff_review() -> Result<()> {
+    let id = OwnId::generate_for_git_url("https://github.com/dpc/crev-proofs");
+    let package = |version: &str, digest: Vec<u8>| proof::PackageInfo {
+        id: None,
+        source: "https://crates.io".into(),
+        name: "foo".into(),
+        version: version.into(),
+        digest,
+        digest_type: proof::default_digest_type(),
+        revision: "".into(),
+        revision_type: proof::default_revision_type(),
+    };
+
+    let full = id
+        .id
+        .create_package_review_proof(package("1.0.0", vec![1; 32]), Default::default(), "".into())?
+        .sign_by(&id)?;
+    assert!(!full.body.contains("diff-base"));
+
+    let diff = proof::review::PackageBuilder::default()
+        .from(id.id.clone())
+        .package(package("1.1.0", vec![2; 32]))
+        .diff_base(Some(package("1.0.0", vec![1; 32])))
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&id)?;
+
+    let parsed = Proof::parse(diff.to_string().as_bytes())?;
+    parsed[0].verify()?;
+    match parsed[0].content {
+        proof::Content::Package(ref review) => {
+            let base = review.diff_base.as_ref().expect("diff base");
+            assert_eq!(base.version, "1.0.0");
+            assert_eq!(base.digest, vec![1; 32]);
+            assert_eq!(review.package.version, "1.1.0");
+        }
+        _ => panic!("wrong proof type"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn trust_root_handover() -> Result<()> {
+    use crate::proof::trust_root_handover::TrustRootHandoverBuilder;
+
+    let old_root = OwnId::generate_for_git_url("https://github.com/dpc/crev-proofs");
+    let new_root = OwnId::generate_for_git_url("https://github.com/crev-dev/crev-proofs");
+    let handover = |to: &OwnId| -> Result<Proof> {
+        TrustRootHandoverBuilder::default()
+            .from(old_root.id.clone())
+            .to(to.id.clone())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(&old_root)
+    };
+
+    let proof = handover(&new_root)?;
+    let parsed = Proof::parse(proof.to_string().as_bytes())?;
+    parsed[0].verify()?;
+    match parsed[0].content {
+        proof::Content::TrustRootHandover(ref handover) => {
+            assert_eq!(handover.to.id, new_root.id.id)
+        }
+        _ => panic!("wrong proof type"),
+    }
+
+    let to_itself = handover(&old_root)?;
+    assert!(Proof::parse(to_itself.to_string().as_bytes()).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn encrypted_proof_round_trip() -> Result<()> {
+    use crate::encrypted::{EncryptedProof, SignedEncryptedProof};
+
+    let author = OwnId::generate_for_git_url("https://example.com/author");
+    let colleague = OwnId::generate_for_git_url("https://example.com/colleague");
+    let outsider = OwnId::generate_for_git_url("https://example.com/outsider");
+
+    let proof = author
+        .create_trust_proof(vec![outsider.id.clone()], proof::trust::TrustLevel::High)?
+        .sign_by(&author)?;
+    let sealed = EncryptedProof::seal(&proof, &author, std::slice::from_ref(&colleague.id.id))?;
+    assert!(!sealed.body.contains(&outsider.id.id.to_string()));
+
+    let parsed = SignedEncryptedProof::parse(&sealed.to_string())?;
+    assert!(parsed.encrypted.is_addressed_to(&author.id.id));
+    assert!(parsed.encrypted.is_addressed_to(&colleague.id.id));
+    assert!(!parsed.encrypted.is_addressed_to(&outsider.id.id));
+    assert_eq!(parsed.open(&colleague)?.digest, proof.digest);
+    assert_eq!(parsed.open(&author)?.digest, proof.digest);
+    assert!(parsed.open(&outsider).is_err());
+
+    let forged = sealed
+        .to_string()
+        .replace(&author.id.id.to_string(), &outsider.id.id.to_string());
+    assert!(SignedEncryptedProof::parse(&forged).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn unsigned_proofs_round_trip() -> Result<()> {
+    let (id, proof) = generate_id_and_proof()?;
+    let other = OwnId::generate_for_git_url("https://example.com/other");
+    let trust = proof::Content::from(
+        id.create_trust_proof(vec![other.id.clone()], proof::trust::TrustLevel::Medium)?,
+    );
+
+    let text = format!(
+        "{}\n{}",
+        proof.content.to_unsigned_string(),
+        trust.to_unsigned_string()
+    );
+    let parsed = proof::Content::parse_unsigned(text.as_bytes())?;
+    assert_eq!(parsed.len(), 2);
+    // signing a parsed draft gives the same proof as signing the original
+    assert_eq!(parsed[0].sign_by(&id)?.digest, proof.digest);
+    assert_eq!(parsed[1].sign_by(&id)?.digest, trust.sign_by(&id)?.digest);
+
+    assert!(proof::Content::parse_unsigned(proof.to_string().as_bytes()).is_err());
+    let truncated = trust.to_unsigned_string();
+    let truncated = &truncated[..truncated.len() / 2];
+    assert!(proof::Content::parse_unsigned(truncated.as_bytes()).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn build_attestation() -> Result<()> {
+    use crate::proof::build_attestation::{Artifact, BuildAttestationBuilder};
+
+    let id = OwnId::generate_for_git_url("https://example.com/builder");
+    let attestation = |revision: &str, artifact_digest: Vec<u8>| -> Result<Proof> {
+        BuildAttestationBuilder::default()
+            .from(id.id.clone())
+            .package(proof::PackageInfo {
+                id: None,
+                source: "https://example.com/foo".into(),
+                name: "foo".into(),
+                version: "1.0.0".into(),
+                digest: vec![1, 2, 3],
+                digest_type: proof::default_digest_type(),
+                revision: revision.into(),
+                revision_type: proof::default_revision_type(),
+            })
+            .artifact(Artifact {
+                name: "foo-x86_64-linux".into(),
+                digest: artifact_digest,
+                digest_type: proof::default_digest_type(),
+            })
+            .environment("cargo build --release, rustc 1.31.0".into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(&id)
+    };
+
+    let proof = attestation("abcdef", vec![4, 5, 6])?;
+    let parsed = Proof::parse(proof.to_string().as_bytes())?;
+    assert_eq!(parsed.len(), 1);
+    parsed[0].verify()?;
+    match parsed[0].content {
+        proof::Content::BuildAttestation(ref attestation) => {
+            assert_eq!(attestation.artifact.digest, vec![4, 5, 6]);
+            assert_eq!(attestation.package.revision, "abcdef");
+        }
+        _ => panic!("wrong proof type"),
+    }
+
+    // the revision and the artifact digest are what's attested
+    assert!(Proof::parse(attestation("", vec![4, 5, 6])?.to_string().as_bytes()).is_err());
+    assert!(Proof::parse(attestation("abcdef", vec![])?.to_string().as_bytes()).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn grouped_code_review_draft() -> Result<()> {
+    use crate::proof::review::code::{File, Group};
+
+    let id = OwnId::generate_for_git_url("https://example.com/reviewer");
+    let file = |path: &str| File {
+        path: PathBuf::from(path),
+        digest: vec![1, 2, 3, 4],
+        digest_type: proof::default_digest_type(),
+        anchors: vec![],
+        normalization: vec![],
+        kind: None,
+    };
+    let group = |path: &str| Group {
+        path: PathBuf::from(path),
+        comment: "".into(),
+    };
+    let review = proof::Content::from(
+        proof::review::CodeBuilder::default()
+            .from(id.id.clone())
+            .package(proof::PackageInfo {
+                id: None,
+                source: "https://example.com/foo".into(),
+                name: "foo".into(),
+                version: "1.0.0".into(),
+                digest: vec![],
+                digest_type: proof::default_digest_type(),
+                revision: "".into(),
+                revision_type: proof::default_revision_type(),
+            })
+            .files(vec![file("src/lib.rs"), file("src/net/tcp.rs")])
+            .groups(vec![group("src"), group("src/net")])
+            .build()
+            .map_err(|e| format_err!("{}", e))?,
+    );
+
+    let draft = review.to_draft_string().replace(
+        "path: src/net\n    comment: \"\"",
+        "path: src/net\n    comment: sockets look fine",
+    );
+    assert!(draft.contains("sockets look fine"));
+    let draft = draft.replace("path: src\n", "path: elsewhere\n");
+    let edited = match proof::Content::parse_draft(&review, &draft)? {
+        proof::Content::Code(code) => code,
+        _ => bail!("not a code review"),
+    };
+    // group paths stay as created; only their comments are taken
+    assert_eq!(edited.groups.len(), 2);
+    assert_eq!(
+        edited.group_of(Path::new("src/lib.rs")),
+        Some(&group("src"))
+    );
+    assert_eq!(
+        edited
+            .group_of(Path::new("src/net/tcp.rs"))
+            .map(|group| &group.comment[..]),
+        Some("sockets look fine")
+    );
+    assert_eq!(edited.group_of(Path::new("README.md")), None);
+
+    let proof = proof::Content::from(edited).sign_by(&id)?;
+    let parsed = Proof::parse(proof.to_string().as_bytes())?;
+    match parsed[0].content {
+        proof::Content::Code(ref code) => assert_eq!(code.groups[1].comment, "sockets look fine"),
+        _ => bail!("not a code review"),
+    }
+    Ok(())
+}
+
+#[test]
+pub fn trust_level_from_str() -> Result<()> {
+    use crate::proof::trust::TrustLevel;
+
+    for level in &[
+        TrustLevel::Distrust,
+        TrustLevel::None,
+        TrustLevel::Low,
+        TrustLevel::Medium,
+        TrustLevel::High,
+    ] {
+        assert_eq!(level.to_string().parse::<TrustLevel>()?, *level);
+    }
+    assert!("very high".parse::<TrustLevel>().is_err());
+    Ok(())
+}
+
+#[test]
+pub fn parse_all_keeps_proofs_before_garbage() -> Result<()> {
+    let (_id, proof) = generate_id_and_proof()?;
+    let proof = proof.to_string();
+    let lines = proof.lines().count();
+
+    let appended = proof.clone() + "\n" + &proof + "garbage\n";
+    let mut parsed = proof::parse_all(appended.as_bytes());
+    for _ in 0..2 {
+        parsed
+            .next()
+            .ok_or_else(|| format_err!("proof missing"))??
+            .verify_signature()?;
+    }
+    let garbage = match parsed.next() {
+        Some(Err(e)) => e.to_string(),
+        _ => bail!("garbage not reported"),
+    };
+    assert!(garbage.starts_with(&format!("Line {}:", 2 * lines + 2)));
+    assert!(parsed.next().is_none());
+    assert!(Serialized::parse(appended.as_bytes()).is_err());
+
+    let cut_short = proof.clone() + &proof[..proof.len() / 2];
+    let parsed: Vec<_> = proof::parse_all(cut_short.as_bytes()).collect();
+    assert_eq!(parsed.len(), 2);
+    assert!(parsed[0].is_ok());
+    match parsed[1] {
+        Err(ref e) => assert!(e
+            .to_string()
+            .ends_with(&format!("beginning on line {}", lines + 1))),
+        Ok(_) => bail!("proof cut short not reported"),
+    }
+    Ok(())
+}
+
+#[test]
+pub fn schema_errors_point_to_fields() -> Result<()> {
+    use crate::proof::{schema::SchemaError, Content, ProofType};
+
+    let (_id, proof) = generate_id_and_proof()?;
+    let fields = |err: failure::Error| -> Vec<String> {
+        err.downcast::<SchemaError>()
+            .map(|err| err.errors.into_iter().map(|e| e.field).collect())
+            .unwrap_or_default()
+    };
+
+    let draft = "review:\n  thoroughness: extreme\n  rating: positive\nexpires: soon\n";
+    let err = Content::parse_draft(&proof.content, draft).unwrap_err();
+    assert_eq!(
+        fields(err),
+        vec!["expires", "review.thoroughness", "review.understanding"]
+    );
+
+    let date_line = proof
+        .body
+        .lines()
+        .find(|line| line.starts_with("date:"))
+        .ok_or_else(|| format_err!("no date"))?;
+    let body = proof
+        .body
+        .replace(date_line, "date: yesterday")
+        .replace("rating: positive", "rating: great");
+    let body = body.replacen("digest: AQIDBA", "digest: \"%%\"", 1);
+    let err = Serialized {
+        body,
+        signature: proof.signature.clone(),
+        type_: ProofType::Code,
+    }
+    .to_parsed()
+    .unwrap_err();
+    assert_eq!(fields(err), vec!["date", "rating", "files[0].digest"]);
+
+    Content::parse(&proof.body, ProofType::Code)?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! Text blocks with a signed body, like id bundles and seed lists
+//!
+//! ```text
+//! -----BEGIN <KIND>-----
+//! <body>
+//! -----BEGIN <KIND> SIGNATURE-----
+//! <signature>
+//! -----END <KIND>-----
+//! ```
+//!
+//! Unlike proofs, there is exactly one block to find, optionally
+//! surrounded by other text (eg. when pasted into an email).
+use crate::{proof::limits, Result};
+use std::fmt;
+
+pub(crate) struct Markers {
+    pub begin: &'static str,
+    pub begin_signature: &'static str,
+    pub end: &'static str,
+}
+
+/// Find the block in `s`, and return its body and signature
+///
+/// The signature is not verified here.
+pub(crate) fn parse(s: &str, markers: &Markers) -> Result<(String, String)> {
+    if s.len() as u64 > limits::MAX_PROOF_FILE_SIZE {
+        bail!("Input too large");
+    }
+
+    let mut lines = s.lines().map(|line| line.trim_end_matches('\r'));
+    if lines.find(|line| line.trim() == markers.begin).is_none() {
+        bail!("`{}` not found", markers.begin);
+    }
+
+    let mut body = String::new();
+    loop {
+        match lines.next() {
+            Some(line) if line.trim() == markers.begin_signature => break,
+            Some(line) => {
+                body += line;
+                body += "\n";
+            }
+            None => bail!(
+                "Unexpected EOF while looking for `{}`",
+                markers.begin_signature
+            ),
+        }
+    }
+
+    let mut signature = String::new();
+    loop {
+        match lines.next() {
+            Some(line) if line.trim() == markers.end => break,
+            Some(line) => signature += line.trim(),
+            None => bail!("Unexpected EOF while looking for `{}`", markers.end),
+        }
+    }
+
+    if signature.len() > limits::MAX_SIGNATURE_SIZE {
+        bail!("Signature too long");
+    }
+    Ok((body, signature))
+}
+
+pub(crate) fn write(
+    f: &mut fmt::Formatter<'_>,
+    markers: &Markers,
+    body: &str,
+    signature: &str,
+) -> fmt::Result {
+    writeln!(f, "{}", markers.begin)?;
+    f.write_str(body)?;
+    writeln!(f, "{}", markers.begin_signature)?;
+    writeln!(f, "{}", signature)?;
+    writeln!(f, "{}", markers.end)?;
+    Ok(())
+}
@@ -1,3 +1,5 @@
+pub(crate) mod armor;
+
 use rand::{self, Rng};
 
 pub fn random_id_str() -> String {
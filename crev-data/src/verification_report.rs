@@ -0,0 +1,98 @@
+//! Verification reports - why a revision of a package was accepted
+//!
+//! A signed statement of what verifying a package at a source revision
+//! found, listing the digests of the proofs the result relied on. Kept
+//! along with those proofs (see `crev evidence`), it lets the decision be
+//! audited long after the web of trust changed.
+use crate::{id::OwnId, proof, proof::canonical, proof::limits, util::armor, PubId, Result};
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::fmt;
+
+const MARKERS: armor::Markers = armor::Markers {
+    begin: "-----BEGIN CREV VERIFICATION REPORT-----",
+    begin_signature: "-----BEGIN CREV VERIFICATION REPORT SIGNATURE-----",
+    end: "-----END CREV VERIFICATION REPORT-----",
+};
+
+const CURRENT_VERIFICATION_REPORT_SERIALIZATION_VERSION: i64 = -1;
+
+fn cur_version() -> i64 {
+    CURRENT_VERIFICATION_REPORT_SERIALIZATION_VERSION
+}
+
+#[derive(Clone, Debug, Builder, Serialize, Deserialize)]
+pub struct VerificationReport {
+    #[builder(default = "cur_version()")]
+    version: i64,
+    #[builder(default = "crev_common::now()")]
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// Who verified the package
+    pub from: PubId,
+    /// The package, with the revision and digest verified
+    pub package: proof::PackageInfo,
+    /// Result of the verification, eg. `high`
+    pub status: String,
+    /// Base64 digests of the proofs the result relied on
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub proofs: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    #[builder(default = "Default::default()")]
+    pub comment: String,
+}
+
+impl VerificationReport {
+    pub fn sign_by(self, id: &OwnId) -> Result<SignedVerificationReport> {
+        if id.id.id != self.from.id {
+            bail!("Verification report can only be signed by its author");
+        }
+        let body = canonical::to_canonical_body(&self)?;
+        let signature = id.sign(canonical::signed_encoding(&body)?.as_bytes());
+        Ok(SignedVerificationReport {
+            body,
+            signature: crev_common::base64_encode(&signature),
+            report: self,
+        })
+    }
+}
+
+/// A `VerificationReport` along with its body as written, signed over the
+/// `canonical::signed_encoding` of it
+#[derive(Clone, Debug)]
+pub struct SignedVerificationReport {
+    pub body: String,
+    pub signature: String,
+    pub report: VerificationReport,
+}
+
+impl SignedVerificationReport {
+    /// Parse a verification report and verify the signature of its author
+    pub fn parse(s: &str) -> Result<Self> {
+        let (body, signature) = armor::parse(s, &MARKERS)?;
+        limits::check_structure(&body)?;
+
+        let report: VerificationReport = serde_yaml::from_str(&body)?;
+        canonical::verify_body(&report.from.id, &body, &signature)?;
+
+        Ok(Self {
+            body,
+            signature,
+            report,
+        })
+    }
+}
+
+impl fmt::Display for SignedVerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        armor::write(f, &MARKERS, &self.body, &self.signature)
+    }
+}
@@ -0,0 +1,72 @@
+//! `crev annotate` - a file with what covers it in the margin
+//!
+//! The header lists the reviews of the current content of the file, and
+//! the advisories against its package, ie. the negative and dangerous
+//! Package Reviews of it. In Rust sources, each item with reviews of its
+//! own (see `rust_anchors`) gets its status in the margin of its lines.
+use crate::{rust_anchors::Region, VerificationStatus};
+use std::{fmt, path::PathBuf};
+
+const MARGIN_WIDTH: usize = 9;
+
+/// A region of a file, verified by the reviews of its anchor
+pub struct RegionStatus {
+    pub region: Region,
+    pub status: VerificationStatus,
+}
+
+/// A file along with what covers it; see `Repo::annotate`
+pub struct Annotation {
+    /// Relative to the root of the scope
+    pub path: PathBuf,
+    pub status: VerificationStatus,
+    /// One line about each review of the file, most trusted first
+    pub reviews: Vec<String>,
+    /// One line about each advisory against the package
+    pub advisories: Vec<String>,
+    /// The regions with reviews of their own, in order
+    pub regions: Vec<RegionStatus>,
+    pub source: String,
+}
+
+impl Annotation {
+    /// Margin of line `line` (counting from 1)
+    fn margin(&self, line: usize) -> String {
+        match self
+            .regions
+            .iter()
+            .find(|r| r.region.first_line <= line && line <= r.region.last_line)
+        {
+            Some(r) if r.region.first_line == line => r.status.to_string(),
+            Some(_) => ":".into(),
+            None => "".into(),
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}", self.path.display(), self.status)?;
+        if self.reviews.is_empty() {
+            writeln!(f, "  no reviews of the current content")?;
+        }
+        for review in &self.reviews {
+            writeln!(f, "  review: {}", review)?;
+        }
+        for advisory in &self.advisories {
+            writeln!(f, "  advisory: {}", advisory)?;
+        }
+        writeln!(f)?;
+        for (i, line) in self.source.lines().enumerate() {
+            writeln!(
+                f,
+                "{:>5} {:<width$}| {}",
+                i + 1,
+                self.margin(i + 1),
+                line,
+                width = MARGIN_WIDTH
+            )?;
+        }
+        Ok(())
+    }
+}
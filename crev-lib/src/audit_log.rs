@@ -0,0 +1,212 @@
+//! `crev audit-log` - what this installation has done, and attested to
+//!
+//! Every id created or revoked, proof signed, trust granted and remote
+//! added is appended to the log, one JSON entry per line. Entries are
+//! never rewritten or removed. Each one holds the digest of the line
+//! before it, so a line changed or dropped later breaks the chain; and
+//! entries made while an own id is unlocked are signed by it. A signed
+//! entry covers the unsigned ones before it, through the chain.
+use crate::prelude::*;
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use crev_data::{proof::trust::TrustLevel, Id, OwnId};
+use serde_json;
+use std::{
+    fmt, fs,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Something done
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Action {
+    IdCreated {
+        id: Id,
+    },
+    IdRevoked {
+        id: Id,
+    },
+    ProofSigned {
+        digest: String,
+        #[serde(rename = "proof-type")]
+        proof_type: String,
+    },
+    TrustGranted {
+        digest: String,
+        ids: Vec<Id>,
+        level: TrustLevel,
+    },
+    RemoteAdded {
+        url: String,
+    },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::IdCreated { id } => write!(f, "created id {}", id),
+            Action::IdRevoked { id } => write!(f, "revoked id {}", id),
+            Action::ProofSigned { digest, proof_type } => {
+                write!(f, "signed {} proof {}", proof_type, digest)
+            }
+            Action::TrustGranted { digest, ids, level } => {
+                write!(f, "trusted at {} level (proof {}):", level, digest)?;
+                for id in ids {
+                    write!(f, " {}", id)?;
+                }
+                Ok(())
+            }
+            Action::RemoteAdded { url } => write!(f, "added remote {}", url),
+        }
+    }
+}
+
+/// An entry of the log
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    #[serde(flatten)]
+    pub action: Action,
+    /// Digest of the previous line; empty for the first one
+    pub prev: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub by: Option<Id>,
+    /// Signature of `by` over the entry with an empty `signature`
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub signature: String,
+}
+
+impl Entry {
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signature = String::new();
+        Ok(serde_json::to_vec(&unsigned)?)
+    }
+
+    fn verify(&self) -> Result<()> {
+        match self.by {
+            Some(ref by) => by.verify_signature(&self.signed_bytes()?, &self.signature),
+            None => Ok(()),
+        }
+    }
+}
+
+fn line_digest(line: &str) -> String {
+    crev_common::base64_encode(&crev_common::blake2b256sum(line.as_bytes()))
+}
+
+/// The audit log of a `Local`
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: &Path) -> Self {
+        AuditLog {
+            path: path.to_owned(),
+        }
+    }
+
+    /// Append `action`, signed by `by` if given
+    ///
+    /// A last line left incomplete, by a crash while appending, is dropped
+    /// first, so the log never has garbage in the middle.
+    pub fn append(&self, action: Action, by: Option<&OwnId>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let complete_len = content.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        if complete_len < content.len() {
+            eprintln!(
+                "Dropping an incomplete last entry of {}",
+                self.path.display()
+            );
+            file.set_len(complete_len as u64)?;
+        }
+        let prev = content[..complete_len]
+            .lines()
+            .last()
+            .map(line_digest)
+            .unwrap_or_default();
+
+        let mut entry = Entry {
+            date: crev_common::now(),
+            action,
+            prev,
+            by: by.map(|by| by.id.id.clone()),
+            signature: String::new(),
+        };
+        if let Some(by) = by {
+            entry.signature = crev_common::base64_encode(&by.sign(&entry.signed_bytes()?));
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        file.seek(SeekFrom::Start(complete_len as u64))?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// All the entries, oldest first
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        Ok(self
+            .lines()?
+            .iter()
+            .map(|line| serde_json::from_str(line))
+            .collect::<std::result::Result<_, _>>()?)
+    }
+
+    /// Problems with the log: entries that can't be parsed, don't follow
+    /// the line before them, or aren't signed by their `by`
+    ///
+    /// Entries are numbered from 1.
+    pub fn check(&self) -> Result<Vec<String>> {
+        let mut problems = vec![];
+        let mut prev = String::new();
+        for (i, line) in self.lines()?.iter().enumerate() {
+            let number = i + 1;
+            match serde_json::from_str::<Entry>(line) {
+                Ok(entry) => {
+                    if entry.prev != prev {
+                        problems.push(format!("entry {} doesn't follow the one before", number));
+                    }
+                    if let Err(e) = entry.verify() {
+                        problems.push(format!("entry {} has an invalid signature: {}", number, e));
+                    }
+                }
+                Err(e) => problems.push(format!("entry {} can't be parsed: {}", number, e)),
+            }
+            prev = line_digest(line);
+        }
+        Ok(problems)
+    }
+
+    /// The complete lines of the log
+    fn lines(&self) -> Result<Vec<String>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let content = crev_common::read_file_to_string(&self.path)?;
+        let complete_len = content.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Ok(content[..complete_len]
+            .lines()
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+}
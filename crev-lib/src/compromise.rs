@@ -0,0 +1,68 @@
+//! `crev id compromise` - what to do when an own id's key leaked
+//!
+//! `Local::respond_to_compromise` does what can be done right away:
+//!
+//! * signs, with the compromised id while it still can, a Revocation Proof
+//!   of its proofs, so copies of them published elsewhere stop counting,
+//! * marks the id revoked in `ids.yaml` of the proof repository (see
+//!   `id_directory`), so everyone fetching it ignores all of the proofs of
+//!   the id from then on - including the ones forged with the leaked key,
+//!   whichever repository they are fetched from - and refuses to unlock it,
+//! * if a `backup-id` is configured, has it re-sign the Trust Proofs of the
+//!   compromised id still in effect, so the web of trust of the user
+//!   carries over, and makes it the current id.
+//!
+//! The rest is up to the user, as listed by `CompromiseResponse::checklist`.
+use crev_data::{proof, Id};
+
+/// What `Local::respond_to_compromise` did
+#[derive(Debug, Clone)]
+pub struct CompromiseResponse {
+    /// The compromised id
+    pub id: Id,
+    /// Revocation of its proofs; none if it had no proofs to revoke
+    pub revocation: Option<proof::Proof>,
+    /// Number of proofs revoked
+    pub revoked: usize,
+    /// The backup id, now current, if one is configured
+    pub backup_id: Option<Id>,
+    /// Trust Proofs of the compromised id, re-signed by the backup id
+    pub resigned: Vec<proof::Proof>,
+}
+
+impl CompromiseResponse {
+    /// Steps left to the user, given whether the proof repository was
+    /// `pushed` already
+    pub fn checklist(&self, pushed: bool) -> Vec<String> {
+        let mut steps = vec![];
+        if !pushed {
+            steps.push("Push the proof repository: `crev git push`".to_owned());
+        }
+        steps.push(format!(
+            "Tell the people trusting {} that it's compromised, and ask them to distrust it: `crev trust distrust {}`",
+            self.id, self.id
+        ));
+        match self.backup_id {
+            Some(ref backup_id) => steps.push(format!(
+                "Ask them to trust your backup id {} instead, now your current id",
+                backup_id
+            )),
+            None => steps.push(
+                "Create a new id (`crev id new`), and ask them to trust it instead".to_owned(),
+            ),
+        }
+        if self.revoked > 0 {
+            steps.push(format!(
+                "Review again, with the new id, what you still stand by of the {} proofs revoked",
+                self.revoked
+            ));
+        }
+        steps.push(
+            "Hand over the trust roots of your packages: `crev package hand-over`".to_owned(),
+        );
+        steps.push(
+            "Find out how the key leaked, and delete every copy of the compromised id, eg. backups of its locked id file".to_owned(),
+        );
+        steps
+    }
+}
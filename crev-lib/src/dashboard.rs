@@ -0,0 +1,264 @@
+//! `crev dashboard` - a small local web UI
+//!
+//! Every request renders a fresh `DashboardInfo`, so reloading the page
+//! shows the effects of commands run in the meantime. The server only
+//! listens on the loopback interface and serves a single page, and only
+//! to requests for a local `Host`, so other sites can't read it through
+//! DNS rebinding.
+use crate::{
+    prelude::*,
+    proof::{html_escape, humanize_age, type_label},
+    VerificationStatus,
+};
+use chrono::prelude::*;
+use crev_data::{proof, proof::trust::TrustLevel, Id, Url};
+use std::{
+    fmt::{self, Write as FmtWrite},
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+/// How long a client can take to send its request, or read the response
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Requests are only a request line and headers
+const MAX_REQUEST_LEN: u64 = 16 * 1024;
+
+/// A trusted Id, as shown on the dashboard
+pub struct TrustedId {
+    pub id: Id,
+    pub url: Option<Url>,
+    pub level: TrustLevel,
+    /// Shortest path from the root of the WoT, if not the root itself
+    pub path: Vec<Id>,
+}
+
+/// Everything shown on the dashboard
+pub struct DashboardInfo {
+    pub current_id: Option<Id>,
+    /// Verification status of the `.crev` scopes (see `Repo::package_verify_nested`)
+    /// of the current directory; empty if not in one
+    pub scopes: Vec<(PathBuf, VerificationStatus)>,
+    pub trusted: Vec<TrustedId>,
+    /// Most recent fetched proofs of other ids, newest first
+    pub recent_proofs: Vec<proof::Proof>,
+    /// Most recent advisories by ids trusted for them, newest first
+    pub advisories: Vec<proof::Advisory>,
+}
+
+impl DashboardInfo {
+    fn write_html(&self, out: &mut String) -> fmt::Result {
+        let now = Utc::now();
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>crev dashboard</title>")?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>crev dashboard</h1>")?;
+        match self.current_id {
+            Some(ref id) => writeln!(out, "<p>Current id: <code>{}</code></p>", id)?,
+            None => writeln!(out, "<p>No current id.</p>")?,
+        }
+
+        writeln!(out, "<h2>Coverage</h2>")?;
+        if self.scopes.is_empty() {
+            writeln!(out, "<p>Not in a <code>.crev</code> scope.</p>")?;
+        } else {
+            writeln!(out, "<table>")?;
+            writeln!(out, "<tr><th>Scope</th><th>Status</th></tr>")?;
+            for (rel_dir, status) in &self.scopes {
+                let rel_dir = if rel_dir.as_os_str().is_empty() {
+                    ".".into()
+                } else {
+                    rel_dir.display().to_string()
+                };
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    html_escape(&rel_dir),
+                    status
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+
+        writeln!(out, "<h2>Web of trust</h2>")?;
+        if self.trusted.is_empty() {
+            writeln!(out, "<p>No trusted ids.</p>")?;
+        } else {
+            writeln!(out, "<table>")?;
+            writeln!(
+                out,
+                "<tr><th>Id</th><th>URL</th><th>Trust</th><th>Path</th></tr>"
+            )?;
+            for trusted in &self.trusted {
+                let path: Vec<_> = trusted.path.iter().map(ToString::to_string).collect();
+                writeln!(
+                    out,
+                    "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    trusted.id,
+                    html_escape(
+                        trusted
+                            .url
+                            .as_ref()
+                            .map(|url| url.url.as_str())
+                            .unwrap_or("")
+                    ),
+                    trusted.level,
+                    html_escape(&path.join(" -> "))
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+
+        writeln!(out, "<h2>Recent proofs</h2>")?;
+        if self.recent_proofs.is_empty() {
+            writeln!(out, "<p>No fetched proofs.</p>")?;
+        } else {
+            writeln!(out, "<table>")?;
+            writeln!(
+                out,
+                "<tr><th>Type</th><th>Date</th><th>Author</th><th>Title</th></tr>"
+            )?;
+            for proof in &self.recent_proofs {
+                let content = &proof.content;
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td></tr>",
+                    type_label(content),
+                    humanize_age(&content.date().with_timezone(&Utc), &now),
+                    content.author_id(),
+                    html_escape(&content.draft_title())
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+
+        writeln!(out, "<h2>Advisories</h2>")?;
+        if self.advisories.is_empty() {
+            writeln!(out, "<p>No trusted advisories.</p>")?;
+        } else {
+            writeln!(out, "<table>")?;
+            writeln!(
+                out,
+                "<tr><th>Project</th><th>Severity</th><th>Date</th><th>Author</th><th>Comment</th></tr>"
+            )?;
+            for advisory in &self.advisories {
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td></tr>",
+                    html_escape(&advisory.project),
+                    advisory.severity,
+                    humanize_age(&advisory.date.with_timezone(&Utc), &now),
+                    advisory.from.id,
+                    html_escape(&advisory.comment)
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        self.write_html(&mut out)
+            .expect("writing to a String can't fail");
+        out
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Whether `host`, a `Host` header value, names this machine's dashboard
+/// on `port`
+pub(crate) fn is_local_host(host: &str, port: u16) -> bool {
+    let host = host.trim().to_lowercase();
+    let name = match host.rfind(':') {
+        Some(colon) if !host.ends_with(']') => {
+            if host[colon + 1..].parse::<u16>().ok() != Some(port) {
+                return false;
+            }
+            &host[..colon]
+        }
+        _ => &host[..],
+    };
+    name == "localhost" || name == "127.0.0.1" || name == "[::1]"
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    port: u16,
+    info: &mut dyn FnMut() -> Result<DashboardInfo>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_LEN));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+
+    let mut host = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        let mut parts = header.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_owned());
+            }
+        }
+    }
+    if !host.map_or(false, |host| is_local_host(&host, port)) {
+        return respond(&mut stream, "403 Forbidden", "Forbidden");
+    }
+
+    match (method, path) {
+        (Some("GET"), Some("/")) => match info() {
+            Ok(info) => respond(&mut stream, "200 OK", &info.to_html()),
+            Err(e) => respond(
+                &mut stream,
+                "500 Internal Server Error",
+                &format!("<pre>{}</pre>", html_escape(&e.to_string())),
+            ),
+        },
+        (Some("GET"), Some(_)) => respond(&mut stream, "404 Not Found", "Not found"),
+        _ => respond(&mut stream, "405 Method Not Allowed", "Method not allowed"),
+    }
+}
+
+/// Serve the dashboard on `127.0.0.1:port` until interrupted
+///
+/// `info` is called for every page view.
+pub fn serve(port: u16, info: &mut dyn FnMut() -> Result<DashboardInfo>) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let addr = listener.local_addr()?;
+    eprintln!("Dashboard available at: http://{}/", addr);
+    eprintln!("Use Ctrl-C to stop.");
+    for stream in listener.incoming() {
+        if let Err(e) = stream
+            .map_err(failure::Error::from)
+            .and_then(|stream| handle_connection(stream, addr.port(), info))
+        {
+            eprintln!("Dashboard request failed: {}", e);
+        }
+    }
+    Ok(())
+}
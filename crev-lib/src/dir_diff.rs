@@ -0,0 +1,124 @@
+//! Changes between two versions of a package
+//!
+//! Used for diff reviews, where only what changed since an already
+//! reviewed version is looked at.
+use crate::prelude::*;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileChange {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// A file that differs between two versions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Relative to the package dir
+    pub path: PathBuf,
+    pub change: FileChange,
+}
+
+/// Content of a file or symlink, for comparison
+fn read_entry(path: &Path) -> Result<Vec<u8>> {
+    let metadata = fs::symlink_metadata(path)?;
+    Ok(if metadata.file_type().is_symlink() {
+        fs::read_link(path)?
+            .to_string_lossy()
+            .into_owned()
+            .into_bytes()
+    } else {
+        fs::read(path)?
+    })
+}
+
+/// All the files and symlinks under `dir`, without the ignored ones
+fn list_files<H>(
+    dir: &Path,
+    ignore_list: &HashSet<PathBuf, H>,
+) -> Result<BTreeMap<PathBuf, PathBuf>>
+where
+    H: std::hash::BuildHasher,
+{
+    let mut files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry
+                .path()
+                .strip_prefix(dir)
+                .map(|rel_path| !ignore_list.contains(rel_path))
+                .unwrap_or(true)
+        })
+    {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let rel_path = entry.path().strip_prefix(dir)?.to_owned();
+        files.insert(rel_path, entry.path().to_owned());
+    }
+    Ok(files)
+}
+
+/// Files that were added, modified or removed going from `old_dir` to
+/// `new_dir`, sorted by path
+///
+/// `ignore_list` works like in `get_dir_digest`.
+pub fn changed_files<H>(
+    old_dir: &Path,
+    new_dir: &Path,
+    ignore_list: &HashSet<PathBuf, H>,
+) -> Result<Vec<ChangedFile>>
+where
+    H: std::hash::BuildHasher,
+{
+    let old_files = list_files(old_dir, ignore_list)?;
+    let new_files = list_files(new_dir, ignore_list)?;
+
+    let mut changes = vec![];
+    for (rel_path, new_path) in &new_files {
+        let change = match old_files.get(rel_path) {
+            None => FileChange::Added,
+            Some(old_path) if read_entry(old_path)? != read_entry(new_path)? => {
+                FileChange::Modified
+            }
+            Some(_) => continue,
+        };
+        changes.push(ChangedFile {
+            path: rel_path.clone(),
+            change,
+        });
+    }
+    for rel_path in old_files.keys() {
+        if !new_files.contains_key(rel_path) {
+            changes.push(ChangedFile {
+                path: rel_path.clone(),
+                change: FileChange::Removed,
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// Copy the added and modified files of `new_dir` to `dst`, to be
+/// reviewed there
+pub fn stage_changed_files(new_dir: &Path, changes: &[ChangedFile], dst: &Path) -> Result<()> {
+    for changed in changes {
+        if changed.change == FileChange::Removed {
+            continue;
+        }
+        let dst_path = dst.join(&changed.path);
+        if let Some(parent) = dst_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dst_path, read_entry(&new_dir.join(&changed.path))?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,268 @@
+//! `crev editor-server` - review status of files for editor integrations
+//!
+//! Editors send one JSON request per line, and get one JSON response per
+//! line back, over stdio or a TCP connection on localhost:
+//!
+//! ```text
+//! {"id": 1, "method": "file", "path": "src/lib.rs"}
+//! {"id":1,"result":{"path":"src/lib.rs","status":"high","verified":true,...}}
+//! ```
+//!
+//! Methods are `file` (a `FileStatus`; the path can be absolute or relative
+//! to the scope), `files` (all of them), `scope` (a `repo::ScopeStatus`)
+//! and `shutdown`. The files are verified again on the first request after
+//! any of them, the `.crev` config and proofs, or the proof repositories
+//! changed, so the answers follow edits and new reviews.
+//!
+//! Any local process can connect over TCP, so there every request needs
+//! the `"token"` printed when the server starts.
+use crate::{
+    prelude::*,
+    repo::{watch::Watcher, FileVerification, ScopeStatus},
+};
+use crev_common;
+use crev_data::proof::{
+    review::{Common, Rating},
+    trust::TrustLevel,
+    ContentCommon,
+};
+use rand::{self, Rng};
+use serde_json;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+};
+
+/// Callback verifying the files of the scope; see `Repo::files_verify`
+pub type VerifyFn<'a> = &'a mut dyn FnMut() -> Result<Vec<FileVerification>>;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: serde_json::Value,
+    method: String,
+    #[serde(default)]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+/// A review of a file, as sent to editors
+#[derive(Serialize, Debug, Clone)]
+pub struct Reviewer {
+    pub id: String,
+    pub url: String,
+    /// Effective trust in the reviewer, if any
+    pub trust: Option<TrustLevel>,
+    pub rating: Rating,
+    pub date: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+}
+
+/// Verification of a file, as sent to editors
+#[derive(Serialize, Debug, Clone)]
+pub struct FileStatus {
+    pub path: PathBuf,
+    /// See `VerificationStatus`
+    pub status: String,
+    pub verified: bool,
+    /// Reviews of the current content, most trusted first
+    pub reviewers: Vec<Reviewer>,
+    /// Those of `reviewers` rating the content negative or dangerous
+    pub flags: Vec<Reviewer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<PathBuf>,
+}
+
+impl From<&FileVerification> for FileStatus {
+    fn from(file: &FileVerification) -> Self {
+        let reviewers: Vec<_> = file
+            .reviews
+            .iter()
+            .map(|candidate| Reviewer {
+                id: candidate.review.from.id.to_string(),
+                url: candidate.review.from.url.url.clone(),
+                trust: candidate.reviewer_trust,
+                rating: candidate.review.review().rating.clone(),
+                date: candidate.review.date().to_rfc3339(),
+                comment: candidate.review.comment.clone(),
+            })
+            .collect();
+        FileStatus {
+            path: file.path.clone(),
+            status: file.status.to_string(),
+            verified: file.status.is_verified(),
+            flags: reviewers
+                .iter()
+                .filter(|reviewer| reviewer.rating <= Rating::Negative)
+                .cloned()
+                .collect(),
+            reviewers,
+            renamed_from: file
+                .renamed_from
+                .as_ref()
+                .map(|(rename, _)| rename.from.clone()),
+        }
+    }
+}
+
+/// State of a running server: the last verification of the scope
+pub struct Server {
+    root_dir: PathBuf,
+    /// Token every request has to carry, if any
+    token: Option<String>,
+    files: Option<(Vec<FileStatus>, ScopeStatus)>,
+}
+
+impl Server {
+    pub fn new(root_dir: &Path, token: Option<String>) -> Self {
+        Server {
+            root_dir: root_dir.to_owned(),
+            token,
+            files: None,
+        }
+    }
+
+    /// Forget the last verification, eg. because files changed since
+    pub fn invalidate(&mut self) {
+        self.files = None;
+    }
+
+    fn files(&mut self, verify: VerifyFn<'_>) -> Result<&(Vec<FileStatus>, ScopeStatus)> {
+        if self.files.is_none() {
+            let files = verify()?;
+            self.files = Some((
+                files.iter().map(FileStatus::from).collect(),
+                ScopeStatus::of_files(&files),
+            ));
+        }
+        Ok(self.files.as_ref().unwrap())
+    }
+
+    fn respond_to(&mut self, request: &Request, verify: VerifyFn<'_>) -> Result<serde_json::Value> {
+        if self.token.is_some() && request.token != self.token {
+            bail!("Invalid token");
+        }
+        Ok(match request.method.as_str() {
+            "file" => {
+                let path = request
+                    .path
+                    .as_ref()
+                    .ok_or_else(|| format_err!("Missing `path`"))?;
+                let path = path.strip_prefix(&self.root_dir).unwrap_or(path).to_owned();
+                let (files, _) = self.files(verify)?;
+                let file = files
+                    .iter()
+                    .find(|file| file.path == path)
+                    .ok_or_else(|| format_err!("Not a file of the scope: {}", path.display()))?;
+                serde_json::to_value(file)?
+            }
+            "files" => serde_json::to_value(&self.files(verify)?.0)?,
+            "scope" => serde_json::to_value(self.files(verify)?.1)?,
+            "shutdown" => serde_json::Value::Null,
+            method => bail!("Unknown method: {}", method),
+        })
+    }
+
+    /// Respond to a single request `line`, and tell if the server should
+    /// keep going
+    pub fn handle(&mut self, line: &str, verify: VerifyFn<'_>) -> (String, bool) {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                let response =
+                    serde_json::json!({"id": null, "error": format!("Invalid request: {}", e)});
+                return (response.to_string(), true);
+            }
+        };
+        match self.respond_to(&request, verify) {
+            Ok(result) => (
+                serde_json::json!({"id": request.id, "result": result}).to_string(),
+                request.method != "shutdown",
+            ),
+            Err(e) => (
+                serde_json::json!({"id": request.id, "error": e.to_string()}).to_string(),
+                true,
+            ),
+        }
+    }
+}
+
+/// Serve requests read from `input` until it ends or asks to shut down,
+/// telling which of the two happened
+///
+/// Besides the files of the scope, the ones in `watch_dirs`, eg. proof
+/// repositories, are watched for changes. Requests have to carry `token`,
+/// if any.
+pub fn serve(
+    root_dir: &Path,
+    watch_dirs: Vec<PathBuf>,
+    token: Option<String>,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    verify: VerifyFn<'_>,
+) -> Result<bool> {
+    let mut server = Server::new(root_dir, token);
+    let mut watcher = Watcher::with_dirs(root_dir, watch_dirs)?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !watcher.poll()?.is_empty() {
+            server.invalidate();
+        }
+        let (response, keep_going) = server.handle(&line, verify);
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+        if !keep_going {
+            return Ok(true);
+        }
+    }
+}
+
+/// Serve connections on `127.0.0.1:port`, one at a time, until one asks
+/// to shut down
+///
+/// Requests need the token printed on start; a connection failing only
+/// ends that connection.
+pub fn serve_tcp(
+    root_dir: &Path,
+    watch_dirs: Vec<PathBuf>,
+    port: u16,
+    verify: VerifyFn<'_>,
+) -> Result<()> {
+    let token_bytes: Vec<u8> = rand::thread_rng()
+        .sample_iter(&rand::distributions::Standard)
+        .take(32)
+        .collect();
+    let token = crev_common::base64_encode(&token_bytes);
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Listening on {}, token: {}", listener.local_addr()?, token);
+    for stream in listener.incoming() {
+        let result = stream.map_err(failure::Error::from).and_then(|stream| {
+            let mut input = BufReader::new(stream.try_clone()?);
+            let mut output = stream;
+            serve(
+                root_dir,
+                watch_dirs.clone(),
+                Some(token.clone()),
+                &mut input,
+                &mut output,
+                verify,
+            )
+        });
+        match result {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => eprintln!("Editor connection failed: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! `crev evidence` - archives of why a revision of a package was accepted
+//!
+//! An archive is a tar file with:
+//!
+//! * `report.crev` - the signed `VerificationReport` of the revision,
+//! * `config.yaml` - the package config, with its review requirements, if
+//!   there is one,
+//! * `proofs.crev` - the reviews and attestations the verification relied
+//!   on, followed by the Trust Proofs making their authors trusted,
+//! * `trust.txt` - the chains of trust to each of those authors.
+//!
+//! Entries are dated with the report, so the same evidence always makes
+//! the same archive.
+use crate::{prelude::*, proofdb::TrustLink};
+use crev_data::{proof, verification_report::SignedVerificationReport, Id};
+use std::{collections::BTreeMap, io::Write};
+use tar;
+
+/// Everything that goes in an evidence archive
+pub struct Evidence {
+    pub report: SignedVerificationReport,
+    /// The package config, as stored, if there's one
+    pub config: Option<String>,
+    /// Reviews and attestations, then Trust Proofs
+    pub proofs: Vec<proof::Proof>,
+    /// Chains of trust to the authors of the reviews and attestations
+    pub trust_chains: Vec<(Id, Vec<Vec<TrustLink>>)>,
+    /// URLs of the ids in `trust_chains`, where known
+    pub urls: BTreeMap<Id, String>,
+}
+
+impl Evidence {
+    /// Write the archive to `out`
+    pub fn write_tar(&self, out: &mut dyn Write) -> Result<()> {
+        let mtime = self.report.report.date.timestamp() as u64;
+        let mut builder = tar::Builder::new(out);
+        let mut append = |path: &str, content: &str| -> Result<()> {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            builder.append_data(&mut header, path, content.as_bytes())?;
+            Ok(())
+        };
+
+        append("report.crev", &self.report.to_string())?;
+        if let Some(ref config) = self.config {
+            append("config.yaml", config)?;
+        }
+        let proofs: String = self.proofs.iter().map(ToString::to_string).collect();
+        append("proofs.crev", &proofs)?;
+        append("trust.txt", &self.trust_text())?;
+
+        builder.finish()?;
+        Ok(())
+    }
+
+    fn trust_text(&self) -> String {
+        let name = |id: &Id| match self.urls.get(id) {
+            Some(url) => url.clone(),
+            None => id.to_string(),
+        };
+        let mut text = String::new();
+        for (author, chains) in &self.trust_chains {
+            text.push_str(&format!("{}:\n", name(author)));
+            if chains.is_empty() {
+                text.push_str("  verifier's own proofs\n");
+            }
+            for chain in chains {
+                for link in chain {
+                    text.push_str(&format!(
+                        "  {} trusts {}: {}\n",
+                        name(&link.from),
+                        name(&link.to),
+                        link
+                    ));
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,76 @@
+//! Checkpoints of fetching many remotes, to resume after an interruption
+//!
+//! `Local::fetch_trusted` and `Local::fetch_all` record each remote once
+//! it's fetched. If they don't get to finish, the next run skips the
+//! remotes fetched already, as long as it's within `MAX_AGE_HOURS` of the
+//! interrupted one; after that it starts over.
+use crate::prelude::*;
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const MAX_AGE_HOURS: i64 = 24;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Checkpoint {
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    started: chrono::DateTime<FixedOffset>,
+    /// URLs of the remotes fetched since `started`
+    fetched: BTreeSet<String>,
+}
+
+/// Progress of fetching the remotes, stored after every one of them
+pub struct FetchProgress {
+    path: PathBuf,
+    checkpoint: Checkpoint,
+}
+
+impl FetchProgress {
+    /// Resume the progress stored at `path`, or start anew
+    pub fn load(path: &Path) -> FetchProgress {
+        let checkpoint = fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_yaml::from_str::<Checkpoint>(&s).ok())
+            .filter(|checkpoint| {
+                crev_common::now().signed_duration_since(checkpoint.started)
+                    < chrono::Duration::hours(MAX_AGE_HOURS)
+            })
+            .unwrap_or_else(|| Checkpoint {
+                started: crev_common::now(),
+                fetched: BTreeSet::new(),
+            });
+        FetchProgress {
+            path: path.to_owned(),
+            checkpoint,
+        }
+    }
+
+    pub fn is_fetched(&self, url: &str) -> bool {
+        self.checkpoint.fetched.contains(url)
+    }
+
+    pub fn mark_fetched(&mut self, url: &str) -> Result<()> {
+        self.checkpoint.fetched.insert(url.to_owned());
+        crev_common::store_str_to_file(&self.path, &serde_yaml::to_string(&self.checkpoint)?)?;
+        Ok(())
+    }
+
+    /// All the remotes were fetched; the next run starts anew
+    pub fn finish(self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
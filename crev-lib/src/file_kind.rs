@@ -0,0 +1,72 @@
+//! Telling code from configuration, documentation and data
+//!
+//! Files get a `FileKind` when staged, recorded in the Code Review Proof.
+//! It's guessed from the name of the file, unless one of the `file-kinds`
+//! of the package config matches it first, eg. to have `deploy/**`
+//! reviewed as configuration. `path-requirements` can then apply to some
+//! kinds only, and verification reports break down by kind.
+use crate::prelude::*;
+use crev_data::proof::review::code::FileKind;
+use std::path::Path;
+
+/// The kind of the files matching `path`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileKindRule {
+    /// Pattern relative to the root of the scope, as in `PathRequirement`
+    pub path: String,
+    pub kind: FileKind,
+}
+
+/// Guess the kind of the file at `rel_path` from its name
+///
+/// Anything not recognized is taken for code, so requirements for code
+/// don't miss it.
+pub fn guess(rel_path: &Path) -> FileKind {
+    let name = rel_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let ext = rel_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let stem = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    match stem.as_str() {
+        "README" | "CHANGELOG" | "CHANGES" | "LICENSE" | "COPYING" | "AUTHORS" | "CONTRIBUTING"
+        | "NOTICE" => return FileKind::Docs,
+        "DOCKERFILE" | "VAGRANTFILE" | "JENKINSFILE" | "PROCFILE" => return FileKind::Config,
+        _ => {}
+    }
+    if name.starts_with('.') && ext.is_empty() || name.ends_with(".lock") {
+        // eg. `.gitignore`, `.editorconfig`, `Cargo.lock`
+        return FileKind::Config;
+    }
+    match ext.as_str() {
+        "md" | "rst" | "txt" | "adoc" | "asciidoc" | "org" | "tex" | "html" | "htm" => {
+            FileKind::Docs
+        }
+        "toml" | "yaml" | "yml" | "json" | "ini" | "cfg" | "conf" | "properties" | "xml" | "tf"
+        | "tfvars" | "hcl" | "nix" | "env" => FileKind::Config,
+        "csv" | "tsv" | "dat" | "bin" | "png" | "jpg" | "jpeg" | "gif" | "svg" | "ico" | "webp"
+        | "pdf" | "ttf" | "otf" | "woff" | "woff2" | "wasm" | "zip" | "gz" | "tar" => {
+            FileKind::Data
+        }
+        _ => FileKind::Code,
+    }
+}
+
+/// The kind of the file at `rel_path`: of the first of `rules` matching
+/// it, or else guessed
+pub fn for_path(rules: &[FileKindRule], rel_path: &Path) -> Result<FileKind> {
+    for rule in rules {
+        if crate::policy::path_matches(&rule.path, rel_path)? {
+            return Ok(rule.kind);
+        }
+    }
+    Ok(guess(rel_path))
+}
@@ -0,0 +1,120 @@
+//! Hooks - external commands run on crev events
+//!
+//! Commands come from the `hooks` of the user config, and from plugins:
+//! executables named `crev-hook-<event>` on `PATH`. They get details of
+//! the event in `CREV_*` environment variables. A failing hook never fails
+//! the command that triggered it; it only gets reported.
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Things happening that hooks can be run on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Event {
+    /// An own proof was stored; see `Local::insert_own`
+    ProofCommitted,
+    /// A proof repository was fetched
+    ProofsFetched,
+    /// A fetched proof rated something negative or dangerous
+    AdvisoryReceived,
+    /// A scope wasn't verified
+    VerificationFailed,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::ProofCommitted => "proof-committed",
+            Event::ProofsFetched => "proofs-fetched",
+            Event::AdvisoryReceived => "advisory-received",
+            Event::VerificationFailed => "verification-failed",
+        }
+    }
+}
+
+/// A command to run on an event, as set in the user config
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Hook {
+    pub event: Event,
+    /// Run by the shell
+    pub command: String,
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Path of the `crev-<name>` executable, if there's one on `PATH`
+pub fn find_plugin(name: &str) -> Option<PathBuf> {
+    let file_name = format!("crev-{}{}", name, env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+fn shell_command(command: &str) -> process::Command {
+    if cfg!(windows) {
+        let mut shell = process::Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = process::Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// Is there anything to run on `event`
+pub fn any_for(hooks: &[Hook], event: Event) -> bool {
+    hooks.iter().any(|hook| hook.event == event)
+        || find_plugin(&format!("hook-{}", event.name())).is_some()
+}
+
+/// Run everything set to run on `event`, one after another
+///
+/// `vars` are put in the environment with `CREV_` prepended to their
+/// names, along with `CREV_EVENT`. Returns the number of hooks that failed.
+pub fn run(hooks: &[Hook], event: Event, vars: &[(&str, String)]) -> usize {
+    let mut commands: Vec<_> = hooks
+        .iter()
+        .filter(|hook| hook.event == event)
+        .map(|hook| (hook.command.clone(), shell_command(&hook.command)))
+        .collect();
+    if let Some(plugin) = find_plugin(&format!("hook-{}", event.name())) {
+        commands.push((plugin.display().to_string(), process::Command::new(plugin)));
+    }
+
+    let mut failed = 0;
+    for (description, mut command) in commands {
+        command.env("CREV_EVENT", event.name());
+        for (name, value) in vars {
+            command.env(format!("CREV_{}", name), value);
+        }
+        let error = match command.status() {
+            Ok(status) if status.success() => continue,
+            Ok(status) => format!("{}", status),
+            Err(e) => e.to_string(),
+        };
+        eprintln!(
+            "Hook `{}` for {} failed: {}",
+            description,
+            event.name(),
+            error
+        );
+        failed += 1;
+    }
+    failed
+}
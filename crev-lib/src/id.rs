@@ -1,5 +1,6 @@
 use crate::prelude::*;
 use argonautica::{self, Hasher};
+use clear_on_drop::ClearOnDrop;
 use crev_common::serde::{as_base64, from_base64};
 use crev_data::id::{OwnId, PubId};
 use miscreant;
@@ -7,12 +8,38 @@ use rand::{self, Rng};
 use serde_yaml;
 use std::{
     self, fmt,
-    io::{Read, Write},
+    io::Read,
     path::Path,
 };
 
 const CURRENT_LOCKED_ID_SERIALIZATION_VERSION: i64 = -1;
 pub type PassphraseFn<'a> = &'a Fn() -> std::io::Result<String>;
+/// Like `PassphraseFn`, for a passphrase about to lock an id, which
+/// has to be wiped once it's used
+pub type NewPassphraseFn<'a> = &'a dyn Fn() -> std::io::Result<ClearOnDrop<String>>;
+
+/// Argon2id parameters used when locking an id with a passphrase
+///
+/// The parameters are stored along with every `LockedId`, so changing them
+/// affects only ids locked afterwards (eg. with `crev id rekey`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KdfParams {
+    pub iterations: u32,
+    /// In kibibytes
+    #[serde(rename = "memory-size")]
+    pub memory_size: u32,
+    pub lanes: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            iterations: 3,
+            memory_size: 64 * 1024,
+            lanes: 4,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PassConfig {
@@ -21,6 +48,9 @@ pub struct PassConfig {
     iterations: u32,
     #[serde(rename = "memory-size")]
     memory_size: u32,
+    // ids locked before this was stored used the number of cpus
+    #[serde(default = "argonautica::config::default_lanes")]
+    lanes: u32,
     #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
     salt: Vec<u8>,
 }
@@ -53,11 +83,23 @@ impl fmt::Display for LockedId {
 
 impl LockedId {
     pub fn from_own_id(own_id: &OwnId, passphrase: &str) -> Result<LockedId> {
+        Self::from_own_id_with_kdf_params(own_id, passphrase, &KdfParams::default())
+    }
+
+    pub fn from_own_id_with_kdf_params(
+        own_id: &OwnId,
+        passphrase: &str,
+        params: &KdfParams,
+    ) -> Result<LockedId> {
         use miscreant::aead::Algorithm;
         let mut hasher = Hasher::default();
 
         hasher
-            .configure_memory_size(4096)
+            .configure_iterations(params.iterations)
+            .configure_lanes(params.lanes)
+            .configure_threads(params.lanes)
+            .configure_memory_size(params.memory_size)
+            .configure_variant(argonautica::config::Variant::Argon2id)
             .configure_hash_len(64)
             .opt_out_of_secret_key(true);
 
@@ -83,6 +125,7 @@ impl LockedId {
                 salt: pwhash.raw_salt_bytes().to_vec(),
                 iterations: hasher_config.iterations(),
                 memory_size: hasher_config.memory_size(),
+                lanes: hasher_config.lanes(),
                 version: 0x13,
                 variant: hasher_config.variant().as_str().to_string(),
             },
@@ -97,13 +140,22 @@ impl LockedId {
         crev_common::base64_encode(&self.public_key)
     }
 
-    pub fn save_to(&self, path: &Path) -> Result<()> {
-        let mut file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)?;
-        write!(file, "{}", self)?;
+    /// Parameters this id was locked with
+    pub fn kdf_params(&self) -> KdfParams {
+        KdfParams {
+            iterations: self.pass.iterations,
+            memory_size: self.pass.memory_size,
+            lanes: self.pass.lanes,
+        }
+    }
 
+    /// Write to `path`, atomically replacing any previous content
+    ///
+    /// The new content is synced before it replaces the old one, so a
+    /// crash can't leave the only copy of the secret key truncated.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let content = self.to_string();
+        crev_common::store_to_file_with(path, |w| w.write_all(content.as_bytes()))??;
         Ok(())
     }
 
@@ -116,6 +168,21 @@ impl LockedId {
     }
 
     pub fn to_unlocked(&self, passphrase_callback: PassphraseFn) -> Result<OwnId> {
+        for _ in 0..5 {
+            let passphrase = ClearOnDrop::new(passphrase_callback()?);
+            match self.try_unlock(&passphrase)? {
+                Some(own_id) => return Ok(own_id),
+                None => eprintln!("Error: incorrect passphrase"),
+            }
+        }
+        Err(format_err!("incorrect passphrase"))
+    }
+
+    /// Single attempt to unlock with `passphrase`
+    ///
+    /// Returns `None` if the passphrase is incorrect. It's up to the caller
+    /// to wipe `passphrase`, eg. by keeping it in a `ClearOnDrop`.
+    pub fn try_unlock(&self, passphrase: &str) -> Result<Option<OwnId>> {
         let LockedId {
             ref version,
             ref url,
@@ -135,35 +202,28 @@ impl LockedId {
                 .configure_memory_size(pass.memory_size)
                 .configure_version(argonautica::config::Version::from_u32(pass.version)?)
                 .configure_iterations(pass.iterations)
+                .configure_lanes(pass.lanes)
+                .configure_threads(pass.lanes)
                 .configure_variant(std::str::FromStr::from_str(&pass.variant)?)
                 .with_salt(&pass.salt)
                 .configure_hash_len(64)
+                // a borrowed passphrase can't be wiped here
+                .configure_password_clearing(false)
                 .opt_out_of_secret_key(true);
 
-            let mut secret_key = Vec::new();
-            for _ in 0..5 {
-                let passphrase = passphrase_callback()?;
-                let passphrase_hash = hasher.with_password(passphrase).hash_raw()?;
-                let mut siv = miscreant::aead::Aes256Siv::new(passphrase_hash.raw_hash_bytes());
-
-                match siv.open(&seal_nonce, &[], &sealed_secret_key) {
-                    Ok(k) => {
-                        secret_key = k;
-                        break;
-                    },
-                    Err(_) => eprintln!("Error: incorrect passphrase")
-                }
-            }
+            let passphrase_hash = hasher.with_password(passphrase).hash_raw()?;
+            let mut siv = miscreant::aead::Aes256Siv::new(passphrase_hash.raw_hash_bytes());
 
-            if secret_key.is_empty() {
-                return Err(format_err!("incorrect passphrase"));
-            }
+            let secret_key = match siv.open(&seal_nonce, &[], &sealed_secret_key) {
+                Ok(k) => ClearOnDrop::new(k),
+                Err(_) => return Ok(None),
+            };
 
-            let result = OwnId::new(url.to_owned(), secret_key)?;
+            let result = OwnId::new(url.to_owned(), &secret_key)?;
             if public_key != &result.keypair.public.to_bytes() {
                 bail!("PubKey mismatch");
             }
-            Ok(result)
+            Ok(Some(result))
         }
     }
 }
@@ -0,0 +1,123 @@
+//! `ids.yaml` - the ids publishing proofs in a proof repository
+//!
+//! Kept at the root of the user's own proof repository and updated
+//! on every change to it. When a repository is fetched, only proofs
+//! by ids it lists (and hasn't revoked) are imported, so a repository
+//! can't pass off proofs of ids that don't publish there as its own.
+//!
+//! Repositories without `ids.yaml`, created before it existed, are
+//! imported as they are.
+//!
+//! An id revoked by the repository at its own URL has its proofs ignored
+//! in all the other repositories too, as its key may have been used to
+//! forge them.
+use crate::prelude::*;
+use crev_common;
+use crev_data::{proof, Id, PubId};
+use serde_yaml;
+use std::path::Path;
+
+pub const IDS_FILE_NAME: &str = "ids.yaml";
+
+const CURRENT_ID_DIRECTORY_SERIALIZATION_VERSION: i64 = -1;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdStatus {
+    /// Id currently used to publish proofs
+    Active,
+    /// Id no longer used, but its proofs are still valid
+    Retired,
+    /// Id that must not be trusted anymore (eg. the key was leaked);
+    /// its proofs are ignored
+    Revoked,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdDirectoryEntry {
+    #[serde(flatten)]
+    pub id: PubId,
+    pub status: IdStatus,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdDirectory {
+    version: i64,
+    pub ids: Vec<IdDirectoryEntry>,
+}
+
+impl Default for IdDirectory {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_ID_DIRECTORY_SERIALIZATION_VERSION,
+            ids: vec![],
+        }
+    }
+}
+
+impl IdDirectory {
+    /// Read `ids.yaml` from the root of a proof repository, if it has one
+    pub fn read_from_dir(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(IDS_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = crev_common::read_file_to_string(&path)?;
+        crev_data::proof::limits::check_no_aliases(&text)?;
+        Ok(Some(serde_yaml::from_str(&text)?))
+    }
+
+    pub fn store_to_dir(&self, dir: &Path) -> Result<()> {
+        crev_common::store_str_to_file(&dir.join(IDS_FILE_NAME), &serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &Id) -> Option<&IdDirectoryEntry> {
+        self.ids.iter().find(|entry| entry.id.id == *id)
+    }
+
+    /// Bring up to date with the ids publishing to the repository
+    ///
+    /// `current` becomes active and all the other ids retired. Revoked
+    /// ids stay revoked, as revoking is up to the user.
+    pub fn update(&mut self, own_ids: &[PubId], current: &Id) {
+        for pub_id in own_ids {
+            if self.get(&pub_id.id).is_none() {
+                self.ids.push(IdDirectoryEntry {
+                    id: pub_id.clone(),
+                    status: IdStatus::Retired,
+                });
+            }
+        }
+
+        for entry in &mut self.ids {
+            if entry.status == IdStatus::Revoked {
+                continue;
+            }
+            entry.status = if entry.id.id == *current {
+                IdStatus::Active
+            } else {
+                IdStatus::Retired
+            };
+        }
+    }
+
+    /// Mark `id` revoked, for good
+    pub fn revoke(&mut self, id: &PubId) {
+        match self.ids.iter_mut().find(|entry| entry.id.id == id.id) {
+            Some(entry) => entry.status = IdStatus::Revoked,
+            None => self.ids.push(IdDirectoryEntry {
+                id: id.clone(),
+                status: IdStatus::Revoked,
+            }),
+        }
+    }
+
+    /// Should `proof` found in the repository be imported
+    pub fn accepts(&self, proof: &proof::Proof) -> bool {
+        match self.get(&proof.content.author_id()) {
+            Some(entry) => entry.status != IdStatus::Revoked,
+            None => false,
+        }
+    }
+}
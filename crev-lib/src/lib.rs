@@ -5,13 +5,38 @@ extern crate term;
 #[macro_use]
 extern crate failure;
 
+pub mod annotate;
+pub mod audit_log;
+pub mod compromise;
+pub mod dashboard;
+pub mod dir_diff;
+pub mod editor_server;
+pub mod evidence;
+pub mod fetch_progress;
+pub mod file_kind;
+pub mod hooks;
 pub mod id;
+pub mod id_directory;
 pub mod local;
+pub mod normalize;
+pub mod org_policy;
+pub mod policy;
 pub(crate) mod prelude;
 pub mod proof;
+pub mod proof_index;
 pub mod proofdb;
+pub mod quarantine;
 pub mod repo;
+pub mod rust_anchors;
+pub mod rust_metrics;
+pub mod size_metrics;
 pub mod staging;
+pub mod stale;
+pub mod store_manifest;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+pub mod todo;
+pub mod trust_wizard;
 pub mod util;
 
 use crate::prelude::*;
@@ -24,7 +49,10 @@ use std::{
 };
 
 pub use self::local::Local;
-pub use crate::proofdb::{ProofDB, TrustDistanceParams};
+pub use crate::proofdb::{
+    IdConflict, ProjectIdentity, ProofDB, TrustDistanceParams, TrustLink, TrustLinkKind,
+    VersionCoverage,
+};
 
 /// Trait representing a place that can keep proofs
 ///
@@ -60,7 +88,7 @@ impl TrustOrDistrust {
 /// Result of verification
 ///
 /// Not named `Result` to avoid confusion with `Result` type.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum VerificationStatus {
     Verified(crev_data::proof::TrustLevel),
     Unknown,
@@ -155,17 +183,22 @@ where
     ))
 }
 
-pub fn get_recursive_digest_for_git_dir<H>(
-    root_path: &Path,
-    ignore_list: &HashSet<PathBuf, H>,
-) -> Result<Digest>
-where
-    H: std::hash::BuildHasher + std::default::Default,
-{
-    let git_repo = git2::Repository::open(root_path)?;
+/// Git status of the files tracked under `root_path`
+///
+/// `root_path` doesn't have to be the root of the git repository. Paths
+/// are relative to `root_path`, and files of nested `.crev` scopes (see
+/// `repo::is_in_nested_scope`) are left out, as they're reviewed separately.
+pub(crate) fn git_scope_statuses(root_path: &Path) -> Result<Vec<(PathBuf, git2::Status)>> {
+    let git_repo = git2::Repository::discover(root_path)?;
+    let workdir = git_repo
+        .workdir()
+        .ok_or_else(|| format_err!("Git repository has no working directory"))?
+        .canonicalize()?;
+    let root_path = root_path.canonicalize()?;
+    let scope_prefix = root_path.strip_prefix(&workdir)?;
 
     let mut status_opts = git2::StatusOptions::new();
-    let mut paths = HashSet::default();
+    let mut statuses = vec![];
 
     status_opts.include_unmodified(true);
     status_opts.include_untracked(false);
@@ -175,7 +208,38 @@ where
                 .path()
                 .ok_or_else(|| format_err!("Git entry without a path"))?,
         );
-        if ignore_list.contains(&entry_path) {
+        let entry_path = match entry_path.strip_prefix(scope_prefix) {
+            Ok(path) => path.to_owned(),
+            Err(_) => continue,
+        };
+        if repo::is_in_nested_scope(&root_path, &entry_path) {
+            continue;
+        }
+
+        statuses.push((entry_path, entry.status()));
+    }
+
+    Ok(statuses)
+}
+
+/// Digest of the files tracked by git under `root_path`
+///
+/// See `git_scope_statuses`. `ignore_list` is relative to `root_path`;
+/// ignoring a directory ignores all the files in it.
+pub fn get_recursive_digest_for_git_dir<H>(
+    root_path: &Path,
+    ignore_list: &HashSet<PathBuf, H>,
+) -> Result<Digest>
+where
+    H: std::hash::BuildHasher + std::default::Default,
+{
+    let mut paths = HashSet::default();
+
+    for (entry_path, _status) in git_scope_statuses(root_path)? {
+        if ignore_list
+            .iter()
+            .any(|ignored| entry_path.starts_with(ignored))
+        {
             continue;
         };
 
@@ -189,6 +253,25 @@ where
     ))
 }
 
+/// Digest of `path`, counting only the files tracked by git if it's in
+/// a git repository
+///
+/// Unlike `dir_or_git_repo_verify`, `path` doesn't have to be the root of
+/// the git repository, eg. it can be a crate of a Cargo workspace.
+pub fn get_dir_or_git_subdir_digest<H>(
+    path: &Path,
+    ignore_list: &HashSet<PathBuf, H>,
+) -> Result<Digest>
+where
+    H: std::hash::BuildHasher + std::default::Default,
+{
+    if git2::Repository::discover(path).is_ok() {
+        get_recursive_digest_for_git_dir(path, ignore_list)
+    } else {
+        get_dir_digest(path, ignore_list)
+    }
+}
+
 pub fn get_recursive_digest_for_paths<H>(
     root_path: &Path,
     paths: HashSet<PathBuf, H>,
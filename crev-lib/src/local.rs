@@ -1,35 +1,151 @@
 use crate::ProofStore;
 use crate::{
-    id::{self, LockedId, PassphraseFn},
+    audit_log::{self, AuditLog},
+    compromise::CompromiseResponse,
+    dashboard::{DashboardInfo, TrustedId},
+    fetch_progress::FetchProgress,
+    hooks::{self, Event},
+    id::{self, LockedId, NewPassphraseFn, PassphraseFn},
+    id_directory::{self, IdDirectory, IdStatus},
+    org_policy,
     prelude::*,
+    proof_index::{ProofFilter, ProofIndex},
     proofdb::TrustSet,
+    quarantine::{self, Quarantine},
+    store_manifest::{self, StoreManifest},
+    todo,
+    trust_wizard::TrustWizardInfo,
     util::{self, APP_INFO},
 };
 use app_dirs::{app_root, AppDataType};
+use clear_on_drop::ClearOnDrop;
 use crev_common;
-use crev_data::{id::OwnId, proof, proof::trust::TrustLevel, Id, PubId, Url};
+use crev_data::{
+    encrypted::{self, EncryptedProof, SignedEncryptedBundle, SignedEncryptedProof},
+    id::OwnId,
+    id_bundle::{Capability, PubIdBundleBuilder, SignedPubIdBundle},
+    org_policy::{BannedId, OrgPolicyBuilder, RequiredReviews, SignedOrgPolicy},
+    proof,
+    proof::trust::{TrustDimension, TrustLevel},
+    seed_list::{SeedListBuilder, SignedSeedList},
+    Id, PubId, Url,
+};
 use default::default;
 use failure::ResultExt;
 use git2;
 use insideout::InsideOut;
 use resiter_dpc_tmp::*;
+use serde_cbor;
 use serde_yaml;
 use std::cell::RefCell;
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     ffi::OsString,
     fs,
-    io::Write,
+    io::{self, Write},
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 const CURRENT_USER_CONFIG_SERIALIZATION_VERSION: i64 = -1;
 
+/// Part of the key of cached trust sets; to be changed along with how they
+/// are calculated or stored, so caches of other versions aren't used
+const TRUST_SET_CACHE_VERSION: i64 = -1;
+
+/// Remotes fetched at a time, unless `fetch-jobs` is set
+pub const DEFAULT_FETCH_JOBS: usize = 4;
+
+/// Callback shown each draft about to be signed, along with the file it's
+/// in, telling whether to sign it; see `Local::sign_drafts`
+pub type DraftApprovalFn<'a> = &'a dyn Fn(&Path, &proof::Content) -> Result<bool>;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserConfig {
     pub version: i64,
     #[serde(rename = "current-id")]
     pub current_id: Option<Id>,
+    /// Pager to use for long listings; see `util::pager`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pager: Option<String>,
+    /// Argon2id parameters for locking ids; see `id::KdfParams`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub kdf: Option<id::KdfParams>,
+    /// Remember passphrases of own ids in the OS keychain; see `util::keychain`
+    #[serde(
+        rename = "use-keychain",
+        skip_serializing_if = "std::ops::Not::not",
+        default
+    )]
+    pub use_keychain: bool,
+    /// Ids to encrypt own proofs to; see `Local::insert_own`
+    #[serde(rename = "encrypt-to", skip_serializing_if = "Vec::is_empty", default)]
+    pub encrypt_to: Vec<Id>,
+    /// Credentials for access-controlled proof repositories
+    #[serde(
+        rename = "remote-credentials",
+        skip_serializing_if = "Vec::is_empty",
+        default
+    )]
+    pub remote_credentials: Vec<util::git::RemoteCredentials>,
+    /// Commands to run on events; see `hooks`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub hooks: Vec<hooks::Hook>,
+    /// Ignore proofs dated in the future; see `ProofDB::set_reject_future_dated`
+    #[serde(
+        rename = "reject-future-dated",
+        skip_serializing_if = "std::ops::Not::not",
+        default
+    )]
+    pub reject_future_dated: bool,
+    /// Own Id to sign imported advisories with; see
+    /// `cargo crev import advisories`
+    #[serde(
+        rename = "advisory-bot-id",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub advisory_bot_id: Option<Id>,
+    /// Bandwidth limit of fetching proofs, in bytes per second
+    #[serde(
+        rename = "fetch-rate-limit",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub fetch_rate_limit: Option<u64>,
+    /// Remotes to fetch at a time; `DEFAULT_FETCH_JOBS` if not set
+    #[serde(
+        rename = "fetch-jobs",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub fetch_jobs: Option<usize>,
+    /// Time to give each remote to be fetched, in seconds
+    #[serde(
+        rename = "fetch-timeout",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub fetch_timeout: Option<u64>,
+    /// Organization policy to follow; see `org_policy`
+    #[serde(
+        rename = "org-policy",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub org_policy: Option<org_policy::OrgPolicySource>,
+    /// Own id to take over when the current one is compromised; see
+    /// `compromise`
+    #[serde(rename = "backup-id", skip_serializing_if = "Option::is_none", default)]
+    pub backup_id: Option<Id>,
+    /// Names to refer to own ids by, eg. `work` and `personal`; see
+    /// `Local::name_id`
+    #[serde(
+        rename = "id-names",
+        skip_serializing_if = "BTreeMap::is_empty",
+        default
+    )]
+    pub id_names: BTreeMap<String, Id>,
 }
 
 impl Default for UserConfig {
@@ -37,6 +153,20 @@ impl Default for UserConfig {
         Self {
             version: CURRENT_USER_CONFIG_SERIALIZATION_VERSION,
             current_id: None,
+            pager: None,
+            kdf: None,
+            use_keychain: false,
+            encrypt_to: vec![],
+            remote_credentials: vec![],
+            hooks: vec![],
+            reject_future_dated: false,
+            advisory_bot_id: None,
+            fetch_rate_limit: None,
+            fetch_jobs: None,
+            fetch_timeout: None,
+            org_policy: None,
+            backup_id: None,
+            id_names: BTreeMap::new(),
         }
     }
 }
@@ -49,6 +179,15 @@ impl UserConfig {
     pub fn get_current_userid_opt(&self) -> Option<&Id> {
         self.current_id.as_ref()
     }
+    pub fn credentials_for(&self, url: &str) -> Option<&util::git::RemoteCredentials> {
+        util::git::find_credentials(&self.remote_credentials, url)
+    }
+
+    /// When a fetch started now has to be done by, per `fetch-timeout`
+    pub fn fetch_deadline(&self) -> Option<Instant> {
+        self.fetch_timeout
+            .map(|timeout| Instant::now() + Duration::from_secs(timeout))
+    }
 }
 
 /// Local config stored in `~/.config/crev`
@@ -60,11 +199,22 @@ pub struct Local {
     cur_url: RefCell<Option<Url>>,
 }
 
+/// Environment variable overriding the path of the user config directory
+pub const CONFIG_DIR_ENV: &str = "CREV_CONFIG_DIR";
+/// Environment variable overriding the path of the cache directory
+pub const CACHE_DIR_ENV: &str = "CREV_CACHE_DIR";
+
 impl Local {
     #[allow(clippy::new_ret_no_self)]
     fn new() -> Result<Self> {
-        let root_path = app_root(AppDataType::UserConfig, &APP_INFO)?;
-        let cache_path = app_root(AppDataType::UserCache, &APP_INFO)?;
+        let root_path = match std::env::var_os(CONFIG_DIR_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => app_root(AppDataType::UserConfig, &APP_INFO)?,
+        };
+        let cache_path = match std::env::var_os(CACHE_DIR_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => app_root(AppDataType::UserCache, &APP_INFO)?,
+        };
         Ok(Self {
             root_path,
             cache_path,
@@ -140,6 +290,11 @@ impl Local {
         self.root_path.join("proofs")
     }
 
+    /// Where public ids imported with `import_pub_id` are kept
+    pub fn imported_ids_path(&self) -> PathBuf {
+        self.user_dir_path().join("imported-ids")
+    }
+
     fn id_path(&self, id: &Id) -> PathBuf {
         match id {
             Id::Crev { id } => self
@@ -167,6 +322,29 @@ impl Local {
         self.cache_path.join("remotes")
     }
 
+    /// Where `fetch_trusted` and `fetch_all` keep their progress; see
+    /// `fetch_progress`
+    fn fetch_progress_path(&self) -> PathBuf {
+        self.cache_path.join("fetch-progress.yaml")
+    }
+
+    /// Where `load_db` caches the trust set it calculated last
+    fn trust_set_cache_path(&self) -> PathBuf {
+        self.cache_path.join("trust-set")
+    }
+
+    /// Drop the cached trust set, after the proofs it was calculated from
+    /// changed
+    ///
+    /// A stale cache wouldn't be used anyway, as it's keyed by the proofs,
+    /// but there's no reason to keep it around.
+    fn invalidate_trust_set_cache(&self) -> Result<()> {
+        match fs::remove_file(self.trust_set_cache_path()) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => Ok(res?),
+        }
+    }
+
     pub fn load_user_config(&self) -> Result<UserConfig> {
         let path = self.user_config_path();
 
@@ -183,6 +361,24 @@ impl Local {
         Ok(util::store_str_to_file(&path, &config_str)?)
     }
 
+    /// Run the hooks of the user config for `event`; see `hooks::run`
+    ///
+    /// Like the hooks themselves, a user config that can't be read only
+    /// gets reported.
+    pub fn run_hooks(&self, event: Event, vars: &[(&str, String)]) {
+        match self.load_user_config() {
+            Ok(config) => {
+                hooks::run(&config.hooks, event, vars);
+            }
+            Err(e) => eprintln!("Not running hooks for {}: {}", event.name(), e),
+        }
+    }
+
+    fn has_hooks(&self, event: Event) -> bool {
+        let config = self.load_user_config().unwrap_or_default();
+        hooks::any_for(&config.hooks, event)
+    }
+
     pub fn get_current_userid(&self) -> Result<Option<Id>> {
         let config = self.load_user_config()?;
         Ok(config.current_id)
@@ -215,9 +411,119 @@ impl Local {
             .ok_or_else(|| format_err!("Current Id not set"))
     }
 
+    /// Fail if own `locked` id was revoked; see `compromise`
+    fn ensure_not_revoked(&self, locked: &id::LockedId) -> Result<()> {
+        let id = &locked.to_pubid().id;
+        let directory =
+            IdDirectory::read_from_dir(&self.get_proofs_dir_path_for_url(&locked.url)?)?;
+        if let Some(IdStatus::Revoked) = directory
+            .as_ref()
+            .and_then(|directory| directory.get(id))
+            .map(|entry| entry.status)
+        {
+            bail!("Id {} was revoked, as compromised", id);
+        }
+        Ok(())
+    }
+
+    /// Unlock own `id`, unless it was revoked; see `compromise`
     pub fn read_unlocked_id(&self, id: &Id, passphrase_callback: PassphraseFn) -> Result<OwnId> {
         let locked = self.read_locked_id(id)?;
-        locked.to_unlocked(passphrase_callback)
+        self.ensure_not_revoked(&locked)?;
+        if !self.load_user_config()?.use_keychain {
+            return locked.to_unlocked(passphrase_callback);
+        }
+
+        if let Some(passphrase) = util::keychain::get_passphrase(id) {
+            let passphrase = ClearOnDrop::new(passphrase);
+            if let Some(own_id) = locked.try_unlock(&passphrase)? {
+                return Ok(own_id);
+            }
+            eprintln!("Passphrase stored in the keychain is incorrect.");
+        }
+
+        // remember whatever passphrase turns out to work
+        let entered = RefCell::new(None);
+        let own_id = locked.to_unlocked(&|| {
+            let passphrase = passphrase_callback()?;
+            *entered.borrow_mut() = Some(ClearOnDrop::new(passphrase.clone()));
+            Ok(passphrase)
+        })?;
+        if let Some(passphrase) = entered.borrow().as_ref() {
+            self.store_passphrase_in_keychain(id, passphrase);
+        }
+        Ok(own_id)
+    }
+
+    fn store_passphrase_in_keychain(&self, id: &Id, passphrase: &str) {
+        if let Err(e) = util::keychain::store_passphrase(id, passphrase) {
+            eprintln!("Couldn't store passphrase in the keychain: {}", e);
+        }
+    }
+
+    /// Remove the passphrase of the current id from the keychain
+    ///
+    /// Works whether `use-keychain` is on or not, to clean up after it was
+    /// turned off; returns whether there was a passphrase to remove.
+    pub fn forget_current_id_passphrase(&self) -> Result<bool> {
+        let id = self
+            .get_current_userid()?
+            .ok_or_else(|| format_err!("Current Id not set"))?;
+        if util::keychain::get_passphrase(&id).is_none() {
+            return Ok(false);
+        }
+        util::keychain::forget_passphrase(&id)?;
+        Ok(true)
+    }
+
+    /// Re-encrypt the current id using KDF parameters from the user config
+    pub fn rekey_current_id(&self, passphrase_callback: PassphraseFn) -> Result<()> {
+        let locked = self.read_current_locked_id()?;
+        let kdf_params = self.load_user_config()?.kdf.unwrap_or_default();
+
+        if locked.kdf_params() == kdf_params {
+            eprintln!("CrevID is already locked with the configured parameters.");
+            return Ok(());
+        }
+
+        let passphrase = ClearOnDrop::new(passphrase_callback()?);
+        let own_id = locked
+            .try_unlock(&passphrase)?
+            .ok_or_else(|| format_err!("incorrect passphrase"))?;
+        self.relock_id(&own_id, &passphrase)
+    }
+
+    /// Change the passphrase protecting the current id
+    ///
+    /// The old passphrase is always asked for, even if the keychain has
+    /// it, and the new one only after the id was unlocked with it.
+    pub fn change_current_id_passphrase(
+        &self,
+        passphrase_callback: PassphraseFn,
+        new_passphrase_callback: NewPassphraseFn,
+    ) -> Result<()> {
+        let locked = self.read_current_locked_id()?;
+        self.ensure_not_revoked(&locked)?;
+        let passphrase = ClearOnDrop::new(passphrase_callback()?);
+        let own_id = locked
+            .try_unlock(&passphrase)?
+            .ok_or_else(|| format_err!("incorrect passphrase"))?;
+        let new_passphrase = new_passphrase_callback()?;
+        self.relock_id(&own_id, &new_passphrase)
+    }
+
+    /// Lock `own_id` with `passphrase` and replace the stored one
+    ///
+    /// The passphrase in the keychain is replaced as well, if it's used.
+    fn relock_id(&self, own_id: &OwnId, passphrase: &str) -> Result<()> {
+        let user_config = self.load_user_config()?;
+        let kdf_params = user_config.kdf.unwrap_or_default();
+        let locked = id::LockedId::from_own_id_with_kdf_params(own_id, passphrase, &kdf_params)?;
+        self.save_locked_id(&locked)?;
+        if user_config.use_keychain {
+            self.store_passphrase_in_keychain(own_id.as_ref(), passphrase);
+        }
+        Ok(())
     }
 
     pub fn save_locked_id(&self, id: &id::LockedId) -> Result<()> {
@@ -260,7 +566,15 @@ impl Local {
 
         self.ensure_proofs_root_exists()?;
 
-        match git2::Repository::clone(git_https_url, &proof_dir) {
+        let config = self.load_user_config()?;
+        let credentials = config.credentials_for(git_https_url);
+        match util::git::clone_git_repo(
+            git_https_url,
+            &proof_dir,
+            credentials,
+            config.fetch_rate_limit,
+            config.fetch_deadline(),
+        ) {
             Ok(repo) => {
                 eprintln!("{} cloned to {}", git_https_url, proof_dir.display());
                 repo.remote_set_url("origin", &push_url)?;
@@ -282,6 +596,23 @@ impl Local {
         Ok(())
     }
 
+    /// Update `ids.yaml` in the proof repository; see `id_directory`
+    fn update_id_directory(&self) -> Result<()> {
+        let current = self.read_current_locked_id()?.to_pubid();
+        let own_ids: Vec<_> = self
+            .list_ids()?
+            .into_iter()
+            .filter(|pub_id| pub_id.url == current.url)
+            .collect();
+
+        let proof_dir = self.get_proofs_dir_path()?;
+        let mut directory = IdDirectory::read_from_dir(&proof_dir)?.unwrap_or_default();
+        directory.update(&own_ids, &current.id);
+        directory.store_to_dir(&proof_dir)?;
+        self.proof_dir_git_add_path(Path::new(id_directory::IDS_FILE_NAME))?;
+        Ok(())
+    }
+
     // Get path relative to `get_proofs_dir_path` to store the `proof`
     fn get_proof_rel_store_path(&self, proof: &proof::Proof) -> PathBuf {
         crate::proof::rel_store_path(&proof.content)
@@ -326,199 +657,1485 @@ impl Local {
         passphrase_callback: PassphraseFn,
         trust_or_distrust: crate::TrustOrDistrust,
     ) -> Result<()> {
+        self.build_trust_proof_with_level(
+            id_strings,
+            passphrase_callback,
+            if trust_or_distrust.is_trust() {
+                TrustLevel::Medium
+            } else {
+                TrustLevel::Distrust
+            },
+        )
+    }
+
+    /// Like `build_trust_proof`, but with a specific default `TrustLevel`
+    pub fn build_trust_proof_with_level(
+        &self,
+        id_strings: Vec<String>,
+        passphrase_callback: PassphraseFn,
+        trust_level: TrustLevel,
+    ) -> Result<()> {
+        self.create_trust_proof(id_strings, trust_level, "".into(), passphrase_callback)?;
+        Ok(())
+    }
+
+    /// Create a Trust Proof of `id_strings`, sign it with the current id
+    /// and store it, like `Repo::commit` does with reviews
+    ///
+    /// `trust_level` and `comment` pre-fill the draft, which is edited
+    /// interactively before signing. URLs of the ids are looked up in the
+    /// proofs fetched so far.
+    pub fn create_trust_proof(
+        &self,
+        id_strings: Vec<String>,
+        trust_level: TrustLevel,
+        comment: String,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<proof::Proof> {
         if id_strings.is_empty() {
             bail!("No ids given.");
         }
 
-        let mut db = crate::ProofDB::new();
-        db.import_from_iter(self.proofs_iter()?);
-        db.import_from_iter(proofs_iter_for_path(self.cache_remotes_path()));
-        let mut pub_ids = vec![];
+        let pub_ids = lookup_pub_ids(&self.load_proof_db()?, id_strings)?;
 
-        for id_string in id_strings {
-            let id = Id::crevid_from_str(&id_string)?;
+        let pub_id = self.read_current_locked_id()?.to_pubid();
+        let mut trust = pub_id.create_trust_proof(pub_ids, trust_level)?;
+        trust.comment = comment;
 
-            if let Some(url) = db.lookup_url(&id) {
-                pub_ids.push(PubId::new(id, url.to_owned()));
-            } else {
-                bail!(
-                    "URL not found for Id {}; Fetch proofs with `fetch url <url>` first",
-                    id_string
-                )
-            }
+        let trust = util::edit_proof_content_iteractively(&trust.into())?;
+
+        // unlock only for signing, to keep the secret key in memory for as short as possible
+        let id = self.read_current_unlocked_id(passphrase_callback)?;
+        let proof = trust.sign_by(&id)?;
+
+        self.insert_own(&proof, &id)?;
+        Ok(proof)
+    }
+
+    /// Revoke own proofs, by their digests (or unambiguous prefixes of
+    /// them)
+    ///
+    /// Once published, the revoked proofs are ignored by everyone; see
+    /// `proof::revocation`.
+    pub fn create_revocation_proof(
+        &self,
+        digests: Vec<String>,
+        comment: String,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<proof::Proof> {
+        if digests.is_empty() {
+            bail!("No proofs given.");
         }
 
-        let own_id = self.read_current_unlocked_id(passphrase_callback)?;
+        let pub_id = self.read_current_locked_id()?.to_pubid();
+        let mut revoked = vec![];
+        for digest in digests {
+            let proof = self.find_proof_by_digest(&digest)?;
+            proof.verify()?;
+            if proof.content.author_id() != pub_id.id {
+                bail!("Proof {} is not yours to revoke", digest);
+            }
+            revoked.push(proof::revocation::RevokedProof {
+                digest: proof.digest,
+            });
+        }
 
-        let trust = own_id.create_trust_proof(
-            pub_ids,
-            if trust_or_distrust.is_trust() {
-                TrustLevel::Medium
-            } else {
-                TrustLevel::Distrust
-            },
-        )?;
+        let revocation = proof::revocation::RevocationBuilder::default()
+            .from(pub_id)
+            .proofs(revoked)
+            .comment(comment)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
 
-        let trust = util::edit_proof_content_iteractively(&trust.into())?;
+        let revocation = util::edit_proof_content_iteractively(&revocation.into())?;
 
-        let proof = trust.sign_by(&own_id)?;
+        // unlock only for signing, to keep the secret key in memory for as short as possible
+        let id = self.read_current_unlocked_id(passphrase_callback)?;
+        let proof = revocation.sign_by(&id)?;
 
-        self.insert(&proof)?;
-        Ok(())
+        self.insert_own(&proof, &id)?;
+        Ok(proof)
     }
 
-    pub fn fetch_url(&self, url: &str) -> Result<()> {
-        let _success = util::err_eprint_and_ignore(self.fetch_remote_git(url).compat());
-        Ok(())
+    /// Edit own proof with `digest` (or an unambiguous prefix of it), and
+    /// sign the result as a new proof superseding it
+    ///
+    /// Once published, the new proof counts instead of the old one; see
+    /// `Content::supersedes`.
+    pub fn amend_proof(
+        &self,
+        digest: &str,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<proof::Proof> {
+        let pub_id = self.read_current_locked_id()?.to_pubid();
+        let proof = self.find_proof_by_digest(digest)?;
+        proof.verify()?;
+        if proof.content.author_id() != pub_id.id {
+            bail!("Proof {} is not yours to amend", digest);
+        }
+        let content = match proof.content.superseding(&proof.digest, crev_common::now()) {
+            Some(content) => content,
+            None => bail!("Only Trust, Code Review and Package Review Proofs can be amended"),
+        };
+        let content = util::edit_proof_content_iteractively(&content)?;
+
+        let id = self.read_current_unlocked_id(passphrase_callback)?;
+        let proof = content.sign_by(&id)?;
+        self.insert_own(&proof, &id)?;
+        Ok(proof)
     }
 
-    pub fn fetch_trusted(&self, trust_params: crate::TrustDistanceParams) -> Result<()> {
-        let mut already_fetched = HashSet::new();
-        let mut db = crate::ProofDB::new();
-        db.import_from_iter(self.proofs_iter()?);
-        db.import_from_iter(proofs_iter_for_path(self.cache_remotes_path()));
-        let user_config = self.load_user_config()?;
-        let user_id = user_config.get_current_userid()?;
+    /// Own proofs that have expired, and weren't reissued since
+    ///
+    /// A proof counts as reissued if there's a later own proof that's the
+    /// same but for the dates; see `Content::same_besides_validity`.
+    pub fn expired_own_proofs(&self) -> Result<Vec<proof::Proof>> {
+        let pub_id = self.read_current_locked_id()?.to_pubid();
+        let own: Vec<_> = self
+            .proofs_iter()?
+            .filter(|proof| proof.content.author_id() == pub_id.id)
+            .collect();
+        let now = chrono::Utc::now();
+        Ok(own
+            .iter()
+            .filter(|proof| proof.content.is_expired_at(&now))
+            .filter(|proof| {
+                !own.iter().any(|other| {
+                    other.content.date() > proof.content.date()
+                        && other.content.same_besides_validity(&proof.content)
+                })
+            })
+            .cloned()
+            .collect())
+    }
 
-        let mut something_was_fetched = true;
-        while something_was_fetched {
-            something_was_fetched = false;
-            let trust_set =
-                db.calculate_trust_set(user_config.get_current_userid()?, &trust_params);
+    /// Sign and store a fresh copy of each of `proofs`, dated now and valid
+    /// for as long as the original was
+    pub fn reissue_proofs(
+        &self,
+        proofs: &[proof::Proof],
+        passphrase_callback: PassphraseFn,
+    ) -> Result<Vec<proof::Proof>> {
+        let id = self.read_current_unlocked_id(passphrase_callback)?;
+        let mut reissued = vec![];
+        for proof in proofs {
+            let content = match proof.content.reissued_at(crev_common::now()) {
+                Some(content) => content,
+                None => bail!(
+                    "Proof {} doesn't expire",
+                    crev_common::base64_encode(&proof.digest)
+                ),
+            };
+            let proof = content.sign_by(&id)?;
+            self.insert_own(&proof, &id)?;
+            reissued.push(proof);
+        }
+        Ok(reissued)
+    }
 
-            for id in trust_set.trusted_ids() {
-                if already_fetched.contains(id) {
-                    continue;
+    /// Revoke the current id, as its key leaked; see `compromise`
+    ///
+    /// The proofs of the id dated from `since` on are revoked, all of them
+    /// if not set, with `comment`. The backup id, if configured, is
+    /// unlocked with `backup_passphrase_callback` before anything is done.
+    pub fn respond_to_compromise(
+        &self,
+        since: Option<chrono::DateTime<chrono::FixedOffset>>,
+        comment: String,
+        passphrase_callback: PassphraseFn,
+        backup_passphrase_callback: PassphraseFn,
+    ) -> Result<CompromiseResponse> {
+        let pub_id = self.read_current_locked_id()?.to_pubid();
+        let backup = match self.load_user_config()?.backup_id {
+            Some(backup_id) => {
+                if backup_id == pub_id.id {
+                    bail!("The backup id is the compromised one");
+                }
+                let backup = self.read_unlocked_id(&backup_id, backup_passphrase_callback)?;
+                if backup.id.url != pub_id.url {
+                    bail!(
+                        "Backup id {} doesn't publish to the proof repository of the compromised one",
+                        backup_id
+                    );
+                }
+                Some(backup)
+            }
+            None => None,
+        };
+        let id = self.read_unlocked_id(&pub_id.id, passphrase_callback)?;
+
+        let own: Vec<_> = self
+            .proofs_iter()?
+            .filter(|proof| proof.content.author_id() == pub_id.id)
+            .collect();
+        let already_revoked: HashSet<&[u8]> = own
+            .iter()
+            .filter_map(|proof| match proof.content {
+                proof::Content::Revocation(ref revocation) => Some(revocation),
+                _ => None,
+            })
+            .flat_map(|revocation| revocation.proofs.iter().map(|revoked| &revoked.digest[..]))
+            .collect();
+        let in_effect: Vec<_> = own
+            .iter()
+            .filter(|proof| match proof.content {
+                proof::Content::Revocation(_) => false,
+                _ => !already_revoked.contains(&proof.digest[..]),
+            })
+            .collect();
+
+        let revoked: Vec<_> = in_effect
+            .iter()
+            .filter(|proof| match since {
+                Some(since) => *proof.content.date() >= since,
+                None => true,
+            })
+            .map(|proof| proof::revocation::RevokedProof {
+                digest: proof.digest.clone(),
+            })
+            .collect();
+        let revoked_count = revoked.len();
+        let revocation = if revoked.is_empty() {
+            None
+        } else {
+            let revocation = proof::revocation::RevocationBuilder::default()
+                .from(pub_id.clone())
+                .proofs(revoked)
+                .comment(if comment.is_empty() {
+                    "The key of this id was compromised".to_owned()
                 } else {
-                    already_fetched.insert(id.to_owned());
+                    comment
+                })
+                .build()
+                .map_err(|e| format_err!("{}", e))?;
+            let proof = proof::Content::from(revocation).sign_by(&id)?;
+            self.insert_own(&proof, &id)?;
+            Some(proof)
+        };
+
+        let proof_dir = self.get_proofs_dir_path()?;
+        let mut directory = IdDirectory::read_from_dir(&proof_dir)?.unwrap_or_default();
+        directory.revoke(&pub_id);
+        directory.store_to_dir(&proof_dir)?;
+        self.proof_dir_git_add_path(Path::new(id_directory::IDS_FILE_NAME))?;
+        self.audit_log().append(
+            audit_log::Action::IdRevoked {
+                id: pub_id.id.clone(),
+            },
+            Some(&id),
+        )?;
+        drop(id);
+
+        let mut resigned = vec![];
+        if let Some(ref backup) = backup {
+            // the latest trust of the compromised id in each id, in effect
+            // until revoked above
+            let now = chrono::Utc::now();
+            let mut latest: HashMap<&Id, &proof::Proof> = HashMap::new();
+            for proof in &in_effect {
+                let trust = match proof.content {
+                    proof::Content::Trust(ref trust) if !proof.content.is_expired_at(&now) => trust,
+                    _ => continue,
+                };
+                for trusted in &trust.ids {
+                    let newer = match latest.get(&trusted.id) {
+                        Some(other) => other.content.date() < proof.content.date(),
+                        None => true,
+                    };
+                    if newer {
+                        latest.insert(&trusted.id, proof);
+                    }
                 }
-                if user_id == id {
+            }
+            let mut sources: Vec<_> = in_effect
+                .iter()
+                .filter(|proof| latest.values().any(|latest| latest.digest == proof.digest))
+                .collect();
+            sources.sort_by_key(|proof| *proof.content.date());
+            for source in sources {
+                let mut trust = match source.content {
+                    proof::Content::Trust(ref trust) => trust.clone(),
+                    _ => continue,
+                };
+                trust.ids.retain(|trusted| {
+                    latest.get(&trusted.id).map(|latest| &latest.digest) == Some(&source.digest)
+                        && trusted.id != pub_id.id
+                        && trusted.id != backup.id.id
+                });
+                if trust.ids.is_empty() {
                     continue;
-                } else if let Some(url) = db.lookup_url(id) {
-                    let success =
-                        util::err_eprint_and_ignore(self.fetch_remote_git(&url.url).compat());
-                    if success {
-                        something_was_fetched = true;
-                        db.import_from_iter(proofs_iter_for_path(
-                            self.get_remote_git_cache_path(&url.url),
-                        ));
-                    }
-                } else {
-                    eprintln!("No URL for {}", id);
                 }
+                trust.from = backup.id.clone();
+                trust.date = crev_common::now();
+                let proof = proof::Content::from(trust).sign_by(backup)?;
+                self.insert_own(&proof, backup)?;
+                resigned.push(proof);
             }
+            self.save_current_id(backup.as_ref())?;
+            self.update_id_directory()?;
         }
-        Ok(())
+
+        Ok(CompromiseResponse {
+            id: pub_id.id,
+            revocation,
+            revoked: revoked_count,
+            backup_id: backup.map(|backup| backup.id.id.clone()),
+            resigned,
+        })
     }
 
-    fn fetch_all_ids_recursively(&self, mut already_fetched_urls: HashSet<String>) -> Result<()> {
-        let mut already_fetched = HashSet::new();
-        let mut db = crate::ProofDB::new();
-        db.import_from_iter(self.proofs_iter()?);
-        db.import_from_iter(proofs_iter_for_path(self.cache_remotes_path()));
-        let user_config = self.load_user_config()?;
-        let user_id = user_config.get_current_userid()?;
+    /// Gather what is known about an Id, for the trust wizard
+    ///
+    /// Includes at most `max_proofs` of the most recent proofs it authored.
+    pub fn trust_wizard_info(&self, id_string: &str, max_proofs: usize) -> Result<TrustWizardInfo> {
+        let id = Id::crevid_from_str(id_string)?;
+        let (db, trust_set) = self.load_db(&default())?;
 
-        let mut something_was_fetched = true;
-        while something_was_fetched {
-            something_was_fetched = false;
+        let url = if let Some(url) = db.lookup_url(&id) {
+            url.to_owned()
+        } else {
+            bail!(
+                "URL not found for Id {}; Fetch proofs with `fetch url <url>` first",
+                id_string
+            )
+        };
 
-            for id in &db.all_known_ids() {
-                if already_fetched.contains(id) {
-                    continue;
-                } else {
-                    already_fetched.insert(id.to_owned());
-                }
-                if user_id == id {
-                    continue;
-                } else if let Some(url) = db.lookup_url(id) {
-                    let url = url.url.to_string();
+        let mut recent_proofs: Vec<_> = self
+            .proofs_iter()?
+            .chain(remote_proofs_iter(self.cache_remotes_path()))
+            .filter(|proof| proof.content.author_id() == id)
+            .collect();
+        // by digest too, so copies of a proof end up next to each other
+        recent_proofs.sort_by(|a, b| {
+            b.content
+                .date()
+                .cmp(a.content.date())
+                .then_with(|| a.digest.cmp(&b.digest))
+        });
+        recent_proofs.dedup_by(|a, b| a.digest == b.digest);
+        recent_proofs.truncate(max_proofs);
+
+        Ok(TrustWizardInfo {
+            current_trust: trust_set.get_effective_trust_level(&id),
+            trust_paths: trust_set.trust_paths_to(&id),
+            pub_id: PubId::new(id, url),
+            recent_proofs,
+        })
+    }
 
-                    if already_fetched_urls.contains(&url) {
-                        continue;
-                    } else {
-                        already_fetched_urls.insert(url.clone());
-                    }
+    /// Gather what is shown on the dashboard
+    ///
+    /// `scopes` are the verification results of the current project, if
+    /// any. Includes at most `max_proofs` of the most recent fetched proofs,
+    /// and as many of the most recent advisories by ids trusted for them.
+    pub fn dashboard_info(
+        &self,
+        scopes: Vec<(PathBuf, crate::VerificationStatus)>,
+        max_proofs: usize,
+    ) -> Result<DashboardInfo> {
+        let current_id = self.get_current_userid()?;
+        let (db, trust_set) = self.load_db(&default())?;
+
+        let mut trusted: Vec<_> = trust_set
+            .trusted_ids()
+            .filter_map(|id| {
+                Some(TrustedId {
+                    id: id.clone(),
+                    url: db.lookup_url(id).cloned(),
+                    level: trust_set.get_effective_trust_level(id)?,
+                    path: trust_set
+                        .trust_paths_to(id)
+                        .into_iter()
+                        .next()
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+        trusted.sort_by(|a, b| b.level.cmp(&a.level).then(a.path.len().cmp(&b.path.len())));
+
+        let mut recent_proofs: Vec<_> = remote_proofs_iter(self.cache_remotes_path())
+            .filter(|proof| Some(&proof.content.author_id()) != current_id.as_ref())
+            .collect();
+        // by digest too, so copies of a proof end up next to each other
+        recent_proofs.sort_by(|a, b| {
+            b.content
+                .date()
+                .cmp(a.content.date())
+                .then_with(|| a.digest.cmp(&b.digest))
+        });
+        recent_proofs.dedup_by(|a, b| a.digest == b.digest);
+        recent_proofs.truncate(max_proofs);
+
+        let mut advisories: Vec<_> = db
+            .get_all_advisories()
+            .filter(|advisory| {
+                trust_set
+                    .get_effective_trust_level_for(&advisory.from.id, TrustDimension::Advisories)
+                    .is_some()
+            })
+            .cloned()
+            .collect();
+        advisories.sort_by(|a, b| b.date.cmp(&a.date));
+        advisories.truncate(max_proofs);
+
+        Ok(DashboardInfo {
+            current_id,
+            scopes,
+            trusted,
+            recent_proofs,
+            advisories,
+        })
+    }
 
-                    let success = util::err_eprint_and_ignore(self.fetch_remote_git(&url).compat());
-                    if success {
-                        something_was_fetched = true;
-                        db.import_from_iter(proofs_iter_for_path(
-                            self.get_remote_git_cache_path(&url),
-                        ));
-                    }
-                } else {
-                    eprintln!("No URL for {}", id);
-                }
-            }
+    /// Link the current id and `other_id`, another own id, to each other;
+    /// see `proof::IdLink`
+    ///
+    /// Trust in the current id can then extend to `other_id` up to
+    /// `level`, and trust in `other_id` to the current id up to
+    /// `back_level`. Both links are signed and stored, one by each id.
+    pub fn link_own_ids(
+        &self,
+        other_id: &Id,
+        level: TrustLevel,
+        back_level: TrustLevel,
+        comment: String,
+        passphrase_callback: PassphraseFn,
+        other_passphrase_callback: PassphraseFn,
+    ) -> Result<Vec<proof::Proof>> {
+        let current = self.read_current_unlocked_id(passphrase_callback)?;
+        if current.id.id == *other_id {
+            bail!("Id can't link to itself");
         }
-        Ok(())
+        let other = self.read_unlocked_id(other_id, other_passphrase_callback)?;
+        let mut proofs = vec![];
+        for (from, to, level) in &[(&current, &other, level), (&other, &current, back_level)] {
+            let proof = proof::id_link::IdLinkBuilder::default()
+                .from(from.id.to_owned())
+                .to(to.id.to_owned())
+                .level(*level)
+                .comment(comment.clone())
+                .build()
+                .map_err(|e| format_err!("{}", e))?
+                .sign_by(from)?;
+            self.insert_own(&proof, from)?;
+            proofs.push(proof);
+        }
+        Ok(proofs)
     }
 
-    pub fn get_remote_git_cache_path(&self, url: &str) -> PathBuf {
-        let digest = crev_common::blake2b256sum(url.as_bytes());
-        let digest = crev_data::Digest::from_vec(digest);
-        self.cache_remotes_path().join(digest.to_string())
+    /// Create a self-signed bundle of the current id, to send to others
+    ///
+    /// `capabilities` restrict what proofs of the id count for others; see
+    /// `id_bundle::Capability`.
+    pub fn export_current_pub_id(
+        &self,
+        name: String,
+        comment: String,
+        capabilities: Option<Vec<Capability>>,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<SignedPubIdBundle> {
+        let pub_id = self.read_current_locked_id()?.to_pubid();
+        let bundle = PubIdBundleBuilder::default()
+            .id(pub_id)
+            .name(name)
+            .comment(comment)
+            .capabilities(capabilities)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        bundle.sign_by(&self.read_current_unlocked_id(passphrase_callback)?)
     }
 
-    pub fn fetch_remote_git(&self, url: &str) -> Result<()> {
-        let dir = self.get_remote_git_cache_path(url);
-
-        if dir.exists() {
-            eprintln!("Fetching {} to {}", url, dir.display());
-            let repo = git2::Repository::open(dir)?;
-            util::git::fetch_and_checkout_git_repo(&repo)?
-        } else {
-            eprintln!("Cloning {} to {}", url, dir.display());
-            git2::Repository::clone(url, dir)?;
+    /// Remember the URL of someone else's id from their bundle
+    ///
+    /// A bundle older than an already imported one for the same id is rejected,
+    /// and so is one lifting restrictions of its capabilities, as a stolen key
+    /// could be used to sign it.
+    pub fn import_pub_id(&self, bundle: &SignedPubIdBundle) -> Result<()> {
+        let id = &bundle.bundle.id.id;
+        if let Some(existing) = self
+            .list_imported_pub_ids()?
+            .into_iter()
+            .find(|existing| existing.bundle.id.id == *id)
+        {
+            if existing.bundle.date > bundle.bundle.date {
+                bail!("A more recent bundle for Id {} was already imported", id);
+            }
+            if let Some(lifted) = Capability::all().iter().find(|&&capability| {
+                bundle.bundle.allows(capability) && !existing.bundle.allows(capability)
+            }) {
+                bail!(
+                    "Bundle for Id {} would lift its restriction of the {} capability",
+                    id,
+                    lifted
+                );
+            }
         }
 
+        let path = self.imported_ids_path().join(format!("{}.crev", id));
+        util::store_str_to_file(&path, &bundle.to_string())?;
         Ok(())
     }
 
-    pub fn fetch_all(&self) -> Result<()> {
-        let mut fetched_urls = HashSet::new();
-        for entry in fs::read_dir(self.cache_remotes_path())? {
-            let path = entry?.path();
-            if !path.is_dir() {
-                continue;
-            }
+    pub fn list_imported_pub_ids(&self) -> Result<Vec<SignedPubIdBundle>> {
+        read_dir_parsed(
+            &self.imported_ids_path(),
+            "id bundle",
+            SignedPubIdBundle::parse,
+        )
+    }
 
-            let repo = git2::Repository::open(&path);
-            if repo.is_err() {
-                continue;
-            }
+    /// Where seed lists imported with `import_seed_list` are kept
+    pub fn seed_lists_path(&self) -> PathBuf {
+        self.user_dir_path().join("seeds")
+    }
 
-            let url = {
-                || -> Result<String> {
-                    let repo = repo.unwrap();
-                    let remote = repo.find_remote("origin")?;
-                    let url = remote
-                        .url()
-                        .ok_or_else(|| format_err!("origin has no url"))?;
-                    Ok(url.to_string())
-                }
-            }();
+    fn seed_list_path(&self, curator: &Id) -> PathBuf {
+        self.seed_lists_path().join(format!("{}.crev", curator))
+    }
 
-            match url {
-                Ok(url) => {
-                    fetched_urls.insert(url.clone());
-                    let _success =
-                        util::err_eprint_and_ignore(self.fetch_remote_git(&url).compat());
-                }
-                Err(e) => {
-                    eprintln!("ERR: {} {}", path.display(), e);
-                }
-            }
+    /// Create a seed list of `id_strings`, signed by the current id
+    pub fn create_seed_list(
+        &self,
+        name: String,
+        id_strings: Vec<String>,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<SignedSeedList> {
+        if id_strings.is_empty() {
+            bail!("No ids given.");
         }
+        let pub_ids = lookup_pub_ids(&self.load_proof_db()?, id_strings)?;
+        let list = SeedListBuilder::default()
+            .from(self.read_current_locked_id()?.to_pubid())
+            .name(name)
+            .ids(pub_ids.into_iter().map(Into::into).collect())
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        list.sign_by(&self.read_current_unlocked_id(passphrase_callback)?)
+    }
 
-        self.fetch_all_ids_recursively(fetched_urls)?;
+    /// Import a seed list, replacing any older one by the same curator
+    pub fn import_seed_list(&self, list: &SignedSeedList) -> Result<()> {
+        let curator = &list.list.from.id;
+        let path = self.seed_list_path(curator);
+        if path.exists() {
+            let existing = SignedSeedList::parse(&util::read_file_to_string(&path)?)?;
+            if existing.list.date > list.list.date {
+                bail!(
+                    "A more recent seed list by {} was already imported",
+                    curator
+                );
+            }
+        }
+        util::store_str_to_file(&path, &list.to_string())?;
+        Ok(())
+    }
 
+    pub fn remove_seed_list(&self, curator_str: &str) -> Result<()> {
+        let path = self.seed_list_path(&Id::crevid_from_str(curator_str)?);
+        if !path.exists() {
+            bail!("No seed list by {} imported", curator_str);
+        }
+        fs::remove_file(path)?;
         Ok(())
     }
 
-    pub fn run_git(&self, args: Vec<OsString>) -> Result<std::process::ExitStatus> {
-        let orig_dir = std::env::current_dir()?;
+    pub fn list_seed_lists(&self) -> Result<Vec<SignedSeedList>> {
+        read_dir_parsed(&self.seed_lists_path(), "seed list", SignedSeedList::parse)
+    }
+
+    /// Where the last organization policy fetched is kept; see `org_policy`
+    fn org_policy_path(&self) -> PathBuf {
+        self.user_dir_path().join("org-policy.crev")
+    }
+
+    /// Create an organization policy, signed by the current id
+    pub fn create_org_policy(
+        &self,
+        name: String,
+        required_reviews: Vec<RequiredReviews>,
+        seed_strings: Vec<String>,
+        banned: Vec<BannedId>,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<SignedOrgPolicy> {
+        let seeds = lookup_pub_ids(&self.load_proof_db()?, seed_strings)?;
+        let policy = OrgPolicyBuilder::default()
+            .from(self.read_current_locked_id()?.to_pubid())
+            .name(name)
+            .required_reviews(required_reviews)
+            .seeds(seeds.into_iter().map(Into::into).collect())
+            .banned(banned)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        policy.sign_by(&self.read_current_unlocked_id(passphrase_callback)?)
+    }
+
+    /// The organization policy in effect, if any
+    ///
+    /// That's the last one fetched, as long as it's by the curator
+    /// configured now.
+    pub fn load_org_policy(&self) -> Result<Option<SignedOrgPolicy>> {
+        let source = match self.load_user_config()?.org_policy {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+        let path = self.org_policy_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let policy = SignedOrgPolicy::parse(&util::read_file_to_string(&path)?)?;
+        Ok(if policy.policy.from.id == source.curator {
+            Some(policy)
+        } else {
+            None
+        })
+    }
+
+    /// Fetch the configured organization policy, and keep it if it's
+    /// signed by the configured curator, and isn't older than the one
+    /// kept already
+    ///
+    /// Returns the policy fetched, or `None` if none is configured.
+    pub fn fetch_org_policy(&self) -> Result<Option<SignedOrgPolicy>> {
+        let config = self.load_user_config()?;
+        let source = match config.org_policy {
+            Some(ref source) => source,
+            None => return Ok(None),
+        };
+        let digest = crev_data::Digest::from_vec(crev_common::blake2b256sum(source.url.as_bytes()));
+        let dir = self.cache_path.join("org-policy").join(digest.to_string());
+        let credentials = config.credentials_for(&source.url);
+        if dir.exists() {
+            eprintln!("Fetching organization policy from {}", source.url);
+            let repo = git2::Repository::open(&dir)?;
+            util::git::fetch_and_checkout_git_repo(
+                &repo,
+                credentials,
+                config.fetch_rate_limit,
+                config.fetch_deadline(),
+            )?;
+        } else {
+            eprintln!("Cloning organization policy from {}", source.url);
+            fs::create_dir_all(self.cache_path.join("org-policy"))?;
+            util::git::clone_git_repo(
+                &source.url,
+                &dir,
+                credentials,
+                config.fetch_rate_limit,
+                config.fetch_deadline(),
+            )?;
+        }
+
+        let policy = SignedOrgPolicy::parse(&util::read_file_to_string(
+            &dir.join(org_policy::POLICY_FILE_NAME),
+        )?)?;
+        if policy.policy.from.id != source.curator {
+            bail!(
+                "Organization policy at {} is signed by {}, not the curator {}",
+                source.url,
+                policy.policy.from.id,
+                source.curator
+            );
+        }
+        if let Some(existing) = self.load_org_policy()? {
+            if existing.policy.date > policy.policy.date {
+                bail!(
+                    "Organization policy at {} is older than the one fetched before",
+                    source.url
+                );
+            }
+        }
+        util::store_str_to_file(&self.org_policy_path(), &policy.to_string())?;
+        Ok(Some(policy))
+    }
+
+    /// Where proofs decrypted from `crev_data::encrypted` envelopes are kept
+    ///
+    /// Only in the local cache, as they must not be published.
+    pub fn decrypted_proofs_path(&self) -> PathBuf {
+        self.cache_path.join("decrypted")
+    }
+
+    /// Where proofs imported from bundles are kept; see `import_bundle`
+    ///
+    /// Only in the local cache, like decrypted ones.
+    pub fn imported_proofs_path(&self) -> PathBuf {
+        self.cache_path.join("imported")
+    }
+
+    /// Set the ids own proofs get encrypted to; none to stop encrypting
+    pub fn set_encrypt_to(&self, id_strings: Vec<String>) -> Result<()> {
+        let mut config = self.load_user_config()?;
+        config.encrypt_to = id_strings
+            .iter()
+            .map(|id_string| Id::crevid_from_str(id_string))
+            .collect::<Result<_>>()?;
+        self.store_user_config(&config)
+    }
+
+    /// Store a new proof signed by `own_id`
+    ///
+    /// With `encrypt-to` set in the user config, only an encrypted copy of
+    /// it goes to the proof repository, and the plaintext to
+    /// `decrypted_proofs_path`.
+    pub fn insert_own(&self, proof: &proof::Proof, own_id: &OwnId) -> Result<()> {
+        self.insert_own_stored(proof, own_id)?;
+        let proof_type = crate::proof::type_label(&proof.content);
+        let digest = crev_common::base64_encode(&proof.digest);
+        let action = match proof.content {
+            proof::Content::Trust(ref trust) => audit_log::Action::TrustGranted {
+                digest,
+                ids: trust.ids.iter().map(|id| id.id.clone()).collect(),
+                level: trust.trust,
+            },
+            _ => audit_log::Action::ProofSigned {
+                digest,
+                proof_type: proof_type.to_owned(),
+            },
+        };
+        self.audit_log().append(action, Some(own_id))?;
+        self.write_store_manifest(Some(own_id))?;
+        self.run_hooks(
+            Event::ProofCommitted,
+            &[
+                ("PROOF_TYPE", proof_type.to_owned()),
+                ("PROOF_DIGEST", crev_common::base64_encode(&proof.digest)),
+                ("AUTHOR", own_id.id.id.to_string()),
+            ],
+        );
+        Ok(())
+    }
+
+    fn insert_own_stored(&self, proof: &proof::Proof, own_id: &OwnId) -> Result<()> {
+        let recipients = self.load_user_config()?.encrypt_to;
+        if recipients.is_empty() {
+            return self.insert(proof);
+        }
+
+        let sealed = EncryptedProof::seal(proof, own_id, &recipients)?;
+        let rel_store_path = PathBuf::from(own_id.id.id.to_string())
+            .join("encrypted")
+            .join(crev_common::base64_encode(&sealed.digest()?))
+            .with_extension(encrypted::FILE_EXTENSION);
+        util::store_str_to_file(
+            &self.get_proofs_dir_path()?.join(&rel_store_path),
+            &sealed.to_string(),
+        )?;
+        self.proof_dir_git_add_path(&rel_store_path)?;
+        self.update_id_directory()?;
+
+        self.store_decrypted(&sealed, proof)
+    }
+
+    fn store_decrypted(&self, sealed: &SignedEncryptedProof, proof: &proof::Proof) -> Result<()> {
+        let path = self
+            .decrypted_proofs_path()
+            .join(crev_common::base64_encode(&sealed.digest()?))
+            .with_extension("proof.crev");
+        util::store_str_to_file(&path, &proof.to_string())?;
+        Ok(())
+    }
+
+    /// Decrypt the encrypted proofs addressed to the current id
+    ///
+    /// Looks through own and fetched proof repositories, skipping proofs
+    /// decrypted before. Returns the number of newly decrypted proofs.
+    pub fn decrypt_proofs(&self, passphrase_callback: PassphraseFn) -> Result<usize> {
+        let current_id = self
+            .get_current_userid()?
+            .ok_or_else(|| format_err!("Current Id not set"))?;
+        let mut own_id = None;
+        let mut decrypted = 0;
+        for path in self
+            .get_proofs_dir_path_opt()?
+            .into_iter()
+            .chain(Some(self.cache_remotes_path()))
+        {
+            for sealed in encrypted_proofs_iter_for_path(path) {
+                let stored_path = self
+                    .decrypted_proofs_path()
+                    .join(crev_common::base64_encode(&sealed.digest()?))
+                    .with_extension("proof.crev");
+                if !sealed.encrypted.is_addressed_to(&current_id) || stored_path.exists() {
+                    continue;
+                }
+                if own_id.is_none() {
+                    own_id = Some(self.read_current_unlocked_id(passphrase_callback)?);
+                }
+                match sealed.open(own_id.as_ref().expect("just set")) {
+                    Ok(proof) => {
+                        self.store_decrypted(&sealed, &proof)?;
+                        decrypted += 1;
+                    }
+                    Err(e) => eprintln!(
+                        "Failed decrypting a proof of {}: {}",
+                        sealed.encrypted.from.id, e
+                    ),
+                }
+            }
+        }
+        if decrypted > 0 {
+            self.write_store_manifest(own_id.as_ref())?;
+        }
+        Ok(decrypted)
+    }
+
+    /// Import the proofs of a bundle made by `Repo::proof_bundle`, either
+    /// as they are or encrypted to the current id
+    ///
+    /// Each proof has to have a valid signature. Returns the number of
+    /// proofs not known before.
+    pub fn import_bundle(&self, bundle: &str, passphrase_callback: PassphraseFn) -> Result<usize> {
+        let mut own_id = None;
+        let proofs = if SignedEncryptedBundle::is_in(bundle) {
+            let sealed = SignedEncryptedBundle::parse(bundle)?;
+            own_id = Some(self.read_current_unlocked_id(passphrase_callback)?);
+            sealed.open(own_id.as_ref().expect("just set"))?
+        } else {
+            let proofs = proof::Proof::parse(bundle.as_bytes())?;
+            for proof in &proofs {
+                proof.verify()?;
+            }
+            proofs
+        };
+        self.store_imported(proofs, own_id.as_ref())
+    }
+
+    /// Import the proofs read from `reader` in `format`, eg. piped from
+    /// another machine or program
+    ///
+    /// Each proof has to have a valid signature. Returns the number of
+    /// proofs not known before.
+    pub fn import_proofs(&self, reader: impl io::BufRead, format: proof::Format) -> Result<usize> {
+        let proofs = proof::Proof::parse_in(reader, format)?;
+        for proof in &proofs {
+            proof.verify()?;
+        }
+        self.store_imported(proofs, None)
+    }
+
+    /// Store `proofs` in `imported_proofs_path`, returning how many weren't
+    /// already
+    fn store_imported(&self, proofs: Vec<proof::Proof>, own_id: Option<&OwnId>) -> Result<usize> {
+        let mut imported = 0;
+        for proof in proofs {
+            let path = self
+                .imported_proofs_path()
+                .join(crev_common::base64_encode(&proof.digest))
+                .with_extension("proof.crev");
+            if !path.exists() {
+                util::store_str_to_file(&path, &proof.to_string())?;
+                imported += 1;
+            }
+        }
+        if imported > 0 {
+            self.write_store_manifest(own_id)?;
+        }
+        Ok(imported)
+    }
+
+    /// Write the known proofs matching `filter` to `writer` in `format`,
+    /// oldest first, returning how many
+    ///
+    /// The decrypted copies of encrypted proofs are left out unless
+    /// `include_decrypted`, as they would be exported in plaintext.
+    pub fn export_proofs(
+        &self,
+        filter: &ProofFilter,
+        include_decrypted: bool,
+        mut writer: impl io::Write,
+        format: proof::Format,
+    ) -> Result<usize> {
+        let decrypted_path = self.decrypted_proofs_path();
+        let proofs = self.find_proofs_in_files(filter, |path| {
+            include_decrypted || !path.starts_with(&decrypted_path)
+        })?;
+        for proof in &proofs {
+            writer.write_all(proof.to_string_in(format)?.as_bytes())?;
+        }
+        writer.flush()?;
+        Ok(proofs.len())
+    }
+
+    /// Where the queue of things to review is kept; see `todo::Queue`
+    pub fn todo_path(&self) -> PathBuf {
+        self.user_dir_path().join("todo.yaml")
+    }
+
+    pub fn quarantine_path(&self) -> PathBuf {
+        self.user_dir_path().join("quarantine")
+    }
+
+    /// Suspicious fetched data; see `quarantine`
+    pub fn quarantine(&self) -> Quarantine {
+        Quarantine::new(&self.quarantine_path())
+    }
+
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.user_dir_path().join("audit-log.jsonl")
+    }
+
+    /// What this installation did; see `audit_log`
+    pub fn audit_log(&self) -> AuditLog {
+        AuditLog::new(&self.audit_log_path())
+    }
+
+    pub fn store_manifest_path(&self) -> PathBuf {
+        self.user_dir_path().join("store-manifest.json")
+    }
+
+    /// Digests of the proof files of the store; see `store_manifest`
+    pub fn store_manifest(&self) -> StoreManifest {
+        StoreManifest::new(&self.store_manifest_path())
+    }
+
+    /// The digests of the own, fetched, decrypted and imported proof files
+    fn scan_store(&self) -> Result<store_manifest::Files> {
+        let mut roots = vec![];
+        if let Some(path) = self.get_proofs_dir_path_opt()? {
+            roots.push(("own", path));
+        }
+        roots.push(("remotes", self.cache_remotes_path()));
+        roots.push(("decrypted", self.decrypted_proofs_path()));
+        roots.push(("imported", self.imported_proofs_path()));
+        store_manifest::scan(&roots)
+    }
+
+    /// Write the manifest of the store as it is now, signed by `by` if given
+    pub fn write_store_manifest(&self, by: Option<&OwnId>) -> Result<()> {
+        self.store_manifest().write(self.scan_store()?, by)
+    }
+
+    /// The manifest of the store, and how the store changed since
+    ///
+    /// Fails if there's no manifest, or it isn't properly signed by an own
+    /// id.
+    pub fn verify_store_manifest(
+        &self,
+    ) -> Result<(store_manifest::Manifest, Vec<store_manifest::Change>)> {
+        let manifest = self.store_manifest().load()?.ok_or_else(|| {
+            format_err!("No store manifest; write one with `crev store write-manifest`")
+        })?;
+        if let Err(e) = manifest.verify() {
+            bail!("The store manifest has an invalid signature: {}", e);
+        }
+        if let Some(ref by) = manifest.by {
+            if !self.list_ids()?.iter().any(|id| id.id == *by) {
+                bail!("The store manifest is signed by {}, not an own id", by);
+            }
+        }
+        let changes = manifest.changes(&self.scan_store()?);
+        Ok((manifest, changes))
+    }
+
+    /// Quarantine the proofs in `dir`, fetched from `url`, that are
+    /// ignored as suspicious, returning how many weren't already
+    fn quarantine_suspicious(&self, url: &str, dir: &Path) -> Result<usize> {
+        // an invalid `ids.yaml` gets the whole repository ignored, and reported
+        let directory = IdDirectory::read_from_dir(dir).unwrap_or(None);
+        let quarantine = self.quarantine();
+        let mut quarantined = 0;
+        let mut add = |path: &Path, reason: String, data: &str| -> Result<()> {
+            let entry = quarantine::Entry {
+                url: url.to_owned(),
+                path: path.strip_prefix(dir)?.to_owned(),
+                reason,
+                date: crev_common::now(),
+            };
+            if quarantine.add(&entry, data)?.is_some() {
+                quarantined += 1;
+            }
+            Ok(())
+        };
+        for entry in walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            let path = entry.path();
+            if !path.is_file() || path.extension() != Some("crev".as_ref()) {
+                continue;
+            }
+            let blocks = match proof::Serialized::parse(io::BufReader::new(fs::File::open(path)?)) {
+                Ok(blocks) => blocks,
+                Err(e) => {
+                    let data = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+                    add(path, format!("can't be parsed: {}", e), &data)?;
+                    continue;
+                }
+            };
+            // the signature is checked first, so forged proofs are reported
+            // as such even if they can't be parsed either
+            for block in blocks {
+                let reason = if let Err(e) = block.verify_signature() {
+                    format!("invalid signature: {}", e)
+                } else {
+                    match (block.to_parsed(), directory.as_ref()) {
+                        (Err(e), _) => format!("can't be parsed: {}", e),
+                        (Ok(ref proof), Some(directory)) if !directory.accepts(proof) => format!(
+                            "author {} is not listed in {}",
+                            proof.content.author_id(),
+                            id_directory::IDS_FILE_NAME
+                        ),
+                        _ => continue,
+                    }
+                };
+                add(path, reason, &block.to_string())?;
+            }
+        }
+        Ok(quarantined)
+    }
+
+    pub fn open_todo(&self) -> Result<todo::Queue> {
+        todo::Queue::open(&self.todo_path())
+    }
+
+    /// Complete the queued items of `kind` for `target`, after reviewing
+    /// it; see `todo::Queue::complete_matching`
+    pub fn complete_todo(&self, kind: todo::Kind, target: &str) -> Result<usize> {
+        let mut queue = self.open_todo()?;
+        let completed = queue.complete_matching(kind, target);
+        if completed > 0 {
+            queue.save()?;
+        }
+        Ok(completed)
+    }
+
+    /// Where drafts of proofs wait to be signed; see `sign_drafts`
+    pub fn drafts_path(&self) -> PathBuf {
+        self.user_dir_path().join("drafts")
+    }
+
+    /// Queue `content` to be signed later by `sign_drafts`
+    pub fn queue_draft(&self, content: &proof::Content) -> Result<PathBuf> {
+        let text = content.to_unsigned_string();
+        let path = self
+            .drafts_path()
+            .join(crev_common::base64_encode(&crev_common::blake2b256sum(
+                text.as_bytes(),
+            )))
+            .with_extension("crev");
+        util::store_str_to_file(&path, &text)?;
+        Ok(path)
+    }
+
+    /// Files of drafts queued in `drafts_path`, oldest name first
+    pub fn queued_drafts(&self) -> Result<Vec<PathBuf>> {
+        let dir = self.drafts_path();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().map_or(false, |ext| ext == "crev") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Sign the drafts in files at `paths` that `approve` approves, with
+    /// the current id, unlocking it just once, and store them like other
+    /// own proofs
+    ///
+    /// Every draft gets parsed and checked to be by the current id
+    /// before any is shown. Files in `drafts_path` are removed once all
+    /// their drafts are signed; declined ones stay queued. Returns the
+    /// number of proofs signed.
+    pub fn sign_drafts(
+        &self,
+        paths: &[PathBuf],
+        approve: DraftApprovalFn,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<usize> {
+        let current_id = self
+            .get_current_userid()?
+            .ok_or_else(|| format_err!("Current Id not set"))?;
+        let mut drafts_by_path = vec![];
+        for path in paths {
+            let file = fs::File::open(path)?;
+            let drafts = proof::Content::parse_unsigned(std::io::BufReader::new(file))
+                .with_context(|_| format!("Parsing {}", path.display()))?;
+            for content in &drafts {
+                if content.author_id() != current_id {
+                    bail!(
+                        "Draft in {} is by {}, not the current id",
+                        path.display(),
+                        content.author_id()
+                    );
+                }
+            }
+            drafts_by_path.push((path, drafts));
+        }
+
+        let mut contents = vec![];
+        let mut fully_approved = vec![];
+        for (path, drafts) in drafts_by_path {
+            let mut all_approved = true;
+            for content in drafts {
+                if approve(path, &content)? {
+                    contents.push(content);
+                } else {
+                    all_approved = false;
+                }
+            }
+            if all_approved {
+                fully_approved.push(path);
+            }
+        }
+        if contents.is_empty() {
+            return Ok(0);
+        }
+
+        let own_id = self.read_current_unlocked_id(passphrase_callback)?;
+        for content in &contents {
+            self.insert_own(&content.sign_by(&own_id)?, &own_id)?;
+        }
+        let drafts_path = self.drafts_path();
+        for path in fully_approved {
+            if path.starts_with(&drafts_path) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(contents.len())
+    }
+
+    pub fn fetch_url(&self, url: &str) -> Result<()> {
+        let known_conflicts = self.load_proof_db()?.id_conflicts();
+        let _success = util::err_eprint_and_ignore(self.fetch_remote_git(url).compat());
+        report_id_conflicts(&self.load_proof_db()?, &known_conflicts);
+        self.write_store_manifest(None)
+    }
+
+    pub fn fetch_trusted(&self, trust_params: crate::TrustDistanceParams) -> Result<()> {
+        let _success = util::err_eprint_and_ignore(self.fetch_org_policy().compat());
+        let mut progress = FetchProgress::load(&self.fetch_progress_path());
+        let mut already_fetched = HashSet::new();
+        let mut db = self.load_proof_db()?;
+        let known_conflicts = db.id_conflicts();
+        let user_config = self.load_user_config()?;
+        let user_id = user_config.get_current_userid()?;
+
+        let mut something_was_fetched = true;
+        while something_was_fetched {
+            something_was_fetched = false;
+            let trust_set =
+                db.calculate_trust_set(user_config.get_current_userid()?, &trust_params);
+
+            let mut urls = vec![];
+            for id in trust_set.trusted_ids() {
+                if already_fetched.contains(id) {
+                    continue;
+                } else {
+                    already_fetched.insert(id.to_owned());
+                }
+                if user_id == id {
+                    continue;
+                } else if let Some(url) = db.lookup_url(id) {
+                    if !urls.contains(&url.url) {
+                        urls.push(url.url.clone());
+                    }
+                } else {
+                    eprintln!("No URL for {}", id);
+                }
+            }
+            for url in self.fetch_remotes(urls, &mut progress)? {
+                something_was_fetched = true;
+                db.import_from_iter(remote_proofs_iter_for_path(
+                    self.get_remote_git_cache_path(&url),
+                ));
+            }
+        }
+        progress.finish()?;
+        report_id_conflicts(&db, &known_conflicts);
+        self.write_store_manifest(None)
+    }
+
+    fn fetch_all_ids_recursively(
+        &self,
+        mut already_fetched_urls: HashSet<String>,
+        progress: &mut FetchProgress,
+    ) -> Result<()> {
+        let mut already_fetched = HashSet::new();
+        let mut db = self.load_proof_db()?;
+        let known_conflicts = db.id_conflicts();
+        let user_config = self.load_user_config()?;
+        let user_id = user_config.get_current_userid()?;
+
+        let mut something_was_fetched = true;
+        while something_was_fetched {
+            something_was_fetched = false;
+
+            let mut urls = vec![];
+            for id in &db.all_known_ids() {
+                if already_fetched.contains(id) {
+                    continue;
+                } else {
+                    already_fetched.insert(id.to_owned());
+                }
+                if user_id == id {
+                    continue;
+                } else if let Some(url) = db.lookup_url(id) {
+                    let url = url.url.to_string();
+
+                    if already_fetched_urls.contains(&url) {
+                        continue;
+                    } else {
+                        already_fetched_urls.insert(url.clone());
+                    }
+                    urls.push(url);
+                } else {
+                    eprintln!("No URL for {}", id);
+                }
+            }
+            for url in self.fetch_remotes(urls, progress)? {
+                something_was_fetched = true;
+                db.import_from_iter(remote_proofs_iter_for_path(
+                    self.get_remote_git_cache_path(&url),
+                ));
+            }
+        }
+        report_id_conflicts(&db, &known_conflicts);
+        Ok(())
+    }
+
+    /// Where the caches of the `.crev` scope at `root_dir` are kept, eg.
+    /// its `merkle::MerkleTree`
+    ///
+    /// They're kept out of `.crev`, as anything there could have been
+    /// committed to the repository, and the caches are trusted.
+    pub(crate) fn scope_cache_path(root_dir: &Path) -> Result<PathBuf> {
+        let local = Self::new()?;
+        let root_dir = root_dir.canonicalize()?;
+        let digest = crev_common::blake2b256sum(root_dir.to_string_lossy().as_bytes());
+        let path = local
+            .cache_path
+            .join("scopes")
+            .join(crev_data::Digest::from_vec(digest).to_string());
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    pub fn get_remote_git_cache_path(&self, url: &str) -> PathBuf {
+        let digest = crev_common::blake2b256sum(url.as_bytes());
+        let digest = crev_data::Digest::from_vec(digest);
+        self.cache_remotes_path().join(digest.to_string())
+    }
+
+    /// Fetch `urls` concurrently, returning the ones fetched, now or
+    /// before an interruption according to `progress`
+    ///
+    /// Errors of single remotes are only reported. What's fetched is
+    /// processed as by `fetch_remote_git`, on this thread, before the
+    /// remote is marked fetched in `progress`; remotes not marked when
+    /// interrupted are fetched again by the next run.
+    fn fetch_remotes(
+        &self,
+        urls: Vec<String>,
+        progress: &mut FetchProgress,
+    ) -> Result<Vec<String>> {
+        let config = self.load_user_config()?;
+        let mut fetched = vec![];
+        let mut remotes = vec![];
+        let mut known_digests = HashMap::new();
+        for url in urls {
+            let dir = self.get_remote_git_cache_path(&url);
+            if progress.is_fetched(&url) && dir.exists() {
+                eprintln!("Fetched {} already, before the interruption", url);
+                fetched.push(url);
+            } else {
+                known_digests.insert(url.clone(), (Self::known_digests_in(&dir), !dir.exists()));
+                remotes.push((url, dir));
+            }
+        }
+
+        util::git::fetch_remotes_parallel(
+            remotes,
+            config.fetch_jobs.unwrap_or(DEFAULT_FETCH_JOBS),
+            &config.remote_credentials,
+            config.fetch_rate_limit,
+            config.fetch_timeout.map(Duration::from_secs),
+            |url, dir, result| {
+                let (known, cloned) = known_digests.remove(&url).unwrap_or_default();
+                let success = util::err_eprint_and_ignore(
+                    result
+                        .and_then(|()| self.process_fetched(&url, &dir, &known, cloned))
+                        .and_then(|()| progress.mark_fetched(&url))
+                        .compat(),
+                );
+                if success {
+                    fetched.push(url);
+                }
+                Ok(())
+            },
+        )?;
+        if util::interrupt::is_interrupted() {
+            bail!("Fetching interrupted; run it again to resume");
+        }
+        Ok(fetched)
+    }
+
+    /// Digests of the proofs fetched into `dir` so far
+    fn known_digests_in(dir: &Path) -> HashSet<Vec<u8>> {
+        proofs_iter_for_path(dir.to_owned())
+            .map(|proof| proof.digest)
+            .collect()
+    }
+
+    pub fn fetch_remote_git(&self, url: &str) -> Result<()> {
+        let dir = self.get_remote_git_cache_path(url);
+        let config = self.load_user_config()?;
+        // new proofs can be advisories for hooks, and review requests to queue
+        let known_digests = Self::known_digests_in(&dir);
+        let cloned = !dir.exists();
+        util::git::fetch_remote(
+            url,
+            &dir,
+            config.credentials_for(url),
+            config.fetch_rate_limit,
+            config.fetch_deadline(),
+        )?;
+        self.process_fetched(url, &dir, &known_digests, cloned)
+    }
+
+    /// Handle the proofs just fetched from `url` into `dir`: run hooks,
+    /// quarantine suspicious ones and queue review requests
+    fn process_fetched(
+        &self,
+        url: &str,
+        dir: &Path,
+        known_digests: &HashSet<Vec<u8>>,
+        cloned: bool,
+    ) -> Result<()> {
+        let advisory_hooks = self.has_hooks(Event::AdvisoryReceived);
+        if cloned {
+            self.audit_log().append(
+                audit_log::Action::RemoteAdded {
+                    url: url.to_owned(),
+                },
+                None,
+            )?;
+        }
+
+        self.run_hooks(
+            Event::ProofsFetched,
+            &[("URL", url.to_owned()), ("PATH", dir.display().to_string())],
+        );
+        let quarantined = self.quarantine_suspicious(url, dir)?;
+        if quarantined > 0 {
+            eprintln!(
+                "Quarantined {} suspicious proofs from {}; see `crev quarantine list`",
+                quarantined, url
+            );
+        }
+        let mut review_requests = vec![];
+        for proof in remote_proofs_iter_for_path(dir.to_owned()) {
+            if known_digests.contains(&proof.digest) {
+                continue;
+            }
+            if let proof::Content::ReviewRequest(ref request) = proof.content {
+                review_requests.push(request.clone());
+            }
+            if !advisory_hooks {
+                continue;
+            }
+            if let Some((package, rating)) = advisory_of(&proof) {
+                self.run_hooks(
+                    Event::AdvisoryReceived,
+                    &[
+                        ("URL", url.to_owned()),
+                        ("PROOF_DIGEST", crev_common::base64_encode(&proof.digest)),
+                        ("AUTHOR", proof.content.author_id().to_string()),
+                        ("PACKAGE", package),
+                        ("RATING", rating),
+                    ],
+                );
+            }
+        }
+        if !review_requests.is_empty() {
+            let queued = self.queue_review_requests(&review_requests)?;
+            if queued > 0 {
+                eprintln!("Queued {} review requests on `crev todo`", queued);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queue the `requests` made by trusted ids on `crev todo`, returning
+    /// how many weren't queued already
+    ///
+    /// Requests by ids not allowed to make them are left out; see
+    /// `ProofDB::allows`.
+    pub fn queue_review_requests(&self, requests: &[proof::ReviewRequest]) -> Result<usize> {
+        let (db, trust_set) = self.load_db(&default())?;
+        let mut queue = self.open_todo()?;
+        let mut queued = 0;
+        for request in requests {
+            let author = &request.from.id;
+            if trust_set.get_effective_trust_level(author).is_none()
+                || !db.allows(author, Capability::Project)
+            {
+                continue;
+            }
+            if queue.add(todo::Item::from_review_request(request)) {
+                queued += 1;
+            }
+        }
+        queue.save()?;
+        Ok(queued)
+    }
+
+    pub fn fetch_all(&self) -> Result<()> {
+        let _success = util::err_eprint_and_ignore(self.fetch_org_policy().compat());
+        let mut progress = FetchProgress::load(&self.fetch_progress_path());
+        let mut fetched_urls = HashSet::new();
+        let mut urls = vec![];
+        for entry in fs::read_dir(self.cache_remotes_path())? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            let repo = git2::Repository::open(&path);
+            if repo.is_err() {
+                continue;
+            }
+
+            let url = {
+                || -> Result<String> {
+                    let repo = repo.unwrap();
+                    let remote = repo.find_remote("origin")?;
+                    let url = remote
+                        .url()
+                        .ok_or_else(|| format_err!("origin has no url"))?;
+                    Ok(url.to_string())
+                }
+            }();
+
+            match url {
+                Ok(url) => {
+                    fetched_urls.insert(url.clone());
+                    urls.push(url);
+                }
+                Err(e) => {
+                    eprintln!("ERR: {} {}", path.display(), e);
+                }
+            }
+        }
+        self.fetch_remotes(urls, &mut progress)?;
+
+        self.fetch_all_ids_recursively(fetched_urls, &mut progress)?;
+        progress.finish()?;
+
+        self.write_store_manifest(None)
+    }
+
+    pub fn run_git(&self, args: Vec<OsString>) -> Result<std::process::ExitStatus> {
+        let orig_dir = std::env::current_dir()?;
         std::env::set_current_dir(self.get_proofs_dir_path()?)?;
 
         use std::process::Command;
@@ -539,21 +2156,251 @@ impl Local {
         Ok(())
     }
 
+    /// Own, fetched, decrypted and imported proofs, with valid signatures
+    pub fn known_proofs_iter(&self) -> Result<impl Iterator<Item = proof::Proof>> {
+        Ok(self
+            .proofs_iter()?
+            .chain(remote_proofs_iter(self.cache_remotes_path()))
+            .chain(proofs_iter_for_path(self.decrypted_proofs_path()))
+            .chain(proofs_iter_for_path(self.imported_proofs_path())))
+    }
+
+    /// Ids revoked in `ids.yaml` of their own proof repository, fetched or
+    /// own
+    ///
+    /// Only the repository at the URL of an id gets to revoke it, as
+    /// anyone can list any id revoked in theirs.
+    fn revoked_ids(&self) -> Result<Vec<Id>> {
+        let mut revoked = vec![];
+        for root in &[self.user_proofs_path(), self.cache_remotes_path()] {
+            let dirs = fs::read_dir(root)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir());
+            for dir in dirs {
+                // invalid ones are reported when the proofs are read
+                let directory = match IdDirectory::read_from_dir(&dir) {
+                    Ok(Some(directory)) => directory,
+                    _ => continue,
+                };
+                for entry in directory.ids {
+                    if entry.status != IdStatus::Revoked {
+                        continue;
+                    }
+                    let own_repo = self.get_proofs_dir_path_for_url(&entry.id.url)? == dir
+                        || self.get_remote_git_cache_path(&entry.id.url.url) == dir;
+                    if own_repo {
+                        revoked.push(entry.id.id);
+                    }
+                }
+            }
+        }
+        Ok(revoked)
+    }
+
+    /// Load own and fetched proofs, and imported ids, into a `ProofDB`
+    ///
+    /// Ids revoked by their own proof repository have all their proofs
+    /// left out, wherever they come from; see `revoked_ids`.
+    fn load_proof_db(&self) -> Result<crate::ProofDB> {
+        Ok(self.load_proof_db_with_digest()?.0)
+    }
+
+    /// `load_proof_db`, along with a digest of everything loaded
+    ///
+    /// There's no digest if the db depends on the current time, as it does
+    /// when rejecting future dated proofs.
+    fn load_proof_db_with_digest(&self) -> Result<(crate::ProofDB, Option<Vec<u8>>)> {
+        let reject_future_dated = self.load_user_config()?.reject_future_dated;
+        let mut db = crate::ProofDB::new();
+        db.set_reject_future_dated(reject_future_dated);
+        // expired proofs are left out, so the digest changes as they expire
+        let now = chrono::Utc::now();
+        let mut hashed = vec![];
+        for id in self.revoked_ids()? {
+            db.import_revoked_id(&id);
+            hashed.extend_from_slice(id.to_string().as_bytes());
+        }
+        let mut proof_digests = BTreeSet::new();
+        db.import_from_iter(self.known_proofs_iter()?.inspect(|proof| {
+            if !proof.content.is_expired_at(&now) {
+                proof_digests.insert(proof.digest.clone());
+            }
+        }));
+        hashed.extend(proof_digests.into_iter().flatten());
+        for bundle in self.list_imported_pub_ids()? {
+            db.import_pub_id_bundle(&bundle);
+            hashed.extend_from_slice(bundle.to_string().as_bytes());
+        }
+        if let Some(current_id) = self.get_current_userid()? {
+            for list in self.list_seed_lists()? {
+                db.import_seed_list(&current_id, &list);
+                hashed.extend_from_slice(list.to_string().as_bytes());
+            }
+            if let Some(policy) = self.load_org_policy()? {
+                db.import_org_policy(&current_id, &policy);
+                hashed.extend_from_slice(policy.to_string().as_bytes());
+            }
+        }
+        let digest = if reject_future_dated {
+            None
+        } else {
+            Some(crev_common::blake2b256sum(&hashed))
+        };
+        Ok((db, digest))
+    }
+
+    /// Load the `ProofDB`, and the trust set of the current id in it
+    ///
+    /// The trust set is cached, keyed by a digest of the proofs and ids
+    /// loaded, the current id, `params` and the version of the cache, so
+    /// it's only calculated again when any of them change.
     pub fn load_db(
         &self,
         params: &crate::TrustDistanceParams,
     ) -> Result<(crate::ProofDB, TrustSet)> {
+        let (db, trust_set, _key) = self.load_db_with_key(params)?;
+        Ok((db, trust_set))
+    }
+
+    /// Like `load_db`, along with the key the trust set is cached by, to
+    /// key other caches depending on the same proofs
+    ///
+    /// There's no key when the proofs can't be cached, or with no current
+    /// id.
+    pub(crate) fn load_db_with_key(
+        &self,
+        params: &crate::TrustDistanceParams,
+    ) -> Result<(crate::ProofDB, TrustSet, Option<Vec<u8>>)> {
         let user_config = self.load_user_config()?;
-        let mut db = crate::ProofDB::new();
-        db.import_from_iter(self.proofs_iter()?);
-        db.import_from_iter(proofs_iter_for_path(self.cache_remotes_path()));
+        let (db, digest) = self.load_proof_db_with_digest()?;
 
-        let trust_set = if let Some(id) = user_config.get_current_userid_opt() {
-            db.calculate_trust_set(id, &params)
+        let id = if let Some(id) = user_config.get_current_userid_opt() {
+            id
         } else {
-            TrustSet::default()
+            return Ok((db, TrustSet::default(), None));
         };
-        Ok((db, trust_set))
+        let mut key = match digest {
+            Some(digest) => digest,
+            None => {
+                let trust_set = db.calculate_trust_set(id, params);
+                return Ok((db, trust_set, None));
+            }
+        };
+        key.extend_from_slice(&serde_cbor::to_vec(&(TRUST_SET_CACHE_VERSION, id, params))?);
+        let key = crev_common::blake2b256sum(&key);
+        let cache_path = self.trust_set_cache_path();
+        if let Some(trust_set) = TrustSet::load_cached(&cache_path, &key) {
+            return Ok((db, trust_set, Some(key)));
+        }
+        let trust_set = db.calculate_trust_set(id, params);
+        trust_set.store_cached(&cache_path, &key)?;
+        Ok((db, trust_set, Some(key)))
+    }
+
+    fn proof_index_path(&self) -> PathBuf {
+        self.cache_path.join("proof-index")
+    }
+
+    /// The proof stores indexed by `proof_index`: own, fetched, decrypted
+    /// and imported proofs, as in `known_proofs_iter`
+    fn proof_index_roots(&self) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .get_proofs_dir_path_opt()?
+            .into_iter()
+            .chain(Some(self.cache_remotes_path()))
+            .chain(Some(self.decrypted_proofs_path()))
+            .chain(Some(self.imported_proofs_path()))
+            .collect())
+    }
+
+    /// The index of known proofs, updated with the files changed
+    /// since it was stored
+    pub fn proof_index(&self) -> Result<ProofIndex> {
+        let path = self.proof_index_path();
+        let mut index = ProofIndex::load(&path);
+        if index.update(&self.proof_index_roots()?) {
+            index.store(&path)?;
+        }
+        Ok(index)
+    }
+
+    /// Index all known proofs again, from scratch
+    pub fn rebuild_proof_index(&self) -> Result<ProofIndex> {
+        let mut index = ProofIndex::default();
+        index.update(&self.proof_index_roots()?);
+        index.store(&self.proof_index_path())?;
+        Ok(index)
+    }
+
+    /// Known proofs matching `filter`, oldest first
+    ///
+    /// Found with `proof_index`, so only the files with matching proofs
+    /// are parsed. Proofs with invalid signatures are left out, and
+    /// reported.
+    pub fn find_proofs(&self, filter: &ProofFilter) -> Result<Vec<proof::Proof>> {
+        self.find_proofs_in_files(filter, |_| true)
+    }
+
+    /// `find_proofs`, only in the files `in_file` is true for
+    fn find_proofs_in_files(
+        &self,
+        filter: &ProofFilter,
+        in_file: impl Fn(&Path) -> bool,
+    ) -> Result<Vec<proof::Proof>> {
+        let index = self.proof_index()?;
+        let mut by_file: BTreeMap<&Path, BTreeSet<usize>> = BTreeMap::new();
+        for entry in index
+            .matching(filter)
+            .filter(|entry| in_file(&entry.location.path))
+        {
+            by_file
+                .entry(&entry.location.path)
+                .or_default()
+                .insert(entry.location.index);
+        }
+
+        let mut found = vec![];
+        for (path, indices) in by_file {
+            for (i, proof) in proof::Proof::parse_from(path)?.into_iter().enumerate() {
+                if !indices.contains(&i) {
+                    continue;
+                }
+                match proof.verify() {
+                    Ok(()) => found.push(proof),
+                    Err(e) => eprintln!("Failed processing a proof: {}", e),
+                }
+            }
+        }
+        found.sort_by_key(|proof| *proof.content.date());
+        Ok(found)
+    }
+
+    /// Find a proof by its digest, or an unambiguous prefix of it
+    ///
+    /// Looks through all known proofs, with `proof_index`.
+    /// Proofs with invalid signatures are returned too, so the caller can
+    /// report them.
+    pub fn find_proof_by_digest(&self, digest: &str) -> Result<proof::Proof> {
+        let index = self.proof_index()?;
+        let mut found: Vec<proof::Proof> = vec![];
+        for entry in index.by_digest_prefix(digest) {
+            if found.iter().any(|p| p.digest == entry.digest) {
+                continue;
+            }
+            let proof = entry.location.read()?;
+            if crev_common::base64_encode(&proof.digest) == digest {
+                return Ok(proof);
+            }
+            found.push(proof);
+        }
+
+        match found.len() {
+            0 => bail!("No proof with digest {} found", digest),
+            1 => Ok(found.pop().expect("not empty")),
+            n => bail!("Digest {} is ambiguous; matches {} proofs", digest, n),
+        }
     }
 
     pub fn proof_dir_git_add_path(&self, rel_path: &Path) -> Result<()> {
@@ -574,12 +2421,19 @@ impl Local {
         Ok(())
     }
 
+    /// Generate an own id, and make it the current one
+    ///
+    /// It's given `name`, if any; see `name_id`.
     pub fn generate_id(
         &self,
         url: Option<String>,
         github_username: Option<String>,
         use_https_push: bool,
+        name: Option<String>,
     ) -> Result<()> {
+        if let Some(ref name) = name {
+            check_id_name(name)?;
+        }
         let url = match (url, github_username) {
             (Some(url), None) => url,
             (None, Some(username)) => format!("https://github.com/{}/crev-proofs", username),
@@ -597,10 +2451,15 @@ impl Local {
         eprintln!("CrevID will be protected by a passphrase.");
         eprintln!("There's no way to recover your CrevID if you forget your passphrase.");
         let passphrase = crev_common::read_new_passphrase()?;
-        let locked = id::LockedId::from_own_id(&id, &passphrase)?;
-
-        self.save_locked_id(&locked)?;
+        self.relock_id(&id, &passphrase)?;
         self.save_current_id(id.as_ref())?;
+        self.audit_log().append(
+            audit_log::Action::IdCreated {
+                id: id.id.id.clone(),
+            },
+            Some(&id),
+        )?;
+        let locked = self.read_locked_id(id.as_ref())?;
 
         eprintln!("");
         eprintln!("Your CrevID was created and will be printed below in an encrypted form.");
@@ -610,20 +2469,80 @@ impl Local {
         println!("{}", locked);
 
         self.init_readme_using_this_repo_file()?;
+        self.update_id_directory()?;
+        if let Some(name) = name {
+            self.name_id(id.as_ref(), &name)?;
+        }
 
         Ok(())
     }
 
-    pub fn switch_id(&self, id_str: &str) -> Result<()> {
-        let id: Id = Id::crevid_from_str(id_str)?;
+    /// Make the own id named `name_or_id`, or with that id, the current one
+    pub fn switch_id(&self, name_or_id: &str) -> Result<()> {
+        let id = self.own_id_from_str(name_or_id)?;
         self.save_current_id(&id)?;
 
         Ok(())
     }
 
+    /// Name the own `id`, eg. `work`, to refer to it by that instead of
+    /// the id; any name it had before is dropped
+    pub fn name_id(&self, id: &Id, name: &str) -> Result<()> {
+        check_id_name(name)?;
+        if !self.list_ids()?.iter().any(|own| own.id == *id) {
+            bail!("{} is not an own id", id);
+        }
+        let mut config = self.load_user_config()?;
+        config.id_names.retain(|_, named| named != id);
+        config.id_names.insert(name.to_owned(), id.clone());
+        self.store_user_config(&config)
+    }
+
+    /// The own id named `name_or_id`, or with that id
+    pub fn own_id_from_str(&self, name_or_id: &str) -> Result<Id> {
+        let id = match self.load_user_config()?.id_names.get(name_or_id) {
+            Some(id) => id.clone(),
+            None => Id::crevid_from_str(name_or_id)?,
+        };
+        if !self.list_ids()?.iter().any(|own| own.id == id) {
+            bail!("{} is not an own id", name_or_id);
+        }
+        Ok(id)
+    }
+
+    /// Own ids, along with their names
+    pub fn own_ids(&self) -> Result<Vec<(PubId, Option<String>)>> {
+        let names = self.load_user_config()?.id_names;
+        Ok(self
+            .list_ids()?
+            .into_iter()
+            .map(|pub_id| {
+                let name = names
+                    .iter()
+                    .find(|(_, id)| **id == pub_id.id)
+                    .map(|(name, _)| name.clone());
+                (pub_id, name)
+            })
+            .collect())
+    }
+
+    /// Print a long listing, using a pager as configured
+    pub fn print_paged(&self, text: &str) -> Result<()> {
+        let config_pager = self.load_user_config().ok().and_then(|config| config.pager);
+        util::pager::print_paged(text, config_pager.as_deref())
+    }
+
     pub fn list_own_ids(&self) -> Result<()> {
-        for id in self.list_ids()? {
-            println!("{} {}", id.id, id.url.url);
+        let current_id = self.get_current_userid()?;
+        for (id, name) in self.own_ids()? {
+            let mut line = format!("{} {}", id.id, id.url.url);
+            if let Some(name) = name {
+                line += &format!(" {}", name);
+            }
+            if Some(&id.id) == current_id.as_ref() {
+                line += " (current)";
+            }
+            println!("{}", line);
         }
         Ok(())
     }
@@ -639,14 +2558,23 @@ impl ProofStore for Local {
             .append(true)
             .create(true)
             .write(true)
-            .open(path)?;
+            .open(&path)?;
 
         file.write_all(proof.to_string().as_bytes())?;
         file.write_all(b"\n")?;
         file.flush()?;
         drop(file);
+        self.invalidate_trust_set_cache()?;
+        // an index not built yet is built on demand
+        let index_path = self.proof_index_path();
+        if index_path.exists() {
+            let mut index = ProofIndex::load(&index_path);
+            index.update_file(&path);
+            index.store(&index_path)?;
+        }
 
         self.proof_dir_git_add_path(&rel_store_path)?;
+        self.update_id_directory()?;
 
         Ok(())
     }
@@ -660,7 +2588,165 @@ impl ProofStore for Local {
     }
 }
 
-fn proofs_iter_for_path(path: PathBuf) -> impl Iterator<Item = proof::Proof> {
+pub(crate) fn proofs_iter_for_path(path: PathBuf) -> impl Iterator<Item = proof::Proof> {
+    unverified_proofs_iter_for_path(path)
+        .and_then_ok(|proof| {
+            proof.verify()?;
+            Ok(proof)
+        })
+        .on_err(|e| {
+            eprintln!("Failed processing a proof: {}", e);
+        })
+        .oks()
+}
+
+/// Names of ids can't be ids themselves, or have whitespace
+fn check_id_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.chars().any(char::is_whitespace) || Id::crevid_from_str(name).is_ok()
+    {
+        bail!("Invalid id name: {:?}", name);
+    }
+    Ok(())
+}
+
+/// Find the most recent URLs of `id_strings`
+fn lookup_pub_ids(db: &crate::ProofDB, id_strings: Vec<String>) -> Result<Vec<PubId>> {
+    let mut pub_ids = vec![];
+
+    for id_string in id_strings {
+        let id = Id::crevid_from_str(&id_string)?;
+
+        if let Some(url) = db.lookup_url(&id) {
+            pub_ids.push(PubId::new(id, url.to_owned()));
+        } else {
+            bail!(
+                "URL not found for Id {}; Fetch proofs with `fetch url <url>` first",
+                id_string
+            )
+        }
+    }
+    Ok(pub_ids)
+}
+
+/// Parse every file in `path`, skipping (and reporting) invalid ones
+fn read_dir_parsed<T>(
+    path: &Path,
+    what: &str,
+    parse: impl Fn(&str) -> Result<T>,
+) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut parsed = vec![];
+    for dir_entry in fs::read_dir(path)? {
+        let path = dir_entry?.path();
+        match parse(&util::read_file_to_string(&path)?) {
+            Ok(t) => parsed.push(t),
+            Err(e) => eprintln!("Ignoring invalid {} {}: {}", what, path.display(), e),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Proofs from all the fetched proof repositories; see `remote_proofs_iter_for_path`
+fn remote_proofs_iter(remotes_path: PathBuf) -> impl Iterator<Item = proof::Proof> {
+    fs::read_dir(remotes_path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .flat_map(remote_proofs_iter_for_path)
+}
+
+/// Package and rating of `proof`, if it's a review rating negative or
+/// dangerous
+fn advisory_of(proof: &proof::Proof) -> Option<(String, String)> {
+    use crev_data::proof::review::{Common, Rating};
+    let (package, review) = match proof.content {
+        proof::Content::Code(ref code) => (&code.package, code.review()),
+        proof::Content::Package(ref package) => (&package.package, package.review()),
+        _ => return None,
+    };
+    if review.rating > Rating::Negative {
+        return None;
+    }
+    Some((package.name.clone(), review.rating.to_string()))
+}
+
+/// Like `proofs_iter_for_path`, but skips proofs of ids not listed in `ids.yaml`
+fn remote_proofs_iter_for_path(path: PathBuf) -> Box<dyn Iterator<Item = proof::Proof>> {
+    match IdDirectory::read_from_dir(&path) {
+        Ok(None) => Box::new(proofs_iter_for_path(path)),
+        Ok(Some(directory)) => Box::new(proofs_iter_for_path(path.clone()).filter(move |proof| {
+            let accepted = directory.accepts(proof);
+            if !accepted {
+                eprintln!(
+                    "Ignoring proof by {} not listed in {}",
+                    proof.content.author_id(),
+                    path.join(id_directory::IDS_FILE_NAME).display()
+                );
+            }
+            accepted
+        })),
+        Err(e) => {
+            eprintln!(
+                "Ignoring proofs in {}: invalid {}: {}",
+                path.display(),
+                id_directory::IDS_FILE_NAME,
+                e
+            );
+            Box::new(std::iter::empty())
+        }
+    }
+}
+
+/// Print the `ProofDB::id_conflicts` of `db` not among the `known` ones,
+/// if any, so they can't go unnoticed after fetching proofs
+fn report_id_conflicts(db: &crate::ProofDB, known: &[crate::IdConflict]) {
+    let conflicts: Vec<_> = db
+        .id_conflicts()
+        .into_iter()
+        .filter(|conflict| !known.contains(conflict))
+        .collect();
+    if conflicts.is_empty() {
+        return;
+    }
+    eprintln!();
+    eprintln!("WARNING: fetched proofs disagree about who is who:");
+    for conflict in &conflicts {
+        eprintln!("  {}", conflict);
+    }
+    eprintln!("Someone may be impersonating an id. Check with its owner before trusting it.");
+    eprintln!();
+}
+
+/// Encrypted proofs in `path`, skipping (and reporting) invalid ones
+fn encrypted_proofs_iter_for_path(path: PathBuf) -> impl Iterator<Item = SignedEncryptedProof> {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.is_file()
+                && path.extension().and_then(|ext| ext.to_str()) == Some(encrypted::FILE_EXTENSION)
+        })
+        .filter_map(|path| {
+            match util::read_file_to_string(&path)
+                .map_err(failure::Error::from)
+                .and_then(|s| SignedEncryptedProof::parse(&s))
+            {
+                Ok(sealed) => Some(sealed),
+                Err(e) => {
+                    eprintln!("Ignoring invalid encrypted proof {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+}
+
+/// Like `proofs_iter_for_path`, but yields proofs with invalid signatures too
+fn unverified_proofs_iter_for_path(path: PathBuf) -> impl Iterator<Item = Result<proof::Proof>> {
     use std::ffi::OsStr;
     let file_iter = walkdir::WalkDir::new(path)
         .into_iter()
@@ -678,16 +2764,7 @@ fn proofs_iter_for_path(path: PathBuf) -> impl Iterator<Item = proof::Proof> {
             }
         });
 
-    let proofs_iter = file_iter
+    file_iter
         .and_then_ok(|path| Ok(proof::Proof::parse_from(&path)?))
         .flatten_ok()
-        .and_then_ok(|proof| {
-            proof.verify()?;
-            Ok(proof)
-        })
-        .on_err(|e| {
-            eprintln!("Failed processing a proof: {}", e);
-        });
-
-    proofs_iter.oks()
 }
@@ -0,0 +1,75 @@
+//! Normalizing the text of files before digesting them
+//!
+//! A package config can set the normalizations to apply to files by their
+//! extension, eg. to ignore trailing whitespace in `.md` files. They are
+//! recorded with each file of a Code Review Proof (see
+//! `proof::review::code::Normalization`), and when verifying a file, each
+//! review counts only if its own recorded normalizations give its digest;
+//! the package config plays no part in that.
+use crate::prelude::*;
+use crev_common;
+use crev_data::proof::review::code::Normalization;
+use std::{collections::BTreeMap, fs, path::Path};
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Normalizations to apply, by file extension (without the dot)
+pub type Normalizations = BTreeMap<String, Vec<Normalization>>;
+
+/// Normalizations of `normalizations` for the file at `path`
+pub fn for_path<'a>(normalizations: &'a Normalizations, path: &Path) -> &'a [Normalization] {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| normalizations.get(ext))
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn apply(normalization: Normalization, content: &[u8]) -> Vec<u8> {
+    match normalization {
+        Normalization::Bom if content.starts_with(UTF8_BOM) => content[UTF8_BOM.len()..].to_owned(),
+        Normalization::Bom => content.to_owned(),
+        Normalization::TrailingWhitespace => {
+            let mut normalized = Vec::with_capacity(content.len());
+            for (i, line) in content.split(|b| *b == b'\n').enumerate() {
+                if i > 0 {
+                    normalized.push(b'\n');
+                }
+                let (line, cr) = match line.split_last() {
+                    Some((b'\r', line)) => (line, true),
+                    _ => (line, false),
+                };
+                let end = line
+                    .iter()
+                    .rposition(|b| *b != b' ' && *b != b'\t')
+                    .map_or(0, |i| i + 1);
+                normalized.extend_from_slice(&line[..end]);
+                if cr {
+                    normalized.push(b'\r');
+                }
+            }
+            normalized
+        }
+    }
+}
+
+/// `content` after `normalizations`, in order
+pub fn normalize(content: &[u8], normalizations: &[Normalization]) -> Vec<u8> {
+    normalizations
+        .iter()
+        .fold(content.to_owned(), |content, normalization| {
+            apply(*normalization, &content)
+        })
+}
+
+/// Digest of the file at `path`, after `normalizations`
+pub fn file_digest(path: &Path, normalizations: &[Normalization]) -> Result<Vec<u8>> {
+    if normalizations.is_empty() {
+        return Ok(crev_common::blake2b256sum_file(path)?);
+    }
+    let content = fs::read(path)?;
+    Ok(crev_common::blake2b256sum(&normalize(
+        &content,
+        normalizations,
+    )))
+}
@@ -0,0 +1,44 @@
+//! Following an organization policy; see `crev_data::org_policy`
+//!
+//! The policy is fetched from the `org-policy` of the user config: a git
+//! repository with the policy in `POLICY_FILE_NAME`, signed by the
+//! configured curator. It's kept in the user dir, so the last one fetched
+//! stays in effect while the repository can't be reached.
+//!
+//! Merged with personal settings, the stricter one wins:
+//!
+//! * required reviews are added to the `path-requirements` of every
+//!   package config, and both have to be met,
+//! * seeds are trusted at `low` level, unless trusted otherwise by the
+//!   user's own Trust Proofs,
+//! * banned ids are distrusted, even if the user trusts them.
+use crate::policy::PathRequirement;
+use crev_data::{org_policy::OrgPolicy, Id};
+
+/// Name of the policy file in the repository of a policy
+pub const POLICY_FILE_NAME: &str = "policy.crev";
+
+/// Where to fetch a policy from, and who must have signed it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrgPolicySource {
+    pub url: String,
+    pub curator: Id,
+}
+
+/// The required reviews of `policy`, as requirements of a package
+pub fn path_requirements(policy: &OrgPolicy) -> Vec<PathRequirement> {
+    policy
+        .required_reviews
+        .iter()
+        .map(|required| PathRequirement {
+            path: required.path.clone(),
+            reviewers: required.reviewers,
+            trust: required.trust,
+            owners: vec![],
+            kinds: vec![],
+            independent: false,
+            tags: required.tags.clone(),
+            metrics: vec![],
+        })
+        .collect()
+}
@@ -0,0 +1,385 @@
+//! Review requirements for sensitive paths of a package
+//!
+//! Set in the package config, eg. to require two reviewers with `high`
+//! trust for `src/crypto/**`. A package or file matching a requirement
+//! is verified only if enough distinct trusted reviewers approved it.
+//! Requirements can name the reviewers that count, like CODEOWNERS does,
+//! directly or by `ReviewerGroups` of the package config (once its trust
+//! root reviewed it), and apply to some kinds of files only (see
+//! `file_kind`). A requirement can also ask for an independent reviewer,
+//! one not among the authors of the file as told by git and the
+//! `AuthorIds` of the package config, so the author of some code can't be
+//! the only one vouching for it. Likewise a package can require its builds
+//! to be attested (see `AttestationRequirement`), and ask for a kind of
+//! review by the tags of the reviews, eg. only reviews tagged `crypto`
+//! counting for `src/crypto/**`. Requirements can be for riskier files
+//! only, by their `Metric`s, eg. more reviewers for files with `unsafe`.
+use crate::{prelude::*, proofdb::TrustSet, ProofDB};
+use crev_data::{
+    proof::{
+        review::{
+            code::{FileKind, Normalization},
+            Common, Rating, RustMetrics,
+        },
+        trust::TrustLevel,
+    },
+    Digest, Id,
+};
+use glob;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    path::Path,
+};
+
+fn default_trust() -> TrustLevel {
+    TrustLevel::Low
+}
+
+fn default_attesters() -> usize {
+    1
+}
+
+/// Named lists of ids, that `PathRequirement::owners` can refer to as `@name`
+pub type ReviewerGroups = BTreeMap<String, Vec<String>>;
+
+/// Ids of the authors of commits, by their git email
+pub type AuthorIds = BTreeMap<String, String>;
+
+/// Tags of the reviews of a file, by their authors; see
+/// `approving_reviewer_tags`
+pub type ReviewerTags = HashMap<Id, HashSet<String>>;
+
+/// A requirement for the files matching `path`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PathRequirement {
+    /// Pattern relative to the root of the scope, where `*` matches within
+    /// a path component, and `**` any number of them
+    pub path: String,
+    /// Number of distinct reviewers needed
+    pub reviewers: usize,
+    /// Minimum effective trust of each of them
+    #[serde(default = "default_trust")]
+    pub trust: TrustLevel,
+    /// Ids whose reviews count, or `@`-prefixed names of groups of them;
+    /// anyone trusted enough if empty
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    pub owners: Vec<String>,
+    /// Kinds of the files it's for; all of them if empty. See `file_kind`
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    pub kinds: Vec<FileKind>,
+    /// Whether one of the reviewers must not be an author of the file
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub independent: bool,
+    /// Tags the reviews must have to count, eg. `crypto`; see
+    /// `Content::tags`
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    pub tags: Vec<String>,
+    /// Only for files with any of these; all of them if empty
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Default::default")]
+    pub metrics: Vec<Metric>,
+}
+
+/// A risky trait of a Rust file, as found by `rust_metrics::file_metrics`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    /// `unsafe` blocks, functions, impls or traits
+    Unsafe,
+    /// A `build.rs` build script
+    BuildScript,
+    /// Procedural macros
+    ProcMacro,
+}
+
+impl Metric {
+    fn is_in(self, metrics: &RustMetrics) -> bool {
+        match self {
+            Metric::Unsafe => metrics.unsafe_count > 0,
+            Metric::BuildScript => metrics.build_script,
+            Metric::ProcMacro => metrics.proc_macro,
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Metric::Unsafe => "unsafe",
+            Metric::BuildScript => "build-script",
+            Metric::ProcMacro => "proc-macro",
+        })
+    }
+}
+
+/// Metrics of the file at `full_path`, if any of `requirements` depends on
+/// them and it's a Rust file; see `PathRequirement::metrics`
+pub fn required_metrics(
+    requirements: &[PathRequirement],
+    rel_path: &Path,
+    full_path: &Path,
+) -> Result<Option<RustMetrics>> {
+    if requirements.iter().all(|requirement| requirement.metrics.is_empty()) {
+        return Ok(None);
+    }
+    crate::rust_metrics::file_metrics(rel_path, full_path)
+}
+
+/// Does `pattern`, as in `PathRequirement::path`, match `rel_path`
+pub fn path_matches(pattern: &str, rel_path: &Path) -> Result<bool> {
+    let compiled = glob::Pattern::new(pattern)
+        .map_err(|e| format_err!("Invalid path pattern {}: {}", pattern, e))?;
+    Ok(compiled.matches_path_with(
+        rel_path,
+        &glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        },
+    ))
+}
+
+impl PathRequirement {
+    /// Does a review by `id` count towards this requirement
+    pub fn is_owner(&self, id: &Id, groups: &ReviewerGroups) -> Result<bool> {
+        if self.owners.is_empty() {
+            return Ok(true);
+        }
+        let id = id.to_string();
+        for owner in &self.owners {
+            let is_owner = match owner.strip_prefix('@') {
+                Some(group) => groups
+                    .get(group)
+                    .ok_or_else(|| format_err!("Unknown reviewer group {}", owner))?
+                    .contains(&id),
+                None => *owner == id,
+            };
+            if is_owner {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn matches(&self, rel_path: &Path) -> Result<bool> {
+        path_matches(&self.path, rel_path)
+    }
+
+    /// Is it for files of `kind`
+    pub fn is_for_kind(&self, kind: FileKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    /// Is it for a file with `metrics`, if it's a Rust file
+    pub fn is_for_metrics(&self, metrics: Option<&RustMetrics>) -> bool {
+        self.metrics.is_empty()
+            || metrics.map_or(false, |metrics| {
+                self.metrics.iter().any(|metric| metric.is_in(metrics))
+            })
+    }
+
+    /// Do reviews with `tags` count towards this requirement
+    pub fn is_tagged(&self, tags: Option<&HashSet<String>>) -> bool {
+        self.tags
+            .iter()
+            .all(|tag| tags.map_or(false, |tags| tags.contains(tag)))
+    }
+}
+
+impl fmt::Display for PathRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} needs {} reviewers with {} trust",
+            self.path, self.reviewers, self.trust
+        )?;
+        if !self.owners.is_empty() {
+            write!(f, " among {}", self.owners.join(", "))?;
+        }
+        if !self.kinds.is_empty() {
+            let kinds: Vec<_> = self.kinds.iter().map(ToString::to_string).collect();
+            write!(f, " for {} files", kinds.join(", "))?;
+        }
+        if !self.metrics.is_empty() {
+            let metrics: Vec<_> = self.metrics.iter().map(ToString::to_string).collect();
+            write!(f, " with {}", metrics.join(", "))?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, ", reviews tagged {}", self.tags.join(", "))?;
+        }
+        if self.independent {
+            write!(f, ", one of them not an author")?;
+        }
+        Ok(())
+    }
+}
+
+/// Trusted reviewers approving a file, with their effective trust
+///
+/// These are authors of Code Reviews of the file with `file_digest` after
+/// `normalization`, and
+/// of Package Reviews of the whole package with `package_digest`, not
+/// rating it negatively.
+pub fn approving_reviewers(
+    db: &ProofDB,
+    trust_set: &TrustSet,
+    file_digest: &[u8],
+    normalization: &[Normalization],
+    package_digest: Option<&Digest>,
+) -> HashMap<Id, TrustLevel> {
+    let code_ratings = db
+        .get_code_reviews_by_file_digest(file_digest, normalization)
+        .map(|review| (review.from.id.clone(), review.review().rating.clone()));
+    let package_ratings = package_digest
+        .into_iter()
+        .flat_map(|digest| db.get_full_package_reviews_by_digest(digest, trust_set))
+        .map(|review| (review.from.id.clone(), review.review.rating));
+
+    code_ratings
+        .chain(package_ratings)
+        .filter(|(_, rating)| Rating::Neutral <= *rating)
+        .filter_map(|(id, _)| {
+            let level = trust_set.get_effective_trust_level(&id)?;
+            Some((id, level))
+        })
+        .collect()
+}
+
+/// Tags of the reviews of `approving_reviewers`, by their authors
+pub fn approving_reviewer_tags(
+    db: &ProofDB,
+    file_digest: &[u8],
+    normalization: &[Normalization],
+    package_digest: Option<&Digest>,
+) -> ReviewerTags {
+    let code_tags = db
+        .get_code_reviews_by_file_digest(file_digest, normalization)
+        .filter(|review| Rating::Neutral <= review.review().rating)
+        .map(|review| (review.from.id.clone(), review.tags.clone()));
+    let package_tags = package_digest
+        .into_iter()
+        .flat_map(|digest| db.get_package_reviews_by_digest(digest))
+        .filter(|review| Rating::Neutral <= review.review.rating)
+        .map(|review| (review.from.id.clone(), review.tags.clone()));
+
+    let mut tags = ReviewerTags::new();
+    for (id, review_tags) in code_tags.chain(package_tags) {
+        tags.entry(id).or_default().extend(review_tags);
+    }
+    tags
+}
+
+/// Are the trusted reviewers approving a file, if any, all among its
+/// `authors`
+pub fn is_self_reviewed(authors: &HashSet<Id>, approvers: &HashMap<Id, TrustLevel>) -> bool {
+    !approvers.is_empty() && approvers.keys().all(|id| authors.contains(id))
+}
+
+/// Requirements matching `rel_path`, of a file of `kind` with `metrics`
+/// by `authors`, that `approvers` don't meet
+///
+/// `groups` are the ones the `owners` of the requirements refer to, and
+/// `tags` the ones of the reviews of the `approvers`. `metrics` are the
+/// ones of `required_metrics`.
+#[allow(clippy::too_many_arguments)]
+pub fn unmet_requirements<'a>(
+    requirements: &'a [PathRequirement],
+    groups: &ReviewerGroups,
+    rel_path: &Path,
+    kind: FileKind,
+    metrics: Option<&RustMetrics>,
+    authors: &HashSet<Id>,
+    approvers: &HashMap<Id, TrustLevel>,
+    tags: &ReviewerTags,
+) -> Result<Vec<&'a PathRequirement>> {
+    let mut unmet = vec![];
+    for requirement in requirements {
+        if !requirement.is_for_kind(kind)
+            || !requirement.is_for_metrics(metrics)
+            || !requirement.matches(rel_path)?
+        {
+            continue;
+        }
+        let mut count = 0;
+        let mut independent = 0;
+        for (id, level) in approvers {
+            if *level >= requirement.trust
+                && requirement.is_owner(id, groups)?
+                && requirement.is_tagged(tags.get(id))
+            {
+                count += 1;
+                if !authors.contains(id) {
+                    independent += 1;
+                }
+            }
+        }
+        if count < requirement.reviewers || requirement.independent && independent == 0 {
+            unmet.push(requirement);
+        }
+    }
+    Ok(unmet)
+}
+
+/// A requirement for Build Attestation Proofs of the sources of a package
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AttestationRequirement {
+    /// Number of distinct ids needed to attest their builds
+    #[serde(default = "default_attesters")]
+    pub attesters: usize,
+    /// Minimum effective trust of each of them
+    #[serde(default = "default_trust")]
+    pub trust: TrustLevel,
+}
+
+impl AttestationRequirement {
+    /// Is the requirement met by the attesters of one of the artifacts
+    ///
+    /// Attesters of different artifacts (or of different digests of the
+    /// same one) don't add up: they didn't reproduce the same build.
+    pub fn is_met(&self, attesters: &AttestersByArtifact) -> bool {
+        attesters.values().any(|attesters| {
+            attesters
+                .values()
+                .filter(|level| **level >= self.trust)
+                .count()
+                >= self.attesters
+        })
+    }
+}
+
+impl fmt::Display for AttestationRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "needs the same build attested by {} ids with {} trust",
+            self.attesters, self.trust
+        )
+    }
+}
+
+/// Attesting ids with their effective trust, by the name and digest of
+/// the artifact they attest
+pub type AttestersByArtifact = HashMap<(String, Vec<u8>), HashMap<Id, TrustLevel>>;
+
+/// Trusted ids attesting builds from the package sources with
+/// `package_digest`, by the artifact they built
+pub fn attesting_ids(
+    db: &ProofDB,
+    trust_set: &TrustSet,
+    package_digest: &Digest,
+) -> AttestersByArtifact {
+    let mut attesters = AttestersByArtifact::new();
+    for attestation in db.get_build_attestations_by_package_digest(package_digest) {
+        let id = attestation.from.id.clone();
+        if let Some(level) = trust_set.get_effective_trust_level(&id) {
+            attesters
+                .entry((
+                    attestation.artifact.name.clone(),
+                    attestation.artifact.digest.clone(),
+                ))
+                .or_default()
+                .insert(id, level);
+        }
+    }
+    attesters
+}
@@ -1,12 +1,47 @@
+use crate::{Colored, ProofDB};
 use chrono::prelude::*;
-use crev_data::proof::Content;
-use std::path::PathBuf;
+use crev_data::{
+    proof::{self, review::Common, Content},
+    PubId,
+};
+use std::{
+    fmt::{self, Write as FmtWrite},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
 
 fn type_name(content: &Content) -> (&str, Option<&str>) {
     match content {
         Content::Trust(_) => ("trust", None),
         Content::Code(_) => ("reviews", Some("code")),
         Content::Package(_) => ("reviews", Some("packages")),
+        Content::Project(_) => ("projects", None),
+        Content::ProjectMigration(_) => ("projects", Some("migrations")),
+        Content::TrustRootHandover(_) => ("trust", Some("handovers")),
+        Content::PathMove(_) => ("projects", Some("moves")),
+        Content::BuildAttestation(_) => ("reviews", Some("builds")),
+        Content::ReviewRequest(_) => ("projects", Some("requests")),
+        Content::Revocation(_) => ("trust", Some("revocations")),
+        Content::Advisory(_) => ("reviews", Some("advisories")),
+        Content::IdLink(_) => ("trust", Some("links")),
+    }
+}
+
+pub(crate) fn type_label(content: &Content) -> &'static str {
+    match content {
+        Content::Trust(_) => "trust",
+        Content::Code(_) => "code review",
+        Content::Package(_) => "package review",
+        Content::Project(_) => "project",
+        Content::ProjectMigration(_) => "project migration",
+        Content::TrustRootHandover(_) => "trust root handover",
+        Content::PathMove(_) => "path move",
+        Content::BuildAttestation(_) => "build attestation",
+        Content::ReviewRequest(_) => "review request",
+        Content::Revocation(_) => "revocation",
+        Content::Advisory(_) => "advisory",
+        Content::IdLink(_) => "id link",
     }
 }
 
@@ -32,3 +67,491 @@ pub(crate) fn rel_store_path(content: &Content) -> PathBuf {
     })
     .with_extension("proof.crev")
 }
+
+/// Status of a proof signature, as displayed to the user
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Valid,
+    Invalid,
+}
+
+impl Colored for SignatureStatus {
+    fn color(&self) -> Option<term::color::Color> {
+        match *self {
+            SignatureStatus::Valid => Some(term::color::GREEN),
+            SignatureStatus::Invalid => Some(term::color::RED),
+        }
+    }
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureStatus::Valid => f.pad("valid"),
+            SignatureStatus::Invalid => f.pad("INVALID"),
+        }
+    }
+}
+
+/// Describe how long ago `date` was, relative to `now`
+///
+/// Eg. "just now", "5 minutes ago", "2 years ago", "in 3 days".
+pub fn humanize_age(date: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let secs = now.signed_duration_since(*date).num_seconds();
+    let (in_future, secs) = if secs < 0 {
+        (true, -secs)
+    } else {
+        (false, secs)
+    };
+
+    let (count, unit) = match secs {
+        s if s < 60 => return "just now".into(),
+        s if s < 60 * 60 => (s / 60, "minute"),
+        s if s < 60 * 60 * 24 => (s / (60 * 60), "hour"),
+        s if s < 60 * 60 * 24 * 30 => (s / (60 * 60 * 24), "day"),
+        s if s < 60 * 60 * 24 * 365 => (s / (60 * 60 * 24 * 30), "month"),
+        s => (s / (60 * 60 * 24 * 365), "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+
+    if in_future {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
+
+/// Format of a document rendered with `Pretty::to_report`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        Ok(match s {
+            "markdown" | "md" => ReportFormat::Markdown,
+            "html" => ReportFormat::Html,
+            _ => bail!("Unknown report format: {}; use `markdown` or `html`", s),
+        })
+    }
+}
+
+pub(crate) fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape `s` to appear as is in a line of Markdown, eg. a list item
+fn markdown_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fence of a Markdown code block containing `s`, longer than any run
+/// of backticks in it
+fn markdown_fence(s: &str) -> String {
+    let longest_run = s
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat((longest_run + 1).max(3))
+}
+
+/// Human-friendly rendering of a `Proof`
+///
+/// Unlike `Display` of the `Proof` itself, which writes out the exact
+/// signed form, this aligns the fields, humanizes dates and shows
+/// every id with the most recent URL known for it in the `ProofDB`.
+pub struct Pretty<'a> {
+    proof: &'a proof::Proof,
+    db: &'a ProofDB,
+    now: DateTime<Utc>,
+}
+
+impl<'a> Pretty<'a> {
+    pub fn new(proof: &'a proof::Proof, db: &'a ProofDB) -> Self {
+        Self {
+            proof,
+            db,
+            now: Utc::now(),
+        }
+    }
+
+    pub fn signature_status(&self) -> SignatureStatus {
+        if self.proof.verify().is_ok() {
+            SignatureStatus::Valid
+        } else {
+            SignatureStatus::Invalid
+        }
+    }
+
+    fn id_label(&self, id: &PubId) -> String {
+        let url = self.db.lookup_url(&id.id).unwrap_or(&id.url);
+        format!("{} ({})", id.id, url.url)
+    }
+
+    fn package_label(package: &proof::PackageInfo) -> String {
+        format!("{} {} ({})", package.name, package.version, package.source)
+    }
+
+    fn review_rows(rows: &mut Vec<(&'static str, String)>, review: &crev_data::Review) {
+        rows.push(("Thoroughness", review.thoroughness.to_string()));
+        rows.push(("Understanding", review.understanding.to_string()));
+        rows.push(("Rating", review.rating.to_string()));
+    }
+
+    /// All the fields to display, except the signature status
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let content = &self.proof.content;
+        let date = content.date();
+        let mut rows = vec![
+            ("Type", type_label(content).to_string()),
+            ("Digest", crev_common::base64_encode(&self.proof.digest)),
+            (
+                "Date",
+                format!(
+                    "{} ({})",
+                    date.format("%Y-%m-%d %H:%M:%S %:z"),
+                    humanize_age(&date.with_timezone(&Utc), &self.now)
+                ),
+            ),
+        ];
+        if let Some(expires) = content.expires() {
+            let expired = if content.is_expired_at(&self.now) {
+                " (expired)"
+            } else {
+                ""
+            };
+            rows.push((
+                "Expires",
+                format!("{}{}", expires.format("%Y-%m-%d %H:%M:%S %:z"), expired),
+            ));
+        }
+
+        match content {
+            Content::Trust(trust) => {
+                rows.push(("From", self.id_label(&trust.from)));
+                rows.push((
+                    "Ids",
+                    trust
+                        .ids
+                        .iter()
+                        .map(|id| self.id_label(id))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ));
+                rows.push(("Trust", trust.trust.to_string()));
+                if !trust.dimensions.is_empty() {
+                    rows.push(("Dimensions", trust.dimensions.to_string()));
+                }
+                rows.push(("Comment", trust.comment.clone()));
+            }
+            Content::Package(package) => {
+                rows.push(("From", self.id_label(&package.from)));
+                rows.push(("Package", Self::package_label(&package.package)));
+                rows.push((
+                    "Package digest",
+                    crev_common::base64_encode(&package.package.digest),
+                ));
+                Self::review_rows(&mut rows, &package.review);
+                rows.push(("Comment", package.comment.clone()));
+            }
+            Content::Code(code) => {
+                rows.push(("From", self.id_label(&code.from)));
+                rows.push(("Package", Self::package_label(&code.package)));
+                rows.push((
+                    "Files",
+                    code.files
+                        .iter()
+                        .map(|file| {
+                            format!(
+                                "{} {}",
+                                crev_common::base64_encode(&file.digest),
+                                file.path.display()
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ));
+                if let Some(ref size) = code.size {
+                    rows.push(("Size", size.to_string()));
+                }
+                Self::review_rows(&mut rows, code.review());
+                rows.push(("Comment", code.comment.clone()));
+                if !code.groups.is_empty() {
+                    rows.push((
+                        "Groups",
+                        code.groups
+                            .iter()
+                            .map(|group| format!("{}: {}", group.path.display(), group.comment))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ));
+                }
+            }
+            Content::Project(project) => {
+                rows.push(("From", self.id_label(&project.from)));
+                rows.push(("Project", project.project_id()));
+                rows.push(("Name", project.name.clone()));
+                rows.push(("URL", project.url.clone()));
+                rows.push(("Comment", project.comment.clone()));
+            }
+            Content::ProjectMigration(migration) => {
+                rows.push(("From", self.id_label(&migration.from)));
+                rows.push(("Project", migration.project.clone()));
+                rows.push(("Moved to", migration.successor_id()));
+                rows.push(("URL", migration.url.clone()));
+                rows.push(("Comment", migration.comment.clone()));
+            }
+            Content::TrustRootHandover(handover) => {
+                rows.push(("From", self.id_label(&handover.from)));
+                rows.push(("To", self.id_label(&handover.to)));
+                rows.push(("Comment", handover.comment.clone()));
+            }
+            Content::IdLink(link) => {
+                rows.push(("From", self.id_label(&link.from)));
+                rows.push(("To", self.id_label(&link.to)));
+                rows.push(("Level", link.level.to_string()));
+                rows.push(("Comment", link.comment.clone()));
+            }
+            Content::PathMove(path_move) => {
+                rows.push(("From", self.id_label(&path_move.from)));
+                rows.push(("Package", path_move.package.name.clone()));
+                rows.push((
+                    "Moves",
+                    path_move
+                        .moves
+                        .iter()
+                        .map(|m| format!("{} -> {}", m.from.display(), m.to.display()))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ));
+                rows.push(("Comment", path_move.comment.clone()));
+            }
+            Content::BuildAttestation(attestation) => {
+                rows.push(("From", self.id_label(&attestation.from)));
+                rows.push(("Package", attestation.package.name.clone()));
+                rows.push(("Version", attestation.package.version.clone()));
+                rows.push(("Revision", attestation.package.revision.clone()));
+                rows.push(("Artifact", attestation.artifact.name.clone()));
+                rows.push((
+                    "Artifact digest",
+                    crev_common::base64_encode(&attestation.artifact.digest),
+                ));
+                rows.push(("Environment", attestation.environment.clone()));
+                rows.push(("Comment", attestation.comment.clone()));
+            }
+            Content::ReviewRequest(request) => {
+                rows.push(("From", self.id_label(&request.from)));
+                rows.push(("Package", request.package.name.clone()));
+                rows.push(("Version", request.package.version.clone()));
+                rows.push(("Revision", request.package.revision.clone()));
+                rows.push(("Comment", request.comment.clone()));
+            }
+            Content::Revocation(revocation) => {
+                rows.push(("From", self.id_label(&revocation.from)));
+                rows.push((
+                    "Proofs",
+                    revocation
+                        .proofs
+                        .iter()
+                        .map(|proof| crev_common::base64_encode(&proof.digest))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ));
+                rows.push(("Comment", revocation.comment.clone()));
+            }
+            Content::Advisory(advisory) => {
+                rows.push(("From", self.id_label(&advisory.from)));
+                rows.push(("Project", advisory.project.clone()));
+                rows.push(("Severity", advisory.severity.to_string()));
+                rows.push(("Introduced", advisory.affected.introduced.clone()));
+                rows.push(("Fixed", advisory.affected.fixed.clone()));
+                rows.push((
+                    "Files",
+                    advisory
+                        .files
+                        .iter()
+                        .map(|file| file.path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ));
+                rows.push(("Comment", advisory.comment.clone()));
+            }
+        }
+        rows.push(("Tags", content.tags().join(", ")));
+
+        rows.retain(|(_, value)| !value.is_empty());
+        rows
+    }
+
+    /// Render as a standalone document, eg. to post on a PR
+    ///
+    /// Along with all the fields, the document includes the proof in its
+    /// signed form, so its readers can verify it themselves.
+    pub fn to_report(&self, format: ReportFormat) -> String {
+        let title = self.proof.content.draft_title();
+        let mut rows = self.rows();
+        rows.push(("Signature", self.signature_status().to_string()));
+        let signed = self.proof.to_string();
+
+        let mut out = String::new();
+        match format {
+            ReportFormat::Markdown => Self::write_markdown(&mut out, &title, &rows, &signed),
+            ReportFormat::Html => Self::write_html(&mut out, &title, &rows, &signed),
+        }
+        .expect("writing to a String can't fail");
+        out
+    }
+
+    fn write_markdown(
+        out: &mut String,
+        title: &str,
+        rows: &[(&str, String)],
+        signed: &str,
+    ) -> fmt::Result {
+        writeln!(out, "# {}", markdown_escape(title))?;
+        writeln!(out)?;
+        for (label, value) in rows {
+            if value.contains('\n') {
+                let fence = markdown_fence(value);
+                writeln!(out, "* **{}**:", label)?;
+                writeln!(out)?;
+                writeln!(out, "  {}text", fence)?;
+                for line in value.lines() {
+                    writeln!(out, "  {}", line)?;
+                }
+                writeln!(out, "  {}", fence)?;
+            } else {
+                writeln!(out, "* **{}**: {}", label, markdown_escape(value))?;
+            }
+        }
+        writeln!(out)?;
+        writeln!(out, "## Signed proof")?;
+        writeln!(out)?;
+        let fence = markdown_fence(signed);
+        writeln!(out, "{}text", fence)?;
+        write!(out, "{}", signed)?;
+        writeln!(out, "{}", fence)
+    }
+
+    fn write_html(
+        out: &mut String,
+        title: &str,
+        rows: &[(&str, String)],
+        signed: &str,
+    ) -> fmt::Result {
+        let title = html_escape(title);
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{}</title>", title)?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+        writeln!(out, "<table>")?;
+        for (label, value) in rows {
+            let value = if value.contains('\n') {
+                format!("<pre>{}</pre>", html_escape(value))
+            } else {
+                html_escape(value)
+            };
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", label, value)?;
+        }
+        writeln!(out, "</table>")?;
+        writeln!(out, "<h2>Signed proof</h2>")?;
+        writeln!(out, "<pre>{}</pre>", html_escape(signed))?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")
+    }
+
+    /// Print to stdout, highlighting the signature status if it's a terminal
+    pub fn print(&self) -> io::Result<()> {
+        let rows = self.rows();
+        let width = label_width(&rows);
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        for (label, value) in &rows {
+            write_row(&mut stdout, width, label, value)?;
+        }
+        stdout.flush()?;
+        drop(stdout);
+
+        let status = self.signature_status();
+        let mut term = term::stdout();
+        let use_color = atty::is(atty::Stream::Stdout)
+            && term.as_ref().map(|t| t.supports_color()).unwrap_or(false);
+        match term {
+            Some(ref mut term) if use_color => {
+                write!(term, "{:width$} ", "Signature:", width = width + 1)?;
+                if let Some(color) = status.color() {
+                    term.fg(color)?;
+                }
+                write!(term, "{}", status)?;
+                term.reset()?;
+                writeln!(term)?;
+            }
+            _ => write_row(&mut io::stdout(), width, "Signature", &status.to_string())?,
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Pretty<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.rows();
+        let width = label_width(&rows);
+        let mut out = vec![];
+        for (label, value) in &rows {
+            write_row(&mut out, width, label, value).map_err(|_| fmt::Error)?;
+        }
+        write_row(
+            &mut out,
+            width,
+            "Signature",
+            &self.signature_status().to_string(),
+        )
+        .map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&out))
+    }
+}
+
+fn label_width(rows: &[(&str, String)]) -> usize {
+    rows.iter()
+        .map(|(label, _)| label.len())
+        .chain(Some("Signature".len()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Write `label: value`, with multi-line values indented under the first line
+fn write_row(w: &mut dyn Write, width: usize, label: &str, value: &str) -> io::Result<()> {
+    let label = format!("{}:", label);
+    for (i, line) in value.lines().enumerate() {
+        if i == 0 {
+            writeln!(w, "{:width$} {}", label, line, width = width + 1)?;
+        } else {
+            writeln!(w, "{:width$} {}", "", line, width = width + 1)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,301 @@
+//! Index of the proofs in the proof stores, to find them without parsing
+//! every proof file
+//!
+//! Kept in the cache, it maps projects, reviewed files, authors, dates
+//! and digests to where the proofs are: their file, and their position in
+//! it. Files are indexed as a whole, again whenever their size or
+//! modification time change, so the index can't get ahead of them: see
+//! `Local::proof_index`, updating it before it's used, and
+//! `Local::rebuild_proof_index`, starting over.
+use crate::prelude::*;
+use chrono::{self, prelude::*};
+use crev_common::serde::{as_rfc3339_fixed, from_rfc3339_fixed};
+use crev_data::{proof, Id};
+use serde_cbor;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+const CURRENT_PROOF_INDEX_VERSION: i64 = -2;
+
+/// Where a proof is stored
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    /// Position of the proof in the file, from 0
+    pub index: usize,
+}
+
+impl Location {
+    /// Read the proof back, without verifying it
+    pub fn read(&self) -> Result<proof::Proof> {
+        proof::Proof::parse_from(&self.path)?
+            .into_iter()
+            .nth(self.index)
+            .ok_or_else(|| format_err!("Proof moved out of {}", self.path.display()))
+    }
+}
+
+/// What a proof is found by
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub digest: Vec<u8>,
+    /// As labeled by `crate::proof::type_label`, eg. `code review`
+    #[serde(rename = "proof-type")]
+    pub proof_type: String,
+    pub from: Id,
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    /// Name of the package reviewed
+    pub package: Option<String>,
+    /// Id of the project reviewed, or declared
+    pub project: Option<Id>,
+    /// Files reviewed, relative to the root of the project
+    pub files: Vec<PathBuf>,
+    pub location: Location,
+}
+
+impl Entry {
+    fn of(proof: &proof::Proof, location: Location) -> Self {
+        let (package, files) = match proof.content {
+            proof::Content::Code(ref code) => (
+                Some(&code.package),
+                code.files.iter().map(|file| file.path.clone()).collect(),
+            ),
+            proof::Content::Package(ref package) => (Some(&package.package), vec![]),
+            _ => (None, vec![]),
+        };
+        let project = match proof.content {
+            proof::Content::Project(ref project) => Some(project.from.id.clone()),
+            _ => package.and_then(|package| package.id.as_ref().map(|id| id.id.clone())),
+        };
+        Entry {
+            digest: proof.digest.clone(),
+            proof_type: crate::proof::type_label(&proof.content).to_owned(),
+            from: proof.content.author_id(),
+            date: *proof.content.date(),
+            package: package.map(|package| package.name.clone()),
+            project,
+            files,
+            location,
+        }
+    }
+}
+
+/// What `Local::find_proofs` looks for; fields not set match anything
+#[derive(Debug, Clone, Default)]
+pub struct ProofFilter {
+    /// Id of the project, see `Entry::project`
+    pub project: Option<Id>,
+    pub package: Option<String>,
+    pub author: Option<Id>,
+    /// A file reviewed, relative to the root of the project
+    pub path: Option<PathBuf>,
+    /// Proofs dated from then on
+    pub since: Option<chrono::DateTime<FixedOffset>>,
+    /// Proofs dated up to then
+    pub until: Option<chrono::DateTime<FixedOffset>>,
+    /// As labeled by `crate::proof::type_label`, eg. `code review`
+    pub proof_type: Option<String>,
+}
+
+impl ProofFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let matching = [
+            unset_or(&self.project, |id| entry.project.as_ref() == Some(id)),
+            unset_or(&self.package, |name| entry.package.as_ref() == Some(name)),
+            unset_or(&self.author, |id| entry.from == *id),
+            unset_or(&self.path, |path| entry.files.contains(path)),
+            unset_or(&self.since, |since| *since <= entry.date),
+            unset_or(&self.until, |until| entry.date <= *until),
+            unset_or(&self.proof_type, |label| entry.proof_type == *label),
+        ];
+        matching.iter().all(|&matching| matching)
+    }
+}
+
+/// Whether `field` of a filter isn't set, or matches by `matches`
+fn unset_or<T>(field: &Option<T>, matches: impl FnOnce(&T) -> bool) -> bool {
+    match field {
+        Some(value) => matches(value),
+        None => true,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IndexedFile {
+    len: u64,
+    modified: Option<SystemTime>,
+    entries: Vec<Entry>,
+}
+
+/// What changed a file was indexed in
+fn file_stamp(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+/// The proof files under `root`
+fn proof_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("crev")
+        })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProofIndex {
+    version: i64,
+    files: BTreeMap<PathBuf, IndexedFile>,
+}
+
+impl Default for ProofIndex {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_PROOF_INDEX_VERSION,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+impl ProofIndex {
+    /// Load the index stored at `path`
+    ///
+    /// An index that can't be read, or of another version, is only a
+    /// reason to build it again, so an empty one is returned.
+    pub fn load(path: &Path) -> Self {
+        fs::File::open(path)
+            .ok()
+            .and_then(|file| serde_cbor::from_reader::<ProofIndex, _>(&file).ok())
+            .filter(|index| index.version == CURRENT_PROOF_INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    pub fn store(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        serde_cbor::to_writer(&mut file, self)?;
+        file.flush()?;
+        drop(file);
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Index the proof files under `roots` changed since they were
+    /// indexed, and forget the ones gone, returning whether anything
+    /// changed
+    pub fn update(&mut self, roots: &[PathBuf]) -> bool {
+        let mut changed = false;
+        let mut present = BTreeSet::new();
+        for path in roots.iter().flat_map(|root| proof_files(root)) {
+            let stamp = file_stamp(&path);
+            present.insert(path.clone());
+            let up_to_date = match (self.files.get(&path), stamp) {
+                (Some(file), Some((len, modified))) => {
+                    file.len == len && file.modified == modified && modified.is_some()
+                }
+                _ => false,
+            };
+            if !up_to_date {
+                self.update_file(&path);
+                changed = true;
+            }
+        }
+        let before = self.files.len();
+        self.files.retain(|path, _| present.contains(path));
+        changed || self.files.len() != before
+    }
+
+    /// Index the proofs of the file at `path` again
+    ///
+    /// A file that can't be parsed is indexed with no proofs, as loading
+    /// the proofs ignores it as well.
+    pub fn update_file(&mut self, path: &Path) {
+        let (len, modified) = match file_stamp(path) {
+            Some(stamp) => stamp,
+            None => {
+                self.files.remove(path);
+                return;
+            }
+        };
+        let entries = proof::Proof::parse_from(path)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(index, proof)| {
+                Entry::of(
+                    proof,
+                    Location {
+                        path: path.to_owned(),
+                        index,
+                    },
+                )
+            })
+            .collect();
+        self.files.insert(
+            path.to_owned(),
+            IndexedFile {
+                len,
+                modified,
+                entries,
+            },
+        );
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.files.values().flat_map(|file| &file.entries)
+    }
+
+    pub fn matching<'a>(&'a self, filter: &'a ProofFilter) -> impl Iterator<Item = &'a Entry> {
+        self.entries().filter(move |entry| filter.matches(entry))
+    }
+
+    pub fn by_package<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries()
+            .filter(move |entry| entry.package.as_deref() == Some(name))
+    }
+
+    pub fn by_project<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = &'a Entry> {
+        self.entries()
+            .filter(move |entry| entry.project.as_ref() == Some(id))
+    }
+
+    /// Reviews of the file at `path`, relative to the root of a project
+    pub fn by_file<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a Entry> {
+        self.entries()
+            .filter(move |entry| entry.files.iter().any(|file| file == path))
+    }
+
+    pub fn by_author<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = &'a Entry> {
+        self.entries().filter(move |entry| entry.from == *id)
+    }
+
+    /// Proofs dated from `since`, up to `until`
+    pub fn by_date(
+        &self,
+        since: chrono::DateTime<FixedOffset>,
+        until: chrono::DateTime<FixedOffset>,
+    ) -> impl Iterator<Item = &Entry> {
+        self.entries()
+            .filter(move |entry| since <= entry.date && entry.date <= until)
+    }
+
+    /// Proofs whose base64 digest starts with `prefix`
+    pub fn by_digest_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries()
+            .filter(move |entry| crev_common::base64_encode(&entry.digest).starts_with(prefix))
+    }
+}
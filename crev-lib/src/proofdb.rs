@@ -1,15 +1,25 @@
+use crate::prelude::*;
 use crate::VerificationStatus;
-use chrono::{self, offset::Utc, DateTime};
+use chrono::{self, offset::Utc, DateTime, TimeZone};
 use crev_data::{
     self,
-    proof::review::Rating,
-    proof::trust::TrustLevel,
+    id_bundle::{Capability, SignedPubIdBundle},
+    org_policy::SignedOrgPolicy,
+    proof::advisory::Severity,
+    proof::review::{
+        code::{Anchor, Normalization},
+        Rating,
+    },
+    proof::trust::{TrustDimension, TrustDimensions, TrustLevel},
     proof::{self, review, Content, ContentCommon},
+    seed_list::{SeedEntry, SignedSeedList},
     Digest, Id, Url,
 };
 use default::default;
+use serde_cbor;
 use std::collections::BTreeMap;
 use std::collections::{hash_map, BTreeSet, HashMap, HashSet};
+use std::{fmt, fs, io::Write, path::Path};
 
 /// A `T` with a timestamp
 ///
@@ -24,6 +34,7 @@ pub struct Timestamped<T> {
 impl<T> Timestamped<T> {
     fn update_to_more_recent(&mut self, date: &chrono::DateTime<Utc>, value: T) {
         if self.date < *date {
+            self.date = *date;
             self.value = value;
         }
     }
@@ -40,15 +51,47 @@ impl<T> Timestamped<T> {
     }
 }
 
+/// How far in the future a proof can be dated before it's future-dated,
+/// to allow for clocks being off a bit
+const MAX_CLOCK_SKEW_MINUTES: i64 = 60;
+
+/// Proofs dated before crev existed are implausibly old
+fn earliest_plausible_date() -> DateTime<Utc> {
+    Utc.ymd(2018, 1, 1).and_hms(0, 0, 0)
+}
+
 type TimestampedUrl = Timestamped<Url>;
-type TimestampedTrustLevel = Timestamped<TrustLevel>;
+type TimestampedTrust = Timestamped<TrustEdge>;
 type TimestampedReview = Timestamped<review::Review>;
+// (author, artifact name) -> most recent Build Attestation Proof
+type BuildAttestationsByAuthor = HashMap<(Id, String), Timestamped<proof::BuildAttestation>>;
 
-impl From<proof::Trust> for TimestampedTrustLevel {
+/// Trust of one Id in another, as in their most recent Trust Proof
+#[derive(Clone, Debug)]
+struct TrustEdge {
+    trust: TrustLevel,
+    dimensions: TrustDimensions,
+    kind: TrustLinkKind,
+}
+
+impl TrustEdge {
+    fn level_for(&self, dimension: TrustDimension) -> TrustLevel {
+        if self.trust == TrustLevel::Distrust {
+            return TrustLevel::None;
+        }
+        self.dimensions.level_for(dimension, self.trust)
+    }
+}
+
+impl From<proof::Trust> for TimestampedTrust {
     fn from(trust: proof::Trust) -> Self {
-        TimestampedTrustLevel {
+        TimestampedTrust {
             date: trust.date().with_timezone(&Utc),
-            value: trust.trust,
+            value: TrustEdge {
+                trust: trust.trust,
+                dimensions: trust.dimensions,
+                kind: TrustLinkKind::Proof,
+            },
         }
     }
 }
@@ -79,6 +122,55 @@ pub struct UniquePackageReview {
     version: String,
 }
 
+/// Review coverage of one version of a package, in a trust set
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionCoverage {
+    pub status: VerificationStatus,
+    /// Number of trusted reviews rating the version neutral or better
+    pub trusted_reviews: usize,
+}
+
+impl Default for VersionCoverage {
+    fn default() -> Self {
+        VersionCoverage {
+            status: VerificationStatus::Unknown,
+            trusted_reviews: 0,
+        }
+    }
+}
+
+/// Number of `reviews` by trusted ids, rating the package neutral or better
+fn trusted_review_count(reviews: &[review::Package], trust_set: &TrustSet) -> usize {
+    reviews
+        .iter()
+        .filter(|review| {
+            Rating::Neutral <= review.review.rating
+                && trust_set
+                    .get_effective_trust_level(&review.from.id)
+                    .is_some()
+        })
+        .count()
+}
+
+impl VersionCoverage {
+    fn rank(&self) -> (u8, TrustLevel, usize) {
+        match self.status {
+            VerificationStatus::Dangerous => (0, TrustLevel::None, 0),
+            VerificationStatus::Flagged => (1, TrustLevel::None, 0),
+            VerificationStatus::Unknown => (2, TrustLevel::None, 0),
+            VerificationStatus::Verified(level) => (3, level, self.trusted_reviews),
+        }
+    }
+
+    /// Is `self` a better supply-chain posture than `other`
+    ///
+    /// Flags are worse than no reviews, and verified versions compare by
+    /// the trust in their reviewers first, and by number of reviews second.
+    pub fn is_better_than(&self, other: &VersionCoverage) -> bool {
+        self.rank() > other.rank()
+    }
+}
+
 type TimestampedSignature = Timestamped<String>;
 
 impl From<review::Package> for UniquePackageReview {
@@ -111,10 +203,17 @@ where
 /// all known proofs, and then query. If it ever becomes too slow,
 /// all the logic here will have to be moved to a real embedded db
 /// of some kind.
+#[derive(Clone)]
 pub struct ProofDB {
-    trust_id_to_id: HashMap<Id, HashMap<Id, TimestampedTrustLevel>>, // who -(trusts)-> whom
+    trust_id_to_id: HashMap<Id, HashMap<Id, TimestampedTrust>>, // who -(trusts)-> whom
+    // who -(links)-> which other id of theirs, see `id_link_level`
+    id_links: HashMap<Id, HashMap<Id, Timestamped<proof::IdLink>>>,
     url_by_id: HashMap<Id, TimestampedUrl>,
     url_by_id_secondary: HashMap<Id, TimestampedUrl>,
+    // every URL an id claimed in its own proofs, and the other way around;
+    // URLs normalized with `normalized_url`
+    urls_by_id: BTreeMap<Id, BTreeSet<String>>,
+    ids_by_url: BTreeMap<String, BTreeSet<Id>>,
 
     package_review_by_signature: HashMap<String, review::Package>,
 
@@ -126,20 +225,72 @@ pub struct ProofDB {
     package_reviews_by_source: BTreeMap<String, HashSet<UniquePackageReview>>,
     package_reviews_by_name: BTreeMap<(String, String), HashSet<UniquePackageReview>>,
     package_reviews_by_version: BTreeMap<(String, String, String), HashSet<UniquePackageReview>>,
+
+    // project id -> trust root -> most recent Project Proof
+    project_by_id: HashMap<String, HashMap<Id, Timestamped<proof::Project>>>,
+    // old project id -> author -> most recent Project Migration Proof
+    project_migrations: HashMap<String, HashMap<Id, Timestamped<proof::ProjectMigration>>>,
+
+    // file digest -> author -> most recent Code Review Proof including it
+    code_reviews_by_file_digest: HashMap<Vec<u8>, HashMap<Id, Timestamped<review::Code>>>,
+    // anchor digest -> author -> most recent Code Review Proof including it
+    code_reviews_by_anchor_digest: HashMap<Vec<u8>, HashMap<Id, Timestamped<review::Code>>>,
+    // normalization lists recorded with reviewed files
+    code_review_normalizations: BTreeSet<Vec<Normalization>>,
+    // source digest -> attestations of builds from it
+    build_attestations_by_package_digest: HashMap<Vec<u8>, BuildAttestationsByAuthor>,
+
+    // id -> capabilities it's restricted to by its most recent bundle, if any
+    capabilities_by_id: HashMap<Id, Timestamped<Option<HashSet<Capability>>>>,
+
+    // ids revoked as compromised, by their own proof repository
+    revoked_ids: HashSet<Id>,
+    // proof digest -> ids that revoked it
+    revoked_by: HashMap<Vec<u8>, HashSet<Id>>,
+    // proof digest -> authors of the proofs superseding it
+    superseded_by: HashMap<Vec<u8>, HashSet<Id>>,
+    // id -> date of its most recent proof
+    last_proof_date_by_id: HashMap<Id, DateTime<Utc>>,
+
+    advisory_by_signature: HashMap<String, proof::Advisory>,
+    // project id -> signatures of the advisories for it
+    advisory_signatures_by_project: HashMap<String, HashSet<String>>,
+    // file digest -> signatures of the advisories flagging it
+    advisory_signatures_by_file_digest: HashMap<Vec<u8>, HashSet<String>>,
+
+    reject_future_dated: bool,
 }
 
 impl Default for ProofDB {
     fn default() -> Self {
         ProofDB {
             trust_id_to_id: default(),
+            id_links: default(),
             url_by_id: default(),
             url_by_id_secondary: default(),
+            urls_by_id: default(),
+            ids_by_url: default(),
             package_review_signatures_by_package_digest: default(),
             package_review_signatures_by_unique_package_review: default(),
             package_review_by_signature: default(),
             package_reviews_by_source: default(),
             package_reviews_by_name: default(),
             package_reviews_by_version: default(),
+            project_by_id: default(),
+            project_migrations: default(),
+            code_reviews_by_file_digest: default(),
+            code_reviews_by_anchor_digest: default(),
+            code_review_normalizations: default(),
+            build_attestations_by_package_digest: default(),
+            capabilities_by_id: default(),
+            revoked_ids: default(),
+            revoked_by: default(),
+            superseded_by: default(),
+            last_proof_date_by_id: default(),
+            advisory_by_signature: default(),
+            advisory_signatures_by_project: default(),
+            advisory_signatures_by_file_digest: default(),
+            reject_future_dated: false,
         }
     }
 }
@@ -149,11 +300,43 @@ impl ProofDB {
         default()
     }
 
+    /// Ignore proofs dated in the future from now on, instead of only
+    /// warning about them
+    ///
+    /// Otherwise such a proof stays the most recent one of its author
+    /// until that date, overriding anything they publish in the meantime.
+    pub fn set_reject_future_dated(&mut self, reject: bool) {
+        self.reject_future_dated = reject;
+    }
+
     fn add_code_review(&mut self, review: &review::Code) {
         let from = &review.from;
         self.record_url_from_from_field(&review.date_utc(), &from);
-        for _file in &review.files {
-            // not implemented right now; just ignore
+        for file in &review.files {
+            self.code_review_normalizations
+                .insert(file.normalization.clone());
+            Timestamped {
+                date: review.date_utc(),
+                value: review.clone(),
+            }
+            .insert_into_or_update_to_more_recent(
+                self.code_reviews_by_file_digest
+                    .entry(file.digest.clone())
+                    .or_default()
+                    .entry(from.id.clone()),
+            );
+            for anchor in &file.anchors {
+                Timestamped {
+                    date: review.date_utc(),
+                    value: review.clone(),
+                }
+                .insert_into_or_update_to_more_recent(
+                    self.code_reviews_by_anchor_digest
+                        .entry(anchor.digest.clone())
+                        .or_default()
+                        .entry(from.id.clone()),
+                );
+            }
         }
     }
 
@@ -249,8 +432,79 @@ impl ProofDB {
         proofs.into_iter()
     }
 
-    fn add_trust_raw(&mut self, from: &Id, to: &Id, date: DateTime<Utc>, trust: TrustLevel) {
-        TimestampedTrustLevel { value: trust, date }.insert_into_or_update_to_more_recent(
+    /// Review coverage of every reviewed version of a package
+    ///
+    /// Only the reviews of ids allowed to review packages count; see
+    /// `allows`. The reviews of a version are told apart by the digest
+    /// they reviewed: if trusted ones disagree on it, they can't all be of
+    /// the published version, so the version is flagged.
+    pub fn get_package_version_coverage(
+        &self,
+        source: &str,
+        name: &str,
+        trust_set: &TrustSet,
+    ) -> HashMap<String, VersionCoverage> {
+        let mut reviews_by_version: HashMap<String, HashMap<Vec<u8>, Vec<review::Package>>> =
+            HashMap::new();
+        for review in self.get_package_reviews_for_package(source, Some(name), None) {
+            if self.allows(&review.from.id, Capability::PackageReview) {
+                reviews_by_version
+                    .entry(review.package.version.clone())
+                    .or_default()
+                    .entry(review.package.digest.clone())
+                    .or_default()
+                    .push(review);
+            }
+        }
+        reviews_by_version
+            .into_iter()
+            .map(|(version, reviews_by_digest)| {
+                let trusted: Vec<_> = reviews_by_digest
+                    .values()
+                    .filter(|reviews| {
+                        reviews.iter().any(|review| {
+                            trust_set
+                                .get_effective_trust_level(&review.from.id)
+                                .is_some()
+                        })
+                    })
+                    .collect();
+                let coverage = match trusted.as_slice() {
+                    [] => VersionCoverage::default(),
+                    [reviews] => VersionCoverage {
+                        status: verification_status(
+                            reviews
+                                .iter()
+                                .map(|review| (&review.from.id, &review.review.rating)),
+                            trust_set,
+                        ),
+                        trusted_reviews: trusted_review_count(reviews, trust_set),
+                    },
+                    _ => VersionCoverage {
+                        status: VerificationStatus::Flagged,
+                        trusted_reviews: 0,
+                    },
+                };
+                (version, coverage)
+            })
+            .collect()
+    }
+
+    /// Review coverage of the package with `digest`, eg. a downloaded one
+    pub fn get_package_digest_coverage(
+        &self,
+        digest: &Digest,
+        trust_set: &TrustSet,
+    ) -> VersionCoverage {
+        let reviews: Vec<_> = self.get_package_reviews_by_digest(digest).collect();
+        VersionCoverage {
+            status: self.verify_package_digest(digest, trust_set),
+            trusted_reviews: trusted_review_count(&reviews, trust_set),
+        }
+    }
+
+    fn add_trust_raw(&mut self, from: &Id, to: &Id, date: DateTime<Utc>, trust: TrustEdge) {
+        TimestampedTrust { value: trust, date }.insert_into_or_update_to_more_recent(
             self.trust_id_to_id
                 .entry(from.to_owned())
                 .or_insert_with(HashMap::new)
@@ -262,13 +516,224 @@ impl ProofDB {
         let from = &trust.from;
         self.record_url_from_from_field(&trust.date_utc(), &from);
         for to in &trust.ids {
-            self.add_trust_raw(&from.id, &to.id, trust.date_utc(), trust.trust);
+            let edge = TrustEdge {
+                trust: trust.trust,
+                dimensions: trust.dimensions.clone(),
+                kind: TrustLinkKind::Proof,
+            };
+            self.add_trust_raw(&from.id, &to.id, trust.date_utc(), edge);
         }
         for to in &trust.ids {
             self.record_url_from_to_field(&trust.date_utc(), &to)
         }
     }
 
+    fn add_id_link(&mut self, link: &proof::IdLink) {
+        self.record_url_from_from_field(&link.date_utc(), &link.from);
+        self.record_url_from_to_field(&link.date_utc(), &link.to);
+        Timestamped {
+            date: link.date_utc(),
+            value: link.clone(),
+        }
+        .insert_into_or_update_to_more_recent(
+            self.id_links
+                .entry(link.from.id.clone())
+                .or_default()
+                .entry(link.to.id.clone()),
+        );
+    }
+
+    /// Level of the most recent link of `from` to `to`, if `to` links
+    /// back to `from`
+    ///
+    /// Without the link back, anyone could claim an id as theirs.
+    pub fn id_link_level(&self, from: &Id, to: &Id) -> Option<TrustLevel> {
+        let link = self.id_links.get(from)?.get(to)?;
+        self.id_links.get(to)?.get(from)?;
+        Some(link.value.level)
+    }
+
+    /// Ids linked to `id` both ways, with the level of the link from `id`
+    pub fn linked_ids(&self, id: &Id) -> Vec<(Id, TrustLevel)> {
+        self.id_links
+            .get(id)
+            .into_iter()
+            .flat_map(|links| links.keys())
+            .filter_map(|to| Some((to.clone(), self.id_link_level(id, to)?)))
+            .collect()
+    }
+
+    /// Only the URLs are recorded; handovers are followed when verifying
+    /// the proofs of a package, see `repo::split_authorized_proofs`
+    fn add_trust_root_handover(&mut self, handover: &proof::TrustRootHandover) {
+        self.record_url_from_from_field(&handover.date_utc(), &handover.from);
+        self.record_url_from_to_field(&handover.date_utc(), &handover.to);
+    }
+
+    /// Only the URL is recorded; moves are followed when verifying the
+    /// files of a package, see `repo::Repo::renames`
+    fn add_path_move(&mut self, path_move: &proof::PathMove) {
+        self.record_url_from_from_field(&path_move.date_utc(), &path_move.from);
+    }
+
+    fn add_build_attestation(&mut self, attestation: &proof::BuildAttestation) {
+        self.record_url_from_from_field(&attestation.date_utc(), &attestation.from);
+        Timestamped {
+            date: attestation.date_utc(),
+            value: attestation.clone(),
+        }
+        .insert_into_or_update_to_more_recent(
+            self.build_attestations_by_package_digest
+                .entry(attestation.package.digest.clone())
+                .or_default()
+                .entry((
+                    attestation.from.id.clone(),
+                    attestation.artifact.name.clone(),
+                )),
+        );
+    }
+
+    fn add_project(&mut self, project: &proof::Project) {
+        self.record_url_from_from_field(&project.date_utc(), &project.from);
+        Timestamped {
+            date: project.date_utc(),
+            value: project.clone(),
+        }
+        .insert_into_or_update_to_more_recent(
+            self.project_by_id
+                .entry(project.project_id())
+                .or_default()
+                .entry(project.from.id.clone()),
+        );
+    }
+
+    fn all_projects(&self) -> impl Iterator<Item = &proof::Project> {
+        self.project_by_id
+            .values()
+            .flat_map(|claims| claims.values())
+            .map(|claim| &claim.value)
+            .filter(move |project| self.allows(&project.from.id, Capability::Project))
+    }
+
+    /// Find out who claims to be the trust root of a project
+    ///
+    /// A project collides with others if more than one trust root claims
+    /// its id, or another trust root claims a project with the same name.
+    pub fn get_project_identity(&self, project_id: &str) -> ProjectIdentity<'_> {
+        let claims: Vec<_> = self
+            .project_by_id
+            .get(project_id)
+            .into_iter()
+            .flat_map(|claims| claims.values())
+            .map(|claim| &claim.value)
+            .filter(|project| self.allows(&project.from.id, Capability::Project))
+            .collect();
+        if claims.is_empty() {
+            return ProjectIdentity::Unknown;
+        }
+
+        let names: HashSet<_> = claims.iter().map(|p| p.name.to_lowercase()).collect();
+        let mut colliding = claims.clone();
+        colliding.extend(self.all_projects().filter(|other| {
+            other.project_id() != project_id
+                && names.contains(&other.name.to_lowercase())
+                && !claims.iter().any(|claim| claim.from.id == other.from.id)
+        }));
+
+        if colliding.len() == 1 {
+            ProjectIdentity::Unique(colliding[0])
+        } else {
+            colliding.sort_by_key(|p| p.date);
+            ProjectIdentity::Colliding(colliding)
+        }
+    }
+
+    fn add_project_migration(&mut self, migration: &proof::ProjectMigration) {
+        self.record_url_from_from_field(&migration.date_utc(), &migration.from);
+        Timestamped {
+            date: migration.date_utc(),
+            value: migration.clone(),
+        }
+        .insert_into_or_update_to_more_recent(
+            self.project_migrations
+                .entry(migration.project.clone())
+                .or_default()
+                .entry(migration.from.id.clone()),
+        );
+    }
+
+    /// Migration of a project to a new id, if its trust root published one
+    ///
+    /// Migrations published by anyone else, or of a project with
+    /// a colliding identity, are ignored.
+    pub fn get_project_migration(&self, project_id: &str) -> Option<&proof::ProjectMigration> {
+        let root = match self.get_project_identity(project_id) {
+            ProjectIdentity::Unique(project) => &project.from.id,
+            _ => return None,
+        };
+        self.project_migrations
+            .get(project_id)?
+            .get(root)
+            .map(|migration| &migration.value)
+    }
+
+    /// Id the project eventually migrated to (`project_id` if it didn't)
+    pub fn get_latest_project_id(&self, project_id: &str) -> String {
+        let mut visited = HashSet::new();
+        let mut current = project_id.to_owned();
+        while visited.insert(current.clone()) {
+            match self.get_project_migration(&current) {
+                Some(migration) => current = migration.successor_id(),
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Ids of the project and all the projects it continues
+    pub fn get_project_lineage(&self, project_id: &str) -> Vec<String> {
+        let mut lineage = vec![project_id.to_owned()];
+        let mut i = 0;
+        while i < lineage.len() {
+            let current = lineage[i].clone();
+            for old in self.project_migrations.keys() {
+                let continues = match self.get_project_migration(old) {
+                    Some(migration) => migration.successor_id() == current,
+                    None => false,
+                };
+                if continues && !lineage.contains(old) {
+                    lineage.push(old.clone());
+                }
+            }
+            i += 1;
+        }
+        lineage
+    }
+
+    /// Package reviews of the project and all the projects it continues
+    ///
+    /// Reviews are matched with projects by their `source`, which for
+    /// a project is the URL of its repository.
+    pub fn get_package_reviews_for_project(
+        &self,
+        project_id: &str,
+    ) -> impl Iterator<Item = proof::review::Package> {
+        let lineage: HashSet<_> = self.get_project_lineage(project_id).into_iter().collect();
+        let mut reviews: Vec<_> = self
+            .package_reviews_by_source
+            .keys()
+            .filter(|source| match proof::project::canonical_url(source) {
+                Ok(url) => lineage.contains(&proof::project::project_id_for_url(&url)),
+                Err(_) => false,
+            })
+            .flat_map(|source| self.get_package_reviews_for_package(source, None, None))
+            .collect();
+
+        reviews.sort_by(|a, b| a.date().cmp(b.date()));
+
+        reviews.into_iter()
+    }
+
     pub fn all_known_ids(&self) -> BTreeSet<Id> {
         self.url_by_id
             .keys()
@@ -291,53 +756,237 @@ impl ProofDB {
                         self.package_review_by_signature[&signature.value].clone()
                     })
             })
+            .filter(move |review| self.allows(&review.from.id, Capability::PackageReview))
     }
 
-    pub fn verify_package_digest(
+    /// Package Reviews of the whole package with `digest`, counting
+    /// towards its verification
+    ///
+    /// A diff review (one with a `diff_base`) vouches only for the changes
+    /// since its base, so unless it's negative, it counts only while the
+    /// package at its base digest is verified itself.
+    pub fn get_full_package_reviews_by_digest(
         &self,
         digest: &Digest,
         trust_set: &TrustSet,
+    ) -> Vec<review::Package> {
+        self.full_package_reviews_by_digest(digest.as_slice(), trust_set, &mut HashSet::new())
+    }
+
+    fn full_package_reviews_by_digest(
+        &self,
+        digest: &[u8],
+        trust_set: &TrustSet,
+        visiting: &mut HashSet<Vec<u8>>,
+    ) -> Vec<review::Package> {
+        // a chain of diff reviews leading back to the same digest verifies nothing
+        if !visiting.insert(digest.to_owned()) {
+            return vec![];
+        }
+        let mut reviews = vec![];
+        for review in self.get_package_reviews_by_digest(&Digest::from_vec(digest.to_owned())) {
+            let counts = match review.diff_base {
+                Some(ref base) if Rating::Neutral <= review.review.rating => self
+                    .package_digest_status(&base.digest, trust_set, visiting)
+                    .is_verified(),
+                _ => true,
+            };
+            if counts {
+                reviews.push(review);
+            }
+        }
+        visiting.remove(digest);
+        reviews
+    }
+
+    fn package_digest_status(
+        &self,
+        digest: &[u8],
+        trust_set: &TrustSet,
+        visiting: &mut HashSet<Vec<u8>>,
     ) -> VerificationStatus {
         let reviews: HashMap<Id, review::Package> = self
-            .get_package_reviews_by_digest(digest)
+            .full_package_reviews_by_digest(digest, trust_set, visiting)
+            .into_iter()
             .map(|review| (review.from.id.clone(), review))
             .collect();
-        // Faster somehow maybe?
-        let reviews_by: HashSet<Id, _> = reviews.keys().map(|s| s.to_owned()).collect();
-        let trusted_ids: HashSet<_> = trust_set.trusted_ids().cloned().collect();
-        let matching_reviewers = trusted_ids.intersection(&reviews_by);
-        let mut trust_count = 0;
-        let mut trust_level = TrustLevel::None;
-        let mut flagged_count = 0;
-        let mut dangerous_count = 0;
-        for matching_reviewer in matching_reviewers {
-            let rating = &reviews[matching_reviewer].review.rating;
-            if Rating::Neutral <= *rating {
-                trust_count += 1;
-                trust_level = std::cmp::max(
-                    trust_level,
-                    trust_set
-                        .get_effective_trust_level(matching_reviewer)
-                        .expect("Id should have been there"),
-                );
-            } else if *rating <= Rating::Dangerous {
-                dangerous_count += 1;
-            } else if *rating < Rating::Neutral {
-                flagged_count += 1;
-            }
+        verification_status(
+            reviews
+                .iter()
+                .map(|(id, review)| (id, &review.review.rating)),
+            trust_set,
+        )
+    }
+
+    pub fn verify_package_digest(
+        &self,
+        digest: &Digest,
+        trust_set: &TrustSet,
+    ) -> VerificationStatus {
+        self.package_digest_status(digest.as_slice(), trust_set, &mut HashSet::new())
+    }
+
+    /// Normalization lists recorded with the files of Code Review Proofs,
+    /// starting with the empty one
+    ///
+    /// A file has to be digested after each of them to find all its reviews.
+    pub fn code_review_normalizations(&self) -> impl Iterator<Item = &[Normalization]> {
+        std::iter::once(&[][..]).chain(
+            self.code_review_normalizations
+                .iter()
+                .map(Vec::as_slice)
+                .filter(|normalization| !normalization.is_empty()),
+        )
+    }
+
+    /// Most recent Code Review Proofs of each author including a file
+    /// with `digest`, digested after `normalization`
+    ///
+    /// A review counts only with the normalization list recorded with its
+    /// file: otherwise eg. content differing from the reviewed one in its
+    /// whitespace would pass for it once normalized.
+    ///
+    /// Reviews by ids not allowed to review code are left out; see `allows`.
+    pub fn get_code_reviews_by_file_digest<'a>(
+        &'a self,
+        digest: &'a [u8],
+        normalization: &'a [Normalization],
+    ) -> impl Iterator<Item = &'a review::Code> + 'a {
+        self.code_reviews_by_file_digest
+            .get(digest)
+            .into_iter()
+            .flat_map(|reviews| reviews.values().map(|review| &review.value))
+            .filter(move |review| {
+                review
+                    .files
+                    .iter()
+                    .any(|file| file.digest == digest && file.normalization == normalization)
+            })
+            .filter(move |review| self.allows(&review.from.id, Capability::CodeReview))
+    }
+
+    /// Like `verify_package_digest`, but for a single file, reviewed with
+    /// Code Review Proofs
+    ///
+    /// Trusted advisories flagging the file override the reviews; see
+    /// `advisory_status`.
+    pub fn verify_file_digest(
+        &self,
+        digest: &[u8],
+        normalization: &[Normalization],
+        trust_set: &TrustSet,
+    ) -> VerificationStatus {
+        let status = verification_status(
+            self.get_code_reviews_by_file_digest(digest, normalization).map(|review| {
+                (
+                    &review.from.id,
+                    &proof::review::Common::review(review).rating,
+                )
+            }),
+            trust_set,
+        );
+        advisory_status(self.get_advisories_for_file_digest(digest), trust_set).unwrap_or(status)
+    }
+
+    /// Most recent Build Attestation Proofs of each author and artifact,
+    /// built from the package sources with `digest`
+    ///
+    /// Attestations by ids not allowed to attest builds are left out.
+    pub fn get_build_attestations_by_package_digest<'a>(
+        &'a self,
+        digest: &Digest,
+    ) -> impl Iterator<Item = &'a proof::BuildAttestation> + 'a {
+        self.build_attestations_by_package_digest
+            .get(digest.as_slice())
+            .into_iter()
+            .flat_map(|attestations| attestations.values().map(|attestation| &attestation.value))
+            .filter(move |attestation| {
+                self.allows(&attestation.from.id, Capability::BuildAttestation)
+            })
+    }
+
+    /// Most recent Code Review Proofs of each author including an anchor
+    /// with `digest`, in any file
+    pub fn get_code_reviews_by_anchor_digest<'a>(
+        &'a self,
+        digest: &[u8],
+    ) -> impl Iterator<Item = &'a review::Code> + 'a {
+        self.code_reviews_by_anchor_digest
+            .get(digest)
+            .into_iter()
+            .flat_map(|reviews| reviews.values().map(|review| &review.value))
+            .filter(move |review| self.allows(&review.from.id, Capability::CodeReview))
+    }
+
+    /// Verify a file of the package `name` from `source` by its `anchors`,
+    /// instead of its digest
+    ///
+    /// All the anchors have to be reviewed, possibly while in other files
+    /// of the same package, so a file verifies at the lowest level of its
+    /// anchors. A single flagged or dangerous anchor makes the whole file so.
+    pub fn verify_anchors(
+        &self,
+        anchors: &[Anchor],
+        source: &str,
+        name: &str,
+        trust_set: &TrustSet,
+    ) -> VerificationStatus {
+        if anchors.is_empty() {
+            return VerificationStatus::Unknown;
         }
+        let statuses: Vec<_> = anchors
+            .iter()
+            .map(|anchor| {
+                verification_status(
+                    self.get_code_reviews_by_anchor_digest(&anchor.digest)
+                        .filter(|review| {
+                            review.package.source == source && review.package.name == name
+                        })
+                        .map(|review| {
+                            (
+                                &review.from.id,
+                                &proof::review::Common::review(review).rating,
+                            )
+                        }),
+                    trust_set,
+                )
+            })
+            .collect();
 
-        if dangerous_count > 0 {
+        if statuses.contains(&VerificationStatus::Dangerous) {
             VerificationStatus::Dangerous
-        } else if flagged_count > 0 {
+        } else if statuses.contains(&VerificationStatus::Flagged) {
             VerificationStatus::Flagged
-        } else if trust_count > 0 {
-            VerificationStatus::Verified(trust_level)
-        } else {
+        } else if statuses.contains(&VerificationStatus::Unknown) {
             VerificationStatus::Unknown
+        } else {
+            statuses
+                .into_iter()
+                .filter_map(|status| match status {
+                    VerificationStatus::Verified(level) => Some(level),
+                    _ => None,
+                })
+                .min()
+                .map_or(VerificationStatus::Unknown, VerificationStatus::Verified)
         }
     }
 
+    /// Record the URL `pub_id` claims for itself
+    ///
+    /// Only the `from` of a proof counts: anyone can pair any id with any
+    /// URL in the ids their proofs are about.
+    fn record_id_url_pair(&mut self, pub_id: &crev_data::PubId) {
+        let url = normalized_url(&pub_id.url.url);
+        self.urls_by_id
+            .entry(pub_id.id.clone())
+            .or_default()
+            .insert(url.clone());
+        self.ids_by_url
+            .entry(url)
+            .or_default()
+            .insert(pub_id.id.clone());
+    }
+
     fn record_url_from_to_field(&mut self, date: &DateTime<Utc>, to: &crev_data::PubId) {
         self.url_by_id_secondary
             .entry(to.id.clone())
@@ -348,32 +997,337 @@ impl ProofDB {
     }
 
     fn record_url_from_from_field(&mut self, date: &DateTime<Utc>, from: &crev_data::PubId) {
+        self.record_id_url_pair(from);
         TimestampedUrl {
             value: from.url.clone(),
             date: date.to_owned(),
         }
         .insert_into_or_update_to_more_recent(self.url_by_id.entry(from.id.clone()));
     }
-    fn add_proof(&mut self, proof: &proof::Proof) {
-        proof
-            .verify()
-            .expect("All proofs were supposed to be valid here");
+    /// Warn about proofs dated in the future or implausibly long ago, and
+    /// fail for future-dated ones if rejecting them
+    fn check_date(&self, proof: &proof::Proof) -> Result<()> {
+        let date = proof.content.date().with_timezone(&Utc);
+        let now = Utc::now();
+        if now + chrono::Duration::minutes(MAX_CLOCK_SKEW_MINUTES) < date {
+            if self.reject_future_dated {
+                bail!("dated in the future: {}", date.to_rfc3339());
+            }
+            eprintln!(
+                "Warning: proof {} by {} is dated in the future: {}",
+                crev_common::base64_encode(&proof.digest),
+                proof.content.author_id(),
+                date.to_rfc3339()
+            );
+        } else if date < earliest_plausible_date() {
+            eprintln!(
+                "Warning: proof {} by {} is implausibly old: {}",
+                crev_common::base64_encode(&proof.digest),
+                proof.content.author_id(),
+                date.to_rfc3339()
+            );
+        }
+        Ok(())
+    }
+
+    fn add_proof(&mut self, proof: &proof::Proof) -> Result<()> {
+        proof.verify()?;
+        self.check_date(proof)?;
+        let date = proof.content.date().with_timezone(&Utc);
+        let last = self
+            .last_proof_date_by_id
+            .entry(proof.content.author_id())
+            .or_insert(date);
+        *last = std::cmp::max(*last, date);
         match proof.content {
             Content::Code(ref review) => self.add_code_review(&review),
             Content::Package(ref review) => self.add_package_review(&review, &proof.signature),
             Content::Trust(ref trust) => self.add_trust(&trust),
+            Content::Project(ref project) => self.add_project(project),
+            Content::ProjectMigration(ref migration) => self.add_project_migration(migration),
+            Content::TrustRootHandover(ref handover) => self.add_trust_root_handover(handover),
+            Content::PathMove(ref path_move) => self.add_path_move(path_move),
+            Content::BuildAttestation(ref attestation) => self.add_build_attestation(attestation),
+            Content::ReviewRequest(ref request) => {
+                self.record_url_from_from_field(&request.date_utc(), &request.from)
+            }
+            Content::Revocation(ref revocation) => self.add_revocation(revocation),
+            Content::Advisory(ref advisory) => self.add_advisory(advisory, &proof.signature),
+            Content::IdLink(ref link) => self.add_id_link(link),
+        }
+        Ok(())
+    }
+
+    fn add_advisory(&mut self, advisory: &proof::Advisory, signature: &str) {
+        self.record_url_from_from_field(&advisory.date_utc(), &advisory.from);
+        self.advisory_signatures_by_project
+            .entry(advisory.project.clone())
+            .or_default()
+            .insert(signature.to_owned());
+        for file in &advisory.files {
+            self.advisory_signatures_by_file_digest
+                .entry(file.digest.clone())
+                .or_default()
+                .insert(signature.to_owned());
         }
+        self.advisory_by_signature
+            .insert(signature.to_owned(), advisory.clone());
     }
 
+    fn advisories_by_signatures<'a>(
+        &'a self,
+        signatures: Option<&'a HashSet<String>>,
+    ) -> impl Iterator<Item = &'a proof::Advisory> + 'a {
+        signatures
+            .into_iter()
+            .flat_map(move |signatures| {
+                signatures
+                    .iter()
+                    .map(move |signature| &self.advisory_by_signature[signature])
+            })
+            .filter(move |advisory| self.allows(&advisory.from.id, Capability::PackageReview))
+    }
+
+    /// Advisory Proofs for the project with `project_id`, whatever
+    /// revisions they affect
+    pub fn get_advisories_for_project<'a>(
+        &'a self,
+        project_id: &str,
+    ) -> impl Iterator<Item = &'a proof::Advisory> + 'a {
+        self.advisories_by_signatures(self.advisory_signatures_by_project.get(project_id))
+    }
+
+    /// All the Advisory Proofs, of any project
+    pub fn get_all_advisories(&self) -> impl Iterator<Item = &proof::Advisory> {
+        self.advisory_by_signature
+            .values()
+            .filter(move |advisory| self.allows(&advisory.from.id, Capability::PackageReview))
+    }
+
+    /// Advisory Proofs flagging a file with `digest`
+    pub fn get_advisories_for_file_digest<'a>(
+        &'a self,
+        digest: &[u8],
+    ) -> impl Iterator<Item = &'a proof::Advisory> + 'a {
+        self.advisories_by_signatures(self.advisory_signatures_by_file_digest.get(digest))
+    }
+
+    fn add_revocation(&mut self, revocation: &proof::Revocation) {
+        self.record_url_from_from_field(&revocation.date_utc(), &revocation.from);
+        for revoked in &revocation.proofs {
+            self.revoked_by
+                .entry(revoked.digest.clone())
+                .or_default()
+                .insert(revocation.from.id.clone());
+        }
+    }
+
+    /// Ignore all the proofs of `id` from now on, wherever they come from
+    ///
+    /// For ids revoked as compromised (see `id_directory`), whose key may
+    /// have been used to forge proofs published anywhere. Proofs of `id`
+    /// imported before stay.
+    pub fn import_revoked_id(&mut self, id: &Id) {
+        self.revoked_ids.insert(id.clone());
+    }
+
+    /// Was `id` revoked as compromised; see `import_revoked_id`
+    pub fn is_id_revoked(&self, id: &Id) -> bool {
+        self.revoked_ids.contains(id)
+    }
+
+    /// Was `proof` revoked by its author, or is its author revoked
+    ///
+    /// Revocations can't be revoked themselves, only along with their
+    /// author.
+    pub fn is_revoked(&self, proof: &proof::Proof) -> bool {
+        if self.is_id_revoked(&proof.content.author_id()) {
+            return true;
+        }
+        if let Content::Revocation(_) = proof.content {
+            return false;
+        }
+        self.revoked_by
+            .get(&proof.digest)
+            .map_or(false, |ids| ids.contains(&proof.content.author_id()))
+    }
+
+    fn add_superseded(&mut self, proof: &proof::Proof) {
+        for superseded in proof.content.supersedes() {
+            self.superseded_by
+                .entry(superseded.digest.clone())
+                .or_default()
+                .insert(proof.content.author_id());
+        }
+    }
+
+    /// Was `proof` superseded by a newer proof of its author; see
+    /// `Content::supersedes`
+    pub fn is_superseded(&self, proof: &proof::Proof) -> bool {
+        self.superseded_by
+            .get(&proof.digest)
+            .map_or(false, |ids| ids.contains(&proof.content.author_id()))
+    }
+
+    /// Date of the most recent proof of `id`, if any
+    pub fn last_proof_date(&self, id: &Id) -> Option<DateTime<Utc>> {
+        self.last_proof_date_by_id.get(id).cloned()
+    }
+
+    /// Provisionally trust the ids on a seed list; see `crev_data::seed_list`
+    ///
+    /// Adds `low` trust from `for_id` to each listed id it has no trust for
+    /// yet. Being dated at the epoch, it gets overridden by any trust proof.
+    /// It doesn't extend to the trust proofs of the seeds, so a seed list
+    /// can't pull in anyone it doesn't list.
+    pub fn import_seed_list(&mut self, for_id: &Id, list: &SignedSeedList) {
+        let list = &list.list;
+        self.import_seeds(for_id, &list.date.with_timezone(&Utc), &list.ids);
+    }
+
+    /// Apply an organization policy to the trust of `for_id`
+    ///
+    /// Its seeds are trusted like the ones of a seed list, and its banned
+    /// ids distrusted whatever proofs say; see `org_policy`.
+    pub fn import_org_policy(&mut self, for_id: &Id, policy: &SignedOrgPolicy) {
+        let policy = &policy.policy;
+        self.import_seeds(for_id, &policy.date.with_timezone(&Utc), &policy.seeds);
+        for banned in &policy.banned {
+            if banned.id != *for_id {
+                self.override_trust(for_id, &banned.id, TrustLevel::Distrust);
+            }
+        }
+    }
+
+    fn import_seeds(&mut self, for_id: &Id, date: &DateTime<Utc>, seeds: &[SeedEntry]) {
+        let provisional = TimestampedTrust {
+            value: TrustEdge {
+                trust: TrustLevel::Low,
+                dimensions: TrustDimensions {
+                    trust: Some(TrustLevel::None),
+                    ..default()
+                },
+                kind: TrustLinkKind::Seed,
+            },
+            date: Utc.timestamp(0, 0),
+        };
+        for entry in seeds {
+            if entry.id.id == *for_id {
+                continue;
+            }
+            self.trust_id_to_id
+                .entry(for_id.clone())
+                .or_default()
+                .entry(entry.id.id.clone())
+                .or_insert_with(|| provisional.clone());
+            self.record_url_from_to_field(date, &entry.id);
+        }
+    }
+
+    /// Set the trust of `for_id` in `id` to `level`, whatever proofs say,
+    /// unless `for_id` distrusts `id`
+    ///
+    /// Meant for trust that only applies in some context, like verifying a
+    /// single package (see `repo::TrustOverride`), so it's applied after
+    /// all the proofs are imported, replacing the trust they give. The
+    /// distrust of `for_id` itself is never lifted; returns whether the
+    /// trust was overridden.
+    pub fn override_trust(&mut self, for_id: &Id, id: &Id, level: TrustLevel) -> bool {
+        let distrusted = self
+            .trust_id_to_id
+            .get(for_id)
+            .and_then(|ids| ids.get(id))
+            .map_or(false, |trust| trust.value.trust == TrustLevel::Distrust);
+        if distrusted {
+            return false;
+        }
+        let edge = TrustEdge {
+            trust: level,
+            dimensions: default(),
+            kind: TrustLinkKind::Override,
+        };
+        self.trust_id_to_id
+            .entry(for_id.clone())
+            .or_default()
+            .insert(
+                id.clone(),
+                TimestampedTrust {
+                    value: edge,
+                    date: Utc::now(),
+                },
+            );
+        true
+    }
+
+    /// Learn the URL of an Id from its self-signed bundle
+    pub fn import_pub_id_bundle(&mut self, bundle: &SignedPubIdBundle) {
+        let bundle = &bundle.bundle;
+        let date = bundle.date.with_timezone(&Utc);
+        self.record_url_from_from_field(&date, &bundle.id);
+        Timestamped {
+            date,
+            value: bundle
+                .capabilities
+                .as_ref()
+                .map(|capabilities| capabilities.iter().cloned().collect()),
+        }
+        .insert_into_or_update_to_more_recent(
+            self.capabilities_by_id.entry(bundle.id.id.clone()),
+        );
+    }
+
+    /// Do proofs of `id` needing `capability` count
+    ///
+    /// Only ids with capabilities declared in their most recent imported
+    /// bundle are restricted; see `id_bundle::Capability`.
+    pub fn allows(&self, id: &Id, capability: Capability) -> bool {
+        match self
+            .capabilities_by_id
+            .get(id)
+            .and_then(|capabilities| capabilities.value.as_ref())
+        {
+            Some(capabilities) => capabilities.contains(&capability),
+            None => true,
+        }
+    }
+
+    /// Import proofs, skipping (and reporting) those that fail verification
+    ///
+    /// Revoked proofs are skipped too. The revocations are imported first,
+    /// so the order of `i` doesn't matter, but proofs imported before
+    /// (by an earlier call) stay even if revoked now. Expired proofs are
+    /// skipped as if they were never issued.
     pub fn import_from_iter(&mut self, i: impl Iterator<Item = proof::Proof>) {
-        for proof in i {
-            self.add_proof(&proof);
+        let now = Utc::now();
+        let (revocations, mut others): (Vec<_>, Vec<_>) =
+            i.partition(|proof| matches!(proof.content, Content::Revocation(_)));
+        // newest first, so a proof is known to be superseded before it's
+        // added; only newer proofs get to supersede older ones that way
+        others.sort_by_key(|proof| std::cmp::Reverse(*proof.content.date()));
+        for proof in revocations.iter().chain(&others) {
+            if self.is_revoked(proof) || proof.content.is_expired_at(&now) {
+                continue;
+            }
+            let result = if self.is_superseded(proof) {
+                // what it superseded in turn stays superseded
+                proof.verify()
+            } else {
+                self.add_proof(proof)
+            };
+            match result {
+                Ok(()) => self.add_superseded(proof),
+                Err(e) => eprintln!(
+                    "Ignoring invalid proof {}: {}",
+                    crev_common::base64_encode(&proof.digest),
+                    e
+                ),
+            }
         }
     }
 
-    fn get_trust_list_of_id(&self, id: &Id) -> impl Iterator<Item = (TrustLevel, &Id)> {
+    fn get_trust_list_of_id(&self, id: &Id) -> impl Iterator<Item = (&TrustEdge, &Id)> {
         if let Some(map) = self.trust_id_to_id.get(id) {
-            Some(map.iter().map(|(id, trust)| (trust.value, id)))
+            Some(map.iter().map(|(id, trust)| (&trust.value, id)))
         } else {
             None
         }
@@ -409,6 +1363,7 @@ impl ProofDB {
         struct Visit {
             distance: u64,
             id: Id,
+            depth: u64,
         }
 
         let mut pending = BTreeSet::new();
@@ -418,14 +1373,61 @@ impl ProofDB {
         pending.insert(Visit {
             distance: 0,
             id: for_id.clone(),
+            depth: 0,
         });
-        visited.record_trusted_id(for_id.clone(), for_id.clone(), 0, TrustLevel::High);
+        visited.record_trusted_id(
+            for_id.clone(),
+            for_id.clone(),
+            0,
+            0,
+            EffectiveTrust::all(TrustLevel::High),
+        );
 
         while let Some(current) = pending.iter().next().cloned() {
             pending.remove(&current);
+            if current.id != *for_id && !self.allows(&current.id, Capability::Trust) {
+                continue;
+            }
+            if params.follow_id_links {
+                // a linked id belongs to the same user, so it's as close,
+                // and as trusted as the link allows
+                let current_effective_trust = visited
+                    .get_effective_trust(&current.id)
+                    .expect("Id should have been inserted to `visited` beforehand");
+                for (linked_id, level) in self.linked_ids(&current.id) {
+                    if visited.distrusted.contains_key(&linked_id) {
+                        continue;
+                    }
+                    if visited.record_trusted_id(
+                        linked_id.clone(),
+                        current.id.clone(),
+                        current.distance,
+                        current.depth,
+                        current_effective_trust.capped_at(level),
+                    ) {
+                        pending.insert(Visit {
+                            distance: current.distance,
+                            id: linked_id,
+                            depth: current.depth,
+                        });
+                    }
+                }
+            }
+            // only the judgements of ids trusted for them extend the WoT
+            let current_trust = visited
+                .get_effective_trust(&current.id)
+                .expect("Id should have been inserted to `visited` beforehand")
+                .trust;
+            if current_trust < TrustLevel::Low {
+                continue;
+            }
+            match params.max_depth {
+                Some(max_depth) if current.depth >= max_depth => continue,
+                _ => {}
+            }
 
-            for (level, candidate_id) in self.get_trust_list_of_id(&&current.id) {
-                if level == TrustLevel::Distrust {
+            for (edge, candidate_id) in self.get_trust_list_of_id(&&current.id) {
+                if edge.trust == TrustLevel::Distrust {
                     visited
                         .distrusted
                         .entry(candidate_id.clone())
@@ -434,8 +1436,9 @@ impl ProofDB {
                     continue;
                 }
 
+                let levels = EffectiveTrust::of(edge);
                 let candidate_distance_from_current =
-                    if let Some(v) = params.distance_by_level(level) {
+                    if let Some(v) = params.distance_by_level(levels.max()) {
                         v
                     } else {
                         continue;
@@ -450,28 +1453,19 @@ impl ProofDB {
                     continue;
                 }
 
-                let candidate_effective_trust = std::cmp::min(
-                    level,
-                    visited
-                        .get_effective_trust_level(&current.id)
-                        .expect("Id should have been inserted to `visited` beforehand"),
-                );
-
-                if candidate_effective_trust < TrustLevel::None {
-                    // can this even happen?
-                    debug_assert!(false);
-                    continue;
-                }
+                let candidate_effective_trust = levels.capped_at(current_trust);
 
                 if visited.record_trusted_id(
                     candidate_id.clone(),
                     current.id.clone(),
                     candidate_total_distance,
+                    current.depth + 1,
                     candidate_effective_trust,
                 ) {
                     pending.insert(Visit {
                         distance: candidate_total_distance,
                         id: candidate_id.to_owned(),
+                        depth: current.depth + 1,
                     });
                 }
             }
@@ -480,29 +1474,345 @@ impl ProofDB {
         visited
     }
 
+    /// Ids claiming the same URL, and ids claiming different URLs, in their
+    /// own proofs
+    ///
+    /// Either can be someone impersonating another id, so they should be
+    /// looked into before trusting any of the ids involved.
+    pub fn id_conflicts(&self) -> Vec<IdConflict> {
+        let mut conflicts: Vec<_> = self
+            .ids_by_url
+            .iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(url, ids)| IdConflict::SharedUrl {
+                url: url.clone(),
+                ids: ids.iter().cloned().collect(),
+            })
+            .collect();
+        conflicts.extend(
+            self.urls_by_id
+                .iter()
+                .filter(|(_, urls)| urls.len() > 1)
+                .map(|(id, urls)| IdConflict::MultipleUrls {
+                    id: id.clone(),
+                    urls: urls.iter().cloned().collect(),
+                }),
+        );
+        conflicts
+    }
+
     pub fn lookup_url(&self, id: &Id) -> Option<&Url> {
         self.url_by_id
             .get(id)
             .or_else(|| self.url_by_id_secondary.get(id))
             .map(|url| &url.value)
     }
+
+    /// Chains of trust proofs through which `from` trusts `to`
+    ///
+    /// One chain per path of `TrustSet::trust_paths_to`, shortest first,
+    /// with the most recent trust between each two ids on the path, and
+    /// where it comes from. Empty if `to` isn't trusted, or is `from` itself.
+    pub fn explain_trust(
+        &self,
+        from: &Id,
+        to: &Id,
+        params: &TrustDistanceParams,
+    ) -> Vec<Vec<TrustLink>> {
+        let trust_set = self.calculate_trust_set(from, params);
+        trust_set
+            .trust_paths_to(to)
+            .iter()
+            .map(|path| {
+                path.windows(2)
+                    .map(|pair| self.trust_link(&trust_set, &pair[0], &pair[1]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn trust_link(&self, trust_set: &TrustSet, from: &Id, to: &Id) -> TrustLink {
+        let link = |level, date, kind| TrustLink {
+            from: from.clone(),
+            to: to.clone(),
+            level,
+            date,
+            kind,
+        };
+        if let Some(trust) = self.trust_id_to_id.get(from).and_then(|ids| ids.get(to)) {
+            let date = Some(trust.date).filter(|_| trust.value.kind == TrustLinkKind::Proof);
+            return link(trust.value.trust, date, trust.value.kind);
+        }
+        // the path may follow an id link instead
+        if let Some(level) = self.id_link_level(from, to) {
+            return link(level, Some(self.id_links[from][to].date), TrustLinkKind::IdLink);
+        }
+        let level = trust_set
+            .referer_trust_level(to, from)
+            .unwrap_or(TrustLevel::None);
+        link(level, None, TrustLinkKind::Unrecorded)
+    }
+}
+
+/// A step on the way to a trusted id; see `ProofDB::explain_trust`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrustLink {
+    pub from: Id,
+    pub to: Id,
+    pub level: TrustLevel,
+    /// Date of the proof the trust comes from, if it comes from one
+    pub date: Option<DateTime<Utc>>,
+    pub kind: TrustLinkKind,
+}
+
+/// Where the trust of a `TrustLink` comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustLinkKind {
+    /// A Trust Proof
+    Proof,
+    /// Id Link Proofs of the two ids to each other
+    IdLink,
+    /// A trust override of a package config; see `ProofDB::override_trust`
+    Override,
+    /// A seed list or an organization policy; see `ProofDB::import_seed_list`
+    Seed,
+    /// Nothing this db still has a record of
+    Unrecorded,
+}
+
+impl fmt::Display for TrustLinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TrustLinkKind::Proof => "trust proof",
+            TrustLinkKind::IdLink => "id link",
+            TrustLinkKind::Override => "trust override",
+            TrustLinkKind::Seed => "seed list",
+            TrustLinkKind::Unrecorded => "unrecorded",
+        })
+    }
+}
+
+impl fmt::Display for TrustLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}", self.level, self.kind)?;
+        if let Some(date) = self.date {
+            write!(f, " since {}", date.to_rfc3339())?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// `url` with the differences that don't make it another repository
+/// removed: case, and a trailing `/` or `.git`
+fn normalized_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let url = url.trim_end_matches('/');
+    url.trim_end_matches(".git").trim_end_matches('/').to_string()
+}
+
+/// Proofs disagreeing about who is who; see `ProofDB::id_conflicts`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdConflict {
+    /// Different ids claiming the same URL
+    SharedUrl { url: String, ids: Vec<Id> },
+    /// The same id under different URLs
+    MultipleUrls { id: Id, urls: Vec<String> },
+}
+
+impl fmt::Display for IdConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdConflict::SharedUrl { url, ids } => {
+                write!(f, "{} is claimed by {} ids:", url, ids.len())?;
+                for id in ids {
+                    write!(f, " {}", id)?;
+                }
+            }
+            IdConflict::MultipleUrls { id, urls } => {
+                write!(f, "{} appears under {} URLs:", id, urls.len())?;
+                for url in urls {
+                    write!(f, " {}", url)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of `ProofDB::get_project_identity`
+pub enum ProjectIdentity<'a> {
+    /// No Project Proof for the project id found
+    Unknown,
+    Unique(&'a proof::Project),
+    /// All the Project Proofs colliding with each other, oldest first
+    Colliding(Vec<&'a proof::Project>),
+}
+
+/// Effective trust in an Id, for each `TrustDimension`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct EffectiveTrust {
+    reviews: TrustLevel,
+    trust: TrustLevel,
+    advisories: TrustLevel,
+}
+
+impl EffectiveTrust {
+    fn all(level: TrustLevel) -> Self {
+        Self {
+            reviews: level,
+            trust: level,
+            advisories: level,
+        }
+    }
+
+    fn of(edge: &TrustEdge) -> Self {
+        Self {
+            reviews: edge.level_for(TrustDimension::Reviews),
+            trust: edge.level_for(TrustDimension::Trust),
+            advisories: edge.level_for(TrustDimension::Advisories),
+        }
+    }
+
+    fn get(&self, dimension: TrustDimension) -> TrustLevel {
+        match dimension {
+            TrustDimension::Reviews => self.reviews,
+            TrustDimension::Trust => self.trust,
+            TrustDimension::Advisories => self.advisories,
+        }
+    }
+
+    fn max(&self) -> TrustLevel {
+        std::cmp::max(self.reviews, std::cmp::max(self.trust, self.advisories))
+    }
+
+    fn capped_at(&self, level: TrustLevel) -> Self {
+        Self {
+            reviews: std::cmp::min(self.reviews, level),
+            trust: std::cmp::min(self.trust, level),
+            advisories: std::cmp::min(self.advisories, level),
+        }
+    }
+
+    /// Raise levels to the ones of `other`; returns `true` if any changed
+    fn raise_to(&mut self, other: EffectiveTrust) -> bool {
+        let raised = Self {
+            reviews: std::cmp::max(self.reviews, other.reviews),
+            trust: std::cmp::max(self.trust, other.trust),
+            advisories: std::cmp::max(self.advisories, other.advisories),
+        };
+        let changed = raised != *self;
+        *self = raised;
+        changed
+    }
 }
 
 /// Details of a one Id that is
+#[derive(Serialize, Deserialize)]
 struct TrustedIdDetails {
     distance: u64,
+    // fewest trust proofs from the root of the WoT
+    depth: u64,
     // effective, global trust from the root of the WoT
-    effective_trust: TrustLevel,
-    referers: HashMap<Id, TrustLevel>,
+    effective_trust: EffectiveTrust,
+    referers: HashMap<Id, EffectiveTrust>,
+}
+
+/// Status resulting from reviews with `ratings` by their authors
+///
+/// Only reviews of ids in `trust_set` count.
+fn verification_status<'a>(
+    ratings: impl Iterator<Item = (&'a Id, &'a Rating)>,
+    trust_set: &TrustSet,
+) -> VerificationStatus {
+    let mut trust_count = 0;
+    let mut trust_level = TrustLevel::None;
+    let mut flagged_count = 0;
+    let mut dangerous_count = 0;
+    for (reviewer, rating) in ratings {
+        let dimension = if Rating::Neutral <= *rating {
+            TrustDimension::Reviews
+        } else {
+            TrustDimension::Advisories
+        };
+        let reviewer_trust =
+            if let Some(level) = trust_set.get_effective_trust_level_for(reviewer, dimension) {
+                level
+            } else {
+                continue;
+            };
+        if Rating::Neutral <= *rating {
+            trust_count += 1;
+            trust_level = std::cmp::max(trust_level, reviewer_trust);
+        } else if *rating <= Rating::Dangerous {
+            dangerous_count += 1;
+        } else if *rating < Rating::Neutral {
+            flagged_count += 1;
+        }
+    }
+
+    if dangerous_count > 0 {
+        VerificationStatus::Dangerous
+    } else if flagged_count > 0 {
+        VerificationStatus::Flagged
+    } else if trust_count > 0 {
+        VerificationStatus::Verified(trust_level)
+    } else {
+        VerificationStatus::Unknown
+    }
 }
 
-#[derive(Default)]
+/// Status of something covered by `advisories`, if any of them is trusted
+///
+/// `Dangerous` for high and critical severity, and `Flagged` otherwise.
+/// Advisories count with the trust in the `advisories` dimension.
+pub fn advisory_status<'a>(
+    advisories: impl Iterator<Item = &'a proof::Advisory>,
+    trust_set: &TrustSet,
+) -> Option<VerificationStatus> {
+    advisories
+        .filter(|advisory| {
+            trust_set
+                .get_effective_trust_level_for(&advisory.from.id, TrustDimension::Advisories)
+                .is_some()
+        })
+        .map(|advisory| advisory.severity)
+        .max()
+        .map(|severity| {
+            if severity >= Severity::High {
+                VerificationStatus::Dangerous
+            } else {
+                VerificationStatus::Flagged
+            }
+        })
+}
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct TrustSet {
     trusted: HashMap<Id, TrustedIdDetails>,
     distrusted: HashMap<Id, HashSet<Id>>,
 }
 
 impl TrustSet {
+    /// Load the set cached at `path`, if it was stored with `key`
+    ///
+    /// A cache that can't be read is only a reason to compute it again.
+    pub fn load_cached(path: &Path, key: &[u8]) -> Option<TrustSet> {
+        let file = fs::File::open(path).ok()?;
+        let (cached_key, trust_set): (Vec<u8>, TrustSet) = serde_cbor::from_reader(&file).ok()?;
+        Some(trust_set).filter(|_| cached_key == key)
+    }
+
+    /// Cache the set at `path`, to be loaded again with the same `key`
+    pub fn store_cached(&self, path: &Path, key: &[u8]) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        serde_cbor::to_writer(&mut file, &(key, self))?;
+        file.flush()?;
+        drop(file);
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
     pub fn trusted_ids(&self) -> impl Iterator<Item = &Id> {
         self.trusted.keys()
     }
@@ -511,12 +1821,15 @@ impl TrustSet {
     ///
     /// Returns `true` if this actually added or changed the `subject` details,
     /// which requires revising it's own downstream trusted Id details in the graph algorithm for it.
+    /// A smaller `depth` is a change too, as it can let trust propagate
+    /// further under `TrustDistanceParams::max_depth`.
     fn record_trusted_id(
         &mut self,
         subject: Id,
         referer: Id,
         distance: u64,
-        effective_trust: TrustLevel,
+        depth: u64,
+        effective_trust: EffectiveTrust,
     ) -> bool {
         // TODO: turn into log or something
         // eprintln!(
@@ -531,6 +1844,7 @@ impl TrustSet {
                 referers.insert(referer, effective_trust);
                 entry.insert(TrustedIdDetails {
                     distance,
+                    depth,
                     effective_trust,
                     referers,
                 });
@@ -543,8 +1857,11 @@ impl TrustSet {
                     details.distance = distance;
                     changed = true;
                 }
-                if details.effective_trust < effective_trust {
-                    details.effective_trust = effective_trust;
+                if details.depth > depth {
+                    details.depth = depth;
+                    changed = true;
+                }
+                if details.effective_trust.raise_to(effective_trust) {
                     changed = true;
                 }
                 match details.referers.entry(referer.clone()) {
@@ -553,9 +1870,7 @@ impl TrustSet {
                         changed = true;
                     }
                     Entry::Occupied(mut entry) => {
-                        let level = entry.get_mut();
-                        if *level < effective_trust {
-                            *level = effective_trust;
+                        if entry.get_mut().raise_to(effective_trust) {
                             changed = true;
                         }
                     }
@@ -565,16 +1880,166 @@ impl TrustSet {
         }
     }
 
-    pub fn get_effective_trust_level(&self, id: &Id) -> Option<TrustLevel> {
+    fn get_effective_trust(&self, id: &Id) -> Option<EffectiveTrust> {
         self.trusted.get(id).map(|details| details.effective_trust)
     }
+
+    /// Effective trust in the reviews of `id`, if any
+    pub fn get_effective_trust_level(&self, id: &Id) -> Option<TrustLevel> {
+        self.get_effective_trust_level_for(id, TrustDimension::Reviews)
+    }
+
+    /// Effective trust in the proofs of `id` of the given `dimension`, if any
+    pub fn get_effective_trust_level_for(
+        &self,
+        id: &Id,
+        dimension: TrustDimension,
+    ) -> Option<TrustLevel> {
+        let level = self.get_effective_trust(id)?.get(dimension);
+        if level < TrustLevel::Low {
+            None
+        } else {
+            Some(level)
+        }
+    }
+
+    /// Trust `referer` gives to `id`, if it is one of its referers
+    fn referer_trust_level(&self, id: &Id, referer: &Id) -> Option<TrustLevel> {
+        Some(self.trusted.get(id)?.referers.get(referer)?.max())
+    }
+
+    /// Paths through the WoT by which `id` is trusted
+    ///
+    /// One path per direct referer of `id`, each starting at the root of the
+    /// WoT and following the shortest way to that referer. Empty if `id` is
+    /// not trusted, or is the root itself.
+    pub fn trust_paths_to(&self, id: &Id) -> Vec<Vec<Id>> {
+        let details = if let Some(details) = self.trusted.get(id) {
+            details
+        } else {
+            return vec![];
+        };
+
+        let mut paths: Vec<Vec<Id>> = details
+            .referers
+            .keys()
+            .filter(|referer| *referer != id)
+            .filter_map(|referer| {
+                let mut path = self.shortest_path_to(referer)?;
+                path.push(id.clone());
+                Some(path)
+            })
+            .collect();
+        paths.sort_by_key(|path| path.len());
+        paths
+    }
+
+    fn shortest_path_to(&self, id: &Id) -> Option<Vec<Id>> {
+        let mut path = vec![id.clone()];
+        let mut current = id;
+        loop {
+            let details = self.trusted.get(current)?;
+            // the root of the WoT is recorded as its own referer
+            if details.referers.contains_key(current) {
+                break;
+            }
+            let next = details
+                .referers
+                .keys()
+                .filter(|referer| !path.contains(referer))
+                .min_by_key(|referer| {
+                    self.trusted
+                        .get(referer)
+                        .map(|details| details.distance)
+                        .unwrap_or(u64::MAX)
+                })?;
+            path.push(next.clone());
+            current = next;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Render the WoT as a Graphviz DOT graph
+    ///
+    /// Trusted ids are labeled with their URLs (as known to `db`) and their
+    /// effective trust, and pointed at by everyone they are trusted by, with
+    /// the trust given. Distrust is drawn as dashed red edges.
+    pub fn to_dot(&self, db: &ProofDB) -> String {
+        fn quoted(s: &str) -> String {
+            let escaped = s
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("\"{}\"", escaped)
+        }
+        let label = |id: &Id| match db.lookup_url(id) {
+            Some(url) => url.url.clone(),
+            None => id.to_string(),
+        };
+
+        let trusted: BTreeMap<_, _> = self.trusted.iter().collect();
+        let mut nodes: BTreeSet<&Id> = trusted.keys().cloned().collect();
+        nodes.extend(self.distrusted.keys());
+
+        let mut dot = String::from("digraph trust {\n");
+        for id in &nodes {
+            let node_label = match trusted.get(id) {
+                Some(details) => format!("{}\n{}", label(id), details.effective_trust.max()),
+                None => format!("{}\n{}", label(id), TrustLevel::Distrust),
+            };
+            dot.push_str(&format!(
+                "    {} [label={}];\n",
+                quoted(&id.to_string()),
+                quoted(&node_label)
+            ));
+        }
+        for (id, details) in &trusted {
+            let referers: BTreeMap<_, _> = details.referers.iter().collect();
+            for (referer, trust) in referers {
+                if referer == *id {
+                    continue;
+                }
+                dot.push_str(&format!(
+                    "    {} -> {} [label={}];\n",
+                    quoted(&referer.to_string()),
+                    quoted(&id.to_string()),
+                    quoted(&trust.max().to_string())
+                ));
+            }
+        }
+        let distrusted: BTreeMap<_, _> = self.distrusted.iter().collect();
+        for (id, by) in distrusted {
+            let by: BTreeSet<_> = by.iter().collect();
+            for referer in by {
+                dot.push_str(&format!(
+                    "    {} -> {} [label={}, style=dashed, color=red];\n",
+                    quoted(&referer.to_string()),
+                    quoted(&id.to_string()),
+                    quoted(&TrustLevel::Distrust.to_string())
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
 
+/// How far trust propagates through the WoT
+///
+/// Every trust edge costs a distance depending on its level; ids further
+/// than `max_distance` from the root aren't trusted, and neither are ids
+/// more than `max_depth` edges away, if set.
+#[derive(Debug, Clone, Serialize)]
 pub struct TrustDistanceParams {
     pub max_distance: u64,
+    pub max_depth: Option<u64>,
     pub high_trust_distance: u64,
     pub medium_trust_distance: u64,
     pub low_trust_distance: u64,
+    /// Extend trust in an id to the ids linked to it both ways, see
+    /// `ProofDB::id_link_level`
+    pub follow_id_links: bool,
 }
 
 impl TrustDistanceParams {
@@ -594,9 +2059,11 @@ impl Default for TrustDistanceParams {
     fn default() -> Self {
         Self {
             max_distance: 10,
+            max_depth: None,
             high_trust_distance: 0,
             medium_trust_distance: 1,
             low_trust_distance: 5,
+            follow_id_links: false,
         }
     }
 }
@@ -0,0 +1,127 @@
+//! `crev quarantine` - suspicious fetched data, kept as evidence
+//!
+//! Fetched proofs that can't be parsed (eg. going over the limits), that
+//! fail their signature check, or whose authors the `ids.yaml` of their
+//! repository doesn't list are ignored either way. Copies of them are
+//! kept here, with where they came from and why they were rejected, so
+//! an attack (or a bug) can be looked into later.
+//!
+//! Each entry is stored as `<id>.crev` with the data, and `<id>.yaml` with
+//! the `Entry`, where the id is the digest of the data. Quarantining the
+//! same data again keeps the first entry.
+use crate::prelude::*;
+use crate::util;
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use serde_yaml;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Why some data was quarantined
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    /// URL of the proof repository it was fetched from
+    pub url: String,
+    /// File it was in, relative to the repository
+    pub path: PathBuf,
+    pub reason: String,
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+}
+
+/// The quarantine directory of a `Local`
+pub struct Quarantine {
+    dir: PathBuf,
+}
+
+impl Quarantine {
+    pub fn new(dir: &Path) -> Self {
+        Quarantine {
+            dir: dir.to_owned(),
+        }
+    }
+
+    /// Keep `data` along with `entry`, returning its id
+    ///
+    /// Returns `None` if the same data is quarantined already.
+    pub fn add(&self, entry: &Entry, data: &str) -> Result<Option<String>> {
+        let id = crev_common::base64_encode(&crev_common::blake2b256sum(data.as_bytes()));
+        let entry_path = self.dir.join(&id).with_extension("yaml");
+        if entry_path.exists() {
+            return Ok(None);
+        }
+        fs::create_dir_all(&self.dir)?;
+        util::store_str_to_file(&self.dir.join(&id).with_extension("crev"), data)?;
+        util::store_str_to_file(&entry_path, &serde_yaml::to_string(entry)?)?;
+        Ok(Some(id))
+    }
+
+    /// All the entries, by id, oldest first
+    pub fn list(&self) -> Result<Vec<(String, Entry)>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut entries = vec![];
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension() != Some("yaml".as_ref()) {
+                continue;
+            }
+            let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(id) => id.to_owned(),
+                None => continue,
+            };
+            let entry: Entry = serde_yaml::from_str(&util::read_file_to_string(&path)?)?;
+            entries.push((id, entry));
+        }
+        entries.sort_by(|a, b| a.1.date.cmp(&b.1.date).then(a.0.cmp(&b.0)));
+        Ok(entries)
+    }
+
+    /// Id of the entry with `id`, or an unambiguous prefix of it
+    fn find(&self, id: &str) -> Result<String> {
+        let mut found: Vec<_> = self
+            .list()?
+            .into_iter()
+            .map(|(entry_id, _)| entry_id)
+            .filter(|entry_id| entry_id.starts_with(id))
+            .collect();
+        match found.len() {
+            0 => bail!("No quarantined data with id {}", id),
+            1 => Ok(found.remove(0)),
+            n => bail!("Id {} is ambiguous: {} quarantined entries match", id, n),
+        }
+    }
+
+    /// The entry with `id` (or a prefix of it), and its data
+    pub fn inspect(&self, id: &str) -> Result<(String, Entry, String)> {
+        let id = self.find(id)?;
+        let entry: Entry = serde_yaml::from_str(&util::read_file_to_string(
+            &self.dir.join(&id).with_extension("yaml"),
+        )?)?;
+        let data = util::read_file_to_string(&self.dir.join(&id).with_extension("crev"))?;
+        Ok((id, entry, data))
+    }
+
+    /// Remove the entry with `id` (or a prefix of it), or all of them,
+    /// returning how many were removed
+    pub fn purge(&self, id: Option<&str>) -> Result<usize> {
+        let ids = match id {
+            Some(id) => vec![self.find(id)?],
+            None => self.list()?.into_iter().map(|(id, _)| id).collect(),
+        };
+        for id in &ids {
+            fs::remove_file(self.dir.join(id).with_extension("crev"))?;
+            fs::remove_file(self.dir.join(id).with_extension("yaml"))?;
+        }
+        Ok(ids.len())
+    }
+}
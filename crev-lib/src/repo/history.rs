@@ -0,0 +1,243 @@
+//! Summaries of git history, to pre-fill comments of Code Review Proofs
+use crate::prelude::*;
+use chrono::{self, TimeZone};
+use git2;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Don't list more commits than that in a summary
+const MAX_SUMMARY_COMMITS: usize = 50;
+
+/// Files worth pointing out even if they were only modified
+fn is_notable_file(path: &Path) -> bool {
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name == "Cargo.toml" || name == "build.rs" || name == "package.json",
+        None => false,
+    }
+}
+
+fn short_id(oid: git2::Oid) -> String {
+    oid.to_string()[..7].to_owned()
+}
+
+/// Paths of the files changed in `diff`, relative to `scope_prefix`,
+/// leaving out files outside of it
+pub(crate) fn scope_deltas<'a>(
+    diff: &'a git2::Diff<'_>,
+    scope_prefix: &'a Path,
+) -> impl Iterator<Item = (git2::Delta, PathBuf)> + 'a {
+    diff.deltas().filter_map(move |delta| {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())?;
+        let rel_path = path.strip_prefix(scope_prefix).ok()?;
+        Some((delta.status(), rel_path.to_owned()))
+    })
+}
+
+/// A commit changing files of a scope; see `scope_commits`
+pub(crate) struct ScopeCommit {
+    pub email: Option<String>,
+    pub date: chrono::DateTime<chrono::Utc>,
+    /// Relative to the root of the scope
+    pub paths: Vec<PathBuf>,
+}
+
+/// The commits up to `head` changing files under `scope_prefix`, newest
+/// first
+///
+/// Merge commits are left out: the changes they bring in are by the
+/// authors of the merged commits.
+pub(crate) fn scope_commits(
+    git_repo: &git2::Repository,
+    scope_prefix: &Path,
+    head: git2::Oid,
+) -> Result<Vec<ScopeCommit>> {
+    let mut revwalk = git_repo.revwalk()?;
+    revwalk.push(head)?;
+
+    let mut commits = vec![];
+    for oid in revwalk {
+        let commit = git_repo.find_commit(oid?)?;
+        if commit.parents().len() > 1 {
+            continue;
+        }
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = git_repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let paths: Vec<_> = scope_deltas(&diff, scope_prefix)
+            .map(|(_status, path)| path)
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+        commits.push(ScopeCommit {
+            email: commit.author().email().map(ToOwned::to_owned),
+            date: chrono::Utc.timestamp(commit.time().seconds(), 0),
+            paths,
+        });
+    }
+    Ok(commits)
+}
+
+/// Emails of the authors of the commits up to `head` changing each of
+/// the files under `scope_prefix`, by path relative to it
+///
+/// As in `scope_commits`, merge commits are left out.
+pub(crate) fn authors_by_file(
+    git_repo: &git2::Repository,
+    scope_prefix: &Path,
+    head: git2::Oid,
+) -> Result<HashMap<PathBuf, BTreeSet<String>>> {
+    let mut authors: HashMap<PathBuf, BTreeSet<String>> = HashMap::new();
+    for commit in scope_commits(git_repo, scope_prefix, head)? {
+        if let Some(email) = commit.email {
+            for path in commit.paths {
+                authors.entry(path).or_default().insert(email.clone());
+            }
+        }
+    }
+    Ok(authors)
+}
+
+/// Summary of commits from `since` (exclusive) to `head`, affecting files
+/// under `scope_prefix` (relative to the root of `git_repo`)
+///
+/// Lists the commits, their authors and notable changes: files added,
+/// removed or renamed and modified manifests and build scripts.
+pub(crate) fn summarize(
+    git_repo: &git2::Repository,
+    scope_prefix: &Path,
+    head: git2::Oid,
+    since: Option<git2::Oid>,
+) -> Result<String> {
+    let mut revwalk = git_repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME);
+    revwalk.push(head)?;
+    if let Some(since) = since {
+        revwalk.hide(since)?;
+    }
+
+    let mut commits = vec![];
+    let mut authors: BTreeMap<String, usize> = BTreeMap::new();
+    for oid in revwalk {
+        let commit = git_repo.find_commit(oid?)?;
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        // `git2::DiffOptions` can't be used, as its zero-initialization
+        // is invalid; scope is checked on the deltas instead
+        let diff = git_repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        if scope_deltas(&diff, scope_prefix).next().is_none() {
+            continue;
+        }
+        *authors
+            .entry(commit.author().name().unwrap_or("unknown").to_owned())
+            .or_insert(0) += 1;
+        commits.push(format!(
+            "{} {}",
+            short_id(commit.id()),
+            commit.summary().unwrap_or("")
+        ));
+    }
+
+    let since_tree = match since {
+        Some(since) => Some(git_repo.find_commit(since)?.tree()?),
+        None => None,
+    };
+    let diff = git_repo.diff_tree_to_tree(
+        since_tree.as_ref(),
+        Some(&git_repo.find_commit(head)?.tree()?),
+        None,
+    )?;
+    let mut notable = vec![];
+    for (status, path) in scope_deltas(&diff, scope_prefix) {
+        let change = match status {
+            git2::Delta::Added => "added",
+            git2::Delta::Deleted => "removed",
+            git2::Delta::Renamed => "renamed",
+            git2::Delta::Modified if is_notable_file(&path) => "modified",
+            _ => continue,
+        };
+        notable.push(format!("{} {}", change, path.display()));
+    }
+
+    let mut out = String::new();
+    let authors: Vec<_> = authors
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect();
+    match since {
+        Some(since) => write!(out, "Changes since {}", short_id(since))?,
+        None => write!(out, "Changes up to {}", short_id(head))?,
+    }
+    writeln!(
+        out,
+        ": {} commits by {}",
+        commits.len(),
+        if authors.is_empty() {
+            "nobody".to_owned()
+        } else {
+            authors.join(", ")
+        }
+    )?;
+    writeln!(out)?;
+    for commit in commits.iter().take(MAX_SUMMARY_COMMITS) {
+        writeln!(out, "* {}", commit)?;
+    }
+    if commits.len() > MAX_SUMMARY_COMMITS {
+        writeln!(
+            out,
+            "* ... and {} more",
+            commits.len() - MAX_SUMMARY_COMMITS
+        )?;
+    }
+    if !notable.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Notable changes:")?;
+        for change in &notable {
+            writeln!(out, "* {}", change)?;
+        }
+    }
+    Ok(out)
+}
+
+/// Minimum similarity (in percent) of a file renamed since a revision
+///
+/// High, so that only near-identical files are reported renamed.
+const RENAME_THRESHOLD: u16 = 90;
+
+/// Files renamed since `base`, in the working directory and index, by
+/// their current path, with their path at `base`
+///
+/// Paths are relative to `scope_prefix`; renames of files from outside of
+/// the scope are left out.
+pub(crate) fn renames_since(
+    git_repo: &git2::Repository,
+    scope_prefix: &Path,
+    base: git2::Oid,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let base_tree = git_repo.find_commit(base)?.tree()?;
+    let mut diff = git_repo.diff_tree_to_workdir_with_index(Some(&base_tree), None)?;
+    diff.find_similar(Some(
+        git2::DiffFindOptions::new()
+            .renames(true)
+            .rename_threshold(RENAME_THRESHOLD),
+    ))?;
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .filter_map(|delta| {
+            let old = delta.old_file().path()?.strip_prefix(scope_prefix).ok()?;
+            let new = delta.new_file().path()?.strip_prefix(scope_prefix).ok()?;
+            Some((new.to_owned(), old.to_owned()))
+        })
+        .collect())
+}
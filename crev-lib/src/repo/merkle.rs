@@ -0,0 +1,297 @@
+//! Merkle tree of the digests of the files of a scope, cached between runs
+//!
+//! Each directory node has a digest of its entries, and remembers the git
+//! tree it was computed from. While a directory matches the same git tree
+//! in HEAD and git reports no changes to its files, its whole subtree is
+//! reused without reading any of the files again.
+//!
+//! The cache is kept in the cache directory of the user (see
+//! `Local::scope_cache_path`), never in `.crev`: a cache committed to the
+//! repository could map any git blob to the digest of reviewed content.
+use common_failures::prelude::*;
+use crev_common;
+use git2;
+use serde_cbor;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const MERKLE_FILE_NAME: &str = "merkle";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+struct FileNode {
+    digest: Vec<u8>,
+    /// Blob in HEAD the digest is of, if the file was unchanged
+    git_blob: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+struct DirNode {
+    digest: Vec<u8>,
+    /// Tree in HEAD the node is of, if no file under it was changed
+    git_tree: Option<String>,
+    files: BTreeMap<String, FileNode>,
+    dirs: BTreeMap<String, DirNode>,
+}
+
+impl DirNode {
+    fn compute_digest(&mut self) {
+        let mut hashed = vec![];
+        for (name, file) in &self.files {
+            hashed.extend_from_slice(b"f");
+            hashed.extend_from_slice(name.as_bytes());
+            hashed.push(0);
+            hashed.extend_from_slice(&file.digest);
+        }
+        for (name, dir) in &self.dirs {
+            hashed.extend_from_slice(b"d");
+            hashed.extend_from_slice(name.as_bytes());
+            hashed.push(0);
+            hashed.extend_from_slice(&dir.digest);
+        }
+        self.digest = crev_common::blake2b256sum(&hashed);
+    }
+
+    fn file_count(&self) -> usize {
+        self.files.len() + self.dirs.values().map(DirNode::file_count).sum::<usize>()
+    }
+
+    fn find(&self, path: &Path) -> Option<&DirNode> {
+        path.iter()
+            .try_fold(self, |dir, name| dir.dirs.get(name.to_str()?))
+    }
+}
+
+/// Files tracked under a directory, with whether git reports them changed
+#[derive(Default)]
+struct Listing {
+    files: BTreeMap<String, bool>,
+    dirs: BTreeMap<String, Listing>,
+}
+
+impl Listing {
+    fn insert(&mut self, path: &Path, changed: bool) {
+        let mut names: Vec<_> = path
+            .iter()
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        let file_name = match names.pop() {
+            Some(name) => name,
+            None => return,
+        };
+        let dir = names
+            .into_iter()
+            .fold(self, |dir, name| dir.dirs.entry(name).or_default());
+        dir.files.insert(file_name, changed);
+    }
+
+    fn any_changed(&self) -> bool {
+        self.files.values().any(|changed| *changed) || self.dirs.values().any(Listing::any_changed)
+    }
+
+    fn file_count(&self) -> usize {
+        self.files.len() + self.dirs.values().map(Listing::file_count).sum::<usize>()
+    }
+}
+
+/// How much `MerkleTree::update` had to redo
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    /// Files read and hashed
+    pub hashed: usize,
+    /// Files whose digests were reused
+    pub reused: usize,
+}
+
+/// Merkle tree of the files of a scope tracked by git; see the module docs
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MerkleTree {
+    root: DirNode,
+}
+
+impl MerkleTree {
+    fn file_path(cache_path: &Path) -> PathBuf {
+        cache_path.join(MERKLE_FILE_NAME)
+    }
+
+    /// Load the tree cached in `cache_path`, if any
+    ///
+    /// A cache that can't be read is only a reason to compute it again.
+    pub fn load(cache_path: &Path) -> MerkleTree {
+        fs::File::open(Self::file_path(cache_path))
+            .ok()
+            .and_then(|file| serde_cbor::from_reader(&file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, cache_path: &Path) -> Result<()> {
+        let path = Self::file_path(cache_path);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        serde_cbor::to_writer(&mut file, self)?;
+        file.flush()?;
+        drop(file);
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Bring the tree up to date with the files of the scope at
+    /// `repo_path`, with their git `statuses` (see `git_scope_statuses`)
+    pub fn update(
+        &mut self,
+        repo_path: &Path,
+        statuses: &[(PathBuf, git2::Status)],
+    ) -> Result<UpdateStats> {
+        let git_repo = git2::Repository::discover(repo_path)?;
+        let workdir = git_repo
+            .workdir()
+            .ok_or_else(|| format_err!("Git repository has no working directory"))?
+            .canonicalize()?;
+        let scope_prefix = repo_path.canonicalize()?.strip_prefix(&workdir)?.to_owned();
+        let head_tree = match git_repo.head().ok().and_then(|head| head.target()) {
+            Some(head) => Some(git_repo.find_commit(head)?.tree()?),
+            None => None,
+        };
+
+        let mut listing = Listing::default();
+        for (path, status) in statuses {
+            listing.insert(path, *status != git2::Status::CURRENT);
+        }
+
+        let mut updater = Updater {
+            head_tree: head_tree.as_ref(),
+            repo_path,
+            scope_prefix: &scope_prefix,
+            stats: UpdateStats::default(),
+        };
+        let previous = std::mem::take(&mut self.root);
+        self.root = updater.update_dir(Path::new(""), &listing, Some(&previous))?;
+        Ok(updater.stats)
+    }
+
+    /// Digest of the whole tree
+    pub fn digest(&self) -> &[u8] {
+        &self.root.digest
+    }
+
+    /// Digest of the directory at `path`, relative to the scope
+    pub fn dir_digest(&self, path: &Path) -> Option<&[u8]> {
+        self.root.find(path).map(|dir| dir.digest.as_slice())
+    }
+
+    /// Digest of the file at `path`, relative to the scope
+    pub fn file_digest(&self, path: &Path) -> Option<&[u8]> {
+        let dir = self.root.find(path.parent()?)?;
+        dir.files
+            .get(path.file_name()?.to_str()?)
+            .map(|file| file.digest.as_slice())
+    }
+
+    /// Digests of all the files, by their path relative to the scope
+    pub fn file_digests(&self) -> HashMap<PathBuf, Vec<u8>> {
+        fn collect(dir: &DirNode, prefix: &Path, out: &mut HashMap<PathBuf, Vec<u8>>) {
+            for (name, file) in &dir.files {
+                out.insert(prefix.join(name), file.digest.clone());
+            }
+            for (name, subdir) in &dir.dirs {
+                collect(subdir, &prefix.join(name), out);
+            }
+        }
+        let mut out = HashMap::new();
+        collect(&self.root, Path::new(""), &mut out);
+        out
+    }
+}
+
+struct Updater<'a> {
+    head_tree: Option<&'a git2::Tree<'a>>,
+    repo_path: &'a Path,
+    scope_prefix: &'a Path,
+    stats: UpdateStats,
+}
+
+impl<'a> Updater<'a> {
+    /// Id of the object at `rel_path` in HEAD, if there's one
+    fn head_id(&self, rel_path: &Path) -> Option<String> {
+        let head_tree = self.head_tree?;
+        let path = self.scope_prefix.join(rel_path);
+        if path.as_os_str().is_empty() {
+            return Some(head_tree.id().to_string());
+        }
+        head_tree
+            .get_path(&path)
+            .ok()
+            .map(|entry| entry.id().to_string())
+    }
+
+    fn update_dir(
+        &mut self,
+        rel_path: &Path,
+        listing: &Listing,
+        previous: Option<&DirNode>,
+    ) -> Result<DirNode> {
+        let changed = listing.any_changed();
+        let git_tree = if changed {
+            None
+        } else {
+            self.head_id(rel_path)
+        };
+        if let Some(previous) = previous {
+            // the same tree in HEAD, and the same files tracked under it
+            if git_tree.is_some()
+                && previous.git_tree == git_tree
+                && previous.file_count() == listing.file_count()
+            {
+                self.stats.reused += listing.file_count();
+                return Ok(previous.clone());
+            }
+        }
+
+        let mut node = DirNode {
+            git_tree,
+            ..DirNode::default()
+        };
+        for (name, file_changed) in &listing.files {
+            let file_path = rel_path.join(name);
+            let git_blob = if *file_changed {
+                None
+            } else {
+                self.head_id(&file_path)
+            };
+            let reusable = previous
+                .and_then(|previous| previous.files.get(name))
+                .filter(|file| git_blob.is_some() && file.git_blob == git_blob);
+            let file = match reusable {
+                Some(file) => {
+                    self.stats.reused += 1;
+                    file.clone()
+                }
+                None => {
+                    let full_path = self.repo_path.join(&file_path);
+                    if !full_path.is_file() {
+                        continue;
+                    }
+                    self.stats.hashed += 1;
+                    FileNode {
+                        digest: crev_common::blake2b256sum_file(&full_path)?,
+                        git_blob,
+                    }
+                }
+            };
+            node.files.insert(name.clone(), file);
+        }
+        for (name, sublisting) in &listing.dirs {
+            let subdir = self.update_dir(
+                &rel_path.join(name),
+                sublisting,
+                previous.and_then(|previous| previous.dirs.get(name)),
+            )?;
+            node.dirs.insert(name.clone(), subdir);
+        }
+        node.compute_digest();
+        Ok(node)
+    }
+}
@@ -1,47 +1,432 @@
 use crate::prelude::*;
-use crate::ProofStore;
 use crate::{local::Local, util};
 use crate::id::PassphraseFn;
+use chrono::prelude::*;
 use crev_data::proof;
-use crev_data::Digest;
+use crev_data::proof::trust::{TrustDimension, TrustLevel};
+use crev_data::{Digest, Id, OwnId};
 use git2;
 use serde_yaml;
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     io::Write,
     path::{Path, PathBuf},
 };
 
+mod history;
+pub mod merkle;
+pub mod simulate;
 pub mod staging;
+pub mod vcs;
+pub(crate) mod verify_cache;
+pub mod watch;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PackageConfig {
     pub version: u64,
     #[serde(rename = "trust-root")]
     pub trust_root: String,
+    /// Set by `crev package identify`; see `proof::Project`
+    #[serde(
+        rename = "project-id",
+        skip_serializing_if = "Option::is_none",
+        default = "Default::default"
+    )]
+    pub project_id: Option<String>,
+    /// See `policy::PathRequirement`
+    #[serde(
+        rename = "path-requirements",
+        skip_serializing_if = "Vec::is_empty",
+        default = "Default::default"
+    )]
+    pub path_requirements: Vec<crate::policy::PathRequirement>,
+    /// See `policy::ReviewerGroups`; they count only once the trust root
+    /// reviewed the config, see `Repo::is_config_authorized`
+    #[serde(
+        rename = "reviewer-groups",
+        skip_serializing_if = "std::collections::BTreeMap::is_empty",
+        default = "Default::default"
+    )]
+    pub reviewer_groups: crate::policy::ReviewerGroups,
+    /// See `policy::AttestationRequirement`
+    #[serde(
+        rename = "build-attestations",
+        skip_serializing_if = "Option::is_none",
+        default = "Default::default"
+    )]
+    pub build_attestations: Option<crate::policy::AttestationRequirement>,
+    /// See `TrustOverride`
+    #[serde(
+        rename = "trust-overrides",
+        skip_serializing_if = "Vec::is_empty",
+        default = "Default::default"
+    )]
+    pub trust_overrides: Vec<TrustOverride>,
+    /// See `normalize`
+    #[serde(
+        skip_serializing_if = "std::collections::BTreeMap::is_empty",
+        default = "Default::default"
+    )]
+    pub normalizations: crate::normalize::Normalizations,
+    /// See `file_kind`
+    #[serde(
+        rename = "file-kinds",
+        skip_serializing_if = "Vec::is_empty",
+        default = "Default::default"
+    )]
+    pub file_kinds: Vec<crate::file_kind::FileKindRule>,
+    /// See `policy::AuthorIds`
+    #[serde(
+        rename = "git-authors",
+        skip_serializing_if = "std::collections::BTreeMap::is_empty",
+        default = "Default::default"
+    )]
+    pub git_authors: crate::policy::AuthorIds,
+}
+
+/// Trust in an id for verifying this package only, in place of the trust
+/// the current id has in it
+///
+/// Lets a team trust its members, or ban someone, for a project without
+/// touching anyone's web of trust. Effective trust still flows from the
+/// overridden id, as if the current id had trusted it with `trust`.
+///
+/// The config isn't signed, so overrides count only if its trust root is
+/// in the web of trust of the current id, and give no more trust than the
+/// trust root has. Ids the current id distrusts stay distrusted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrustOverride {
+    pub id: String,
+    /// `distrust` bans the id
+    #[serde(default)]
+    pub trust: TrustLevel,
 }
 
 const CREV_DOT_NAME: &str = ".crev";
+const STATUS_CACHE_FILE_NAME: &str = "status-cache.yaml";
+const STAGING_DRAFT_FILE_NAME: &str = "staging-draft.yaml";
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+/// Files in `.crev` that are local to a checkout, and never committed
+///
+/// `merkle` and `verify-cache` are where older versions kept their caches.
+const GITIGNORE_CONTENT: &str =
+    "merkle\nverify-cache\nstatus-cache.yaml\nstaging\nstaging-draft.yaml\n";
 
 #[derive(Fail, Debug)]
 #[fail(display = "Package config not-initialized. Use `crev package init` to generate it.")]
 struct PackageDirNotFound;
 
-fn find_package_root_dir() -> Result<PathBuf> {
-    let mut path = PathBuf::from(".").canonicalize()?;
+/// Is `rel_path` (relative to `root_dir`) in a `.crev` scope nested in `root_dir`
+///
+/// A git repository (eg. a monorepo) can have many `.crev` scopes in its
+/// subdirectories, each reviewed and verified independently of the others.
+pub(crate) fn is_in_nested_scope(root_dir: &Path, rel_path: &Path) -> bool {
+    rel_path
+        .ancestors()
+        .skip(1)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .any(|dir| root_dir.join(dir).join(CREV_DOT_NAME).is_dir())
+}
+
+/// Callback picking one of nested `.crev` scopes; see `Repo::auto_open_with`
+pub type ScopeChoiceFn<'a> = &'a dyn Fn(&[PathBuf]) -> Result<PathBuf>;
+
+/// Find all the `.crev` scopes containing the current directory, nearest first
+pub fn find_package_root_dirs() -> Result<Vec<PathBuf>> {
+    let path = PathBuf::from(".").canonicalize()?;
+    let dirs: Vec<_> = path
+        .ancestors()
+        .filter(|dir| dir.join(CREV_DOT_NAME).is_dir())
+        .map(ToOwned::to_owned)
+        .collect();
+    if dirs.is_empty() {
+        return Err(PackageDirNotFound.into());
+    }
+    Ok(dirs)
+}
+
+/// Trust roots of a `.crev` scope, following Trust Root Handover Proofs
+/// among `proofs` back from the current `trust_root`
+///
+/// Each comes with the date it handed over at, after which its proofs no
+/// longer count; the current trust root has none.
+pub(crate) fn trust_root_chain(
+    proofs: &[proof::Proof],
+    trust_root: &Id,
+) -> Vec<(Id, Option<DateTime<Utc>>)> {
+    let mut chain: Vec<(Id, Option<DateTime<Utc>>)> = vec![(trust_root.clone(), None)];
     loop {
-        if path.join(CREV_DOT_NAME).is_dir() {
-            return Ok(path);
+        let (current, until) = chain.last().expect("not empty").clone();
+        let handover = proofs
+            .iter()
+            .filter_map(|proof| match proof.content {
+                proof::Content::TrustRootHandover(ref handover) => Some(handover),
+                _ => None,
+            })
+            .filter(|handover| handover.to.id == current)
+            .filter(|handover| match until {
+                Some(until) => handover.date.with_timezone(&Utc) <= until,
+                None => true,
+            })
+            .filter(|handover| chain.iter().all(|(root, _)| *root != handover.from.id))
+            .max_by_key(|handover| handover.date.with_timezone(&Utc));
+        match handover {
+            Some(handover) => chain.push((
+                handover.from.id.clone(),
+                Some(handover.date.with_timezone(&Utc)),
+            )),
+            None => return chain,
+        }
+    }
+}
+
+/// Split proofs stored in a `.crev` scope into those authorized by its
+/// `trust_root` and foreign ones
+///
+/// A proof is authorized if its author is the trust root, or is trusted by
+/// it, directly or transitively, with Trust Proofs stored in the scope
+/// itself. This detects proofs injected with a tampered `.crev`. Former
+/// trust roots (see `trust_root_chain`) authorize proofs the same way, as
+/// long as they are dated before their handover.
+pub(crate) fn split_authorized_proofs(
+    proofs: Vec<proof::Proof>,
+    trust_root: &Id,
+) -> (Vec<proof::Proof>, Vec<proof::Proof>) {
+    let chain = trust_root_chain(&proofs, trust_root);
+    let (expired, proofs): (Vec<_>, Vec<_>) = proofs.into_iter().partition(|proof| {
+        let author = proof.content.author_id();
+        let date = proof.content.date().with_timezone(&Utc);
+        chain.iter().any(|(root, until)| match until {
+            Some(until) => *root == author && date > *until,
+            None => false,
+        })
+    });
+
+    let mut scope_db = crate::ProofDB::new();
+    scope_db.import_from_iter(proofs.iter().cloned());
+    let trust_sets: Vec<_> = chain
+        .iter()
+        .map(|(root, _)| scope_db.calculate_trust_set(root, &Default::default()))
+        .collect();
+
+    let (authorized, mut foreign): (Vec<_>, Vec<_>) = proofs.into_iter().partition(|proof| {
+        let author = proof.content.author_id();
+        trust_sets.iter().any(
+            |trust_set| match trust_set.get_effective_trust_level(&author) {
+                Some(level) => level > TrustLevel::None,
+                None => false,
+            },
+        )
+    });
+    foreign.extend(expired);
+    (authorized, foreign)
+}
+
+/// Are there any changes to the files of the scope at `root_dir`
+fn is_scope_unclean(root_dir: &Path) -> Result<bool> {
+    vcs::open(root_dir)?
+        .ok_or_else(|| format_err!("{} is not under version control", root_dir.display()))?
+        .is_dirty()
+}
+
+/// A Code Review Proof of a file; see `Repo::files_verify`
+pub struct CandidateReview {
+    pub review: proof::review::Code,
+    /// Effective trust of the reviewer in the current WoT, if any
+    pub reviewer_trust: Option<proof::trust::TrustLevel>,
+}
+
+/// Verification of a single file; see `Repo::files_verify`
+pub struct FileVerification {
+    pub path: PathBuf,
+    pub status: crate::VerificationStatus,
+    /// Reviews of the current content of the file, most trusted first
+    pub reviews: Vec<CandidateReview>,
+    /// Set if the file was renamed since a reviewed revision, along with
+    /// the verification of its content from before the rename
+    pub renamed_from: Option<(Rename, crate::VerificationStatus)>,
+    /// Set if verified, but only by reviewers among its authors; see
+    /// `policy::AuthorIds`
+    pub self_reviewed: bool,
+}
+
+/// Proofs a verification of the current tree of a scope relies on; see
+/// `Repo::relied_on_proofs`
+struct ReliedOnProofs {
+    /// Trusted reviews and attestations, by digest
+    proofs: BTreeMap<Vec<u8>, proof::Proof>,
+    /// Chains of trust to the authors of `proofs`
+    trust_chains: Vec<(Id, Vec<Vec<crate::proofdb::TrustLink>>)>,
+    /// Trust Proofs making up `trust_chains`, by digest
+    trust_proofs: BTreeMap<Vec<u8>, proof::Proof>,
+}
+
+/// Why a file is verified or not, as in `VerificationResult`
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileOutcome {
+    Verified,
+    /// Rated negative or dangerous by trusted reviewers
+    Flagged,
+    /// Reviewed by trusted reviewers, but not enough for the
+    /// `path-requirements` of the package config
+    RequirementsUnmet,
+    /// Reviewed, but by no one trusted
+    ReviewedByUntrusted,
+    /// Not reviewed as it is now, but an earlier version of it was
+    ModifiedSinceReview,
+    Unreviewed,
+}
+
+impl FileOutcome {
+    /// Outcome of `file`, that was reviewed at `reviewed_paths` before
+    pub(crate) fn of(file: &FileVerification, reviewed_paths: &HashSet<PathBuf>) -> Self {
+        match file.status {
+            crate::VerificationStatus::Verified(_) => return FileOutcome::Verified,
+            crate::VerificationStatus::Flagged | crate::VerificationStatus::Dangerous => {
+                return FileOutcome::Flagged
+            }
+            crate::VerificationStatus::Unknown => {}
         }
-        path = if let Some(parent) = path.parent() {
-            parent.to_owned()
+        if file
+            .reviews
+            .iter()
+            .any(|candidate| candidate.reviewer_trust.is_some())
+        {
+            FileOutcome::RequirementsUnmet
+        } else if !file.reviews.is_empty() {
+            FileOutcome::ReviewedByUntrusted
+        } else if file.renamed_from.is_some() || reviewed_paths.contains(&file.path) {
+            FileOutcome::ModifiedSinceReview
         } else {
-            return Err(PackageDirNotFound.into());
+            FileOutcome::Unreviewed
+        }
+    }
+}
+
+impl std::fmt::Display for FileOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            FileOutcome::Verified => "verified",
+            FileOutcome::Flagged => "flagged",
+            FileOutcome::RequirementsUnmet => "requirements-unmet",
+            FileOutcome::ReviewedByUntrusted => "reviewed-by-untrusted",
+            FileOutcome::ModifiedSinceReview => "modified-since-review",
+            FileOutcome::Unreviewed => "unreviewed",
+        })
+    }
+}
+
+/// Verification of a file, as in `VerificationResult`
+#[derive(Serialize, Debug, Clone)]
+pub struct FileResult {
+    pub path: PathBuf,
+    /// See `file_kind`
+    pub kind: crev_data::proof::review::code::FileKind,
+    pub outcome: FileOutcome,
+    /// See `VerificationStatus`
+    pub status: String,
+    /// See `FileVerification::self_reviewed`
+    #[serde(rename = "self-reviewed")]
+    pub self_reviewed: bool,
+}
+
+/// Verification of a scope and each of its files, for tools to consume;
+/// see `Repo::verify`
+#[derive(Serialize, Debug, Clone)]
+pub struct VerificationResult {
+    /// Of the package as a whole; see `VerificationStatus`
+    pub status: String,
+    pub verified: bool,
+    /// Sorted by path, relative to the root of the scope
+    pub files: Vec<FileResult>,
+}
+
+impl VerificationResult {
+    /// Numbers of files, and of verified ones, of each kind
+    pub fn coverage_by_kind(
+        &self,
+    ) -> BTreeMap<crev_data::proof::review::code::FileKind, (usize, usize)> {
+        let mut coverage = BTreeMap::new();
+        for file in &self.files {
+            let (files, verified) = coverage.entry(file.kind).or_insert((0, 0));
+            *files += 1;
+            if file.outcome == FileOutcome::Verified {
+                *verified += 1;
+            }
         }
+        coverage
     }
 }
 
+/// How much of a scope is verified, as of its last verification; see
+/// `Repo::cached_status`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScopeStatus {
+    /// All the files are verified
+    Verified,
+    /// Some of the files are verified
+    Partially,
+    /// None of the files is verified
+    Unverified,
+}
+
+impl ScopeStatus {
+    pub fn of_files(files: &[FileVerification]) -> Self {
+        let verified = files
+            .iter()
+            .filter(|file| file.status.is_verified())
+            .count();
+        if verified == files.len() {
+            ScopeStatus::Verified
+        } else if verified > 0 {
+            ScopeStatus::Partially
+        } else {
+            ScopeStatus::Unverified
+        }
+    }
+}
+
+impl std::fmt::Display for ScopeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            ScopeStatus::Verified => "verified",
+            ScopeStatus::Partially => "partially",
+            ScopeStatus::Unverified => "unverified",
+        })
+    }
+}
+
+/// `ScopeStatus` stored by `Repo::files_verify`, along with the HEAD
+/// commit it was computed at
+#[derive(Serialize, Deserialize, Debug)]
+struct StatusCache {
+    head: Option<String>,
+    status: ScopeStatus,
+}
+
+/// How long `Repo::watch_verify` waits for changes at a time
+const WATCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Callback of `Repo::watch_verify`
+pub type WatchUpdateFn<'a> = &'a mut dyn FnMut(&[PathBuf], &[FileVerification]) -> Result<bool>;
+
+/// A file renamed since the revision of a Code Review of its scope; see
+/// `Repo::renames`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rename {
+    /// Path of the file at that revision
+    pub from: PathBuf,
+    /// Digest of its content at that revision
+    pub digest: Vec<u8>,
+    /// Is the current content byte-identical
+    pub unchanged: bool,
+    /// Was the move recorded with a Path Move Proof, rather than detected
+    pub recorded: bool,
+}
+
 /// `crev` repository dir inside a package dir
 ///
 /// This represents the `.crev` directory and all
@@ -63,15 +448,22 @@ impl Repo {
         if config_path.exists() {
             bail!("`{}` already exists", config_path.display());
         }
-        util::store_to_file_with(&config_path, move |w| {
-            serde_yaml::to_writer(
-                w,
-                &PackageConfig {
-                    version: 0,
-                    trust_root: id_str.clone(),
-                },
-            )
-        })??;
+        repo.store_package_config(&PackageConfig {
+            version: 0,
+            trust_root: id_str,
+            project_id: None,
+            path_requirements: vec![],
+            reviewer_groups: Default::default(),
+            build_attestations: None,
+            trust_overrides: vec![],
+            normalizations: Default::default(),
+            file_kinds: vec![],
+            git_authors: Default::default(),
+        })?;
+        fs::write(
+            repo.dot_crev_path().join(GITIGNORE_FILE_NAME),
+            GITIGNORE_CONTENT,
+        )?;
 
         Ok(repo)
     }
@@ -87,8 +479,20 @@ impl Repo {
         Self::new(path)
     }
 
+    /// Open the nearest `.crev` scope containing the current directory
     pub fn auto_open() -> Result<Self> {
-        let root_path = find_package_root_dir()?;
+        let root_path = find_package_root_dirs()?.remove(0);
+        Self::open(&root_path)
+    }
+
+    /// Like `auto_open`, but if `.crev` scopes nest, let `choose` pick one
+    pub fn auto_open_with(choose: ScopeChoiceFn) -> Result<Self> {
+        let mut dirs = find_package_root_dirs()?;
+        let root_path = if dirs.len() > 1 {
+            choose(&dirs)?
+        } else {
+            dirs.remove(0)
+        };
         Self::open(&root_path)
     }
 
@@ -123,10 +527,33 @@ impl Repo {
         Ok(Some(serde_yaml::from_str(&config_str)?))
     }
 
+    fn store_package_config(&self, config: &PackageConfig) -> Result<()> {
+        util::store_to_file_with(&self.package_config_path(), |w| {
+            serde_yaml::to_writer(w, config)
+        })??;
+        Ok(())
+    }
+
+    /// Where `.crev` of this scope resides
+    pub fn root_dir(&self) -> &Path {
+        &self.root_dir
+    }
+
     pub fn dot_crev_path(&self) -> PathBuf {
         self.root_dir.join(CREV_DOT_NAME)
     }
 
+    /// Merkle tree of the files of this scope, updated from its cache in
+    /// `Local::scope_cache_path`; see `merkle::MerkleTree`
+    pub fn merkle_tree(&self) -> Result<merkle::MerkleTree> {
+        let statuses = crate::git_scope_statuses(&self.root_dir)?;
+        let cache_path = Local::scope_cache_path(&self.root_dir)?;
+        let mut tree = merkle::MerkleTree::load(&cache_path);
+        tree.update(&self.root_dir, &statuses)?;
+        tree.save(&cache_path)?;
+        Ok(tree)
+    }
+
     pub fn staging(&mut self) -> Result<&mut staging::Staging> {
         if self.staging.is_none() {
             self.staging = Some(staging::Staging::open(&self.root_dir)?);
@@ -154,17 +581,975 @@ impl Repo {
         PathBuf::from("proofs").join(crate::proof::rel_package_path(&proof.content))
     }
 
-    pub fn package_verify(&mut self, allow_dirty: bool) -> Result<crate::VerificationStatus> {
+    pub fn package_verify(
+        &mut self,
+        allow_dirty: bool,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<crate::VerificationStatus> {
+        let mut statuses = self.verify_scopes(vec![PathBuf::new()], allow_dirty, trust_params)?;
+        Ok(statuses.remove(0).1)
+    }
+
+    /// Verify this scope, and all the `.crev` scopes nested in it
+    ///
+    /// This scope comes first, with an empty path; the paths of nested
+    /// scopes are relative to it.
+    pub fn package_verify_nested(
+        &mut self,
+        allow_dirty: bool,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<Vec<(PathBuf, crate::VerificationStatus)>> {
+        let mut rel_dirs = vec![PathBuf::new()];
+        rel_dirs.extend(self.nested_scopes()?);
+        self.verify_scopes(rel_dirs, allow_dirty, trust_params)
+    }
+
+    /// Verify scopes at `rel_dirs`, relative to this one
+    ///
+    /// Proofs stored in `.crev` of this scope and of all the nested ones are
+    /// taken into account, so reviews of a component are found when verifying
+    /// the whole.
+    fn verify_scopes(
+        &mut self,
+        rel_dirs: Vec<PathBuf>,
+        allow_dirty: bool,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<Vec<(PathBuf, crate::VerificationStatus)>> {
+        for rel_dir in &rel_dirs {
+            if !allow_dirty && is_scope_unclean(&self.root_dir.join(rel_dir))? {
+                bail!("Git repository is not in a clean state");
+            }
+        }
+
+        let (db, trusted_set) = self.load_scope_db(trust_params)?;
+        self.verify_scopes_in(rel_dirs, &db, &trusted_set)
+    }
+
+    fn verify_scopes_in(
+        &self,
+        rel_dirs: Vec<PathBuf>,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+    ) -> Result<Vec<(PathBuf, crate::VerificationStatus)>> {
+        let ignore_list = HashSet::new();
+        let mut statuses = vec![];
+        for rel_dir in rel_dirs {
+            let scope_dir = self.root_dir.join(&rel_dir);
+            let digest = crate::get_recursive_digest_for_git_dir(&scope_dir, &ignore_list)?;
+            let mut status = db.verify_package_digest(&digest, trusted_set);
+            if status.is_verified()
+                && (!Self::path_requirements_met(&scope_dir, &digest, db, trusted_set)?
+                    || !Self::attestations_met(&scope_dir, &digest, db, trusted_set)?)
+            {
+                status = crate::VerificationStatus::Unknown;
+            }
+            let advisories: Vec<_> = Self::new(&scope_dir)?
+                .advisories_affecting(db)?
+                .into_iter()
+                .filter(|advisory| {
+                    trusted_set
+                        .get_effective_trust_level_for(
+                            &advisory.from.id,
+                            TrustDimension::Advisories,
+                        )
+                        .is_some()
+                })
+                .collect();
+            for advisory in &advisories {
+                eprintln!(
+                    "{}: {} advisory by {}: {}",
+                    scope_dir.display(),
+                    advisory.severity,
+                    advisory.from.id,
+                    advisory.comment.lines().next().unwrap_or("")
+                );
+            }
+            if let Some(advisory_status) =
+                crate::proofdb::advisory_status(advisories.into_iter(), trusted_set)
+            {
+                status = advisory_status;
+            }
+            if !status.is_verified() {
+                Local::auto_open()?.run_hooks(
+                    crate::hooks::Event::VerificationFailed,
+                    &[
+                        ("SCOPE", scope_dir.display().to_string()),
+                        ("STATUS", status.to_string()),
+                    ],
+                );
+            }
+            statuses.push((rel_dir, status));
+        }
+        Ok(statuses)
+    }
+
+    /// Advisory Proofs for the project of this scope that affect the
+    /// revision checked out, trusted or not
+    ///
+    /// The project is the `project-id` of the package config. A revision
+    /// of an advisory unknown to the VCS is assumed to be before the one
+    /// checked out; out of VCS, the advisories not fixed yet affect it.
+    fn advisories_affecting<'a>(&self, db: &'a crate::ProofDB) -> Result<Vec<&'a proof::Advisory>> {
+        let project_id = match self.try_load_package_config()? {
+            Some(PackageConfig {
+                project_id: Some(project_id),
+                ..
+            }) => project_id,
+            _ => return Ok(vec![]),
+        };
+        let vcs = vcs::open(&self.root_dir)?;
+        let revision = match vcs {
+            Some(ref vcs) => Some(vcs.revision()?.revision),
+            None => None,
+        };
+        let at_or_after = |ancestor: &str| -> Result<bool> {
+            Ok(match (&vcs, &revision) {
+                (Some(vcs), Some(revision)) => {
+                    vcs.descends_from(revision, ancestor)?.unwrap_or(true)
+                }
+                _ => true,
+            })
+        };
+        let mut affecting = vec![];
+        for advisory in db.get_advisories_for_project(&project_id) {
+            let range = &advisory.affected;
+            if (range.introduced.is_empty() || at_or_after(&range.introduced)?)
+                && (!range.is_fixed() || !at_or_after(&range.fixed)?)
+            {
+                affecting.push(advisory);
+            }
+        }
+        Ok(affecting)
+    }
+
+    /// Has the trust root of `config`, trusted by the current id, vouched
+    /// for it
+    ///
+    /// The package config isn't signed, so anyone able to change the
+    /// repository could change it. It's vouched for with a Code Review
+    /// Proof of `.crev/config.yaml`, with its current digest and not
+    /// rating it negatively, by the trust root.
+    fn is_config_authorized(
+        &self,
+        config: &PackageConfig,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+    ) -> Result<bool> {
+        let trust_root = Id::crevid_from_str(&config.trust_root)?;
+        if trusted_set.get_effective_trust_level(&trust_root).is_none() {
+            return Ok(false);
+        }
+        let digest = crev_common::blake2b256sum_file(&self.package_config_path())?;
+        let reviewed = db
+            .get_code_reviews_by_file_digest(&digest, &[])
+            .filter(|review| review.from.id == trust_root)
+            .any(|review| {
+                proof::review::Rating::Neutral <= proof::review::Common::review(review).rating
+            });
+        Ok(reviewed)
+    }
+
+    /// The `reviewer_groups` of `config`, if it's authorized (see
+    /// `is_config_authorized`), and the same groups without members
+    /// otherwise
+    fn authorized_reviewer_groups(
+        &self,
+        config: &PackageConfig,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+    ) -> Result<crate::policy::ReviewerGroups> {
+        if config.reviewer_groups.is_empty()
+            || self.is_config_authorized(config, db, trusted_set)?
+        {
+            return Ok(config.reviewer_groups.clone());
+        }
+        eprintln!(
+            "Ignoring reviewer groups of {}: its package config is not reviewed by trust root {}",
+            self.root_dir.display(),
+            config.trust_root
+        );
+        Ok(config
+            .reviewer_groups
+            .keys()
+            .map(|name| (name.clone(), vec![]))
+            .collect())
+    }
+
+    /// Check the files of the scope at `scope_dir` against its
+    /// `path_requirements`, reporting the unmet ones
+    fn path_requirements_met(
+        scope_dir: &Path,
+        package_digest: &Digest,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+    ) -> Result<bool> {
+        let scope = Self::new(scope_dir)?;
+        let (requirements, groups, kinds, author_ids) = scope
+            .try_load_package_config()?
+            .map(|config| -> Result<_> {
+                Ok((
+                    scope.authorized_reviewer_groups(&config, db, trusted_set)?,
+                    config,
+                ))
+            })
+            .transpose()?
+            .map(|(groups, config)| {
+                (
+                    config.path_requirements,
+                    groups,
+                    config.file_kinds,
+                    config.git_authors,
+                )
+            })
+            .unwrap_or_default();
+        let requirements = Self::with_org_requirements(requirements)?;
+        if requirements.is_empty() {
+            return Ok(true);
+        }
+        let authors = Self::new(scope_dir)?.file_authors(&author_ids)?;
+        let no_authors = HashSet::new();
+
+        let mut all_met = true;
+        let mut digests: Vec<_> = Self::new(scope_dir)?
+            .merkle_tree()?
+            .file_digests()
+            .into_iter()
+            .collect();
+        digests.sort();
+        for (path, digest) in digests {
+            let approvers = crate::policy::approving_reviewers(
+                db,
+                trusted_set,
+                &digest,
+                &[],
+                Some(package_digest),
+            );
+            let tags =
+                crate::policy::approving_reviewer_tags(db, &digest, &[], Some(package_digest));
+            let kind = crate::file_kind::for_path(&kinds, &path)?;
+            let metrics =
+                crate::policy::required_metrics(&requirements, &path, &scope_dir.join(&path))?;
+            let unmet = crate::policy::unmet_requirements(
+                &requirements,
+                &groups,
+                &path,
+                kind,
+                metrics.as_ref(),
+                authors.get(&path).unwrap_or(&no_authors),
+                &approvers,
+                &tags,
+            )?;
+            for requirement in unmet {
+                eprintln!("{} not verified: {}", path.display(), requirement);
+                all_met = false;
+            }
+        }
+        Ok(all_met)
+    }
+
+    /// `requirements`, along with the required reviews of the
+    /// organization policy in effect; see `org_policy`
+    fn with_org_requirements(
+        mut requirements: Vec<crate::policy::PathRequirement>,
+    ) -> Result<Vec<crate::policy::PathRequirement>> {
+        if let Some(policy) = Local::auto_open()?.load_org_policy()? {
+            requirements.extend(crate::org_policy::path_requirements(&policy.policy));
+        }
+        Ok(requirements)
+    }
+
+    fn attestations_met(
+        scope_dir: &Path,
+        package_digest: &Digest,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+    ) -> Result<bool> {
+        let requirement = match Self::new(scope_dir)?.try_load_package_config()? {
+            Some(PackageConfig {
+                build_attestations: Some(requirement),
+                ..
+            }) => requirement,
+            _ => return Ok(true),
+        };
+        let attesters = crate::policy::attesting_ids(db, trusted_set, package_digest);
+        if requirement.is_met(&attesters) {
+            return Ok(true);
+        }
+        eprintln!("{} not verified: {}", scope_dir.display(), requirement);
+        Ok(false)
+    }
+
+    /// Load the local proof db, including the proofs stored in `.crev` of
+    /// this scope and of all the nested ones
+    ///
+    /// The trust set is of the current id, with the `trust-overrides` of
+    /// this scope applied.
+    fn load_scope_db(
+        &self,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<(crate::ProofDB, crate::proofdb::TrustSet)> {
+        let (db, trusted_set, _key) = self.load_scope_db_with_key(trust_params)?;
+        Ok((db, trusted_set))
+    }
+
+    /// Like `load_scope_db`, along with a key covering everything loaded:
+    /// the key of `Local::load_db_with_key`, and the package configs and
+    /// proofs of the scopes
+    fn load_scope_db_with_key(
+        &self,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<(crate::ProofDB, crate::proofdb::TrustSet, Option<Vec<u8>>)> {
+        let local = Local::auto_open()?;
+        let (mut db, mut trusted_set, local_key) = local.load_db_with_key(trust_params)?;
+        let cacheable = local_key.is_some();
+        let mut hashed = local_key.unwrap_or_default();
+        if let Some(current_id) = local.get_current_userid()? {
+            let (overrides, root_trust) = match self.try_load_package_config()? {
+                Some(config) => {
+                    let trust_root = Id::crevid_from_str(&config.trust_root)?;
+                    match trusted_set.get_effective_trust_level(&trust_root) {
+                        Some(root_trust) => (config.trust_overrides, root_trust),
+                        None => {
+                            if !config.trust_overrides.is_empty() {
+                                eprintln!(
+                                    "Ignoring trust overrides of {}: trust root {} is not in your web of trust",
+                                    self.root_dir.display(),
+                                    trust_root
+                                );
+                            }
+                            (vec![], TrustLevel::None)
+                        }
+                    }
+                }
+                None => (vec![], TrustLevel::None),
+            };
+            for trust_override in &overrides {
+                let id = Id::crevid_from_str(&trust_override.id)?;
+                let level = std::cmp::min(trust_override.trust, root_trust);
+                if !db.override_trust(&current_id, &id, level) {
+                    eprintln!("Ignoring trust override of {}: you distrust it", id);
+                }
+            }
+            if !overrides.is_empty() {
+                trusted_set = db.calculate_trust_set(&current_id, trust_params);
+            }
+        }
+        let mut scope_dirs = vec![self.root_dir.clone()];
+        scope_dirs.extend(self.nested_scopes()?.iter().map(|d| self.root_dir.join(d)));
+        for scope_dir in scope_dirs {
+            let scope = Self::new(&scope_dir)?;
+            hashed.extend_from_slice(scope_dir.to_string_lossy().as_bytes());
+            hashed.push(0);
+            if scope.package_config_path().exists() {
+                hashed.extend_from_slice(&fs::read(scope.package_config_path())?);
+            }
+            let proofs_dir = scope_dir.join(CREV_DOT_NAME).join("proofs");
+            if !proofs_dir.exists() {
+                continue;
+            }
+            let proofs: Vec<_> = crate::local::proofs_iter_for_path(proofs_dir).collect();
+            let now = Utc::now();
+            let mut digests: Vec<_> = proofs
+                .iter()
+                .filter(|proof| !proof.content.is_expired_at(&now))
+                .map(|proof| &proof.digest)
+                .collect();
+            digests.sort();
+            for digest in digests {
+                hashed.extend_from_slice(digest);
+            }
+            let config = scope.try_load_package_config()?;
+            let trust_root = match config {
+                Some(config) => Id::crevid_from_str(&config.trust_root)?,
+                None => {
+                    // without a trust root, none of them is authorized
+                    if !proofs.is_empty() {
+                        eprintln!(
+                            "Ignoring {} proofs in {}: there is no package config with a trust root",
+                            proofs.len(),
+                            scope_dir.display()
+                        );
+                    }
+                    continue;
+                }
+            };
+            if local.get_current_userid()?.is_some()
+                && trust_root_chain(&proofs, &trust_root)
+                    .iter()
+                    .all(|(root, _)| trusted_set.get_effective_trust_level(root).is_none())
+            {
+                bail!(
+                    "Trust root {} of {} is not in your web of trust",
+                    trust_root,
+                    scope_dir.display()
+                );
+            }
+            let (authorized, foreign) = split_authorized_proofs(proofs, &trust_root);
+            for proof in foreign {
+                eprintln!(
+                    "Ignoring proof {} in {}: author {} is not authorized by the trust root {}",
+                    crev_common::base64_encode(&proof.digest),
+                    scope_dir.display(),
+                    proof.content.author_id(),
+                    trust_root
+                );
+            }
+            db.import_from_iter(authorized.into_iter());
+        }
+        self.warn_about_project_identity(&db)?;
+        let key = if cacheable {
+            Some(crev_common::blake2b256sum(&hashed))
+        } else {
+            None
+        };
+        Ok((db, trusted_set, key))
+    }
+
+    /// Verify each file of this scope separately, by Code Review Proofs
+    /// of its current content
+    ///
+    /// Sorted by path, relative to the root of the scope.
+    pub fn files_verify(
+        &mut self,
+        allow_dirty: bool,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<Vec<FileVerification>> {
         if !allow_dirty && self.is_unclean()? {
             bail!("Git repository is not in a clean state");
         }
 
-        let local = Local::auto_open()?;
-        let params = Default::default();
-        let (db, trusted_set) = local.load_db(&params)?;
-        let ignore_list = HashSet::new();
-        let digest = crate::get_recursive_digest_for_git_dir(&self.root_dir, &ignore_list)?;
-        Ok(db.verify_package_digest(&digest, &trusted_set))
+        let (db, trusted_set, key) = self.load_scope_db_with_key(trust_params)?;
+        self.files_verify_in(&db, &trusted_set, key, None)
+    }
+
+    /// Like `files_verify`, but only of the files at or under `paths`, eg.
+    /// of `src/crypto`
+    ///
+    /// Paths are relative to the current directory, and have to be in this
+    /// scope. The other files aren't checked, and the status of the scope
+    /// as a whole (see `cached_status`) is left as it was.
+    pub fn verify_paths(
+        &mut self,
+        paths: &[PathBuf],
+        allow_dirty: bool,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<Vec<FileVerification>> {
+        if !allow_dirty && self.is_unclean()? {
+            bail!("Git repository is not in a clean state");
+        }
+
+        let rel_paths = paths
+            .iter()
+            .map(|path| self.rel_path_of(path))
+            .collect::<Result<Vec<_>>>()?;
+        let (db, trusted_set, key) = self.load_scope_db_with_key(trust_params)?;
+        let files = self.files_verify_in(&db, &trusted_set, key, Some(&rel_paths))?;
+        for (path, rel_path) in paths.iter().zip(&rel_paths) {
+            if !files.iter().any(|file| file.path.starts_with(rel_path)) {
+                bail!("No files of the scope at {}", path.display());
+            }
+        }
+        Ok(files)
+    }
+
+    /// The file at `path` (relative to the current directory), with the
+    /// reviews and advisories covering it; see `annotate`
+    ///
+    /// Uncommitted changes are fine, as the file is annotated as it is.
+    pub fn annotate(
+        &mut self,
+        path: &Path,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<crate::annotate::Annotation> {
+        let rel_path = self.rel_path_of(path)?;
+        let (db, trusted_set, key) = self.load_scope_db_with_key(trust_params)?;
+        let only_paths = [rel_path.clone()];
+        let file = self
+            .files_verify_in(&db, &trusted_set, key, Some(&only_paths))?
+            .into_iter()
+            .find(|file| file.path == rel_path)
+            .ok_or_else(|| format_err!("{} is not a file of the scope", path.display()))?;
+        let source = util::read_file_to_string(&self.root_dir.join(&rel_path))?;
+
+        let label = |id: &Id| match db.lookup_url(id) {
+            Some(url) => url.url.clone(),
+            None => id.to_string(),
+        };
+        let reviews = file
+            .reviews
+            .iter()
+            .map(|candidate| {
+                let review = &candidate.review;
+                let common = proof::review::Common::review(review);
+                let trust = candidate
+                    .reviewer_trust
+                    .map_or_else(|| "untrusted".to_owned(), |level| level.to_string());
+                let mut line = format!(
+                    "{} ({}): {}, {} thoroughness, {} understanding",
+                    label(&review.from.id),
+                    trust,
+                    common.rating,
+                    common.thoroughness,
+                    common.understanding
+                );
+                if let Some(comment) = review.comment.lines().next() {
+                    line.push_str(&format!(" - {}", comment));
+                }
+                if let Some(group) = review.group_of(&rel_path) {
+                    if !group.comment.is_empty() {
+                        line.push_str(&format!(" ({}/: {})", group.path.display(), group.comment));
+                    }
+                }
+                line
+            })
+            .collect();
+
+        let package_digest = self.package_digest(true)?;
+        let advisories = db
+            .get_package_reviews_by_digest(&package_digest)
+            .filter(|review| {
+                proof::review::Common::review(review).rating <= proof::review::Rating::Negative
+            })
+            .map(|review| {
+                format!(
+                    "{} ({}): {}",
+                    label(&review.from.id),
+                    proof::review::Common::review(&review).rating,
+                    review.comment.lines().next().unwrap_or("")
+                )
+            })
+            .collect();
+
+        let regions = if rel_path.extension() == Some("rs".as_ref()) {
+            let package_name = self.package_name();
+            crate::rust_anchors::source_regions(&source)
+                .into_iter()
+                .map(|region| {
+                    let status = db.verify_anchors(
+                        std::slice::from_ref(&region.anchor),
+                        "",
+                        &package_name,
+                        &trusted_set,
+                    );
+                    crate::annotate::RegionStatus { region, status }
+                })
+                .filter(|region| region.status != crate::VerificationStatus::Unknown)
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Ok(crate::annotate::Annotation {
+            path: rel_path,
+            status: file.status,
+            reviews,
+            advisories,
+            regions,
+            source,
+        })
+    }
+
+    /// Trusted reviews of the current files that are likely stale; see
+    /// `stale`
+    pub fn stale_reviews(
+        &mut self,
+        params: &crate::stale::StaleParams,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<Vec<crate::stale::StaleReview>> {
+        let (db, trusted_set, key) = self.load_scope_db_with_key(trust_params)?;
+        let files = self.files_verify_in(&db, &trusted_set, key, None)?;
+        let changes = match self.vcs()? {
+            Some(vcs) => vcs.changes()?,
+            None => vec![],
+        };
+        Ok(crate::stale::find(
+            &files,
+            &changes,
+            |id| db.last_proof_date(id),
+            params,
+            Utc::now(),
+        ))
+    }
+
+    /// With a `cache_key` (see `load_scope_db_with_key`), files unchanged
+    /// since the last verification with the same key aren't checked again;
+    /// see `verify_cache`. With `only_paths`, only the files at or under
+    /// them are verified.
+    ///
+    /// Package Reviews of the whole tree cover each of its files: they make
+    /// the files not reviewed otherwise verified, as long as the
+    /// `path-requirements` are met, and flag all of them if negative.
+    /// Being about the current tree as a whole, that's never cached.
+    fn files_verify_in(
+        &mut self,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+        cache_key: Option<Vec<u8>>,
+        only_paths: Option<&[PathBuf]>,
+    ) -> Result<Vec<FileVerification>> {
+        let (requirements, groups, kinds, author_ids) = self
+            .try_load_package_config()?
+            .map(|config| -> Result<_> {
+                Ok((self.authorized_reviewer_groups(&config, db, trusted_set)?, config))
+            })
+            .transpose()?
+            .map(|(groups, config)| {
+                (
+                    config.path_requirements,
+                    groups,
+                    config.file_kinds,
+                    config.git_authors,
+                )
+            })
+            .unwrap_or_default();
+        let requirements = Self::with_org_requirements(requirements)?;
+        let authors = self.file_authors(&author_ids)?;
+        let no_authors = HashSet::new();
+        let package_digest = self.package_digest(true)?;
+        let package_status = db.verify_package_digest(&package_digest, trusted_set);
+        let mut renames = self.renames()?;
+        let cache_path = Local::scope_cache_path(&self.root_dir)?;
+        let mut cache = cache_key.map(|key| verify_cache::VerifyCache::load(&cache_path, &key));
+        let mut cached_files = HashMap::new();
+        let mut files = vec![];
+        for (path, raw_digest) in self.merkle_tree()?.file_digests() {
+            if let Some(only_paths) = only_paths {
+                if !only_paths.iter().any(|only| path.starts_with(only)) {
+                    continue;
+                }
+            }
+            let rename = renames.remove(&path);
+            let file_authors = authors.get(&path).unwrap_or(&no_authors);
+            let hit = cache
+                .as_ref()
+                .and_then(|cache| cache.get(&path, &raw_digest, rename.as_ref()))
+                .cloned();
+            let cached = match hit {
+                Some(cached) => cached,
+                None => {
+                    let full_path = self.root_dir.join(&path);
+                    // each review counts with the normalizations it recorded, not the ones
+                    // of the package config; flags on any of them win, then approvals
+                    let rank = |status: &crate::VerificationStatus| match status {
+                        crate::VerificationStatus::Dangerous => 0,
+                        crate::VerificationStatus::Flagged => 1,
+                        crate::VerificationStatus::Verified(_) => 2,
+                        crate::VerificationStatus::Unknown => 3,
+                    };
+                    let mut digest = raw_digest.clone();
+                    let mut normalization = vec![];
+                    let mut status = db.verify_file_digest(&digest, &normalization, trusted_set);
+                    for candidate in db.code_review_normalizations().skip(1) {
+                        let candidate_digest =
+                            crate::normalize::file_digest(&full_path, candidate)?;
+                        let candidate_status =
+                            db.verify_file_digest(&candidate_digest, candidate, trusted_set);
+                        if rank(&candidate_status) < rank(&status) {
+                            digest = candidate_digest;
+                            normalization = candidate.to_owned();
+                            status = candidate_status;
+                        }
+                    }
+                    if status == crate::VerificationStatus::Unknown {
+                        // the file might consist of items reviewed elsewhere
+                        let anchors = crate::rust_anchors::file_anchors(&path, &full_path)?;
+                        let anchors_status =
+                            db.verify_anchors(&anchors, "", &self.package_name(), trusted_set);
+                        if anchors_status.is_verified() {
+                            status = anchors_status;
+                        }
+                    }
+                    if status.is_verified() {
+                        let approvers = crate::policy::approving_reviewers(
+                            db,
+                            trusted_set,
+                            &digest,
+                            &normalization,
+                            None,
+                        );
+                        let tags = crate::policy::approving_reviewer_tags(
+                            db,
+                            &digest,
+                            &normalization,
+                            None,
+                        );
+                        // requirements follow files moved on purpose out of their paths
+                        let mut paths = vec![path.as_path()];
+                        paths.extend(
+                            rename
+                                .iter()
+                                .filter(|r| r.recorded)
+                                .map(|r| r.from.as_path()),
+                        );
+                        let kind = crate::file_kind::for_path(&kinds, &path)?;
+                        let metrics =
+                            crate::policy::required_metrics(&requirements, &path, &full_path)?;
+                        for path in paths {
+                            if !crate::policy::unmet_requirements(
+                                &requirements,
+                                &groups,
+                                path,
+                                kind,
+                                metrics.as_ref(),
+                                file_authors,
+                                &approvers,
+                                &tags,
+                            )?
+                            .is_empty()
+                            {
+                                status = crate::VerificationStatus::Unknown;
+                            }
+                        }
+                    }
+                    // identical content is verified by its digest already, but a
+                    // near-identical one needs another review
+                    let previous_status = rename
+                        .as_ref()
+                        .map(|rename| db.verify_file_digest(&rename.digest, &[], trusted_set))
+                        .filter(|previous_status| {
+                            *previous_status != crate::VerificationStatus::Unknown
+                        });
+                    verify_cache::CachedFile {
+                        raw_digest,
+                        digest,
+                        normalization,
+                        status,
+                        rename,
+                        previous_status,
+                    }
+                }
+            };
+            let mut reviews: Vec<_> = db
+                .get_code_reviews_by_file_digest(&cached.digest, &cached.normalization)
+                .map(|review| CandidateReview {
+                    reviewer_trust: trusted_set.get_effective_trust_level(&review.from.id),
+                    review: review.clone(),
+                })
+                .collect();
+            reviews.sort_by_key(|candidate| std::cmp::Reverse(candidate.reviewer_trust));
+            let renamed_from = match (&cached.rename, &cached.previous_status) {
+                (Some(rename), Some(previous_status)) => {
+                    Some((rename.clone(), previous_status.clone()))
+                }
+                _ => None,
+            };
+            let status = match (&cached.status, &package_status) {
+                (crate::VerificationStatus::Unknown, crate::VerificationStatus::Verified(_)) => {
+                    let approvers = crate::policy::approving_reviewers(
+                        db,
+                        trusted_set,
+                        &cached.digest,
+                        &cached.normalization,
+                        Some(&package_digest),
+                    );
+                    let tags = crate::policy::approving_reviewer_tags(
+                        db,
+                        &cached.digest,
+                        &cached.normalization,
+                        Some(&package_digest),
+                    );
+                    let kind = crate::file_kind::for_path(&kinds, &path)?;
+                    let metrics = crate::policy::required_metrics(
+                        &requirements,
+                        &path,
+                        &self.root_dir.join(&path),
+                    )?;
+                    if crate::policy::unmet_requirements(
+                        &requirements,
+                        &groups,
+                        &path,
+                        kind,
+                        metrics.as_ref(),
+                        file_authors,
+                        &approvers,
+                        &tags,
+                    )?
+                    .is_empty()
+                    {
+                        package_status.clone()
+                    } else {
+                        cached.status.clone()
+                    }
+                }
+                (crate::VerificationStatus::Dangerous, _) => cached.status.clone(),
+                (_, crate::VerificationStatus::Dangerous)
+                | (_, crate::VerificationStatus::Flagged) => package_status.clone(),
+                _ => cached.status.clone(),
+            };
+            let self_reviewed = !file_authors.is_empty()
+                && status.is_verified()
+                && crate::policy::is_self_reviewed(
+                    file_authors,
+                    &crate::policy::approving_reviewers(
+                        db,
+                        trusted_set,
+                        &cached.digest,
+                        &cached.normalization,
+                        Some(&package_digest),
+                    ),
+                );
+            files.push(FileVerification {
+                status,
+                path: path.clone(),
+                reviews,
+                renamed_from,
+                self_reviewed,
+            });
+            cached_files.insert(path, cached);
+        }
+        if let Some(ref mut cache) = cache {
+            if only_paths.is_some() {
+                cache.update_files(cached_files);
+            } else {
+                cache.set_files(cached_files);
+            }
+            cache.save(&cache_path)?;
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        if only_paths.is_none() {
+            self.store_status_cache(ScopeStatus::of_files(&files))?;
+        }
+        Ok(files)
+    }
+
+    /// Verify the package, and each of its files
+    ///
+    /// Along with the status of each file (see `files_verify`), tells what
+    /// keeps it from being verified, eg. that it was modified since it was
+    /// reviewed.
+    pub fn verify(
+        &mut self,
+        allow_dirty: bool,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<VerificationResult> {
+        if !allow_dirty && self.is_unclean()? {
+            bail!("Git repository is not in a clean state");
+        }
+
+        let (db, trusted_set, key) = self.load_scope_db_with_key(trust_params)?;
+        let status = self
+            .verify_scopes_in(vec![PathBuf::new()], &db, &trusted_set)?
+            .remove(0)
+            .1;
+        let files = self.files_verify_in(&db, &trusted_set, key, None)?;
+        let reviewed_paths = self.reviewed_paths();
+        let kinds = self
+            .try_load_package_config()?
+            .map(|config| config.file_kinds)
+            .unwrap_or_default();
+        Ok(VerificationResult {
+            status: status.to_string(),
+            verified: status.is_verified(),
+            files: files
+                .iter()
+                .map(|file| {
+                    Ok(FileResult {
+                        path: file.path.clone(),
+                        kind: crate::file_kind::for_path(&kinds, &file.path)?,
+                        outcome: FileOutcome::of(file, &reviewed_paths),
+                        status: file.status.to_string(),
+                        self_reviewed: file.self_reviewed,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Paths of the files in the Code Review Proofs stored in this scope
+    fn reviewed_paths(&self) -> HashSet<PathBuf> {
+        let proofs_dir = self.dot_crev_path().join("proofs");
+        if !proofs_dir.exists() {
+            return HashSet::new();
+        }
+        crate::local::proofs_iter_for_path(proofs_dir)
+            .filter_map(|proof| match proof.content {
+                proof::Content::Code(code) => Some(code),
+                _ => None,
+            })
+            .flat_map(|code| code.files.into_iter().map(|file| file.path))
+            .collect()
+    }
+
+    fn status_cache_path(&self) -> PathBuf {
+        self.dot_crev_path().join(STATUS_CACHE_FILE_NAME)
+    }
+
+    /// Where the draft of the review being committed is kept until it's
+    /// signed; see `util::edit_proof_content_iteractively_in`
+    pub fn staging_draft_path(&self) -> PathBuf {
+        self.dot_crev_path().join(STAGING_DRAFT_FILE_NAME)
+    }
+
+    /// Commit `HEAD` of the git repository of the scope points to, if any
+    pub fn head_commit_id(&self) -> Option<String> {
+        let vcs = vcs::open(&self.root_dir).ok()??;
+        Some(vcs.revision().ok()?.revision)
+    }
+
+    fn store_status_cache(&self, status: ScopeStatus) -> Result<()> {
+        let cache = StatusCache {
+            head: self.head_commit_id(),
+            status,
+        };
+        util::store_to_file_with(&self.status_cache_path(), |w| {
+            serde_yaml::to_writer(w, &cache)
+        })??;
+        Ok(())
+    }
+
+    /// Status of the scope as of its last verification, if still at the
+    /// same HEAD commit
+    ///
+    /// Only reads the cache and the HEAD of git, so it's fast enough for
+    /// shell prompts, but doesn't notice uncommitted changes made since.
+    pub fn cached_status(&self) -> Result<Option<ScopeStatus>> {
+        let path = self.status_cache_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let cache: StatusCache = serde_yaml::from_str(&util::read_file_to_string(&path)?)?;
+        Ok(Some(cache.status).filter(|_| cache.head == self.head_commit_id()))
+    }
+
+    /// Verify the files of the scope again whenever some of them change
+    ///
+    /// `on_update` gets the paths changed since the previous verification
+    /// (none the first time) and the verification of all the files, and
+    /// returns whether to keep watching. Only the changed files are read
+    /// and checked again, thanks to `merkle_tree` and `verify_cache`.
+    pub fn watch_verify(
+        &mut self,
+        trust_params: &crate::TrustDistanceParams,
+        on_update: WatchUpdateFn<'_>,
+    ) -> Result<()> {
+        let mut watcher = watch::Watcher::new(&self.root_dir)?;
+        let mut changed = vec![];
+        loop {
+            let files = self.files_verify(true, trust_params)?;
+            if !on_update(&changed, &files)? {
+                return Ok(());
+            }
+            changed = vec![];
+            while changed.is_empty() {
+                changed = watcher.wait(WATCH_TIMEOUT)?;
+            }
+        }
+    }
+
+    fn warn_about_project_identity(&self, db: &crate::ProofDB) -> Result<()> {
+        let project_id = self.try_load_package_config()?.and_then(|c| c.project_id);
+        if let Some(project_id) = project_id {
+            if let crate::ProjectIdentity::Colliding(_) = db.get_project_identity(&project_id) {
+                eprintln!(
+                    "Warning: identity of project {} collides with another project. See `crev package identity`.",
+                    project_id
+                );
+            }
+            if let Some(migration) = db.get_project_migration(&project_id) {
+                eprintln!("Warning: project {} moved to {}", project_id, migration.url);
+            }
+        }
+        Ok(())
     }
 
     pub fn package_digest(&mut self, allow_dirty: bool) -> Result<Digest> {
@@ -180,105 +1565,999 @@ impl Repo {
     }
 
     fn is_unclean(&self) -> Result<bool> {
-        let git_repo = git2::Repository::open(&self.root_dir)?;
-        if git_repo.state() != git2::RepositoryState::Clean {
+        is_scope_unclean(&self.root_dir)
+    }
+
+    /// `.crev` scopes nested in this one, relative to its root dir
+    pub fn nested_scopes(&self) -> Result<Vec<PathBuf>> {
+        let mut scopes = vec![];
+        let walker = walkdir::WalkDir::new(&self.root_dir)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.file_name() != ".git" && entry.file_name() != CREV_DOT_NAME
+            });
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_dir() && entry.path().join(CREV_DOT_NAME).is_dir() {
+                scopes.push(entry.path().strip_prefix(&self.root_dir)?.to_owned());
+            }
+        }
+        scopes.sort();
+        Ok(scopes)
+    }
+
+    /// The VCS this scope is kept in, if any
+    pub fn vcs(&self) -> Result<Option<Box<dyn vcs::Vcs>>> {
+        vcs::open(&self.root_dir)
+    }
+
+    fn read_revision(&self) -> Result<crev_data::proof::Revision> {
+        match self.vcs()? {
+            Some(vcs) => vcs.revision(),
+            None => bail!("Couldn't identify revision info"),
+        }
+    }
+
+    /// Create a Package Review Proof of the whole tree of this scope, at
+    /// its current revision
+    ///
+    /// It covers each of the files for `verify`; see `files_verify_in`.
+    pub fn trust_package(
+        &mut self,
+        passphrase_callback: PassphraseFn,
+        allow_dirty: bool,
+    ) -> Result<()> {
+        if !self.staging()?.is_empty() {
+            bail!("Can't review with uncommitted staged files.");
+        }
+
+        if !allow_dirty && self.is_unclean()? {
             bail!("Git repository is not in a clean state");
         }
-        let mut status_opts = git2::StatusOptions::new();
-        status_opts.include_unmodified(true);
-        status_opts.include_untracked(false);
-        let mut unclean_found = false;
-        for entry in git_repo.statuses(Some(&mut status_opts))?.iter() {
-            if entry.status() != git2::Status::CURRENT {
-                unclean_found = true;
+
+        let local = Local::auto_open()?;
+        let mut package = self.code_review_package_info(self.read_revision()?);
+        package.digest = self.package_digest(allow_dirty)?.into_vec();
+        let pub_id = local.read_current_locked_id()?.to_pubid();
+
+        let review = proof::review::PackageBuilder::default()
+            .from(pub_id)
+            .package(package)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        let review = util::edit_proof_content_iteractively(&review.into())?;
+
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = review.sign_by(&id)?;
+
+        self.save_signed_review(&local, &proof, &id)?;
+        Ok(())
+    }
+
+    /// Revisions of the Code Reviews of this scope, by `author` if set,
+    /// that HEAD descends from, most recent review first
+    fn reviewed_revisions(
+        &self,
+        git_repo: &git2::Repository,
+        head: git2::Oid,
+        author: Option<&crev_data::Id>,
+    ) -> Vec<git2::Oid> {
+        let proofs_dir = self.dot_crev_path().join("proofs");
+        if !proofs_dir.exists() {
+            return vec![];
+        }
+        let mut revisions: Vec<_> = crate::local::proofs_iter_for_path(proofs_dir)
+            .filter_map(|proof| match proof.content {
+                proof::Content::Code(code) => Some(code),
+                _ => None,
+            })
+            .filter(|code| author.map_or(true, |author| code.from.id == *author))
+            .filter(|code| code.package.revision_type == "git")
+            .filter_map(|code| {
+                let oid = git2::Oid::from_str(&code.package.revision).ok()?;
+                Some((proof::ContentCommon::date_utc(&code), oid))
+            })
+            .filter(|(_, oid)| {
+                *oid == head || git_repo.graph_descendant_of(head, *oid).unwrap_or(false)
+            })
+            .collect();
+        revisions.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+        let mut unique = vec![];
+        for (_, oid) in revisions {
+            if !unique.contains(&oid) {
+                unique.push(oid);
             }
         }
+        unique
+    }
 
-        Ok(unclean_found)
+    /// The most recent Code Review of this scope by `author`, made at a
+    /// revision HEAD descends from
+    fn last_reviewed_revision(
+        &self,
+        git_repo: &git2::Repository,
+        head: git2::Oid,
+        author: &crev_data::Id,
+    ) -> Option<git2::Oid> {
+        self.reviewed_revisions(git_repo, head, Some(author))
+            .into_iter()
+            .next()
     }
 
-    fn try_read_git_revision(&self) -> Result<Option<crev_data::proof::Revision>> {
-        let dot_git_path = self.root_dir.join(".git");
-        if !dot_git_path.exists() {
-            return Ok(None);
+    /// Path Move Proofs stored in this scope, most recent first
+    ///
+    /// Only those the trust root authorizes count, so none without a
+    /// package config; see `split_authorized_proofs`.
+    fn recorded_moves(&self) -> Result<Vec<proof::PathMove>> {
+        let proofs_dir = self.dot_crev_path().join("proofs");
+        let config = match self.try_load_package_config()? {
+            Some(config) => config,
+            None => return Ok(vec![]),
+        };
+        if !proofs_dir.exists() {
+            return Ok(vec![]);
         }
-        let git_repo = git2::Repository::open(&self.root_dir)?;
+        let proofs: Vec<_> = crate::local::proofs_iter_for_path(proofs_dir).collect();
+        let proofs = split_authorized_proofs(proofs, &Id::crevid_from_str(&config.trust_root)?).0;
+        let mut moves: Vec<_> = proofs
+            .into_iter()
+            .filter_map(|proof| match proof.content {
+                proof::Content::PathMove(path_move) => Some(path_move),
+                _ => None,
+            })
+            .collect();
+        moves.sort_by_key(|path_move| std::cmp::Reverse(proof::ContentCommon::date_utc(path_move)));
+        Ok(moves)
+    }
 
-        let head = git_repo.head()?;
-        let rev = head
-            .resolve()?
-            .target()
-            .ok_or_else(|| format_err!("HEAD target does not resolve to oid"))?
-            .to_string();
-        Ok(Some(crev_data::proof::Revision {
-            revision_type: "git".into(),
-            revision: rev,
-        }))
+    /// `Rename` of a file now at `path`, from `from` in the first of
+    /// `bases` that has it
+    fn rename_in(
+        &self,
+        git_repo: &git2::Repository,
+        scope_prefix: &Path,
+        bases: &[git2::Oid],
+        path: &Path,
+        from: &Path,
+        recorded: bool,
+    ) -> Result<Option<Rename>> {
+        for base in bases {
+            let entry = match git_repo
+                .find_commit(*base)?
+                .tree()?
+                .get_path(&scope_prefix.join(from))
+            {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let digest = crev_common::blake2b256sum(git_repo.find_blob(entry.id())?.content());
+            let full_path = self.root_dir.join(path);
+            let unchanged =
+                full_path.is_file() && crev_common::blake2b256sum_file(&full_path)? == digest;
+            return Ok(Some(Rename {
+                from: from.to_owned(),
+                digest,
+                unchanged,
+                recorded,
+            }));
+        }
+        Ok(None)
     }
 
-    fn read_revision(&self) -> Result<crev_data::proof::Revision> {
-        if let Some(info) = self.try_read_git_revision()? {
-            return Ok(info);
+    /// Ids of the authors of the changes to each file of this scope, by
+    /// its path, as far as `author_ids` tell
+    ///
+    /// Emails are compared ignoring case. Git is only asked with some
+    /// `author_ids` to map authors by.
+    pub fn file_authors(
+        &self,
+        author_ids: &crate::policy::AuthorIds,
+    ) -> Result<HashMap<PathBuf, HashSet<Id>>> {
+        let vcs = match self.vcs()? {
+            Some(vcs) if !author_ids.is_empty() => vcs,
+            _ => return Ok(HashMap::new()),
+        };
+        let mut ids = HashMap::new();
+        for (email, id) in author_ids {
+            let id = Id::crevid_from_str(id)
+                .map_err(|e| format_err!("Invalid id of git author {}: {}", email, e))?;
+            ids.insert(email.to_lowercase(), id);
         }
-        bail!("Couldn't identify revision info");
+        Ok(vcs
+            .file_authors()?
+            .into_iter()
+            .map(|(path, emails)| {
+                let authors = emails
+                    .iter()
+                    .filter_map(|email| ids.get(&email.to_lowercase()).cloned())
+                    .collect();
+                (path, authors)
+            })
+            .collect())
     }
 
-    pub fn trust_package(&mut self, passphrase_callback: PassphraseFn, allow_dirty: bool) -> Result<()> {
-        if !self.staging()?.is_empty() {
-            bail!("Can't review with uncommitted staged files.");
+    /// Files renamed since the revisions of Code Reviews of this scope, by
+    /// their current path
+    ///
+    /// Moves recorded with Path Move Proofs come first. Other renames are
+    /// found with git rename detection, so renamed files don't need to be
+    /// byte-identical. The most recent reviewed revision a file was renamed
+    /// since is reported.
+    pub fn renames(&self) -> Result<HashMap<PathBuf, Rename>> {
+        let git_repo = git2::Repository::discover(&self.root_dir)?;
+        let workdir = git_repo
+            .workdir()
+            .ok_or_else(|| format_err!("Git repository has no working directory"))?
+            .canonicalize()?;
+        let scope_prefix = self.root_dir.strip_prefix(&workdir)?;
+        let head = match git_repo.head().ok().and_then(|head| head.target()) {
+            Some(head) => head,
+            None => return Ok(HashMap::new()),
+        };
+        let reviewed = self.reviewed_revisions(&git_repo, head, None);
+
+        let mut renames = HashMap::new();
+        for path_move in self.recorded_moves()? {
+            // the content right before the move is the one to compare to
+            let mut bases: Vec<_> = git2::Oid::from_str(&path_move.package.revision)
+                .into_iter()
+                .collect();
+            bases.extend(&reviewed);
+            for m in &path_move.moves {
+                if renames.contains_key(&m.to) {
+                    continue;
+                }
+                if let Some(rename) =
+                    self.rename_in(&git_repo, scope_prefix, &bases, &m.to, &m.from, true)?
+                {
+                    renames.insert(m.to.clone(), rename);
+                }
+            }
+        }
+        for base in &reviewed {
+            for (path, from) in history::renames_since(&git_repo, scope_prefix, *base)? {
+                if renames.contains_key(&path) {
+                    continue;
+                }
+                if let Some(rename) = self.rename_in(
+                    &git_repo,
+                    scope_prefix,
+                    std::slice::from_ref(base),
+                    &path,
+                    &from,
+                    false,
+                )? {
+                    renames.insert(path, rename);
+                }
+            }
+        }
+        Ok(renames)
+    }
+
+    /// Create a Path Move Proof of moving the file at `from` to `to`
+    ///
+    /// If the file is still at `from`, it's moved in the working directory
+    /// too; otherwise it has to be at `to` already, eg. after `git mv`.
+    pub fn move_path(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<()> {
+        let from = self.rel_path_of(from)?;
+        let to = self.rel_path_of(to)?;
+        let (full_from, full_to) = (self.root_dir.join(&from), self.root_dir.join(&to));
+        if full_from.is_file() && !full_to.exists() {
+            if let Some(parent) = full_to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&full_from, &full_to)?;
+            println!("Moved {} to {}", from.display(), to.display());
         }
+        if !full_to.is_file() {
+            bail!("No file at {}", to.display());
+        }
+        if full_from.exists() {
+            bail!("{} still exists", from.display());
+        }
+
+        let local = Local::auto_open()?;
+        let revision = self.read_revision()?;
+        let pub_id = local.read_current_locked_id()?.to_pubid();
+        let path_move = proof::path_move::PathMoveBuilder::default()
+            .from(pub_id)
+            .package(self.code_review_package_info(revision))
+            .moves(vec![proof::path_move::Move { from, to }])
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        let path_move = util::edit_proof_content_iteractively(&path_move.into())?;
+
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = path_move.sign_by(&id)?;
+        self.save_signed_review(&local, &proof, &id)
+    }
 
+    /// Create a Build Attestation Proof of `artifact`, built from the
+    /// current sources of this scope
+    pub fn attest_build(
+        &mut self,
+        artifact: &Path,
+        passphrase_callback: PassphraseFn,
+        allow_dirty: bool,
+    ) -> Result<()> {
         if !allow_dirty && self.is_unclean()? {
             bail!("Git repository is not in a clean state");
         }
+        let name = artifact
+            .file_name()
+            .ok_or_else(|| format_err!("Not a file path: {}", artifact.display()))?
+            .to_string_lossy()
+            .into_owned();
+        let artifact = proof::build_attestation::Artifact {
+            name,
+            digest: crev_common::blake2b256sum_file(artifact)?,
+            digest_type: proof::default_digest_type(),
+        };
 
         let local = Local::auto_open()?;
-        let _revision = self.read_revision()?;
+        let mut package = self.code_review_package_info(self.read_revision()?);
+        package.digest = self.package_digest(allow_dirty)?.into_vec();
+        let pub_id = local.read_current_locked_id()?.to_pubid();
+        let attestation = proof::build_attestation::BuildAttestationBuilder::default()
+            .from(pub_id)
+            .package(package)
+            .artifact(artifact)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        let attestation = util::edit_proof_content_iteractively(&attestation.into())?;
 
-        let ignore_list = HashSet::new();
-        let _digest = crate::get_recursive_digest_for_git_dir(&self.root_dir, &ignore_list)?;
         let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = attestation.sign_by(&id)?;
+        self.save_signed_review(&local, &proof, &id)
+    }
 
-        let review = proof::review::PackageBuilder::default()
-            .from(id.id.to_owned())
+    /// Create a Review Request Proof for the current revision of this
+    /// scope, for followers to find on `crev todo`
+    pub fn request_review(
+        &mut self,
+        passphrase_callback: PassphraseFn,
+        allow_dirty: bool,
+    ) -> Result<()> {
+        if !allow_dirty && self.is_unclean()? {
+            bail!("Git repository is not in a clean state");
+        }
+        let local = Local::auto_open()?;
+        let mut package = self.code_review_package_info(self.read_revision()?);
+        package.digest = self.package_digest(allow_dirty)?.into_vec();
+        let pub_id = local.read_current_locked_id()?.to_pubid();
+        let request = proof::review_request::ReviewRequestBuilder::default()
+            .from(pub_id)
+            .package(package)
             .build()
             .map_err(|e| format_err!("{}", e))?;
 
-        let review = util::edit_proof_content_iteractively(&review.into())?;
+        let request = util::edit_proof_content_iteractively(&request.into())?;
 
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = request.sign_by(&id)?;
+        self.save_signed_review(&local, &proof, &id)
+    }
+
+    /// Create an Advisory Proof for the project of this scope
+    ///
+    /// `introduced` and `fixed` can be anything the VCS resolves to
+    /// a revision, eg. tags. The files at `paths` are flagged as they are
+    /// now.
+    pub fn create_advisory(
+        &mut self,
+        severity: proof::advisory::Severity,
+        introduced: Option<&str>,
+        fixed: Option<&str>,
+        paths: &[PathBuf],
+        passphrase_callback: PassphraseFn,
+    ) -> Result<()> {
+        let project_id = match self.load_package_config()?.project_id {
+            Some(project_id) => project_id,
+            None => bail!("The package has no project id; see `crev package identify`"),
+        };
+        let vcs = vcs::open(&self.root_dir)?;
+        let resolve = |name: Option<&str>| -> Result<String> {
+            Ok(match (name, &vcs) {
+                (None, _) => "".into(),
+                (Some(name), Some(vcs)) => vcs.resolve_revision(name)?,
+                (Some(name), None) => name.to_owned(),
+            })
+        };
+        let affected = proof::advisory::AffectedRange {
+            introduced: resolve(introduced)?,
+            fixed: resolve(fixed)?,
+        };
+        let file_digests = self.merkle_tree()?.file_digests();
+        let mut files = vec![];
+        for path in paths {
+            let path = self.rel_path_of(path)?;
+            let digest = match file_digests.get(&path) {
+                Some(digest) => digest.clone(),
+                None => bail!("{} is not a file of the scope", path.display()),
+            };
+            files.push(proof::advisory::AdvisoryFile { path, digest });
+        }
+
+        let local = Local::auto_open()?;
+        let pub_id = local.read_current_locked_id()?.to_pubid();
+        let advisory = proof::advisory::AdvisoryBuilder::default()
+            .from(pub_id)
+            .project(project_id)
+            .severity(severity)
+            .affected(affected)
+            .files(files)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        let advisory = util::edit_proof_content_iteractively(&advisory.into())?;
+
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = advisory.sign_by(&id)?;
+        self.save_signed_review(&local, &proof, &id)
+    }
+
+    /// Verify the scope at revision `rev`, and gather the evidence of the
+    /// result, with a Verification Report signed by the current id
+    ///
+    /// `rev` has to be checked out, without any changes. The evidence
+    /// includes the trusted reviews and attestations of the current files
+    /// and of the whole package, and the Trust Proofs leading to their
+    /// authors.
+    pub fn collect_evidence(
+        &mut self,
+        rev: &str,
+        passphrase_callback: PassphraseFn,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<crate::evidence::Evidence> {
+        let vcs = self.vcs()?.ok_or_else(|| {
+            format_err!("{} is not under version control", self.root_dir.display())
+        })?;
+        let revision = vcs.revision()?;
+        let rev_id = vcs.resolve_revision(rev)?;
+        if rev_id != revision.revision {
+            bail!(
+                "{} is {}, but {} is checked out; check it out first",
+                rev,
+                rev_id,
+                revision.revision
+            );
+        }
+        let status = self.package_verify(false, trust_params)?;
+
+        let local = Local::auto_open()?;
+        let current_id = local
+            .get_current_userid()?
+            .ok_or_else(|| format_err!("Current Id not set"))?;
+        let (db, trusted_set) = self.load_scope_db(trust_params)?;
+        let digest = self.package_digest(false)?;
+        let ReliedOnProofs {
+            proofs: evidence_proofs,
+            trust_chains,
+            trust_proofs,
+        } = self.relied_on_proofs(&local, &db, &trusted_set, &current_id, trust_params)?;
+
+        let urls = trust_chains
+            .iter()
+            .flat_map(|(author, chains)| {
+                std::iter::once(author).chain(chains.iter().flatten().map(|link| &link.from))
+            })
+            .filter_map(|id| Some((id.clone(), db.lookup_url(id)?.url.clone())))
+            .collect();
+
+        let mut package = self.code_review_package_info(revision);
+        package.digest = digest.into_vec();
+        let report = crev_data::verification_report::VerificationReportBuilder::default()
+            .from(local.read_current_locked_id()?.to_pubid())
+            .package(package)
+            .status(status.to_string())
+            .proofs(
+                evidence_proofs
+                    .keys()
+                    .chain(trust_proofs.keys())
+                    .map(crev_common::base64_encode)
+                    .collect(),
+            )
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+
+        let config_path = self.package_config_path();
+        Ok(crate::evidence::Evidence {
+            report: report.sign_by(&id)?,
+            config: if config_path.exists() {
+                Some(crev_common::read_file_to_string(&config_path)?)
+            } else {
+                None
+            },
+            proofs: evidence_proofs
+                .into_iter()
+                .chain(trust_proofs)
+                .map(|(_, proof)| proof)
+                .collect(),
+            trust_chains,
+            urls,
+        })
+    }
+
+    /// The proofs `recipient` needs to verify the current tree of this
+    /// scope, and nothing else: the trusted reviews and attestations of it,
+    /// along with the Trust Proofs on the chains of trust to their authors
+    ///
+    /// The chains start from the recipient, when any of their Trust Proofs
+    /// is known here, and from the current id otherwise, for a recipient
+    /// trusting it. Proofs of the recipient are left out, as they have them
+    /// already.
+    pub fn proof_bundle(
+        &mut self,
+        recipient: &Id,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<Vec<proof::Proof>> {
+        let local = Local::auto_open()?;
+        let current_id = local
+            .get_current_userid()?
+            .ok_or_else(|| format_err!("Current Id not set"))?;
+        let (db, own_trusted_set) = self.load_scope_db(trust_params)?;
+        let recipient_trusted_set = db.calculate_trust_set(recipient, trust_params);
+        let (root, trusted_set) = if recipient_trusted_set
+            .trusted_ids()
+            .any(|id| id != recipient)
+        {
+            (recipient.clone(), recipient_trusted_set)
+        } else {
+            (current_id, own_trusted_set)
+        };
+
+        let relied_on = self.relied_on_proofs(&local, &db, &trusted_set, &root, trust_params)?;
+        let proofs: Vec<_> = relied_on
+            .proofs
+            .into_iter()
+            .chain(relied_on.trust_proofs)
+            .map(|(_, proof)| proof)
+            .filter(|proof| proof.content.author_id() != *recipient)
+            .collect();
+        if proofs.is_empty() {
+            bail!(
+                "No trusted reviews of {} to bundle",
+                self.root_dir.display()
+            );
+        }
+        Ok(proofs)
+    }
+
+    /// Trusted reviews and attestations of the current tree, and the Trust
+    /// Proofs making their authors trusted by `root`; see `ReliedOnProofs`
+    fn relied_on_proofs(
+        &mut self,
+        local: &Local,
+        db: &crate::ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+        root: &Id,
+        trust_params: &crate::TrustDistanceParams,
+    ) -> Result<ReliedOnProofs> {
+        let digest = self.package_digest(false)?;
+        // only what `db` counts: the most recent proof of each author, and
+        // nothing revoked, superseded or expired; proofs are told apart by
+        // their author and date
+        let counted_code_reviews: HashSet<(Id, DateTime<Utc>)> = self
+            .files_verify_in(db, trusted_set, None, None)?
+            .into_iter()
+            .flat_map(|file| file.reviews)
+            .map(|candidate| {
+                (
+                    candidate.review.from.id.clone(),
+                    proof::ContentCommon::date_utc(&candidate.review),
+                )
+            })
+            .collect();
+        let counted_package_reviews: HashSet<(Id, DateTime<Utc>)> = db
+            .get_full_package_reviews_by_digest(&digest, trusted_set)
+            .iter()
+            .map(|review| (review.from.id.clone(), proof::ContentCommon::date_utc(review)))
+            .collect();
+        let counted_attestations: HashSet<(Id, DateTime<Utc>)> = db
+            .get_build_attestations_by_package_digest(&digest)
+            .map(|attestation| {
+                (
+                    attestation.from.id.clone(),
+                    proof::ContentCommon::date_utc(attestation),
+                )
+            })
+            .collect();
+
+        let now = Utc::now();
+        let known_proofs: Vec<_> = local
+            .known_proofs_iter()?
+            .chain(crate::local::proofs_iter_for_path(
+                self.dot_crev_path().join("proofs"),
+            ))
+            .filter(|proof| {
+                !db.is_revoked(proof)
+                    && !db.is_superseded(proof)
+                    && !proof.content.is_expired_at(&now)
+            })
+            .collect();
+        let mut proofs = std::collections::BTreeMap::new();
+        for proof in &known_proofs {
+            let key = (proof.content.author_id(), proof.content.date().with_timezone(&Utc));
+            let counted = match proof.content {
+                proof::Content::Code(_) => counted_code_reviews.contains(&key),
+                proof::Content::Package(_) => counted_package_reviews.contains(&key),
+                proof::Content::BuildAttestation(_) => counted_attestations.contains(&key),
+                _ => false,
+            };
+            if counted
+                && trusted_set
+                    .get_effective_trust_level(&proof.content.author_id())
+                    .is_some()
+            {
+                proofs.insert(proof.digest.clone(), proof.clone());
+            }
+        }
+
+        let authors: std::collections::BTreeSet<_> = proofs
+            .values()
+            .map(|proof| proof.content.author_id())
+            .collect();
+        let trust_chains: Vec<_> = authors
+            .into_iter()
+            .map(|author| {
+                let chains = db.explain_trust(root, &author, trust_params);
+                (author, chains)
+            })
+            .collect();
+        let mut trust_proofs = std::collections::BTreeMap::new();
+        for link in trust_chains
+            .iter()
+            .flat_map(|(_, chains)| chains.iter().flatten())
+        {
+            for proof in &known_proofs {
+                if let proof::Content::Trust(ref trust) = proof.content {
+                    if trust.from.id == link.from
+                        && Some(trust.date.with_timezone(&Utc)) == link.date
+                        && trust.ids.iter().any(|id| id.id == link.to)
+                    {
+                        trust_proofs.insert(proof.digest.clone(), proof.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(ReliedOnProofs {
+            proofs,
+            trust_chains,
+            trust_proofs,
+        })
+    }
+
+    /// `path` relative to the root of this scope; it doesn't have to exist
+    fn rel_path_of(&self, path: &Path) -> Result<PathBuf> {
+        let path = std::env::current_dir()?.join(path);
+        let name = path
+            .file_name()
+            .ok_or_else(|| format_err!("Not a file path: {}", path.display()))?;
+        let parent = path
+            .parent()
+            .ok_or_else(|| format_err!("Not a file path: {}", path.display()))?
+            .canonicalize()?;
+        Ok(parent
+            .join(name)
+            .strip_prefix(&self.root_dir)
+            .map_err(|_| {
+                format_err!(
+                    "{} is outside of {}",
+                    path.display(),
+                    self.root_dir.display()
+                )
+            })?
+            .to_owned())
+    }
+
+    /// Summary of the git history of this scope since the last Code Review
+    /// by `author`, to pre-fill the comment of the next one
+    pub fn history_summary(&self, author: &crev_data::Id) -> Result<String> {
+        let git_repo = git2::Repository::discover(&self.root_dir)?;
+        let workdir = git_repo
+            .workdir()
+            .ok_or_else(|| format_err!("Git repository has no working directory"))?
+            .canonicalize()?;
+        let scope_prefix = self.root_dir.strip_prefix(&workdir)?;
+        let head = git_repo
+            .head()?
+            .resolve()?
+            .target()
+            .ok_or_else(|| format_err!("HEAD target does not resolve to oid"))?;
+        let since = self.last_reviewed_revision(&git_repo, head, author);
+        history::summarize(&git_repo, scope_prefix, head, since)
+    }
+
+    /// Name of the package of Code Reviews of this scope, with no source
+    fn package_name(&self) -> String {
+        self.root_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// `PackageInfo` of a Code Review of this scope at `revision`
+    fn code_review_package_info(&self, revision: proof::Revision) -> proof::PackageInfo {
+        proof::PackageInfo {
+            id: None,
+            source: "".into(),
+            name: self.package_name(),
+            version: "".into(),
+            revision: revision.revision,
+            revision_type: revision.revision_type,
+            digest: vec![],
+            digest_type: proof::default_digest_type(),
+        }
+    }
+
+    /// Create a Code Review Proof of the staged files
+    ///
+    /// With `summarize`, the comment is pre-filled with `history_summary`.
+    /// With `grouped`, the files are grouped by directory, each group with
+    /// a comment of its own. It's signed by the current id, unless `as_id`
+    /// names another own one, or is one; see `Local::own_id_from_str`.
+    pub fn commit(
+        &mut self,
+        passphrase_callback: PassphraseFn,
+        allow_dirty: bool,
+        summarize: bool,
+        grouped: bool,
+        as_id: Option<&str>,
+    ) -> Result<()> {
+        let local = Local::auto_open()?;
+        let id = match as_id {
+            Some(name_or_id) => local.own_id_from_str(name_or_id)?,
+            None => local.read_current_id()?,
+        };
+        let review = self.staged_code_review(&local, &id, allow_dirty, summarize, grouped, None)?;
+
+        let draft_path = self.staging_draft_path();
+        let review = util::edit_proof_content_iteractively_in(&review.into(), &draft_path)?;
+
+        // unlock only for signing, to keep the secret key in memory for as short as possible
+        let id = local.read_unlocked_id(&id, passphrase_callback)?;
         let proof = review.sign_by(&id)?;
 
-        self.save_signed_review(&local, &proof)?;
+        self.save_signed_review(&local, &proof, &id)?;
+        self.staging()?.wipe()?;
+        fs::remove_file(&draft_path)?;
+        if let proof::Content::Code(ref code) = proof.content {
+            if let Some(ref size) = code.size {
+                eprintln!(
+                    "Reviewed {} at {} thoroughness",
+                    size,
+                    proof::review::Common::review(code).thoroughness
+                );
+            }
+        }
         Ok(())
     }
 
-    pub fn commit(&mut self, passphrase_callback: PassphraseFn, allow_dirty: bool) -> Result<()> {
+    /// Like `commit`, but only return the body that would be signed
+    ///
+    /// Nothing is signed, stored or edited, and the staging area stays as
+    /// it is. With a `date`, the body is dated then instead of now, so the
+    /// same staged files give the same body.
+    pub fn commit_dry_run(
+        &mut self,
+        allow_dirty: bool,
+        summarize: bool,
+        grouped: bool,
+        date: Option<chrono::DateTime<FixedOffset>>,
+    ) -> Result<String> {
+        let local = Local::auto_open()?;
+        let id = local.read_current_id()?;
+        let review = self.staged_code_review(&local, &id, allow_dirty, summarize, grouped, date)?;
+        Ok(proof::Content::from(review).canonical_body())
+    }
+
+    /// Code Review of the staged files by `id`, before editing, dated
+    /// `date` or now
+    fn staged_code_review(
+        &mut self,
+        local: &Local,
+        id: &Id,
+        allow_dirty: bool,
+        summarize: bool,
+        grouped: bool,
+        date: Option<chrono::DateTime<FixedOffset>>,
+    ) -> Result<proof::review::Code> {
         if self.staging()?.is_empty() && !allow_dirty {
             bail!("No reviews to commit. Use `add` first or use `-a` for the whole package.");
         }
 
-        let local = Local::auto_open()?;
-        let _revision = self.read_revision()?;
+        let revision = self.read_revision()?;
         self.staging()?.enforce_current()?;
         let files = self.staging()?.to_review_files();
-        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let metrics = self.staging()?.to_review_metrics();
+        let size = self.staging()?.to_review_size();
+        let groups = if grouped {
+            self.staging()?.to_review_groups()
+        } else {
+            vec![]
+        };
+        let pub_id = local.read_locked_id(id)?.to_pubid();
+        let comment = if summarize {
+            self.history_summary(&pub_id.id)?
+        } else {
+            "".into()
+        };
 
-        let review = proof::review::CodeBuilder::default()
-            .from(id.id.to_owned())
+        let mut builder = proof::review::CodeBuilder::default();
+        if let Some(date) = date {
+            builder.date(date);
+        }
+        let review = builder
+            .from(pub_id)
+            .package(self.code_review_package_info(revision))
+            .comment(comment)
             .files(files)
+            .groups(groups)
+            .metrics(metrics)
+            .size(size)
             .build()
             .map_err(|e| format_err!("{}", e))?;
+        Ok(review)
+    }
 
-        let review = util::edit_proof_content_iteractively(&review.into())?;
+    /// Create a Project Proof binding `name` and `url` to the package
+    ///
+    /// Only the trust root of the package can do it. The resulting project
+    /// id is saved in the package config and returned.
+    pub fn identify_project(
+        &mut self,
+        name: String,
+        url: &str,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<String> {
+        let mut config = self.load_package_config()?;
+        let local = Local::auto_open()?;
+        let pub_id = Self::read_trust_root_pub_id(&local, &config)?;
 
-        let proof = review.sign_by(&id)?;
+        let project = proof::project::ProjectBuilder::default()
+            .from(pub_id)
+            .name(name)
+            .url(proof::project::canonical_url(url)?)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+        let project_id = project.project_id();
 
-        self.save_signed_review(&local, &proof)?;
-        self.staging()?.wipe()?;
+        let project = util::edit_proof_content_iteractively(&project.into())?;
+
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = project.sign_by(&id)?;
+
+        self.save_signed_review(&local, &proof, &id)?;
+        config.project_id = Some(project_id.clone());
+        self.store_package_config(&config)?;
+        Ok(project_id)
+    }
+
+    /// Create a Project Migration Proof moving the project to `url`
+    ///
+    /// Along with it, a Project Proof for the new URL is created, under
+    /// `name` or the current name of the project. The new project id is
+    /// saved in the package config and returned.
+    pub fn migrate_project(
+        &mut self,
+        url: &str,
+        name: Option<String>,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<String> {
+        let mut config = self.load_package_config()?;
+        let old_project_id = config.project_id.clone().ok_or_else(|| {
+            format_err!("Project identity not set. Use `crev package identify` to create it.")
+        })?;
+        let local = Local::auto_open()?;
+        let pub_id = Self::read_trust_root_pub_id(&local, &config)?;
+
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let (db, _trust_set) = local.load_db(&Default::default())?;
+                match db.get_project_identity(&old_project_id) {
+                    crate::ProjectIdentity::Unique(project) => project.name.clone(),
+                    _ => bail!("Current name of the project not known. Use `--name`."),
+                }
+            }
+        };
+
+        let url = proof::project::canonical_url(url)?;
+        let migration = proof::project_migration::ProjectMigrationBuilder::default()
+            .from(pub_id.clone())
+            .project(old_project_id)
+            .url(url.clone())
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+        let project = proof::project::ProjectBuilder::default()
+            .from(pub_id)
+            .name(name)
+            .url(url)
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+        let project_id = project.project_id();
+
+        let migration = util::edit_proof_content_iteractively(&migration.into())?;
+
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let migration = migration.sign_by(&id)?;
+        let project = project.sign_by(&id)?;
+
+        self.save_signed_review(&local, &migration, &id)?;
+        self.save_signed_review(&local, &project, &id)?;
+        config.project_id = Some(project_id.clone());
+        self.store_package_config(&config)?;
+        Ok(project_id)
+    }
+
+    /// Create a Trust Root Handover Proof making `new_root` the trust root
+    /// of the package
+    ///
+    /// Only the current trust root can do it. The URL of `new_root` has to
+    /// be known already. The package config is updated to the new trust root.
+    pub fn hand_over_trust_root(
+        &mut self,
+        new_root: &str,
+        passphrase_callback: PassphraseFn,
+    ) -> Result<()> {
+        let mut config = self.load_package_config()?;
+        let local = Local::auto_open()?;
+        let pub_id = Self::read_trust_root_pub_id(&local, &config)?;
+
+        let new_root_id = Id::crevid_from_str(new_root)?;
+        let (db, _trust_set) = local.load_db(&Default::default())?;
+        let url = db.lookup_url(&new_root_id).cloned().ok_or_else(|| {
+            format_err!(
+                "URL not found for Id {}; Fetch its proofs or import it first",
+                new_root
+            )
+        })?;
+
+        let handover = proof::trust_root_handover::TrustRootHandoverBuilder::default()
+            .from(pub_id)
+            .to(crev_data::PubId::new(new_root_id.clone(), url))
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+
+        let handover = util::edit_proof_content_iteractively(&handover.into())?;
+
+        let id = local.read_current_unlocked_id(passphrase_callback)?;
+        let proof = handover.sign_by(&id)?;
+
+        self.save_signed_review(&local, &proof, &id)?;
+        config.trust_root = new_root_id.to_string();
+        self.store_package_config(&config)?;
         Ok(())
     }
 
-    fn save_signed_review(&mut self, local: &Local, proof: &proof::Proof) -> Result<()> {
+    fn read_trust_root_pub_id(local: &Local, config: &PackageConfig) -> Result<crev_data::PubId> {
+        let pub_id = local.read_current_locked_id()?.to_pubid();
+        if pub_id.id.to_string() != config.trust_root {
+            bail!(
+                "Only the trust root of the package ({}) can speak for the project",
+                config.trust_root
+            );
+        }
+        Ok(pub_id)
+    }
+
+    fn save_signed_review(
+        &mut self,
+        local: &Local,
+        proof: &proof::Proof,
+        own_id: &OwnId,
+    ) -> Result<()> {
         let rel_store_path = self.get_proof_rel_store_path(&proof);
 
         println!("{}", proof);
@@ -287,7 +2566,7 @@ impl Repo {
             "Proof written to: {}",
             PathBuf::from(".crev").join(rel_store_path).display()
         );
-        local.insert(proof)?;
+        local.insert_own(proof, own_id)?;
         eprintln!("Proof added to your store");
 
         Ok(())
@@ -303,15 +2582,33 @@ impl Repo {
     }
 
     pub fn add(&mut self, file_paths: Vec<PathBuf>) -> Result<()> {
+        let (normalizations, kinds) = self
+            .try_load_package_config()?
+            .map(|config| (config.normalizations, config.file_kinds))
+            .unwrap_or_default();
         let staging = self.staging()?;
         for path in file_paths {
-            staging.insert(&path)?;
+            staging.insert(&path, &normalizations, &kinds)?;
         }
         staging.save()?;
 
         Ok(())
     }
 
+    /// Merge the staging file at `path`, eg. from another checkout, into
+    /// the staging of this scope; see `staging::Staging::merge`
+    pub fn merge_staging(
+        &mut self,
+        path: &Path,
+        resolve: staging::ConflictResolveFn<'_>,
+    ) -> Result<staging::MergeSummary> {
+        let theirs = staging::Staging::read_entries(path)?;
+        let staging = self.staging()?;
+        let summary = staging.merge(theirs, resolve)?;
+        staging.save()?;
+        Ok(summary)
+    }
+
     pub fn remove(&mut self, file_paths: Vec<PathBuf>) -> Result<()> {
         let staging = self.staging()?;
         for path in file_paths {
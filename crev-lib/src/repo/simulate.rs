@@ -0,0 +1,141 @@
+//! What-if analysis of the web of trust, for `crev trust simulate`
+//!
+//! The files of a scope are verified as they would be with more trust
+//! from the current id, without creating any Trust Proofs: the trust is
+//! only added to the loaded `ProofDB`, the way the `trust-overrides` of
+//! the package config are.
+use super::{FileVerification, Repo};
+use crate::{local::Local, prelude::*, ProofDB, TrustDistanceParams, VerificationStatus};
+use crev_data::{proof::trust::TrustLevel, Id};
+use std::{collections::BTreeSet, path::PathBuf};
+
+/// A file whose status would change
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub path: PathBuf,
+    pub before: VerificationStatus,
+    pub after: VerificationStatus,
+}
+
+/// An id not trusted now, and the files trusting it would verify
+#[derive(Debug, Clone)]
+pub struct TrustCandidate {
+    pub id: Id,
+    pub verified: Vec<PathBuf>,
+}
+
+impl Repo {
+    /// How the statuses of the files would change if the current id
+    /// trusted each of `ids` at `level`
+    pub fn simulate_trust(
+        &mut self,
+        ids: &[Id],
+        level: TrustLevel,
+        trust_params: &TrustDistanceParams,
+    ) -> Result<Vec<StatusChange>> {
+        let current_id = Self::simulating_id()?;
+        let (db, trusted_set) = self.load_scope_db(trust_params)?;
+        let before = self.simulated_files_verify(&db, &trusted_set)?;
+        let after = self.files_verify_trusting(db, &current_id, ids, level, trust_params)?;
+        Ok(Self::status_changes(&before, after))
+    }
+
+    /// The reviewers of files not verified now that aren't trusted, each
+    /// with the files trusting only them at `level` would verify, the most
+    /// files first
+    ///
+    /// Ids that wouldn't verify any file are left out.
+    pub fn trust_candidates(
+        &mut self,
+        level: TrustLevel,
+        trust_params: &TrustDistanceParams,
+    ) -> Result<Vec<TrustCandidate>> {
+        let current_id = Self::simulating_id()?;
+        let (db, trusted_set) = self.load_scope_db(trust_params)?;
+        let before = self.simulated_files_verify(&db, &trusted_set)?;
+        let mut ids: BTreeSet<Id> = before
+            .iter()
+            .filter(|file| !file.status.is_verified())
+            .flat_map(|file| &file.reviews)
+            .filter(|candidate| candidate.reviewer_trust.is_none())
+            .map(|candidate| candidate.review.from.id.clone())
+            .collect();
+        let package_digest = self.package_digest(true)?;
+        ids.extend(
+            db.get_package_reviews_by_digest(&package_digest)
+                .map(|review| review.from.id)
+                .filter(|id| trusted_set.get_effective_trust_level(id).is_none()),
+        );
+        ids.remove(&current_id);
+
+        let mut candidates = vec![];
+        for id in ids {
+            let after = self.files_verify_trusting(
+                db.clone(),
+                &current_id,
+                std::slice::from_ref(&id),
+                level,
+                trust_params,
+            )?;
+            let verified: Vec<_> = Self::status_changes(&before, after)
+                .into_iter()
+                .filter(|change| change.after.is_verified() && !change.before.is_verified())
+                .map(|change| change.path)
+                .collect();
+            if !verified.is_empty() {
+                candidates.push(TrustCandidate { id, verified });
+            }
+        }
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.verified.len()));
+        Ok(candidates)
+    }
+
+    fn simulating_id() -> Result<Id> {
+        Local::auto_open()?
+            .get_current_userid()?
+            .ok_or_else(|| format_err!("Current Id not set"))
+    }
+
+    fn files_verify_trusting(
+        &mut self,
+        mut db: ProofDB,
+        current_id: &Id,
+        ids: &[Id],
+        level: TrustLevel,
+        trust_params: &TrustDistanceParams,
+    ) -> Result<Vec<FileVerification>> {
+        for id in ids {
+            db.override_trust(current_id, id, level);
+        }
+        let trusted_set = db.calculate_trust_set(current_id, trust_params);
+        self.simulated_files_verify(&db, &trusted_set)
+    }
+
+    /// Verify all the files, leaving the caches as they are
+    fn simulated_files_verify(
+        &mut self,
+        db: &ProofDB,
+        trusted_set: &crate::proofdb::TrustSet,
+    ) -> Result<Vec<FileVerification>> {
+        // every path starts with the empty one; with `only_paths` set, the
+        // status cache isn't updated with the simulated statuses
+        self.files_verify_in(db, trusted_set, None, Some(&[PathBuf::new()]))
+    }
+
+    fn status_changes(
+        before: &[FileVerification],
+        after: Vec<FileVerification>,
+    ) -> Vec<StatusChange> {
+        // both are sorted by path, and of the same files
+        before
+            .iter()
+            .zip(after)
+            .filter(|(before, after)| before.status != after.status)
+            .map(|(before, after)| StatusChange {
+                path: after.path,
+                before: before.status.clone(),
+                after: after.status,
+            })
+            .collect()
+    }
+}
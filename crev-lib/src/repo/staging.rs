@@ -1,9 +1,13 @@
+use crate::file_kind::{self, FileKindRule};
+use crate::normalize::{self, Normalizations};
 use common_failures::prelude::*;
-use crev_common;
-use crev_data::proof;
+use crev_data::{
+    proof,
+    proof::review::code::{FileKind, Normalization},
+};
 use serde_cbor;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fs,
     io::Write,
     path::{Path, PathBuf},
@@ -12,6 +16,60 @@ use std::{
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StagingPathInfo {
     blake_hash: Vec<u8>,
+    /// See `rust_metrics::file_metrics`
+    #[serde(default)]
+    metrics: Option<proof::review::RustMetrics>,
+    /// See `size_metrics::count_lines`
+    #[serde(default)]
+    lines: u64,
+    /// See `rust_anchors::file_anchors`
+    #[serde(default)]
+    anchors: Vec<proof::review::code::Anchor>,
+    /// Applied before digesting the file; see `normalize`
+    #[serde(default)]
+    normalization: Vec<Normalization>,
+    /// See `file_kind`
+    #[serde(default)]
+    kind: Option<FileKind>,
+}
+
+impl StagingPathInfo {
+    /// Digest of the file when it was staged
+    pub fn digest(&self) -> &[u8] {
+        &self.blake_hash
+    }
+}
+
+/// Which side of a conflict to keep; see `Staging::merge`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeChoice {
+    Ours,
+    Theirs,
+    /// Unstage the path; it has to be added again
+    Drop,
+}
+
+/// A path staged with different digests on both sides of a merge
+pub struct MergeConflict<'a> {
+    pub path: &'a Path,
+    pub ours: &'a StagingPathInfo,
+    pub theirs: &'a StagingPathInfo,
+    /// The side matching the current content of the file, if any
+    pub current: Option<MergeChoice>,
+}
+
+/// Callback resolving a `MergeConflict`
+pub type ConflictResolveFn<'a> = &'a dyn Fn(&MergeConflict<'_>) -> Result<MergeChoice>;
+
+/// What `Staging::merge` did
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Paths staged only on their side
+    pub added: usize,
+    /// Paths staged with the same digest on both sides
+    pub same: usize,
+    /// Paths staged with different digests, resolved with a callback
+    pub conflicts: usize,
 }
 
 pub struct Staging {
@@ -46,17 +104,80 @@ impl Staging {
             });
         }
 
-        let file = fs::File::open(&path)?;
-
-        let path_info: HashMap<PathBuf, StagingPathInfo> = serde_cbor::from_reader(&file)?;
-
         Ok(Self {
             root_path: repo_path.to_owned(),
+            entries: Self::read_entries(&path)?,
             file_path: path,
-            entries: path_info,
         })
     }
 
+    /// Read the entries of a staging file, eg. one from another checkout
+    pub fn read_entries(path: &Path) -> Result<HashMap<PathBuf, StagingPathInfo>> {
+        let file = fs::File::open(path)?;
+        Ok(serde_cbor::from_reader(&file)?)
+    }
+
+    /// Merge `theirs` entries, from another staging file, into these
+    ///
+    /// Entries are reconciled by digest: paths staged on one side only, or
+    /// with the same digest on both, are kept. For a path staged with
+    /// different digests, `resolve` picks a side.
+    pub fn merge(
+        &mut self,
+        theirs: HashMap<PathBuf, StagingPathInfo>,
+        resolve: ConflictResolveFn<'_>,
+    ) -> Result<MergeSummary> {
+        let mut summary = MergeSummary::default();
+        let mut theirs: Vec<_> = theirs.into_iter().collect();
+        theirs.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, their_info) in theirs {
+            let choice = match self.entries.get(&path) {
+                None => {
+                    summary.added += 1;
+                    MergeChoice::Theirs
+                }
+                Some(our_info) if our_info.blake_hash == their_info.blake_hash => {
+                    summary.same += 1;
+                    MergeChoice::Ours
+                }
+                Some(our_info) => {
+                    summary.conflicts += 1;
+                    let full_path = self.root_path.join(&path);
+                    let current = if full_path.is_file() {
+                        let digest = |info: &StagingPathInfo| {
+                            normalize::file_digest(&full_path, &info.normalization)
+                        };
+                        if digest(our_info)? == our_info.blake_hash {
+                            Some(MergeChoice::Ours)
+                        } else if digest(&their_info)? == their_info.blake_hash {
+                            Some(MergeChoice::Theirs)
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    };
+                    resolve(&MergeConflict {
+                        path: &path,
+                        ours: our_info,
+                        theirs: &their_info,
+                        current,
+                    })?
+                }
+            };
+            match choice {
+                MergeChoice::Ours => {}
+                MergeChoice::Theirs => {
+                    self.entries.insert(path, their_info);
+                }
+                MergeChoice::Drop => {
+                    self.entries.remove(&path);
+                }
+            }
+        }
+        Ok(summary)
+    }
+
     fn write_to_file(&self, path: &Path) -> Result<()> {
         let tmp_path = path.with_extension("tmp");
         let mut file = fs::File::create(&tmp_path)?;
@@ -67,15 +188,34 @@ impl Staging {
         Ok(())
     }
 
-    pub fn insert(&mut self, path: &Path) -> Result<()> {
+    /// Stage the file at `path`, digested after its `normalizations`, and
+    /// of the kind `kinds` tell
+    pub fn insert(
+        &mut self,
+        path: &Path,
+        normalizations: &Normalizations,
+        kinds: &[FileKindRule],
+    ) -> Result<()> {
         let full_path = path.canonicalize()?;
 
         let path = full_path.strip_prefix(&self.root_path)?.to_owned();
+        if super::is_in_nested_scope(&self.root_path, &path) {
+            bail!(
+                "{} belongs to a nested `.crev` scope; review it from there",
+                path.display()
+            );
+        }
         println!("Adding {}", path.display());
+        let normalization = normalize::for_path(normalizations, &path).to_owned();
         self.entries.insert(
             path.to_owned(),
             StagingPathInfo {
-                blake_hash: crev_common::blake2b256sum_file(&full_path)?,
+                blake_hash: normalize::file_digest(&full_path, &normalization)?,
+                metrics: crate::rust_metrics::file_metrics(&path, &full_path)?,
+                lines: crate::size_metrics::count_lines(&full_path)?,
+                anchors: crate::rust_anchors::file_anchors(&path, &full_path)?,
+                normalization,
+                kind: Some(file_kind::for_path(kinds, &path)?),
             },
         );
 
@@ -100,14 +240,57 @@ impl Staging {
                 path: k.to_owned(),
                 digest: v.blake_hash.clone(),
                 digest_type: "blake2b".into(),
+                anchors: v.anchors.clone(),
+                normalization: v.normalization.clone(),
+                kind: v.kind,
+            })
+            .collect()
+    }
+
+    /// One group per directory of the staged files, to comment on each
+    /// separately
+    pub fn to_review_groups(&self) -> Vec<proof::review::code::Group> {
+        let dirs: BTreeSet<_> = self
+            .entries
+            .keys()
+            .map(|path| path.parent().unwrap_or_else(|| Path::new("")))
+            .collect();
+        dirs.into_iter()
+            .map(|dir| proof::review::code::Group {
+                path: dir.to_owned(),
+                comment: "".into(),
             })
             .collect()
     }
 
+    /// Combined metrics of the staged Rust files, if any
+    pub fn to_review_metrics(&self) -> Option<proof::review::RustMetrics> {
+        self.entries
+            .values()
+            .filter_map(|info| info.metrics.as_ref())
+            .fold(None, |total, metrics| {
+                let mut total = total.unwrap_or_default();
+                total.add(metrics);
+                Some(total)
+            })
+    }
+
+    /// Size of the staged files
+    pub fn to_review_size(&self) -> Option<proof::review::SizeMetrics> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let mut size = proof::review::SizeMetrics::default();
+        for (path, info) in &self.entries {
+            size.add_file(crate::size_metrics::language_for_path(path), info.lines);
+        }
+        Some(size)
+    }
+
     pub fn enforce_current(&self) -> Result<()> {
         for (rel_path, info) in self.entries.iter() {
             let path = self.root_path.join(rel_path);
-            if crev_common::blake2b256sum_file(&path)? != info.blake_hash {
+            if normalize::file_digest(&path, &info.normalization)? != info.blake_hash {
                 bail!(
                     "File {} not current. Review again use `crev add` to
                       update.",
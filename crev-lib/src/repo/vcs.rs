@@ -0,0 +1,176 @@
+//! Version control systems the scopes are kept in
+//!
+//! `Repo` goes through the `Vcs` trait for revisions and changes of the
+//! files of a scope, and `open` picks the implementation for a directory.
+//! Paths are relative to the scope, which doesn't have to be the root of
+//! the VCS repository. Git is the only implementation so far.
+use super::history;
+use crate::prelude::*;
+use crev_data::proof;
+use git2;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// A version control system, as seen from a scope kept in it
+pub trait Vcs {
+    /// Revision checked out
+    fn revision(&self) -> Result<proof::Revision>;
+
+    /// Revision `name` refers to, eg. a tag or a branch
+    fn resolve_revision(&self, name: &str) -> Result<String>;
+
+    /// Are there changes to the files of the scope not committed yet
+    fn is_dirty(&self) -> Result<bool>;
+
+    /// Content of the file at `path` as of `revision`, if it existed then
+    fn file_at_revision(&self, revision: &str, path: &Path) -> Result<Option<Vec<u8>>>;
+
+    /// Files of the scope changed from revision `from` to `to`, including
+    /// the ones added and removed
+    fn changed_files(&self, from: &str, to: &str) -> Result<Vec<PathBuf>>;
+
+    /// Is `revision` the same as `ancestor`, or descending from it
+    ///
+    /// `None` if either of them is unknown, eg. not fetched yet.
+    fn descends_from(&self, revision: &str, ancestor: &str) -> Result<Option<bool>>;
+
+    /// Emails of the authors of the changes to each file of the scope, up
+    /// to the revision checked out
+    fn file_authors(&self) -> Result<HashMap<PathBuf, BTreeSet<String>>>;
+
+    /// Dates of the changes to the files of the scope up to the revision
+    /// checked out, newest first, each with the files it changed
+    fn changes(&self) -> Result<Vec<(chrono::DateTime<chrono::Utc>, Vec<PathBuf>)>>;
+}
+
+/// The VCS `scope_dir` is kept in, if any
+pub fn open(scope_dir: &Path) -> Result<Option<Box<dyn Vcs>>> {
+    Ok(match Git::discover(scope_dir)? {
+        Some(git) => Some(Box::new(git)),
+        None => None,
+    })
+}
+
+/// A scope in a git repository
+pub struct Git {
+    repo: git2::Repository,
+    scope_dir: PathBuf,
+    /// Path of the scope, relative to the working directory
+    scope_prefix: PathBuf,
+}
+
+impl Git {
+    /// Open the git repository `scope_dir` is in, if it's in one
+    pub fn discover(scope_dir: &Path) -> Result<Option<Self>> {
+        let repo = match git2::Repository::discover(scope_dir) {
+            Ok(repo) => repo,
+            Err(ref e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| format_err!("Git repository has no working directory"))?
+            .canonicalize()?;
+        let scope_prefix = scope_dir.canonicalize()?.strip_prefix(&workdir)?.to_owned();
+        Ok(Some(Git {
+            repo,
+            scope_dir: scope_dir.to_owned(),
+            scope_prefix,
+        }))
+    }
+
+    fn tree_at(&self, revision: &str) -> Result<git2::Tree<'_>> {
+        Ok(self
+            .repo
+            .find_commit(git2::Oid::from_str(revision)?)?
+            .tree()?)
+    }
+}
+
+impl Vcs for Git {
+    fn revision(&self) -> Result<proof::Revision> {
+        let rev = self
+            .repo
+            .head()?
+            .resolve()?
+            .target()
+            .ok_or_else(|| format_err!("HEAD target does not resolve to oid"))?
+            .to_string();
+        Ok(proof::Revision {
+            revision_type: "git".into(),
+            revision: rev,
+        })
+    }
+
+    fn resolve_revision(&self, name: &str) -> Result<String> {
+        Ok(self
+            .repo
+            .revparse_single(name)?
+            .peel_to_commit()?
+            .id()
+            .to_string())
+    }
+
+    fn is_dirty(&self) -> Result<bool> {
+        if self.repo.state() != git2::RepositoryState::Clean {
+            bail!("Git repository is not in a clean state");
+        }
+        Ok(crate::git_scope_statuses(&self.scope_dir)?
+            .iter()
+            .any(|(_path, status)| *status != git2::Status::CURRENT))
+    }
+
+    fn file_at_revision(&self, revision: &str, path: &Path) -> Result<Option<Vec<u8>>> {
+        let tree = self.tree_at(revision)?;
+        let entry = match tree.get_path(&self.scope_prefix.join(path)) {
+            Ok(entry) => entry,
+            Err(ref e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let blob = entry.to_object(&self.repo)?.peel_to_blob()?;
+        Ok(Some(blob.content().to_owned()))
+    }
+
+    fn changed_files(&self, from: &str, to: &str) -> Result<Vec<PathBuf>> {
+        let diff = self.repo.diff_tree_to_tree(
+            Some(&self.tree_at(from)?),
+            Some(&self.tree_at(to)?),
+            None,
+        )?;
+        let mut paths: Vec<_> = history::scope_deltas(&diff, &self.scope_prefix)
+            .map(|(_delta, path)| path)
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn descends_from(&self, revision: &str, ancestor: &str) -> Result<Option<bool>> {
+        let (revision, ancestor) = match (
+            git2::Oid::from_str(revision).and_then(|oid| self.repo.find_commit(oid)),
+            git2::Oid::from_str(ancestor).and_then(|oid| self.repo.find_commit(oid)),
+        ) {
+            (Ok(revision), Ok(ancestor)) => (revision.id(), ancestor.id()),
+            _ => return Ok(None),
+        };
+        Ok(Some(
+            revision == ancestor || self.repo.graph_descendant_of(revision, ancestor)?,
+        ))
+    }
+
+    fn file_authors(&self) -> Result<HashMap<PathBuf, BTreeSet<String>>> {
+        let head = git2::Oid::from_str(&self.revision()?.revision)?;
+        history::authors_by_file(&self.repo, &self.scope_prefix, head)
+    }
+
+    fn changes(&self) -> Result<Vec<(chrono::DateTime<chrono::Utc>, Vec<PathBuf>)>> {
+        let head = git2::Oid::from_str(&self.revision()?.revision)?;
+        Ok(
+            history::scope_commits(&self.repo, &self.scope_prefix, head)?
+                .into_iter()
+                .map(|commit| (commit.date, commit.paths))
+                .collect(),
+        )
+    }
+}
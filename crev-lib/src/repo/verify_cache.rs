@@ -0,0 +1,98 @@
+//! Verification of each file of a scope, cached between runs
+//!
+//! The cache is keyed by everything else the verification depends on:
+//! the proofs and the trust set it was done with, and the package configs
+//! (see `Repo::load_scope_db`). While the key stays the same, a file with
+//! the same digest and rename as before keeps its verification, so only
+//! the files changed since are normalized, parsed and checked again.
+//!
+//! Like the merkle tree, it's kept in the cache directory of the user.
+use super::Rename;
+use crate::VerificationStatus;
+use crev_data::proof::review::code::Normalization;
+use common_failures::prelude::*;
+use serde_cbor;
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const VERIFY_CACHE_FILE_NAME: &str = "verify-cache";
+
+/// What verifying a file found, besides its reviews
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedFile {
+    /// Digest of the file, as in `MerkleTree::file_digests`
+    pub raw_digest: Vec<u8>,
+    /// Digest the file was verified by, eg. after its normalizations
+    pub digest: Vec<u8>,
+    /// Normalizations `digest` is after; see `ProofDB::get_code_reviews_by_file_digest`
+    pub normalization: Vec<Normalization>,
+    pub status: VerificationStatus,
+    pub rename: Option<Rename>,
+    /// Verification of the content from before `rename`, if known
+    pub previous_status: Option<VerificationStatus>,
+}
+
+/// Cached verification of the files of a scope; see the module docs
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct VerifyCache {
+    key: Vec<u8>,
+    files: HashMap<PathBuf, CachedFile>,
+}
+
+impl VerifyCache {
+    fn file_path(cache_path: &Path) -> PathBuf {
+        cache_path.join(VERIFY_CACHE_FILE_NAME)
+    }
+
+    /// Load the cache in `cache_path`, if it has the same `key`
+    ///
+    /// Otherwise, or if it can't be read, the cache starts empty.
+    pub fn load(cache_path: &Path, key: &[u8]) -> VerifyCache {
+        fs::File::open(Self::file_path(cache_path))
+            .ok()
+            .and_then(|file| serde_cbor::from_reader::<VerifyCache, _>(&file).ok())
+            .filter(|cache| cache.key == key)
+            .unwrap_or_else(|| VerifyCache {
+                key: key.to_owned(),
+                files: HashMap::new(),
+            })
+    }
+
+    pub fn save(&self, cache_path: &Path) -> Result<()> {
+        let path = Self::file_path(cache_path);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        serde_cbor::to_writer(&mut file, self)?;
+        file.flush()?;
+        drop(file);
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Verification of the file at `path`, if it's still of `raw_digest`
+    /// and `rename`
+    pub fn get(
+        &self,
+        path: &Path,
+        raw_digest: &[u8],
+        rename: Option<&Rename>,
+    ) -> Option<&CachedFile> {
+        self.files
+            .get(path)
+            .filter(|cached| cached.raw_digest == raw_digest && cached.rename.as_ref() == rename)
+    }
+
+    /// Replace the files with `files`, dropping the ones no longer there
+    pub fn set_files(&mut self, files: HashMap<PathBuf, CachedFile>) {
+        self.files = files;
+    }
+
+    /// Replace the files in `files`, keeping the others
+    pub fn update_files(&mut self, files: HashMap<PathBuf, CachedFile>) {
+        self.files.extend(files);
+    }
+}
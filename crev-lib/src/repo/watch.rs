@@ -0,0 +1,153 @@
+//! Watching the files of a scope for changes, for `crev verify --watch`
+//! and `crev editor-server`
+//!
+//! Changes come from the notifications of the file system, through
+//! `notify`, gathered for a `DELAY` so that a burst of writes to a file,
+//! eg. by an editor saving it, is reported once.
+use crate::prelude::*;
+use notify::{self, DebouncedEvent, RecursiveMode, Watcher as _};
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    time::{Duration, Instant},
+};
+
+/// How long a changed path stays quiet before it's reported
+pub const DELAY: Duration = Duration::from_millis(200);
+
+pub struct Watcher {
+    root_dir: PathBuf,
+    dirs: Vec<PathBuf>,
+    events: Receiver<DebouncedEvent>,
+    // notifications stop once it's dropped
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl Watcher {
+    /// Start watching the files under `root_dir`
+    pub fn new(root_dir: &Path) -> Result<Self> {
+        Self::with_dirs(root_dir, vec![])
+    }
+
+    /// Start watching the files under `root_dir`, and the ones in `dirs`
+    /// elsewhere, eg. proof repositories
+    ///
+    /// Only the `dirs` existing already are watched.
+    pub fn with_dirs(root_dir: &Path, dirs: Vec<PathBuf>) -> Result<Self> {
+        // notifications name the paths the way the file system knows them
+        let root_dir = root_dir.canonicalize()?;
+        let dirs = dirs
+            .iter()
+            .filter(|dir| dir.exists())
+            .map(|dir| dir.canonicalize())
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::watcher(sender, DELAY)?;
+        watcher.watch(&root_dir, RecursiveMode::Recursive)?;
+        for dir in &dirs {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+        }
+        Ok(Watcher {
+            root_dir,
+            dirs,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Paths of files added, modified or removed since the last call;
+    /// relative to the scope, or absolute for the files of other dirs
+    ///
+    /// `.git` is skipped, and so are the files of `.crev` local to a
+    /// checkout, which change on their own while verifying; the config
+    /// and proofs in it are watched like any other file. If notifications
+    /// were lost, the scope itself is reported, as an empty path.
+    pub fn poll(&mut self) -> Result<Vec<PathBuf>> {
+        let mut changed = BTreeSet::new();
+        loop {
+            match self.events.try_recv() {
+                Ok(event) => self.record(event, &mut changed)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => bail!("File system watcher stopped"),
+            }
+        }
+        Ok(changed.into_iter().collect())
+    }
+
+    /// Like `poll`, but waiting up to `timeout` for some changes first;
+    /// none came in time if no paths are returned
+    pub fn wait(&mut self, timeout: Duration) -> Result<Vec<PathBuf>> {
+        let deadline = Instant::now() + timeout;
+        let mut changed = BTreeSet::new();
+        while changed.is_empty() {
+            let now = Instant::now();
+            if deadline <= now {
+                break;
+            }
+            match self.events.recv_timeout(deadline - now) {
+                Ok(event) => self.record(event, &mut changed)?,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => bail!("File system watcher stopped"),
+            }
+            changed.extend(self.poll()?);
+        }
+        Ok(changed.into_iter().collect())
+    }
+
+    /// Add the paths changed by `event` to `changed`
+    fn record(&self, event: DebouncedEvent, changed: &mut BTreeSet<PathBuf>) -> Result<()> {
+        let paths = match event {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Remove(path) => vec![path],
+            DebouncedEvent::Rename(from, to) => vec![from, to],
+            DebouncedEvent::Rescan => {
+                changed.insert(PathBuf::new());
+                return Ok(());
+            }
+            DebouncedEvent::Error(err, path) => match path {
+                Some(path) => bail!("Watching {}: {}", path.display(), err),
+                None => return Err(err.into()),
+            },
+            // followed by `Write` or `Remove`, and permissions are not content
+            DebouncedEvent::NoticeWrite(_)
+            | DebouncedEvent::NoticeRemove(_)
+            | DebouncedEvent::Chmod(_) => vec![],
+        };
+        for path in paths {
+            if path.is_dir() {
+                continue;
+            }
+            if let Some(path) = self.reported_path(&path) {
+                changed.insert(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// How a change of `path` is reported, if at all
+    fn reported_path(&self, path: &Path) -> Option<PathBuf> {
+        let is_in_git = |rel_path: &Path| {
+            rel_path
+                .components()
+                .any(|component| component == Component::Normal(".git".as_ref()))
+        };
+        if let Ok(rel_path) = path.strip_prefix(&self.root_dir) {
+            let dot_crev = Path::new(super::CREV_DOT_NAME);
+            let is_local_to_checkout = super::GITIGNORE_CONTENT
+                .lines()
+                .any(|name| rel_path.starts_with(dot_crev.join(name)));
+            return Some(rel_path.to_owned()).filter(|rel_path| {
+                !rel_path.as_os_str().is_empty() && !is_in_git(rel_path) && !is_local_to_checkout
+            });
+        }
+        self.dirs
+            .iter()
+            .filter_map(|dir| path.strip_prefix(dir).ok())
+            .next()
+            .filter(|rel_path| !is_in_git(rel_path))
+            .map(|_| path.to_owned())
+    }
+}
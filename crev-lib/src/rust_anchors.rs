@@ -0,0 +1,259 @@
+//! Semantic anchors of Rust sources; see `crev_data::proof::review::code::Anchor`
+//!
+//! Every top-level item of a file becomes an anchor, along with the
+//! attributes before it. Items are found using the tokens of
+//! `rust_metrics`, tracking only brackets, so the anchors and their
+//! digests don't depend on formatting or comments.
+use crate::{
+    prelude::*,
+    rust_metrics::{tokenize_with_offsets, Token},
+};
+use crev_common;
+use crev_data::proof::review::code::Anchor;
+use std::{ops::Range, path::Path};
+
+/// Kinds of items ending with their first `{ }` block, if not with a `;`
+const BLOCK_ITEMS: &[&str] = &[
+    "fn",
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "impl",
+    "mod",
+    "macro_rules",
+];
+/// Kinds of items always ending with a `;`
+const STATEMENT_ITEMS: &[&str] = &["const", "static", "type", "use", "extern"];
+/// Words that can precede the kind of an item
+const MODIFIERS: &[&str] = &["pub", "crate", "unsafe", "async", "default", "auto"];
+
+fn token_text(token: &Token<'_>) -> String {
+    match token {
+        Token::Ident(s) | Token::Literal(s) => (*s).to_owned(),
+        Token::Punct(c) => c.to_string(),
+    }
+}
+
+fn canonical_text(tokens: &[Token<'_>]) -> String {
+    tokens.iter().map(token_text).collect::<Vec<_>>().join(" ")
+}
+
+/// Index just past the bracket group opening at `start`
+fn skip_group(tokens: &[Token<'_>], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < tokens.len() {
+        match tokens[i] {
+            Token::Punct('(') | Token::Punct('[') | Token::Punct('{') => depth += 1,
+            Token::Punct(')') | Token::Punct(']') | Token::Punct('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Index just past the item starting at `start`, ending with `;` or, if
+/// `block_ends` is set, with a top-level `{ }` block
+fn item_end(tokens: &[Token<'_>], start: usize, block_ends: bool) -> usize {
+    let mut i = start;
+    while i < tokens.len() {
+        match tokens[i] {
+            Token::Punct(';') => return i + 1,
+            Token::Punct('{') if block_ends => {
+                let end = skip_group(tokens, i);
+                // tuple structs and `macro_rules!` with parentheses end with `;`
+                return match tokens.get(end) {
+                    Some(Token::Punct(';')) => end + 1,
+                    _ => end,
+                };
+            }
+            Token::Punct('(') | Token::Punct('[') | Token::Punct('{') => {
+                i = skip_group(tokens, i);
+            }
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+/// Kind and name of the item with its keywords starting at `start`,
+/// and where it ends
+fn describe_item(tokens: &[Token<'_>], start: usize) -> (String, String, usize) {
+    let mut i = start;
+    loop {
+        match tokens.get(i) {
+            Some(Token::Ident(word)) if MODIFIERS.contains(word) => i += 1,
+            Some(Token::Punct('(')) if i > start => i = skip_group(tokens, i),
+            // `const fn`, `extern "C" fn`
+            Some(Token::Ident("const")) | Some(Token::Ident("extern"))
+                if matches!(
+                    tokens.get(i + 1),
+                    Some(Token::Ident("fn"))
+                        | Some(Token::Ident("unsafe"))
+                        | Some(Token::Literal(_))
+                ) =>
+            {
+                i += 1;
+                if let Some(Token::Literal(_)) = tokens.get(i) {
+                    i += 1;
+                }
+            }
+            _ => break,
+        }
+    }
+
+    let kind = match tokens.get(i) {
+        Some(Token::Ident(word)) => *word,
+        _ => "",
+    };
+    if BLOCK_ITEMS.contains(&kind) {
+        let end = item_end(tokens, i, true);
+        let name = if kind == "impl" {
+            let header_end = tokens[i..end]
+                .iter()
+                .position(|token| *token == Token::Punct('{') || *token == Token::Ident("where"))
+                .map_or(end, |pos| i + pos);
+            canonical_text(&tokens[i + 1..header_end])
+        } else {
+            tokens[i + 1..end]
+                .iter()
+                .find_map(|token| match token {
+                    Token::Ident(name) => Some((*name).to_owned()),
+                    _ => None,
+                })
+                .unwrap_or_default()
+        };
+        (kind.to_owned(), name, end)
+    } else if STATEMENT_ITEMS.contains(&kind) {
+        let end = item_end(tokens, i, false);
+        let name = match kind {
+            "use" | "extern" => canonical_text(&tokens[i + 1..end.saturating_sub(1).max(i + 1)]),
+            _ => tokens.get(i + 1).map(token_text).unwrap_or_default(),
+        };
+        (kind.to_owned(), name, end)
+    } else {
+        // macro invocations, and anything else
+        let end = item_end(tokens, i, true).max(i + 1);
+        match tokens[i..end]
+            .iter()
+            .position(|token| *token == Token::Punct('!'))
+        {
+            Some(pos) => ("macro".into(), canonical_text(&tokens[i..i + pos]), end),
+            None => ("other".into(), canonical_text(&tokens[i..i + 1]), end),
+        }
+    }
+}
+
+/// An anchor, with the lines of the source it covers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub anchor: Anchor,
+    /// Counting from 1
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+/// Anchors of all the top-level items of a Rust `source`
+pub fn source_anchors(source: &str) -> Vec<Anchor> {
+    anchor_spans(source)
+        .into_iter()
+        .map(|(anchor, _span)| anchor)
+        .collect()
+}
+
+/// Like `source_anchors`, along with the lines of each
+pub fn source_regions(source: &str) -> Vec<Region> {
+    let line_of = |offset: usize| source[..offset].matches('\n').count() + 1;
+    anchor_spans(source)
+        .into_iter()
+        .map(|(anchor, span)| Region {
+            anchor,
+            first_line: line_of(span.start),
+            last_line: line_of(span.end.saturating_sub(1).max(span.start)),
+        })
+        .collect()
+}
+
+/// Anchors of `source`, with the bytes each spans
+fn anchor_spans(source: &str) -> Vec<(Anchor, Range<usize>)> {
+    let (offsets, tokens): (Vec<_>, Vec<_>) = tokenize_with_offsets(source).into_iter().unzip();
+    let span = |start: usize, end: usize| {
+        let last_end = match tokens[end - 1] {
+            Token::Ident(s) | Token::Literal(s) => offsets[end - 1] + s.len(),
+            Token::Punct(c) => offsets[end - 1] + c.len_utf8(),
+        };
+        offsets[start]..last_end
+    };
+    let mut anchors = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        let start = i;
+        // inner attributes are anchors of their own
+        if tokens[i] == Token::Punct('#')
+            && tokens.get(i + 1) == Some(&Token::Punct('!'))
+            && tokens.get(i + 2) == Some(&Token::Punct('['))
+        {
+            i = skip_group(&tokens, i + 2);
+            anchors.push((
+                Anchor {
+                    kind: "attr".into(),
+                    name: canonical_text(&tokens[start..i]),
+                    digest: crev_common::blake2b256sum(
+                        canonical_text(&tokens[start..i]).as_bytes(),
+                    ),
+                },
+                span(start, i),
+            ));
+            continue;
+        }
+        while tokens.get(i) == Some(&Token::Punct('#'))
+            && tokens.get(i + 1) == Some(&Token::Punct('['))
+        {
+            i = skip_group(&tokens, i + 1);
+        }
+        if i >= tokens.len() {
+            // attributes with no item left to apply to still count
+            if start < i {
+                anchors.push((
+                    Anchor {
+                        kind: "attr".into(),
+                        name: canonical_text(&tokens[start..i]),
+                        digest: crev_common::blake2b256sum(
+                            canonical_text(&tokens[start..i]).as_bytes(),
+                        ),
+                    },
+                    span(start, i),
+                ));
+            }
+            break;
+        }
+        let (kind, name, end) = describe_item(&tokens, i);
+        anchors.push((
+            Anchor {
+                kind,
+                name,
+                digest: crev_common::blake2b256sum(canonical_text(&tokens[start..end]).as_bytes()),
+            },
+            span(start, end),
+        ));
+        i = end;
+    }
+    anchors
+}
+
+/// Anchors of a file to review, if it's a Rust source
+pub fn file_anchors(rel_path: &Path, full_path: &Path) -> Result<Vec<Anchor>> {
+    if rel_path.extension() != Some("rs".as_ref()) {
+        return Ok(vec![]);
+    }
+    Ok(source_anchors(&crev_common::read_file_to_string(
+        full_path,
+    )?))
+}
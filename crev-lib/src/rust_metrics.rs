@@ -0,0 +1,199 @@
+//! Collecting `RustMetrics` of files staged for review
+//!
+//! Sources are not parsed, only split into identifiers, literals and
+//! punctuation, skipping comments, which is enough to find keywords and
+//! attributes reliably.
+use crate::prelude::*;
+use crev_data::proof::review::RustMetrics;
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    Ident(&'a str),
+    /// Literals and lifetimes, as written
+    Literal(&'a str),
+    Punct(char),
+}
+
+/// Skip a string literal starting at `i` (just after the opening `"`)
+fn skip_string(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+/// Skip a raw string literal starting at `i` (just after the `r`)
+///
+/// Returns `None` if it's not a raw string after all (eg. `r#ident`).
+fn skip_raw_string(bytes: &[u8], mut i: usize) -> Option<usize> {
+    let mut hashes = 0;
+    while i < bytes.len() && bytes[i] == b'#' {
+        hashes += 1;
+        i += 1;
+    }
+    if i >= bytes.len() || bytes[i] != b'"' {
+        return None;
+    }
+    i += 1;
+    while i < bytes.len() {
+        if bytes[i] == b'"'
+            && bytes.len() - i > hashes
+            && bytes[i + 1..=i + hashes].iter().all(|b| *b == b'#')
+        {
+            return Some(i + 1 + hashes);
+        }
+        i += 1;
+    }
+    Some(i)
+}
+
+/// Tokens of `source`, along with the offsets they start at
+pub(crate) fn tokenize_with_offsets(source: &str) -> Vec<(usize, Token<'_>)> {
+    let bytes = source.as_bytes();
+    let is_ident_byte = |b: u8| b == b'_' || b.is_ascii_alphanumeric() || b >= 0x80;
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_whitespace() {
+            i += 1;
+        } else if bytes[i..].starts_with(b"//") {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        } else if bytes[i..].starts_with(b"/*") {
+            let mut depth = 0;
+            while i < bytes.len() {
+                if bytes[i..].starts_with(b"/*") {
+                    depth += 1;
+                    i += 2;
+                } else if bytes[i..].starts_with(b"*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+        } else if b == b'"' {
+            let start = i;
+            i = skip_string(bytes, i + 1).min(bytes.len());
+            tokens.push((start, Token::Literal(&source[start..i])));
+        } else if b == b'\'' {
+            let start = i;
+            // char literal, or a lifetime that is skipped like an identifier
+            if bytes.get(i + 1) == Some(&b'\\') {
+                i += 3;
+                while i < bytes.len() && bytes[i] != b'\'' {
+                    i += 1;
+                }
+                i += 1;
+            } else {
+                let char_len = source[i + 1..].chars().next().map_or(0, char::len_utf8);
+                if bytes.get(i + 1 + char_len) == Some(&b'\'') {
+                    i += char_len + 2;
+                } else {
+                    i += 1;
+                    while i < bytes.len() && is_ident_byte(bytes[i]) {
+                        i += 1;
+                    }
+                }
+            }
+            i = i.min(bytes.len());
+            tokens.push((start, Token::Literal(&source[start..i])));
+        } else if b.is_ascii_digit() {
+            let start = i;
+            while i < bytes.len() && is_ident_byte(bytes[i]) {
+                i += 1;
+            }
+            tokens.push((start, Token::Literal(&source[start..i])));
+        } else if is_ident_byte(b) {
+            let start = i;
+            while i < bytes.len() && is_ident_byte(bytes[i]) {
+                i += 1;
+            }
+            let ident = &source[start..i];
+            if ident == "r" || ident == "br" {
+                if let Some(end) = skip_raw_string(bytes, i) {
+                    i = end.min(bytes.len());
+                    tokens.push((start, Token::Literal(&source[start..i])));
+                    continue;
+                }
+            }
+            tokens.push((start, Token::Ident(ident)));
+        } else {
+            let c = source[i..].chars().next().expect("not at the end");
+            tokens.push((i, Token::Punct(c)));
+            i += c.len_utf8();
+        }
+    }
+    tokens
+}
+
+pub(crate) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    tokenize_with_offsets(source)
+        .into_iter()
+        .map(|(_offset, token)| token)
+        .collect()
+}
+
+/// Metrics of Rust source code
+pub fn source_metrics(source: &str) -> RustMetrics {
+    let tokens = tokenize(source);
+    let unsafe_count = tokens
+        .iter()
+        .filter(|token| **token == Token::Ident("unsafe"))
+        .count() as u64;
+    let proc_macro = tokens.windows(3).any(|window| match window {
+        [Token::Punct('#'), Token::Punct('['), Token::Ident(attr)] => {
+            *attr == "proc_macro" || *attr == "proc_macro_derive" || *attr == "proc_macro_attribute"
+        }
+        _ => false,
+    });
+
+    RustMetrics {
+        unsafe_count,
+        build_script: false,
+        proc_macro,
+    }
+}
+
+/// Metrics of a `Cargo.toml` manifest
+fn manifest_metrics(manifest: &str) -> RustMetrics {
+    let proc_macro = manifest.lines().any(|line| {
+        let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        line == "proc-macro=true" || line == "proc_macro=true"
+    });
+    RustMetrics {
+        proc_macro,
+        ..Default::default()
+    }
+}
+
+/// Metrics of a file at `rel_path`, if it's a Rust source or manifest
+///
+/// Invalid UTF-8 doesn't stop the file being counted, like it doesn't
+/// stop it being reviewed.
+pub fn file_metrics(rel_path: &Path, full_path: &Path) -> Result<Option<RustMetrics>> {
+    let read = || -> Result<String> {
+        Ok(String::from_utf8_lossy(&std::fs::read(full_path)?).into_owned())
+    };
+    if rel_path.file_name() == Some("Cargo.toml".as_ref()) {
+        return Ok(Some(manifest_metrics(&read()?)));
+    }
+    if rel_path.extension() != Some("rs".as_ref()) {
+        return Ok(None);
+    }
+
+    let mut metrics = source_metrics(&read()?);
+    metrics.build_script = rel_path.file_name() == Some("build.rs".as_ref());
+    Ok(Some(metrics))
+}
@@ -0,0 +1,41 @@
+//! Collecting `SizeMetrics` of files staged for review
+use crate::prelude::*;
+use std::{fs, path::Path};
+
+/// Language of a file, judging by its extension
+pub fn language_for_path(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "rs" => "rust",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "c++",
+        "go" => "go",
+        "java" => "java",
+        "js" => "javascript",
+        "ts" => "typescript",
+        "py" => "python",
+        "rb" => "ruby",
+        "sh" | "bash" => "shell",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "md" => "markdown",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "json" => "json",
+        _ => return None,
+    })
+}
+
+/// Lines of code in a file
+///
+/// Blank lines are not counted, and binary files have none.
+pub fn count_lines(path: &Path) -> Result<u64> {
+    let content = fs::read(path)?;
+    if content.contains(&0) {
+        return Ok(0);
+    }
+    Ok(content
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .count() as u64)
+}
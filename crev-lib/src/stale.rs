@@ -0,0 +1,144 @@
+//! `crev stale` - reviews that still count, but may not deserve to
+//!
+//! A Code Review Proof of the current content of a file verifies it
+//! however long ago it was made. `Repo::stale_reviews` points out the
+//! trusted reviews where coverage is likely rotting:
+//!
+//! * reviews older than `max_age_days`,
+//! * reviews of files the rest of the scope changed around in more than
+//!   `max_commits` commits since, so the code they were reviewed with is
+//!   gone,
+//! * reviews by ids without any proof for more than `max_inactive_days`.
+use crate::repo::FileVerification;
+use chrono::{self, prelude::*};
+use crev_data::{
+    proof::{trust::TrustLevel, ContentCommon},
+    Id,
+};
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+/// Thresholds of `Repo::stale_reviews`
+#[derive(Debug, Clone)]
+pub struct StaleParams {
+    pub max_age_days: i64,
+    pub max_commits: usize,
+    pub max_inactive_days: i64,
+}
+
+impl Default for StaleParams {
+    fn default() -> Self {
+        StaleParams {
+            max_age_days: 2 * 365,
+            max_commits: 50,
+            max_inactive_days: 365,
+        }
+    }
+}
+
+/// Why a review looks stale
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleReason {
+    Old {
+        days: i64,
+    },
+    /// Commits changing other files of the scope since the review
+    ChangedAround {
+        commits: usize,
+    },
+    /// Days since the last proof of the reviewer
+    InactiveReviewer {
+        days: i64,
+    },
+}
+
+impl fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaleReason::Old { days } => write!(f, "made {} days ago", days),
+            StaleReason::ChangedAround { commits } => write!(
+                f,
+                "the rest of the scope changed in {} commits since",
+                commits
+            ),
+            StaleReason::InactiveReviewer { days } => {
+                write!(f, "no proof of the reviewer in {} days", days)
+            }
+        }
+    }
+}
+
+/// A trusted review, still covering `files`, that looks stale
+#[derive(Debug, Clone)]
+pub struct StaleReview {
+    pub from: Id,
+    pub date: chrono::DateTime<FixedOffset>,
+    /// Relative to the root of the scope
+    pub files: Vec<PathBuf>,
+    pub reasons: Vec<StaleReason>,
+}
+
+/// The stale reviews of `files`, oldest first
+///
+/// `changes` are the dates of the commits of the scope, with the files
+/// each changed, and `last_proof_date` tells when an id was last active.
+pub(crate) fn find(
+    files: &[FileVerification],
+    changes: &[(DateTime<Utc>, Vec<PathBuf>)],
+    last_proof_date: impl Fn(&Id) -> Option<DateTime<Utc>>,
+    params: &StaleParams,
+    now: DateTime<Utc>,
+) -> Vec<StaleReview> {
+    // reviews by author and date, with the files they still cover
+    let mut reviews = BTreeMap::new();
+    for file in files {
+        for candidate in &file.reviews {
+            if candidate.reviewer_trust < Some(TrustLevel::Low) {
+                continue;
+            }
+            let review = &candidate.review;
+            reviews
+                .entry((review.date_utc(), review.from.id.clone()))
+                .or_insert_with(|| (review, vec![]))
+                .1
+                .push(file.path.clone());
+        }
+    }
+
+    let mut stale = vec![];
+    for ((date, from), (review, covered)) in reviews {
+        let mut reasons = vec![];
+        let age = now.signed_duration_since(date);
+        if age > chrono::Duration::days(params.max_age_days) {
+            reasons.push(StaleReason::Old {
+                days: age.num_days(),
+            });
+        }
+        let commits = changes
+            .iter()
+            .filter(|(commit_date, _)| *commit_date > date)
+            .filter(|(_, paths)| {
+                paths
+                    .iter()
+                    .any(|path| !review.files.iter().any(|file| file.path == *path))
+            })
+            .count();
+        if commits > params.max_commits {
+            reasons.push(StaleReason::ChangedAround { commits });
+        }
+        let inactive = now.signed_duration_since(last_proof_date(&from).unwrap_or(date));
+        if inactive > chrono::Duration::days(params.max_inactive_days) {
+            reasons.push(StaleReason::InactiveReviewer {
+                days: inactive.num_days(),
+            });
+        }
+        if !reasons.is_empty() {
+            stale.push(StaleReview {
+                from,
+                date: *review.date(),
+                files: covered,
+                reasons,
+            });
+        }
+    }
+    stale
+}
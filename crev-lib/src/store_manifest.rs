@@ -0,0 +1,160 @@
+//! `crev store verify-manifest` - whether proof files changed behind our back
+//!
+//! Signatures tell a proof file that was changed, but not one that was
+//! deleted. The manifest lists the digest of every proof file of the
+//! `Local` store - own, fetched, decrypted and imported - and is written
+//! again whenever crev changes them: signed by the own id when it's
+//! unlocked for signing a proof, unsigned after a fetch. Files added,
+//! changed or gone since then were touched by something else, or rotted.
+use crate::prelude::*;
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use crev_data::{encrypted, Id, OwnId};
+use serde_json;
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Digests of proof files, by `<root>/<path relative to the root>`
+pub type Files = BTreeMap<String, String>;
+
+/// The digests of the store's proof files at `date`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub date: chrono::DateTime<FixedOffset>,
+    pub files: Files,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub by: Option<Id>,
+    /// Signature of `by` over the manifest with an empty `signature`
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub signature: String,
+}
+
+impl Manifest {
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signature = String::new();
+        Ok(serde_json::to_vec(&unsigned)?)
+    }
+
+    /// Check the signature, if there's one
+    pub fn verify(&self) -> Result<()> {
+        match self.by {
+            Some(ref by) => by.verify_signature(&self.signed_bytes()?, &self.signature),
+            None => Ok(()),
+        }
+    }
+
+    /// How `files` differ from the ones of the manifest
+    pub fn changes(&self, files: &Files) -> Vec<Change> {
+        let mut changes = vec![];
+        for (path, digest) in &self.files {
+            match files.get(path) {
+                None => changes.push(Change::Removed(path.clone())),
+                Some(current) if current != digest => changes.push(Change::Changed(path.clone())),
+                Some(_) => {}
+            }
+        }
+        for path in files.keys() {
+            if !self.files.contains_key(path) {
+                changes.push(Change::Added(path.clone()));
+            }
+        }
+        changes
+    }
+}
+
+/// A proof file that differs from the manifest
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(String),
+    Changed(String),
+    Removed(String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(path) => write!(f, "added: {}", path),
+            Change::Changed(path) => write!(f, "changed: {}", path),
+            Change::Removed(path) => write!(f, "removed: {}", path),
+        }
+    }
+}
+
+fn is_proof_file(path: &Path) -> bool {
+    path.extension() == Some("crev".as_ref())
+        || path.extension() == Some(encrypted::FILE_EXTENSION.as_ref())
+}
+
+/// The digests of the proof files in `roots`, each given with its name
+pub fn scan(roots: &[(&str, PathBuf)]) -> Result<Files> {
+    let mut files = Files::new();
+    for (name, root) in roots {
+        if !root.exists() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git")
+        {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type().is_file() || !is_proof_file(path) {
+                continue;
+            }
+            let rel_path = path.strip_prefix(root)?;
+            files.insert(
+                format!("{}/{}", name, rel_path.display()),
+                crev_common::base64_encode(&crev_common::blake2b256sum(&fs::read(path)?)),
+            );
+        }
+    }
+    Ok(files)
+}
+
+/// The manifest of a `Local`
+pub struct StoreManifest {
+    path: PathBuf,
+}
+
+impl StoreManifest {
+    pub fn new(path: &Path) -> Self {
+        StoreManifest {
+            path: path.to_owned(),
+        }
+    }
+
+    /// Replace the manifest with one of `files`, signed by `by` if given
+    pub fn write(&self, files: Files, by: Option<&OwnId>) -> Result<()> {
+        let mut manifest = Manifest {
+            date: crev_common::now(),
+            files,
+            by: by.map(|by| by.id.id.clone()),
+            signature: String::new(),
+        };
+        if let Some(by) = by {
+            manifest.signature = crev_common::base64_encode(&by.sign(&manifest.signed_bytes()?));
+        }
+        crev_common::store_str_to_file(&self.path, &serde_json::to_string_pretty(&manifest)?)?;
+        Ok(())
+    }
+
+    /// The manifest, if one was written
+    pub fn load(&self) -> Result<Option<Manifest>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = crev_common::read_file_to_string(&self.path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+}
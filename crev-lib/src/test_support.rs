@@ -0,0 +1,232 @@
+//! Throwaway users, projects and proof repositories, for scripting whole
+//! workflows in tests
+//!
+//! Everything lives in the temporary directory of a `TestEnv`. Each
+//! `TestUser` has a `Local` of its own, found through `CONFIG_DIR_ENV` and
+//! `CACHE_DIR_ENV`, and publishes its proofs to a git repository that the
+//! others can fetch from by its path:
+//!
+//! ```no_run
+//! # fn main() -> common_failures::Result<()> {
+//! use crev_lib::{repo::Repo, test_support::TestEnv};
+//!
+//! let env = TestEnv::new()?;
+//! let alice = env.new_user("alice")?;
+//! let project = env.new_project("foo", &[("src/lib.rs", "fn foo() {}")])?;
+//!
+//! alice.activate();
+//! let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+//! repo.add(vec![project.join("src/lib.rs")])?;
+//! repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+//! alice.publish()?;
+//!
+//! let bob = env.new_user("bob")?;
+//! bob.local()?.fetch_url(&alice.url())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The environment variables are process-wide, so only one `TestEnv` is
+//! alive at a time; `TestEnv::new` waits for the previous one to go away.
+//! Drafts of proofs are signed as they are, with `true` for the editor.
+use crate::{
+    local::{Local, CACHE_DIR_ENV, CONFIG_DIR_ENV},
+    prelude::*,
+};
+use crev_data::{
+    id::OwnId,
+    proof::{
+        self,
+        review::{self, code::File, Rating},
+        PackageInfo,
+    },
+    Url,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Passphrase of all the ids of test users
+pub const PASSPHRASE: &str = "test passphrase";
+
+/// A temporary directory with users and projects
+pub struct TestEnv {
+    dir: tempdir::TempDir,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TestEnv {
+    pub fn new() -> Result<Self> {
+        // a test failing while holding the lock doesn't spoil it for the others
+        let lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::set_var("VISUAL", "true");
+        Ok(TestEnv {
+            dir: tempdir::TempDir::new("crev-test-env")?,
+            _lock: lock,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// `PassphraseFn` for the ids of test users
+    pub fn passphrase() -> std::io::Result<String> {
+        Ok(PASSPHRASE.into())
+    }
+
+    /// Create a user with a new id, and an empty proof repository
+    ///
+    /// The user is left active; see `TestUser::activate`.
+    pub fn new_user(&self, name: &str) -> Result<TestUser> {
+        let user_dir = self.path().join("users").join(name);
+        let remote_path = self.path().join("remotes").join(name);
+        git2::Repository::init_bare(&remote_path)?;
+
+        let url = Url::new_git(remote_path.display().to_string());
+        let user = TestUser {
+            id: OwnId::generate(url.clone()),
+            config_dir: user_dir.join("config"),
+            cache_dir: user_dir.join("cache"),
+            remote_path,
+        };
+        user.activate();
+
+        let local = Local::auto_create()?;
+        let proofs_dir = local.get_proofs_dir_path_for_url(&url)?;
+        git2::Repository::init(&proofs_dir)?;
+        local.save_locked_id(&crate::id::LockedId::from_own_id(&user.id, PASSPHRASE)?)?;
+        local.save_current_id(user.id.as_ref())?;
+        Ok(user)
+    }
+
+    /// Create a git repository at `<name>` with `files`, as paths and
+    /// contents, all committed
+    pub fn new_project(&self, name: &str, files: &[(&str, &str)]) -> Result<PathBuf> {
+        let path = self.path().join("projects").join(name);
+        git2::Repository::init(&path)?;
+        Self::change_project(&path, files)?;
+        Ok(path)
+    }
+
+    /// Write `files` to the project at `path`, and commit them all,
+    /// returning the new revision
+    pub fn change_project(path: &Path, files: &[(&str, &str)]) -> Result<String> {
+        for (file_path, content) in files {
+            let file_path = path.join(file_path);
+            fs::create_dir_all(file_path.parent().expect("not a root path"))?;
+            fs::write(file_path, content)?;
+        }
+        let oid = commit_all(&git2::Repository::open(path)?, "Change files")?;
+        Ok(oid.to_string())
+    }
+}
+
+/// A user of a `TestEnv`
+pub struct TestUser {
+    pub id: OwnId,
+    config_dir: PathBuf,
+    cache_dir: PathBuf,
+    /// Where `publish` pushes the proofs to; the URL of the id
+    remote_path: PathBuf,
+}
+
+impl TestUser {
+    /// Make `Local::auto_open`, and everything using it, open the `Local`
+    /// of this user
+    pub fn activate(&self) {
+        std::env::set_var(CONFIG_DIR_ENV, &self.config_dir);
+        std::env::set_var(CACHE_DIR_ENV, &self.cache_dir);
+    }
+
+    /// Activate this user and open their `Local`
+    pub fn local(&self) -> Result<Local> {
+        self.activate();
+        Local::auto_open()
+    }
+
+    /// URL of the id, to fetch the published proofs from
+    pub fn url(&self) -> String {
+        self.id.id.url.url.clone()
+    }
+
+    /// Commit the proof repository of this user, and push it to `url`
+    pub fn publish(&self) -> Result<()> {
+        let proofs_dir = self.local()?.get_proofs_dir_path()?;
+        commit_all(&git2::Repository::open(&proofs_dir)?, "Add proofs")?;
+
+        let remote = git2::Repository::open_bare(&self.remote_path)?;
+        remote
+            .remote_anonymous(&proofs_dir.display().to_string())?
+            .fetch(&["+refs/heads/master:refs/heads/master"], None, None)?;
+        Ok(())
+    }
+}
+
+/// A package `name` from `source`, of no particular version, revision or
+/// digest; tests set the ones they need with struct update syntax
+pub fn package_info(source: &str, name: &str) -> PackageInfo {
+    PackageInfo {
+        id: None,
+        source: source.into(),
+        name: name.into(),
+        version: "".into(),
+        digest: vec![],
+        digest_type: proof::default_digest_type(),
+        revision: "".into(),
+        revision_type: proof::default_revision_type(),
+    }
+}
+
+/// A file at `path` of a Code Review Proof, with content digested to
+/// `digest`
+pub fn reviewed_file(path: &str, digest: Vec<u8>) -> File {
+    File {
+        path: path.into(),
+        digest,
+        digest_type: proof::default_digest_type(),
+        anchors: vec![],
+        normalization: vec![],
+        kind: None,
+    }
+}
+
+/// A Code Review Proof by `author` of `files` of a package `foo`, rated
+/// `rating`, ready to sign
+pub fn code_review(author: &OwnId, rating: Rating, files: Vec<File>) -> Result<review::Code> {
+    review::CodeBuilder::default()
+        .from(author.id.clone())
+        .package(package_info("https://example.com", "foo"))
+        .review(review::Review {
+            rating,
+            ..Default::default()
+        })
+        .files(files)
+        .build()
+        .map_err(|e| format_err!("{}", e))
+}
+
+/// Commit all the files of the working directory of `repo` to `master`
+fn commit_all(repo: &git2::Repository, message: &str) -> Result<git2::Oid> {
+    let mut index = repo.index()?;
+    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("crev test", "test@example.com")?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(_) => None,
+    };
+    Ok(repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )?)
+}
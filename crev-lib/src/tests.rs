@@ -1,5 +1,6 @@
 use super::*;
 
+use crate::test_support::{code_review, package_info, reviewed_file};
 use crev_data::proof::trust::TrustLevel;
 use crev_data::Digest;
 use crev_data::OwnId;
@@ -48,6 +49,8 @@ fn proofdb_distance() -> Result<()> {
         medium_trust_distance: 10,
         low_trust_distance: 100,
         max_distance: 111,
+        max_depth: None,
+        follow_id_links: false,
     };
 
     let a_to_b = a
@@ -99,6 +102,324 @@ fn proofdb_distance() -> Result<()> {
     Ok(())
 }
 
+// Trust doesn't propagate through more trust proofs than `max_depth`,
+// however cheap they are.
+#[test]
+fn trust_depth_limit() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let b_to_c = b
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&b)?;
+    let c_to_d = c
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&c)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, b_to_c, c_to_d].into_iter());
+
+    let trusted_with = |max_depth| -> HashSet<crev_data::Id> {
+        let params = TrustDistanceParams {
+            max_depth,
+            ..Default::default()
+        };
+        trustdb
+            .calculate_trust_set(a.as_ref(), &params)
+            .trusted_ids()
+            .cloned()
+            .collect()
+    };
+
+    assert!(trusted_with(None).contains(d.as_ref()));
+
+    let trust_set = trusted_with(Some(2));
+    assert!(trust_set.contains(c.as_ref()));
+    assert!(!trust_set.contains(d.as_ref()));
+
+    let trust_set = trusted_with(Some(0));
+    assert!(trust_set.contains(a.as_ref()));
+    assert!(!trust_set.contains(b.as_ref()));
+    Ok(())
+}
+
+// An id first reached through a longer but cheaper chain still passes trust
+// on once a shorter chain to it is found.
+#[test]
+fn trust_depth_limit_uses_the_shortest_chain() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let m = OwnId::generate_for_git_url("https://m");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+    let trust = |from: &OwnId, to: &OwnId, level| -> Result<_> {
+        from.create_trust_proof(vec![to.as_pubid().to_owned()], level)?
+            .sign_by(from)
+    };
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(
+        vec![
+            trust(&a, &m, TrustLevel::High)?,
+            trust(&m, &b, TrustLevel::High)?,
+            trust(&a, &b, TrustLevel::Medium)?,
+            trust(&b, &c, TrustLevel::High)?,
+            trust(&c, &d, TrustLevel::High)?,
+        ]
+        .into_iter(),
+    );
+    let params = TrustDistanceParams {
+        max_depth: Some(3),
+        ..Default::default()
+    };
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &params);
+    assert!(trust_set.get_effective_trust_level(d.as_ref()).is_some());
+    Ok(())
+}
+
+// A cached trust set is loaded back as calculated, but only for the key it
+// was stored with.
+#[test]
+fn trust_set_cache() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::Medium)?
+        .sign_by(&a)?;
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    let tmp_dir = tempdir::TempDir::new("crev-trust-set")?;
+    let path = tmp_dir.path().join("trust-set");
+    assert!(TrustSet::load_cached(&path, b"key").is_none());
+    trust_set.store_cached(&path, b"key")?;
+
+    let cached = TrustSet::load_cached(&path, b"key").expect("cached");
+    assert_eq!(
+        cached.get_effective_trust_level(b.as_ref()),
+        Some(TrustLevel::Medium)
+    );
+    assert_eq!(
+        cached.trusted_ids().collect::<HashSet<_>>(),
+        trust_set.trusted_ids().collect::<HashSet<_>>()
+    );
+    assert!(TrustSet::load_cached(&path, b"other key").is_none());
+    Ok(())
+}
+
+// A later distrust replaces the trust given before, cutting off everyone
+// trusted only through the distrusted id.
+#[test]
+fn distrust_revokes_trust() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let mut a_to_b = a.create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?;
+    a_to_b.date = a_to_b.date - chrono::Duration::days(1);
+    let a_to_b = a_to_b.sign_by(&a)?;
+    let b_to_c = b
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&b)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, b_to_c].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &Default::default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_some());
+    assert!(trust_set.get_effective_trust_level(c.as_ref()).is_some());
+
+    let a_distrusts_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::Distrust)?
+        .sign_by(&a)?;
+    trustdb.import_from_iter(vec![a_distrusts_b].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &Default::default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_none());
+    assert!(trust_set.get_effective_trust_level(c.as_ref()).is_none());
+    Ok(())
+}
+
+// Overriding trust replaces what the proofs say, both ways, and trust still
+// flows on from the overridden ids.
+#[test]
+fn trust_overrides() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let c_to_d = c
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&c)?;
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, c_to_d].into_iter());
+
+    assert!(trustdb.override_trust(a.as_ref(), b.as_ref(), TrustLevel::Distrust));
+    assert!(trustdb.override_trust(a.as_ref(), c.as_ref(), TrustLevel::Medium));
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_none());
+    assert_eq!(
+        trust_set.get_effective_trust_level(c.as_ref()),
+        Some(TrustLevel::Medium)
+    );
+    assert!(trust_set.get_effective_trust_level(d.as_ref()).is_some());
+
+    // distrust of `a` itself stays
+    let a_distrusts_d = a
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Distrust)?
+        .sign_by(&a)?;
+    trustdb.import_from_iter(vec![a_distrusts_d].into_iter());
+    assert!(!trustdb.override_trust(a.as_ref(), d.as_ref(), TrustLevel::High));
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    assert!(trust_set.get_effective_trust_level(d.as_ref()).is_none());
+    Ok(())
+}
+
+#[test]
+fn trust_graph_to_dot() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let b_to_c = b
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::Distrust)?
+        .sign_by(&b)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, b_to_c].into_iter());
+    let dot = trustdb
+        .calculate_trust_set(a.as_ref(), &Default::default())
+        .to_dot(&trustdb);
+
+    assert!(dot.starts_with("digraph trust {\n"));
+    assert!(dot.contains(&format!("\"{}\" [label=\"https://b\\nhigh\"];", b.id.id)));
+    assert!(dot.contains(&format!(
+        "\"{}\" -> \"{}\" [label=\"high\"];",
+        a.id.id, b.id.id
+    )));
+    assert!(dot.contains(&format!(
+        "\"{}\" -> \"{}\" [label=\"distrust\", style=dashed, color=red];",
+        b.id.id, c.id.id
+    )));
+    assert!(!dot.contains(&format!("-> \"{}\" [label=\"", a.id.id)));
+    Ok(())
+}
+
+#[test]
+fn explain_trust_chains() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let mut a_to_b = a.create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?;
+    a_to_b.date = a_to_b.date - chrono::Duration::days(2);
+    let a_to_b_date = a_to_b.date.with_timezone(&chrono::Utc);
+    let b_to_c = b.create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::Medium)?;
+    let b_to_c_date = b_to_c.date.with_timezone(&chrono::Utc);
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b.sign_by(&a)?, b_to_c.sign_by(&b)?].into_iter());
+
+    let chains = trustdb.explain_trust(a.as_ref(), c.as_ref(), &Default::default());
+    assert_eq!(
+        chains,
+        vec![vec![
+            TrustLink {
+                from: a.id.id.clone(),
+                to: b.id.id.clone(),
+                level: TrustLevel::High,
+                date: Some(a_to_b_date),
+                kind: TrustLinkKind::Proof,
+            },
+            TrustLink {
+                from: b.id.id.clone(),
+                to: c.id.id.clone(),
+                level: TrustLevel::Medium,
+                date: Some(b_to_c_date),
+                kind: TrustLinkKind::Proof,
+            },
+        ]]
+    );
+
+    assert!(trustdb
+        .explain_trust(c.as_ref(), a.as_ref(), &Default::default())
+        .is_empty());
+    assert!(trustdb
+        .explain_trust(a.as_ref(), a.as_ref(), &Default::default())
+        .is_empty());
+    Ok(())
+}
+
+// The most recent Trust Proof wins, whatever order they're imported in
+#[test]
+fn most_recent_trust_proof_wins() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+
+    let trust = |days_ago: i64, level: TrustLevel| -> Result<crev_data::proof::Proof> {
+        let mut trust = a.create_trust_proof(vec![b.as_pubid().to_owned()], level)?;
+        trust.date = trust.date - chrono::Duration::days(days_ago);
+        Ok(trust.sign_by(&a)?)
+    };
+    // each import is sorted, but proofs of several repositories are not
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![trust(3, TrustLevel::High)?].into_iter());
+    trustdb.import_from_iter(vec![trust(1, TrustLevel::Distrust)?].into_iter());
+    trustdb.import_from_iter(vec![trust(2, TrustLevel::Medium)?].into_iter());
+
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &Default::default());
+    assert_eq!(trust_set.get_effective_trust_level(b.as_ref()), None);
+    Ok(())
+}
+
+// Trust that comes from no Trust Proof is still a step of the chain
+#[test]
+fn explain_trust_keeps_overridden_links() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let b_to_c = b.create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::Medium)?;
+    let b_to_c_date = b_to_c.date.with_timezone(&chrono::Utc);
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![b_to_c.sign_by(&b)?].into_iter());
+    assert!(trustdb.override_trust(a.as_ref(), b.as_ref(), TrustLevel::High));
+
+    let chains = trustdb.explain_trust(a.as_ref(), c.as_ref(), &Default::default());
+    assert_eq!(
+        chains,
+        vec![vec![
+            TrustLink {
+                from: a.id.id.clone(),
+                to: b.id.id.clone(),
+                level: TrustLevel::High,
+                date: None,
+                kind: TrustLinkKind::Override,
+            },
+            TrustLink {
+                from: b.id.id.clone(),
+                to: c.id.id.clone(),
+                level: TrustLevel::Medium,
+                date: Some(b_to_c_date),
+                kind: TrustLinkKind::Proof,
+            },
+        ]]
+    );
+    Ok(())
+}
+
 // A subsequent review of exactly same package version
 // is supposed to overwrite the previous one, and it
 // should be visible in all the user-facing stats, listings
@@ -182,6 +503,8 @@ fn proofdb_distrust() -> Result<()> {
         medium_trust_distance: 10,
         low_trust_distance: 100,
         max_distance: 10000,
+        max_depth: None,
+        follow_id_links: false,
     };
 
     let a_to_bc = a
@@ -236,3 +559,4537 @@ fn proofdb_distrust() -> Result<()> {
 
     Ok(())
 }
+
+// The trust wizard shows every way an Id is reached in the WoT,
+// starting from the root.
+#[test]
+fn trust_paths() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+
+    let a_to_bc = a
+        .create_trust_proof(
+            vec![b.as_pubid().to_owned(), c.as_pubid().to_owned()],
+            TrustLevel::High,
+        )?
+        .sign_by(&a)?;
+    let b_to_d = b
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Medium)?
+        .sign_by(&b)?;
+    let c_to_d = c
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Low)?
+        .sign_by(&c)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_bc, b_to_d, c_to_d].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    let a_id: &crev_data::Id = a.as_ref();
+    let b_id: &crev_data::Id = b.as_ref();
+    let c_id: &crev_data::Id = c.as_ref();
+    let d_id: &crev_data::Id = d.as_ref();
+
+    assert!(trust_set.trust_paths_to(a_id).is_empty());
+    assert_eq!(
+        trust_set.trust_paths_to(b_id),
+        vec![vec![a_id.clone(), b_id.clone()]]
+    );
+
+    let mut paths = trust_set.trust_paths_to(d_id);
+    paths.sort();
+    let mut expected = vec![
+        vec![a_id.clone(), b_id.clone(), d_id.clone()],
+        vec![a_id.clone(), c_id.clone(), d_id.clone()],
+    ];
+    expected.sort();
+    assert_eq!(paths, expected);
+
+    Ok(())
+}
+
+// An imported id bundle makes the URL of an Id known, even before
+// any of its proofs were fetched.
+#[test]
+fn proofdb_imported_pub_id() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let b_id: &crev_data::Id = b.as_ref();
+
+    let mut trustdb = ProofDB::new();
+    assert!(trustdb.lookup_url(b_id).is_none());
+
+    let bundle = crev_data::id_bundle::PubIdBundleBuilder::default()
+        .id(b.id.clone())
+        .date(crev_common::now() - chrono::Duration::days(1))
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&b)?;
+    trustdb.import_pub_id_bundle(&bundle);
+    assert_eq!(trustdb.lookup_url(b_id), Some(&b.id.url));
+
+    // the URL the owner uses in their own proofs wins over an older bundle
+    let moved = OwnId::new(
+        crev_data::Url::new_git("https://b2".into()),
+        b.keypair.secret.as_bytes(),
+    )?;
+    let b_to_a = moved
+        .create_trust_proof(vec![a.as_pubid().to_owned()], TrustLevel::Low)?
+        .sign_by(&moved)?;
+    trustdb.import_from_iter(vec![b_to_a].into_iter());
+    assert_eq!(trustdb.lookup_url(b_id), Some(&moved.id.url));
+
+    Ok(())
+}
+
+// Proofs fetched from a repository are only imported if `ids.yaml`
+// lists their author, and doesn't mark it as revoked.
+#[test]
+fn id_directory() -> Result<()> {
+    use crate::id_directory::{IdDirectory, IdStatus};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let a2 = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let proof_by = |id: &OwnId| -> Result<_> {
+        id.create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::Low)?
+            .sign_by(id)
+    };
+
+    let mut directory = IdDirectory::default();
+    directory.update(std::slice::from_ref(&a.id), a.as_ref());
+    assert_eq!(directory.get(a.as_ref()).unwrap().status, IdStatus::Active);
+    assert!(directory.accepts(&proof_by(&a)?));
+    assert!(!directory.accepts(&proof_by(&a2)?));
+
+    directory.update(&[a.id.clone(), a2.id.clone()], a2.as_ref());
+    assert_eq!(directory.get(a.as_ref()).unwrap().status, IdStatus::Retired);
+    assert_eq!(directory.get(a2.as_ref()).unwrap().status, IdStatus::Active);
+    assert!(directory.accepts(&proof_by(&a)?));
+
+    let tmp_dir = tempdir::TempDir::new("crev-ids")?;
+    directory.ids[0].status = IdStatus::Revoked;
+    directory.store_to_dir(tmp_dir.path())?;
+
+    let mut directory = IdDirectory::read_from_dir(tmp_dir.path())?.unwrap();
+    directory.update(&[a.id.clone(), a2.id.clone()], a.as_ref());
+    assert_eq!(directory.get(a.as_ref()).unwrap().status, IdStatus::Revoked);
+    assert!(!directory.accepts(&proof_by(&a)?));
+    assert!(directory.accepts(&proof_by(&a2)?));
+
+    Ok(())
+}
+
+// Ids on an imported seed list are trusted provisionally, at low level,
+// and not the ids they trust, but any trust proof of the user takes
+// precedence.
+#[test]
+fn proofdb_seed_list() -> Result<()> {
+    let user = OwnId::generate_for_git_url("https://user");
+    let curator = OwnId::generate_for_git_url("https://curator");
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let user_id: &crev_data::Id = user.as_ref();
+
+    let seed_list = crev_data::seed_list::SeedListBuilder::default()
+        .from(curator.id.clone())
+        .name("Reviewers".into())
+        .ids(vec![a.id.clone().into(), b.id.clone().into()])
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&curator)?;
+    let user_to_b = user
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::Distrust)?
+        .sign_by(&user)?;
+    let a_to_c = a
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![user_to_b, a_to_c].into_iter());
+    trustdb.import_seed_list(user_id, &seed_list);
+    assert_eq!(trustdb.lookup_url(a.as_ref()), Some(&a.id.url));
+
+    let trust_set = trustdb.calculate_trust_set(user_id, &default());
+    assert_eq!(
+        trust_set.get_effective_trust_level(a.as_ref()),
+        Some(TrustLevel::Low)
+    );
+    assert_eq!(trust_set.get_effective_trust_level(c.as_ref()), None);
+    assert_eq!(trust_set.get_effective_trust_level(b.as_ref()), None);
+    assert_eq!(trust_set.get_effective_trust_level(curator.as_ref()), None);
+
+    let user_to_a = user
+        .create_trust_proof(vec![a.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&user)?;
+    trustdb.import_from_iter(vec![user_to_a].into_iter());
+    let trust_set = trustdb.calculate_trust_set(user_id, &default());
+    assert_eq!(
+        trust_set.get_effective_trust_level(a.as_ref()),
+        Some(TrustLevel::High)
+    );
+    assert_eq!(
+        trust_set.get_effective_trust_level(c.as_ref()),
+        Some(TrustLevel::High)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn trust_wizard_levels() {
+    use crate::trust_wizard::{Acquaintance::*, TrustWizardAnswers};
+
+    let answers = |fingerprint_confirmed, acquaintance, careful_reviewer| TrustWizardAnswers {
+        malicious: false,
+        fingerprint_confirmed,
+        acquaintance,
+        careful_reviewer,
+    };
+
+    for &(fingerprint_confirmed, acquaintance, careful_reviewer, level) in &[
+        (true, Personal, true, TrustLevel::High),
+        (true, Personal, false, TrustLevel::Medium),
+        (true, Reputation, true, TrustLevel::Medium),
+        (true, Stranger, false, TrustLevel::None),
+        // the key might not be theirs
+        (false, Personal, true, TrustLevel::Low),
+        (false, Stranger, true, TrustLevel::None),
+    ] {
+        assert_eq!(
+            answers(fingerprint_confirmed, acquaintance, careful_reviewer).trust_level(),
+            level
+        );
+    }
+
+    let malicious = TrustWizardAnswers {
+        malicious: true,
+        ..answers(true, Personal, true)
+    };
+    assert_eq!(malicious.trust_level(), TrustLevel::Distrust);
+}
+
+#[test]
+fn pretty_proof() -> Result<()> {
+    use chrono::{Duration, Utc};
+
+    let now = Utc::now();
+    assert_eq!(proof::humanize_age(&now, &now), "just now");
+    assert_eq!(
+        proof::humanize_age(&(now - Duration::minutes(1)), &now),
+        "1 minute ago"
+    );
+    assert_eq!(
+        proof::humanize_age(&(now - Duration::days(3)), &now),
+        "3 days ago"
+    );
+    assert_eq!(
+        proof::humanize_age(&(now + Duration::days(800)), &now),
+        "in 2 years"
+    );
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let mut a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+
+    let db = ProofDB::new();
+    let pretty = proof::Pretty::new(&a_to_b, &db).to_string();
+    assert!(pretty.contains("Trust:"));
+    assert!(pretty.contains(&format!("{} (https://b)", b.id.id)));
+    assert!(pretty.ends_with("Signature: valid\n"));
+
+    a_to_b.body = a_to_b.body.replace("high", "low");
+    assert_eq!(
+        proof::Pretty::new(&a_to_b, &db).signature_status(),
+        proof::SignatureStatus::Invalid
+    );
+
+    Ok(())
+}
+
+// Proofs with broken signatures can end up in a `ProofDB` only by a mistake,
+// but shouldn't bring the whole thing down.
+#[test]
+fn proofdb_ignores_invalid_proofs() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+
+    let mut a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    a_to_b.signature = "garbage".into();
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b].into_iter());
+
+    assert!(trustdb.all_known_ids().is_empty());
+    Ok(())
+}
+
+// KDF parameters are stored with the locked id, so it can be
+// unlocked no matter what the current defaults are.
+#[test]
+fn lock_with_kdf_params() -> Result<()> {
+    let id = OwnId::generate_for_git_url("https://a");
+    let params = id::KdfParams {
+        iterations: 2,
+        memory_size: 1024,
+        lanes: 2,
+    };
+
+    let locked = id::LockedId::from_own_id_with_kdf_params(&id, "pass", &params)?;
+    assert_eq!(locked.kdf_params(), params);
+
+    let tmp_dir = tempdir::TempDir::new("crev")?;
+    let path = tmp_dir.path().join("id.yaml");
+    locked.save_to(&path)?;
+    // saving again has to overwrite, not append
+    locked.save_to(&path)?;
+
+    let restored = id::LockedId::read_from_yaml_file(&path)?;
+    assert_eq!(restored.kdf_params(), params);
+    let unlocked = restored.to_unlocked(&|| Ok("pass".to_string()))?;
+    assert_eq!(id.id.id, unlocked.id.id);
+    assert!(restored.try_unlock("wrong")?.is_none());
+    Ok(())
+}
+
+// Two trust roots claiming the same project url, or the same project
+// name, collide.
+#[test]
+fn proofdb_project_identity() -> Result<()> {
+    use crev_data::proof::project::{project_id_for_url, ProjectBuilder};
+
+    let root = OwnId::generate_for_git_url("https://root");
+    let impostor = OwnId::generate_for_git_url("https://impostor");
+    let project = |id: &OwnId, name: &str, url: &str| -> Result<crev_data::proof::Proof> {
+        ProjectBuilder::default()
+            .from(id.id.clone())
+            .name(name.into())
+            .url(url.into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(id)
+    };
+    let foo_id = project_id_for_url("https://github.com/root/foo");
+    let bar_id = project_id_for_url("https://github.com/root/bar");
+
+    let mut trustdb = ProofDB::new();
+    match trustdb.get_project_identity(&foo_id) {
+        ProjectIdentity::Unknown => {}
+        _ => panic!("unexpected project identity"),
+    }
+
+    trustdb.import_from_iter(
+        vec![
+            project(&root, "foo", "https://github.com/root/foo")?,
+            project(&root, "bar", "https://github.com/root/bar")?,
+        ]
+        .into_iter(),
+    );
+    match trustdb.get_project_identity(&foo_id) {
+        ProjectIdentity::Unique(p) => assert_eq!(p.from.id, root.id.id),
+        _ => panic!("unexpected project identity"),
+    }
+
+    trustdb.import_from_iter(
+        vec![
+            project(&impostor, "foo", "https://github.com/root/foo")?,
+            project(&impostor, "Bar", "https://github.com/impostor/bar")?,
+        ]
+        .into_iter(),
+    );
+    for project_id in &[&foo_id, &bar_id] {
+        match trustdb.get_project_identity(project_id) {
+            ProjectIdentity::Colliding(projects) => assert_eq!(projects.len(), 2),
+            _ => panic!("unexpected project identity"),
+        }
+    }
+
+    Ok(())
+}
+
+// Only the trust root of a project can migrate it, and reviews of the old
+// project follow it to the new one.
+#[test]
+fn proofdb_project_migration() -> Result<()> {
+    use crev_data::proof::{
+        project::{project_id_for_url, ProjectBuilder},
+        project_migration::ProjectMigrationBuilder,
+    };
+
+    let root = OwnId::generate_for_git_url("https://root");
+    let impostor = OwnId::generate_for_git_url("https://impostor");
+    let reviewer = OwnId::generate_for_git_url("https://reviewer");
+    let project = |url: &str| -> Result<crev_data::proof::Proof> {
+        ProjectBuilder::default()
+            .from(root.id.clone())
+            .name("foo".into())
+            .url(url.into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(&root)
+    };
+    let migration = |id: &OwnId, from: &str, to: &str| -> Result<crev_data::proof::Proof> {
+        ProjectMigrationBuilder::default()
+            .from(id.id.clone())
+            .project(project_id_for_url(from))
+            .url(to.into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(id)
+    };
+    let review = |source: &str| -> Result<crev_data::proof::Proof> {
+        let package = crev_data::proof::PackageInfo {
+            version: "1.0.0".into(),
+            digest: vec![0; 32],
+            ..package_info(source, "foo")
+        };
+        reviewer
+            .create_package_review_proof(package, default(), "".into())?
+            .sign_by(&reviewer)
+    };
+    let old_url = "https://github.com/root/foo";
+    let new_url = "https://github.com/root/foo2";
+    let old_id = project_id_for_url(old_url);
+    let new_id = project_id_for_url(new_url);
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(
+        vec![
+            project(old_url)?,
+            project(new_url)?,
+            migration(&root, old_url, new_url)?,
+            migration(&impostor, new_url, "https://github.com/impostor/foo")?,
+            review(old_url)?,
+            review("https://github.com/root/foo2.git")?,
+        ]
+        .into_iter(),
+    );
+
+    assert_eq!(
+        trustdb
+            .get_project_migration(&old_id)
+            .map(|m| m.successor_id()),
+        Some(new_id.clone())
+    );
+    assert!(trustdb.get_project_migration(&new_id).is_none());
+    assert_eq!(trustdb.get_latest_project_id(&old_id), new_id);
+    assert_eq!(
+        trustdb.get_project_lineage(&new_id),
+        vec![new_id.clone(), old_id.clone()]
+    );
+    assert_eq!(trustdb.get_package_reviews_for_project(&new_id).count(), 2);
+    assert_eq!(trustdb.get_package_reviews_for_project(&old_id).count(), 1);
+
+    Ok(())
+}
+
+// In a monorepo, every `.crev` scope covers its own files only, so changes
+// to a nested scope don't affect the digest of the outer one.
+#[test]
+fn monorepo_scopes() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("crev-monorepo")?;
+    let root = tmp_dir.path();
+    let git_repo = git2::Repository::init(root)?;
+    for (path, content) in &[
+        ("a.txt", "a"),
+        ("comp/.crev/config.yaml", "version: 0"),
+        ("comp/b.txt", "b"),
+        ("other/c.txt", "c"),
+    ] {
+        let full_path = root.join(path);
+        std::fs::create_dir_all(full_path.parent().unwrap())?;
+        std::fs::write(&full_path, content)?;
+        let mut index = git_repo.index()?;
+        index.add_path(std::path::Path::new(path))?;
+        index.write()?;
+    }
+
+    assert!(repo::is_in_nested_scope(root, "comp/b.txt".as_ref()));
+    assert!(repo::is_in_nested_scope(
+        root,
+        "comp/.crev/config.yaml".as_ref()
+    ));
+    assert!(!repo::is_in_nested_scope(root, "other/c.txt".as_ref()));
+
+    let comp_paths: Vec<_> = git_scope_statuses(&root.join("comp"))?
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    assert_eq!(comp_paths.len(), 2);
+    assert!(comp_paths.contains(&"b.txt".into()));
+
+    let ignore_list = HashSet::new();
+    let digests = || -> Result<(String, String)> {
+        Ok((
+            get_recursive_digest_for_git_dir(root, &ignore_list)?.to_string(),
+            get_recursive_digest_for_git_dir(&root.join("comp"), &ignore_list)?.to_string(),
+        ))
+    };
+    let (root_digest, comp_digest) = digests()?;
+    std::fs::write(root.join("comp/b.txt"), "changed")?;
+    let (new_root_digest, new_comp_digest) = digests()?;
+    assert_eq!(new_root_digest, root_digest);
+    assert_ne!(new_comp_digest, comp_digest);
+
+    // ignoring a directory ignores all the files in it
+    let ignore_other: HashSet<PathBuf> = vec!["other".into()].into_iter().collect();
+    let without_other = get_recursive_digest_for_git_dir(root, &ignore_other)?.to_string();
+    std::fs::write(root.join("other/c.txt"), "changed")?;
+    assert_eq!(
+        get_recursive_digest_for_git_dir(root, &ignore_other)?.to_string(),
+        without_other
+    );
+    assert_ne!(without_other, root_digest);
+
+    Ok(())
+}
+
+#[test]
+fn nested_scopes() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("crev-nested")?;
+    let root = tmp_dir.path();
+    for dir in &[".crev", "comp/.crev", "comp/sub/.crev", "other/.git/.crev"] {
+        std::fs::create_dir_all(root.join(dir))?;
+    }
+
+    let repo = repo::Repo::open(root)?;
+    let expected: Vec<PathBuf> = vec!["comp".into(), "comp/sub".into()];
+    assert_eq!(repo.nested_scopes()?, expected);
+    Ok(())
+}
+
+#[test]
+fn diff_between_versions() -> Result<()> {
+    use crate::dir_diff::{ChangedFile, FileChange};
+
+    let tmp_dir = tempdir::TempDir::new("crev-diff")?;
+    let old_dir = tmp_dir.path().join("foo-1.0.0");
+    let new_dir = tmp_dir.path().join("foo-1.1.0");
+    let staged_dir = tmp_dir.path().join("staged");
+    for (dir, path, content) in &[
+        (&old_dir, "Cargo.toml", "version = \"1.0.0\""),
+        (&old_dir, "src/lib.rs", "same"),
+        (&old_dir, "src/old.rs", "old"),
+        (&old_dir, "target/junk", "old"),
+        (&new_dir, "Cargo.toml", "version = \"1.1.0\""),
+        (&new_dir, "src/lib.rs", "same"),
+        (&new_dir, "src/new.rs", "new"),
+        (&new_dir, "target/junk", "new"),
+    ] {
+        let full_path = dir.join(path);
+        std::fs::create_dir_all(full_path.parent().unwrap())?;
+        std::fs::write(&full_path, content)?;
+    }
+
+    let ignore_list: HashSet<PathBuf> = vec!["target".into()].into_iter().collect();
+    let changes = dir_diff::changed_files(&old_dir, &new_dir, &ignore_list)?;
+    let change = |path: &str, change| ChangedFile {
+        path: path.into(),
+        change,
+    };
+    assert_eq!(
+        changes,
+        vec![
+            change("Cargo.toml", FileChange::Modified),
+            change("src/new.rs", FileChange::Added),
+            change("src/old.rs", FileChange::Removed),
+        ]
+    );
+
+    dir_diff::stage_changed_files(&new_dir, &changes, &staged_dir)?;
+    assert_eq!(
+        std::fs::read_to_string(staged_dir.join("src/new.rs"))?,
+        "new"
+    );
+    assert!(staged_dir.join("Cargo.toml").exists());
+    assert!(!staged_dir.join("src/lib.rs").exists());
+    assert!(!staged_dir.join("src/old.rs").exists());
+
+    Ok(())
+}
+
+#[test]
+fn rust_source_metrics() -> Result<()> {
+    let source = r####"
+        // unsafe in a comment
+        /* and /* nested */ unsafe */
+        const S: &str = "unsafe \" unsafe";
+        const R: &str = r#"unsafe " unsafe"#;
+        const C: char = '\'';
+        fn f<'a>(x: &'a u8) -> u8 {
+            unsafe { *(x as *const u8) }
+        }
+        unsafe impl Send for Foo {}
+        #[proc_macro_derive(Foo)]
+        pub fn foo(input: TokenStream) -> TokenStream { input }
+    "####;
+    let metrics = rust_metrics::source_metrics(source);
+    assert_eq!(metrics.unsafe_count, 2);
+    assert!(metrics.proc_macro);
+    assert!(!rust_metrics::source_metrics("fn proc_macro() {}").proc_macro);
+
+    let tmp_dir = tempdir::TempDir::new("crev-metrics")?;
+    let root = tmp_dir.path();
+    std::fs::write(root.join("build.rs"), "fn main() { unsafe {} }")?;
+    std::fs::write(root.join("Cargo.toml"), "[lib]\nproc-macro = true")?;
+    std::fs::write(root.join("README.md"), "unsafe")?;
+    std::fs::write(root.join("latin1.rs"), b"// caf\xe9\nunsafe fn f() {}\n")?;
+
+    let metrics = |path: &str| rust_metrics::file_metrics(path.as_ref(), &root.join(path));
+    let build_rs = metrics("build.rs")?.expect("metrics of build.rs");
+    assert!(build_rs.build_script);
+    assert_eq!(build_rs.unsafe_count, 1);
+    assert!(
+        metrics("Cargo.toml")?
+            .expect("metrics of Cargo.toml")
+            .proc_macro
+    );
+    assert!(metrics("README.md")?.is_none());
+    assert_eq!(
+        metrics("latin1.rs")?
+            .expect("metrics of latin1.rs")
+            .unsafe_count,
+        1
+    );
+
+    Ok(())
+}
+
+// Requirements can be only for Rust files with risky metrics
+#[test]
+fn path_requirement_metrics() -> Result<()> {
+    use crate::policy::{required_metrics, unmet_requirements, PathRequirement};
+    use crev_data::proof::review::code::FileKind::Code;
+
+    let requirements: Vec<PathRequirement> = serde_yaml::from_str(
+        "- path: '**'\n  reviewers: 2\n  metrics: [unsafe, build-script]\n",
+    )?;
+    assert_eq!(
+        requirements[0].to_string(),
+        "** needs 2 reviewers with low trust with unsafe, build-script"
+    );
+
+    let tmp_dir = tempdir::TempDir::new("crev-metrics-policy")?;
+    let root = tmp_dir.path();
+    std::fs::write(root.join("safe.rs"), "fn f() {}")?;
+    std::fs::write(root.join("unsafe.rs"), "unsafe fn f() {}")?;
+    std::fs::write(root.join("build.rs"), "fn main() {}")?;
+    std::fs::write(root.join("README.md"), "unsafe")?;
+
+    let unmet = |path: &str| -> Result<usize> {
+        let metrics = required_metrics(&requirements, path.as_ref(), &root.join(path))?;
+        Ok(unmet_requirements(
+            &requirements,
+            &default(),
+            path.as_ref(),
+            Code,
+            metrics.as_ref(),
+            &default(),
+            &default(),
+            &default(),
+        )?
+        .len())
+    };
+    assert_eq!(unmet("safe.rs")?, 0);
+    assert_eq!(unmet("unsafe.rs")?, 1);
+    assert_eq!(unmet("build.rs")?, 1);
+    assert_eq!(unmet("README.md")?, 0);
+
+    // files aren't read for requirements that don't depend on metrics
+    let plain: Vec<PathRequirement> = serde_yaml::from_str("- path: '**'\n  reviewers: 1\n")?;
+    assert!(required_metrics(&plain, "missing.rs".as_ref(), &root.join("missing.rs"))?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn review_size_metrics() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("crev-size")?;
+    let root = tmp_dir.path();
+    std::fs::write(root.join("lib.rs"), "fn main() {\n\n    \n}\n")?;
+    std::fs::write(root.join("logo.png"), b"\x89PNG\n\x00\n\n")?;
+
+    assert_eq!(size_metrics::count_lines(&root.join("lib.rs"))?, 2);
+    assert_eq!(size_metrics::count_lines(&root.join("logo.png"))?, 0);
+    assert_eq!(
+        size_metrics::language_for_path("src/Lib.RS".as_ref()),
+        Some("rust")
+    );
+    assert_eq!(size_metrics::language_for_path("logo.png".as_ref()), None);
+
+    let mut size = crev_data::proof::review::SizeMetrics::default();
+    size.add_file(Some("rust"), 11_500);
+    size.add_file(Some("markdown"), 834);
+    size.add_file(None, 12);
+    size.add_file(Some("rust"), 100);
+    assert_eq!(size.files, 4);
+    assert_eq!(
+        size.to_string(),
+        "12k LOC in 4 files (rust: 11k, markdown: 834)"
+    );
+
+    Ok(())
+}
+
+// Paths are relative to the scope, even when it's not the root of the
+// git repository.
+#[test]
+fn git_vcs_of_nested_scope() -> Result<()> {
+    use crate::repo::vcs;
+
+    let tmp_dir = tempdir::TempDir::new("crev-vcs")?;
+    let root = tmp_dir.path();
+    let git_repo = git2::Repository::init(root)?;
+    let commit = |files: &[(&str, &str)]| -> Result<String> {
+        let mut index = git_repo.index()?;
+        for (path, content) in files {
+            let full_path = root.join(path);
+            std::fs::create_dir_all(full_path.parent().unwrap())?;
+            std::fs::write(&full_path, content)?;
+            index.add_path(std::path::Path::new(path))?;
+        }
+        index.write()?;
+        let tree = git_repo.find_tree(index.write_tree()?)?;
+        let signature = git2::Signature::now("Alice", "alice@example.com")?;
+        let parent = match git_repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(_) => None,
+        };
+        let parents: Vec<_> = parent.iter().collect();
+        Ok(git_repo
+            .commit(Some("HEAD"), &signature, &signature, "", &tree, &parents)?
+            .to_string())
+    };
+
+    let first = commit(&[("comp/a.txt", "a1"), ("other.txt", "o1")])?;
+    let second = commit(&[("comp/b.txt", "b1"), ("other.txt", "o2")])?;
+
+    let vcs = vcs::open(&root.join("comp"))?.expect("in a git repository");
+    assert_eq!(vcs.revision()?.revision, second);
+    assert_eq!(vcs.revision()?.revision_type, "git");
+    assert!(!vcs.is_dirty()?);
+    assert_eq!(
+        vcs.file_at_revision(&first, "a.txt".as_ref())?,
+        Some(b"a1".to_vec())
+    );
+    assert_eq!(vcs.file_at_revision(&first, "b.txt".as_ref())?, None);
+    assert_eq!(
+        vcs.changed_files(&first, &second)?,
+        vec![PathBuf::from("b.txt")]
+    );
+
+    std::fs::write(root.join("other.txt"), "o3")?;
+    assert!(!vcs.is_dirty()?);
+    std::fs::write(root.join("comp/a.txt"), "a2")?;
+    assert!(vcs.is_dirty()?);
+
+    let outside = tempdir::TempDir::new("crev-no-vcs")?;
+    assert!(vcs::open(outside.path())?.is_none());
+    Ok(())
+}
+
+#[test]
+fn history_summary_since_last_review() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("crev-history")?;
+    let root = tmp_dir.path();
+    let git_repo = git2::Repository::init(root)?;
+    let commit = |author: &str, path: &str, content: &str, message: &str| -> Result<git2::Oid> {
+        let full_path = root.join(path);
+        std::fs::create_dir_all(full_path.parent().unwrap())?;
+        std::fs::write(&full_path, content)?;
+        let mut index = git_repo.index()?;
+        index.add_path(std::path::Path::new(path))?;
+        index.write()?;
+        let tree = git_repo.find_tree(index.write_tree()?)?;
+        let signature = git2::Signature::now(author, "author@example.com")?;
+        let parent = match git_repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(_) => None,
+        };
+        let parents: Vec<_> = parent.iter().collect();
+        Ok(git_repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )?)
+    };
+
+    let reviewed = commit("Alice", "src/lib.rs", "v1", "Initial version")?;
+    commit("Bob", "src/lib.rs", "v2", "Tweak lib")?;
+    commit("Alice", "build.rs", "fn main() {}", "Add a build script")?;
+    commit("Alice", "README.md", "readme", "Document")?;
+
+    let id = OwnId::generate_for_git_url("https://a");
+    std::fs::create_dir_all(root.join(".crev/proofs"))?;
+    let repo = repo::Repo::open(root)?;
+    let summary = repo.history_summary(&id.id.id)?;
+    assert!(summary.starts_with("Changes up to "));
+    assert!(summary.contains("4 commits by Alice (3), Bob (1)"));
+
+    let package = crev_data::proof::PackageInfo {
+        revision: reviewed.to_string(),
+        revision_type: "git".into(),
+        ..package_info("", "history")
+    };
+    let review = crev_data::proof::review::CodeBuilder::default()
+        .from(id.id.clone())
+        .package(package)
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&id)?;
+    std::fs::write(
+        root.join(".crev/proofs/review.proof.crev"),
+        review.to_string(),
+    )?;
+
+    let summary = repo.history_summary(&id.id.id)?;
+    assert!(summary.starts_with(&format!("Changes since {}", &reviewed.to_string()[..7])));
+    assert!(summary.contains("3 commits by Alice (2), Bob (1)"));
+    assert!(summary.contains(" Tweak lib\n"));
+    assert!(!summary.contains("Initial version"));
+    assert!(summary.contains("* added build.rs"));
+    assert!(summary.contains("* added README.md"));
+    assert!(!summary.contains("src/lib.rs"));
+
+    // reviews by others don't count
+    let other = OwnId::generate_for_git_url("https://b");
+    assert!(repo
+        .history_summary(&other.id.id)?
+        .starts_with("Changes up to "));
+
+    Ok(())
+}
+
+#[test]
+fn proof_reports() -> Result<()> {
+    use crate::proof::ReportFormat;
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let review = crev_data::proof::review::CodeBuilder::default()
+        .from(a.id.clone())
+        .package(crev_data::proof::PackageInfo {
+            version: "1.0".into(),
+            ..package_info("https://example.com", "foo")
+        })
+        .comment("Checked <script> handling\nAll good".into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&a)?;
+    let db = ProofDB::new();
+    let pretty = proof::Pretty::new(&review, &db);
+
+    let markdown = pretty.to_report(ReportFormat::Markdown);
+    assert!(markdown.starts_with("# Code Review of 0 files of foo 1.0\n"));
+    assert!(markdown.contains("* **Signature**: valid\n"));
+    assert!(markdown.contains("  Checked <script> handling\n  All good\n"));
+    assert!(markdown.contains(&review.to_string()));
+
+    let html = pretty.to_report(ReportFormat::Html);
+    assert!(html.contains("<h1>Code Review of 0 files of foo 1.0</h1>"));
+    assert!(html.contains("Checked &lt;script&gt; handling"));
+    assert!(!html.contains("<script>"));
+
+    assert_eq!("md".parse::<ReportFormat>()?, ReportFormat::Markdown);
+    assert!("pdf".parse::<ReportFormat>().is_err());
+
+    // values can't break out of their list item, or code block
+    let review = crev_data::proof::review::CodeBuilder::default()
+        .from(a.id.clone())
+        .package(package_info("https://example.com", "foo"))
+        .comment("a | b *c*".into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&a)?;
+    let markdown = proof::Pretty::new(&review, &db).to_report(ReportFormat::Markdown);
+    assert!(markdown.contains("* **Comment**: a \\| b \\*c\\*\n"));
+    let review = crev_data::proof::review::CodeBuilder::default()
+        .from(a.id.clone())
+        .package(package_info("https://example.com", "foo"))
+        .comment("```\n# Not a heading".into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&a)?;
+    let markdown = proof::Pretty::new(&review, &db).to_report(ReportFormat::Markdown);
+    assert!(markdown.contains("  ````text\n  ```\n  # Not a heading\n  ````\n"));
+    assert!(markdown.contains("\n````text\n"));
+
+    Ok(())
+}
+
+#[test]
+fn dashboard_html() -> Result<()> {
+    use crate::dashboard::{DashboardInfo, TrustedId};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let review = crev_data::proof::review::CodeBuilder::default()
+        .from(b.id.clone())
+        .package(crev_data::proof::PackageInfo {
+            version: "1.0".into(),
+            ..package_info("https://example.com", "<foo>")
+        })
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&b)?;
+
+    let info = DashboardInfo {
+        current_id: Some(a.id.id.clone()),
+        scopes: vec![
+            (
+                PathBuf::new(),
+                VerificationStatus::Verified(TrustLevel::High),
+            ),
+            ("sub".into(), VerificationStatus::Unknown),
+        ],
+        trusted: vec![TrustedId {
+            id: b.id.id.clone(),
+            url: Some(b.id.url.clone()),
+            level: TrustLevel::Medium,
+            path: vec![a.id.id.clone(), b.id.id.clone()],
+        }],
+        recent_proofs: vec![review],
+        advisories: vec![crev_data::proof::advisory::AdvisoryBuilder::default()
+            .from(b.id.clone())
+            .project("<bar>".into())
+            .severity(crev_data::proof::advisory::Severity::High)
+            .comment("don't use".into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?],
+    };
+    let html = info.to_html();
+    assert!(html.contains(&format!("Current id: <code>{}</code>", a.id.id)));
+    assert!(html.contains("<tr><td>.</td><td>high</td></tr>"));
+    assert!(html.contains("<tr><td>sub</td><td>unknown</td></tr>"));
+    assert!(html.contains(&format!("{} -&gt; {}", a.id.id, b.id.id)));
+    assert!(html.contains("Code Review of 0 files of &lt;foo&gt; 1.0"));
+    assert!(!html.contains("<foo>"));
+    assert!(html.contains("<tr><td>&lt;bar&gt;</td><td>high</td>"));
+
+    let empty = DashboardInfo {
+        current_id: None,
+        scopes: vec![],
+        trusted: vec![],
+        recent_proofs: vec![],
+        advisories: vec![],
+    };
+    assert!(empty
+        .to_html()
+        .contains("Not in a <code>.crev</code> scope."));
+
+    Ok(())
+}
+
+// The dashboard is only served to requests naming it as a local host
+#[test]
+fn dashboard_only_serves_local_hosts() {
+    use crate::dashboard::is_local_host;
+
+    assert!(is_local_host("127.0.0.1:8080", 8080));
+    assert!(is_local_host("LocalHost:8080", 8080));
+    assert!(is_local_host("[::1]:8080", 8080));
+    assert!(is_local_host("localhost", 8080));
+    assert!(!is_local_host("localhost:8081", 8080));
+    assert!(!is_local_host("evil.example.com:8080", 8080));
+    assert!(!is_local_host("localhost.evil.example.com:8080", 8080));
+    assert!(!is_local_host("127.0.0.1.evil.example.com", 8080));
+}
+
+// Files are verified by Code Review Proofs of their content, counting
+// only reviews by trusted ids.
+#[test]
+fn proofdb_file_verification() -> Result<()> {
+    use crev_data::proof::review::Rating;
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let code_review = |author: &OwnId, digest: Vec<u8>, rating: Rating| {
+        code_review(author, rating, vec![reviewed_file("src/lib.rs", digest)])
+    };
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let b_review = code_review(&b, vec![1], Rating::Positive)?.sign_by(&b)?;
+    let c_review = code_review(&c, vec![2], Rating::Positive)?.sign_by(&c)?;
+    let c_flag = code_review(&c, vec![1], Rating::Dangerous)?.sign_by(&c)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, b_review, c_review, c_flag].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    // c is not trusted, so its flag doesn't count
+    assert_eq!(
+        trustdb.verify_file_digest(&[1], &[], &trust_set),
+        VerificationStatus::Verified(TrustLevel::High)
+    );
+    assert_eq!(trustdb.get_code_reviews_by_file_digest(&[1], &[]).count(), 2);
+    assert_eq!(
+        trustdb.verify_file_digest(&[2], &[], &trust_set),
+        VerificationStatus::Unknown
+    );
+    assert_eq!(trustdb.get_code_reviews_by_file_digest(&[3], &[]).count(), 0);
+
+    Ok(())
+}
+
+// Proofs stored in a `.crev` scope count only if authored by its trust
+// root, or by ids it trusts through Trust Proofs stored there too.
+#[test]
+fn scope_proofs_authorized_by_trust_root() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+    let e = OwnId::generate_for_git_url("https://e");
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let b_to_d = b
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Medium)?
+        .sign_by(&b)?;
+    let c_to_e = c
+        .create_trust_proof(vec![e.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&c)?;
+    let d_to_e = d
+        .create_trust_proof(vec![e.as_pubid().to_owned()], TrustLevel::Distrust)?
+        .sign_by(&d)?;
+
+    let (authorized, foreign) = repo::split_authorized_proofs(
+        vec![
+            a_to_b.clone(),
+            c_to_e.clone(),
+            b_to_d.clone(),
+            d_to_e.clone(),
+        ],
+        a.as_ref(),
+    );
+    let digests = |proofs: &[crev_data::proof::Proof]| -> Vec<Vec<u8>> {
+        proofs.iter().map(|proof| proof.digest.clone()).collect()
+    };
+    assert_eq!(
+        digests(&authorized),
+        digests(&[a_to_b.clone(), b_to_d, d_to_e])
+    );
+    assert_eq!(digests(&foreign), digests(&[c_to_e]));
+
+    // a hijacked config pointing at another trust root
+    let (authorized, foreign) = repo::split_authorized_proofs(vec![a_to_b], c.as_ref());
+    assert!(authorized.is_empty());
+    assert_eq!(foreign.len(), 1);
+
+    Ok(())
+}
+
+// After a trust root hands over to a new one, proofs the old trust root
+// made before the handover still count, but not the ones made after it.
+#[test]
+fn scope_proofs_after_trust_root_handover() -> Result<()> {
+    use chrono::Duration;
+    use crev_data::proof::trust_root_handover::TrustRootHandoverBuilder;
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let a_to_c = a
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::Medium)?
+        .sign_by(&a)?;
+    let handover = |date| -> Result<crev_data::proof::Proof> {
+        TrustRootHandoverBuilder::default()
+            .date(date)
+            .from(a.id.clone())
+            .to(b.id.clone())
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(&a)
+    };
+    let digests = |proofs: &[crev_data::proof::Proof]| -> Vec<Vec<u8>> {
+        proofs.iter().map(|proof| proof.digest.clone()).collect()
+    };
+
+    let later = handover(crev_common::now() + Duration::days(1))?;
+    assert_eq!(
+        repo::trust_root_chain(std::slice::from_ref(&later), b.as_ref())
+            .iter()
+            .map(|(root, _)| root.clone())
+            .collect::<Vec<_>>(),
+        vec![b.id.id.clone(), a.id.id.clone()]
+    );
+    let (authorized, foreign) =
+        repo::split_authorized_proofs(vec![a_to_c.clone(), later.clone()], b.as_ref());
+    assert_eq!(digests(&authorized), digests(&[a_to_c.clone(), later]));
+    assert!(foreign.is_empty());
+
+    let earlier = handover(crev_common::now() - Duration::days(1))?;
+    let (authorized, foreign) =
+        repo::split_authorized_proofs(vec![a_to_c.clone(), earlier.clone()], b.as_ref());
+    assert_eq!(digests(&authorized), digests(&[earlier]));
+    assert_eq!(digests(&foreign), digests(&[a_to_c]));
+
+    Ok(())
+}
+
+// Paths matching a requirement need enough distinct reviewers with
+// enough trust.
+#[test]
+fn path_requirements() -> Result<()> {
+    use crate::policy::{approving_reviewers, unmet_requirements, PathRequirement};
+    use crev_data::proof::review::{code::FileKind::Code, Rating};
+
+    let requirements: Vec<PathRequirement> = serde_yaml::from_str(
+        "- path: src/crypto/**\n  reviewers: 2\n  trust: high\n- path: '*.toml'\n  reviewers: 1\n",
+    )?;
+    assert_eq!(requirements[1].trust, TrustLevel::Low);
+    assert!(requirements[0].matches(Path::new("src/crypto/aes/mod.rs"))?);
+    assert!(!requirements[0].matches(Path::new("src/cryptography.rs"))?);
+    assert!(requirements[1].matches(Path::new("Cargo.toml"))?);
+    assert!(!requirements[1].matches(Path::new("sub/Cargo.toml"))?);
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+    let code_review = |author: &OwnId, rating: Rating| {
+        code_review(
+            author,
+            rating,
+            vec![reviewed_file("src/crypto/aes.rs", vec![1])],
+        )
+    };
+
+    let a_to_bc = a
+        .create_trust_proof(
+            vec![b.as_pubid().to_owned(), c.as_pubid().to_owned()],
+            TrustLevel::High,
+        )?
+        .sign_by(&a)?;
+    let a_to_d = a
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Low)?
+        .sign_by(&a)?;
+    let b_review = code_review(&b, Rating::Positive)?.sign_by(&b)?;
+    let d_review = code_review(&d, Rating::Positive)?.sign_by(&d)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_bc, a_to_d, b_review, d_review].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    let path = Path::new("src/crypto/aes.rs");
+    let approvers = approving_reviewers(&trustdb, &trust_set, &[1], &[], None);
+    assert_eq!(approvers.len(), 2);
+    // d doesn't have enough trust to count
+    assert_eq!(
+        unmet_requirements(
+            &requirements,
+            &default(),
+            path,
+            Code,
+            None,
+            &default(),
+            &approvers,
+            &default()
+        )?,
+        vec![&requirements[0]]
+    );
+
+    let c_review = code_review(&c, Rating::Strong)?.sign_by(&c)?;
+    trustdb.import_from_iter(vec![c_review].into_iter());
+    let approvers = approving_reviewers(&trustdb, &trust_set, &[1], &[], None);
+    assert!(unmet_requirements(
+        &requirements,
+        &default(),
+        path,
+        Code,
+        None,
+        &default(),
+        &approvers,
+        &default()
+    )?
+    .is_empty());
+
+    Ok(())
+}
+
+// Only reviews by the owners of a path count, be they listed directly or
+// by group.
+#[test]
+fn path_requirement_owners() -> Result<()> {
+    use crate::policy::{unmet_requirements, PathRequirement, ReviewerGroups};
+    use crev_data::proof::review::code::FileKind::Code;
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let requirements: Vec<PathRequirement> = serde_yaml::from_str(&format!(
+        "- path: src/crypto/**\n  reviewers: 2\n  owners: ['@crypto', '{}']\n",
+        c.id.id
+    ))?;
+    assert_eq!(
+        requirements[0].to_string(),
+        format!(
+            "src/crypto/** needs 2 reviewers with low trust among @crypto, {}",
+            c.id.id
+        )
+    );
+    let mut groups = ReviewerGroups::new();
+    groups.insert("crypto".into(), vec![a.id.id.to_string()]);
+
+    let path = Path::new("src/crypto/aes.rs");
+    let approvers = |ids: &[&OwnId]| -> std::collections::HashMap<crev_data::Id, TrustLevel> {
+        ids.iter()
+            .map(|id| (id.id.id.clone(), TrustLevel::High))
+            .collect()
+    };
+    // b is trusted, but not an owner
+    assert_eq!(
+        unmet_requirements(
+            &requirements,
+            &groups,
+            path,
+            Code,
+            None,
+            &default(),
+            &approvers(&[&a, &b]),
+            &default()
+        )?,
+        vec![&requirements[0]]
+    );
+    assert!(unmet_requirements(
+        &requirements,
+        &groups,
+        path,
+        Code,
+        None,
+        &default(),
+        &approvers(&[&a, &c]),
+        &default()
+    )?
+    .is_empty());
+    assert!(unmet_requirements(
+        &requirements,
+        &default(),
+        path,
+        Code,
+        None,
+        &default(),
+        &approvers(&[&a, &c]),
+        &default()
+    )
+    .is_err());
+    assert!(unmet_requirements(
+        &requirements,
+        &groups,
+        Path::new("src/lib.rs"),
+        Code,
+        None,
+        &default(),
+        &approvers(&[&b]),
+        &default()
+    )?
+    .is_empty());
+
+    Ok(())
+}
+
+// Proofs of an id restricted to some capabilities by its bundle count
+// only if they are of those kinds.
+#[test]
+fn proofdb_restricted_capabilities() -> Result<()> {
+    use crev_data::id_bundle::{Capability, PubIdBundleBuilder};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let bot = OwnId::generate_for_git_url("https://bot");
+    let c = OwnId::generate_for_git_url("https://c");
+    let bot_id: &crev_data::Id = bot.as_ref();
+    let c_id: &crev_data::Id = c.as_ref();
+    let digest = vec![0; 32];
+    let package = crev_data::proof::PackageInfo {
+        version: "version".into(),
+        digest: digest.clone(),
+        ..package_info("source", "name")
+    };
+
+    let a_to_bot = a
+        .create_trust_proof(vec![bot.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let bot_to_c = bot
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&bot)?;
+    let bot_review = bot
+        .create_package_review_proof(package, default(), "".into())?
+        .sign_by(&bot)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_bot, bot_to_c, bot_review].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    assert!(trust_set.get_effective_trust_level(c_id).is_some());
+    assert!(trustdb
+        .verify_package_digest(&Digest::from_vec(digest.clone()), &trust_set)
+        .is_verified());
+
+    let bundle = PubIdBundleBuilder::default()
+        .id(bot.id.clone())
+        .capabilities(Some(vec![Capability::CodeReview]))
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&bot)?;
+    trustdb.import_pub_id_bundle(&bundle);
+    assert!(trustdb.allows(bot_id, Capability::CodeReview));
+    assert!(!trustdb.allows(bot_id, Capability::Trust));
+    // an older bundle doesn't lift the restriction, whenever it's imported
+    let mut older = PubIdBundleBuilder::default()
+        .id(bot.id.clone())
+        .capabilities(Some(vec![Capability::CodeReview, Capability::Trust]))
+        .build()
+        .map_err(|e| format_err!("{}", e))?;
+    older.date = older.date - chrono::Duration::days(1);
+    trustdb.import_pub_id_bundle(&older.sign_by(&bot)?);
+    assert!(!trustdb.allows(bot_id, Capability::Trust));
+
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    assert_eq!(
+        trust_set.get_effective_trust_level(bot_id),
+        Some(TrustLevel::High)
+    );
+    assert!(trust_set.get_effective_trust_level(c_id).is_none());
+    assert!(!trustdb
+        .verify_package_digest(&Digest::from_vec(digest), &trust_set)
+        .is_verified());
+
+    Ok(())
+}
+
+// An id restricted to attesting builds has its Build Attestation Proofs
+// count, but not its Package Review Proofs.
+#[test]
+fn proofdb_build_attestation_capability() -> Result<()> {
+    use crev_data::{
+        id_bundle::{Capability, PubIdBundleBuilder},
+        proof::build_attestation::{Artifact, BuildAttestationBuilder},
+    };
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let bot = OwnId::generate_for_git_url("https://bot");
+    let bot_id: &crev_data::Id = bot.as_ref();
+    let digest = vec![0; 32];
+    let package = crev_data::proof::PackageInfo {
+        version: "version".into(),
+        digest: digest.clone(),
+        revision: "abcdef".into(),
+        ..package_info("source", "name")
+    };
+
+    let a_to_bot = a
+        .create_trust_proof(vec![bot.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let bot_review = bot
+        .create_package_review_proof(package.clone(), default(), "".into())?
+        .sign_by(&bot)?;
+    let bot_attestation = BuildAttestationBuilder::default()
+        .from(bot.id.clone())
+        .package(package)
+        .artifact(Artifact {
+            name: "name".into(),
+            digest: vec![9, 9],
+            digest_type: crev_data::proof::default_digest_type(),
+        })
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&bot)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_bot, bot_review, bot_attestation].into_iter());
+    let bundle = PubIdBundleBuilder::default()
+        .id(bot.id.clone())
+        .capabilities(Some(vec![Capability::BuildAttestation]))
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&bot)?;
+    trustdb.import_pub_id_bundle(&bundle);
+    assert!(trustdb.allows(bot_id, Capability::BuildAttestation));
+    assert!(!trustdb.allows(bot_id, Capability::PackageReview));
+    assert_eq!(
+        "build-attestation".parse::<Capability>()?,
+        Capability::BuildAttestation
+    );
+    assert!(Capability::all().contains(&Capability::BuildAttestation));
+
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    let digest = Digest::from_vec(digest);
+    assert!(!trustdb
+        .verify_package_digest(&digest, &trust_set)
+        .is_verified());
+    assert_eq!(
+        trustdb
+            .get_build_attestations_by_package_digest(&digest)
+            .count(),
+        1
+    );
+
+    Ok(())
+}
+
+// A diff review vouches for its package only while the package at its base
+// digest is verified.
+#[test]
+fn diff_review_needs_a_verified_base() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let bob = OwnId::generate_for_git_url("https://bob");
+    let old = crev_data::proof::PackageInfo {
+        version: "1.0.0".into(),
+        digest: vec![1; 32],
+        ..package_info("SOURCE_ID", "foo")
+    };
+    let new = crev_data::proof::PackageInfo {
+        version: "1.1.0".into(),
+        digest: vec![2; 32],
+        ..package_info("SOURCE_ID", "foo")
+    };
+
+    let a_to_bob = a
+        .create_trust_proof(vec![bob.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let mut diff_review = bob.create_package_review_proof(new, default(), "".into())?;
+    diff_review.diff_base = Some(old.clone());
+    let diff_review = diff_review.sign_by(&bob)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_bob, diff_review].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    let new_digest = Digest::from_vec(vec![2; 32]);
+    assert!(!trustdb
+        .verify_package_digest(&new_digest, &trust_set)
+        .is_verified());
+    assert!(trustdb
+        .get_full_package_reviews_by_digest(&new_digest, &trust_set)
+        .is_empty());
+
+    let base_review = bob
+        .create_package_review_proof(old, default(), "".into())?
+        .sign_by(&bob)?;
+    trustdb.import_from_iter(vec![base_review].into_iter());
+    assert!(trustdb
+        .verify_package_digest(&new_digest, &trust_set)
+        .is_verified());
+
+    Ok(())
+}
+
+// Remotes get the credentials with the longest matching URL prefix, and
+// tokens can be kept out of the config in an environment variable
+#[test]
+fn remote_credentials() -> Result<()> {
+    let config: local::UserConfig = serde_yaml::from_str(
+        r#"
+version: -1
+current-id: ~
+remote-credentials:
+  - url: "https://git.example.com/"
+    token: org-token
+  - url: "https://git.example.com/security/"
+    username: auditor
+    token-env: CREV_TEST_REMOTE_TOKEN
+  - url: "git@git.example.com:"
+    ssh-key: /home/someone/.ssh/id_crev
+"#,
+    )?;
+
+    let org = config
+        .credentials_for("https://git.example.com/team/crev-proofs")
+        .expect("org credentials");
+    assert_eq!(org.resolve_token()?, Some("org-token".to_string()));
+
+    let security = config
+        .credentials_for("https://git.example.com/security/crev-proofs")
+        .expect("security credentials");
+    assert_eq!(security.username, Some("auditor".to_string()));
+    assert!(security.resolve_token().is_err());
+    std::env::set_var("CREV_TEST_REMOTE_TOKEN", "security-token");
+    assert_eq!(
+        security.resolve_token()?,
+        Some("security-token".to_string())
+    );
+
+    let ssh = config
+        .credentials_for("git@git.example.com:team/crev-proofs.git")
+        .expect("ssh credentials");
+    assert!(ssh.ssh_key.is_some());
+    assert!(config
+        .credentials_for("https://github.com/dpc/crev-proofs")
+        .is_none());
+    // lookalike hosts and paths get nothing
+    assert!(config
+        .credentials_for("https://git.example.com.evil.org/team/crev-proofs")
+        .is_none());
+    assert!(config
+        .credentials_for("https://git.example.com@evil.org/team/crev-proofs")
+        .is_none());
+    assert!(config
+        .credentials_for("git@git.example.com.evil.org:team/crev-proofs.git")
+        .is_none());
+    let org = config
+        .credentials_for("https://git.example.com/security-evil/crev-proofs")
+        .expect("org credentials");
+    assert_eq!(org.resolve_token()?, Some("org-token".to_string()));
+
+    Ok(())
+}
+
+// Trust Proofs can trust an id differently for its reviews, for its
+// trust judgements and for its advisories (negative reviews)
+#[test]
+fn proofdb_trust_dimensions() -> Result<()> {
+    use crev_data::proof::{review::Rating, trust::TrustDimensions};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let reviewer = OwnId::generate_for_git_url("https://reviewer");
+    let c = OwnId::generate_for_git_url("https://c");
+    let watcher = OwnId::generate_for_git_url("https://watcher");
+    let package = |digest: u8| crev_data::proof::PackageInfo {
+        version: digest.to_string(),
+        digest: vec![digest; 32],
+        ..package_info("source", "name")
+    };
+    let review = |rating: Rating| crev_data::Review {
+        rating,
+        ..default()
+    };
+    let verify = |db: &ProofDB, trust_set: &proofdb::TrustSet, digest: u8| {
+        db.verify_package_digest(&Digest::from_vec(vec![digest; 32]), trust_set)
+    };
+
+    let mut a_to_reviewer =
+        a.create_trust_proof(vec![reviewer.as_pubid().to_owned()], TrustLevel::High)?;
+    a_to_reviewer.dimensions = TrustDimensions {
+        trust: Some(TrustLevel::None),
+        ..default()
+    };
+    let mut a_to_watcher =
+        a.create_trust_proof(vec![watcher.as_pubid().to_owned()], TrustLevel::Medium)?;
+    a_to_watcher.dimensions = TrustDimensions {
+        reviews: Some(TrustLevel::None),
+        ..default()
+    };
+    let parsed = crev_data::proof::Trust::parse(&a_to_watcher.to_string())?;
+    assert_eq!(parsed.dimensions, a_to_watcher.dimensions);
+
+    let proofs = vec![
+        a_to_reviewer.sign_by(&a)?,
+        a_to_watcher.sign_by(&a)?,
+        reviewer
+            .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::High)?
+            .sign_by(&reviewer)?,
+        reviewer
+            .create_package_review_proof(package(1), review(Rating::Positive), "".into())?
+            .sign_by(&reviewer)?,
+        watcher
+            .create_package_review_proof(package(2), review(Rating::Positive), "".into())?
+            .sign_by(&watcher)?,
+        watcher
+            .create_package_review_proof(package(3), review(Rating::Dangerous), "".into())?
+            .sign_by(&watcher)?,
+    ];
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(proofs.into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    assert_eq!(
+        trust_set.get_effective_trust_level(reviewer.as_ref()),
+        Some(TrustLevel::High)
+    );
+    assert!(trust_set.get_effective_trust_level(c.as_ref()).is_none());
+    assert_eq!(
+        verify(&trustdb, &trust_set, 1),
+        VerificationStatus::Verified(TrustLevel::High)
+    );
+
+    assert!(trust_set
+        .get_effective_trust_level(watcher.as_ref())
+        .is_none());
+    assert_eq!(verify(&trustdb, &trust_set, 2), VerificationStatus::Unknown);
+    assert_eq!(
+        verify(&trustdb, &trust_set, 3),
+        VerificationStatus::Dangerous
+    );
+
+    Ok(())
+}
+
+// A function reviewed in one file stays reviewed after moving to another,
+// as long as the new file consists of reviewed items only.
+#[test]
+fn review_anchors() -> Result<()> {
+    use crev_data::proof::review::{code::File, Rating};
+
+    let old_source = r#"
+        /// Parse it
+        #[inline]
+        pub fn parse(s: &str) -> u8 { s.len() as u8 }
+
+        fn other() {}
+    "#;
+    let anchors = rust_anchors::source_anchors(old_source);
+    assert_eq!(
+        anchors
+            .iter()
+            .map(|anchor| (anchor.kind.as_str(), anchor.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("fn", "parse"), ("fn", "other")]
+    );
+    // formatting and comments don't matter
+    let moved = rust_anchors::source_anchors(
+        "// moved here\n#[inline] pub fn parse(s: &str) -> u8 {\n    s.len() as u8\n}\n",
+    );
+    let changed =
+        rust_anchors::source_anchors("#[inline] pub fn parse(s: &str) -> u8 { s.len() as u8 + 1 }");
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].digest, anchors[0].digest);
+    assert_ne!(changed[0].digest, anchors[0].digest);
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let review = code_review(
+        &b,
+        Rating::Positive,
+        vec![File {
+            anchors: anchors.clone(),
+            ..reviewed_file("src/old.rs", vec![1])
+        }],
+    )?;
+    let reparsed = crev_data::proof::review::Code::parse(&review.to_string())?;
+    assert_eq!(reparsed.files[0].anchors.len(), 2);
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::Medium)?
+        .sign_by(&a)?;
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, review.sign_by(&b)?].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    assert_eq!(
+        trustdb.verify_anchors(&moved, "https://example.com", "foo", &trust_set),
+        VerificationStatus::Verified(TrustLevel::Medium)
+    );
+    assert_eq!(
+        trustdb.verify_anchors(&changed, "https://example.com", "foo", &trust_set),
+        VerificationStatus::Unknown
+    );
+    let mut partly_reviewed = moved.clone();
+    partly_reviewed.extend(changed);
+    assert_eq!(
+        trustdb.verify_anchors(&partly_reviewed, "https://example.com", "foo", &trust_set),
+        VerificationStatus::Unknown
+    );
+    assert_eq!(
+        trustdb.verify_anchors(&[], "https://example.com", "foo", &trust_set),
+        VerificationStatus::Unknown
+    );
+    // reviews of another package don't count
+    assert_eq!(
+        trustdb.verify_anchors(&moved, "https://example.com", "bar", &trust_set),
+        VerificationStatus::Unknown
+    );
+
+    // attributes at the end of a file are an anchor of their own, to review too
+    let trailing = rust_anchors::source_anchors("fn other() {}\n#[cfg(test)]\n");
+    assert_eq!(
+        trailing
+            .iter()
+            .map(|anchor| (anchor.kind.as_str(), anchor.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("fn", "other"), ("attr", "# [ cfg ( test ) ]")]
+    );
+    assert_eq!(trailing[0].digest, anchors[1].digest);
+    assert_eq!(
+        trustdb.verify_anchors(&trailing, "https://example.com", "foo", &trust_set),
+        VerificationStatus::Unknown
+    );
+
+    Ok(())
+}
+
+#[test]
+fn renames_since_review() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("crev-renames")?;
+    let root = tmp_dir.path();
+    let git_repo = git2::Repository::init(root)?;
+    let long: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+    std::fs::create_dir_all(root.join("src"))?;
+    std::fs::write(root.join("src/old.rs"), "fn old() {}\n")?;
+    std::fs::write(root.join("src/long.rs"), &long)?;
+    let mut index = git_repo.index()?;
+    index.add_path(std::path::Path::new("src/old.rs"))?;
+    index.add_path(std::path::Path::new("src/long.rs"))?;
+    index.write()?;
+    let tree = git_repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("Alice", "alice@example.com")?;
+    let reviewed = git_repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])?;
+
+    let id = OwnId::generate_for_git_url("https://a");
+    let review = crev_data::proof::review::CodeBuilder::default()
+        .from(id.id.clone())
+        .package(crev_data::proof::PackageInfo {
+            revision: reviewed.to_string(),
+            revision_type: "git".into(),
+            ..package_info("", "renames")
+        })
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&id)?;
+    std::fs::create_dir_all(root.join(".crev/proofs"))?;
+    std::fs::write(
+        root.join(".crev/proofs/review.proof.crev"),
+        review.to_string(),
+    )?;
+
+    // one file moved as is, the other one moved and tweaked
+    std::fs::rename(root.join("src/old.rs"), root.join("src/new.rs"))?;
+    std::fs::remove_file(root.join("src/long.rs"))?;
+    std::fs::write(
+        root.join("src/moved.rs"),
+        long.replace("line 7\n", "line seven\n"),
+    )?;
+    index.remove_path(std::path::Path::new("src/old.rs"))?;
+    index.remove_path(std::path::Path::new("src/long.rs"))?;
+    index.add_path(std::path::Path::new("src/new.rs"))?;
+    index.add_path(std::path::Path::new("src/moved.rs"))?;
+    index.write()?;
+
+    let repo = repo::Repo::open(root)?;
+    let renames = repo.renames()?;
+    assert_eq!(renames.len(), 2);
+    let new = &renames[std::path::Path::new("src/new.rs")];
+    assert_eq!(new.from, std::path::Path::new("src/old.rs"));
+    assert_eq!(new.digest, crev_common::blake2b256sum(b"fn old() {}\n"));
+    assert!(new.unchanged);
+    let moved = &renames[std::path::Path::new("src/moved.rs")];
+    assert_eq!(moved.from, std::path::Path::new("src/long.rs"));
+    assert_eq!(moved.digest, crev_common::blake2b256sum(long.as_bytes()));
+    assert!(!moved.unchanged);
+
+    Ok(())
+}
+
+// Moves recorded with Path Move Proofs are reported even when git can't
+// pair the files up.
+#[test]
+fn recorded_path_moves() -> Result<()> {
+    let tmp_dir = tempdir::TempDir::new("crev-moves")?;
+    let root = tmp_dir.path();
+    let git_repo = git2::Repository::init(root)?;
+    std::fs::write(root.join("old.rs"), "fn old() {}\n")?;
+    let mut index = git_repo.index()?;
+    index.add_path(std::path::Path::new("old.rs"))?;
+    index.write()?;
+    let tree = git_repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("Alice", "alice@example.com")?;
+    let moved_at = git_repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])?;
+
+    std::fs::remove_file(root.join("old.rs"))?;
+    std::fs::write(root.join("new.rs"), "fn new() { completely(different) }\n")?;
+    index.remove_path(std::path::Path::new("old.rs"))?;
+    index.add_path(std::path::Path::new("new.rs"))?;
+    index.write()?;
+
+    let id = OwnId::generate_for_git_url("https://a");
+    let package = crev_data::proof::PackageInfo {
+        revision: moved_at.to_string(),
+        revision_type: "git".into(),
+        ..package_info("", "moves")
+    };
+    let path_move = |from: &str, to: &str| {
+        crev_data::proof::path_move::PathMoveBuilder::default()
+            .from(id.id.clone())
+            .package(package.clone())
+            .moves(vec![crev_data::proof::path_move::Move {
+                from: from.into(),
+                to: to.into(),
+            }])
+            .build()
+            .map_err(|e| format_err!("{}", e))
+    };
+    assert!(
+        crev_data::proof::PathMove::parse(&path_move("../old.rs", "new.rs")?.to_string()).is_err()
+    );
+    assert!(
+        crev_data::proof::PathMove::parse(&path_move("new.rs", "new.rs")?.to_string()).is_err()
+    );
+
+    let repo = repo::Repo::init(root, id.id.id.to_string())?;
+    std::fs::create_dir_all(root.join(".crev/proofs"))?;
+    assert!(repo.renames()?.is_empty());
+
+    let proof = path_move("old.rs", "new.rs")?.sign_by(&id)?;
+    std::fs::write(root.join(".crev/proofs/move.proof.crev"), proof.to_string())?;
+    let renames = repo.renames()?;
+    let rename = &renames[std::path::Path::new("new.rs")];
+    assert!(rename.recorded);
+    assert!(!rename.unchanged);
+    assert_eq!(rename.from, std::path::Path::new("old.rs"));
+    assert_eq!(rename.digest, crev_common::blake2b256sum(b"fn old() {}\n"));
+
+    // without a trust root to authorize it, the move doesn't count
+    std::fs::remove_file(root.join(".crev/config.yaml"))?;
+    assert!(repo.renames()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn merge_staging_files() -> Result<()> {
+    use crate::repo::staging::{MergeChoice, MergeSummary};
+
+    let tmp_dir = tempdir::TempDir::new("crev-merge-staging")?;
+    let root = tmp_dir.path().canonicalize()?;
+    std::fs::create_dir_all(root.join(".crev"))?;
+    let staging_path = root.join(".crev/staging");
+    std::fs::write(root.join("a.rs"), "a")?;
+    std::fs::write(root.join("b.rs"), "b1")?;
+    std::fs::write(root.join("c.rs"), "c")?;
+
+    repo::Repo::open(&root)?.add(vec![root.join("a.rs"), root.join("b.rs")])?;
+    let ours = std::fs::read(&staging_path)?;
+    std::fs::remove_file(&staging_path)?;
+    // the other checkout staged a newer `b.rs`
+    std::fs::write(root.join("b.rs"), "b2")?;
+    repo::Repo::open(&root)?.add(vec![root.join("b.rs"), root.join("c.rs")])?;
+    let theirs_path = root.join("theirs");
+    std::fs::rename(&staging_path, &theirs_path)?;
+    std::fs::write(&staging_path, ours)?;
+
+    let mut repo = repo::Repo::open(&root)?;
+    let summary = repo.merge_staging(&theirs_path, &|conflict| {
+        assert_eq!(conflict.path, std::path::Path::new("b.rs"));
+        assert_eq!(conflict.current, Some(MergeChoice::Theirs));
+        Ok(MergeChoice::Theirs)
+    })?;
+    assert_eq!(
+        summary,
+        MergeSummary {
+            added: 1,
+            same: 0,
+            conflicts: 1,
+        }
+    );
+    let entries = &repo.staging()?.entries;
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[std::path::Path::new("b.rs")].digest(),
+        crev_common::blake2b256sum(b"b2").as_slice()
+    );
+    repo.staging()?.enforce_current()?;
+
+    // merging the same file again changes nothing
+    let summary =
+        repo::Repo::open(&root)?.merge_staging(&theirs_path, &|_| Ok(MergeChoice::Drop))?;
+    assert_eq!(summary.same, 2);
+    assert_eq!(summary.conflicts, 0);
+
+    Ok(())
+}
+
+#[test]
+fn merkle_tree_reuses_unchanged_subtrees() -> Result<()> {
+    use crate::repo::merkle::{MerkleTree, UpdateStats};
+    use std::path::Path;
+
+    let tmp_dir = tempdir::TempDir::new("crev-merkle")?;
+    let root = tmp_dir.path().canonicalize()?;
+    let cache_dir = tempdir::TempDir::new("crev-merkle-cache")?;
+    let git_repo = git2::Repository::init(&root)?;
+    std::fs::create_dir_all(root.join(".crev"))?;
+    std::fs::create_dir_all(root.join("src"))?;
+    std::fs::create_dir_all(root.join("docs"))?;
+    std::fs::write(root.join("src/a.rs"), "a")?;
+    std::fs::write(root.join("src/b.rs"), "b")?;
+    std::fs::write(root.join("docs/x.md"), "x")?;
+    let mut index = git_repo.index()?;
+    for path in &["src/a.rs", "src/b.rs", "docs/x.md"] {
+        index.add_path(Path::new(path))?;
+    }
+    index.write()?;
+    let tree = git_repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("Alice", "alice@example.com")?;
+    git_repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])?;
+
+    let update = || -> Result<(MerkleTree, UpdateStats)> {
+        let mut tree = MerkleTree::load(cache_dir.path());
+        let stats = tree.update(&root, &git_scope_statuses(&root)?)?;
+        tree.save(cache_dir.path())?;
+        Ok((tree, stats))
+    };
+    let (first, stats) = update()?;
+    assert_eq!((stats.hashed, stats.reused), (3, 0));
+    assert_eq!(
+        first.file_digest(Path::new("src/a.rs")),
+        Some(crev_common::blake2b256sum(b"a").as_slice())
+    );
+    assert_eq!(first.file_digests().len(), 3);
+
+    let (second, stats) = update()?;
+    assert_eq!((stats.hashed, stats.reused), (0, 3));
+    assert_eq!(second.digest(), first.digest());
+
+    // only the changed file is read again
+    std::fs::write(root.join("src/a.rs"), "a2")?;
+    let (third, stats) = update()?;
+    assert_eq!((stats.hashed, stats.reused), (1, 2));
+    assert_ne!(third.digest(), first.digest());
+    assert_ne!(
+        third.dir_digest(Path::new("src")),
+        first.dir_digest(Path::new("src"))
+    );
+    assert_eq!(
+        third.dir_digest(Path::new("docs")),
+        first.dir_digest(Path::new("docs"))
+    );
+    assert_eq!(
+        third.file_digest(Path::new("src/a.rs")),
+        Some(crev_common::blake2b256sum(b"a2").as_slice())
+    );
+
+    Ok(())
+}
+
+// A merkle tree committed to `.crev` is never used: it could map the blob
+// of any file to the digest of reviewed content
+#[test]
+fn merkle_tree_in_dot_crev_is_ignored() -> Result<()> {
+    use crate::{
+        repo::{merkle::MerkleTree, Repo},
+        test_support::TestEnv,
+    };
+    use std::path::Path;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "evil")])?;
+    Repo::init(&project, alice.id.id.id.to_string())?;
+    let repo = Repo::open(&project)?;
+
+    let mut forged = serde_json::to_value(&repo.merkle_tree()?)?;
+    forged["root"]["dirs"]["src"]["files"]["lib.rs"]["digest"] =
+        serde_json::to_value(crev_common::blake2b256sum(b"good"))?;
+    let forged: MerkleTree = serde_json::from_value(forged)?;
+    forged.save(&project.join(".crev"))?;
+
+    assert_eq!(
+        repo.merkle_tree()?.file_digest(Path::new("src/lib.rs")),
+        Some(crev_common::blake2b256sum(b"evil").as_slice())
+    );
+    // and the `.crev` of new scopes keeps local files out of git
+    let gitignore = std::fs::read_to_string(project.join(".crev/.gitignore"))?;
+    assert!(gitignore.lines().any(|line| line == "merkle"));
+
+    Ok(())
+}
+
+#[test]
+fn watcher_reports_changed_files() -> Result<()> {
+    use crate::repo::watch::{Watcher, DELAY};
+    use std::{collections::BTreeSet, path::PathBuf};
+
+    // changes reported until all the `expected` ones are, along with any
+    // coming right after them; waiting is bounded, so a missed change
+    // fails the test rather than hangs it
+    let changes = |watcher: &mut Watcher, expected: &[PathBuf]| -> Result<Vec<PathBuf>> {
+        let mut changed = BTreeSet::new();
+        while !expected.iter().all(|path| changed.contains(path)) {
+            let batch = watcher.wait(DELAY * 50)?;
+            if batch.is_empty() {
+                break;
+            }
+            changed.extend(batch);
+        }
+        changed.extend(watcher.wait(DELAY * 2)?);
+        Ok(changed.into_iter().collect())
+    };
+
+    let tmp_dir = tempdir::TempDir::new("crev-watch")?;
+    let root = tmp_dir.path().canonicalize()?;
+    std::fs::create_dir_all(root.join(".crev"))?;
+    std::fs::create_dir_all(root.join("src"))?;
+    std::fs::write(root.join("src/a.rs"), "a")?;
+    std::fs::write(root.join("src/b.rs"), "b")?;
+
+    let proofs_dir = tempdir::TempDir::new("crev-watch-proofs")?;
+    let proofs = proofs_dir.path().canonicalize()?;
+
+    let mut watcher = Watcher::with_dirs(&root, vec![proofs.clone()])?;
+    assert!(watcher.poll()?.is_empty());
+
+    std::fs::write(root.join("src/a.rs"), "a changed")?;
+    std::fs::write(root.join("src/c.rs"), "c")?;
+    std::fs::remove_file(root.join("src/b.rs"))?;
+    // writes of crev itself don't count
+    std::fs::write(root.join(".crev/merkle"), "cache")?;
+    std::fs::write(root.join(".crev/status-cache.yaml"), "cache")?;
+    let expected = vec![
+        PathBuf::from("src/a.rs"),
+        PathBuf::from("src/b.rs"),
+        PathBuf::from("src/c.rs"),
+    ];
+    assert_eq!(changes(&mut watcher, &expected)?, expected);
+    assert!(watcher.poll()?.is_empty());
+
+    // but new proofs do, in the scope or elsewhere
+    std::fs::write(root.join(".crev/config.yaml"), "trust-root: ~")?;
+    std::fs::write(proofs.join("new.proof.crev"), "proof")?;
+    let expected = vec![proofs.join("new.proof.crev"), PathBuf::from(".crev/config.yaml")];
+    assert_eq!(changes(&mut watcher, &expected)?, expected);
+
+    std::fs::write(root.join("src/a.rs"), "a changed again")?;
+    assert!(watcher
+        .wait(DELAY * 50)?
+        .contains(&PathBuf::from("src/a.rs")));
+    assert!(watcher.wait(DELAY)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn scope_status_of_files() {
+    use crate::repo::{FileVerification, ScopeStatus};
+
+    let file = |verified: bool| FileVerification {
+        path: "src/lib.rs".into(),
+        status: if verified {
+            VerificationStatus::Verified(TrustLevel::High)
+        } else {
+            VerificationStatus::Unknown
+        },
+        reviews: vec![],
+        renamed_from: None,
+        self_reviewed: false,
+    };
+    assert_eq!(
+        ScopeStatus::of_files(&[file(true), file(true)]),
+        ScopeStatus::Verified
+    );
+    assert_eq!(
+        ScopeStatus::of_files(&[file(true), file(false)]),
+        ScopeStatus::Partially
+    );
+    assert_eq!(
+        ScopeStatus::of_files(&[file(false)]),
+        ScopeStatus::Unverified
+    );
+    assert_eq!(ScopeStatus::Partially.to_string(), "partially");
+}
+
+#[test]
+fn editor_server_answers_file_queries() -> Result<()> {
+    use crate::{editor_server::Server, repo::FileVerification};
+
+    let root = std::path::PathBuf::from("/project");
+    let mut verifications = 0;
+    let mut verify = || -> Result<Vec<FileVerification>> {
+        verifications += 1;
+        Ok(vec![
+            FileVerification {
+                path: "src/lib.rs".into(),
+                status: VerificationStatus::Verified(TrustLevel::Medium),
+                reviews: vec![],
+                renamed_from: None,
+                self_reviewed: false,
+            },
+            FileVerification {
+                path: "src/new.rs".into(),
+                status: VerificationStatus::Unknown,
+                reviews: vec![],
+                renamed_from: None,
+                self_reviewed: false,
+            },
+        ])
+    };
+    let mut server = Server::new(&root, None);
+    let mut query = |server: &mut Server, line: &str| -> Result<serde_json::Value> {
+        let (response, _) = server.handle(line, &mut verify);
+        Ok(serde_json::from_str(&response)?)
+    };
+
+    let response = query(
+        &mut server,
+        r#"{"id": 1, "method": "file", "path": "src/lib.rs"}"#,
+    )?;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["status"], "medium");
+    assert_eq!(response["result"]["verified"], true);
+    // absolute paths work too, and the verification is reused
+    let response = query(
+        &mut server,
+        r#"{"id": 2, "method": "file", "path": "/project/src/new.rs"}"#,
+    )?;
+    assert_eq!(response["result"]["verified"], false);
+    let response = query(&mut server, r#"{"id": 3, "method": "scope"}"#)?;
+    assert_eq!(response["result"], "partially");
+    let response = query(
+        &mut server,
+        r#"{"id": 4, "method": "file", "path": "nope.rs"}"#,
+    )?;
+    assert!(response["error"].is_string());
+    assert!(query(&mut server, "not json")?["error"].is_string());
+
+    server.invalidate();
+    query(&mut server, r#"{"id": 5, "method": "files"}"#)?;
+    let (_, keep_going) = server.handle(r#"{"id": 6, "method": "shutdown"}"#, &mut || Ok(vec![]));
+    assert!(!keep_going);
+    assert_eq!(verifications, 2);
+
+    Ok(())
+}
+
+// Over TCP, requests without the token of the session are refused
+#[test]
+fn editor_server_checks_the_token() -> Result<()> {
+    use crate::editor_server::Server;
+
+    let mut server = Server::new(std::path::Path::new("/project"), Some("secret".into()));
+    let mut query = |line: &str| -> Result<(serde_json::Value, bool)> {
+        let (response, keep_going) = server.handle(line, &mut || Ok(vec![]));
+        Ok((serde_json::from_str(&response)?, keep_going))
+    };
+
+    let (response, _) = query(r#"{"id": 1, "method": "scope"}"#)?;
+    assert_eq!(response["error"], "Invalid token");
+    let (response, keep_going) = query(r#"{"id": 2, "method": "shutdown", "token": "wrong"}"#)?;
+    assert_eq!(response["error"], "Invalid token");
+    assert!(keep_going);
+    let (response, keep_going) = query(r#"{"id": 3, "method": "shutdown", "token": "secret"}"#)?;
+    assert!(response["error"].is_null());
+    assert!(!keep_going);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn hooks_run_on_their_events() -> Result<()> {
+    use crate::hooks::{self, Event, Hook};
+
+    let tmp_dir = tempdir::TempDir::new("crev-hooks")?;
+    let out = tmp_dir.path().join("out");
+    let hooks = vec![
+        Hook {
+            event: Event::ProofsFetched,
+            command: format!("echo \"$CREV_EVENT $CREV_URL\" >> {}", out.display()),
+        },
+        Hook {
+            event: Event::ProofsFetched,
+            command: "exit 3".into(),
+        },
+        Hook {
+            event: Event::ProofCommitted,
+            command: format!("echo committed >> {}", out.display()),
+        },
+    ];
+
+    let failed = hooks::run(
+        &hooks,
+        Event::ProofsFetched,
+        &[("URL", "https://example.com".into())],
+    );
+    assert_eq!(failed, 1);
+    assert_eq!(
+        std::fs::read_to_string(&out)?,
+        "proofs-fetched https://example.com\n"
+    );
+    assert!(hooks::any_for(&hooks, Event::ProofCommitted));
+    assert_eq!(hooks::run(&hooks, Event::AdvisoryReceived, &[]), 0);
+
+    let config: crate::local::UserConfig =
+        serde_yaml::from_str("version: -1\ncurrent-id: ~\nhooks:\n  - event: verification-failed\n    command: notify-send crev\n")?;
+    assert_eq!(config.hooks[0].event, Event::VerificationFailed);
+
+    Ok(())
+}
+
+#[test]
+fn build_attestation_requirements() -> Result<()> {
+    use crate::policy::{attesting_ids, AttestationRequirement};
+    use crev_data::proof::build_attestation::{Artifact, BuildAttestationBuilder};
+
+    let requirement: AttestationRequirement = serde_yaml::from_str("trust: medium")?;
+    assert_eq!(requirement.attesters, 1);
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let attestation = |author: &OwnId, source_digest: Vec<u8>, artifact_digest: Vec<u8>| {
+        BuildAttestationBuilder::default()
+            .from(author.id.clone())
+            .package(crev_data::proof::PackageInfo {
+                version: "1.0.0".into(),
+                digest: source_digest,
+                revision: "abcdef".into(),
+                ..package_info("https://example.com", "foo")
+            })
+            .artifact(Artifact {
+                name: "foo".into(),
+                digest: artifact_digest,
+                digest_type: crev_data::proof::default_digest_type(),
+            })
+            .build()
+            .map_err(|e| format_err!("{}", e))
+    };
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let a_to_c = a
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::Low)?
+        .sign_by(&a)?;
+    let c_attestation = attestation(&c, vec![1; 32], vec![9, 9])?.sign_by(&c)?;
+    let b_other_attestation = attestation(&b, vec![3; 32], vec![9, 9])?.sign_by(&b)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(
+        vec![a_to_b, a_to_c, c_attestation, b_other_attestation].into_iter(),
+    );
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    let digest = Digest::from_vec(vec![1; 32]);
+
+    // only `low` trust attests these sources
+    let attesters = attesting_ids(&trustdb, &trust_set, &digest);
+    assert_eq!(attesters.len(), 1);
+    assert!(!requirement.is_met(&attesters));
+
+    trustdb.import_from_iter(
+        vec![attestation(&b, vec![1; 32], vec![9, 9])?.sign_by(&b)?].into_iter(),
+    );
+    let attesters = attesting_ids(&trustdb, &trust_set, &digest);
+    assert!(requirement.is_met(&attesters));
+
+    // attesters have to agree on what was built
+    let d = OwnId::generate_for_git_url("https://d");
+    let a_to_d = a
+        .create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Low)?
+        .sign_by(&a)?;
+    let d_attestation = attestation(&d, vec![1; 32], vec![8, 8])?.sign_by(&d)?;
+    trustdb.import_from_iter(vec![a_to_d, d_attestation].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    let attesters = attesting_ids(&trustdb, &trust_set, &digest);
+    assert_eq!(attesters.len(), 2);
+    let two_low: AttestationRequirement = serde_yaml::from_str("attesters: 2\ntrust: low")?;
+    let three_low: AttestationRequirement = serde_yaml::from_str("attesters: 3\ntrust: low")?;
+    assert!(two_low.is_met(&attesters));
+    assert!(!three_low.is_met(&attesters));
+
+    assert!(attestation(&b, vec![], vec![9, 9])?
+        .sign_by(&b)
+        .and_then(|proof| crev_data::proof::Proof::parse(proof.to_string().as_bytes()))
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn package_version_coverage() -> Result<()> {
+    use crev_data::proof::review::Review;
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let stranger = OwnId::generate_for_git_url("https://stranger");
+    let review = |author: &OwnId, version: &str, digest: u8, review: Review| {
+        author.as_pubid().create_package_review_proof(
+            crev_data::proof::PackageInfo {
+                version: version.into(),
+                digest: vec![digest; 32],
+                ..package_info("https://crates.io", "foo")
+            },
+            review,
+            "".into(),
+        )
+    };
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let a_to_c = a
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::Low)?
+        .sign_by(&a)?;
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(
+        vec![
+            a_to_b,
+            a_to_c,
+            review(&c, "1.0.0", 1, Review::new_positive())?.sign_by(&c)?,
+            review(&b, "1.1.0", 2, Review::new_positive())?.sign_by(&b)?,
+            review(&c, "1.1.0", 2, Review::new_positive())?.sign_by(&c)?,
+            review(&stranger, "1.1.0", 2, Review::new_positive())?.sign_by(&stranger)?,
+            review(&c, "1.2.0", 3, Review::new_negative())?.sign_by(&c)?,
+            review(&b, "1.3.0", 4, Review::new_positive())?.sign_by(&b)?,
+            review(&c, "1.3.0", 5, Review::new_positive())?.sign_by(&c)?,
+            review(&stranger, "1.0.0", 6, Review::new_positive())?.sign_by(&stranger)?,
+        ]
+        .into_iter(),
+    );
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+
+    let coverage = trustdb.get_package_version_coverage("https://crates.io", "foo", &trust_set);
+    assert_eq!(coverage.len(), 4);
+    assert_eq!(
+        coverage["1.0.0"].status,
+        VerificationStatus::Verified(TrustLevel::Low)
+    );
+    assert_eq!(
+        coverage["1.1.0"],
+        VersionCoverage {
+            status: VerificationStatus::Verified(TrustLevel::High),
+            trusted_reviews: 2,
+        }
+    );
+    assert_eq!(coverage["1.2.0"].status, VerificationStatus::Flagged);
+    // trusted reviews of different digests of the same version
+    assert_eq!(coverage["1.3.0"].status, VerificationStatus::Flagged);
+
+    assert_eq!(
+        trustdb.get_package_digest_coverage(&Digest::from_vec(vec![2; 32]), &trust_set),
+        coverage["1.1.0"]
+    );
+    assert_eq!(
+        trustdb.get_package_digest_coverage(&Digest::from_vec(vec![6; 32]), &trust_set),
+        VersionCoverage::default()
+    );
+
+    assert!(coverage["1.1.0"].is_better_than(&coverage["1.0.0"]));
+    assert!(coverage["1.0.0"].is_better_than(&VersionCoverage::default()));
+    assert!(VersionCoverage::default().is_better_than(&coverage["1.2.0"]));
+
+    Ok(())
+}
+
+#[test]
+fn todo_queue() -> Result<()> {
+    use crate::todo::{Item, Kind, Queue};
+
+    let tmp_dir = tempdir::TempDir::new("crev-todo")?;
+    let path = tmp_dir.path().join("todo.yaml");
+    let project = tmp_dir.path().join("project");
+
+    let mut queue = Queue::open(&path)?;
+    assert!(queue.items().is_empty());
+    assert!(queue.add(Item::new(Kind::Dependency, "foo 1.0.0".into())));
+    assert!(queue.add(Item::new(Kind::Dependency, "bar 0.1.0".into())));
+    let mut urgent = Item::new(Kind::Request, "baz".into());
+    urgent.priority = 1;
+    assert!(queue.add(urgent));
+    assert!(!queue.add(Item::new(Kind::Dependency, "foo 1.0.0".into())));
+
+    let targets = |queue: &Queue| -> Vec<String> {
+        queue
+            .items()
+            .iter()
+            .map(|item| item.target.clone())
+            .collect()
+    };
+    assert_eq!(targets(&queue), vec!["baz", "foo 1.0.0", "bar 0.1.0"]);
+    assert_eq!(queue.skip(2)?, 3);
+    assert_eq!(targets(&queue), vec!["baz", "bar 0.1.0", "foo 1.0.0"]);
+    assert!(queue.skip(4).is_err());
+
+    queue.watch(&project, "aaa".into());
+    let head = std::cell::RefCell::new(Some("aaa".to_string()));
+    assert_eq!(queue.scan(&|_| head.borrow().clone()), 0);
+    *head.borrow_mut() = Some("bbb".into());
+    assert_eq!(queue.scan(&|_| head.borrow().clone()), 1);
+    *head.borrow_mut() = Some("ccc".into());
+    assert_eq!(queue.scan(&|_| head.borrow().clone()), 1);
+    assert_eq!(queue.items().len(), 4);
+    queue.save()?;
+
+    let mut queue = Queue::open(&path)?;
+    assert_eq!(queue.items().len(), 4);
+    assert_eq!(
+        queue.complete_matching(Kind::Revision, &project.display().to_string()),
+        1
+    );
+    assert_eq!(queue.watched().next(), Some((project.as_path(), "ccc")));
+    assert_eq!(queue.scan(&|_| head.borrow().clone()), 0);
+    assert_eq!(queue.complete(1)?.target, "baz");
+    assert_eq!(targets(&queue), vec!["bar 0.1.0", "foo 1.0.0"]);
+
+    Ok(())
+}
+
+#[test]
+fn review_request_proofs() -> Result<()> {
+    use crate::todo::{Item, Kind, Queue};
+    use crev_data::{
+        id_bundle::Capability,
+        proof::{review_request::ReviewRequestBuilder, Content, Proof},
+    };
+
+    let maintainer = OwnId::generate_for_git_url("https://maintainer");
+    let request = ReviewRequestBuilder::default()
+        .from(maintainer.id.clone())
+        .package(crev_data::proof::PackageInfo {
+            digest: vec![1; 32],
+            revision: "abcdef".into(),
+            ..package_info("", "foo")
+        })
+        .comment("new parser".into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?;
+    let proof = request.clone().sign_by(&maintainer)?;
+    let parsed = Proof::parse(proof.to_string().as_bytes())?.remove(0);
+    parsed.verify()?;
+    assert_eq!(
+        Capability::required_for(&parsed.content),
+        Some(Capability::Project)
+    );
+    let request = match parsed.content {
+        Content::ReviewRequest(request) => request,
+        _ => bail!("Wrong proof type"),
+    };
+
+    let item = Item::from_review_request(&request);
+    assert_eq!(item.kind, Kind::Request);
+    assert_eq!(item.target, "foo");
+    assert_eq!(item.revision, "abcdef");
+    assert_eq!(item.comment, "requested by https://maintainer: new parser");
+
+    let tmp_dir = tempdir::TempDir::new("crev-todo")?;
+    let mut queue = Queue::open(&tmp_dir.path().join("todo.yaml"))?;
+    assert!(queue.add(item.clone()));
+    assert!(!queue.add(item));
+
+    let mut unnamed = request.clone();
+    unnamed.package.revision = "".into();
+    assert!(unnamed
+        .sign_by(&maintainer)
+        .and_then(|proof| Proof::parse(proof.to_string().as_bytes()))
+        .is_err());
+
+    Ok(())
+}
+
+// Ids conflict over the URLs they claim in their own proofs, compared
+// normalized, and not over what others pair them with.
+#[test]
+fn id_conflicts_are_detected() -> Result<()> {
+    use crate::IdConflict;
+    use crev_data::{PubId, Url};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let impostor = OwnId::generate_for_git_url("https://B.git/");
+    let c = OwnId::generate_for_git_url("https://c");
+    let c_elsewhere = OwnId::new(
+        Url::new_git("https://c2".into()),
+        c.keypair.secret.as_bytes(),
+    )?;
+    let trust = |from: &OwnId, to: PubId| -> Result<_> {
+        from.create_trust_proof(vec![to], TrustLevel::Low)?
+            .sign_by(from)
+    };
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(
+        vec![
+            trust(&a, b.as_pubid().to_owned())?,
+            trust(&b, a.as_pubid().to_owned())?,
+            trust(
+                &a,
+                PubId::new(c.id.id.clone(), Url::new_git("https://c3".into())),
+            )?,
+        ]
+        .into_iter(),
+    );
+    assert!(trustdb.id_conflicts().is_empty());
+
+    trustdb.import_from_iter(
+        vec![
+            trust(&impostor, a.as_pubid().to_owned())?,
+            trust(&c, a.as_pubid().to_owned())?,
+            trust(&c_elsewhere, a.as_pubid().to_owned())?,
+        ]
+        .into_iter(),
+    );
+    let mut claimants = vec![b.id.id.clone(), impostor.id.id.clone()];
+    claimants.sort();
+    assert_eq!(
+        trustdb.id_conflicts(),
+        vec![
+            IdConflict::SharedUrl {
+                url: "https://b".into(),
+                ids: claimants,
+            },
+            IdConflict::MultipleUrls {
+                id: c.id.id.clone(),
+                urls: vec!["https://c".into(), "https://c2".into()],
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn future_dated_proofs() -> Result<()> {
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let dated = |to: &OwnId, days: i64| -> Result<crev_data::proof::Proof> {
+        let mut trust = a.create_trust_proof(vec![to.as_pubid().to_owned()], TrustLevel::High)?;
+        trust.date = trust.date + chrono::Duration::days(days);
+        trust.sign_by(&a)
+    };
+    let proofs = vec![dated(&b, 0)?, dated(&c, 30)?];
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(proofs.clone().into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    assert!(trust_set.get_effective_trust_level(c.as_ref()).is_some());
+
+    let mut trustdb = ProofDB::new();
+    trustdb.set_reject_future_dated(true);
+    trustdb.import_from_iter(proofs.into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_some());
+    assert_eq!(trust_set.get_effective_trust_level(c.as_ref()), None);
+
+    let config: crate::local::UserConfig =
+        serde_yaml::from_str("version: -1\ncurrent-id: ~\nreject-future-dated: true\n")?;
+    assert!(config.reject_future_dated);
+
+    Ok(())
+}
+
+#[test]
+fn normalized_file_digests() -> Result<()> {
+    use crate::normalize::{self, Normalizations};
+    use crev_data::proof::review::code::Normalization;
+
+    let normalizations: Normalizations = serde_yaml::from_str("md: [bom, trailing-whitespace]\n")?;
+    let md = normalize::for_path(&normalizations, Path::new("docs/README.md"));
+    assert_eq!(
+        md,
+        &[Normalization::Bom, Normalization::TrailingWhitespace][..]
+    );
+    assert!(normalize::for_path(&normalizations, Path::new("src/lib.rs")).is_empty());
+
+    assert_eq!(
+        normalize::normalize(b"\xef\xbb\xbf# Title  \r\ntext\t\n \n", md),
+        b"# Title\r\ntext\n\n".to_vec()
+    );
+    // a BOM is only one at the very start
+    assert_eq!(
+        normalize::normalize(b"a\xef\xbb\xbf", &[Normalization::Bom]),
+        b"a\xef\xbb\xbf".to_vec()
+    );
+
+    let tmp_dir = tempdir::TempDir::new("crev-normalize")?;
+    let path = tmp_dir.path().join("README.md");
+    std::fs::write(&path, "# Title  \n")?;
+    assert_eq!(
+        normalize::file_digest(&path, md)?,
+        crev_common::blake2b256sum(b"# Title\n")
+    );
+    assert_eq!(
+        normalize::file_digest(&path, &[])?,
+        crev_common::blake2b256sum_file(&path)?
+    );
+    Ok(())
+}
+
+#[test]
+fn verification_result_outcomes() -> Result<()> {
+    use crate::repo::{FileOutcome, FileResult, FileVerification, VerificationResult};
+
+    let file = |path: &str, status| FileVerification {
+        path: PathBuf::from(path),
+        status,
+        reviews: vec![],
+        renamed_from: None,
+        self_reviewed: false,
+    };
+    let reviewed_paths: HashSet<PathBuf> = vec![PathBuf::from("src/old.rs")].into_iter().collect();
+    let outcome = |file: &FileVerification| FileOutcome::of(file, &reviewed_paths);
+
+    assert_eq!(
+        outcome(&file(
+            "src/lib.rs",
+            VerificationStatus::Verified(TrustLevel::High)
+        )),
+        FileOutcome::Verified
+    );
+    assert_eq!(
+        outcome(&file("src/lib.rs", VerificationStatus::Dangerous)),
+        FileOutcome::Flagged
+    );
+    assert_eq!(
+        outcome(&file("src/old.rs", VerificationStatus::Unknown)),
+        FileOutcome::ModifiedSinceReview
+    );
+    assert_eq!(
+        outcome(&file("src/new.rs", VerificationStatus::Unknown)),
+        FileOutcome::Unreviewed
+    );
+
+    let result = VerificationResult {
+        status: "unknown".into(),
+        verified: false,
+        files: vec![FileResult {
+            path: PathBuf::from("src/old.rs"),
+            kind: crev_data::proof::review::code::FileKind::Code,
+            outcome: FileOutcome::ModifiedSinceReview,
+            status: "unknown".into(),
+            self_reviewed: false,
+        }],
+    };
+    assert_eq!(
+        serde_json::to_string(&result)?,
+        r#"{"status":"unknown","verified":false,"files":[{"path":"src/old.rs","kind":"code","outcome":"modified-since-review","status":"unknown","self-reviewed":false}]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_cache_keyed_by_proofs_and_digests() -> Result<()> {
+    use crate::repo::{
+        verify_cache::{CachedFile, VerifyCache},
+        Rename,
+    };
+
+    let tmp_dir = tempdir::TempDir::new("crev-verify-cache")?;
+    let root = tmp_dir.path();
+    std::fs::create_dir_all(root.join(".crev"))?;
+    let rename = Rename {
+        from: PathBuf::from("src/old.rs"),
+        digest: vec![3; 32],
+        unchanged: true,
+        recorded: false,
+    };
+    let cached = CachedFile {
+        raw_digest: vec![1; 32],
+        digest: vec![2; 32],
+        normalization: vec![],
+        status: VerificationStatus::Verified(TrustLevel::Medium),
+        rename: Some(rename.clone()),
+        previous_status: None,
+    };
+    let mut cache = VerifyCache::load(root, b"key");
+    cache.set_files(
+        vec![(PathBuf::from("src/lib.rs"), cached)]
+            .into_iter()
+            .collect(),
+    );
+    cache.save(root)?;
+
+    let cache = VerifyCache::load(root, b"key");
+    let path = Path::new("src/lib.rs");
+    let hit = cache.get(path, &[1; 32], Some(&rename));
+    assert_eq!(hit.map(|cached| &cached.digest), Some(&vec![2; 32]));
+    assert_eq!(
+        hit.map(|cached| &cached.status),
+        Some(&VerificationStatus::Verified(TrustLevel::Medium))
+    );
+    // the file changed, or was renamed differently
+    assert!(cache.get(path, &[4; 32], Some(&rename)).is_none());
+    assert!(cache.get(path, &[1; 32], None).is_none());
+    // the proofs changed
+    assert!(VerifyCache::load(root, b"other key")
+        .get(path, &[1; 32], Some(&rename))
+        .is_none());
+    Ok(())
+}
+
+#[test]
+fn annotate_regions_in_margin() {
+    use crate::annotate::{Annotation, RegionStatus};
+
+    let source = "use std::fmt;\n\n/// Parse it\nfn parse(s: &str) -> u8 {\n    s.len() as u8\n}\n";
+    let regions = rust_anchors::source_regions(source);
+    let lines: Vec<_> = regions
+        .iter()
+        .map(|region| (&region.anchor.name[..], region.first_line, region.last_line))
+        .collect();
+    assert_eq!(lines, vec![("std : : fmt", 1, 1), ("parse", 4, 6)]);
+    assert_eq!(
+        regions
+            .iter()
+            .map(|region| region.anchor.clone())
+            .collect::<Vec<_>>(),
+        rust_anchors::source_anchors(source)
+    );
+
+    let annotation = Annotation {
+        path: PathBuf::from("src/lib.rs"),
+        status: VerificationStatus::Unknown,
+        reviews: vec![],
+        advisories: vec!["https://example.com/bot (dangerous): RUSTSEC-0000-0000".into()],
+        regions: vec![RegionStatus {
+            region: regions[1].clone(),
+            status: VerificationStatus::Flagged,
+        }],
+        source: source.into(),
+    };
+    let text = annotation.to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "src/lib.rs: unknown");
+    assert_eq!(lines[1], "  no reviews of the current content");
+    assert_eq!(
+        lines[2],
+        "  advisory: https://example.com/bot (dangerous): RUSTSEC-0000-0000"
+    );
+    assert_eq!(lines[4], "    1          | use std::fmt;");
+    assert_eq!(lines[7], "    4 flagged  | fn parse(s: &str) -> u8 {");
+    assert_eq!(lines[8], "    5 :        |     s.len() as u8");
+}
+
+#[test]
+fn quarantine_keeps_evidence() -> Result<()> {
+    use crate::quarantine::{Entry, Quarantine};
+
+    let tmp_dir = tempdir::TempDir::new("crev-quarantine")?;
+    let quarantine = Quarantine::new(&tmp_dir.path().join("quarantine"));
+    assert!(quarantine.list()?.is_empty());
+
+    let entry = |reason: &str| Entry {
+        url: "https://example.com/proofs".into(),
+        path: PathBuf::from("abc/reviews/2019-01.proof.crev"),
+        reason: reason.into(),
+        date: crev_common::now(),
+    };
+    let forged = quarantine
+        .add(&entry("invalid signature"), "forged proof")?
+        .expect("new data");
+    // the same data again keeps its first entry
+    assert_eq!(quarantine.add(&entry("again"), "forged proof")?, None);
+    let huge = quarantine
+        .add(&entry("can't be parsed"), "huge proof")?
+        .expect("new data");
+    assert_eq!(quarantine.list()?.len(), 2);
+
+    let (id, found, data) = quarantine.inspect(&forged[..10])?;
+    assert_eq!(id, forged);
+    assert_eq!(found.reason, "invalid signature");
+    assert_eq!(data, "forged proof");
+    assert!(quarantine.inspect("no such id").is_err());
+
+    assert_eq!(quarantine.purge(Some(&huge))?, 1);
+    assert_eq!(quarantine.list()?.len(), 1);
+    assert_eq!(quarantine.purge(None)?, 1);
+    assert!(quarantine.list()?.is_empty());
+    Ok(())
+}
+
+// Revocations hide the proofs of their own author, wherever they come in
+// the import, and round-trip through the signed form.
+#[test]
+fn revocation_hides_own_proofs() -> Result<()> {
+    use crev_data::proof::{
+        revocation::{RevocationBuilder, RevokedProof},
+        Content, Proof,
+    };
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let a_to_c = a
+        .create_trust_proof(vec![c.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let revoke = |from: &OwnId, digest: &[u8]| -> Result<Proof> {
+        let revocation = RevocationBuilder::default()
+            .from(from.id.clone())
+            .proofs(vec![RevokedProof {
+                digest: digest.to_owned(),
+            }])
+            .comment("mistake".into())
+            .build()
+            .map_err(|e| format_err!("{}", e))?;
+        revocation.sign_by(from)
+    };
+    let a_revokes_b = revoke(&a, &a_to_b.digest)?;
+    // only the author of a proof can revoke it
+    let c_revokes_c = revoke(&c, &a_to_c.digest)?;
+
+    let parsed = Proof::parse(a_revokes_b.to_string().as_bytes())?.remove(0);
+    parsed.verify()?;
+    match parsed.content {
+        Content::Revocation(ref revocation) => assert!(revocation.revokes(&a_to_b.digest)),
+        _ => bail!("Wrong proof type"),
+    }
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b.clone(), a_to_c.clone(), parsed, c_revokes_c].into_iter());
+    assert!(trustdb.is_revoked(&a_to_b));
+    assert!(!trustdb.is_revoked(&a_to_c));
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &Default::default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_none());
+    assert!(trust_set.get_effective_trust_level(c.as_ref()).is_some());
+
+    // proofs imported later stay revoked
+    trustdb.import_from_iter(vec![a_to_b].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &Default::default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_none());
+    Ok(())
+}
+
+// A revoked proof written back with its keys reordered and its whitespace
+// changed keeps its digest, and so stays revoked.
+#[test]
+fn reformatted_proof_stays_revoked() -> Result<()> {
+    use crev_data::proof::revocation::{RevocationBuilder, RevokedProof};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+    let a_revokes_b = RevocationBuilder::default()
+        .from(a.id.clone())
+        .proofs(vec![RevokedProof {
+            digest: a_to_b.digest.clone(),
+        }])
+        .comment("mistake".into())
+        .build()
+        .map_err(|e| format_err!("{}", e))?
+        .sign_by(&a)?;
+
+    // JSON is YAML too, with the keys sorted and laid out differently
+    let mut reformatted = a_to_b.to_serialized();
+    let value: serde_yaml::Value = serde_yaml::from_str(&reformatted.body)?;
+    reformatted.body = serde_json::to_string_pretty(&value)? + "\n";
+    assert_ne!(reformatted.body, a_to_b.body);
+    let reformatted = reformatted.to_parsed()?;
+    reformatted.verify()?;
+    assert_eq!(reformatted.digest, a_to_b.digest);
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_revokes_b, reformatted.clone()].into_iter());
+    assert!(trustdb.is_revoked(&reformatted));
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &Default::default());
+    assert!(trust_set.get_effective_trust_level(b.as_ref()).is_none());
+    Ok(())
+}
+
+#[test]
+fn fetch_progress_resumes_until_finished() -> Result<()> {
+    use crate::fetch_progress::FetchProgress;
+
+    let tmp_dir = tempdir::TempDir::new("crev-fetch-progress")?;
+    let path = tmp_dir.path().join("fetch-progress.yaml");
+
+    let mut progress = FetchProgress::load(&path);
+    assert!(!progress.is_fetched("https://a"));
+    progress.mark_fetched("https://a")?;
+    // interrupted here; the next run picks up the checkpoint
+    let mut progress = FetchProgress::load(&path);
+    assert!(progress.is_fetched("https://a"));
+    assert!(!progress.is_fetched("https://b"));
+    progress.mark_fetched("https://b")?;
+    progress.finish()?;
+
+    let progress = FetchProgress::load(&path);
+    assert!(!progress.is_fetched("https://a"));
+    assert!(!progress.is_fetched("https://b"));
+    Ok(())
+}
+
+// The whole workflow, through `test_support`: a review published by one
+// user verifies the project for another once they trust the reviewer.
+#[test]
+fn review_fetch_and_verify_workflow() -> Result<()> {
+    use crate::{
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    alice.publish()?;
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    local.fetch_url(&alice.url())?;
+    let outcome = |repo: &mut Repo| -> Result<FileOutcome> {
+        let result = repo.verify(true, &default())?;
+        let file = result
+            .files
+            .into_iter()
+            .find(|file| file.path == Path::new("src/lib.rs"))
+            .ok_or_else(|| format_err!("src/lib.rs not verified"))?;
+        Ok(file.outcome)
+    };
+    let mut repo = Repo::open(&project)?;
+    // alice, the trust root of the project, is not trusted yet
+    assert!(outcome(&mut repo).is_err());
+
+    local.create_trust_proof(
+        vec![alice.id.id.id.to_string()],
+        TrustLevel::High,
+        "".into(),
+        &TestEnv::passphrase,
+    )?;
+    assert_eq!(outcome(&mut repo)?, FileOutcome::Verified);
+    Ok(())
+}
+
+// Queued drafts are shown one by one before signing, and the declined
+// ones stay queued.
+#[test]
+fn sign_approved_drafts() -> Result<()> {
+    use crate::{test_support::TestEnv, ProofStore};
+    use std::cell::RefCell;
+
+    let env = TestEnv::new()?;
+    let bob = env.new_user("bob")?;
+    let carol = env.new_user("carol")?;
+    let alice = env.new_user("alice")?;
+    let local = alice.local()?;
+    let trust = |to: &OwnId| -> Result<crev_data::proof::Content> {
+        Ok(alice
+            .id
+            .create_trust_proof(vec![to.as_pubid().to_owned()], TrustLevel::High)?
+            .into())
+    };
+    let to_bob = trust(&bob.id)?;
+    let to_carol = trust(&carol.id)?;
+    local.queue_draft(&to_bob)?;
+    local.queue_draft(&to_carol)?;
+    let to_bob = to_bob.to_unsigned_string();
+    let queued = local.queued_drafts()?;
+    let proofs_before = local.proofs_iter()?.count();
+
+    let shown = RefCell::new(vec![]);
+    let approve = |_path: &std::path::Path, content: &crev_data::proof::Content| {
+        shown.borrow_mut().push(content.to_unsigned_string());
+        Ok(content.to_unsigned_string() == to_bob)
+    };
+    assert_eq!(local.sign_drafts(&queued, &approve, &TestEnv::passphrase)?, 1);
+    assert_eq!(shown.borrow().len(), 2);
+    assert_eq!(local.proofs_iter()?.count(), proofs_before + 1);
+    let left = local.queued_drafts()?;
+    assert_eq!(left.len(), 1);
+    assert_eq!(
+        crev_common::read_file_to_string(&left[0])?,
+        to_carol.to_unsigned_string()
+    );
+
+    Ok(())
+}
+
+// Proofs stored in a scope count only as authorized by its trust root, so
+// removing the package config doesn't let them all in, and a trust root
+// outside of the web of trust of the user is an error.
+#[test]
+fn scope_proofs_need_a_trusted_trust_root() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+    use crev_data::proof::review::Rating;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let source = "pub fn foo() {}\n";
+    let project = env.new_project("foo", &[("src/lib.rs", source)])?;
+    Repo::init(&project, alice.id.id.id.to_string())?;
+
+    let mallory = OwnId::generate_for_git_url("https://mallory");
+    let review = code_review(
+        &mallory,
+        Rating::Positive,
+        vec![reviewed_file(
+            "src/lib.rs",
+            crev_common::blake2b256sum(source.as_bytes()),
+        )],
+    )?
+    .sign_by(&mallory)?;
+    std::fs::create_dir_all(project.join(".crev/proofs"))?;
+    std::fs::write(project.join(".crev/proofs/mallory.proof.crev"), review.to_string())?;
+
+    let review_count = |repo: &mut Repo| -> Result<usize> {
+        Ok(repo.files_verify(true, &default())?[0].reviews.len())
+    };
+    let mut repo = Repo::open(&project)?;
+    assert_eq!(review_count(&mut repo)?, 0);
+
+    std::fs::remove_file(project.join(".crev/config.yaml"))?;
+    assert_eq!(review_count(&mut repo)?, 0);
+
+    Repo::init(&project, mallory.id.id.to_string())?;
+    let error = repo
+        .verify(true, &default())
+        .err()
+        .ok_or_else(|| format_err!("an untrusted trust root was accepted"))?;
+    assert!(error.to_string().contains("is not in your web of trust"));
+    Ok(())
+}
+
+// Reviewer groups count only once the trust root reviewed the package
+// config with them.
+#[test]
+fn reviewer_groups_need_a_reviewed_config() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+    use crev_data::proof::review::Rating;
+    use std::io::Write;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let source = "pub fn foo() {}\n";
+    let project = env.new_project("foo", &[("src/lib.rs", source)])?;
+    Repo::init(&project, alice.id.id.id.to_string())?;
+    let config_path = project.join(".crev/config.yaml");
+    let mut config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&config_path)?;
+    writeln!(
+        config,
+        "\npath-requirements:\n  - path: \"src/**\"\n    reviewers: 1\n    owners: [\"@core\"]\n\
+         reviewer-groups:\n  core: [\"{}\"]",
+        alice.id.id.id
+    )?;
+    drop(config);
+
+    let review = |path: &str, digest: Vec<u8>| -> Result<()> {
+        let proof = code_review(&alice.id, Rating::Positive, vec![reviewed_file(path, digest)])?
+            .sign_by(&alice.id)?;
+        alice.local()?.insert_own(&proof, &alice.id)
+    };
+    let is_verified = |repo: &mut Repo| -> Result<bool> {
+        Ok(repo.files_verify(true, &default())?[0].status.is_verified())
+    };
+    let mut repo = Repo::open(&project)?;
+
+    review("src/lib.rs", crev_common::blake2b256sum(source.as_bytes()))?;
+    assert!(!is_verified(&mut repo)?);
+
+    review(
+        ".crev/config.yaml",
+        crev_common::blake2b256sum_file(&config_path)?,
+    )?;
+    assert!(is_verified(&mut repo)?);
+
+    Ok(())
+}
+
+// Evidence holds only the proofs the verification counted, and doesn't
+// need a package config.
+#[test]
+fn evidence_holds_only_counted_proofs() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+    use crev_data::proof::review::Rating;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let source = "pub fn foo() {}\n";
+    let project = env.new_project("foo", &[("src/lib.rs", source)])?;
+    let review = |comment: &str| -> Result<crev_data::proof::Proof> {
+        let mut review = code_review(
+            &alice.id,
+            Rating::Positive,
+            vec![reviewed_file(
+                "src/lib.rs",
+                crev_common::blake2b256sum(source.as_bytes()),
+            )],
+        )?;
+        review.comment = comment.into();
+        let proof = review.sign_by(&alice.id)?;
+        alice.local()?.insert_own(&proof, &alice.id)?;
+        Ok(proof)
+    };
+    review("first look")?;
+    let latest = review("second look")?;
+
+    let mut repo = Repo::open(&project)?;
+    let evidence = repo.collect_evidence("HEAD", &TestEnv::passphrase, &default())?;
+    assert!(evidence.config.is_none());
+    assert_eq!(
+        evidence
+            .proofs
+            .iter()
+            .map(|proof| proof.digest.clone())
+            .collect::<Vec<_>>(),
+        vec![latest.digest]
+    );
+
+    Ok(())
+}
+
+// A review counts only with the normalizations recorded with its file,
+// not with the ones of the package config
+#[test]
+fn reviews_count_with_their_own_normalizations() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+    use crev_data::proof::review::{code::Normalization, Rating};
+    use std::io::Write;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("docs/a.md", "text  \n")])?;
+    Repo::init(&project, alice.id.id.id.to_string())?;
+    let mut config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(project.join(".crev/config.yaml"))?;
+    writeln!(config, "\nnormalizations:\n  md: [trailing-whitespace]")?;
+
+    let status = |repo: &mut Repo| -> Result<crate::VerificationStatus> {
+        Ok(repo.files_verify(true, &default())?[0].status.clone())
+    };
+    let review = |normalization: Vec<Normalization>| -> Result<()> {
+        let mut file = reviewed_file("docs/a.md", crev_common::blake2b256sum(b"text\n"));
+        file.normalization = normalization;
+        let proof = code_review(&alice.id, Rating::Positive, vec![file])?.sign_by(&alice.id)?;
+        alice.local()?.insert_own(&proof, &alice.id)
+    };
+    let mut repo = Repo::open(&project)?;
+
+    // reviewed without trailing whitespace, which the content has
+    review(vec![])?;
+    assert_eq!(status(&mut repo)?, crate::VerificationStatus::Unknown);
+
+    review(vec![Normalization::TrailingWhitespace])?;
+    assert!(status(&mut repo)?.is_verified());
+
+    Ok(())
+}
+
+// Trust overrides of a package config count only if its trust root is
+// trusted, and don't lift the distrust of the current id.
+#[test]
+fn trust_overrides_need_a_trusted_trust_root() -> Result<()> {
+    use crate::{
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+    use std::io::Write;
+
+    let env = TestEnv::new()?;
+    let bob = env.new_user("bob")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, bob.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    bob.publish()?;
+    std::fs::remove_dir_all(project.join(".crev/proofs"))?;
+
+    let alice = env.new_user("alice")?;
+    let local = alice.local()?;
+    local.fetch_url(&bob.url())?;
+    let configure = |trust_root: &crev_data::Id| -> Result<()> {
+        std::fs::remove_file(project.join(".crev/config.yaml"))?;
+        Repo::init(&project, trust_root.to_string())?;
+        let mut config = std::fs::OpenOptions::new()
+            .append(true)
+            .open(project.join(".crev/config.yaml"))?;
+        writeln!(
+            config,
+            "\ntrust-overrides:\n  - id: {}\n    trust: high",
+            bob.id.id.id
+        )?;
+        Ok(())
+    };
+    let outcome = || -> Result<FileOutcome> {
+        Ok(Repo::open(&project)?.verify(true, &default())?.files[0].outcome)
+    };
+
+    // bob overriding the trust in himself
+    configure(&bob.id.id.id)?;
+    assert_eq!(outcome()?, FileOutcome::ReviewedByUntrusted);
+
+    configure(&alice.id.id.id)?;
+    assert_eq!(outcome()?, FileOutcome::Verified);
+
+    local.create_trust_proof(
+        vec![bob.id.id.id.to_string()],
+        TrustLevel::Distrust,
+        "".into(),
+        &TestEnv::passphrase,
+    )?;
+    assert_eq!(outcome()?, FileOutcome::ReviewedByUntrusted);
+    Ok(())
+}
+
+#[test]
+fn expired_proofs_are_ignored_until_reissued() -> Result<()> {
+    use crate::test_support::TestEnv;
+    use crev_data::proof::Content;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let bob = OwnId::generate_for_git_url("https://bob");
+
+    let mut trust = alice
+        .id
+        .create_trust_proof(vec![bob.as_pubid().to_owned()], TrustLevel::High)?;
+    trust.date = crev_common::now() - chrono::Duration::days(30);
+    trust.expires = Some(trust.date + chrono::Duration::days(10));
+    let expired = trust.sign_by(&alice.id)?;
+    assert!(expired.content.is_expired_at(&chrono::Utc::now()));
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![expired.clone()].into_iter());
+    let trust_set = trustdb.calculate_trust_set(alice.id.as_ref(), &default());
+    assert!(trust_set.get_effective_trust_level(bob.as_ref()).is_none());
+
+    let local = alice.local()?;
+    local.insert_own(&expired, &alice.id)?;
+    assert_eq!(local.expired_own_proofs()?.len(), 1);
+
+    let reissued = local.reissue_proofs(std::slice::from_ref(&expired), &TestEnv::passphrase)?;
+    let content: &Content = &reissued[0].content;
+    assert!(!content.is_expired_at(&chrono::Utc::now()));
+    assert!(content.same_besides_validity(&expired.content));
+    assert_eq!(
+        content
+            .expires()
+            .map(|e| e.signed_duration_since(*content.date())),
+        Some(chrono::Duration::days(10))
+    );
+    assert!(local.expired_own_proofs()?.is_empty());
+
+    let (_db, trust_set) = local.load_db(&default())?;
+    assert!(trust_set.get_effective_trust_level(bob.as_ref()).is_some());
+    Ok(())
+}
+
+#[test]
+fn advisory_flags_affected_revisions() -> Result<()> {
+    use crate::{
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+    use crev_data::proof::advisory::Severity;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.identify_project(
+        "foo".into(),
+        "https://github.com/example/foo",
+        &TestEnv::passphrase,
+    )?;
+    let introduced =
+        TestEnv::change_project(&project, &[("src/lib.rs", "pub fn foo() { bar() }\n")])?;
+    let fixed = TestEnv::change_project(&project, &[("src/lib.rs", "pub fn foo() {}\n")])?;
+
+    let mut repo = Repo::open(&project)?;
+    repo.create_advisory(
+        Severity::High,
+        Some(&introduced),
+        Some("HEAD"),
+        &[],
+        &TestEnv::passphrase,
+    )?;
+    assert_eq!(repo.verify(true, &default())?.status, "unknown");
+
+    let git = git2::Repository::open(&project)?;
+    git.set_head_detached(git2::Oid::from_str(&introduced)?)?;
+    git.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+    assert_eq!(repo.verify(true, &default())?.status, "danger");
+
+    // flagged files are flagged wherever they are, whatever the revision
+    repo.create_advisory(
+        Severity::Low,
+        Some(&fixed),
+        Some(&fixed),
+        &[project.join("src/lib.rs")],
+        &TestEnv::passphrase,
+    )?;
+    let result = repo.verify(true, &default())?;
+    let file = result
+        .files
+        .iter()
+        .find(|file| file.path == Path::new("src/lib.rs"))
+        .ok_or_else(|| format_err!("src/lib.rs not verified"))?;
+    assert_eq!(file.outcome, FileOutcome::Flagged);
+    assert_eq!(result.status, "danger");
+    Ok(())
+}
+
+#[test]
+fn commit_dry_run_is_deterministic() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+    use crev_data::proof::Content;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+
+    let date = chrono::DateTime::parse_from_rfc3339("2019-01-01T00:00:00+00:00")?;
+    let body = repo.commit_dry_run(false, false, false, Some(date))?;
+    assert_eq!(body, repo.commit_dry_run(false, false, false, Some(date))?);
+    assert!(body.contains("src/lib.rs"));
+    let content = Content::parse(&body, crev_data::proof::ProofType::Code)?;
+    assert_eq!(content.date(), &date);
+    assert_eq!(content.canonical_body(), body);
+
+    // nothing was stored, and the files are still staged
+    assert!(alice.local()?.known_proofs_iter()?.next().is_none());
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    Ok(())
+}
+
+#[test]
+fn package_review_covers_all_files() -> Result<()> {
+    use crate::{
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let files = [("src/lib.rs", "pub fn foo() {}\n"), ("README.md", "foo\n")];
+    let project = env.new_project("foo", &files)?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.trust_package(&TestEnv::passphrase, true)?;
+
+    let result = repo.verify(true, &default())?;
+    assert_eq!(result.files.len(), 2);
+    assert!(result
+        .files
+        .iter()
+        .all(|file| file.outcome == FileOutcome::Verified));
+    assert!(result.verified);
+
+    // the review is of the tree as it was
+    TestEnv::change_project(&project, &[("README.md", "bar\n")])?;
+    let result = repo.verify(true, &default())?;
+    assert!(result
+        .files
+        .iter()
+        .all(|file| file.outcome != FileOutcome::Verified));
+    Ok(())
+}
+
+// Verifying some paths of a project checks only the files under them, and
+// refuses paths outside of it.
+#[test]
+fn verify_paths_checks_only_the_files_under_them() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project(
+        "foo",
+        &[
+            ("src/lib.rs", "pub mod crypto;\n"),
+            ("src/crypto/aes.rs", "pub fn encrypt() {}\n"),
+            ("src/crypto/rsa.rs", "pub fn sign() {}\n"),
+        ],
+    )?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/crypto/aes.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let files = repo.verify_paths(&[project.join("src/crypto")], true, &default())?;
+    let outcome = |path: &str| {
+        files
+            .iter()
+            .find(|file| file.path == Path::new(path))
+            .map(|file| file.status.is_verified())
+    };
+    assert_eq!(files.len(), 2);
+    assert_eq!(outcome("src/crypto/aes.rs"), Some(true));
+    assert_eq!(outcome("src/crypto/rsa.rs"), Some(false));
+    assert_eq!(outcome("src/lib.rs"), None);
+
+    let files = repo.verify_paths(&[project.join("src/crypto/aes.rs")], true, &default())?;
+    assert_eq!(files.len(), 1);
+
+    let outside = env.path().join("elsewhere.rs");
+    std::fs::write(&outside, "fn elsewhere() {}\n")?;
+    let error = repo
+        .verify_paths(&[outside], true, &default())
+        .err()
+        .ok_or_else(|| format_err!("a path outside of the project verified"))?;
+    assert!(error.to_string().contains("is outside of"));
+    Ok(())
+}
+
+#[test]
+fn simulate_trust_in_reviewers() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv, VerificationStatus};
+
+    let env = TestEnv::new()?;
+    let bob = env.new_user("bob")?;
+    let files = [("src/lib.rs", "pub fn foo() {}\n"), ("README.md", "foo\n")];
+    let project = env.new_project("foo", &files)?;
+    Repo::init(&project, bob.id.id.id.to_string())?;
+
+    let alice = env.new_user("alice")?;
+    let mut repo = Repo::open(&project)?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    alice.publish()?;
+
+    bob.local()?.fetch_url(&alice.url())?;
+    let mut repo = Repo::open(&project)?;
+
+    let alice_id = &alice.id.id.id;
+    let changes = repo.simulate_trust(
+        std::slice::from_ref(alice_id),
+        TrustLevel::Medium,
+        &default(),
+    )?;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, Path::new("src/lib.rs"));
+    assert_eq!(changes[0].before, VerificationStatus::Unknown);
+    assert!(changes[0].after.is_verified());
+
+    let candidates = repo.trust_candidates(TrustLevel::Medium, &default())?;
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].id, *alice_id);
+    assert_eq!(candidates[0].verified, vec![PathBuf::from("src/lib.rs")]);
+
+    // nothing was trusted for real
+    assert!(!repo.verify(true, &default())?.verified);
+    Ok(())
+}
+
+#[test]
+fn org_policy_is_merged_with_personal_settings() -> Result<()> {
+    use crate::{
+        org_policy::{OrgPolicySource, POLICY_FILE_NAME},
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+    use crev_data::org_policy::BannedId;
+
+    let env = TestEnv::new()?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let alice = env.new_user("alice")?;
+    Repo::init(&project, alice.id.id.id.to_string())?;
+    let carol = env.new_user("carol")?;
+    for user in &[&alice, &carol] {
+        user.activate();
+        let mut repo = Repo::open(&project)?;
+        repo.add(vec![project.join("src/lib.rs")])?;
+        repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+        user.publish()?;
+    }
+
+    let acme = env.new_user("acme")?;
+    let local = acme.local()?;
+    local.fetch_url(&alice.url())?;
+    let policy = local.create_org_policy(
+        "Acme".into(),
+        vec!["2:low".parse()?],
+        vec![alice.id.id.id.to_string()],
+        vec![BannedId {
+            id: carol.id.id.id.clone(),
+            reason: "Compromised".into(),
+        }],
+        &TestEnv::passphrase,
+    )?;
+    let policy_repo = env.new_project("acme-policy", &[(POLICY_FILE_NAME, &policy.to_string())])?;
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    local.fetch_url(&alice.url())?;
+    local.fetch_url(&carol.url())?;
+    local.build_trust_proof_with_level(
+        vec![alice.id.id.id.to_string(), carol.id.id.id.to_string()],
+        &TestEnv::passphrase,
+        TrustLevel::Medium,
+    )?;
+    let mut repo = Repo::open(&project)?;
+    let mut lib_verified = || -> Result<bool> {
+        let result = repo.verify(true, &default())?;
+        Ok(result.files[0].outcome == FileOutcome::Verified)
+    };
+    assert!(lib_verified()?);
+
+    let mut config = local.load_user_config()?;
+    config.org_policy = Some(OrgPolicySource {
+        url: policy_repo.display().to_string(),
+        curator: acme.id.id.id.clone(),
+    });
+    local.store_user_config(&config)?;
+    assert!(local.fetch_org_policy()?.is_some());
+
+    // the own trust in a seed stands, but not in a banned id
+    let (_db, trust_set) = local.load_db(&default())?;
+    assert_eq!(
+        trust_set.get_effective_trust_level(&alice.id.id.id),
+        Some(TrustLevel::Medium)
+    );
+    assert_eq!(trust_set.get_effective_trust_level(&carol.id.id.id), None);
+    // so only one of the two reviewers required counts
+    assert!(!lib_verified()?);
+
+    // only a policy by the configured curator is kept
+    config.org_policy = Some(OrgPolicySource {
+        url: policy_repo.display().to_string(),
+        curator: bob.id.id.id.clone(),
+    });
+    local.store_user_config(&config)?;
+    assert!(local.fetch_org_policy().is_err());
+    assert!(local.load_org_policy()?.is_none());
+    assert!(lib_verified()?);
+    Ok(())
+}
+
+#[test]
+fn audit_log_records_local_actions() -> Result<()> {
+    use crate::{audit_log::Action, repo::Repo, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    alice.publish()?;
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    local.fetch_url(&alice.url())?;
+    local.build_trust_proof_with_level(
+        vec![alice.id.id.id.to_string()],
+        &TestEnv::passphrase,
+        TrustLevel::High,
+    )?;
+
+    let log = local.audit_log();
+    let entries = log.entries()?;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].action, Action::RemoteAdded { url: alice.url() });
+    assert_eq!(entries[0].by, None);
+    match entries[1].action {
+        Action::TrustGranted { ref ids, level, .. } => {
+            assert_eq!(ids, &vec![alice.id.id.id.clone()]);
+            assert_eq!(level, TrustLevel::High);
+        }
+        ref action => panic!("unexpected {:?}", action),
+    }
+    assert_eq!(entries[1].by, Some(bob.id.id.id.clone()));
+    assert!(log.check()?.is_empty());
+
+    // an entry interrupted while being written is dropped on the next one
+    let path = local.audit_log_path();
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, format!("{}{{\"date\":", content))?;
+    log.append(Action::RemoteAdded { url: "x".into() }, None)?;
+    assert_eq!(log.entries()?.len(), 3);
+    assert!(log.check()?.is_empty());
+
+    // removing an entry breaks the chain
+    let content = std::fs::read_to_string(&path)?;
+    let mut lines: Vec<_> = content.lines().collect();
+    lines.remove(1);
+    std::fs::write(&path, format!("{}\n", lines.join("\n")))?;
+    assert_eq!(log.check()?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn interrupted_parallel_fetch_resumes() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv, util::interrupt};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    alice.publish()?;
+    let carol = env.new_user("carol")?;
+    let dave = env.new_user("dave")?;
+    for user in &[&carol, &dave] {
+        let local = user.local()?;
+        local.fetch_url(&alice.url())?;
+        local.build_trust_proof_with_level(
+            vec![alice.id.id.id.to_string()],
+            &TestEnv::passphrase,
+            TrustLevel::Low,
+        )?;
+        user.publish()?;
+    }
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    let mut config = local.load_user_config()?;
+    config.fetch_jobs = Some(2);
+    config.fetch_timeout = Some(60);
+    local.store_user_config(&config)?;
+    for user in &[&alice, &carol, &dave] {
+        local.fetch_url(&user.url())?;
+    }
+
+    let alice_local = alice.local()?;
+    alice_local.fetch_url(&carol.url())?;
+    alice_local.build_trust_proof_with_level(
+        vec![carol.id.id.id.to_string()],
+        &TestEnv::passphrase,
+        TrustLevel::Medium,
+    )?;
+    alice.publish()?;
+    let alice_trusts_carol = || -> Result<bool> {
+        let (db, _) = local.load_db(&default())?;
+        Ok(db
+            .calculate_trust_set(&alice.id.id.id, &default())
+            .get_effective_trust_level(&carol.id.id.id)
+            .is_some())
+    };
+
+    // interrupted before anything new came in: nothing half-fetched
+    interrupt::interrupt();
+    let res = local.fetch_all();
+    interrupt::reset();
+    assert!(format!("{}", res.unwrap_err()).contains("interrupted"));
+    assert!(!alice_trusts_carol()?);
+    for user in &[&alice, &carol, &dave] {
+        let dir = local.get_remote_git_cache_path(&user.url());
+        assert!(dir.exists());
+        assert!(!dir.with_extension("partial").exists());
+    }
+
+    // the next run picks up where it was left
+    local.fetch_all()?;
+    assert!(alice_trusts_carol()?);
+    Ok(())
+}
+
+#[test]
+fn proof_index_follows_proof_stores() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    alice.publish()?;
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    local.fetch_url(&alice.url())?;
+    let index = local.proof_index()?;
+    let reviews: Vec<_> = index.by_file(Path::new("src/lib.rs")).collect();
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].from, alice.id.id.id);
+    assert_eq!(reviews[0].package.as_deref(), Some("foo"));
+    assert_eq!(index.by_package("foo").count(), 1);
+    assert_eq!(reviews[0].location.read()?.digest, reviews[0].digest);
+    assert_eq!(index.by_author(&bob.id.id.id).count(), 0);
+
+    // kept up to date with own proofs as they're added
+    local.build_trust_proof_with_level(
+        vec![alice.id.id.id.to_string()],
+        &TestEnv::passphrase,
+        TrustLevel::High,
+    )?;
+    let index = local.proof_index()?;
+    let trust: Vec<_> = index.by_author(&bob.id.id.id).collect();
+    assert_eq!(trust.len(), 1);
+    let digest = crev_common::base64_encode(&trust[0].digest);
+    assert_eq!(
+        local.find_proof_by_digest(&digest[..8])?.digest,
+        trust[0].digest
+    );
+
+    // and rebuilt when it can't be read
+    std::fs::write(env.path().join("users/bob/cache/proof-index"), "garbage")?;
+    let count = index.entries().count();
+    assert_eq!(local.proof_index()?.entries().count(), count);
+    assert_eq!(local.rebuild_proof_index()?.entries().count(), count);
+    Ok(())
+}
+
+#[test]
+fn find_proofs_by_filter() -> Result<()> {
+    use crate::{proof_index::ProofFilter, repo::Repo, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project(
+        "foo",
+        &[("src/lib.rs", "pub fn foo() {}\n"), ("README", "Foo\n")],
+    )?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    alice.publish()?;
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    local.fetch_url(&alice.url())?;
+    local.build_trust_proof_with_level(
+        vec![alice.id.id.id.to_string()],
+        &TestEnv::passphrase,
+        TrustLevel::High,
+    )?;
+
+    let find = |filter: ProofFilter| local.find_proofs(&filter);
+    assert_eq!(find(default())?.len(), 2);
+    let reviews = find(ProofFilter {
+        path: Some("src/lib.rs".into()),
+        ..default()
+    })?;
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].content.author_id(), alice.id.id.id);
+    assert!(find(ProofFilter {
+        path: Some("README".into()),
+        ..default()
+    })?
+    .is_empty());
+
+    let trust = find(ProofFilter {
+        author: Some(bob.id.id.id.clone()),
+        proof_type: Some("trust".into()),
+        ..default()
+    })?;
+    assert_eq!(trust.len(), 1);
+    assert!(find(ProofFilter {
+        author: Some(bob.id.id.id.clone()),
+        proof_type: Some("code review".into()),
+        ..default()
+    })?
+    .is_empty());
+    assert_eq!(
+        find(ProofFilter {
+            package: Some("foo".into()),
+            ..default()
+        })?
+        .len(),
+        1
+    );
+
+    let date = *trust[0].content.date();
+    let since_trust = find(ProofFilter {
+        since: Some(date),
+        ..default()
+    })?;
+    assert_eq!(since_trust.len(), 1);
+    assert_eq!(since_trust[0].digest, trust[0].digest);
+    assert!(find(ProofFilter {
+        until: Some(date - chrono::Duration::days(365)),
+        ..default()
+    })?
+    .is_empty());
+    Ok(())
+}
+
+#[test]
+fn file_kinds_scope_requirements_and_coverage() -> Result<()> {
+    use crate::{
+        file_kind,
+        proof_index::ProofFilter,
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+    use crev_data::proof::review::code::FileKind;
+    use std::io::Write;
+
+    let guess = |path: &str| file_kind::guess(Path::new(path));
+    assert_eq!(guess("src/lib.rs"), FileKind::Code);
+    assert_eq!(guess("Cargo.toml"), FileKind::Config);
+    assert_eq!(guess("infra/main.tf"), FileKind::Config);
+    assert_eq!(guess("Dockerfile"), FileKind::Config);
+    assert_eq!(guess("README.md"), FileKind::Docs);
+    assert_eq!(guess("LICENSE"), FileKind::Docs);
+    assert_eq!(guess("assets/logo.png"), FileKind::Data);
+    assert_eq!(guess("deploy/run"), FileKind::Code);
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project(
+        "foo",
+        &[
+            ("src/lib.rs", "pub fn foo() {}\n"),
+            ("README.md", "Foo\n"),
+            ("deploy/run", "exec foo\n"),
+        ],
+    )?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(project.join(".crev/config.yaml"))?
+        .write_all(
+            b"\npath-requirements:\n  - path: '**'\n    reviewers: 2\n    kinds: [code]\n\
+              file-kinds:\n  - path: 'deploy/**'\n    kind: config\n",
+        )?;
+    repo.add(vec![
+        project.join("src/lib.rs"),
+        project.join("README.md"),
+        project.join("deploy/run"),
+    ])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let reviews = alice.local()?.find_proofs(&ProofFilter {
+        proof_type: Some("code review".into()),
+        ..default()
+    })?;
+    let kinds: Vec<_> = match reviews[0].content {
+        crev_data::proof::Content::Code(ref code) => {
+            let mut files: Vec<_> = code.files.iter().collect();
+            files.sort_by_key(|file| file.path.clone());
+            files.iter().map(|file| file.kind).collect()
+        }
+        _ => bail!("not a code review"),
+    };
+    assert_eq!(
+        kinds,
+        vec![
+            Some(FileKind::Docs),
+            Some(FileKind::Config),
+            Some(FileKind::Code)
+        ]
+    );
+
+    // only the code needs a second reviewer
+    let result = Repo::open(&project)?.verify(true, &default())?;
+    let outcomes: Vec<_> = result
+        .files
+        .iter()
+        .filter(|file| !file.path.starts_with(".crev"))
+        .map(|file| (file.path.to_string_lossy().into_owned(), file.outcome))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("README.md".to_owned(), FileOutcome::Verified),
+            ("deploy/run".to_owned(), FileOutcome::Verified),
+            ("src/lib.rs".to_owned(), FileOutcome::RequirementsUnmet),
+        ]
+    );
+    let coverage = result.coverage_by_kind();
+    assert_eq!(coverage[&FileKind::Code].1, 0);
+    assert_eq!(coverage[&FileKind::Docs], (1, 1));
+    assert_eq!(coverage[&FileKind::Config].1, 1);
+    Ok(())
+}
+
+// A file verified only by its own author is told self-reviewed, and a
+// requirement for an independent reviewer isn't met by such reviews.
+#[test]
+fn self_reviews_and_independent_reviewers() -> Result<()> {
+    use crate::{
+        policy::{is_self_reviewed, unmet_requirements, PathRequirement},
+        repo::{FileOutcome, Repo},
+        test_support::TestEnv,
+    };
+    use crev_data::proof::review::code::FileKind::Code;
+    use std::{collections::HashMap, io::Write};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let requirements: Vec<PathRequirement> =
+        serde_yaml::from_str("- path: '**'\n  reviewers: 1\n  independent: true\n")?;
+    assert_eq!(
+        requirements[0].to_string(),
+        "** needs 1 reviewers with low trust, one of them not an author"
+    );
+    let authors = vec![a.id.id.clone()].into_iter().collect();
+    let approvers: HashMap<_, _> = vec![(a.id.id.clone(), TrustLevel::High)]
+        .into_iter()
+        .collect();
+    let path = Path::new("src/lib.rs");
+    assert!(is_self_reviewed(&authors, &approvers));
+    assert!(!is_self_reviewed(&authors, &default()));
+    assert_eq!(
+        unmet_requirements(
+            &requirements,
+            &default(),
+            path,
+            Code,
+            None,
+            &authors,
+            &approvers,
+            &default()
+        )?
+        .len(),
+        1
+    );
+    let mut approvers = approvers;
+    approvers.insert(b.id.id.clone(), TrustLevel::Low);
+    assert!(!is_self_reviewed(&authors, &approvers));
+    assert!(unmet_requirements(
+        &requirements,
+        &default(),
+        path,
+        Code,
+        None,
+        &authors,
+        &approvers,
+        &default()
+    )?
+    .is_empty());
+
+    // test projects are committed by test@example.com
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    let config_path = project.join(".crev/config.yaml");
+    let append_config = |yaml: &str| -> Result<()> {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&config_path)?
+            .write_all(format!("\n{}", yaml).as_bytes())?;
+        Ok(())
+    };
+    append_config(&format!(
+        "git-authors:\n  TEST@example.com: {}\n",
+        alice.id.id.id
+    ))?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let lib_rs = || -> Result<_> {
+        let result = Repo::open(&project)?.verify(true, &default())?;
+        let file = result
+            .files
+            .into_iter()
+            .find(|file| file.path == Path::new("src/lib.rs"))
+            .ok_or_else(|| format_err!("src/lib.rs not verified"))?;
+        Ok((file.outcome, file.self_reviewed))
+    };
+    assert_eq!(lib_rs()?, (FileOutcome::Verified, true));
+
+    append_config("path-requirements:\n  - path: '**'\n    reviewers: 1\n    independent: true\n")?;
+    assert_eq!(lib_rs()?, (FileOutcome::RequirementsUnmet, false));
+
+    // once the code is someone else's, alice is an independent reviewer
+    let config = std::fs::read_to_string(&config_path)?;
+    std::fs::write(
+        &config_path,
+        config.replace(
+            &format!("TEST@example.com: {}", alice.id.id.id),
+            &format!("test@example.com: {}", b.id.id),
+        ),
+    )?;
+    assert_eq!(lib_rs()?, (FileOutcome::Verified, false));
+    Ok(())
+}
+
+// Responding to a compromise revokes the proofs of the id and the id
+// itself, and carries its trust over to the backup id.
+#[test]
+fn compromised_id_hands_over_to_backup() -> Result<()> {
+    use crate::{
+        id::LockedId,
+        id_directory::{IdDirectory, IdStatus},
+        test_support::{TestEnv, PASSPHRASE},
+    };
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let local = alice.local()?;
+    let bob = OwnId::generate_for_git_url("https://bob");
+    let carol = OwnId::generate_for_git_url("https://carol");
+    let trust = |ids: &[&OwnId], level| -> Result<()> {
+        let ids = ids.iter().map(|id| id.as_pubid().to_owned()).collect();
+        let proof = alice
+            .id
+            .create_trust_proof(ids, level)?
+            .sign_by(&alice.id)?;
+        local.insert_own(&proof, &alice.id)
+    };
+    trust(&[&bob, &carol], TrustLevel::Low)?;
+    trust(&[&bob], TrustLevel::High)?;
+
+    let backup = OwnId::generate(alice.id.id.url.clone());
+    local.save_locked_id(&LockedId::from_own_id(&backup, PASSPHRASE)?)?;
+    let mut config = local.load_user_config()?;
+    config.backup_id = Some(backup.id.id.clone());
+    local.store_user_config(&config)?;
+
+    let response =
+        local.respond_to_compromise(None, "".into(), &TestEnv::passphrase, &TestEnv::passphrase)?;
+    assert_eq!(response.revoked, 2);
+    assert!(response.revocation.is_some());
+    assert_eq!(response.backup_id.as_ref(), Some(&backup.id.id));
+    let resigned: Vec<_> = response
+        .resigned
+        .iter()
+        .map(|proof| match proof.content {
+            crev_data::proof::Content::Trust(ref trust) => Ok((
+                trust.from.id.clone(),
+                trust.ids.iter().map(|id| id.id.clone()).collect::<Vec<_>>(),
+                trust.trust,
+            )),
+            _ => bail!("not a trust proof"),
+        })
+        .collect::<Result<_>>()?;
+    assert_eq!(
+        resigned,
+        vec![
+            (
+                backup.id.id.clone(),
+                vec![carol.id.id.clone()],
+                TrustLevel::Low
+            ),
+            (
+                backup.id.id.clone(),
+                vec![bob.id.id.clone()],
+                TrustLevel::High
+            ),
+        ]
+    );
+    assert!(response.checklist(false)[0].contains("crev git push"));
+    assert!(!response.checklist(true)[0].contains("crev git push"));
+
+    let directory = IdDirectory::read_from_dir(&local.get_proofs_dir_path()?)?
+        .ok_or_else(|| format_err!("no ids.yaml"))?;
+    let status = |id: &crev_data::Id| directory.get(id).map(|entry| entry.status);
+    assert_eq!(status(&alice.id.id.id), Some(IdStatus::Revoked));
+    assert_eq!(status(&backup.id.id), Some(IdStatus::Active));
+    assert_eq!(local.read_current_id()?, backup.id.id);
+    assert!(local
+        .read_unlocked_id(&alice.id.id.id, &TestEnv::passphrase)
+        .is_err());
+
+    let (_db, trust_set) = local.load_db(&default())?;
+    assert_eq!(
+        trust_set.get_effective_trust_level(&bob.id.id),
+        Some(TrustLevel::High)
+    );
+    assert_eq!(
+        trust_set.get_effective_trust_level(&carol.id.id),
+        Some(TrustLevel::Low)
+    );
+    Ok(())
+}
+
+// Once an id is revoked by its own proof repository, proofs forged with its
+// key are ignored in any other repository too.
+#[test]
+fn revoked_id_is_ignored_in_other_remotes() -> Result<()> {
+    use crate::{id_directory::IdDirectory, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let mallory = env.new_user("mallory")?;
+
+    // a review forged with the leaked key of alice, listed as one of the
+    // ids publishing to the repository of mallory
+    let forged = alice
+        .id
+        .create_package_review_proof(
+            crev_data::proof::PackageInfo {
+                version: "version".into(),
+                digest: vec![0; 32],
+                ..package_info("source", "name")
+            },
+            default(),
+            "".into(),
+        )?
+        .sign_by(&alice.id)?;
+    let mallory_dir = mallory.local()?.get_proofs_dir_path()?;
+    crev_common::store_str_to_file(
+        &mallory_dir.join(crate::proof::rel_store_path(&forged.content)),
+        &forged.to_string(),
+    )?;
+    let mut directory = IdDirectory::default();
+    directory.update(
+        &[
+            mallory.id.as_pubid().to_owned(),
+            alice.id.as_pubid().to_owned(),
+        ],
+        mallory.id.as_ref(),
+    );
+    directory.store_to_dir(&mallory_dir)?;
+    mallory.publish()?;
+
+    let bob = env.new_user("bob")?;
+    let local = bob.local()?;
+    local.fetch_url(&mallory.url())?;
+    let (db, _trust_set) = local.load_db(&default())?;
+    assert_eq!(db.get_package_review_count("source", None, None), 1);
+
+    alice.local()?.respond_to_compromise(
+        None,
+        "".into(),
+        &TestEnv::passphrase,
+        &TestEnv::passphrase,
+    )?;
+    alice.publish()?;
+
+    let local = bob.local()?;
+    local.fetch_url(&alice.url())?;
+    let (db, _trust_set) = local.load_db(&default())?;
+    assert!(db.is_id_revoked(alice.id.as_ref()));
+    assert!(!db.is_id_revoked(mallory.id.as_ref()));
+    assert_eq!(db.get_package_review_count("source", None, None), 0);
+
+    // only the repository of an id gets to revoke it
+    let mut directory = IdDirectory::default();
+    directory.revoke(bob.id.as_pubid());
+    directory.store_to_dir(&local.get_remote_git_cache_path(&mallory.url()))?;
+    let (db, _trust_set) = local.load_db(&default())?;
+    assert!(!db.is_id_revoked(bob.id.as_ref()));
+    Ok(())
+}
+
+// Trust in an id extends to the own ids linked to it both ways, up to the
+// level of the link, if the verifier chooses to follow links.
+#[test]
+fn linked_own_ids_share_trust() -> Result<()> {
+    use crate::{
+        id::LockedId,
+        test_support::{TestEnv, PASSPHRASE},
+    };
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let local = alice.local()?;
+    let work = OwnId::generate_for_git_url("https://alice-work");
+    local.save_locked_id(&LockedId::from_own_id(&work, PASSPHRASE)?)?;
+    let mut proofs = local.link_own_ids(
+        &work.id.id,
+        TrustLevel::Low,
+        TrustLevel::High,
+        "".into(),
+        &TestEnv::passphrase,
+        &TestEnv::passphrase,
+    )?;
+    assert_eq!(proofs.len(), 2);
+
+    // carol claims alice's id as her own, without alice linking back
+    let bob = OwnId::generate_for_git_url("https://bob");
+    let carol = OwnId::generate_for_git_url("https://carol");
+    proofs.push(
+        crev_data::proof::id_link::IdLinkBuilder::default()
+            .from(carol.as_pubid().to_owned())
+            .to(alice.id.as_pubid().to_owned())
+            .level(TrustLevel::High)
+            .build()
+            .map_err(|e| format_err!("{}", e))?
+            .sign_by(&carol)?,
+    );
+    for (trusted, level) in &[(&alice.id, TrustLevel::Medium), (&carol, TrustLevel::Low)] {
+        proofs.push(
+            bob.create_trust_proof(vec![trusted.as_pubid().to_owned()], *level)?
+                .sign_by(&bob)?,
+        );
+    }
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(proofs.into_iter());
+    assert_eq!(
+        trustdb.id_link_level(&alice.id.id.id, &work.id.id),
+        Some(TrustLevel::Low)
+    );
+    assert_eq!(trustdb.id_link_level(&carol.id.id, &alice.id.id.id), None);
+
+    let level_of = |id: &crev_data::Id, follow_id_links| {
+        let params = TrustDistanceParams {
+            follow_id_links,
+            ..Default::default()
+        };
+        trustdb
+            .calculate_trust_set(bob.as_ref(), &params)
+            .get_effective_trust_level(id)
+    };
+    assert_eq!(level_of(&work.id.id, false), None);
+    assert_eq!(level_of(&work.id.id, true), Some(TrustLevel::Low));
+    assert_eq!(level_of(&alice.id.id.id, true), Some(TrustLevel::Medium));
+    Ok(())
+}
+
+// A proof superseded by a newer one of its author no longer counts, and
+// neither do the ones it superseded in turn.
+#[test]
+fn superseded_proofs_are_ignored() -> Result<()> {
+    use crev_data::proof::{Content, SupersededProof};
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let c = OwnId::generate_for_git_url("https://c");
+    let d = OwnId::generate_for_git_url("https://d");
+
+    let first = a
+        .create_trust_proof(
+            vec![b.as_pubid().to_owned(), c.as_pubid().to_owned()],
+            TrustLevel::High,
+        )?
+        .sign_by(&a)?;
+    let amend = |proof: &crev_data::proof::Proof, ids: &[&OwnId]| {
+        let mut content = proof
+            .content
+            .superseding(&proof.digest, crev_common::now())
+            .ok_or_else(|| format_err!("can't supersede"))?;
+        if let Content::Trust(ref mut trust) = content {
+            trust.ids = ids.iter().map(|id| id.as_pubid().to_owned()).collect();
+        }
+        content.sign_by(&a)
+    };
+    let second = amend(&first, &[&b])?;
+    let third = amend(&second, &[&b, &d])?;
+
+    let trusted = |proofs: Vec<crev_data::proof::Proof>| -> HashSet<crev_data::Id> {
+        let mut trustdb = ProofDB::new();
+        trustdb.import_from_iter(proofs.into_iter());
+        trustdb
+            .calculate_trust_set(a.as_ref(), &default())
+            .trusted_ids()
+            .cloned()
+            .collect()
+    };
+    let with_all = trusted(vec![third.clone(), first.clone(), second.clone()]);
+    assert!(with_all.contains(b.as_ref()));
+    assert!(with_all.contains(d.as_ref()));
+    assert!(!with_all.contains(c.as_ref()));
+    assert!(!trusted(vec![first.clone(), second]).contains(c.as_ref()));
+
+    // only the author of a proof can supersede it
+    let mut by_other = b.create_trust_proof(vec![d.as_pubid().to_owned()], TrustLevel::Low)?;
+    by_other.supersedes = vec![SupersededProof {
+        digest: first.digest.clone(),
+    }];
+    assert!(trusted(vec![first, by_other.sign_by(&b)?]).contains(c.as_ref()));
+    Ok(())
+}
+
+// A trusted review of a file still verifying it is flagged stale once it
+// is old enough, the rest of the scope changed enough since, or its
+// reviewer was inactive long enough.
+#[test]
+fn stale_reviews_are_reported() -> Result<()> {
+    use crate::{
+        repo::Repo,
+        stale::{StaleParams, StaleReason},
+        test_support::TestEnv,
+    };
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let files = [("src/lib.rs", "pub fn foo() {}\n"), ("README.md", "foo\n")];
+    let project = env.new_project("foo", &files)?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    assert!(repo.stale_reviews(&default(), &default())?.is_empty());
+
+    // commit dates are in seconds
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    TestEnv::change_project(&project, &[("README.md", "bar\n")])?;
+    let params = StaleParams {
+        max_age_days: 0,
+        max_commits: 0,
+        max_inactive_days: 0,
+    };
+    let mut repo = Repo::open(&project)?;
+    let stale = repo.stale_reviews(&params, &default())?;
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].from, alice.id.id.id);
+    assert_eq!(stale[0].files, vec![PathBuf::from("src/lib.rs")]);
+    assert_eq!(
+        stale[0].reasons,
+        vec![
+            StaleReason::Old { days: 0 },
+            StaleReason::ChangedAround { commits: 1 },
+            StaleReason::InactiveReviewer { days: 0 },
+        ]
+    );
+    Ok(())
+}
+
+// A path requirement with tags counts only the reviews having all of
+// them.
+#[test]
+fn path_requirement_tags() -> Result<()> {
+    use crate::policy::{
+        approving_reviewer_tags, approving_reviewers, unmet_requirements, PathRequirement,
+    };
+    use crev_data::proof::review::{code::FileKind::Code, Rating};
+
+    let requirements: Vec<PathRequirement> = serde_yaml::from_str(
+        "- path: src/crypto/**\n  reviewers: 1\n  tags: [crypto, unsafe-audit]\n",
+    )?;
+    assert_eq!(
+        requirements[0].to_string(),
+        "src/crypto/** needs 1 reviewers with low trust, reviews tagged crypto, unsafe-audit"
+    );
+
+    let a = OwnId::generate_for_git_url("https://a");
+    let b = OwnId::generate_for_git_url("https://b");
+    let code_review = |tags: &[&str]| {
+        let mut review = code_review(
+            &b,
+            Rating::Positive,
+            vec![reviewed_file("src/crypto/aes.rs", vec![1])],
+        )?;
+        review.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        review.sign_by(&b)
+    };
+    let a_to_b = a
+        .create_trust_proof(vec![b.as_pubid().to_owned()], TrustLevel::High)?
+        .sign_by(&a)?;
+
+    let mut trustdb = ProofDB::new();
+    trustdb.import_from_iter(vec![a_to_b, code_review(&["crypto"])?].into_iter());
+    let trust_set = trustdb.calculate_trust_set(a.as_ref(), &default());
+    let path = Path::new("src/crypto/aes.rs");
+    let unmet = |trustdb: &ProofDB| {
+        unmet_requirements(
+            &requirements,
+            &default(),
+            path,
+            Code,
+            None,
+            &default(),
+            &approving_reviewers(trustdb, &trust_set, &[1], &[], None),
+            &approving_reviewer_tags(trustdb, &[1], &[], None),
+        )
+        .map(|unmet| unmet.len())
+    };
+    assert_eq!(unmet(&trustdb)?, 1);
+
+    let tagged = code_review(&["unsafe-audit", "crypto"])?;
+    trustdb.import_from_iter(vec![tagged].into_iter());
+    assert_eq!(unmet(&trustdb)?, 0);
+
+    assert!(crev_data::proof::ensure_valid_tags(&["unsafe audit".into()]).is_err());
+    Ok(())
+}
+
+// The draft of a review being committed is kept when the editor fails,
+// and removed once the review is signed.
+#[test]
+fn staging_draft_survives_editor_failure() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    let draft_path = repo.staging_draft_path();
+
+    std::env::set_var("VISUAL", "false");
+    let result = repo.commit(&TestEnv::passphrase, false, false, false, None);
+    std::env::set_var("VISUAL", "true");
+    assert!(result.is_err());
+    let draft = crate::util::read_file_to_string(&draft_path)?;
+    assert!(draft.starts_with("# Code Review of 1 files"));
+    assert!(draft.contains("thoroughness:"));
+
+    std::fs::remove_file(&draft_path)?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+    assert!(!draft_path.exists());
+    Ok(())
+}
+
+// A bundle has the trusted reviews of the project, minus the proofs of
+// its recipient, and can be encrypted to the recipient and imported.
+#[test]
+fn proof_bundle_for_recipient() -> Result<()> {
+    use crate::{repo::Repo, test_support::TestEnv};
+    use crev_data::encrypted::{EncryptedProof, SignedEncryptedBundle};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let bob = env.new_user("bob")?;
+    alice.activate();
+    let proofs = repo.proof_bundle(&bob.id.id.id, &default())?;
+    assert_eq!(proofs.len(), 1);
+    assert_eq!(proofs[0].content.author_id(), alice.id.id.id);
+    // alice needs nothing from herself
+    assert!(repo.proof_bundle(&alice.id.id.id, &default()).is_err());
+
+    let sealed =
+        EncryptedProof::seal_bundle(&proofs, &alice.id, std::slice::from_ref(&bob.id.id.id))?;
+    let text = sealed.to_string();
+    assert!(SignedEncryptedBundle::is_in(&text));
+    let opened = SignedEncryptedBundle::parse(&text)?.open(&bob.id)?;
+    assert_eq!(opened[0].digest, proofs[0].digest);
+    // a body way larger than any bundle is refused before it's parsed
+    let begin = "-----BEGIN CREV ENCRYPTED BUNDLE-----\n";
+    let padding = format!("#{}\n", "a".repeat(5 * 1024 * 1024));
+    let oversized = text.replacen(begin, &format!("{}{}", begin, padding), 1);
+    let error = SignedEncryptedBundle::parse(&oversized).unwrap_err();
+    assert!(error.to_string().contains("too long"));
+
+    let bob_local = bob.local()?;
+    assert_eq!(bob_local.import_bundle(&text, &TestEnv::passphrase)?, 1);
+    let plain: String = proofs.iter().map(ToString::to_string).collect();
+    assert_eq!(bob_local.import_bundle(&plain, &TestEnv::passphrase)?, 0);
+    assert!(bob_local
+        .known_proofs_iter()?
+        .any(|proof| proof.digest == proofs[0].digest));
+    Ok(())
+}
+
+#[test]
+fn store_manifest_spots_removed_proofs() -> Result<()> {
+    use crate::{repo::Repo, store_manifest::Change, test_support::TestEnv};
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let local = alice.local()?;
+    let (manifest, changes) = local.verify_store_manifest()?;
+    assert_eq!(manifest.by, Some(alice.id.id.id.clone()));
+    assert!(changes.is_empty());
+    let own: Vec<_> = manifest
+        .files
+        .keys()
+        .filter(|path| path.starts_with("own/"))
+        .collect();
+    assert_eq!(own.len(), 1);
+
+    let rel_path = own[0].trim_start_matches("own/");
+    std::fs::remove_file(local.get_proofs_dir_path()?.join(rel_path))?;
+    let (_, changes) = local.verify_store_manifest()?;
+    assert_eq!(changes, vec![Change::Removed(own[0].clone())]);
+
+    // a manifest changed to match is no longer signed properly
+    let path = local.store_manifest_path();
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(
+        &path,
+        content.replace(own[0].as_str(), "own/elsewhere.crev"),
+    )?;
+    assert!(local.verify_store_manifest().is_err());
+    Ok(())
+}
+
+#[test]
+fn proofs_round_trip_through_export_and_import() -> Result<()> {
+    use crate::{proof_index::ProofFilter, repo::Repo, test_support::TestEnv};
+    use crev_data::proof::Format;
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let filter = ProofFilter {
+        author: Some(alice.id.id.id.clone()),
+        proof_type: Some("code review".into()),
+        ..default()
+    };
+    let exporter = alice.local()?;
+    let bob = env.new_user("bob")?;
+    for format in &[Format::Armor, Format::Json] {
+        let mut exported = vec![];
+        assert_eq!(
+            exporter.export_proofs(&filter, false, &mut exported, *format)?,
+            1
+        );
+        let local = bob.local()?;
+        let imported = local.import_proofs(&exported[..], *format)?;
+        assert_eq!(imported, if *format == Format::Armor { 1 } else { 0 });
+
+        let tampered =
+            String::from_utf8(exported)?.replacen("rating: positive", "rating: negative", 1);
+        assert!(local.import_proofs(tampered.as_bytes(), *format).is_err());
+    }
+    let reviews = bob.local()?.find_proofs(&filter)?;
+    assert_eq!(reviews.len(), 1);
+    Ok(())
+}
+
+// Encrypted proofs are only exported, decrypted, when asked for.
+#[test]
+fn encrypted_proofs_are_not_exported_in_plaintext() -> Result<()> {
+    use crate::{proof_index::ProofFilter, repo::Repo, test_support::TestEnv};
+    use crev_data::proof::Format;
+
+    let env = TestEnv::new()?;
+    let bob = env.new_user("bob")?;
+    let alice = env.new_user("alice")?;
+    let local = alice.local()?;
+    let mut config = local.load_user_config()?;
+    config.encrypt_to = vec![bob.id.id.id.clone()];
+    local.store_user_config(&config)?;
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, None)?;
+
+    let filter = ProofFilter {
+        author: Some(alice.id.id.id.clone()),
+        proof_type: Some("code review".into()),
+        ..default()
+    };
+    assert_eq!(local.find_proofs(&filter)?.len(), 1);
+    let mut exported = vec![];
+    assert_eq!(
+        local.export_proofs(&filter, false, &mut exported, Format::Armor)?,
+        0
+    );
+    assert!(exported.is_empty());
+    assert_eq!(
+        local.export_proofs(&filter, true, &mut exported, Format::Armor)?,
+        1
+    );
+    assert!(String::from_utf8(exported)?.contains("src/lib.rs"));
+    Ok(())
+}
+
+#[test]
+fn commit_as_another_named_own_id() -> Result<()> {
+    use crate::{
+        id::LockedId,
+        repo::Repo,
+        test_support::{TestEnv, PASSPHRASE},
+    };
+
+    let env = TestEnv::new()?;
+    let alice = env.new_user("alice")?;
+    let local = alice.local()?;
+    let work = OwnId::generate(alice.id.id.url.clone());
+    local.save_locked_id(&LockedId::from_own_id(&work, PASSPHRASE)?)?;
+    local.name_id(&work.id.id, "work")?;
+    local.name_id(&alice.id.id.id, "personal")?;
+    assert!(local.name_id(&alice.id.id.id, "my id").is_err());
+    let stranger = OwnId::generate_for_git_url("https://stranger");
+    assert!(local.name_id(&stranger.id.id, "stranger").is_err());
+
+    let names: Vec<_> = local
+        .own_ids()?
+        .into_iter()
+        .filter(|(id, _)| id.id == work.id.id)
+        .map(|(_, name)| name)
+        .collect();
+    assert_eq!(names, vec![Some("work".to_owned())]);
+    assert!(local.switch_id("stranger").is_err());
+    local.switch_id("work")?;
+    assert_eq!(local.read_current_id()?, work.id.id);
+    local.switch_id("personal")?;
+
+    let project = env.new_project("foo", &[("src/lib.rs", "pub fn foo() {}\n")])?;
+    let mut repo = Repo::init(&project, alice.id.id.id.to_string())?;
+    repo.add(vec![project.join("src/lib.rs")])?;
+    repo.commit(&TestEnv::passphrase, false, false, false, Some("work"))?;
+
+    let authors: Vec<_> = local
+        .proofs_iter()?
+        .filter(|proof| matches!(proof.content, crev_data::proof::Content::Code(_)))
+        .map(|proof| proof.content.author_id())
+        .collect();
+    assert_eq!(authors, vec![work.id.id.clone()]);
+    assert_eq!(local.read_current_id()?, alice.id.id.id);
+    Ok(())
+}
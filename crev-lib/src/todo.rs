@@ -0,0 +1,286 @@
+//! `crev todo` - a local queue of things to review
+//!
+//! Items are kept most important first: by priority, then by how many
+//! times they were skipped, then by age. They get numbered in that order,
+//! starting at 1. Directories of projects can be watched, to queue them up
+//! again whenever their `HEAD` moves past the revision last reviewed.
+use crate::prelude::*;
+use crate::util;
+use chrono::{self, prelude::*};
+use crev_common::{
+    self,
+    serde::{as_rfc3339_fixed, from_rfc3339_fixed},
+};
+use crev_data::proof;
+use serde_yaml;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// What kind of thing an `Item` is to review
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// A dependency that isn't verified; the target is `name version`
+    Dependency,
+    /// A new revision of a watched project; the target is its directory
+    Revision,
+    /// Someone asked for a review
+    Request,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Dependency => "dependency",
+            Kind::Revision => "revision",
+            Kind::Request => "request",
+        })
+    }
+}
+
+/// A thing to review
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Item {
+    pub kind: Kind,
+    pub target: String,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub revision: String,
+    /// Higher goes first
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(skip_serializing_if = "String::is_empty", default = "Default::default")]
+    pub comment: String,
+    #[serde(
+        serialize_with = "as_rfc3339_fixed",
+        deserialize_with = "from_rfc3339_fixed"
+    )]
+    pub added: chrono::DateTime<FixedOffset>,
+    /// How many times it was put off by `Queue::skip`
+    #[serde(default)]
+    pub skips: u32,
+}
+
+impl Item {
+    pub fn new(kind: Kind, target: String) -> Self {
+        Item {
+            kind,
+            target,
+            revision: String::new(),
+            priority: 0,
+            comment: String::new(),
+            added: crev_common::now(),
+            skips: 0,
+        }
+    }
+
+    /// Item to review what `request` asks for, as of when it was made
+    pub fn from_review_request(request: &proof::ReviewRequest) -> Self {
+        let mut item = Item::new(Kind::Request, request.package.name.clone());
+        item.revision = request.package.revision.clone();
+        item.added = request.date;
+        item.comment = if request.comment.is_empty() {
+            format!("requested by {}", request.from.url.url)
+        } else {
+            format!("requested by {}: {}", request.from.url.url, request.comment)
+        };
+        item
+    }
+
+    fn is_same_as(&self, other: &Item) -> bool {
+        self.kind == other.kind && self.target == other.target && self.revision == other.revision
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<10} {}", self.kind, self.target)?;
+        if !self.revision.is_empty() {
+            write!(f, " @ {}", self.revision)?;
+        }
+        if !self.comment.is_empty() {
+            write!(f, " - {}", self.comment)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct QueueFile {
+    #[serde(default)]
+    items: Vec<Item>,
+    /// Watched project directories, with the revision last reviewed
+    #[serde(default)]
+    watched: BTreeMap<PathBuf, String>,
+}
+
+pub struct Queue {
+    file_path: PathBuf,
+    items: Vec<Item>,
+    watched: BTreeMap<PathBuf, String>,
+}
+
+impl Queue {
+    /// Open the queue stored at `path`; a missing file is an empty queue
+    pub fn open(path: &Path) -> Result<Self> {
+        let file: QueueFile = if path.exists() {
+            serde_yaml::from_str(&crev_common::read_file_to_string(path)?)?
+        } else {
+            Default::default()
+        };
+        let mut queue = Queue {
+            file_path: path.to_owned(),
+            items: file.items,
+            watched: file.watched,
+        };
+        queue.sort();
+        Ok(queue)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let file = QueueFile {
+            items: self.items.clone(),
+            watched: self.watched.clone(),
+        };
+        util::store_str_to_file(&self.file_path, &serde_yaml::to_string(&file)?)?;
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.items.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.skips.cmp(&b.skips))
+                .then(a.added.cmp(&b.added))
+        });
+    }
+
+    /// Items, most important first
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    fn index(&self, number: usize) -> Result<usize> {
+        if number == 0 || self.items.len() < number {
+            bail!("No item number {} in the queue", number);
+        }
+        Ok(number - 1)
+    }
+
+    /// Queue `item`, telling if it wasn't queued already
+    ///
+    /// An item already queued keeps its place, unless `item` has a higher
+    /// priority.
+    pub fn add(&mut self, item: Item) -> bool {
+        let added = match self
+            .items
+            .iter_mut()
+            .find(|queued| queued.is_same_as(&item))
+        {
+            Some(queued) => {
+                queued.priority = std::cmp::max(queued.priority, item.priority);
+                false
+            }
+            None => {
+                self.items.push(item);
+                true
+            }
+        };
+        self.sort();
+        added
+    }
+
+    /// Put off the item `number`, after the others of the same priority,
+    /// returning its new number
+    pub fn skip(&mut self, number: usize) -> Result<usize> {
+        let index = self.index(number)?;
+        let mut item = self.items.remove(index);
+        item.skips += 1;
+        self.items.push(item.clone());
+        self.sort();
+        let index = self
+            .items
+            .iter()
+            .position(|queued| queued.is_same_as(&item))
+            .expect("just queued");
+        Ok(index + 1)
+    }
+
+    /// Remove the item `number`, as reviewed
+    ///
+    /// Completing a revision of a watched project records it as the last
+    /// one reviewed.
+    pub fn complete(&mut self, number: usize) -> Result<Item> {
+        let index = self.index(number)?;
+        let item = self.items.remove(index);
+        if item.kind == Kind::Revision {
+            if let Some(revision) = self.watched.get_mut(Path::new(&item.target)) {
+                *revision = item.revision.clone();
+            }
+        }
+        Ok(item)
+    }
+
+    /// Complete every item of `kind` for `target`, eg. after reviewing it
+    /// outside of the queue, returning how many there were
+    pub fn complete_matching(&mut self, kind: Kind, target: &str) -> usize {
+        let mut completed = 0;
+        while let Some(index) = self
+            .items
+            .iter()
+            .position(|item| item.kind == kind && item.target == target)
+        {
+            self.complete(index + 1).expect("index in range");
+            completed += 1;
+        }
+        completed
+    }
+
+    /// Watch the project in `dir`, with `revision` already reviewed
+    pub fn watch(&mut self, dir: &Path, revision: String) {
+        self.watched.insert(dir.to_owned(), revision);
+    }
+
+    pub fn unwatch(&mut self, dir: &Path) -> bool {
+        self.watched.remove(dir).is_some()
+    }
+
+    pub fn watched(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.watched
+            .iter()
+            .map(|(dir, revision)| (dir.as_path(), revision.as_str()))
+    }
+
+    /// Queue a revision item for every watched project whose current
+    /// revision, as told by `revision_of`, isn't the last one reviewed
+    ///
+    /// Items for older revisions of the same project are dropped, as the
+    /// new one covers them. Returns the number of items added.
+    pub fn scan(&mut self, revision_of: &dyn Fn(&Path) -> Option<String>) -> usize {
+        let mut new_items = vec![];
+        for (dir, reviewed) in &self.watched {
+            match revision_of(dir) {
+                Some(revision) if revision != *reviewed => {
+                    let mut item = Item::new(Kind::Revision, dir.display().to_string());
+                    item.revision = revision;
+                    new_items.push(item);
+                }
+                _ => {}
+            }
+        }
+        let mut added = 0;
+        for item in new_items {
+            self.items.retain(|queued| {
+                queued.kind != Kind::Revision
+                    || queued.target != item.target
+                    || queued.revision == item.revision
+            });
+            if self.add(item) {
+                added += 1;
+            }
+        }
+        added
+    }
+}
@@ -0,0 +1,115 @@
+//! Guided creation of Trust Proofs
+//!
+//! Instead of picking a `TrustLevel` directly, the user is shown what is
+//! known about an Id and answers a couple of simple questions, which are
+//! then mapped to a level.
+use crate::proof::{humanize_age, type_label};
+use chrono::prelude::*;
+use crev_data::{proof, proof::trust::TrustLevel, Id, PubId};
+use std::fmt;
+
+/// How well the user knows the owner of an Id
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Acquaintance {
+    /// Doesn't know who they are
+    Stranger,
+    /// Knows them from their public work and reputation
+    Reputation,
+    /// Knows them personally
+    Personal,
+}
+
+/// Answers to the questions asked by the trust wizard
+#[derive(Clone, Debug)]
+pub struct TrustWizardAnswers {
+    /// Is the owner believed to be malicious or careless?
+    pub malicious: bool,
+    /// Was the fingerprint confirmed with the owner using another channel?
+    pub fingerprint_confirmed: bool,
+    pub acquaintance: Acquaintance,
+    /// Are the reviews of the owner known to be careful?
+    pub careful_reviewer: bool,
+}
+
+impl TrustWizardAnswers {
+    /// Map the answers to a trust level
+    ///
+    /// An unconfirmed fingerprint never gives more than `low` trust, as
+    /// the key might not belong to who it claims.
+    pub fn trust_level(&self) -> TrustLevel {
+        use self::Acquaintance::*;
+
+        if self.malicious {
+            return TrustLevel::Distrust;
+        }
+
+        let known = self.acquaintance != Stranger;
+        if !self.fingerprint_confirmed {
+            return if known && self.careful_reviewer {
+                TrustLevel::Low
+            } else {
+                TrustLevel::None
+            };
+        }
+
+        match (self.acquaintance, self.careful_reviewer) {
+            (Personal, true) => TrustLevel::High,
+            (Personal, false) | (Reputation, true) => TrustLevel::Medium,
+            (Stranger, true) | (Reputation, false) => TrustLevel::Low,
+            (Stranger, false) => TrustLevel::None,
+        }
+    }
+}
+
+/// Everything known about an Id, shown before asking the questions
+pub struct TrustWizardInfo {
+    pub pub_id: PubId,
+    /// Effective trust of the Id in the current WoT, if any
+    pub current_trust: Option<TrustLevel>,
+    /// See `TrustSet::trust_paths_to`
+    pub trust_paths: Vec<Vec<Id>>,
+    /// Most recent proofs authored by the Id, newest first
+    pub recent_proofs: Vec<proof::Proof>,
+}
+
+impl fmt::Display for TrustWizardInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let now = Utc::now();
+        writeln!(f, "Id:          {}", self.pub_id.id)?;
+        writeln!(f, "URL:         {}", self.pub_id.url.url)?;
+        writeln!(f, "Fingerprint: {}", self.pub_id.id.fingerprint())?;
+        match self.current_trust {
+            Some(level) => writeln!(f, "Trust:       {}", level)?,
+            None => writeln!(f, "Trust:       not in your web of trust")?,
+        }
+
+        writeln!(f)?;
+        if self.trust_paths.is_empty() {
+            writeln!(f, "No trust paths from you to this id.")?;
+        } else {
+            writeln!(f, "Trust paths:")?;
+            for path in &self.trust_paths {
+                let path: Vec<_> = path.iter().map(ToString::to_string).collect();
+                writeln!(f, "  {}", path.join(" -> "))?;
+            }
+        }
+
+        writeln!(f)?;
+        if self.recent_proofs.is_empty() {
+            writeln!(f, "No proofs by this id found.")?;
+        } else {
+            writeln!(f, "Recent proofs:")?;
+            for proof in &self.recent_proofs {
+                let content = &proof.content;
+                writeln!(
+                    f,
+                    "  {:<14} {:<14} {}",
+                    type_label(content),
+                    humanize_age(&content.date().with_timezone(&Utc), &now),
+                    content.draft_title()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
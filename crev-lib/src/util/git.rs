@@ -1,4 +1,15 @@
+use super::interrupt;
 use crate::prelude::*;
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 #[derive(PartialEq, Debug, Default)]
 pub struct GitUrlComponents {
@@ -33,8 +44,266 @@ pub fn parse_git_url_https(http_url: &str) -> Option<GitUrlComponents> {
     })
 }
 
-pub fn fetch_and_checkout_git_repo(repo: &git2::Repository) -> Result<()> {
-    repo.find_remote("origin")?.fetch(&["master"], None, None)?;
+/// Credentials for fetching access-controlled proof repositories
+///
+/// Stored in the user config, and used for the remotes with URLs on the
+/// same host as `url`, and with its path as a prefix (see `matches`):
+///
+/// ```yaml
+/// remote-credentials:
+///   - url: "git@git.example.com:"
+///     ssh-key: /home/someone/.ssh/id_crev
+///   - url: "https://git.example.com/"
+///     token-env: EXAMPLE_GIT_TOKEN
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RemoteCredentials {
+    /// Prefix of the URLs of the remotes
+    pub url: String,
+    /// User name, if not given in the URL; `git` for ssh, and
+    /// `x-access-token` for tokens by default
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub username: Option<String>,
+    /// Private key for ssh URLs
+    #[serde(rename = "ssh-key", skip_serializing_if = "Option::is_none", default)]
+    pub ssh_key: Option<PathBuf>,
+    /// Access token for https URLs
+    ///
+    /// Sent as the password of HTTP basic authentication, which hosting
+    /// services accept in place of a bearer token.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub token: Option<String>,
+    /// Environment variable holding the token, to keep it out of the config
+    #[serde(rename = "token-env", skip_serializing_if = "Option::is_none", default)]
+    pub token_env: Option<String>,
+}
+
+impl RemoteCredentials {
+    /// Are these the credentials for `url`
+    ///
+    /// The scheme and host have to be the same, and the path of `self.url`
+    /// has to be a whole number of segments of the path of `url`, so that
+    /// neither `https://git.example.com.evil/` nor
+    /// `https://git.example.com/team-evil` get the credentials of
+    /// `https://git.example.com/team`.
+    pub fn matches(&self, url: &str) -> bool {
+        let (prefix, url) = match (RemoteUrl::parse(&self.url), RemoteUrl::parse(url)) {
+            (Some(prefix), Some(url)) => (prefix, url),
+            _ => return false,
+        };
+        let prefix_path = prefix.path.trim_end_matches('/');
+        prefix.scheme == url.scheme
+            && prefix.host == url.host
+            && (prefix_path.is_empty()
+                || url.path == prefix_path
+                || url.path.starts_with(&format!("{}/", prefix_path)))
+    }
+
+    /// The access token, from `token` or the `token_env` variable
+    pub fn resolve_token(&self) -> Result<Option<String>> {
+        if let Some(ref token) = self.token {
+            return Ok(Some(token.clone()));
+        }
+        match self.token_env {
+            Some(ref var) => match std::env::var(var) {
+                Ok(token) => Ok(Some(token)),
+                Err(_) => bail!(
+                    "Environment variable {} with the token of {} is not set",
+                    var,
+                    self.url
+                ),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn add_credentials_callback<'a>(
+        &'a self,
+        callbacks: &mut git2::RemoteCallbacks<'a>,
+    ) -> Result<()> {
+        let token = self.resolve_token()?;
+        let mut tried = false;
+        callbacks.credentials(move |_url, username_from_url, allowed| {
+            // libgit2 asks again after rejected credentials; don't loop forever
+            if tried {
+                return Err(git2::Error::from_str("Authentication failed"));
+            }
+            tried = true;
+
+            if allowed.contains(git2::CredentialType::SSH_KEY) {
+                if let Some(ref ssh_key) = self.ssh_key {
+                    let username = username_from_url
+                        .or(self.username.as_deref())
+                        .unwrap_or("git");
+                    return git2::Cred::ssh_key(username, None, ssh_key, None);
+                }
+            }
+            if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+                if let Some(ref token) = token {
+                    let username = self
+                        .username
+                        .as_deref()
+                        .or(username_from_url)
+                        .unwrap_or("x-access-token");
+                    return git2::Cred::userpass_plaintext(username, token);
+                }
+            }
+            Err(git2::Error::from_str(
+                "No matching credentials configured for the remote",
+            ))
+        });
+        Ok(())
+    }
+}
+
+/// How long to wait after receiving `received_bytes` in `elapsed`, to
+/// keep to `bytes_per_sec`
+pub fn throttle_delay(received_bytes: u64, bytes_per_sec: u64, elapsed: Duration) -> Duration {
+    if bytes_per_sec == 0 {
+        return Duration::from_secs(0);
+    }
+    let due = Duration::from_millis(received_bytes.saturating_mul(1000) / bytes_per_sec);
+    due.checked_sub(elapsed).unwrap_or_default()
+}
+
+/// Options to fetch with `credentials`, receiving at most `rate_limit`
+/// bytes per second
+///
+/// The limit is kept by pausing the transfer whenever it gets ahead of it.
+/// The transfer is aborted once past `deadline`, or when interrupted.
+fn fetch_options(
+    credentials: Option<&RemoteCredentials>,
+    rate_limit: Option<u64>,
+    deadline: Option<Instant>,
+) -> Result<git2::FetchOptions<'_>> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    if let Some(credentials) = credentials {
+        credentials.add_credentials_callback(&mut callbacks)?;
+    }
+    let start = Instant::now();
+    callbacks.transfer_progress(move |progress| {
+        if let Some(rate_limit) = rate_limit {
+            thread::sleep(throttle_delay(
+                progress.received_bytes() as u64,
+                rate_limit,
+                start.elapsed(),
+            ));
+        }
+        !interrupt::is_interrupted() && !is_past(deadline)
+    });
+
+    let mut opts = git2::FetchOptions::new();
+    opts.remote_callbacks(callbacks);
+    Ok(opts)
+}
+
+fn is_past(deadline: Option<Instant>) -> bool {
+    match deadline {
+        Some(deadline) => deadline <= Instant::now(),
+        None => false,
+    }
+}
+
+/// Explain a transfer aborted by `fetch_options`
+fn abort_reason(e: git2::Error, url: &str, deadline: Option<Instant>) -> failure::Error {
+    if interrupt::is_interrupted() {
+        format_err!("Fetching {} interrupted", url)
+    } else if is_past(deadline) {
+        format_err!("Fetching {} timed out", url)
+    } else {
+        e.into()
+    }
+}
+
+/// The parts of a remote URL that credentials are matched by
+///
+/// Both `scheme://[user@]host[:port]/path` and the scp-like
+/// `user@host:path` of ssh are understood; the latter with the `ssh`
+/// scheme.
+#[derive(Debug, PartialEq, Eq)]
+struct RemoteUrl {
+    scheme: String,
+    host: String,
+    /// Without the leading `/`
+    path: String,
+}
+
+impl RemoteUrl {
+    fn parse(url: &str) -> Option<RemoteUrl> {
+        let (scheme, authority, path) = if let Some(i) = url.find("://") {
+            let rest = &url[i + 3..];
+            let (authority, path) = match rest.find('/') {
+                Some(j) => (&rest[..j], &rest[j + 1..]),
+                None => (rest, ""),
+            };
+            (url[..i].to_lowercase(), authority, path)
+        } else {
+            let i = url.find(':')?;
+            ("ssh".to_string(), &url[..i], &url[i + 1..])
+        };
+        let host = match authority.rfind('@') {
+            Some(i) => &authority[i + 1..],
+            None => authority,
+        };
+        if host.is_empty() {
+            return None;
+        }
+        Some(RemoteUrl {
+            scheme,
+            host: host.to_lowercase(),
+            path: path.trim_start_matches('/').to_string(),
+        })
+    }
+}
+
+/// The most specific credentials for `url`, if any
+pub fn find_credentials<'a>(
+    credentials: &'a [RemoteCredentials],
+    url: &str,
+) -> Option<&'a RemoteCredentials> {
+    credentials
+        .iter()
+        .filter(|credentials| credentials.matches(url))
+        .max_by_key(|credentials| credentials.url.len())
+}
+
+/// Clone `url` into `dir`
+///
+/// The clone is made next to `dir` and moved in place once complete, so
+/// an interrupted one doesn't leave a broken repository behind; the next
+/// attempt starts over.
+pub fn clone_git_repo(
+    url: &str,
+    dir: &Path,
+    credentials: Option<&RemoteCredentials>,
+    rate_limit: Option<u64>,
+    deadline: Option<Instant>,
+) -> Result<git2::Repository> {
+    let partial_dir = dir.with_extension("partial");
+    if partial_dir.exists() {
+        std::fs::remove_dir_all(&partial_dir)?;
+    }
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch_options(credentials, rate_limit, deadline)?);
+    builder
+        .clone(url, &partial_dir)
+        .map_err(|e| abort_reason(e, url, deadline))?;
+    std::fs::rename(&partial_dir, dir)?;
+    Ok(git2::Repository::open(dir)?)
+}
+
+pub fn fetch_and_checkout_git_repo(
+    repo: &git2::Repository,
+    credentials: Option<&RemoteCredentials>,
+    rate_limit: Option<u64>,
+    deadline: Option<Instant>,
+) -> Result<()> {
+    let mut fetch_options = fetch_options(credentials, rate_limit, deadline)?;
+    let mut remote = repo.find_remote("origin")?;
+    let url = remote.url().unwrap_or_default().to_owned();
+    remote
+        .fetch(&["master"], Some(&mut fetch_options), None)
+        .map_err(|e| abort_reason(e, &url, deadline))?;
     repo.set_head("FETCH_HEAD")?;
     let mut opts = git2::build::CheckoutBuilder::new();
     opts.force();
@@ -42,6 +311,106 @@ pub fn fetch_and_checkout_git_repo(repo: &git2::Repository) -> Result<()> {
     Ok(())
 }
 
+/// Fetch `url` into `dir`, cloning it if it isn't there yet
+pub fn fetch_remote(
+    url: &str,
+    dir: &Path,
+    credentials: Option<&RemoteCredentials>,
+    rate_limit: Option<u64>,
+    deadline: Option<Instant>,
+) -> Result<()> {
+    if dir.exists() {
+        eprintln!("Fetching {} to {}", url, dir.display());
+        let repo = git2::Repository::open(dir)?;
+        fetch_and_checkout_git_repo(&repo, credentials, rate_limit, deadline)
+    } else {
+        eprintln!("Cloning {} to {}", url, dir.display());
+        clone_git_repo(url, dir, credentials, rate_limit, deadline)?;
+        Ok(())
+    }
+}
+
+/// Fetch each `(url, dir)` of `remotes` with `fetch_remote`, up to `jobs`
+/// of them at a time, each for at most `timeout`
+///
+/// `rate_limit` is shared by all of them. `on_done` is called on the
+/// calling thread as each one completes, so it doesn't have to be
+/// thread-safe. Once interrupted, remotes not started yet are skipped,
+/// and the transfers in progress are aborted.
+pub fn fetch_remotes_parallel(
+    remotes: Vec<(String, PathBuf)>,
+    jobs: usize,
+    credentials: &[RemoteCredentials],
+    rate_limit: Option<u64>,
+    timeout: Option<Duration>,
+    mut on_done: impl FnMut(String, PathBuf, Result<()>) -> Result<()>,
+) -> Result<()> {
+    let jobs = jobs.max(1).min(remotes.len());
+    let rate_limit = rate_limit.map(|limit| (limit / jobs.max(1) as u64).max(1));
+    let queue = Arc::new(Mutex::new(VecDeque::from(remotes)));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+
+    let workers: Vec<_> = (0..jobs)
+        .map(|_| {
+            let queue = queue.clone();
+            let tx = tx.clone();
+            let stop = stop.clone();
+            let credentials = credentials.to_vec();
+            thread::spawn(move || loop {
+                if interrupt::is_interrupted() || stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let next = queue
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .pop_front();
+                let (url, dir) = match next {
+                    Some(remote) => remote,
+                    None => break,
+                };
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                let result = fetch_remote(
+                    &url,
+                    &dir,
+                    find_credentials(&credentials, &url),
+                    rate_limit,
+                    deadline,
+                );
+                if tx.send((url, dir, result)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let mut res = Ok(());
+    for (url, dir, result) in rx {
+        if res.is_ok() {
+            res = on_done(url, dir, result);
+            if res.is_err() {
+                stop.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+    for worker in workers {
+        if worker.join().is_err() {
+            bail!("A fetching thread panicked");
+        }
+    }
+    res
+}
+
+#[test]
+fn throttle_delay_test() {
+    let secs = Duration::from_secs;
+    assert_eq!(throttle_delay(1000, 100, secs(4)), secs(6));
+    assert_eq!(throttle_delay(1000, 100, secs(10)), secs(0));
+    assert_eq!(throttle_delay(1000, 100, secs(20)), secs(0));
+    assert_eq!(throttle_delay(1000, 0, secs(0)), secs(0));
+}
+
 #[test]
 fn parse_git_url_https_test() {
     assert_eq!(
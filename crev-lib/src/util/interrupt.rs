@@ -0,0 +1,48 @@
+//! Ctrl-C handling, for long operations like fetching
+//!
+//! With `install_handler`, the first Ctrl-C only sets a flag, checked by
+//! such operations between their steps, so they can stop leaving
+//! everything consistent. A second one terminates the process as usual.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether an interruption was requested
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Request an interruption, like Ctrl-C does
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Forget a requested interruption
+pub fn reset() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_signal: libc::c_int) {
+    interrupt();
+    // SAFETY: `signal` is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
+
+/// Make Ctrl-C request an interruption, the first time
+#[cfg(unix)]
+pub fn install_handler() {
+    // SAFETY: the handler only touches an atomic and calls `signal`
+    unsafe {
+        let handler: extern "C" fn(libc::c_int) = on_sigint;
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+/// Make Ctrl-C request an interruption, the first time
+///
+/// Not supported on this platform; Ctrl-C keeps terminating the process.
+#[cfg(not(unix))]
+pub fn install_handler() {}
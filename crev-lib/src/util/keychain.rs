@@ -0,0 +1,81 @@
+//! Keeping id passphrases in the OS keychain
+//!
+//! Only the Secret Service (GNOME Keyring, KWallet, ...) is supported,
+//! through the `secret-tool` command from `libsecret`. Passphrases are
+//! passed to it over stdin, never as arguments.
+use crate::prelude::*;
+use crev_data::Id;
+use std::{
+    io::{self, Write},
+    process,
+};
+
+const SERVICE: &str = "crev";
+
+fn attributes(id: &Id) -> Vec<String> {
+    vec!["service".into(), SERVICE.into(), "id".into(), id.to_string()]
+}
+
+/// Look up a passphrase stored for `id`
+///
+/// Any problem with the keychain is treated as the passphrase not being there.
+pub fn get_passphrase(id: &Id) -> Option<String> {
+    let output = process::Command::new("secret-tool")
+        .arg("lookup")
+        .args(attributes(id))
+        .stderr(process::Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let passphrase = String::from_utf8(output.stdout).ok()?;
+    if passphrase.is_empty() {
+        None
+    } else {
+        Some(passphrase)
+    }
+}
+
+/// Store `passphrase` for `id`, replacing any previous one
+pub fn store_passphrase(id: &Id, passphrase: &str) -> Result<()> {
+    let mut child = process::Command::new("secret-tool")
+        .arg("store")
+        .arg(format!("--label=crev passphrase for {}", id))
+        .args(attributes(id))
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                format_err!("`secret-tool` not found; install libsecret to use the keychain")
+            } else {
+                e.into()
+            }
+        })?;
+    child
+        .stdin
+        .as_mut()
+        .expect("stdin is piped")
+        .write_all(passphrase.as_bytes())?;
+    drop(child.stdin.take());
+
+    if !child.wait()?.success() {
+        bail!("Storing passphrase in the keychain failed");
+    }
+    Ok(())
+}
+
+/// Remove a passphrase stored for `id`, if any
+pub fn forget_passphrase(id: &Id) -> Result<()> {
+    let status = process::Command::new("secret-tool")
+        .arg("clear")
+        .args(attributes(id))
+        .stderr(process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        bail!("Removing passphrase from the keychain failed");
+    }
+    Ok(())
+}
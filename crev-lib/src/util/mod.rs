@@ -1,4 +1,7 @@
 pub mod git;
+pub mod interrupt;
+pub mod keychain;
+pub mod pager;
 
 use crate::prelude::*;
 use app_dirs;
@@ -59,10 +62,27 @@ pub fn get_documentation_for(content: &proof::Content) -> &'static str {
         Content::Trust(_) => include_str!("../../rc/doc/editing-trust.md"),
         Content::Code(_) => include_str!("../../rc/doc/editing-code-review.md"),
         Content::Package(_) => include_str!("../../rc/doc/editing-package-review.md"),
+        Content::Project(_) => include_str!("../../rc/doc/editing-project.md"),
+        Content::ProjectMigration(_) => {
+            include_str!("../../rc/doc/editing-project-migration.md")
+        }
+        Content::TrustRootHandover(_) => {
+            include_str!("../../rc/doc/editing-trust-root-handover.md")
+        }
+        Content::PathMove(_) => include_str!("../../rc/doc/editing-path-move.md"),
+        Content::BuildAttestation(_) => {
+            include_str!("../../rc/doc/editing-build-attestation.md")
+        }
+        Content::ReviewRequest(_) => include_str!("../../rc/doc/editing-review-request.md"),
+        Content::Revocation(_) => include_str!("../../rc/doc/editing-revocation.md"),
+        Content::Advisory(_) => include_str!("../../rc/doc/editing-advisory.md"),
+        Content::IdLink(_) => include_str!("../../rc/doc/editing-id-link.md"),
     }
 }
 
-pub fn edit_proof_content_iteractively(content: &proof::Content) -> Result<proof::Content> {
+/// The text to edit the draft of `content` in, with its title and
+/// documentation in comments
+fn draft_text(content: &proof::Content) -> Result<String> {
     let mut text = String::new();
 
     text.write_str(&format!("# {}\n", content.draft_title()))?;
@@ -71,6 +91,11 @@ pub fn edit_proof_content_iteractively(content: &proof::Content) -> Result<proof
     for line in get_documentation_for(content).lines() {
         text.write_fmt(format_args!("# {}\n", line))?;
     }
+    Ok(text)
+}
+
+pub fn edit_proof_content_iteractively(content: &proof::Content) -> Result<proof::Content> {
+    let mut text = draft_text(content)?;
     loop {
         text = edit_text_iteractively(&text)?;
         match proof::Content::parse_draft(content, &text) {
@@ -85,6 +110,43 @@ pub fn edit_proof_content_iteractively(content: &proof::Content) -> Result<proof
     }
 }
 
+/// Like `edit_proof_content_iteractively`, but editing the draft in the
+/// file at `draft_path`, so it's not lost when the editor fails or the
+/// user gives up
+///
+/// If the file is there already, from an earlier attempt, the user is
+/// offered to resume from it. It's left in place; the caller removes it
+/// once the proof is signed and stored.
+pub fn edit_proof_content_iteractively_in(
+    content: &proof::Content,
+    draft_path: &Path,
+) -> Result<proof::Content> {
+    let resume = draft_path.exists()
+        && crev_common::yes_or_no_was_y(&format!(
+            "Resume the unfinished draft in {} (y/n) ",
+            draft_path.display()
+        ))?;
+    if !resume {
+        store_str_to_file(draft_path, &draft_text(content)?)?;
+    }
+    loop {
+        edit_file(draft_path)
+            .map_err(|e| format_err!("{}; the draft is kept in {}", e, draft_path.display()))?;
+        match proof::Content::parse_draft(content, &read_file_to_string(draft_path)?) {
+            Err(e) => {
+                eprintln!("There was an error parsing content: {}", e);
+                if !crev_common::yes_or_no_was_y("Try again (y/n) ")? {
+                    bail!(
+                        "User canceled; the draft is kept in {}",
+                        draft_path.display()
+                    );
+                }
+            }
+            Ok(content) => return Ok(content),
+        }
+    }
+}
+
 pub fn err_eprint_and_ignore<O, E: std::error::Error>(res: std::result::Result<O, E>) -> bool {
     match res {
         Err(e) => {
@@ -0,0 +1,85 @@
+//! Paging long outputs, similar to what `git` does
+use crate::prelude::*;
+use std::{
+    env,
+    io::{self, Write},
+    process,
+};
+
+/// Name of ENV that overrides any other pager setting
+const CREV_PAGER_ENV: &str = "CREV_PAGER";
+
+const DEFAULT_PAGER: &str = "less";
+
+/// Options passed to `less` (unless user set their own), same as `git` does
+///
+/// * `F` - quit if the output fits on one screen
+/// * `R` - pass color escape sequences
+/// * `X` - don't clear the screen on exit
+const DEFAULT_LESS_ENV: &str = "FRX";
+
+/// Pick the pager command to use
+///
+/// In order of precedence: `$CREV_PAGER`, the `pager` set in the user config,
+/// `$PAGER`, and finally `less`. Setting any of them to an empty string
+/// or `cat` disables paging.
+pub fn get_pager_to_use(config_pager: Option<&str>) -> Option<String> {
+    let pager = env::var(CREV_PAGER_ENV)
+        .ok()
+        .or_else(|| config_pager.map(ToOwned::to_owned))
+        .or_else(|| env::var("PAGER").ok())
+        .unwrap_or_else(|| DEFAULT_PAGER.into());
+
+    let pager = pager.trim();
+    if pager.is_empty() || pager == "cat" {
+        None
+    } else {
+        Some(pager.to_owned())
+    }
+}
+
+/// Print `text` on stdout, piping it through a pager if stdout is a terminal
+pub fn print_paged(text: &str, config_pager: Option<&str>) -> Result<()> {
+    if atty::is(atty::Stream::Stdout) {
+        if let Some(pager) = get_pager_to_use(config_pager) {
+            match run_pager(&pager, text) {
+                // no such pager installed; just print everything
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                res => return Ok(res?),
+            }
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    ignore_broken_pipe(stdout.write_all(text.as_bytes()))?;
+    ignore_broken_pipe(stdout.flush())?;
+    Ok(())
+}
+
+fn run_pager(pager: &str, text: &str) -> io::Result<()> {
+    let mut words = pager.split_whitespace();
+    let cmd = words.next().expect("pager command not empty");
+
+    let mut command = process::Command::new(cmd);
+    command.args(words).stdin(process::Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", DEFAULT_LESS_ENV);
+    }
+
+    let mut child = command.spawn()?;
+    {
+        let stdin = child.stdin.as_mut().expect("stdin is piped");
+        // user quitting the pager early is not an error
+        ignore_broken_pipe(stdin.write_all(text.as_bytes()))?;
+    }
+    child.wait()?;
+    Ok(())
+}
+
+fn ignore_broken_pipe(res: io::Result<()>) -> io::Result<()> {
+    match res {
+        Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => res,
+    }
+}
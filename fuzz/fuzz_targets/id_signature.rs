@@ -0,0 +1,16 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use crev_data::Id;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let mut parts = s.splitn(3, '\n');
+        if let (Some(id), Some(sig), Some(content)) = (parts.next(), parts.next(), parts.next()) {
+            if let Ok(id) = Id::crevid_from_str(id) {
+                let _ = id.verify_signature(content.as_bytes(), sig);
+            }
+        }
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use crev_lib::id::LockedId;
+
+// Not unlocking here: arbitrary KDF parameters would make every run
+// take forever, and ids are never fetched from untrusted sources anyway.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(locked) = serde_yaml::from_str::<LockedId>(s) {
+            let _ = locked.to_pubid();
+            let _ = locked.to_string();
+        }
+    }
+});
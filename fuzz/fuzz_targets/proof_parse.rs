@@ -0,0 +1,17 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use crev_data::proof::Proof;
+
+// Everything a fetched proof repository can make us do with a proof file
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proofs) = Proof::parse(data) {
+        for proof in proofs {
+            let _ = proof.verify();
+            let _ = proof.content.to_string();
+            let _ = proof.content.draft_title();
+            let _ = proof.content.to_draft_string();
+        }
+    }
+});